@@ -116,31 +116,31 @@ impl App {
             self.update_view(render_sys, UpdateFlag::all());
             self.init = false;
         }
-        self.sound_source_viewer.handle_event(&render_sys, event);
-        self.field_slice_viewer.handle_event(&render_sys, event);
+        self.sound_source_viewer.handle_event(render_sys, event);
+        self.field_slice_viewer.handle_event(render_sys, event);
     }
 
     fn update_ui(&mut self, ui: &Ui, render_sys: &mut RenderSystem) -> UpdateFlag {
         let mut update_flag = UpdateFlag::empty();
-        TabBar::new(im_str!("Settings")).build(&ui, || {
-            TabItem::new(im_str!("Focus")).build(&ui, || {
+        TabBar::new(im_str!("Settings")).build(ui, || {
+            TabItem::new(im_str!("Focus")).build(ui, || {
                 ui.text(im_str!("Focus position"));
-                if Drag::new(im_str!("Pos X")).build(&ui, &mut self.focal_pos[0]) {
+                if Drag::new(im_str!("Pos X")).build(ui, &mut self.focal_pos[0]) {
                     Self::calc_focus_phase(self.focal_pos, &mut self.sources, &self.settings);
                     update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                 }
-                if Drag::new(im_str!("Pos Y")).build(&ui, &mut self.focal_pos[1]) {
+                if Drag::new(im_str!("Pos Y")).build(ui, &mut self.focal_pos[1]) {
                     Self::calc_focus_phase(self.focal_pos, &mut self.sources, &self.settings);
                     update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                 }
-                if Drag::new(im_str!("Pos Z")).build(&ui, &mut self.focal_pos[2]) {
+                if Drag::new(im_str!("Pos Z")).build(ui, &mut self.focal_pos[2]) {
                     Self::calc_focus_phase(self.focal_pos, &mut self.sources, &self.settings);
 
                     update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                 }
                 if Drag::new(im_str!("Wavelength"))
                     .range(0.0..=f32::INFINITY)
-                    .build(&ui, &mut self.settings.wave_length)
+                    .build(ui, &mut self.settings.wave_length)
                 {
                     Self::calc_focus_phase(self.focal_pos, &mut self.sources, &self.settings);
                     update_flag |= UpdateFlag::UPDATE_WAVENUM;
@@ -149,22 +149,22 @@ impl App {
                 ui.separator();
                 if Slider::new(im_str!("Transducer alpha"))
                     .range(0.0..=1.0)
-                    .build(&ui, &mut self.settings.source_alpha)
+                    .build(ui, &mut self.settings.source_alpha)
                 {
                     update_flag |= UpdateFlag::UPDATE_SOURCE_ALPHA;
                 }
             });
-            TabItem::new(im_str!("Slice")).build(&ui, || {
+            TabItem::new(im_str!("Slice")).build(ui, || {
                 ui.text(im_str!("Slice position"));
-                if Drag::new(im_str!("Slice X")).build(&ui, &mut self.settings.slice_pos[0]) {
+                if Drag::new(im_str!("Slice X")).build(ui, &mut self.settings.slice_pos[0]) {
                     self.field_slice_viewer.move_to(self.settings.slice_pos);
                     update_flag |= UpdateFlag::UPDATE_SLICE_POS;
                 }
-                if Drag::new(im_str!("Slice Y")).build(&ui, &mut self.settings.slice_pos[1]) {
+                if Drag::new(im_str!("Slice Y")).build(ui, &mut self.settings.slice_pos[1]) {
                     self.field_slice_viewer.move_to(self.settings.slice_pos);
                     update_flag |= UpdateFlag::UPDATE_SLICE_POS;
                 }
-                if Drag::new(im_str!("Slice Z")).build(&ui, &mut self.settings.slice_pos[2]) {
+                if Drag::new(im_str!("Slice Z")).build(ui, &mut self.settings.slice_pos[2]) {
                     self.field_slice_viewer.move_to(self.settings.slice_pos);
                     update_flag |= UpdateFlag::UPDATE_SLICE_POS;
                 }
@@ -172,21 +172,21 @@ impl App {
                 ui.text(im_str!("Slice Rotation"));
                 if AngleSlider::new(im_str!("Slice RX"))
                     .range_degrees(0.0..=360.0)
-                    .build(&ui, &mut self.settings.slice_angle[0])
+                    .build(ui, &mut self.settings.slice_angle[0])
                 {
                     self.field_slice_viewer.rotate_to(self.settings.slice_angle);
                     update_flag |= UpdateFlag::UPDATE_SLICE_POS;
                 }
                 if AngleSlider::new(im_str!("Slice RY"))
                     .range_degrees(0.0..=360.0)
-                    .build(&ui, &mut self.settings.slice_angle[1])
+                    .build(ui, &mut self.settings.slice_angle[1])
                 {
                     self.field_slice_viewer.rotate_to(self.settings.slice_angle);
                     update_flag |= UpdateFlag::UPDATE_SLICE_POS;
                 }
                 if AngleSlider::new(im_str!("Slice RZ"))
                     .range_degrees(0.0..=360.0)
-                    .build(&ui, &mut self.settings.slice_angle[2])
+                    .build(ui, &mut self.settings.slice_angle[2])
                 {
                     self.field_slice_viewer.rotate_to(self.settings.slice_angle);
                     update_flag |= UpdateFlag::UPDATE_SLICE_POS;
@@ -196,13 +196,13 @@ impl App {
                 ui.text(im_str!("Slice color setting"));
                 if Slider::new(im_str!("Color scale"))
                     .range(0.0..=10.0)
-                    .build(&ui, &mut self.settings.color_scale)
+                    .build(ui, &mut self.settings.color_scale)
                 {
                     update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
                 }
                 if Slider::new(im_str!("Slice alpha"))
                     .range(0.0..=1.0)
-                    .build(&ui, &mut self.settings.slice_alpha)
+                    .build(ui, &mut self.settings.slice_alpha)
                 {
                     update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
                 }
@@ -226,19 +226,19 @@ impl App {
                     update_flag |= UpdateFlag::UPDATE_SLICE_POS;
                 }
             });
-            TabItem::new(im_str!("Camera")).build(&ui, || {
+            TabItem::new(im_str!("Camera")).build(ui, || {
                 ui.text(im_str!("Camera pos"));
-                if Drag::new(im_str!("Camera X")).build(&ui, &mut self.settings.camera_pos[0]) {
+                if Drag::new(im_str!("Camera X")).build(ui, &mut self.settings.camera_pos[0]) {
                     render_sys.camera.position = self.settings.camera_pos;
                     self.view_projection = render_sys.get_view_projection(&self.settings);
                     update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
                 }
-                if Drag::new(im_str!("Camera Y")).build(&ui, &mut self.settings.camera_pos[1]) {
+                if Drag::new(im_str!("Camera Y")).build(ui, &mut self.settings.camera_pos[1]) {
                     render_sys.camera.position = self.settings.camera_pos;
                     self.view_projection = render_sys.get_view_projection(&self.settings);
                     update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
                 }
-                if Drag::new(im_str!("Camera Z")).build(&ui, &mut self.settings.camera_pos[2]) {
+                if Drag::new(im_str!("Camera Z")).build(ui, &mut self.settings.camera_pos[2]) {
                     render_sys.camera.position = self.settings.camera_pos;
                     self.view_projection = render_sys.get_view_projection(&self.settings);
                     update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
@@ -247,7 +247,7 @@ impl App {
                 ui.text(im_str!("Camera rotation"));
                 if AngleSlider::new(im_str!("Camera RX"))
                     .range_degrees(-180.0..=180.0)
-                    .build(&ui, &mut self.settings.camera_angle[0])
+                    .build(ui, &mut self.settings.camera_angle[0])
                 {
                     camera_helper::set_camera_angle(
                         &mut render_sys.camera,
@@ -258,7 +258,7 @@ impl App {
                 }
                 if AngleSlider::new(im_str!("Camera RY"))
                     .range_degrees(-180.0..=180.0)
-                    .build(&ui, &mut self.settings.camera_angle[1])
+                    .build(ui, &mut self.settings.camera_angle[1])
                 {
                     camera_helper::set_camera_angle(
                         &mut render_sys.camera,
@@ -269,7 +269,7 @@ impl App {
                 }
                 if AngleSlider::new(im_str!("Camera RZ"))
                     .range_degrees(-180.0..=180.0)
-                    .build(&ui, &mut self.settings.camera_angle[2])
+                    .build(ui, &mut self.settings.camera_angle[2])
                 {
                     camera_helper::set_camera_angle(
                         &mut render_sys.camera,
@@ -282,21 +282,21 @@ impl App {
                 ui.text(im_str!("Camera perspective"));
                 if AngleSlider::new(im_str!("FOV"))
                     .range_degrees(0.0..=180.0)
-                    .build(&ui, &mut self.settings.fov)
+                    .build(ui, &mut self.settings.fov)
                 {
                     self.view_projection = render_sys.get_view_projection(&self.settings);
                     update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
                 }
                 if Drag::new(im_str!("Near clip"))
                     .range(0.0..=f32::INFINITY)
-                    .build(&ui, &mut self.settings.near_clip)
+                    .build(ui, &mut self.settings.near_clip)
                 {
                     self.view_projection = render_sys.get_view_projection(&self.settings);
                     update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
                 }
                 if Drag::new(im_str!("Far clip"))
                     .range(0.0..=f32::INFINITY)
-                    .build(&ui, &mut self.settings.far_clip)
+                    .build(ui, &mut self.settings.far_clip)
                 {
                     self.view_projection = render_sys.get_view_projection(&self.settings);
                     update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
@@ -0,0 +1,130 @@
+/*
+ * File: calibration.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::medium::Medium;
+
+/// Reference pressure of the sound pressure level in Pa
+pub const SPL_REFERENCE: f32 = 20e-6;
+
+/// Pressure amplitude of a transducer driven at the full amplitude at a reference distance on its
+/// axis, which converts the field of the slice, i.e., the amplitude over the distance in mm, to Pa
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PressureCalibration {
+    /// Pressure amplitude in Pa
+    pub pressure: f32,
+    /// Reference distance in mm
+    pub distance: f32,
+    /// Amplitude attenuation coefficient in Np/mm of the medium in which the pressure was measured,
+    /// air by default
+    #[serde(default = "default_attenuation")]
+    pub attenuation: f32,
+}
+
+fn default_attenuation() -> f32 {
+    Medium::air().attenuation
+}
+
+impl PressureCalibration {
+    /// 121.5 dB SPL at 300 mm of T4010A1
+    pub fn t4010a1() -> Self {
+        Self::from_spl(121.5, 300.0)
+    }
+
+    /// Calibration from the SPL in dB at `distance` in mm
+    pub fn from_spl(spl: f32, distance: f32) -> Self {
+        Self {
+            pressure: spl_to_pa(spl),
+            distance,
+            attenuation: default_attenuation(),
+        }
+    }
+
+    /// Pa per unit of the field, where the pressure is of the decayed wave at the distance
+    pub fn pa_per_unit(&self) -> f32 {
+        self.pressure * self.distance * (self.attenuation * self.distance).exp()
+    }
+
+    /// Pressure amplitude in Pa of `value` in the unit of the field
+    pub fn to_pa(&self, value: f32) -> f32 {
+        value * self.pa_per_unit()
+    }
+
+    /// Inverse of [PressureCalibration::to_pa]
+    pub fn from_pa(&self, pa: f32) -> f32 {
+        pa / self.pa_per_unit()
+    }
+}
+
+impl Default for PressureCalibration {
+    fn default() -> Self {
+        Self::t4010a1()
+    }
+}
+
+/// SPL in dB of the pressure amplitude `pa` in Pa, i.e., for its RMS value
+pub fn pa_to_spl(pa: f32) -> f32 {
+    20.0 * (pa / std::f32::consts::SQRT_2 / SPL_REFERENCE).log10()
+}
+
+/// Pressure amplitude in Pa of the SPL in dB
+pub fn spl_to_pa(spl: f32) -> f32 {
+    SPL_REFERENCE * std::f32::consts::SQRT_2 * 10f32.powf(spl / 20.0)
+}
+
+/// Unit in which the pressure is shown in the UI
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PressureUnit {
+    /// The amplitude over the distance in mm, summed over the transducers
+    #[default]
+    Arbitrary,
+    /// Amplitude in Pa
+    Pa,
+    /// Sound pressure level in dB
+    DbSpl,
+}
+
+impl PressureUnit {
+    pub const ALL: [PressureUnit; 3] = [
+        PressureUnit::Arbitrary,
+        PressureUnit::Pa,
+        PressureUnit::DbSpl,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PressureUnit::Arbitrary => "a.u.",
+            PressureUnit::Pa => "Pa",
+            PressureUnit::DbSpl => "dB SPL",
+        }
+    }
+
+    /// `value` in the unit of the field converted to this unit
+    pub fn convert(&self, value: f32, calibration: &PressureCalibration) -> f32 {
+        match self {
+            PressureUnit::Arbitrary => value,
+            PressureUnit::Pa => calibration.to_pa(value),
+            PressureUnit::DbSpl => pa_to_spl(calibration.to_pa(value)),
+        }
+    }
+
+    /// Inverse of [PressureUnit::convert]
+    pub fn invert(&self, value: f32, calibration: &PressureCalibration) -> f32 {
+        match self {
+            PressureUnit::Arbitrary => value,
+            PressureUnit::Pa => calibration.from_pa(value),
+            PressureUnit::DbSpl => calibration.from_pa(spl_to_pa(value)),
+        }
+    }
+}
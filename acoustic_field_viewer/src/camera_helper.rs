@@ -13,7 +13,7 @@
 
 use camera_controllers::Camera;
 
-use crate::{Matrix3, Vector3};
+use crate::{Matrix3, Matrix4, Vector3};
 
 pub fn set_camera_angle(camera: &mut Camera<f32>, angle: Vector3) {
     let rot = quaternion::euler_angles(angle[0], angle[1], angle[2]);
@@ -23,6 +23,76 @@ pub fn set_camera_angle(camera: &mut Camera<f32>, angle: Vector3) {
     camera.forward = vecmath_util::to_vec3(&model[2]);
 }
 
+/// Euler angles whose third axis is `dir`, i.e., the normal of a slice or the backward of the
+/// camera, turned around the x axis after the y axis without the roll.
+///
+/// In this case [quaternion::euler_angles] is the exact rotation, and the angles of the slices on
+/// the xy, yz and zx planes are reproduced for their normals.
+pub fn euler_angles_facing(dir: Vector3) -> Vector3 {
+    let n = vecmath::vec3_normalized(dir);
+    [(-n[1]).atan2(n[2]), n[0].clamp(-1.0, 1.0).asin(), 0.]
+}
+
+/// Projects `pos` to the window coordinate of `screen_size`, or returns `None` if it is behind the camera
+pub fn project_to_screen(
+    view_projection: (Matrix4, Matrix4),
+    pos: Vector3,
+    screen_size: [f32; 2],
+) -> Option<[f32; 2]> {
+    let (view, projection) = view_projection;
+    let clip = vecmath::col_mat4_transform(
+        vecmath::col_mat4_mul(projection, view),
+        [pos[0], pos[1], pos[2], 1.0],
+    );
+    if clip[3] <= 0.0 {
+        return None;
+    }
+    let x = clip[0] / clip[3];
+    let y = clip[1] / clip[3];
+    Some([
+        (x + 1.0) / 2.0 * screen_size[0],
+        (1.0 - y) / 2.0 * screen_size[1],
+    ])
+}
+
+/// Ray from the camera through `screen_pos` in the window coordinate of `screen_size`, as the
+/// origin on the near plane and the unit direction, the inverse of [project_to_screen]
+pub fn unproject_ray(
+    view_projection: (Matrix4, Matrix4),
+    screen_pos: [f32; 2],
+    screen_size: [f32; 2],
+) -> (Vector3, Vector3) {
+    let (view, projection) = view_projection;
+    let inv = vecmath::mat4_inv(vecmath::col_mat4_mul(projection, view));
+    let x = screen_pos[0] / screen_size[0] * 2.0 - 1.0;
+    let y = 1.0 - screen_pos[1] / screen_size[1] * 2.0;
+    let unproject = |z: f32| {
+        let p = vecmath::col_mat4_transform(inv, [x, y, z, 1.0]);
+        [p[0] / p[3], p[1] / p[3], p[2] / p[3]]
+    };
+    let near = unproject(-1.0);
+    let far = unproject(1.0);
+    (near, vecmath::vec3_normalized(vecmath::vec3_sub(far, near)))
+}
+
+/// Intersection of the ray with the plane through `point` with `normal`, if it is in front of
+/// the origin
+pub fn intersect_plane(
+    (origin, dir): (Vector3, Vector3),
+    point: Vector3,
+    normal: Vector3,
+) -> Option<Vector3> {
+    let denom = vecmath::vec3_dot(dir, normal);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let t = vecmath::vec3_dot(vecmath::vec3_sub(point, origin), normal) / denom;
+    if t < 0.0 {
+        return None;
+    }
+    Some(vecmath::vec3_add(origin, vecmath::vec3_scale(dir, t)))
+}
+
 pub fn rot_mat_to_euler_angles(mat: &Matrix3) -> Vector3 {
     let sy = (mat[0][0] * mat[0][0] + mat[1][0] * mat[1][0]).sqrt();
     if sy < 1e-3 {
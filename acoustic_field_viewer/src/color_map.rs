@@ -0,0 +1,242 @@
+/*
+ * File: color_map.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::path::Path;
+
+use scarlet::{
+    color::RGBColor,
+    colormap::{ColorMap, ListedColorMap},
+};
+use serde::{Deserialize, Serialize};
+
+/// Number of the colors sampled from the color maps
+pub const COLOR_MAP_SIZE: usize = 100;
+
+const COOLWARM: [[u8; 3]; 9] = [
+    [59, 76, 192],
+    [98, 130, 234],
+    [141, 176, 254],
+    [184, 208, 249],
+    [221, 221, 221],
+    [245, 196, 173],
+    [244, 154, 123],
+    [222, 96, 77],
+    [180, 4, 38],
+];
+
+const RDBU: [[u8; 3]; 11] = [
+    [5, 48, 97],
+    [33, 102, 172],
+    [67, 147, 195],
+    [146, 197, 222],
+    [209, 229, 240],
+    [247, 247, 247],
+    [253, 219, 199],
+    [244, 165, 130],
+    [214, 96, 77],
+    [178, 24, 43],
+    [103, 0, 31],
+];
+
+/// Color map of the slice, the volume and the color bar
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMapKind {
+    #[default]
+    Inferno,
+    Viridis,
+    Magma,
+    Plasma,
+    /// Rainbow of MATLAB from dark blue to dark red through cyan and yellow
+    Jet,
+    Grayscale,
+    /// Diverging map of Moreland from blue to red through light gray
+    Coolwarm,
+    /// Diverging map of ColorBrewer from blue to red through white, i.e., `RdBu_r` of matplotlib
+    RdBu,
+    /// Colors imported from a CSV file, or the same as `Inferno` until they are imported
+    Imported,
+}
+
+impl ColorMapKind {
+    pub const ALL: [ColorMapKind; 9] = [
+        ColorMapKind::Inferno,
+        ColorMapKind::Viridis,
+        ColorMapKind::Magma,
+        ColorMapKind::Plasma,
+        ColorMapKind::Jet,
+        ColorMapKind::Grayscale,
+        ColorMapKind::Coolwarm,
+        ColorMapKind::RdBu,
+        ColorMapKind::Imported,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorMapKind::Inferno => "Inferno",
+            ColorMapKind::Viridis => "Viridis",
+            ColorMapKind::Magma => "Magma",
+            ColorMapKind::Plasma => "Plasma",
+            ColorMapKind::Jet => "Jet",
+            ColorMapKind::Grayscale => "Grayscale",
+            ColorMapKind::Coolwarm => "Coolwarm",
+            ColorMapKind::RdBu => "RdBu",
+            ColorMapKind::Imported => "Imported",
+        }
+    }
+
+    /// Maps with a neutral color at the middle, for the signed quantities
+    pub fn is_diverging(&self) -> bool {
+        matches!(self, ColorMapKind::Coolwarm | ColorMapKind::RdBu)
+    }
+
+    /// Colors from the lowest to the highest, where `imported` is used for `Imported`
+    pub fn colors(&self, imported: Option<&[RGBColor]>) -> Vec<RGBColor> {
+        let iter = (0..COLOR_MAP_SIZE).map(|x| x as f64 / COLOR_MAP_SIZE as f64);
+        match (self, imported) {
+            (ColorMapKind::Viridis, _) => ListedColorMap::viridis().transform(iter),
+            (ColorMapKind::Magma, _) => ListedColorMap::magma().transform(iter),
+            (ColorMapKind::Plasma, _) => ListedColorMap::plasma().transform(iter),
+            (ColorMapKind::Jet, _) => iter
+                .map(|t| {
+                    let c = |x: f64| (1.5 - (4.0 * t - x).abs()).clamp(0.0, 1.0);
+                    RGBColor {
+                        r: c(3.0),
+                        g: c(2.0),
+                        b: c(1.0),
+                    }
+                })
+                .collect(),
+            (ColorMapKind::Grayscale, _) => iter.map(|t| RGBColor { r: t, g: t, b: t }).collect(),
+            (ColorMapKind::Coolwarm, _) => resample(&from_rgb8(&COOLWARM), iter),
+            (ColorMapKind::RdBu, _) => resample(&from_rgb8(&RDBU), iter),
+            // resampled linearly, since the texture of the color map is not filtered
+            (ColorMapKind::Imported, Some(colors)) if colors.len() > 1 => resample(colors, iter),
+            _ => ListedColorMap::inferno().transform(iter),
+        }
+    }
+}
+
+/// Colors at `t` from 0 to 1, interpolated linearly between the evenly spaced `colors`
+fn resample(colors: &[RGBColor], t: impl Iterator<Item = f64>) -> Vec<RGBColor> {
+    t.map(|t| {
+        let x = t.clamp(0.0, 1.0) * (colors.len() - 1) as f64;
+        let (i, f) = (x.floor() as usize, x.fract());
+        let (c0, c1) = (&colors[i], &colors[(i + 1).min(colors.len() - 1)]);
+        RGBColor {
+            r: c0.r + (c1.r - c0.r) * f,
+            g: c0.g + (c1.g - c0.g) * f,
+            b: c0.b + (c1.b - c0.b) * f,
+        }
+    })
+    .collect()
+}
+
+fn from_rgb8(colors: &[[u8; 3]]) -> Vec<RGBColor> {
+    colors
+        .iter()
+        .map(|&[r, g, b]| RGBColor {
+            r: r as f64 / 255.0,
+            g: g as f64 / 255.0,
+            b: b as f64 / 255.0,
+        })
+        .collect()
+}
+
+/// Reads the colors from the lowest, one `r,g,b` per line in 0 to 1, or in 0 to 255 if any
+/// exceeds 1. The empty lines and those starting with `#` are skipped.
+pub fn read_csv<P: AsRef<Path>>(path: P) -> Result<Vec<RGBColor>, String> {
+    let path = path.as_ref();
+    let csv = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_csv(&csv).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn parse_csv(csv: &str) -> Result<Vec<RGBColor>, String> {
+    let rows = csv
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            match line
+                .split(',')
+                .map(parse_f64)
+                .collect::<Result<Vec<_>, _>>()?
+            {
+                row if row.len() == 3 => Ok([row[0], row[1], row[2]]),
+                row => Err(format!("{} columns, expected 3", row.len())),
+            }
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if rows.len() < 2 {
+        return Err("at least 2 colors are required".to_owned());
+    }
+    let max = rows.iter().flatten().fold(0.0, |a: f64, &b| a.max(b));
+    let scale = if max > 1.0 { 255.0 } else { 1.0 };
+    Ok(rows
+        .iter()
+        .map(|&[r, g, b]| RGBColor {
+            r: (r / scale).clamp(0.0, 1.0),
+            g: (g / scale).clamp(0.0, 1.0),
+            b: (b / scale).clamp(0.0, 1.0),
+        })
+        .collect())
+}
+
+fn parse_f64(s: &str) -> Result<f64, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid number: {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(colors: &[RGBColor]) -> Vec<[f64; 3]> {
+        colors.iter().map(|c| [c.r, c.g, c.b]).collect()
+    }
+
+    #[test]
+    fn parse_unit_colors() {
+        let colors = parse_csv("# low to high\n0, 0, 0.5\n\n1,0.25,0\n").unwrap();
+        assert_eq!(rgb(&colors), vec![[0.0, 0.0, 0.5], [1.0, 0.25, 0.0]]);
+    }
+
+    #[test]
+    fn parse_8bit_colors() {
+        let colors = parse_csv("0,0,0\n255,51,0.5\n").unwrap();
+        assert_eq!(rgb(&colors), vec![[0.0, 0.0, 0.0], [1.0, 0.2, 0.5 / 255.0]]);
+    }
+
+    #[test]
+    fn invalid_colors() {
+        assert!(parse_csv("0,0,0\n").is_err());
+        assert!(parse_csv("0,0,0\n1,1\n").is_err());
+        assert!(parse_csv("0,0,0\n1,1,white\n").is_err());
+    }
+
+    #[test]
+    fn resample_linearly() {
+        let colors = parse_csv("0,0,0\n1,1,1\n0,0,1\n").unwrap();
+        let resampled = resample(&colors, [0.0, 0.25, 0.75, 1.0, 2.0].iter().copied());
+        assert_eq!(
+            rgb(&resampled),
+            vec![
+                [0.0, 0.0, 0.0],
+                [0.5, 0.5, 0.5],
+                [0.5, 0.5, 1.0],
+                [0.0, 0.0, 1.0],
+                [0.0, 0.0, 1.0]
+            ]
+        );
+    }
+}
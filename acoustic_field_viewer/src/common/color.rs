@@ -13,6 +13,7 @@
 
 pub trait Color {
     fn rgba(&self) -> [f32; 4];
+    #[allow(dead_code)]
     fn hsva(&self) -> [f32; 4];
 }
 
@@ -11,22 +11,14 @@
  *
  */
 
-use std::path::Path;
-
 use gfx::{format::Srgba8, handle::ShaderResourceView};
-use image::DynamicImage;
+use image::RgbaImage;
 
-pub fn create_texture_resource<P: AsRef<Path>, F: gfx::Factory<R>, R: gfx::Resources>(
-    path: P,
+/// Texture of the sRGB image, whose first row is at the texture coordinate 0
+pub fn create_texture_from_image<F: gfx::Factory<R>, R: gfx::Resources>(
+    img: &RgbaImage,
     factory: &mut F,
 ) -> Result<ShaderResourceView<R, [f32; 4]>, anyhow::Error> {
-    let img = image::open(path)?;
-
-    let img = match img {
-        DynamicImage::ImageRgba8(img) => img,
-        img => img.to_rgba8(),
-    };
-
     let (width, height) = img.dimensions();
 
     fn create_texture<T, F, R>(
@@ -65,14 +57,7 @@ pub fn create_texture_resource<P: AsRef<Path>, F: gfx::Factory<R>, R: gfx::Resou
 
     let (width, height) = (width as u16, height as u16);
     let tex_kind = gfx::texture::Kind::D2(width, height, gfx::texture::AaMode::Single);
-    let filter_method = gfx::texture::FilterMethod::Scale;
-    let wrap_mode_u = gfx::texture::WrapMode::Tile;
-    let wrap_mode_v = gfx::texture::WrapMode::Tile;
-    let mut sampler_info = gfx::texture::SamplerInfo::new(filter_method, wrap_mode_u);
-    sampler_info.wrap_mode.1 = wrap_mode_v;
-    sampler_info.border = [0.0, 0.0, 0.0, 1.0].into();
-
-    let view = create_texture::<Srgba8, F, R>(factory, tex_kind, &[&img])?;
+    let view = create_texture::<Srgba8, F, R>(factory, tex_kind, &[img])?;
 
     Ok(view)
 }
@@ -0,0 +1,263 @@
+/*
+ * File: directivity.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{f32::consts::PI, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// Radius of the piston in mm, that of T4010A1
+pub const PISTON_RADIUS: f32 = 4.5;
+
+// Cubic interpolation of the measured directivity of T4010A1 for every 10 degrees
+const T4010A1_COEF_A: [f32; 9] = [
+    1.0,
+    1.0,
+    1.0,
+    0.891_250_9,
+    0.707_945_8,
+    0.501_187_2,
+    0.354_813_4,
+    0.251_188_6,
+    0.199_526_2,
+];
+const T4010A1_COEF_B: [f32; 9] = [
+    0.0,
+    0.0,
+    -0.004_596_48,
+    -0.015_552_08,
+    -0.020_811_48,
+    -0.018_221_12,
+    -0.012_243_75,
+    -0.007_803_456,
+    -0.003_128_575,
+];
+const T4010A1_COEF_C: [f32; 9] = [
+    0.0,
+    0.0,
+    -0.000_787_968_1,
+    -0.000_307_591_5,
+    -0.000_218_348_6,
+    0.000_477_384_2,
+    0.000_120_353_1,
+    0.000_323_676_3,
+    0.000_143_850_5,
+];
+const T4010A1_COEF_D: [f32; 9] = [
+    0.0,
+    0.0,
+    1.601_255_3e-5,
+    2.974_762_5e-6,
+    2.319_109_3e-5,
+    -1.190_103_4e-5,
+    6.777_437e-6,
+    -5.995_48e-6,
+    -4.793_728_4e-6,
+];
+
+/// Amplitude of a transducer relative to that on its axis, as a function of the off-axis angle
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Directivity {
+    /// Omnidirectional point source
+    #[default]
+    Monopole,
+    /// Circular piston of [PISTON_RADIUS] in an infinite baffle
+    Piston,
+    /// Measured directivity of Nippon Ceramic T4010A1
+    T4010A1,
+    /// [DirectivityTable] imported from a file, or the same as `Monopole` until it is imported
+    Imported,
+}
+
+impl Directivity {
+    pub const ALL: [Directivity; 4] = [
+        Directivity::Monopole,
+        Directivity::Piston,
+        Directivity::T4010A1,
+        Directivity::Imported,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Directivity::Monopole => "Monopole",
+            Directivity::Piston => "Baffled piston",
+            Directivity::T4010A1 => "T4010A1 (measured)",
+            Directivity::Imported => "Imported",
+        }
+    }
+
+    /// Relative amplitude at `theta` in radians from the axis, for the wavenumber in 1/mm
+    pub fn value(&self, theta: f32, wavenumber: f32) -> f32 {
+        match self {
+            Directivity::Monopole | Directivity::Imported => 1.0,
+            Directivity::Piston => {
+                if theta > PI / 2.0 {
+                    return 0.0;
+                }
+                let x = wavenumber * PISTON_RADIUS * theta.sin();
+                if x < 1e-6 {
+                    1.0
+                } else {
+                    2.0 * bessel_j1(x) / x
+                }
+            }
+            Directivity::T4010A1 => {
+                let deg = theta.to_degrees().abs() % 180.0;
+                let deg = if deg > 90.0 { 180.0 - deg } else { deg };
+                let i = ((deg / 10.0).ceil() as usize).clamp(1, T4010A1_COEF_A.len()) - 1;
+                let x = deg - i as f32 * 10.0;
+                T4010A1_COEF_A[i]
+                    + T4010A1_COEF_B[i] * x
+                    + T4010A1_COEF_C[i] * x * x
+                    + T4010A1_COEF_D[i] * x * x * x
+            }
+        }
+    }
+
+    /// `n` samples of [Directivity::value] for the angles from 0 to pi inclusive
+    pub fn table(&self, n: usize, wavenumber: f32) -> Vec<f32> {
+        (0..n)
+            .map(|i| self.value(PI * i as f32 / (n - 1).max(1) as f32, wavenumber))
+            .collect()
+    }
+}
+
+/// Measured directivity, the relative amplitude for each angle and frequency.
+///
+/// The CSV has a header of `angle` followed by the frequencies in Hz, and then one row for each
+/// angle in degrees from the axis, e.g.,
+///
+/// ```text
+/// angle,25000,40000
+/// 0,1.0,1.0
+/// 30,0.9,0.7
+/// 90,0.4,0.2
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectivityTable {
+    /// Ascending angles in degrees
+    angles: Vec<f32>,
+    /// Ascending frequencies in Hz, with the amplitudes for `angles` at each
+    curves: Vec<(f32, Vec<f32>)>,
+}
+
+impl DirectivityTable {
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let csv =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&csv).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(csv: &str) -> Result<Self, String> {
+        let mut lines = csv
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let frequencies = lines
+            .next()
+            .ok_or_else(|| "empty table".to_owned())?
+            .split(',')
+            .skip(1)
+            .map(parse_f32)
+            .collect::<Result<Vec<_>, _>>()?;
+        if frequencies.is_empty() {
+            return Err("no frequency in the header".to_owned());
+        }
+        let mut rows = lines
+            .map(|line| {
+                let row = line
+                    .split(',')
+                    .map(parse_f32)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if row.len() != frequencies.len() + 1 {
+                    return Err(format!(
+                        "{} columns, expected {}",
+                        row.len(),
+                        frequencies.len() + 1
+                    ));
+                }
+                Ok(row)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if rows.is_empty() {
+            return Err("no angle".to_owned());
+        }
+        rows.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        let angles = rows.iter().map(|row| row[0]).collect();
+        let mut curves: Vec<_> = frequencies
+            .iter()
+            .enumerate()
+            .map(|(i, &f)| (f, rows.iter().map(|row| row[i + 1]).collect()))
+            .collect();
+        curves.sort_by(|a: &(f32, Vec<f32>), b| a.0.total_cmp(&b.0));
+        Ok(Self { angles, curves })
+    }
+
+    pub fn frequencies(&self) -> Vec<f32> {
+        self.curves.iter().map(|&(f, _)| f).collect()
+    }
+
+    /// Relative amplitude at `theta` in degrees, linearly interpolated in angle and frequency and
+    /// clamped to the range of the table
+    pub fn value(&self, theta: f32, frequency: f32) -> f32 {
+        let i = self.curves.partition_point(|&(f, _)| f < frequency);
+        if i == 0 {
+            return self.curve_value(0, theta);
+        }
+        if i == self.curves.len() {
+            return self.curve_value(i - 1, theta);
+        }
+        let (f0, f1) = (self.curves[i - 1].0, self.curves[i].0);
+        let t = (frequency - f0) / (f1 - f0);
+        self.curve_value(i - 1, theta) * (1.0 - t) + self.curve_value(i, theta) * t
+    }
+
+    fn curve_value(&self, curve: usize, theta: f32) -> f32 {
+        let amps = &self.curves[curve].1;
+        let i = self.angles.partition_point(|&a| a < theta);
+        if i == 0 {
+            return amps[0];
+        }
+        if i == self.angles.len() {
+            return amps[i - 1];
+        }
+        let (a0, a1) = (self.angles[i - 1], self.angles[i]);
+        let t = (theta - a0) / (a1 - a0);
+        amps[i - 1] * (1.0 - t) + amps[i] * t
+    }
+
+    /// `n` samples of [DirectivityTable::value] for the angles from 0 to pi inclusive
+    pub fn table(&self, n: usize, frequency: f32) -> Vec<f32> {
+        (0..n)
+            .map(|i| self.value(180.0 * i as f32 / (n - 1).max(1) as f32, frequency))
+            .collect()
+    }
+}
+
+fn parse_f32(s: &str) -> Result<f32, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid number: {}", s))
+}
+
+// The power series converges fast enough for the arguments of a transducer, i.e., ka < 10
+fn bessel_j1(x: f32) -> f32 {
+    let h = x as f64 / 2.0;
+    let mut term = h;
+    let mut sum = term;
+    for m in 1..30 {
+        term *= -h * h / (m * (m + 1)) as f64;
+        sum += term;
+    }
+    sum as f32
+}
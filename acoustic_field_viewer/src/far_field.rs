@@ -0,0 +1,176 @@
+/*
+ * File: far_field.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use rayon::prelude::*;
+
+use crate::{
+    field::{Complex, FieldModel},
+    Vector3,
+};
+
+/// Direction of a local maximum of a [FarFieldPattern]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FarFieldLobe {
+    /// Polar angle from the normal of the array in degrees
+    pub theta: f32,
+    /// Azimuth from the first axis of the plot in degrees
+    pub phi: f32,
+    /// Level relative to the maximum in dB
+    pub level: f32,
+}
+
+/// Far-field directivity of the array, i.e., the pressure at a large distance times the distance,
+/// over the hemisphere around the mean direction of the sources, sampled on a grid of the
+/// direction cosines along the axes of the plot. The reflector, the obstacles, the scatterers
+/// and the attenuation are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FarFieldPattern {
+    /// Axes of the plot and the normal of the array
+    pub axes: [Vector3; 3],
+    /// Number of the samples of each direction cosine from -1 to 1
+    pub resolution: usize,
+    /// Level relative to the maximum in dB row by row from the bottom, or None out of the
+    /// hemisphere
+    pub levels: Vec<Option<f32>>,
+    /// Maximum of the amplitude times the distance in mm
+    pub max: f32,
+}
+
+/// Axes of the plot of the far field and the normal of the array, which is the mean direction of
+/// the sources, the first axis being the x axis of the workspace in the plane of the array or the
+/// y axis if the normal is along x
+pub fn array_axes(model: &FieldModel) -> [Vector3; 3] {
+    let dir_sum = model
+        .sources
+        .iter()
+        .fold([0.0; 3], |acc, s| vecmath::vec3_add(acc, s.dir));
+    let normal = if vecmath::vec3_len(dir_sum) > 0.0 {
+        vecmath::vec3_normalized(dir_sum)
+    } else {
+        [0.0, 0.0, 1.0]
+    };
+    let x = if normal[0].abs() < 0.99 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    let u = vecmath::vec3_normalized(vecmath::vec3_sub(
+        x,
+        vecmath::vec3_scale(normal, vecmath::vec3_dot(x, normal)),
+    ));
+    let v = vecmath::vec3_cross(normal, u);
+    [u, v, normal]
+}
+
+/// Far-field pressure toward the unit vector `dir` times the distance from the origin in mm, in
+/// the unit of the slice
+pub fn array_factor(model: &FieldModel, dir: Vector3) -> Complex {
+    model.sources.iter().fold([0.0, 0.0], |[re, im], s| {
+        let k = s.wavenumber(model.settings);
+        let cos = vecmath::vec3_dot(dir, s.dir).clamp(-1.0, 1.0);
+        let amp = s.amp * model.directivity(cos.acos(), k);
+        // the distance from the source is R - dir.r of the distance R from the origin
+        let p = s.phase + k * vecmath::vec3_dot(dir, s.pos);
+        [re + amp * p.cos(), im + amp * p.sin()]
+    })
+}
+
+impl FarFieldPattern {
+    /// None if there is no source
+    pub fn compute(model: &FieldModel, resolution: usize) -> Option<Self> {
+        if model.sources.is_empty() {
+            return None;
+        }
+        let resolution = resolution.max(2);
+        let axes = array_axes(model);
+        let amps: Vec<Option<f32>> = (0..resolution * resolution)
+            .into_par_iter()
+            .map(|k| {
+                let dir = Self::cosines_to_dir(&axes, resolution, k % resolution, k / resolution)?;
+                let [re, im] = array_factor(model, dir);
+                Some((re * re + im * im).sqrt())
+            })
+            .collect();
+        let max = amps.iter().flatten().copied().fold(0.0, f32::max);
+        let levels = amps
+            .into_iter()
+            .map(|a| a.map(|a| 20.0 * (a / max.max(f32::MIN_POSITIVE)).max(1e-10).log10()))
+            .collect();
+        Some(Self {
+            axes,
+            resolution,
+            levels,
+            max,
+        })
+    }
+
+    fn cosines_to_dir(
+        axes: &[Vector3; 3],
+        resolution: usize,
+        i: usize,
+        j: usize,
+    ) -> Option<Vector3> {
+        let cosine = |i: usize| -1.0 + 2.0 * (i as f32 + 0.5) / resolution as f32;
+        let (a, b) = (cosine(i), cosine(j));
+        let c2 = 1.0 - a * a - b * b;
+        if c2 < 0.0 {
+            return None;
+        }
+        let [u, v, n] = axes;
+        let d = vecmath::vec3_add(vecmath::vec3_scale(*u, a), vecmath::vec3_scale(*v, b));
+        Some(vecmath::vec3_add(d, vecmath::vec3_scale(*n, c2.sqrt())))
+    }
+
+    /// Direction of the sample at the column `i` and the row `j` from the bottom, or None out of
+    /// the hemisphere
+    pub fn direction(&self, i: usize, j: usize) -> Option<Vector3> {
+        Self::cosines_to_dir(&self.axes, self.resolution, i, j)
+    }
+
+    /// Local maxima above `floor` in dB in descending order of the level, the first of which is
+    /// the main lobe
+    pub fn lobes(&self, floor: f32) -> Vec<FarFieldLobe> {
+        let n = self.resolution as isize;
+        let level = |i: isize, j: isize| {
+            if i < 0 || j < 0 || i >= n || j >= n {
+                return None;
+            }
+            self.levels[(j * n + i) as usize]
+        };
+        let mut lobes: Vec<_> = (0..n)
+            .flat_map(|j| (0..n).map(move |i| (i, j)))
+            .filter_map(|(i, j)| {
+                let l = level(i, j)?;
+                let is_max = l >= floor
+                    && (-1..=1)
+                        .flat_map(|di| (-1..=1).map(move |dj| (di, dj)))
+                        .filter(|&d| d != (0, 0))
+                        .all(|(di, dj)| level(i + di, j + dj).is_none_or(|m| m < l));
+                if !is_max {
+                    return None;
+                }
+                let d = self.direction(i as usize, j as usize)?;
+                let [u, v, normal] = self.axes;
+                let cos = vecmath::vec3_dot(d, normal).clamp(-1.0, 1.0);
+                let phi = vecmath::vec3_dot(d, v).atan2(vecmath::vec3_dot(d, u));
+                Some(FarFieldLobe {
+                    theta: cos.acos().to_degrees(),
+                    phi: phi.to_degrees(),
+                    level: l,
+                })
+            })
+            .collect();
+        lobes.sort_by(|a, b| b.level.total_cmp(&a.level));
+        lobes
+    }
+}
@@ -0,0 +1,1040 @@
+/*
+ * File: field.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use autd3_core::hardware_defined::NUM_TRANS_IN_UNIT;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    directivity::{Directivity, DirectivityTable},
+    levitation::{GorkovCoefficients, Particle},
+    scatterer::Scattering,
+    sound_source::SoundSource,
+    view::{CompareView, FieldQuantity, ViewerSettings},
+    Matrix4, Vector3,
+};
+
+/// Complex pressure as [re, im], in the arbitrary unit of the slice
+pub type Complex = [f32; 2];
+
+/// Model of the propagation from each source to a point
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FieldApproximation {
+    /// Spherical wave of each source
+    #[default]
+    Exact,
+    /// Fresnel approximation about the center of each [SourceGroup], where the amplitude, the
+    /// directivity, the attenuation and the obstacles are evaluated once per group and the
+    /// distance of each source is expanded to the second order of its offset from the center.
+    /// The phase error grows with the cube of the size of the group and decreases with the square
+    /// of the distance, see [FieldModel::fresnel_phase_error_on_slice].
+    Fresnel,
+}
+
+impl FieldApproximation {
+    pub const ALL: [FieldApproximation; 2] =
+        [FieldApproximation::Exact, FieldApproximation::Fresnel];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldApproximation::Exact => "Exact",
+            FieldApproximation::Fresnel => "Fresnel",
+        }
+    }
+}
+
+/// Consecutive sources approximated together in [FieldApproximation::Fresnel]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceGroup {
+    /// Indices of the sources from `start` to `end` exclusive
+    pub start: usize,
+    pub end: usize,
+    /// Mean of the positions in mm
+    pub center: Vector3,
+    /// Direction of the first source
+    pub dir: Vector3,
+    /// Largest distance of the sources from the center in mm
+    pub radius: f32,
+}
+
+impl SourceGroup {
+    /// `sources` split into the devices of `device_sizes`, the number of the sources of each, or
+    /// every NUM_TRANS_IN_UNIT if the devices are unknown. The sources beyond the devices are a
+    /// group.
+    pub fn split(sources: &[SoundSource], device_sizes: &[usize]) -> Vec<SourceGroup> {
+        let mut ends: Vec<_> = if device_sizes.is_empty() {
+            (1..=sources.len() / NUM_TRANS_IN_UNIT)
+                .map(|i| i * NUM_TRANS_IN_UNIT)
+                .collect()
+        } else {
+            device_sizes
+                .iter()
+                .scan(0, |end, &size| {
+                    *end += size;
+                    Some(*end)
+                })
+                .collect()
+        };
+        ends.retain(|&end| end < sources.len());
+        ends.push(sources.len());
+        let starts = std::iter::once(0).chain(ends.clone());
+        starts
+            .zip(ends)
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| {
+                let group = &sources[start..end];
+                let sum = group
+                    .iter()
+                    .fold([0.0; 3], |acc, s| vecmath::vec3_add(acc, s.pos));
+                let center = vecmath::vec3_scale(sum, 1.0 / group.len() as f32);
+                let radius = group
+                    .iter()
+                    .map(|s| vecmath_util::dist(s.pos, center))
+                    .fold(0.0, f32::max);
+                SourceGroup {
+                    start,
+                    end,
+                    center,
+                    dir: group[0].dir,
+                    radius,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Acoustic field computed on the CPU with the same model as the slice shader, for exports and
+/// measurements
+#[derive(Clone, Copy)]
+pub struct FieldModel<'a> {
+    pub sources: &'a [SoundSource],
+    pub settings: &'a ViewerSettings,
+    /// Used for [Directivity::Imported]
+    pub directivity_table: Option<&'a DirectivityTable>,
+    /// Rigid bodies scattering the field of the sources, of the viewer frequency
+    pub scattering: Option<&'a Scattering>,
+    /// Number of the sources of each device, see [SourceGroup::split]
+    pub device_sizes: &'a [usize],
+}
+
+impl<'a> FieldModel<'a> {
+    pub(crate) fn directivity(&self, theta: f32, wavenumber: f32) -> f32 {
+        match (self.settings.directivity, self.directivity_table) {
+            (Directivity::Imported, Some(table)) => {
+                table.value(theta.to_degrees(), self.settings.frequency)
+            }
+            (directivity, _) => directivity.value(theta, wavenumber),
+        }
+    }
+
+    // The source and its image in the reflector if enabled, as the position, the direction and
+    // the factor of the amplitude
+    fn emitters(&self, source: &SoundSource) -> impl Iterator<Item = (Vector3, Vector3, f32)> {
+        let reflector = self.settings.reflector;
+        let image = reflector.enabled.then(|| {
+            (
+                reflector.image(source.pos),
+                reflector.image_dir(source.dir),
+                reflector.coefficient,
+            )
+        });
+        std::iter::once((source.pos, source.dir, 1.0)).chain(image)
+    }
+
+    // Behind the reflector, where the field is zero
+    fn is_shadowed(&self, pos: Vector3) -> bool {
+        let reflector = &self.settings.reflector;
+        reflector.enabled && reflector.distance(pos) < 0.0
+    }
+
+    // Wavenumber in 1/mm of the viewer frequency, of the scattered field
+    fn wavenumber(&self) -> f32 {
+        2.0 * PI / self.settings.wave_length
+    }
+
+    /// Complex pressure at `pos` in mm
+    pub fn pressure_at(&self, pos: Vector3) -> Complex {
+        if self.is_shadowed(pos) {
+            return [0.0, 0.0];
+        }
+        let [re, im] = self.incident_pressure(pos, |_| true);
+        let attenuation = self.settings.medium.attenuation;
+        match self.scattering {
+            Some(scattering) => {
+                let [sr, si] = scattering.pressure_at(pos, self.wavenumber(), attenuation);
+                [re + sr, im + si]
+            }
+            None => [re, im],
+        }
+    }
+
+    /// Pressure at `pos` on a surface with the outward `normal` of the sources and their images
+    /// in front of it, without the scattering, i.e., the field lighting the surface
+    pub fn incident_pressure_on(&self, pos: Vector3, normal: Vector3) -> Complex {
+        if self.is_shadowed(pos) {
+            return [0.0, 0.0];
+        }
+        self.incident_pressure(pos, |e| {
+            vecmath::vec3_dot(vecmath::vec3_sub(e, pos), normal) > 0.0
+        })
+    }
+
+    // Whether an obstacle blocks the path from the source at `source_pos` to `pos` through the
+    // emitter at `emitter_pos`, which is the source itself or its image, whose path is reflected
+    // at the point of the reflector on the segment from the image to `pos`
+    fn is_occluded(&self, source_pos: Vector3, emitter_pos: Vector3, pos: Vector3) -> bool {
+        let mut obstacles = self.settings.obstacles.iter().filter(|o| o.enabled);
+        if emitter_pos == source_pos {
+            return obstacles.any(|o| o.blocks(source_pos, pos));
+        }
+        let reflector = &self.settings.reflector;
+        let (de, dp) = (reflector.distance(emitter_pos), reflector.distance(pos));
+        let t = de / (de - dp);
+        let r = vecmath::vec3_add(
+            emitter_pos,
+            vecmath::vec3_scale(vecmath::vec3_sub(pos, emitter_pos), t),
+        );
+        obstacles.any(|o| o.blocks(source_pos, r) || o.blocks(r, pos))
+    }
+
+    // Calls `f` with the pressure at `pos` of each emitter at the position for which `front`
+    // holds, whose path is not blocked by the obstacles, the unit vector from it to `pos`, the
+    // distance of the amplitude and the wavenumber
+    fn for_each_contribution(
+        &self,
+        pos: Vector3,
+        front: impl Fn(Vector3) -> bool,
+        mut f: impl FnMut(Complex, Vector3, f32, f32),
+    ) {
+        let attenuation = self.settings.medium.attenuation;
+        if self.settings.approximation == FieldApproximation::Fresnel {
+            for group in SourceGroup::split(self.sources, self.device_sizes) {
+                self.for_each_fresnel(&group, pos, &front, &mut f);
+            }
+            return;
+        }
+        let emitters = self
+            .sources
+            .iter()
+            .flat_map(|source| self.emitters(source).map(move |e| (source, e)))
+            .filter(|(_, (source_pos, _, _))| front(*source_pos))
+            .filter(|(source, (source_pos, _, _))| !self.is_occluded(source.pos, *source_pos, pos));
+        for (source, (source_pos, dir, gain)) in emitters {
+            let r = vecmath::vec3_sub(pos, source_pos);
+            let d = vecmath::vec3_len(r);
+            if d == 0.0 {
+                continue;
+            }
+            let k = source.wavenumber(self.settings);
+            let cos = (vecmath::vec3_dot(r, dir) / d).clamp(-1.0, 1.0);
+            let amp =
+                gain * source.amp * self.directivity(cos.acos(), k) * (-attenuation * d).exp() / d;
+            let p = source.phase - k * d;
+            f(
+                [amp * p.cos(), amp * p.sin()],
+                vecmath::vec3_scale(r, 1.0 / d),
+                d,
+                k,
+            );
+        }
+    }
+
+    // for_each_contribution of the sources of `group` and their images in the Fresnel
+    // approximation about the center of the group, the same as the slice shader
+    fn for_each_fresnel(
+        &self,
+        group: &SourceGroup,
+        pos: Vector3,
+        front: impl Fn(Vector3) -> bool,
+        mut f: impl FnMut(Complex, Vector3, f32, f32),
+    ) {
+        let attenuation = self.settings.medium.attenuation;
+        let sources = &self.sources[group.start..group.end];
+        let k_center = sources[0].wavenumber(self.settings);
+        let centers = self.emitters(&SoundSource::new(group.center, group.dir, 1.0, 0.0));
+        for (image, (center, dir, gain)) in centers.enumerate() {
+            if self.is_occluded(group.center, center, pos) {
+                continue;
+            }
+            let r = vecmath::vec3_sub(pos, center);
+            let big_r = vecmath::vec3_len(r);
+            if big_r == 0.0 {
+                continue;
+            }
+            let u = vecmath::vec3_scale(r, 1.0 / big_r);
+            let cos = vecmath::vec3_dot(u, dir).clamp(-1.0, 1.0);
+            let common =
+                gain * self.directivity(cos.acos(), k_center) * (-attenuation * big_r).exp()
+                    / big_r;
+            for source in sources {
+                let source_pos = match self.emitters(source).nth(image) {
+                    Some((p, _, _)) => p,
+                    None => continue,
+                };
+                if !front(source_pos) {
+                    continue;
+                }
+                // |R u - r'| to the second order of r'
+                let offset = vecmath::vec3_sub(source_pos, center);
+                let along = vecmath::vec3_dot(u, offset);
+                let d = big_r - along
+                    + (vecmath::vec3_dot(offset, offset) - along * along) / (2.0 * big_r);
+                let k = source.wavenumber(self.settings);
+                let amp = common * source.amp;
+                let p = source.phase - k * d;
+                f([amp * p.cos(), amp * p.sin()], u, big_r, k);
+            }
+        }
+    }
+
+    // Pressure of the emitters at the positions for which `front` holds, whose paths are not
+    // blocked by the obstacles
+    fn incident_pressure(&self, pos: Vector3, front: impl Fn(Vector3) -> bool) -> Complex {
+        let mut sum = [0.0, 0.0];
+        self.for_each_contribution(pos, front, |[re, im], _, _, _| {
+            sum = [sum[0] + re, sum[1] + im];
+        });
+        sum
+    }
+
+    pub fn pressure(&self, points: &[Vector3]) -> Vec<Complex> {
+        points.iter().map(|&p| self.pressure_at(p)).collect()
+    }
+
+    /// Complex pressure and its gradient in 1/mm at `pos`, where the gradient of the directivity
+    /// is neglected as in the slice shader
+    pub fn pressure_gradient_at(&self, pos: Vector3) -> (Complex, [Complex; 3]) {
+        let attenuation = self.settings.medium.attenuation;
+        let mut p = [0.0, 0.0];
+        let mut grad = [[0.0, 0.0]; 3];
+        if self.is_shadowed(pos) {
+            return (p, grad);
+        }
+        self.for_each_contribution(
+            pos,
+            |_| true,
+            |[re, im], u, d, k| {
+                p[0] += re;
+                p[1] += im;
+                // d/dd of exp(-ikd - ad) / d is (-1/d - a - ik) times itself
+                let a = -1.0 / d - attenuation;
+                for (g, &ui) in grad.iter_mut().zip(u.iter()) {
+                    g[0] += (a * re + k * im) * ui;
+                    g[1] += (a * im - k * re) * ui;
+                }
+            },
+        );
+        if let Some(scattering) = self.scattering {
+            let (ps, grad_s) = scattering.pressure_gradient_at(pos, self.wavenumber(), attenuation);
+            p = [p[0] + ps[0], p[1] + ps[1]];
+            for (g, s) in grad.iter_mut().zip(grad_s.iter()) {
+                *g = [g[0] + s[0], g[1] + s[1]];
+            }
+        }
+        (p, grad)
+    }
+
+    /// Gor'kov potential at `pos`, in J for the pressure in Pa
+    pub fn gorkov_at(&self, pos: Vector3) -> f32 {
+        let (p, grad) = self.pressure_gradient_at(pos);
+        GorkovCoefficients::new(self.settings).potential(p, grad)
+    }
+
+    /// Force on the particle, the negative gradient of the Gor'kov potential, in N for the
+    /// pressure in Pa, by the central difference
+    pub fn gorkov_force_vector_at(&self, pos: Vector3) -> Vector3 {
+        self.gorkov_force(pos, GorkovCoefficients::new(self.settings))
+    }
+
+    /// [FieldModel::gorkov_force_vector_at] for `particle` instead of [ViewerSettings::particle]
+    pub fn gorkov_force_on(&self, pos: Vector3, particle: &Particle) -> Vector3 {
+        self.gorkov_force(
+            pos,
+            GorkovCoefficients::for_particle(self.settings, particle),
+        )
+    }
+
+    fn gorkov_force(&self, pos: Vector3, coef: GorkovCoefficients) -> Vector3 {
+        let h = self.settings.wave_length / 20.0;
+        let potential = |pos| {
+            let (p, grad) = self.pressure_gradient_at(pos);
+            coef.potential(p, grad)
+        };
+        [0, 1, 2].map(|i| {
+            let mut p0 = pos;
+            let mut p1 = pos;
+            p0[i] -= h;
+            p1[i] += h;
+            -(potential(p1) - potential(p0)) / (2.0 * h) * 1000.0
+        })
+    }
+
+    /// Magnitude of [FieldModel::gorkov_force_vector_at]
+    pub fn gorkov_force_at(&self, pos: Vector3) -> f32 {
+        vecmath::vec3_len(self.gorkov_force_vector_at(pos))
+    }
+
+    /// Value of [ViewerSettings::field_quantity] at `pos`, where the real part is taken at the
+    /// phase `wt`
+    pub fn quantity_at(&self, pos: Vector3, wt: f32) -> f32 {
+        match self.settings.field_quantity {
+            FieldQuantity::GorkovPotential => self.gorkov_at(pos),
+            FieldQuantity::GorkovForce => self.gorkov_force_at(pos),
+            _ => quantity_value(self.pressure_at(pos), self.settings, wt),
+        }
+    }
+
+    pub fn quantity(&self, points: &[Vector3], wt: f32) -> Vec<f32> {
+        points.iter().map(|&p| self.quantity_at(p, wt)).collect()
+    }
+
+    /// Position and amplitude of the maximum of the amplitude on the slice transformed by `model`,
+    /// searched on a grid of a half wavelength in parallel and refined around the maximum on it
+    pub fn peak_on_slice(&self, model: Matrix4) -> (Vector3, f32) {
+        const REFINE: i32 = 4;
+
+        let amp = |p| {
+            let [re, im] = self.pressure_at(p);
+            (re * re + im * im).sqrt()
+        };
+        let max = |a: (Vector3, f32), b: (Vector3, f32)| if b.1 > a.1 { b } else { a };
+        let grid = SliceGrid::new(model, self.settings, self.settings.wave_length / 2.0);
+        let coarse = grid
+            .points
+            .par_iter()
+            .map(|&p| (p, amp(p)))
+            .reduce(|| ([0.0; 3], 0.0), max);
+        let h = self.settings.wave_length / (4 * REFINE) as f32;
+        let (u, v) = (
+            vecmath_util::to_vec3(&model[0]),
+            vecmath_util::to_vec3(&model[1]),
+        );
+        (-REFINE..=REFINE)
+            .flat_map(|i| (-REFINE..=REFINE).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                let p = vecmath::vec3_add(coarse.0, vecmath::vec3_scale(u, i as f32 * h));
+                vecmath::vec3_add(p, vecmath::vec3_scale(v, j as f32 * h))
+            })
+            .map(|p| (p, amp(p)))
+            .fold(coarse, max)
+    }
+
+    /// Largest phase error in radians of [FieldApproximation::Fresnel] on the slice transformed
+    /// by `model`, bounded by k a^3 / (3 sqrt(3) R^2), the maximum of the third-order term
+    /// k (u.r')(|r'|^2 - (u.r')^2) / (2 R^2) over the directions u, for the radius a of each group
+    /// and the distance R from its center to the nearest point of the slice
+    pub fn fresnel_phase_error_on_slice(&self, model: Matrix4) -> f32 {
+        let half = [
+            self.settings.slice_width as f32 / 2.0,
+            self.settings.slice_height as f32 / 2.0,
+        ];
+        let origin = vecmath_util::to_vec3(&model[3]);
+        let axes = [
+            vecmath_util::to_vec3(&model[0]),
+            vecmath_util::to_vec3(&model[1]),
+        ];
+        SourceGroup::split(self.sources, self.device_sizes)
+            .iter()
+            .map(|g| {
+                let c = vecmath::vec3_sub(g.center, origin);
+                let nearest = (0..2).fold(origin, |p, i| {
+                    let axis = axes[i];
+                    let t = vecmath::vec3_dot(c, axis) / vecmath::vec3_dot(axis, axis).max(1e-12);
+                    vecmath::vec3_add(p, vecmath::vec3_scale(axis, t.clamp(-half[i], half[i])))
+                });
+                let r = vecmath_util::dist(g.center, nearest).max(g.radius);
+                let k = self.sources[g.start].wavenumber(self.settings);
+                k * g.radius.powi(3) / (3.0 * 3f32.sqrt() * r * r)
+            })
+            .fold(0.0, f32::max)
+    }
+
+    /// Maximum of the amplitude on the slice transformed by `model` and the widths of the spot
+    /// around it, for which it is marched from the maximum along each axis of the slice
+    pub fn focal_spot(&self, model: Matrix4) -> FocalSpot {
+        let (pos, amp) = self.peak_on_slice(model);
+        let h = self.settings.wave_length / 32.0;
+        let center = vecmath_util::to_vec3(&model[3]);
+        let half_extents = [
+            self.settings.slice_width as f32 / 2.0,
+            self.settings.slice_height as f32 / 2.0,
+        ];
+        let half = amp / 2.0;
+        let amp_at = |p| {
+            let [re, im] = self.pressure_at(p);
+            (re * re + im * im).sqrt()
+        };
+        let widths = [0, 1].map(|i| {
+            let axis = vecmath::vec3_normalized(vecmath_util::to_vec3(&model[i]));
+            let offset = vecmath::vec3_dot(vecmath::vec3_sub(pos, center), axis);
+            // distance to the half maximum in the direction `sign`, linearly interpolated
+            let reach = |sign: f32| {
+                let mut prev = (0.0, amp);
+                let mut s = h;
+                while (offset + sign * s).abs() <= half_extents[i] {
+                    let a = amp_at(vecmath::vec3_add(pos, vecmath::vec3_scale(axis, sign * s)));
+                    if a < half {
+                        return Some(prev.0 + (prev.1 - half) / (prev.1 - a) * (s - prev.0));
+                    }
+                    prev = (s, a);
+                    s += h;
+                }
+                None
+            };
+            Some(reach(-1.0)? + reach(1.0)?)
+        });
+        FocalSpot { pos, amp, widths }
+    }
+}
+
+/// Maximum of the amplitude on a slice, see [FieldModel::focal_spot]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocalSpot {
+    pub pos: Vector3,
+    pub amp: f32,
+    /// Full widths in mm at -6 dB, i.e., the half of the maximum amplitude, along the x and y axes
+    /// of the slice, or None if the spot is not bounded within the slice
+    pub widths: [Option<f32>; 2],
+}
+
+/// Value of [ViewerSettings::field_quantity] for the pressure `p`, where the real part is taken at
+/// the phase `wt`. The Gor'kov potential and force need the gradient, see
+/// [FieldModel::quantity_at].
+pub fn quantity_value(p: Complex, settings: &ViewerSettings, wt: f32) -> f32 {
+    let [re, im] = p;
+    match settings.field_quantity {
+        FieldQuantity::Amplitude => (re * re + im * im).sqrt(),
+        FieldQuantity::Phase => im.atan2(re),
+        FieldQuantity::RealPart => re * wt.cos() - im * wt.sin(),
+        FieldQuantity::Intensity => re * re + im * im,
+        FieldQuantity::RadiationPressure => settings
+            .medium
+            .radiation_pressure((re * re + im * im).sqrt()),
+        FieldQuantity::GorkovPotential | FieldQuantity::GorkovForce => f32::NAN,
+    }
+}
+
+/// Value of [ViewerSettings::field_quantity] at the full scale of the color map, and pi for the
+/// phase
+fn full_scale(settings: &ViewerSettings) -> f32 {
+    let scale = settings.color_scale;
+    match settings.field_quantity {
+        FieldQuantity::Amplitude | FieldQuantity::RealPart => scale,
+        FieldQuantity::Phase => PI,
+        FieldQuantity::Intensity => scale * scale,
+        FieldQuantity::RadiationPressure => settings.medium.radiation_pressure(scale),
+        FieldQuantity::GorkovPotential => GorkovCoefficients::new(settings).reference(scale),
+        FieldQuantity::GorkovForce => {
+            let k = 2.0 * PI / settings.wave_length * 1000.0;
+            GorkovCoefficients::new(settings).reference(scale) * k
+        }
+    }
+}
+
+/// `current` minus `reference` of [ViewerSettings::field_quantity] for [CompareView::Difference],
+/// where the difference of the phases is wrapped from -pi to pi
+pub fn value_difference(current: f32, reference: f32, settings: &ViewerSettings) -> f32 {
+    match settings.field_quantity {
+        FieldQuantity::Phase => (current - reference + PI).rem_euclid(2.0 * PI) - PI,
+        _ => current - reference,
+    }
+}
+
+/// Position from 0 to 1 on the color map of `value` of [ViewerSettings::field_quantity], the
+/// same as the slice shader. The value is a [value_difference] for [CompareView::Difference],
+/// with 0 at the middle of the color map.
+pub fn color_position(value: f32, settings: &ViewerSettings) -> f32 {
+    let scale = settings.color_scale;
+    let gamma = settings.gamma;
+    if settings.compare == CompareView::Difference {
+        let t = match settings.field_quantity {
+            FieldQuantity::Phase => (value + PI) / (2.0 * PI),
+            _ => {
+                let v = value / full_scale(settings);
+                0.5 + 0.5 * v.signum() * v.abs().min(1.0).powf(1.0 / gamma)
+            }
+        };
+        return t.clamp(0.0, 1.0);
+    }
+    let t = match settings.field_quantity {
+        FieldQuantity::Amplitude => magnitude_position(value / scale, 20.0, settings),
+        FieldQuantity::Phase => (value + PI) / (2.0 * PI),
+        FieldQuantity::RealPart => {
+            let v = value / scale;
+            0.5 + 0.5 * v.signum() * v.abs().min(1.0).powf(1.0 / gamma)
+        }
+        FieldQuantity::Intensity => magnitude_position(value / (scale * scale), 10.0, settings),
+        FieldQuantity::RadiationPressure => magnitude_position(
+            value / settings.medium.radiation_pressure(scale),
+            10.0,
+            settings,
+        ),
+        FieldQuantity::GorkovPotential => {
+            let v = value / GorkovCoefficients::new(settings).reference(scale);
+            0.5 + 0.5 * v.signum() * v.abs().min(1.0).powf(1.0 / gamma)
+        }
+        FieldQuantity::GorkovForce => {
+            let k = 2.0 * PI / settings.wave_length * 1000.0;
+            let full = GorkovCoefficients::new(settings).reference(scale) * k;
+            magnitude_position(value / full, 10.0, settings)
+        }
+    };
+    t.clamp(0.0, 1.0)
+}
+
+/// Position on the color map of the magnitude `v` relative to the full scale, in dB over
+/// [ViewerSettings::dynamic_range] if set, where `db` is 20 for amplitudes and 10 for powers,
+/// otherwise with the gamma
+fn magnitude_position(v: f32, db: f32, settings: &ViewerSettings) -> f32 {
+    match settings.dynamic_range {
+        Some(range) if range > 0.0 => 1.0 + db * v.max(1e-30).log10() / range,
+        _ => v.powf(1.0 / settings.gamma),
+    }
+}
+
+/// Inverse of [magnitude_position]
+fn magnitude_value(t: f32, db: f32, settings: &ViewerSettings) -> f32 {
+    match settings.dynamic_range {
+        Some(range) if range > 0.0 => 10f32.powf((t - 1.0) * range / db),
+        _ => t.powf(settings.gamma),
+    }
+}
+
+/// Value of [ViewerSettings::field_quantity] at the position `t` from 0 to 1 on the color map, the
+/// inverse of [color_position], e.g., for the ticks of a color bar
+pub fn color_value(t: f32, settings: &ViewerSettings) -> f32 {
+    let scale = settings.color_scale;
+    let gamma = settings.gamma;
+    let t = t.clamp(0.0, 1.0);
+    let signed = |full: f32| {
+        let v = 2.0 * t - 1.0;
+        full * v.signum() * v.abs().powf(gamma)
+    };
+    if settings.compare == CompareView::Difference {
+        return match settings.field_quantity {
+            FieldQuantity::Phase => t * 2.0 * PI - PI,
+            _ => signed(full_scale(settings)),
+        };
+    }
+    match settings.field_quantity {
+        FieldQuantity::Amplitude => scale * magnitude_value(t, 20.0, settings),
+        FieldQuantity::Phase => t * 2.0 * PI - PI,
+        FieldQuantity::RealPart => signed(scale),
+        FieldQuantity::Intensity => scale * scale * magnitude_value(t, 10.0, settings),
+        FieldQuantity::RadiationPressure => {
+            settings.medium.radiation_pressure(scale) * magnitude_value(t, 10.0, settings)
+        }
+        FieldQuantity::GorkovPotential => {
+            signed(GorkovCoefficients::new(settings).reference(scale))
+        }
+        FieldQuantity::GorkovForce => {
+            let k = 2.0 * PI / settings.wave_length * 1000.0;
+            GorkovCoefficients::new(settings).reference(scale)
+                * k
+                * magnitude_value(t, 10.0, settings)
+        }
+    }
+}
+
+/// Values of [ViewerSettings::field_quantity] of `field` at the points of the [SliceGrid] spaced by
+/// `resolution` mm on the slice transformed by `model`, computed in parallel on the CPU, e.g.,
+/// without working GL or as the reference of the slice shader. The values are the same, bit for
+/// bit, as [FieldModel::quantity] and the exports.
+pub fn compute_slice(
+    field: &FieldModel,
+    model: Matrix4,
+    resolution: f32,
+    wt: f32,
+) -> (SliceGrid, Vec<f32>) {
+    let grid = SliceGrid::new(model, field.settings, resolution);
+    let values = grid
+        .points
+        .par_iter()
+        .map(|&p| field.quantity_at(p, wt))
+        .collect();
+    (grid, values)
+}
+
+/// Complex pressure of `field` at the points of the [SliceGrid] of [compute_slice], computed in
+/// parallel on the CPU
+pub fn compute_slice_pressure(
+    field: &FieldModel,
+    model: Matrix4,
+    resolution: f32,
+) -> (SliceGrid, Vec<Complex>) {
+    let grid = SliceGrid::new(model, field.settings, resolution);
+    let values = grid
+        .points
+        .par_iter()
+        .map(|&p| field.pressure_at(p))
+        .collect();
+    (grid, values)
+}
+
+/// Force of [FieldModel::gorkov_force_vector_at] at the points of the [SliceGrid] of
+/// [compute_slice], computed in parallel on the CPU
+pub fn compute_slice_force(
+    field: &FieldModel,
+    model: Matrix4,
+    resolution: f32,
+) -> (SliceGrid, Vec<Vector3>) {
+    let grid = SliceGrid::new(model, field.settings, resolution);
+    let values = grid
+        .points
+        .par_iter()
+        .map(|&p| field.gorkov_force_vector_at(p))
+        .collect();
+    (grid, values)
+}
+
+/// Complex pressure of `field` at the `dims` points spaced by `resolution` mm along the x, y and z
+/// axes from `min`, with x the fastest and z the slowest, computed in parallel on the CPU
+pub fn compute_grid(
+    field: &FieldModel,
+    min: Vector3,
+    dims: [usize; 3],
+    resolution: f32,
+) -> Vec<Complex> {
+    let [nx, ny, nz] = dims;
+    (0..nx * ny * nz)
+        .into_par_iter()
+        .map(|i| {
+            let idx = [i % nx, i / nx % ny, i / (nx * ny)];
+            field.pressure_at([0, 1, 2].map(|k| min[k] + idx[k] as f32 * resolution))
+        })
+        .collect()
+}
+
+/// Grid of points on a slice
+pub struct SliceGrid {
+    pub nx: usize,
+    pub ny: usize,
+    /// Row by row from the bottom of the slice
+    pub points: Vec<Vector3>,
+}
+
+impl SliceGrid {
+    /// Points spaced by `resolution` mm over the slice of `settings`, whose transform is `model`
+    pub fn new(model: Matrix4, settings: &ViewerSettings, resolution: f32) -> Self {
+        let resolution = resolution.max(1e-3);
+        let (width, height) = (settings.slice_width as f32, settings.slice_height as f32);
+        let nx = (width / resolution).floor() as usize + 1;
+        let ny = (height / resolution).floor() as usize + 1;
+        let points = (0..ny)
+            .flat_map(|iy| (0..nx).map(move |ix| (ix, iy)))
+            .map(|(ix, iy)| {
+                let x = -width / 2.0 + ix as f32 * resolution;
+                let y = -height / 2.0 + iy as f32 * resolution;
+                let p = vecmath::col_mat4_transform(model, [x, y, 0.0, 1.0]);
+                [p[0], p[1], p[2]]
+            })
+            .collect();
+        Self { nx, ny, points }
+    }
+
+    /// Segments of the contour at `level` of `values` at the points by marching squares, where
+    /// the saddles are resolved by the mean of the cell
+    pub fn contour(&self, values: &[f32], level: f32) -> Vec<(Vector3, Vector3)> {
+        let mut segments = Vec::new();
+        if values.len() != self.points.len() || self.nx < 2 || self.ny < 2 {
+            return segments;
+        }
+        for iy in 0..self.ny - 1 {
+            for ix in 0..self.nx - 1 {
+                // counterclockwise from the bottom left, where the edge e is from the corner e
+                let corners = [
+                    ix + iy * self.nx,
+                    ix + 1 + iy * self.nx,
+                    ix + 1 + (iy + 1) * self.nx,
+                    ix + (iy + 1) * self.nx,
+                ];
+                let v = corners.map(|i| values[i]);
+                let above = v.map(|v| v >= level);
+                let cross = |e: usize| {
+                    let (a, b) = (e, (e + 1) % 4);
+                    let t = (level - v[a]) / (v[b] - v[a]);
+                    let (pa, pb) = (self.points[corners[a]], self.points[corners[b]]);
+                    vecmath::vec3_add(pa, vecmath::vec3_scale(vecmath::vec3_sub(pb, pa), t))
+                };
+                let edges: Vec<_> = (0..4).filter(|&e| above[e] != above[(e + 1) % 4]).collect();
+                match edges.len() {
+                    2 => segments.push((cross(edges[0]), cross(edges[1]))),
+                    4 => {
+                        // the corners on the other side than the center are cut off
+                        let center = v.iter().sum::<f32>() / 4.0 >= level;
+                        let pairs = if center == above[0] {
+                            [(0, 1), (2, 3)]
+                        } else {
+                            [(3, 0), (1, 2)]
+                        };
+                        segments.extend(pairs.iter().map(|&(a, b)| (cross(a), cross(b))));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use autd3_core::hardware_defined::TRANS_SPACING_MM;
+
+    use crate::reflector::Reflector;
+
+    // Sources of a device of 18 x 14 on the xy plane facing +z, focused on `focus`
+    fn array(settings: &ViewerSettings, focus: Vector3) -> Vec<SoundSource> {
+        let k = 2.0 * PI / settings.wave_length;
+        (0..14)
+            .flat_map(|y| (0..18).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let pos = [
+                    x as f32 * TRANS_SPACING_MM as f32,
+                    y as f32 * TRANS_SPACING_MM as f32,
+                    0.0,
+                ];
+                let phase = k * vecmath_util::dist(pos, focus);
+                SoundSource::new(pos, [0.0, 0.0, 1.0], 1.0, phase)
+            })
+            .collect()
+    }
+
+    fn field<'a>(sources: &'a [SoundSource], settings: &'a ViewerSettings) -> FieldModel<'a> {
+        FieldModel {
+            sources,
+            settings,
+            directivity_table: None,
+            scattering: None,
+            device_sizes: &[],
+        }
+    }
+
+    fn translation(pos: Vector3) -> Matrix4 {
+        [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [pos[0], pos[1], pos[2], 1.0],
+        ]
+    }
+
+    fn abs([re, im]: Complex) -> f32 {
+        (re * re + im * im).sqrt()
+    }
+
+    fn sub(a: Complex, b: Complex) -> Complex {
+        [a[0] - b[0], a[1] - b[1]]
+    }
+
+    #[test]
+    fn fresnel_within_the_phase_error_on_a_far_slice() {
+        let center = [
+            TRANS_SPACING_MM as f32 * 8.5,
+            TRANS_SPACING_MM as f32 * 6.5,
+            1000.0,
+        ];
+        let mut settings = ViewerSettings {
+            slice_width: 100,
+            slice_height: 100,
+            ..Default::default()
+        };
+        let sources = array(&settings, center);
+        let model = translation(center);
+
+        let exact: Vec<_> = SliceGrid::new(model, &settings, 10.0)
+            .points
+            .iter()
+            .map(|&p| (p, field(&sources, &settings).pressure_at(p)))
+            .collect();
+        settings.approximation = FieldApproximation::Fresnel;
+        let fresnel = field(&sources, &settings);
+        let bound = fresnel.fresnel_phase_error_on_slice(model);
+        assert!(bound > 0.0 && bound < 0.3, "{}", bound);
+
+        // each contribution is off by at most the bound in phase, so is the sum relative to the
+        // sum of their magnitudes
+        let attenuation = settings.medium.attenuation;
+        for (p, expected) in exact {
+            let magnitude: f32 = sources
+                .iter()
+                .map(|s| {
+                    let d = vecmath_util::dist(p, s.pos);
+                    (-attenuation * d).exp() / d
+                })
+                .sum();
+            let error = abs(sub(fresnel.pressure_at(p), expected));
+            assert!(
+                error <= bound * magnitude,
+                "{:?}: {} > {} x {}",
+                p,
+                error,
+                bound,
+                magnitude
+            );
+        }
+    }
+
+    #[test]
+    fn reflector_mirrors_the_sources() {
+        let mut settings = ViewerSettings {
+            directivity: Directivity::T4010A1,
+            ..Default::default()
+        };
+        settings.reflector.enabled = true;
+        settings.reflector.center = [20.0, 10.0, 80.0];
+        settings.reflector.angle = [PI, 0.3, 0.2];
+        let reflector = settings.reflector;
+        let source = SoundSource::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], 1.0, 0.7);
+        let image = SoundSource::new(
+            reflector.image(source.pos),
+            reflector.image_dir(source.dir),
+            1.0,
+            0.7,
+        );
+        let with_reflector = [source];
+        let mirrored = [source, image];
+        let free_settings = ViewerSettings {
+            reflector: Reflector {
+                enabled: false,
+                ..reflector
+            },
+            ..settings
+        };
+        let reflected = field(&with_reflector, &settings);
+        let free = field(&mirrored, &free_settings);
+        let single = field(&with_reflector, &free_settings);
+
+        let n = reflector.normal();
+        let [c0, c1, c2, _] = reflector.corners();
+        let on_wall = [
+            reflector.center,
+            vecmath::vec3_scale(vecmath::vec3_add(c0, c1), 0.5),
+            vecmath::vec3_scale(vecmath::vec3_add(c1, c2), 0.5),
+        ];
+        for &w in &on_wall {
+            for &t in &[5.0, 23.0, 61.0] {
+                let front = vecmath::vec3_add(w, vecmath::vec3_scale(n, t));
+                let behind = reflector.image(front);
+                // the image sources are symmetric about the plane, of which the field in front
+                // is that with the reflector, and zero behind it
+                let p = free.pressure_at(front);
+                assert!(abs(sub(p, free.pressure_at(behind))) < 1e-3 * abs(p));
+                assert!(abs(sub(p, reflected.pressure_at(front))) < 1e-4 * abs(p));
+                assert_eq!(reflected.pressure_at(behind), [0.0, 0.0]);
+            }
+
+            // on a rigid wall, the pressure is doubled and its normal derivative vanishes, where
+            // the point is kept in front of the plane against the rounding
+            let w = vecmath::vec3_add(w, vecmath::vec3_scale(n, 1e-4));
+            let incident = single.pressure_at(w);
+            let (p, grad) = reflected.pressure_gradient_at(w);
+            assert!(abs(sub(p, [2.0 * incident[0], 2.0 * incident[1]])) < 1e-4 * abs(p));
+            let normal = [0, 1].map(|j| (0..3).map(|i| grad[i][j] * n[i]).sum::<f32>());
+            let magnitude = grad.iter().map(|&g| abs(g)).fold(0.0, f32::max);
+            assert!(abs(normal) < 1e-3 * magnitude, "{:?}", normal);
+        }
+    }
+
+    #[test]
+    fn obstacle_shadows_the_field() {
+        let center = [TRANS_SPACING_MM as f32 * 8.5, TRANS_SPACING_MM as f32 * 6.5];
+        let focus = [center[0], center[1], 200.0];
+        let below = [center[0] + 30.0, center[1], 40.0];
+        let mut settings = ViewerSettings::default();
+        let sources = array(&settings, focus);
+
+        // the sum of the monopoles without any obstacle nor scatterer
+        let k = 2.0 * PI / settings.wave_length;
+        let attenuation = settings.medium.attenuation;
+        let free_field = |pos| {
+            sources.iter().fold([0.0, 0.0], |[re, im], s| {
+                let d = vecmath_util::dist(pos, s.pos);
+                let amp = s.amp * (-attenuation * d).exp() / d;
+                let p = s.phase - k * d;
+                [re + amp * p.cos(), im + amp * p.sin()]
+            })
+        };
+        let scattering = Scattering::new(Vec::new(), &field(&sources, &settings));
+        let unscattered = FieldModel {
+            scattering: Some(&scattering),
+            ..field(&sources, &settings)
+        };
+        for &pos in &[focus, below] {
+            let expected = free_field(pos);
+            let p = field(&sources, &settings).pressure_at(pos);
+            assert!(abs(sub(p, expected)) < 1e-4 * abs(expected));
+            assert_eq!(unscattered.pressure_at(pos), p);
+        }
+
+        // a sphere between the array and the focus, above the other point
+        settings.obstacles[0].enabled = true;
+        settings.obstacles[0].center = [center[0], center[1], 100.0];
+        settings.obstacles[0].size = [50.0; 3];
+        let shadowed = field(&sources, &settings);
+        assert!(abs(shadowed.pressure_at(focus)) < 0.3 * abs(free_field(focus)));
+        assert_eq!(
+            shadowed.pressure_at(below),
+            field(&sources, &ViewerSettings::default()).pressure_at(below)
+        );
+    }
+
+    #[test]
+    fn gorkov_potential_of_a_standing_wave() {
+        const D: f32 = 500.0;
+
+        let mut settings = ViewerSettings::default();
+        settings.medium.attenuation = 0.0;
+        let k = 2.0 * PI / settings.wave_length;
+        let antinode = [0.0, 0.0, 0.0];
+        let node = [0.0, 0.0, settings.wave_length / 4.0];
+        let mut sources = [
+            SoundSource::new([0.0, 0.0, -D], [0.0, 0.0, 1.0], 0.0, 0.0),
+            SoundSource::new([0.0, 0.0, D], [0.0, 0.0, -1.0], 0.0, 0.0),
+        ];
+        for &pos in &[antinode, node] {
+            assert_eq!(field(&sources, &settings).gorkov_at(pos), 0.0);
+            assert_eq!(
+                field(&sources, &settings).gorkov_force_vector_at(pos),
+                [0.0; 3]
+            );
+        }
+
+        // the counter-propagating waves of the same phase at the antinode are nearly plane there
+        for source in &mut sources {
+            source.amp = 1.0;
+        }
+        let model = field(&sources, &settings);
+        let coef = GorkovCoefficients::new(&settings);
+        let (amp, grad) = (2.0 / D, 2.0 * k / D);
+        let u_antinode = model.gorkov_at(antinode);
+        let u_node = model.gorkov_at(node);
+        assert!((u_antinode / (coef.k1 * amp * amp) - 1.0).abs() < 1e-2);
+        assert!((u_node / (-coef.k2 * grad * grad) - 1.0).abs() < 1e-2);
+
+        // a polystyrene bead is repelled from the pressure antinode to the node, by the ratio
+        // -3 f2 / (2 f1) of the potentials in a plane standing wave
+        let (medium, particle) = (&settings.medium, &settings.particle);
+        let c = medium.sound_speed / 1000.0;
+        let c_p = particle.sound_speed / 1000.0;
+        let f1 = 1.0 - medium.density * c * c / (particle.density * c_p * c_p);
+        let f2 =
+            2.0 * (particle.density - medium.density) / (2.0 * particle.density + medium.density);
+        assert!(u_antinode > 0.0 && u_node < 0.0);
+        assert!((u_node / u_antinode / (-1.5 * f2 / f1) - 1.0).abs() < 1e-2);
+    }
+}
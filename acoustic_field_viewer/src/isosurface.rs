@@ -0,0 +1,186 @@
+/*
+ * File: isosurface.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use crate::{mesh::Mesh, volume::VolumeGrid, Vector3};
+
+// Each cube is split into six tetrahedra around its diagonal from the corner 0 to 7, where the
+// bits 0, 1 and 2 of a corner are its offsets along x, y and z. The faces of the neighboring
+// cubes are split by the same diagonals, so that the surface is closed.
+const TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 1, 3, 7],
+    [0, 3, 2, 7],
+    [0, 2, 6, 7],
+    [0, 6, 4, 7],
+    [0, 4, 5, 7],
+    [0, 5, 1, 7],
+];
+
+/// Isosurface of the amplitude of `grid` at `level` in the unit of the field by marching cubes,
+/// each of which is split into tetrahedra, so that no case table is needed and no case is
+/// ambiguous. The triangles face toward the lower amplitude.
+pub fn extract(grid: &VolumeGrid, level: f32) -> Mesh {
+    let [w, h, d] = grid.dims;
+    let mut triangles = Vec::new();
+    if w < 2 || h < 2 || d < 2 {
+        return Mesh { triangles };
+    }
+    let step: Vector3 = [0, 1, 2].map(|k| (grid.max[k] - grid.min[k]) / (grid.dims[k] - 1) as f32);
+    for z in 0..d - 1 {
+        for y in 0..h - 1 {
+            for x in 0..w - 1 {
+                let corner = |c: usize| {
+                    let (i, j, k) = (x + (c & 1), y + ((c >> 1) & 1), z + ((c >> 2) & 1));
+                    let pos = [
+                        grid.min[0] + step[0] * i as f32,
+                        grid.min[1] + step[1] * j as f32,
+                        grid.min[2] + step[2] * k as f32,
+                    ];
+                    (pos, grid.amps[i + w * (j + h * k)])
+                };
+                let corners: [(Vector3, f32); 8] = [0, 1, 2, 3, 4, 5, 6, 7].map(corner);
+                if corners.iter().all(|c| c.1 >= level) || corners.iter().all(|c| c.1 < level) {
+                    continue;
+                }
+                for tetrahedron in &TETRAHEDRA {
+                    march_tetrahedron(tetrahedron.map(|c| corners[c]), level, &mut triangles);
+                }
+            }
+        }
+    }
+    Mesh { triangles }
+}
+
+fn march_tetrahedron(vertices: [(Vector3, f32); 4], level: f32, triangles: &mut Vec<[Vector3; 3]>) {
+    let (inside, outside): (Vec<_>, Vec<_>) = vertices.iter().partition(|v| v.1 >= level);
+    let cross = |(a, va): &(Vector3, f32), (b, vb): &(Vector3, f32)| {
+        let t = ((level - va) / (vb - va)).clamp(0.0, 1.0);
+        vecmath::vec3_add(*a, vecmath::vec3_scale(vecmath::vec3_sub(*b, *a), t))
+    };
+    let polygon = match (inside.len(), outside.len()) {
+        (1, 3) => vec![
+            cross(inside[0], outside[0]),
+            cross(inside[0], outside[1]),
+            cross(inside[0], outside[2]),
+        ],
+        (3, 1) => vec![
+            cross(inside[0], outside[0]),
+            cross(inside[1], outside[0]),
+            cross(inside[2], outside[0]),
+        ],
+        (2, 2) => vec![
+            cross(inside[0], outside[0]),
+            cross(inside[0], outside[1]),
+            cross(inside[1], outside[1]),
+            cross(inside[1], outside[0]),
+        ],
+        _ => return,
+    };
+    let centroid = |vs: &[&(Vector3, f32)]| {
+        let sum = vs.iter().fold([0.0; 3], |s, v| vecmath::vec3_add(s, v.0));
+        vecmath::vec3_scale(sum, 1.0 / vs.len() as f32)
+    };
+    let outward = vecmath::vec3_sub(centroid(&outside), centroid(&inside));
+    for k in 1..polygon.len() - 1 {
+        let (a, b, c) = (polygon[0], polygon[k], polygon[k + 1]);
+        let normal = vecmath::vec3_cross(vecmath::vec3_sub(b, a), vecmath::vec3_sub(c, a));
+        if vecmath::vec3_dot(normal, outward) < 0.0 {
+            triangles.push([a, c, b]);
+        } else {
+            triangles.push([a, b, c]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Grid of 3 points along each axis over 0 to 2 mm of the amplitude `f` of the position
+    fn grid(f: impl Fn(Vector3) -> f32) -> VolumeGrid {
+        let amps = (0..27)
+            .map(|i| f([(i % 3) as f32, (i / 3 % 3) as f32, (i / 9) as f32]))
+            .collect();
+        VolumeGrid {
+            min: [0.0; 3],
+            max: [2.0; 3],
+            dims: [3; 3],
+            amps,
+        }
+    }
+
+    fn normal([a, b, c]: [Vector3; 3]) -> Vector3 {
+        vecmath::vec3_cross(vecmath::vec3_sub(b, a), vecmath::vec3_sub(c, a))
+    }
+
+    #[test]
+    fn plane_of_a_linear_field() {
+        let mesh = extract(&grid(|p| p[0]), 0.5);
+        assert!(!mesh.triangles.is_empty());
+        assert!(mesh
+            .triangles
+            .iter()
+            .flatten()
+            .all(|p| (p[0] - 0.5).abs() < 1e-6));
+        // the whole cross section of the box, facing toward the lower amplitude
+        let area: f32 = mesh
+            .triangles
+            .iter()
+            .map(|&t| vecmath::vec3_len(normal(t)) / 2.0)
+            .sum();
+        assert!((area - 4.0).abs() < 1e-4);
+        assert!(mesh.triangles.iter().all(|&t| normal(t)[0] < 0.0));
+    }
+
+    #[test]
+    fn closed_surface_around_a_peak() {
+        let mesh = extract(&grid(|p| if p == [1.0; 3] { 1.0 } else { 0.0 }), 0.5);
+        // the vertices are halfway to the neighbors of the peak, and every edge is shared by two
+        // triangles in the opposite directions
+        assert!(mesh
+            .triangles
+            .iter()
+            .flatten()
+            .all(|p| p.iter().all(|x| (0.5..=1.5).contains(x))));
+        let key = |p: Vector3| p.map(|x| (x * 1e3).round() as i32);
+        let mut edges = std::collections::HashMap::new();
+        for t in &mesh.triangles {
+            for k in 0..3 {
+                *edges.entry((key(t[k]), key(t[(k + 1) % 3]))).or_insert(0) += 1;
+            }
+        }
+        assert!(edges
+            .iter()
+            .all(|(&(a, b), &n)| n == 1 && edges.get(&(b, a)) == Some(&1)));
+        // the normals face away from the peak
+        let center = [1.0; 3];
+        assert!(mesh.triangles.iter().all(|&t| {
+            let c = vecmath::vec3_scale(
+                vecmath::vec3_add(vecmath::vec3_add(t[0], t[1]), t[2]),
+                1.0 / 3.0,
+            );
+            vecmath::vec3_dot(normal(t), vecmath::vec3_sub(c, center)) > 0.0
+        }));
+    }
+
+    #[test]
+    fn no_surface_out_of_the_range() {
+        assert!(extract(&grid(|p| p[0]), 3.0).triangles.is_empty());
+        assert!(extract(&grid(|p| p[0]), -1.0).triangles.is_empty());
+        let flat = VolumeGrid {
+            dims: [3, 3, 1],
+            amps: vec![0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0],
+            ..grid(|_| 0.0)
+        };
+        assert!(extract(&flat, 0.5).triangles.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * File: levitation.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{field::FieldModel, view::ViewerSettings, Vector3};
+
+/// Gravitational acceleration in mm/s^2, along -z
+pub const GRAVITY: Vector3 = [0.0, 0.0, -9.80665e3];
+/// Viscosity of air in Pa s, for the Stokes drag
+pub const AIR_VISCOSITY: f32 = 1.81e-5;
+
+/// Small sphere levitated in the field, much smaller than the wavelength
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    /// Radius in mm
+    pub radius: f32,
+    /// Density in kg/m^3
+    pub density: f32,
+    /// Sound speed in mm/s
+    pub sound_speed: f32,
+}
+
+impl Particle {
+    /// Expanded polystyrene bead of 1 mm radius
+    pub const fn polystyrene() -> Self {
+        Self {
+            radius: 1.0,
+            density: 29.0,
+            sound_speed: 900.0e3,
+        }
+    }
+
+    /// Volume in m^3
+    pub fn volume(&self) -> f32 {
+        let r = self.radius / 1000.0;
+        4.0 / 3.0 * PI * r * r * r
+    }
+
+    /// Mass in kg
+    pub fn mass(&self) -> f32 {
+        self.density * self.volume()
+    }
+}
+
+impl Default for Particle {
+    fn default() -> Self {
+        Self::polystyrene()
+    }
+}
+
+/// Coefficients of the Gor'kov potential U = k1 |p|^2 - k2 |grad p|^2 in J, for the complex
+/// pressure p in Pa and its gradient in Pa/mm
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GorkovCoefficients {
+    pub k1: f32,
+    pub k2: f32,
+}
+
+impl GorkovCoefficients {
+    /// Coefficients for [ViewerSettings::particle] in [ViewerSettings::medium] at
+    /// [ViewerSettings::frequency]
+    pub fn new(settings: &ViewerSettings) -> Self {
+        Self::for_particle(settings, &settings.particle)
+    }
+
+    pub fn for_particle(settings: &ViewerSettings, particle: &Particle) -> Self {
+        let medium = &settings.medium;
+        let (rho, c) = (medium.density, medium.sound_speed / 1000.0);
+        let (rho_p, c_p) = (particle.density, particle.sound_speed / 1000.0);
+        let f1 = 1.0 - (rho * c * c) / (rho_p * c_p * c_p);
+        let f2 = 2.0 * (rho_p - rho) / (2.0 * rho_p + rho);
+        let omega = 2.0 * PI * settings.frequency;
+        let v = particle.volume();
+        Self {
+            k1: v * f1 / (4.0 * rho * c * c),
+            k2: 3.0 * v * f2 / (8.0 * rho * omega * omega) * 1.0e6,
+        }
+    }
+
+    pub fn potential(&self, p: [f32; 2], grad: [[f32; 2]; 3]) -> f32 {
+        let [re, im] = p;
+        let grad2: f32 = grad.iter().map(|[re, im]| re * re + im * im).sum();
+        self.k1 * (re * re + im * im) - self.k2 * grad2
+    }
+
+    /// Magnitude of the potential, i.e., the pressure term, for the amplitude `amp` in Pa, to
+    /// which the potential is normalized on the color map
+    pub fn reference(&self, amp: f32) -> f32 {
+        self.k1.abs() * amp * amp
+    }
+}
+
+/// Particle moving under the acoustic radiation force, gravity and the Stokes drag
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevitatedParticle {
+    pub particle: Particle,
+    /// Position in mm
+    pub pos: Vector3,
+    /// Velocity in mm/s
+    pub vel: Vector3,
+}
+
+impl LevitatedParticle {
+    pub fn new(particle: Particle, pos: Vector3) -> Self {
+        Self {
+            particle,
+            pos,
+            vel: [0.0; 3],
+        }
+    }
+
+    /// Acceleration in mm/s^2, where the pressure of `model` is scaled by `pressure_scale` to Pa
+    pub fn acceleration(&self, model: &FieldModel, pressure_scale: f32) -> Vector3 {
+        let force = model.gorkov_force_on(self.pos, &self.particle);
+        let drag = 6.0 * PI * AIR_VISCOSITY * self.particle.radius / 1000.0;
+        let mass = self.particle.mass();
+        let scale = pressure_scale * pressure_scale;
+        [0, 1, 2].map(|i| {
+            // N/kg is converted to mm/s^2, so the drag is for the velocity in mm/s as it is
+            GRAVITY[i] + (force[i] * scale * 1000.0 - drag * self.vel[i]) / mass
+        })
+    }
+
+    /// Advances by `dt` seconds with the semi-implicit Euler method
+    pub fn step(&mut self, model: &FieldModel, pressure_scale: f32, dt: f32) {
+        let acc = self.acceleration(model, pressure_scale);
+        self.vel = vecmath::vec3_add(self.vel, vecmath::vec3_scale(acc, dt));
+        self.pos = vecmath::vec3_add(self.pos, vecmath::vec3_scale(self.vel, dt));
+    }
+}
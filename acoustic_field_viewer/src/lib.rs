@@ -3,10 +3,29 @@ extern crate gfx;
 #[macro_use]
 extern crate bitflags;
 
+pub mod calibration;
 pub mod camera_helper;
+pub mod color_map;
 mod common;
+pub mod directivity;
+pub mod far_field;
+pub mod field;
+pub mod isosurface;
+pub mod levitation;
+pub mod lobes;
+pub mod medium;
+pub mod mesh;
+pub mod metadata;
+pub mod obstacle;
+pub mod power;
+pub mod reflector;
+pub mod region;
+pub mod scatterer;
 pub mod sound_source;
+pub mod text;
 pub mod view;
+pub mod view_controller;
+pub mod volume;
 
 pub type Vector3 = vecmath::Vector3<f32>;
 pub type Vector4 = vecmath::Vector4<f32>;
@@ -0,0 +1,130 @@
+/*
+ * File: lobes.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use crate::{field::FieldModel, Matrix4, Vector3};
+
+/// Local maximum of the amplitude on a slice
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lobe {
+    pub pos: Vector3,
+    pub amp: f32,
+    /// Level relative to the main lobe in dB
+    pub level: f32,
+    /// Distance from the main lobe in mm
+    pub distance: f32,
+}
+
+/// Main lobe and the secondary maxima, i.e., the sidelobes and the grating lobes, in descending
+/// order of the level
+#[derive(Debug, Clone, PartialEq)]
+pub struct LobeReport {
+    pub main: Lobe,
+    pub secondary: Vec<Lobe>,
+}
+
+/// Area of the slice searched for the lobes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LobeSearch {
+    /// Width and height in mm of the area around the slice center
+    pub area: [f32; 2],
+    /// Secondary maxima below this level in dB are ignored
+    pub floor: f32,
+    /// At most this number of secondary maxima are reported
+    pub max_lobes: usize,
+}
+
+impl LobeSearch {
+    /// Finds the local maxima on a grid of a quarter wavelength within the area on the slice
+    /// transformed by `model`, refined around each maximum
+    pub fn search(&self, field: &FieldModel, model: Matrix4) -> Option<LobeReport> {
+        let h = field.settings.wave_length / 4.0;
+        let [w, hh] = self.area;
+        let nx = (w / h).floor() as usize + 1;
+        let ny = (hh / h).floor() as usize + 1;
+        if nx < 3 || ny < 3 {
+            return None;
+        }
+        let (u, v) = (
+            vecmath::vec3_normalized(vecmath_util::to_vec3(&model[0])),
+            vecmath::vec3_normalized(vecmath_util::to_vec3(&model[1])),
+        );
+        let at = |x: f32, y: f32| {
+            let p = vecmath::col_mat4_transform(model, [x, y, 0.0, 1.0]);
+            [p[0], p[1], p[2]]
+        };
+        let amp = |p| {
+            let [re, im] = field.pressure_at(p);
+            (re * re + im * im).sqrt()
+        };
+        let coord = |ix: usize, iy: usize| (-w / 2.0 + ix as f32 * h, -hh / 2.0 + iy as f32 * h);
+        let grid: Vec<f32> = (0..ny)
+            .flat_map(|iy| (0..nx).map(move |ix| (ix, iy)))
+            .map(|(ix, iy)| {
+                let (x, y) = coord(ix, iy);
+                amp(at(x, y))
+            })
+            .collect();
+
+        let refine = |p: Vector3, a: f32| {
+            const REFINE: i32 = 4;
+            let s = h / (2 * REFINE) as f32;
+            (-REFINE..=REFINE)
+                .flat_map(|i| (-REFINE..=REFINE).map(move |j| (i, j)))
+                .map(|(i, j)| {
+                    let q = vecmath::vec3_add(p, vecmath::vec3_scale(u, i as f32 * s));
+                    vecmath::vec3_add(q, vecmath::vec3_scale(v, j as f32 * s))
+                })
+                .map(|q| (q, amp(q)))
+                .fold((p, a), |m, c| if c.1 > m.1 { c } else { m })
+        };
+
+        // the maxima among the 8 neighbors, where the ties are broken by the index
+        let mut maxima: Vec<(Vector3, f32)> = (1..ny - 1)
+            .flat_map(|iy| (1..nx - 1).map(move |ix| (ix, iy)))
+            .filter(|&(ix, iy)| {
+                let i = iy * nx + ix;
+                let a = grid[i];
+                a > 0.0
+                    && (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                        .filter(|&d| d != (0, 0))
+                        .all(|(dx, dy)| {
+                            let j = (iy as i64 + dy) as usize * nx + (ix as i64 + dx) as usize;
+                            grid[j] < a || (grid[j] == a && j > i)
+                        })
+            })
+            .map(|(ix, iy)| {
+                let (x, y) = coord(ix, iy);
+                refine(at(x, y), grid[iy * nx + ix])
+            })
+            .collect();
+        maxima.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let (main_pos, main_amp) = *maxima.first()?;
+        let lobe = |(pos, a): (Vector3, f32)| Lobe {
+            pos,
+            amp: a,
+            level: 20.0 * (a / main_amp).log10(),
+            distance: vecmath_util::dist(pos, main_pos),
+        };
+        Some(LobeReport {
+            main: lobe((main_pos, main_amp)),
+            secondary: maxima
+                .into_iter()
+                .skip(1)
+                .map(lobe)
+                .filter(|l| l.level >= self.floor)
+                .take(self.max_lobes)
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * File: medium.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// Medium in which the sound propagates
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Medium {
+    /// Sound speed in mm/s
+    pub sound_speed: f32,
+    /// Density in kg/m^3
+    pub density: f32,
+    /// Amplitude attenuation coefficient in Np/mm, the pressure decays as exp(-attenuation * r)
+    pub attenuation: f32,
+    /// Coefficient of nonlinearity, 1 + B/2A
+    #[serde(default = "default_nonlinearity")]
+    pub nonlinearity: f32,
+}
+
+fn default_nonlinearity() -> f32 {
+    Medium::air().nonlinearity
+}
+
+impl Medium {
+    /// Air at 15 degrees Celsius, with the absorption around 40 kHz
+    pub const fn air() -> Self {
+        Self {
+            sound_speed: 340.0e3,
+            density: 1.225,
+            attenuation: 1.5e-4,
+            nonlinearity: 1.2,
+        }
+    }
+
+    /// Fresh water at 20 degrees Celsius
+    pub const fn water() -> Self {
+        Self {
+            sound_speed: 1481.0e3,
+            density: 998.2,
+            attenuation: 4.0e-8,
+            nonlinearity: 3.5,
+        }
+    }
+
+    /// Amplitude attenuation coefficient in Np/mm of air at `frequency` in Hz, `temperature` in
+    /// Celsius and the relative `humidity` in %, under the standard atmospheric pressure, by the
+    /// absorption of ISO 9613-1 with the relaxation of oxygen and nitrogen
+    pub fn air_absorption(frequency: f32, temperature: f32, humidity: f32) -> f32 {
+        const T0: f64 = 293.15;
+        const T01: f64 = 273.16;
+
+        let (f, t) = (frequency as f64, temperature as f64 + 273.15);
+        let tr = t / T0;
+        // the molar concentration of water vapour in %, for the pressure ratio of 1
+        let c = -6.8346 * (T01 / t).powf(1.261) + 4.6151;
+        let h = humidity.max(0.0) as f64 * 10f64.powf(c);
+        let fr_o = 24.0 + 4.04e4 * h * (0.02 + h) / (0.391 + h);
+        let fr_n = tr.powf(-0.5) * (9.0 + 280.0 * h * (-4.170 * (tr.powf(-1.0 / 3.0) - 1.0)).exp());
+        // in Np/m, which is the coefficient in dB/m of ISO 9613-1 divided by 20 log10(e)
+        let alpha = f
+            * f
+            * (1.84e-11 * tr.sqrt()
+                + tr.powf(-2.5)
+                    * (0.01275 * (-2239.1 / t).exp() / (fr_o + f * f / fr_o)
+                        + 0.1068 * (-3352.0 / t).exp() / (fr_n + f * f / fr_n)));
+        (alpha / 1000.0) as f32
+    }
+
+    /// Characteristic acoustic impedance in Pa s/m
+    pub fn impedance(&self) -> f32 {
+        self.density * self.sound_speed / 1000.0
+    }
+
+    /// Growth in 1/mm of the shock parameter of a plane wave of the pressure amplitude `amp` in Pa
+    /// and `frequency` in Hz, beta k p / (rho c^2), which forms a shock at 1
+    pub fn shock_parameter_rate(&self, amp: f32, frequency: f32) -> f32 {
+        let c = self.sound_speed / 1000.0;
+        let k = 2.0 * std::f32::consts::PI * frequency / c;
+        self.nonlinearity * k * amp / (self.density * c * c) / 1000.0
+    }
+
+    /// Time-averaged radiation pressure in Pa on a perfectly reflecting surface at normal
+    /// incidence, for the pressure amplitude `amp` in Pa
+    pub fn radiation_pressure(&self, amp: f32) -> f32 {
+        let c = self.sound_speed / 1000.0;
+        amp * amp / (self.density * c * c)
+    }
+}
+
+impl Default for Medium {
+    fn default() -> Self {
+        Self::air()
+    }
+}
+
+pub const MEDIUM_PRESETS: [(&str, Medium); 2] =
+    [("Air", Medium::air()), ("Water", Medium::water())];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Absorption in dB/km, as the tables of ISO 9613
+    fn db_per_km(frequency: f32, temperature: f32, humidity: f32) -> f32 {
+        let np_per_mm = Medium::air_absorption(frequency, temperature, humidity);
+        np_per_mm * 1e6 * 20.0 * std::f32::consts::LOG10_E
+    }
+
+    #[test]
+    fn air_absorption_of_the_iso_9613_tables() {
+        // the reference values of ISO 9613-2, Table 2
+        for &(frequency, temperature, humidity, expected) in &[
+            (1000.0, 10.0, 70.0, 3.66),
+            (4000.0, 10.0, 70.0, 32.8),
+            (8000.0, 10.0, 70.0, 117.0),
+            (1000.0, 20.0, 70.0, 4.98),
+            (4000.0, 20.0, 70.0, 22.9),
+            (8000.0, 20.0, 70.0, 76.6),
+        ] {
+            let alpha = db_per_km(frequency, temperature, humidity);
+            assert!(
+                (alpha / expected - 1.0).abs() < 0.02,
+                "{} Hz, {} C, {} %RH: {} dB/km",
+                frequency,
+                temperature,
+                humidity,
+                alpha
+            );
+        }
+    }
+
+    #[test]
+    fn air_absorption_of_ultrasound() {
+        assert!((db_per_km(40e3, 20.0, 50.0) / 1000.0 - 1.3).abs() < 0.05);
+        assert!((db_per_km(40e3, 20.0, 10.0) / 1000.0 - 0.46).abs() < 0.05);
+        // the negative humidity is of the dry air
+        assert_eq!(
+            Medium::air_absorption(40e3, 20.0, -10.0),
+            Medium::air_absorption(40e3, 20.0, 0.0)
+        );
+    }
+}
@@ -0,0 +1,251 @@
+/*
+ * File: mesh.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::Vector3;
+
+// Not parallel to the edges and the faces of the usual CAD models, so that the ray hardly passes
+// through an edge or a vertex
+const RAY_DIR: Vector3 = [0.577_215_7, 0.412_310_6, 0.704_846_9];
+const EPS: f32 = 1e-6;
+
+/// Triangle mesh of an object in the workspace, e.g., a reflector or a mounting frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    pub triangles: Vec<[Vector3; 3]>,
+}
+
+impl Mesh {
+    /// Loads an STL, either ASCII or binary, or a Wavefront OBJ file by the extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mesh = match ext.as_deref() {
+            Some("stl") => Self::parse_stl(&bytes),
+            Some("obj") => Self::parse_obj(&String::from_utf8_lossy(&bytes)),
+            _ => Err("unknown mesh format, which must be .stl or .obj".to_owned()),
+        };
+        mesh.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse_stl(bytes: &[u8]) -> Result<Self, String> {
+        // some binary files also begin with "solid" in the header
+        if bytes.len() >= 84 {
+            let n = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+            if bytes.len() == 84 + n * 50 {
+                return Ok(Self::parse_binary_stl(&bytes[84..], n));
+            }
+        }
+        if bytes.starts_with(b"solid") {
+            return Self::parse_ascii_stl(&String::from_utf8_lossy(bytes));
+        }
+        Err("truncated binary STL".to_owned())
+    }
+
+    fn parse_binary_stl(body: &[u8], n: usize) -> Self {
+        let f = |b: &[u8]| f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        let triangles = body
+            .chunks_exact(50)
+            .take(n)
+            .map(|c| {
+                // the facet normal in the first 12 bytes is not used
+                let v = |i: usize| {
+                    let o = 12 + i * 12;
+                    [f(&c[o..]), f(&c[o + 4..]), f(&c[o + 8..])]
+                };
+                [v(0), v(1), v(2)]
+            })
+            .collect();
+        Self { triangles }
+    }
+
+    fn parse_ascii_stl(text: &str) -> Result<Self, String> {
+        let mut triangles = Vec::new();
+        let mut vertices = Vec::with_capacity(3);
+        for (i, line) in text.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("vertex") => vertices.push(parse_vec3(tokens, i)?),
+                Some("endloop") => {
+                    if vertices.len() != 3 {
+                        return Err(format!(
+                            "line {}: facet of {} vertices",
+                            i + 1,
+                            vertices.len()
+                        ));
+                    }
+                    triangles.push([vertices[0], vertices[1], vertices[2]]);
+                    vertices.clear();
+                }
+                _ => {}
+            }
+        }
+        Ok(Self { triangles })
+    }
+
+    /// Parses the vertices and the faces, where the polygons are triangulated as fans
+    pub fn parse_obj(text: &str) -> Result<Self, String> {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => vertices.push(parse_vec3(tokens, i)?),
+                Some("f") => {
+                    // "f v/vt/vn ...", where the negative indices are relative to the end
+                    let face = tokens
+                        .map(|t| {
+                            let idx: i64 = t
+                                .split('/')
+                                .next()
+                                .and_then(|s| s.parse().ok())
+                                .ok_or_else(|| format!("line {}: invalid face", i + 1))?;
+                            let idx = if idx < 0 {
+                                vertices.len() as i64 + idx
+                            } else {
+                                idx - 1
+                            };
+                            vertices
+                                .get(idx as usize)
+                                .copied()
+                                .ok_or_else(|| format!("line {}: vertex out of range", i + 1))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    for k in 1..face.len().saturating_sub(1) {
+                        triangles.push([face[0], face[k], face[k + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self { triangles })
+    }
+
+    /// Scaled by `scale` and then translated by `offset`, e.g., from m to mm in the workspace
+    pub fn transformed(&self, offset: Vector3, scale: f32) -> Self {
+        let t = |p: Vector3| vecmath::vec3_add(vecmath::vec3_scale(p, scale), offset);
+        Self {
+            triangles: self
+                .triangles
+                .iter()
+                .map(|[a, b, c]| [t(*a), t(*b), t(*c)])
+                .collect(),
+        }
+    }
+
+    /// Writes a binary STL, whose facet normals follow the counterclockwise winding
+    pub fn write_stl(&self, path: &Path) -> Result<(), String> {
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let res: std::io::Result<()> = (|| {
+            writer.write_all(&[0; 80])?;
+            writer.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+            for [a, b, c] in &self.triangles {
+                let normal =
+                    vecmath::vec3_cross(vecmath::vec3_sub(*b, *a), vecmath::vec3_sub(*c, *a));
+                let len = vecmath::vec3_len(normal);
+                let normal = if len > 0.0 {
+                    vecmath::vec3_scale(normal, 1.0 / len)
+                } else {
+                    normal
+                };
+                for v in [normal, *a, *b, *c].iter().flatten() {
+                    writer.write_all(&v.to_le_bytes())?;
+                }
+                writer.write_all(&[0; 2])?;
+            }
+            writer.flush()
+        })();
+        res.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Minimum and maximum corners of the bounding box
+    pub fn bounds(&self) -> Option<(Vector3, Vector3)> {
+        let mut vertices = self.triangles.iter().flatten();
+        let first = *vertices.next()?;
+        Some(vertices.fold((first, first), |(min, max), v| {
+            (
+                [min[0].min(v[0]), min[1].min(v[1]), min[2].min(v[2])],
+                [max[0].max(v[0]), max[1].max(v[1]), max[2].max(v[2])],
+            )
+        }))
+    }
+
+    /// Distance along the unit `dir` from `origin` to the nearest triangle hit in front
+    pub fn ray_intersect(&self, origin: Vector3, dir: Vector3) -> Option<f32> {
+        self.triangles
+            .iter()
+            .filter_map(|t| intersect(t, origin, dir))
+            .fold(None, |m: Option<f32>, t| Some(m.map_or(t, |m| m.min(t))))
+    }
+
+    /// Whether `p` is inside the mesh by the parity of the crossings of a ray, which assumes the
+    /// mesh is closed
+    pub fn contains(&self, p: Vector3) -> bool {
+        match self.bounds() {
+            Some((min, max)) if (0..3).all(|i| min[i] <= p[i] && p[i] <= max[i]) => {}
+            _ => return false,
+        }
+        let crossings = self
+            .triangles
+            .iter()
+            .filter(|t| intersect(t, p, RAY_DIR).is_some())
+            .count();
+        crossings % 2 == 1
+    }
+}
+
+fn parse_vec3<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    line: usize,
+) -> Result<Vector3, String> {
+    let mut v = [0.0; 3];
+    for c in v.iter_mut() {
+        *c = tokens
+            .next()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| format!("line {}: invalid vertex", line + 1))?;
+    }
+    Ok(v)
+}
+
+// Moller-Trumbore, hitting only in front of the origin
+fn intersect([a, b, c]: &[Vector3; 3], origin: Vector3, dir: Vector3) -> Option<f32> {
+    let e1 = vecmath::vec3_sub(*b, *a);
+    let e2 = vecmath::vec3_sub(*c, *a);
+    let p = vecmath::vec3_cross(dir, e2);
+    let det = vecmath::vec3_dot(e1, p);
+    if det.abs() < EPS {
+        return None;
+    }
+    let s = vecmath::vec3_sub(origin, *a);
+    let u = vecmath::vec3_dot(s, p) / det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = vecmath::vec3_cross(s, e1);
+    let v = vecmath::vec3_dot(dir, q) / det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = vecmath::vec3_dot(e2, q) / det;
+    (t > EPS).then_some(t)
+}
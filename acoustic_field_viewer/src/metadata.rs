@@ -0,0 +1,40 @@
+/*
+ * File: metadata.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// Information on the experiment embedded into every exported file
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ExperimentMetadata {
+    pub experiment_id: String,
+    pub operator: String,
+    pub notes: String,
+    /// Tags of the current gain sent by the controlling program, joined by commas
+    #[serde(default)]
+    pub gain_tags: String,
+}
+
+impl ExperimentMetadata {
+    /// Key-value pairs of the non-empty fields
+    pub fn entries(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("Experiment ID", self.experiment_id.as_str()),
+            ("Operator", self.operator.as_str()),
+            ("Notes", self.notes.as_str()),
+            ("Gain tags", self.gain_tags.as_str()),
+        ]
+        .into_iter()
+        .filter(|(_, v)| !v.is_empty())
+        .collect()
+    }
+}
@@ -0,0 +1,202 @@
+/*
+ * File: obstacle.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use autd3_core::hardware_defined::TRANS_SPACING_MM;
+use serde::{Deserialize, Serialize};
+
+use crate::{Matrix4, Vector3};
+
+/// Number of the obstacles, which is fixed for the uniforms of the slice shader
+pub const MAX_OBSTACLES: usize = 4;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleShape {
+    /// Ellipsoid of the radii [Obstacle::size], a sphere if they are the same
+    #[default]
+    Sphere,
+    /// Box of the half extents [Obstacle::size]
+    Box,
+    /// Rectangle in the xy plane of the obstacle of the half extents of the x and y of
+    /// [Obstacle::size]
+    Plane,
+}
+
+impl ObstacleShape {
+    pub const ALL: [ObstacleShape; 3] = [
+        ObstacleShape::Sphere,
+        ObstacleShape::Box,
+        ObstacleShape::Plane,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObstacleShape::Sphere => "Sphere",
+            ObstacleShape::Box => "Box",
+            ObstacleShape::Plane => "Plane",
+        }
+    }
+}
+
+/// Primitive shape casting the shadow, i.e., the contribution of a source to a point is zero if
+/// the straight path between them crosses the shape. Neither the reflection nor the diffraction
+/// by it is modeled, see [crate::scatterer::Scattering] for those.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Obstacle {
+    pub enabled: bool,
+    pub shape: ObstacleShape,
+    /// Center in mm
+    pub center: Vector3,
+    /// Radii or half extents in mm along the axes of the obstacle, see [ObstacleShape]
+    pub size: Vector3,
+    /// Euler angles rotating the axes of the obstacle
+    pub angle: Vector3,
+}
+
+impl Obstacle {
+    /// Sphere of a radius of 20 mm at 150 mm above the default array, about a fingertip
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            shape: ObstacleShape::Sphere,
+            center: [
+                TRANS_SPACING_MM as f32 * 8.5,
+                TRANS_SPACING_MM as f32 * 6.5,
+                150.0,
+            ],
+            size: [20.0, 20.0, 20.0],
+            angle: [0.0, 0.0, 0.0],
+        }
+    }
+
+    // The quaternion is normalized, which quaternion::euler_angles is not about multiple axes
+    fn rotation(&self) -> Matrix4 {
+        let [rx, ry, rz] = self.angle;
+        let (w, v) = quaternion::euler_angles(rx, ry, rz);
+        let norm = (w * w + vecmath::vec3_dot(v, v)).sqrt();
+        vecmath_util::mat4_rot((w / norm, vecmath::vec3_scale(v, 1.0 / norm)))
+    }
+
+    /// Maps the workspace onto the coordinates of the obstacle scaled by [Obstacle::size], in
+    /// which the shape is the unit sphere, the cube from -1 to 1 or the square from -1 to 1 in
+    /// the xy plane
+    pub fn to_local(&self) -> Matrix4 {
+        let rot = self.rotation();
+        let size = self.size.map(|s| s.max(1e-6));
+        let mut m = vecmath_util::mat4_scale(1.0);
+        for i in 0..3 {
+            for j in 0..3 {
+                m[j][i] = rot[i][j] / size[i];
+            }
+            m[3][i] = -(0..3).map(|j| m[j][i] * self.center[j]).sum::<f32>();
+        }
+        m
+    }
+
+    /// Point in the workspace of `local` in the coordinates of [Obstacle::to_local]
+    pub fn to_world(&self, local: Vector3) -> Vector3 {
+        let scaled = [0, 1, 2].map(|i| local[i] * self.size[i]);
+        let p =
+            vecmath::col_mat4_transform(self.rotation(), [scaled[0], scaled[1], scaled[2], 0.0]);
+        vecmath::vec3_add(self.center, vecmath_util::to_vec3(&p))
+    }
+
+    /// Whether the segment from `a` to `b` crosses the shape or is inside it, the same as the
+    /// slice shader
+    pub fn blocks(&self, a: Vector3, b: Vector3) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let m = self.to_local();
+        let local = |p: Vector3| {
+            vecmath_util::to_vec3(&vecmath::col_mat4_transform(m, [p[0], p[1], p[2], 1.0]))
+        };
+        let (la, lb) = (local(a), local(b));
+        let d = vecmath::vec3_sub(lb, la);
+        match self.shape {
+            ObstacleShape::Sphere => {
+                let dd = vecmath::vec3_dot(d, d).max(1e-12);
+                let t = (-vecmath::vec3_dot(la, d) / dd).clamp(0.0, 1.0);
+                let q = vecmath::vec3_add(la, vecmath::vec3_scale(d, t));
+                vecmath::vec3_dot(q, q) < 1.0
+            }
+            ObstacleShape::Box => {
+                let (mut near, mut far) = (0.0f32, 1.0f32);
+                for i in 0..3 {
+                    let (t0, t1) = ((-1.0 - la[i]) / d[i], (1.0 - la[i]) / d[i]);
+                    near = near.max(t0.min(t1));
+                    far = far.min(t0.max(t1));
+                }
+                near <= far
+            }
+            ObstacleShape::Plane => {
+                if la[2] * lb[2] >= 0.0 {
+                    return false;
+                }
+                let t = la[2] / (la[2] - lb[2]);
+                (0..2).all(|i| (la[i] + t * d[i]).abs() <= 1.0)
+            }
+        }
+    }
+
+    /// Segments of the wireframe of the shape, three great circles of the sphere
+    pub fn edges(&self) -> Vec<(Vector3, Vector3)> {
+        const SEGMENTS: usize = 32;
+
+        let local: Vec<(Vector3, Vector3)> = match self.shape {
+            ObstacleShape::Sphere => (0..3)
+                .flat_map(|axis| {
+                    (0..SEGMENTS).map(move |i| {
+                        let point = |i: usize| {
+                            let t = 2.0 * PI * i as f32 / SEGMENTS as f32;
+                            let mut p = [0.0; 3];
+                            p[(axis + 1) % 3] = t.cos();
+                            p[(axis + 2) % 3] = t.sin();
+                            p
+                        };
+                        (point(i), point(i + 1))
+                    })
+                })
+                .collect(),
+            ObstacleShape::Box => {
+                let corner =
+                    |i: usize| [0, 1, 2].map(|k| if (i >> k) & 1 == 1 { 1.0 } else { -1.0 });
+                (0..8)
+                    .flat_map(|i| (0..3).map(move |k| (i, i | (1 << k))))
+                    .filter(|&(i, j)| i != j)
+                    .map(|(i, j)| (corner(i), corner(j)))
+                    .collect()
+            }
+            ObstacleShape::Plane => {
+                let corners = [
+                    [-1.0, -1.0, 0.0],
+                    [1.0, -1.0, 0.0],
+                    [1.0, 1.0, 0.0],
+                    [-1.0, 1.0, 0.0],
+                ];
+                (0..4).map(|i| (corners[i], corners[(i + 1) % 4])).collect()
+            }
+        };
+        local
+            .into_iter()
+            .map(|(a, b)| (self.to_world(a), self.to_world(b)))
+            .collect()
+    }
+}
+
+impl Default for Obstacle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
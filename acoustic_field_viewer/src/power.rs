@@ -0,0 +1,113 @@
+/*
+ * File: power.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use rayon::prelude::*;
+
+use crate::{
+    far_field::{array_axes, array_factor},
+    field::FieldModel,
+    Matrix4, Vector3,
+};
+
+/// Acoustic power and the efficiency metrics of the drive, for comparing the gains
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerSummary {
+    /// Power in W radiated into the hemisphere in front of the array, integrated over the far
+    /// field without the reflector, the obstacles and the scatterers
+    pub power: f32,
+    /// Mean amplitude of the drive from 0 to 1
+    pub drive: f32,
+    /// Maximum of the amplitude on the slice and its position
+    pub peak_pos: Vector3,
+    pub peak_amp: f32,
+    /// Peak over the amplitude of one transducer at the full drive at the same distance on its
+    /// axis from the center of the array
+    pub focal_gain: f32,
+    /// Peak over the amplitude of the same drive in phase at the peak, which is 1 for a single
+    /// focus there
+    pub coherence: f32,
+    /// Peak over the amplitude of all the transducers at the full drive in phase at the peak
+    pub efficiency: f32,
+}
+
+impl PowerSummary {
+    const POLAR_STEPS: usize = 64;
+    const AZIMUTH_STEPS: usize = 128;
+
+    /// None if there is no source, with the slice transformed by `model` and `pa_per_unit` to
+    /// convert the field to Pa
+    pub fn compute(field: &FieldModel, model: Matrix4, pa_per_unit: f32) -> Option<Self> {
+        let sources = field.sources;
+        if sources.is_empty() {
+            return None;
+        }
+        let [u, v, normal] = array_axes(field);
+        let (dt, dp) = (
+            PI / 2.0 / Self::POLAR_STEPS as f32,
+            2.0 * PI / Self::AZIMUTH_STEPS as f32,
+        );
+        let integral: f32 = (0..Self::POLAR_STEPS * Self::AZIMUTH_STEPS)
+            .into_par_iter()
+            .map(|k| {
+                let theta = (k / Self::AZIMUTH_STEPS) as f32 * dt + dt / 2.0;
+                let phi = (k % Self::AZIMUTH_STEPS) as f32 * dp;
+                let (s, c) = theta.sin_cos();
+                let dir =
+                    [0, 1, 2].map(|i| s * phi.cos() * u[i] + s * phi.sin() * v[i] + c * normal[i]);
+                let [re, im] = array_factor(field, dir);
+                (re * re + im * im) * s * dt * dp
+            })
+            .sum();
+        // the intensity p^2 / (2 rho c) of the pressure amplitude in Pa at 1 m times 1 m^2
+        let medium = &field.settings.medium;
+        let c = medium.sound_speed / 1000.0;
+        let power = integral * (pa_per_unit / 1000.0).powi(2) / (2.0 * medium.density * c);
+
+        let (peak_pos, peak_amp) = field.peak_on_slice(model);
+        let in_phase = |full: bool| {
+            let mut sources = sources.to_vec();
+            for s in sources.iter_mut() {
+                let k = s.wavenumber(field.settings);
+                s.phase = (k * vecmath_util::dist(s.pos, peak_pos)).rem_euclid(2.0 * PI);
+                if full {
+                    s.amp = 1.0;
+                }
+            }
+            let [re, im] = FieldModel {
+                sources: &sources,
+                ..*field
+            }
+            .pressure_at(peak_pos);
+            (re * re + im * im).sqrt().max(f32::MIN_POSITIVE)
+        };
+        let center = vecmath::vec3_scale(
+            sources
+                .iter()
+                .fold([0.0; 3], |acc, s| vecmath::vec3_add(acc, s.pos)),
+            1.0 / sources.len() as f32,
+        );
+        let distance = vecmath_util::dist(center, peak_pos);
+        let single = (-medium.attenuation * distance).exp() / distance.max(f32::MIN_POSITIVE);
+        Some(Self {
+            power,
+            drive: sources.iter().map(|s| s.amp).sum::<f32>() / sources.len() as f32,
+            peak_pos,
+            peak_amp,
+            focal_gain: peak_amp / single,
+            coherence: peak_amp / in_phase(false),
+            efficiency: peak_amp / in_phase(true),
+        })
+    }
+}
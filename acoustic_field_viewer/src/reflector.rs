@@ -0,0 +1,100 @@
+/*
+ * File: reflector.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use autd3_core::hardware_defined::TRANS_SPACING_MM;
+use serde::{Deserialize, Serialize};
+
+use crate::Vector3;
+
+/// Infinite rigid plane modeled with the image sources, i.e., each source is mirrored in the plane
+/// with its amplitude multiplied by the reflection coefficient. The field is only on the front
+/// side, to which the normal points, and zero behind.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Reflector {
+    pub enabled: bool,
+    /// Center in mm
+    pub center: Vector3,
+    /// Euler angles rotating the z axis to the normal
+    pub angle: Vector3,
+    /// Pressure reflection coefficient, 1 for a perfectly rigid plane
+    pub coefficient: f32,
+    /// Width and height in mm of the outline drawn, which does not limit the plane
+    pub size: [f32; 2],
+}
+
+impl Reflector {
+    /// Facing down at 100 mm above the default array, as for a standing-wave levitator
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            center: [
+                TRANS_SPACING_MM as f32 * 8.5,
+                TRANS_SPACING_MM as f32 * 6.5,
+                100.0,
+            ],
+            angle: [PI, 0.0, 0.0],
+            coefficient: 1.0,
+            size: [200.0, 200.0],
+        }
+    }
+
+    fn axis(&self, axis: Vector3) -> Vector3 {
+        let [rx, ry, rz] = self.angle;
+        let rot = vecmath_util::mat4_rot(quaternion::euler_angles(rx, ry, rz));
+        vecmath_util::to_vec3(&vecmath::col_mat4_transform(
+            rot,
+            [axis[0], axis[1], axis[2], 0.0],
+        ))
+    }
+
+    pub fn normal(&self) -> Vector3 {
+        self.axis([0.0, 0.0, 1.0])
+    }
+
+    /// Distance in mm from the plane, negative behind it
+    pub fn distance(&self, pos: Vector3) -> f32 {
+        vecmath::vec3_dot(vecmath::vec3_sub(pos, self.center), self.normal())
+    }
+
+    /// Mirror image of the point `pos`
+    pub fn image(&self, pos: Vector3) -> Vector3 {
+        let d = self.distance(pos);
+        vecmath::vec3_sub(pos, vecmath::vec3_scale(self.normal(), 2.0 * d))
+    }
+
+    /// Mirror image of the direction `dir`
+    pub fn image_dir(&self, dir: Vector3) -> Vector3 {
+        let n = self.normal();
+        vecmath::vec3_sub(dir, vecmath::vec3_scale(n, 2.0 * vecmath::vec3_dot(dir, n)))
+    }
+
+    /// Corners of the outline, counterclockwise seen from the front
+    pub fn corners(&self) -> [Vector3; 4] {
+        let (u, v) = (self.axis([1.0, 0.0, 0.0]), self.axis([0.0, 1.0, 0.0]));
+        let [w, h] = self.size.map(|s| s / 2.0);
+        [(-w, -h), (w, -h), (w, h), (-w, h)].map(|(a, b)| {
+            vecmath::vec3_add(
+                self.center,
+                vecmath::vec3_add(vecmath::vec3_scale(u, a), vecmath::vec3_scale(v, b)),
+            )
+        })
+    }
+}
+
+impl Default for Reflector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * File: region.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use autd3_core::hardware_defined::TRANS_SPACING_MM;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{field::FieldModel, Vector3};
+
+/// Samples of a region beyond which the spacing is widened, see [TargetRegion::sample_points]
+pub const MAX_REGION_SAMPLES: usize = 1 << 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RegionShape {
+    /// Disk of the radius in mm perpendicular to [TargetRegion::normal]
+    Disk { radius: f32 },
+    /// Box of the size in mm along the x, y and z axes
+    Box { size: Vector3 },
+}
+
+/// Region over which the acoustic intensity is integrated, e.g., a palm for mid-air haptics
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TargetRegion {
+    /// Center in mm
+    pub center: Vector3,
+    /// Normal of the disk, through which the power is integrated
+    pub normal: Vector3,
+    pub shape: RegionShape,
+    /// Spacing of the sampled points in mm
+    pub resolution: f32,
+}
+
+/// Field integrated over a [TargetRegion], assuming the local plane wave, i.e., the intensity
+/// p^2 / (2 rho c) and the energy density p^2 / (2 rho c^2) of the pressure amplitude p
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegionIntegral {
+    /// Acoustic power in W through the disk, or acoustic energy in J in the box
+    pub integral: f32,
+    /// Mean intensity in W/m^2
+    pub mean_intensity: f32,
+    /// Maximum pressure amplitude in Pa
+    pub peak_pressure: f32,
+    /// Number of the sampled points
+    pub samples: usize,
+}
+
+impl TargetRegion {
+    /// Disk of 40 mm radius, about the size of a palm, facing the array at 150 mm
+    pub fn new() -> Self {
+        Self {
+            center: [
+                TRANS_SPACING_MM as f32 * 8.5,
+                TRANS_SPACING_MM as f32 * 6.5,
+                150.0,
+            ],
+            normal: [0.0, 0.0, 1.0],
+            shape: RegionShape::Disk { radius: 40.0 },
+            resolution: 2.0,
+        }
+    }
+
+    /// Orthonormal axes of the region, the last of which is the normal for the disk
+    pub fn axes(&self) -> [Vector3; 3] {
+        match self.shape {
+            RegionShape::Disk { .. } => {
+                let n = vecmath::vec3_normalized(self.normal);
+                let a = if n[0].abs() < 0.9 {
+                    [1.0, 0.0, 0.0]
+                } else {
+                    [0.0, 1.0, 0.0]
+                };
+                let u = vecmath::vec3_normalized(vecmath::vec3_cross(a, n));
+                let v = vecmath::vec3_cross(n, u);
+                [u, v, n]
+            }
+            RegionShape::Box { .. } => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+
+    /// Centers of the cells of the region and the size of a cell in mm^2 for the disk and mm^3
+    /// for the box, where the cells are enlarged from [TargetRegion::resolution] so that there
+    /// are at most about [MAX_REGION_SAMPLES] of them
+    pub fn sample_points(&self) -> (Vec<Vector3>, f32) {
+        let h = self.resolution.max(0.1).max(self.min_resolution());
+        let [u, v, w] = self.axes();
+        let at = |x: f32, y: f32, z: f32| {
+            let p = vecmath::vec3_add(self.center, vecmath::vec3_scale(u, x));
+            let p = vecmath::vec3_add(p, vecmath::vec3_scale(v, y));
+            vecmath::vec3_add(p, vecmath::vec3_scale(w, z))
+        };
+        // Cell centers at (i + 0.5) h in [-half, half]
+        let centers = |half: f32| {
+            let n = (2.0 * half / h).round().max(1.0) as i32;
+            (0..n).map(move |i| -n as f32 * h / 2.0 + (i as f32 + 0.5) * h)
+        };
+        match self.shape {
+            RegionShape::Disk { radius } => {
+                let points = centers(radius)
+                    .flat_map(|x| centers(radius).map(move |y| (x, y)))
+                    .filter(|(x, y)| x * x + y * y <= radius * radius)
+                    .map(|(x, y)| at(x, y, 0.0))
+                    .collect();
+                (points, h * h)
+            }
+            RegionShape::Box { size } => {
+                let [sx, sy, sz] = vecmath_util::vec3_map(size, |s| s / 2.0);
+                let points = centers(sx)
+                    .flat_map(|x| centers(sy).map(move |y| (x, y)))
+                    .flat_map(|(x, y)| centers(sz).map(move |z| (x, y, z)))
+                    .map(|(x, y, z)| at(x, y, z))
+                    .collect();
+                (points, h * h * h)
+            }
+        }
+    }
+
+    // Spacing in mm of MAX_REGION_SAMPLES cells over the region
+    fn min_resolution(&self) -> f32 {
+        let n = MAX_REGION_SAMPLES as f32;
+        match self.shape {
+            RegionShape::Disk { radius } => (4.0 * radius * radius / n).sqrt(),
+            RegionShape::Box { size } => (size[0] * size[1] * size[2] / n).abs().cbrt(),
+        }
+    }
+
+    /// Integrates the field of `model` over the region, computed in parallel on the CPU
+    pub fn integrate(&self, model: &FieldModel) -> RegionIntegral {
+        let (points, cell) = self.sample_points();
+        let settings = model.settings;
+        let medium = settings.medium;
+        let c = medium.sound_speed / 1000.0;
+        let (sum, peak) = points
+            .par_iter()
+            .map(|&p| {
+                let [re, im] = model.pressure_at(p);
+                settings.calibration.to_pa((re * re + im * im).sqrt())
+            })
+            .fold(
+                || (0.0, 0.0f32),
+                |(sum, peak), p| (sum + p * p, peak.max(p)),
+            )
+            .reduce(|| (0.0, 0.0), |a, b| (a.0 + b.0, a.1.max(b.1)));
+        let mean_square = if points.is_empty() {
+            0.0
+        } else {
+            sum / points.len() as f32
+        };
+        let integral = match self.shape {
+            RegionShape::Disk { .. } => sum / (2.0 * medium.density * c) * cell * 1e-6,
+            RegionShape::Box { .. } => sum / (2.0 * medium.density * c * c) * cell * 1e-9,
+        };
+        RegionIntegral {
+            integral,
+            mean_intensity: mean_square / (2.0 * medium.density * c),
+            peak_pressure: peak,
+            samples: points.len(),
+        }
+    }
+}
+
+impl Default for TargetRegion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
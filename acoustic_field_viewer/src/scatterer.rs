@@ -0,0 +1,183 @@
+/*
+ * File: scatterer.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use rayon::prelude::*;
+
+use crate::{
+    field::{Complex, FieldModel},
+    mesh::Mesh,
+    Vector3,
+};
+
+/// Largest number of the facets of all the scatterers, which are evaluated per pixel by the slice
+/// shader
+pub const MAX_FACETS: usize = 8192;
+
+/// Element of the surface of a rigid scatterer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Facet {
+    pub center: Vector3,
+    /// Outward normal scaled by the area in mm^2
+    pub normal: Vector3,
+    /// Pressure on the surface, see [Scattering]
+    pub pressure: Complex,
+}
+
+/// Rigid bodies scattering the field of the sources in the Kirchhoff approximation, where the
+/// pressure on the surface is twice the incident pressure of the sources in front of each facet
+/// and zero of those behind it. The field radiated by the facets makes both the first-order
+/// reflection and, cancelling the incident field, the shadow behind the bodies. The facets must be
+/// smaller than about a half wavelength, and the multiple reflections and the diffraction around
+/// the edges are only approximated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scattering {
+    pub facets: Vec<Facet>,
+}
+
+fn mul(a: Complex, b: Complex) -> Complex {
+    [a[0] * b[0] - a[1] * b[1], a[0] * b[1] + a[1] * b[0]]
+}
+
+impl Facet {
+    /// Facets of the triangles of `meshes` split until the edges are at most `max_edge` mm, or
+    /// longer if the facets exceed [MAX_FACETS], whose normals follow the counterclockwise
+    /// winding seen from the outside. Only the first [MAX_FACETS] triangles are taken if there
+    /// are more.
+    pub fn split(meshes: &[&Mesh], max_edge: f32) -> Vec<Facet> {
+        let triangles: Vec<_> = meshes.iter().flat_map(|m| m.triangles.iter()).collect();
+        let longest = |t: &[Vector3; 3]| {
+            (0..3)
+                .map(|i| vecmath_util::dist(t[i], t[(i + 1) % 3]))
+                .fold(0.0, f32::max)
+        };
+        let mut max_edge = max_edge.max(1e-3);
+        let divisions = loop {
+            let divisions: Vec<_> = triangles
+                .iter()
+                .map(|t| (longest(t) / max_edge).ceil().max(1.0) as usize)
+                .collect();
+            let n: usize = divisions.iter().map(|m| m * m).sum();
+            if n <= MAX_FACETS || divisions.iter().all(|&m| m == 1) {
+                break divisions;
+            }
+            max_edge *= (n as f32 / MAX_FACETS as f32).sqrt().max(1.01);
+        };
+        triangles
+            .iter()
+            .zip(divisions)
+            .flat_map(|(t, m)| {
+                let e1 = vecmath::vec3_sub(t[1], t[0]);
+                let e2 = vecmath::vec3_sub(t[2], t[0]);
+                let normal = vecmath::vec3_scale(vecmath::vec3_cross(e1, e2), 0.5 / (m * m) as f32);
+                let at = move |i: f32, j: f32| {
+                    let p = vecmath::vec3_add(t[0], vecmath::vec3_scale(e1, i / m as f32));
+                    vecmath::vec3_add(p, vecmath::vec3_scale(e2, j / m as f32))
+                };
+                // the centroids of the m^2 congruent triangles, m(m+1)/2 of which are upward
+                (0..m)
+                    .flat_map(move |i| (0..m - i).map(move |j| (i as f32, j as f32)))
+                    .flat_map(move |(i, j)| {
+                        let up = at(i + 1.0 / 3.0, j + 1.0 / 3.0);
+                        let down =
+                            (i + j + 2.0 <= m as f32).then(|| at(i + 2.0 / 3.0, j + 2.0 / 3.0));
+                        std::iter::once(up).chain(down)
+                    })
+                    .map(move |center| Facet {
+                        center,
+                        normal,
+                        pressure: [0.0, 0.0],
+                    })
+            })
+            .filter(|f| vecmath::vec3_len(f.normal) > 0.0)
+            .take(MAX_FACETS)
+            .collect()
+    }
+}
+
+impl Scattering {
+    /// Sets the pressure on `facets` from the incident field of `model`, whose scattering is
+    /// ignored
+    pub fn new(facets: Vec<Facet>, model: &FieldModel) -> Self {
+        let facets = facets
+            .into_par_iter()
+            .map(|f| {
+                let [re, im] = model.incident_pressure_on(f.center, f.normal);
+                Facet {
+                    pressure: [2.0 * re, 2.0 * im],
+                    ..f
+                }
+            })
+            .collect();
+        Self { facets }
+    }
+
+    /// Scattered pressure at `pos` for the wavenumber `k` in 1/mm and the `attenuation` in 1/mm
+    pub fn pressure_at(&self, pos: Vector3, k: f32, attenuation: f32) -> Complex {
+        self.facets.iter().fold([0.0, 0.0], |[re, im], f| {
+            let r = vecmath::vec3_sub(pos, f.center);
+            let d = vecmath::vec3_len(r);
+            if d == 0.0 {
+                return [re, im];
+            }
+            // the normal derivative of the Green function exp(-ikd) / (4 pi d), times the area
+            let c = vecmath::vec3_dot(r, f.normal) * (-attenuation * d).exp() / (4.0 * PI * d * d);
+            let e = [(k * d).cos() * c, -(k * d).sin() * c];
+            let [pr, pi] = mul(mul(f.pressure, [1.0 / d, k]), e);
+            [re + pr, im + pi]
+        })
+    }
+
+    /// [Scattering::pressure_at] and its gradient in 1/mm
+    pub fn pressure_gradient_at(
+        &self,
+        pos: Vector3,
+        k: f32,
+        attenuation: f32,
+    ) -> (Complex, [Complex; 3]) {
+        let mut p = [0.0, 0.0];
+        let mut grad = [[0.0, 0.0]; 3];
+        for f in &self.facets {
+            let r = vecmath::vec3_sub(pos, f.center);
+            let d = vecmath::vec3_len(r);
+            if d == 0.0 {
+                continue;
+            }
+            // (r.n) h(d) with h = (1/d^3 + ik/d^2) exp(-ikd - ad) / (4 pi), whose gradient is
+            // n h + (r.n) h'(d) r / d
+            let a = (-attenuation * d).exp() / (4.0 * PI);
+            let e = [(k * d).cos() * a, -(k * d).sin() * a];
+            let h = mul(mul(f.pressure, [1.0 / (d * d * d), k / (d * d)]), e);
+            let dh = mul(
+                mul(
+                    f.pressure,
+                    [
+                        -3.0 / (d * d * d * d) + k * k / (d * d),
+                        -3.0 * k / (d * d * d),
+                    ],
+                ),
+                e,
+            );
+            let dh = [dh[0] - attenuation * h[0], dh[1] - attenuation * h[1]];
+            let rn = vecmath::vec3_dot(r, f.normal);
+            p[0] += rn * h[0];
+            p[1] += rn * h[1];
+            for i in 0..3 {
+                grad[i][0] += f.normal[i] * h[0] + rn * dh[0] * r[i] / d;
+                grad[i][1] += f.normal[i] * h[1] + rn * dh[1] * r[i] / d;
+            }
+        }
+        (p, grad)
+    }
+}
@@ -11,14 +11,18 @@
  *
  */
 
-use crate::Vector3;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy)]
+use crate::{view::ViewerSettings, Vector3};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct SoundSource {
     pub pos: Vector3,
     pub dir: Vector3,
     pub amp: f32,
     pub phase: f32,
+    /// Frequency in Hz, or `None` to use the frequency of the viewer settings
+    pub frequency: Option<f32>,
 }
 
 impl SoundSource {
@@ -28,6 +32,15 @@ impl SoundSource {
             dir,
             amp,
             phase,
+            frequency: None,
+        }
+    }
+
+    /// Wavenumber in 1/mm
+    pub fn wavenumber(&self, settings: &ViewerSettings) -> f32 {
+        match self.frequency {
+            Some(frequency) => 2.0 * std::f32::consts::PI * frequency / settings.sound_speed(),
+            None => 2.0 * std::f32::consts::PI / settings.wave_length,
         }
     }
 }
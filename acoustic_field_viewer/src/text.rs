@@ -0,0 +1,88 @@
+/*
+ * File: text.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use image::{Rgba, RgbaImage};
+use rusttype::{point, Font, Scale};
+
+const FONT: &[u8] = include_bytes!("../../assets/fonts/NotoSans-Regular.ttf");
+
+/// Rasterizer of the bundled font into images, for the labels drawn in the view
+pub struct TextRenderer {
+    font: Font<'static>,
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        Self {
+            font: Font::try_from_bytes(FONT).expect("invalid bundled font"),
+        }
+    }
+
+    /// Width and height of the box of `text` of the font `size` in pixels
+    pub fn measure(&self, text: &str, size: f32) -> [f32; 2] {
+        let scale = Scale::uniform(size);
+        let v_metrics = self.font.v_metrics(scale);
+        let width = self
+            .font
+            .layout(text, scale, point(0.0, 0.0))
+            .last()
+            .map_or(0.0, |g| {
+                g.position().x + g.unpositioned().h_metrics().advance_width
+            });
+        [width, v_metrics.ascent - v_metrics.descent]
+    }
+
+    /// Draws `text` over `image` at `pos`, where `align` is the position of `pos` in the box of the
+    /// text from the upper left [0, 0] to the lower right [1, 1]
+    pub fn draw(
+        &self,
+        image: &mut RgbaImage,
+        text: &str,
+        pos: [f32; 2],
+        size: f32,
+        align: [f32; 2],
+        color: Rgba<u8>,
+    ) {
+        let scale = Scale::uniform(size);
+        let ascent = self.font.v_metrics(scale).ascent;
+        let [width, height] = self.measure(text, size);
+        let origin = point(
+            (pos[0] - width * align[0]).round(),
+            (pos[1] - height * align[1]).round() + ascent,
+        );
+        for glyph in self.font.layout(text, scale, origin) {
+            let bb = match glyph.pixel_bounding_box() {
+                Some(bb) => bb,
+                None => continue,
+            };
+            glyph.draw(|gx, gy, coverage| {
+                let (x, y) = (gx as i32 + bb.min.x, gy as i32 + bb.min.y);
+                if x < 0 || y < 0 || x >= image.width() as i32 || y >= image.height() as i32 {
+                    return;
+                }
+                let pixel = image.get_pixel_mut(x as u32, y as u32);
+                let a = coverage * color[3] as f32 / 255.0;
+                for (p, &c) in pixel.0.iter_mut().zip(color.0.iter()).take(3) {
+                    *p = (*p as f32 * (1.0 - a) + c as f32 * a) as u8;
+                }
+                pixel[3] = (pixel[3] as f32 * (1.0 - a) + 255.0 * a) as u8;
+            });
+        }
+    }
+}
+
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
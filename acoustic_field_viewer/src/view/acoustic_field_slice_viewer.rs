@@ -19,21 +19,34 @@ use gfx::{
     handle::{Buffer, DepthStencilView, RenderTargetView, ShaderResourceView},
     preset::depth,
     state::{Blend, ColorMask},
-    texture::{FilterMethod, Kind, Mipmap, SamplerInfo, WrapMode},
+    texture::{AaMode, FilterMethod, Kind, Mipmap, SamplerInfo, WrapMode},
     traits::*,
-    BlendTarget, DepthTarget, Global, PipelineState, Slice, TextureSampler, VertexBuffer,
+    BlendTarget, DepthTarget, Global, PipelineState, RenderTarget, Slice, TextureSampler,
+    VertexBuffer,
 };
 use gfx_device_gl::{CommandBuffer, Resources};
 use glutin::event::{Event, WindowEvent};
-use scarlet::{color::RGBColor, colormap::ColorMap};
+use scarlet::color::RGBColor;
 use shader_version::{glsl::GLSL, OpenGL, Shaders};
 
 use crate::{
+    directivity::{Directivity, DirectivityTable},
+    field::{FieldApproximation, SourceGroup},
+    levitation::GorkovCoefficients,
+    obstacle::{Obstacle, ObstacleShape},
+    scatterer::Scattering,
     sound_source::SoundSource,
-    view::{render_system, render_system::RenderSystem, UpdateFlag, ViewerSettings},
+    view::{
+        render_system, render_system::RenderSystem, shader_watcher, CompareView, PickId,
+        PickingBuffer, UpdateFlag, ViewerSettings,
+    },
     Matrix4, Vector3, Vector4,
 };
 
+const DIRECTIVITY_TABLE_SIZE: usize = 256;
+/// Largest number of the points of the sampled slice along an axis
+pub const MAX_SAMPLING_SIZE: usize = 8192;
+
 gfx_vertex_struct!(Vertex {
     a_pos: [i16; 4] = "a_pos",
 });
@@ -60,21 +73,134 @@ gfx_pipeline!( pipe {
     u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
     u_model: Global<[[f32; 4]; 4]> = "u_model",
     u_color_scale : Global<f32> = "u_color_scale",
-    u_wavenum : Global<f32> = "u_wavenum",
+    u_gamma : Global<f32> = "u_gamma",
+    u_dynamic_range : Global<f32> = "u_dynamic_range",
+    u_quantity : Global<i32> = "u_quantity",
+    u_wt : Global<f32> = "u_wt",
     u_color_map: TextureSampler<[f32; 4]> = "u_color_map",
     u_trans_num : Global<f32> = "u_trans_num",
+    u_attenuation : Global<f32> = "u_attenuation",
+    u_reflector : Global<i32> = "u_reflector",
+    u_reflector_center : Global<[f32; 3]> = "u_reflector_center",
+    u_reflector_normal : Global<[f32; 3]> = "u_reflector_normal",
+    u_reflection : Global<f32> = "u_reflection",
+    u_obstacle_shapes : Global<[f32; 4]> = "u_obstacle_shapes",
+    u_obstacle0 : Global<[[f32; 4]; 4]> = "u_obstacle0",
+    u_obstacle1 : Global<[[f32; 4]; 4]> = "u_obstacle1",
+    u_obstacle2 : Global<[[f32; 4]; 4]> = "u_obstacle2",
+    u_obstacle3 : Global<[[f32; 4]; 4]> = "u_obstacle3",
+    u_gorkov : Global<[f32; 2]> = "u_gorkov",
+    u_wave_length : Global<f32> = "u_wave_length",
     u_trans_pos: TextureSampler<[f32; 4]> = "u_trans_pos",
+    u_trans_dir: TextureSampler<[f32; 4]> = "u_trans_dir",
+    u_directivity: TextureSampler<[f32; 4]> = "u_directivity",
     u_trans_drive: TextureSampler<[f32; 4]> = "u_trans_drive",
+    u_approximation : Global<i32> = "u_approximation",
+    u_group_num : Global<f32> = "u_group_num",
+    u_group_center: TextureSampler<[f32; 4]> = "u_group_center",
+    u_group_dir: TextureSampler<[f32; 4]> = "u_group_dir",
+    u_facet_num : Global<f32> = "u_facet_num",
+    u_facet_pos: TextureSampler<[f32; 4]> = "u_facet_pos",
+    u_facet_normal: TextureSampler<[f32; 4]> = "u_facet_normal",
+    u_compare : Global<i32> = "u_compare",
+    u_ref_drive: TextureSampler<[f32; 4]> = "u_ref_drive",
+    u_ref_facet: TextureSampler<[f32; 4]> = "u_ref_facet",
+    out_color: BlendTarget<format::Srgba8> = ("o_Color", ColorMask::all(), alpha_blender()),
+    out_depth: DepthTarget<format::DepthStencil> = depth::LESS_EQUAL_WRITE,
+});
+
+// The same as pipe, but writes to the sampling texture without blending, and is drawn with the
+// quad covering the whole texture
+gfx_pipeline!( sample_pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
+    u_model: Global<[[f32; 4]; 4]> = "u_model",
+    u_color_scale : Global<f32> = "u_color_scale",
+    u_gamma : Global<f32> = "u_gamma",
+    u_dynamic_range : Global<f32> = "u_dynamic_range",
+    u_quantity : Global<i32> = "u_quantity",
+    u_wt : Global<f32> = "u_wt",
+    u_color_map: TextureSampler<[f32; 4]> = "u_color_map",
+    u_trans_num : Global<f32> = "u_trans_num",
+    u_attenuation : Global<f32> = "u_attenuation",
+    u_reflector : Global<i32> = "u_reflector",
+    u_reflector_center : Global<[f32; 3]> = "u_reflector_center",
+    u_reflector_normal : Global<[f32; 3]> = "u_reflector_normal",
+    u_reflection : Global<f32> = "u_reflection",
+    u_obstacle_shapes : Global<[f32; 4]> = "u_obstacle_shapes",
+    u_obstacle0 : Global<[[f32; 4]; 4]> = "u_obstacle0",
+    u_obstacle1 : Global<[[f32; 4]; 4]> = "u_obstacle1",
+    u_obstacle2 : Global<[[f32; 4]; 4]> = "u_obstacle2",
+    u_obstacle3 : Global<[[f32; 4]; 4]> = "u_obstacle3",
+    u_gorkov : Global<[f32; 2]> = "u_gorkov",
+    u_wave_length : Global<f32> = "u_wave_length",
+    u_trans_pos: TextureSampler<[f32; 4]> = "u_trans_pos",
+    u_trans_dir: TextureSampler<[f32; 4]> = "u_trans_dir",
+    u_directivity: TextureSampler<[f32; 4]> = "u_directivity",
+    u_trans_drive: TextureSampler<[f32; 4]> = "u_trans_drive",
+    u_approximation : Global<i32> = "u_approximation",
+    u_group_num : Global<f32> = "u_group_num",
+    u_group_center: TextureSampler<[f32; 4]> = "u_group_center",
+    u_group_dir: TextureSampler<[f32; 4]> = "u_group_dir",
+    u_facet_num : Global<f32> = "u_facet_num",
+    u_facet_pos: TextureSampler<[f32; 4]> = "u_facet_pos",
+    u_facet_normal: TextureSampler<[f32; 4]> = "u_facet_normal",
+    u_compare : Global<i32> = "u_compare",
+    u_ref_drive: TextureSampler<[f32; 4]> = "u_ref_drive",
+    u_ref_facet: TextureSampler<[f32; 4]> = "u_ref_facet",
+    out_color: RenderTarget<format::Srgba8> = "o_Color",
+});
+
+gfx_pipeline!( texture_pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
+    u_uv_offset: Global<[f32; 2]> = "u_uv_offset",
+    u_uv_scale: Global<[f32; 2]> = "u_uv_scale",
+    u_field: TextureSampler<[f32; 4]> = "u_field",
     out_color: BlendTarget<format::Srgba8> = ("o_Color", ColorMask::all(), alpha_blender()),
     out_depth: DepthTarget<format::DepthStencil> = depth::LESS_EQUAL_WRITE,
 });
 
+gfx_pipeline!( pick_pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
+    u_model: Global<[[f32; 4]; 4]> = "u_model",
+    u_id: Global<[f32; 4]> = "u_id",
+    out_id: RenderTarget<format::Rgba8> = "o_Id",
+    out_depth: DepthTarget<format::DepthStencil> = depth::LESS_EQUAL_WRITE,
+});
+
+// Texture into which the field is evaluated at the points of the grid of the sampling, one texel
+// per point, which is shown on the slice without interpolation
+struct Sampling {
+    target: RenderTargetView<Resources, format::Srgba8>,
+    target_view: ShaderResourceView<Resources, [f32; 4]>,
+    // The texels are uploaded from the CPU instead of rendering into the target
+    uploaded: bool,
+    // Maps the local coordinates of the slice onto the texture
+    projection: Matrix4,
+    resolution: f32,
+    size: [usize; 2],
+    texture_data: texture_pipe::Data<Resources>,
+}
+
 pub struct AcousticFiledSliceViewer {
     pipe_data: pipe::Data<Resources>,
     model: Matrix4,
     pso: PipelineState<Resources, pipe::Meta>,
+    pick_pso: PipelineState<Resources, pick_pipe::Meta>,
+    sample_pso: PipelineState<Resources, sample_pipe::Meta>,
+    texture_pso: PipelineState<Resources, texture_pipe::Meta>,
     slice: Slice<Resources>,
+    sample_vertex_buffer: Buffer<Resources, Vertex>,
+    sample_slice: Slice<Resources>,
+    sampling: Option<Sampling>,
     color_map: Vec<RGBColor>,
+    imported_color_map: Option<Vec<RGBColor>>,
+    directivity_table: Option<DirectivityTable>,
+    // Number of the sources of each device, grouped in the Fresnel approximation
+    device_sizes: Vec<usize>,
+    has_reference: bool,
 }
 
 impl AcousticFiledSliceViewer {
@@ -90,9 +216,17 @@ impl AcousticFiledSliceViewer {
         let drive_view = AcousticFiledSliceViewer::generate_empty_view(factory);
 
         let (vertex_buffer, slice) = Self::initialize_vertex_buf_and_slice(factory, settings);
+        let (sample_vertex_buffer, sample_slice) = factory.create_vertex_buffer_with_slice(
+            &[
+                Vertex::new([i16::MIN, i16::MIN, 0]),
+                Vertex::new([i16::MAX, i16::MIN, 0]),
+                Vertex::new([i16::MAX, i16::MAX, 0]),
+                Vertex::new([i16::MIN, i16::MAX, 0]),
+            ],
+            &[0u16, 1, 2, 2, 3, 0][..],
+        );
 
-        let iter = (0..100).map(|x| x as f64 / 100.0);
-        AcousticFiledSliceViewer {
+        let mut viewer = AcousticFiledSliceViewer {
             pipe_data: Self::initialize_pipe_data(
                 factory,
                 vertex_buffer,
@@ -102,9 +236,28 @@ impl AcousticFiledSliceViewer {
             ),
             model: vecmath_util::mat4_scale(1.0),
             pso: Self::initialize_shader(factory, glsl),
+            pick_pso: Self::initialize_pick_shader(factory, glsl),
+            sample_pso: Self::initialize_sample_shader(factory, glsl),
+            texture_pso: Self::initialize_texture_shader(factory, glsl),
             slice,
-            color_map: scarlet::colormap::ListedColorMap::inferno().transform(iter),
-        }
+            sample_vertex_buffer,
+            sample_slice,
+            sampling: None,
+            color_map: settings.active_color_map().colors(None),
+            imported_color_map: None,
+            directivity_table: None,
+            device_sizes: Vec::new(),
+            has_reference: false,
+        };
+        viewer.update_sampling(factory, settings);
+        viewer
+    }
+
+    /// Spacing in mm and the number of the points along the width and the height of the sampling
+    /// grid, or None if the field is evaluated per pixel. The spacing is larger than that of
+    /// [ViewerSettings::slice_sampling] if the grid exceeds [MAX_SAMPLING_SIZE].
+    pub fn sampling(&self) -> Option<(f32, [usize; 2])> {
+        self.sampling.as_ref().map(|s| (s.resolution, s.size))
     }
 
     pub fn move_to(&mut self, pos: Vector4) {
@@ -118,14 +271,47 @@ impl AcousticFiledSliceViewer {
         self.model = model;
     }
 
+    /// Phase ωt in radians at which [crate::view::FieldQuantity::RealPart] is shown,
+    /// i.e., Re(p e^{iωt})
+    pub fn set_wave_phase(&mut self, wt: f32) {
+        self.pipe_data.u_wt = wt.rem_euclid(2.0 * std::f32::consts::PI);
+    }
+
     pub fn model(&self) -> Matrix4 {
         self.model
     }
 
+    /// Colors of [ViewerSettings::active_color_map] applied with the last
+    /// [UpdateFlag::UPDATE_COLOR_MAP]
     pub fn color_map(&self) -> &[RGBColor] {
         &self.color_map
     }
 
+    /// Colors used for [crate::color_map::ColorMapKind::Imported], applied with
+    /// [UpdateFlag::UPDATE_COLOR_MAP]
+    pub fn set_imported_color_map(&mut self, colors: Option<Vec<RGBColor>>) {
+        self.imported_color_map = colors;
+    }
+
+    pub fn imported_color_map(&self) -> Option<&[RGBColor]> {
+        self.imported_color_map.as_deref()
+    }
+
+    /// Table used for [Directivity::Imported], applied with [UpdateFlag::UPDATE_WAVENUM]
+    pub fn set_directivity_table(&mut self, table: Option<DirectivityTable>) {
+        self.directivity_table = table;
+    }
+
+    pub fn directivity_table(&self) -> Option<&DirectivityTable> {
+        self.directivity_table.as_ref()
+    }
+
+    /// Sets the number of the sources of each device, see [SourceGroup::split], which is applied
+    /// on the next update with [UpdateFlag::INIT_SOURCE]
+    pub fn set_device_sizes(&mut self, device_sizes: &[usize]) {
+        self.device_sizes = device_sizes.to_vec();
+    }
+
     pub fn update(
         &mut self,
         renderer_sys: &mut RenderSystem,
@@ -135,10 +321,16 @@ impl AcousticFiledSliceViewer {
         update_flag: UpdateFlag,
     ) {
         if update_flag.contains(UpdateFlag::UPDATE_SLICE_SIZE) {
+            tracing::debug!(
+                "resize slice to {}x{}",
+                settings.slice_width,
+                settings.slice_height
+            );
             let (vertex_buffer, slice) =
                 Self::initialize_vertex_buf_and_slice(&mut renderer_sys.factory, settings);
             self.pipe_data.vertex_buffer = vertex_buffer;
             self.slice = slice;
+            self.update_sampling(&mut renderer_sys.factory, settings);
         }
 
         if update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE) {
@@ -149,16 +341,41 @@ impl AcousticFiledSliceViewer {
             );
         }
 
-        if update_flag.contains(UpdateFlag::INIT_SOURCE) {
+        if update_flag.contains(UpdateFlag::INIT_SOURCE)
+            || update_flag.contains(UpdateFlag::UPDATE_WAVENUM)
+        {
             self.pipe_data.u_trans_num = sources.len() as f32;
+            self.pipe_data.u_attenuation = settings.medium.attenuation;
+            let reflector = &settings.reflector;
+            self.pipe_data.u_reflector = reflector.enabled as i32;
+            self.pipe_data.u_reflector_center = reflector.center;
+            self.pipe_data.u_reflector_normal = reflector.normal();
+            self.pipe_data.u_reflection = reflector.coefficient;
+            self.pipe_data.u_approximation = match settings.approximation {
+                FieldApproximation::Exact => 0,
+                FieldApproximation::Fresnel => 1,
+            };
+            self.update_obstacles(settings);
+            self.update_gorkov(settings);
             AcousticFiledSliceViewer::update_position_texture(
                 &mut self.pipe_data,
                 &mut renderer_sys.factory,
+                settings,
                 sources,
+                &self.device_sizes,
+            );
+            AcousticFiledSliceViewer::update_directivity_texture(
+                &mut self.pipe_data,
+                &mut renderer_sys.factory,
+                settings,
+                self.directivity_table.as_ref(),
             );
         }
 
         if update_flag.contains(UpdateFlag::UPDATE_COLOR_MAP) {
+            self.color_map = settings
+                .active_color_map()
+                .colors(self.imported_color_map.as_deref());
             let alpha = settings.slice_alpha;
             self.pipe_data.u_color_map = AcousticFiledSliceViewer::update_color_map_texture(
                 &mut renderer_sys.factory,
@@ -166,10 +383,15 @@ impl AcousticFiledSliceViewer {
                 alpha,
             );
             self.pipe_data.u_color_scale = settings.color_scale;
-        }
-
-        if update_flag.contains(UpdateFlag::UPDATE_WAVENUM) {
-            self.pipe_data.u_wavenum = 2.0 * std::f32::consts::PI / settings.wave_length;
+            self.pipe_data.u_gamma = settings.gamma;
+            self.pipe_data.u_dynamic_range = settings.dynamic_range.unwrap_or(0.0);
+            self.pipe_data.u_quantity = settings.field_quantity.index();
+            self.pipe_data.u_compare = if self.has_reference {
+                settings.compare.index()
+            } else {
+                CompareView::Current.index()
+            };
+            self.update_gorkov(settings);
         }
 
         if update_flag.contains(UpdateFlag::UPDATE_CAMERA_POS)
@@ -182,14 +404,273 @@ impl AcousticFiledSliceViewer {
         }
     }
 
+    // The points are those of crate::field::SliceGrid with the same spacing, at the texel centers
+    fn update_sampling(&mut self, factory: &mut gfx_device_gl::Factory, settings: &ViewerSettings) {
+        let density = match settings.slice_sampling {
+            Some(density) if density > 0.0 => density,
+            _ => {
+                self.sampling = None;
+                return;
+            }
+        };
+        let (width, height) = (
+            settings.slice_width.max(0) as f32,
+            settings.slice_height.max(0) as f32,
+        );
+        let max_extent = (MAX_SAMPLING_SIZE - 1) as f32;
+        let resolution = (1.0 / density)
+            .max(width / max_extent)
+            .max(height / max_extent);
+        let nx = ((width / resolution).floor() as usize + 1).min(MAX_SAMPLING_SIZE);
+        let ny = ((height / resolution).floor() as usize + 1).min(MAX_SAMPLING_SIZE);
+        let (_, view, target) =
+            match factory.create_render_target::<format::Srgba8>(nx as u16, ny as u16) {
+                Ok(t) => t,
+                Err(e) => {
+                    tracing::error!("failed to create the sampling texture: {}", e);
+                    self.sampling = None;
+                    return;
+                }
+            };
+        let origin = [
+            -width / 2.0 - resolution / 2.0,
+            -height / 2.0 - resolution / 2.0,
+        ];
+        let extent = [nx as f32 * resolution, ny as f32 * resolution];
+        let mut projection = vecmath_util::mat4_scale(1.0);
+        for k in 0..2 {
+            projection[k][k] = 2.0 / extent[k];
+            projection[3][k] = -1.0 - 2.0 * origin[k] / extent[k];
+        }
+        let sampler =
+            factory.create_sampler(SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp));
+        self.sampling = Some(Sampling {
+            target,
+            target_view: view.clone(),
+            uploaded: false,
+            projection,
+            resolution,
+            size: [nx, ny],
+            texture_data: texture_pipe::Data {
+                vertex_buffer: self.pipe_data.vertex_buffer.clone(),
+                u_model_view_proj: self.pipe_data.u_model_view_proj,
+                u_uv_offset: origin,
+                u_uv_scale: [1.0 / extent[0], 1.0 / extent[1]],
+                u_field: (view, sampler),
+                out_color: self.pipe_data.out_color.clone(),
+                out_depth: self.pipe_data.out_depth.clone(),
+            },
+        });
+        tracing::debug!("sample slice at {}x{} points", nx, ny);
+    }
+
+    /// Shows `colors` at the points of the sampling grid, row by row from the bottom, e.g.,
+    /// computed on the CPU, instead of evaluating the shader, until the grid changes or
+    /// [AcousticFiledSliceViewer::clear_sampled_colors]. Returns false, ignoring the colors, if
+    /// the slice is not sampled or the number of the colors is not that of the grid.
+    pub fn set_sampled_colors(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        colors: &[[u8; 4]],
+    ) -> bool {
+        let sampling = match &mut self.sampling {
+            Some(sampling) if colors.len() == sampling.size[0] * sampling.size[1] => sampling,
+            _ => return false,
+        };
+        // not sRGB, the same as the color map texture evaluated by the shader
+        let (_, view) = match factory.create_texture_immutable::<format::Rgba8>(
+            Kind::D2(
+                sampling.size[0] as u16,
+                sampling.size[1] as u16,
+                AaMode::Single,
+            ),
+            Mipmap::Provided,
+            &[colors],
+        ) {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::error!("failed to upload the sampled slice: {}", e);
+                return false;
+            }
+        };
+        sampling.texture_data.u_field.0 = view;
+        sampling.uploaded = true;
+        true
+    }
+
+    /// Evaluates the shader on the sampling grid again
+    pub fn clear_sampled_colors(&mut self) {
+        if let Some(sampling) = &mut self.sampling {
+            sampling.texture_data.u_field.0 = sampling.target_view.clone();
+            sampling.uploaded = false;
+        }
+    }
+
+    /// Facets of the scatterers with the pressure on them, which is not updated with the drive,
+    /// or no scattering if None
+    pub fn set_scattering(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        scattering: Option<&Scattering>,
+    ) {
+        let facets = match scattering {
+            Some(scattering) if !scattering.facets.is_empty() => &scattering.facets,
+            _ => {
+                self.pipe_data.u_facet_num = 0.0;
+                return;
+            }
+        };
+        let sampler_info = SamplerInfo::new(FilterMethod::Scale, WrapMode::Tile);
+        let kind = Kind::D1(facets.len() as u16);
+        // the real and the imaginary parts of the pressure in the w components
+        let texels: Vec<[u32; 4]> = facets
+            .iter()
+            .map(|f| {
+                let [x, y, z] = f.center;
+                [x, y, z, f.pressure[0]].map(f32::to_bits)
+            })
+            .collect();
+        let (_, texture_view) = factory
+            .create_texture_immutable::<Rgba32F>(kind, Mipmap::Provided, &[&texels])
+            .unwrap();
+        self.pipe_data.u_facet_pos = (texture_view, factory.create_sampler(sampler_info));
+        let texels: Vec<[u32; 4]> = facets
+            .iter()
+            .map(|f| {
+                let [x, y, z] = f.normal;
+                [x, y, z, f.pressure[1]].map(f32::to_bits)
+            })
+            .collect();
+        let (_, texture_view) = factory
+            .create_texture_immutable::<Rgba32F>(kind, Mipmap::Provided, &[&texels])
+            .unwrap();
+        self.pipe_data.u_facet_normal = (texture_view, factory.create_sampler(sampler_info));
+        self.pipe_data.u_facet_num = facets.len() as f32;
+    }
+
+    /// Drive of the sources and the scattering frozen as the reference of [CompareView], which
+    /// must be those of the same sources and facets as the current ones, applied with
+    /// [UpdateFlag::UPDATE_COLOR_MAP]. The slice shows the current drive regardless of
+    /// [ViewerSettings::compare] if None.
+    pub fn set_reference(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        reference: Option<(&[SoundSource], Option<&Scattering>)>,
+    ) {
+        let (sources, scattering) = match reference {
+            Some((sources, scattering)) if !sources.is_empty() => (sources, scattering),
+            _ => {
+                self.has_reference = false;
+                self.pipe_data.u_compare = CompareView::Current.index();
+                return;
+            }
+        };
+        self.pipe_data.u_ref_drive = Self::drive_texture(factory, sources);
+        if let Some(scattering) = scattering.filter(|s| !s.facets.is_empty()) {
+            let texels: Vec<[u32; 4]> = scattering
+                .facets
+                .iter()
+                .map(|f| [f.pressure[0], f.pressure[1], 0.0, 0.0].map(f32::to_bits))
+                .collect();
+            let (_, texture_view) = factory
+                .create_texture_immutable::<Rgba32F>(
+                    Kind::D1(texels.len() as u16),
+                    Mipmap::Provided,
+                    &[&texels],
+                )
+                .unwrap();
+            let sampler_info = SamplerInfo::new(FilterMethod::Scale, WrapMode::Tile);
+            self.pipe_data.u_ref_facet = (texture_view, factory.create_sampler(sampler_info));
+        }
+        self.has_reference = true;
+    }
+
+    // The shapes are 0 for the disabled ones, and 1, 2 and 3 for a sphere, a box and a plane
+    fn update_obstacles(&mut self, settings: &ViewerSettings) {
+        let obstacles = &settings.obstacles;
+        self.pipe_data.u_obstacle_shapes = [0, 1, 2, 3].map(|i| match obstacles[i] {
+            Obstacle { enabled: false, .. } => 0.0,
+            Obstacle { shape, .. } => match shape {
+                ObstacleShape::Sphere => 1.0,
+                ObstacleShape::Box => 2.0,
+                ObstacleShape::Plane => 3.0,
+            },
+        });
+        self.pipe_data.u_obstacle0 = obstacles[0].to_local();
+        self.pipe_data.u_obstacle1 = obstacles[1].to_local();
+        self.pipe_data.u_obstacle2 = obstacles[2].to_local();
+        self.pipe_data.u_obstacle3 = obstacles[3].to_local();
+    }
+
+    // The potential is normalized on the GPU to avoid the underflow of f32
+    fn update_gorkov(&mut self, settings: &ViewerSettings) {
+        let gorkov = GorkovCoefficients::new(settings);
+        let reference = gorkov
+            .reference(settings.color_scale)
+            .max(f32::MIN_POSITIVE);
+        self.pipe_data.u_gorkov = [gorkov.k1 / reference, gorkov.k2 / reference];
+        self.pipe_data.u_wave_length = settings.wave_length;
+    }
+
+    /// Rebuilds the pipelines from the shaders in assets/shaders. The current ones are kept on error.
+    pub fn reload_shaders(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        opengl: OpenGL,
+    ) -> Result<(), String> {
+        let glsl = opengl.to_glsl();
+        let pso = shader_watcher::create_pipeline(
+            factory,
+            glsl,
+            "slice.vert",
+            "slice.frag",
+            pipe::new(),
+        )?;
+        let pick_pso = shader_watcher::create_pipeline(
+            factory,
+            glsl,
+            "slice.vert",
+            "pick.frag",
+            pick_pipe::new(),
+        )?;
+        let sample_pso = shader_watcher::create_pipeline(
+            factory,
+            glsl,
+            "slice.vert",
+            "slice.frag",
+            sample_pipe::new(),
+        )?;
+        let texture_pso = shader_watcher::create_pipeline(
+            factory,
+            glsl,
+            "slice_sampled.vert",
+            "slice_sampled.frag",
+            texture_pipe::new(),
+        )?;
+        self.pso = pso;
+        self.pick_pso = pick_pso;
+        self.sample_pso = sample_pso;
+        self.texture_pso = texture_pso;
+        Ok(())
+    }
+
     pub fn handle_event(&mut self, renderer_sys: &RenderSystem, event: &Event<()>) {
         if let Event::WindowEvent {
             event: WindowEvent::Resized(_),
             ..
         } = event
         {
-            self.pipe_data.out_color = renderer_sys.output_color.clone();
-            self.pipe_data.out_depth = renderer_sys.output_stencil.clone();
+            self.retarget(renderer_sys);
+        }
+    }
+
+    /// Draws into the current output of `renderer_sys` from the next frame
+    pub fn retarget(&mut self, renderer_sys: &RenderSystem) {
+        self.pipe_data.out_color = renderer_sys.output_color.clone();
+        self.pipe_data.out_depth = renderer_sys.output_stencil.clone();
+        if let Some(sampling) = &mut self.sampling {
+            sampling.texture_data.out_color = renderer_sys.output_color.clone();
+            sampling.texture_data.out_depth = renderer_sys.output_stencil.clone();
         }
     }
 
@@ -197,7 +678,79 @@ impl AcousticFiledSliceViewer {
         &mut self,
         encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
     ) {
-        encoder.draw(&self.slice, &self.pso, &self.pipe_data);
+        let sampling = match &mut self.sampling {
+            Some(sampling) => sampling,
+            None => {
+                encoder.draw(&self.slice, &self.pso, &self.pipe_data);
+                return;
+            }
+        };
+        let data = &self.pipe_data;
+        sampling.texture_data.vertex_buffer = data.vertex_buffer.clone();
+        sampling.texture_data.u_model_view_proj = data.u_model_view_proj;
+        if sampling.uploaded {
+            encoder.draw(&self.slice, &self.texture_pso, &sampling.texture_data);
+            return;
+        }
+        let sample_data = sample_pipe::Data {
+            vertex_buffer: self.sample_vertex_buffer.clone(),
+            u_model_view_proj: sampling.projection,
+            u_model: data.u_model,
+            u_color_scale: data.u_color_scale,
+            u_gamma: data.u_gamma,
+            u_dynamic_range: data.u_dynamic_range,
+            u_quantity: data.u_quantity,
+            u_wt: data.u_wt,
+            u_color_map: data.u_color_map.clone(),
+            u_trans_num: data.u_trans_num,
+            u_attenuation: data.u_attenuation,
+            u_reflector: data.u_reflector,
+            u_reflector_center: data.u_reflector_center,
+            u_reflector_normal: data.u_reflector_normal,
+            u_reflection: data.u_reflection,
+            u_obstacle_shapes: data.u_obstacle_shapes,
+            u_obstacle0: data.u_obstacle0,
+            u_obstacle1: data.u_obstacle1,
+            u_obstacle2: data.u_obstacle2,
+            u_obstacle3: data.u_obstacle3,
+            u_gorkov: data.u_gorkov,
+            u_wave_length: data.u_wave_length,
+            u_trans_pos: data.u_trans_pos.clone(),
+            u_trans_dir: data.u_trans_dir.clone(),
+            u_directivity: data.u_directivity.clone(),
+            u_trans_drive: data.u_trans_drive.clone(),
+            u_approximation: data.u_approximation,
+            u_group_num: data.u_group_num,
+            u_group_center: data.u_group_center.clone(),
+            u_group_dir: data.u_group_dir.clone(),
+            u_facet_num: data.u_facet_num,
+            u_facet_pos: data.u_facet_pos.clone(),
+            u_facet_normal: data.u_facet_normal.clone(),
+            u_compare: data.u_compare,
+            u_ref_drive: data.u_ref_drive.clone(),
+            u_ref_facet: data.u_ref_facet.clone(),
+            out_color: sampling.target.clone(),
+        };
+        encoder.clear(&sampling.target, [0.0; 4]);
+        encoder.draw(&self.sample_slice, &self.sample_pso, &sample_data);
+        encoder.draw(&self.slice, &self.texture_pso, &sampling.texture_data);
+    }
+
+    /// Draws the slice into the ID buffer as [PickId::Slice]
+    pub fn render_picking(
+        &self,
+        encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
+        picking: &PickingBuffer,
+    ) {
+        let data = pick_pipe::Data {
+            vertex_buffer: self.pipe_data.vertex_buffer.clone(),
+            u_model_view_proj: self.pipe_data.u_model_view_proj,
+            u_model: self.pipe_data.u_model,
+            u_id: PickId::Slice.to_color(),
+            out_id: picking.target().clone(),
+            out_depth: picking.depth().clone(),
+        };
+        encoder.draw(&self.slice, &self.pick_pso, &data);
     }
 
     fn update_drive_texture(
@@ -208,6 +761,16 @@ impl AcousticFiledSliceViewer {
         if sources.is_empty() {
             return;
         }
+        data.u_trans_drive = Self::drive_texture(factory, sources);
+    }
+
+    fn drive_texture(
+        factory: &mut gfx_device_gl::Factory,
+        sources: &[SoundSource],
+    ) -> (
+        ShaderResourceView<Resources, [f32; 4]>,
+        gfx::handle::Sampler<Resources>,
+    ) {
         let sampler_info = SamplerInfo::new(FilterMethod::Scale, WrapMode::Tile);
         let mut texels = Vec::with_capacity(sources.len());
         for source in sources {
@@ -225,15 +788,19 @@ impl AcousticFiledSliceViewer {
                 &[&texels],
             )
             .unwrap();
-        data.u_trans_drive = (texture_view, factory.create_sampler(sampler_info));
+        (texture_view, factory.create_sampler(sampler_info))
     }
 
+    // The w component holds the wavenumber of each source
     fn update_position_texture(
         data: &mut pipe::Data<gfx_device_gl::Resources>,
         factory: &mut gfx_device_gl::Factory,
+        settings: &ViewerSettings,
         sources: &[SoundSource],
+        device_sizes: &[usize],
     ) {
         if sources.is_empty() {
+            data.u_group_num = 0.0;
             return;
         }
         let sampler_info = SamplerInfo::new(FilterMethod::Scale, WrapMode::Tile);
@@ -244,7 +811,8 @@ impl AcousticFiledSliceViewer {
         let texels: Vec<[u32; 4]> = sources
             .iter()
             .map(|source| {
-                let pos = vecmath_util::to_vec4(source.pos);
+                let mut pos = vecmath_util::to_vec4(source.pos);
+                pos[3] = source.wavenumber(settings);
                 vecmath_util::vec4_map(pos, |p| unsafe { *(&p as *const _ as *const u32) })
             })
             .collect();
@@ -252,6 +820,73 @@ impl AcousticFiledSliceViewer {
             .create_texture_immutable::<Rgba32F>(kind, mipmap, &[&texels])
             .unwrap();
         data.u_trans_pos = (texture_view, factory.create_sampler(sampler_info));
+
+        let texels: Vec<[u32; 4]> = sources
+            .iter()
+            .map(|source| {
+                let dir = vecmath_util::to_vec4(source.dir);
+                vecmath_util::vec4_map(dir, |d| unsafe { *(&d as *const _ as *const u32) })
+            })
+            .collect();
+        let (_, texture_view) = factory
+            .create_texture_immutable::<Rgba32F>(kind, mipmap, &[&texels])
+            .unwrap();
+        data.u_trans_dir = (texture_view, factory.create_sampler(sampler_info));
+
+        // the w components hold the range of the indices of the sources
+        let groups = SourceGroup::split(sources, device_sizes);
+        let kind = Kind::D1(groups.len() as u16);
+        let texels: Vec<[u32; 4]> = groups
+            .iter()
+            .map(|g| {
+                let [x, y, z] = g.center;
+                [x, y, z, g.start as f32].map(f32::to_bits)
+            })
+            .collect();
+        let (_, texture_view) = factory
+            .create_texture_immutable::<Rgba32F>(kind, mipmap, &[&texels])
+            .unwrap();
+        data.u_group_center = (texture_view, factory.create_sampler(sampler_info));
+        let texels: Vec<[u32; 4]> = groups
+            .iter()
+            .map(|g| {
+                let [x, y, z] = g.dir;
+                [x, y, z, g.end as f32].map(f32::to_bits)
+            })
+            .collect();
+        let (_, texture_view) = factory
+            .create_texture_immutable::<Rgba32F>(kind, mipmap, &[&texels])
+            .unwrap();
+        data.u_group_dir = (texture_view, factory.create_sampler(sampler_info));
+        data.u_group_num = groups.len() as f32;
+    }
+
+    // The directivity of the viewer frequency is tabulated over the off-axis angle from 0 to pi
+    fn update_directivity_texture(
+        data: &mut pipe::Data<gfx_device_gl::Resources>,
+        factory: &mut gfx_device_gl::Factory,
+        settings: &ViewerSettings,
+        table: Option<&DirectivityTable>,
+    ) {
+        let wavenumber = 2.0 * std::f32::consts::PI / settings.wave_length;
+        let values = match (settings.directivity, table) {
+            (Directivity::Imported, Some(table)) => {
+                table.table(DIRECTIVITY_TABLE_SIZE, settings.frequency)
+            }
+            (directivity, _) => directivity.table(DIRECTIVITY_TABLE_SIZE, wavenumber),
+        };
+        let texels: Vec<[u32; 4]> = values.into_iter().map(|d| [d.to_bits(), 0, 0, 0]).collect();
+        let (_, texture_view) = factory
+            .create_texture_immutable::<Rgba32F>(
+                Kind::D1(DIRECTIVITY_TABLE_SIZE as u16),
+                Mipmap::Provided,
+                &[&texels],
+            )
+            .unwrap();
+        data.u_directivity = (
+            texture_view,
+            factory.create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp)),
+        );
     }
 
     fn update_color_map_texture(
@@ -317,8 +952,23 @@ impl AcousticFiledSliceViewer {
             u_model_view_proj: [[0.; 4]; 4],
             u_model: vecmath_util::mat4_scale(1.0),
             u_color_scale: 1.0,
-            u_wavenum: 0.0,
+            u_gamma: 1.0,
+            u_dynamic_range: 0.0,
+            u_quantity: 0,
+            u_wt: 0.0,
             u_trans_num: 0.0,
+            u_attenuation: 0.0,
+            u_reflector: 0,
+            u_reflector_center: [0.0; 3],
+            u_reflector_normal: [0.0, 0.0, 1.0],
+            u_reflection: 0.0,
+            u_obstacle_shapes: [0.0; 4],
+            u_obstacle0: vecmath_util::mat4_scale(1.0),
+            u_obstacle1: vecmath_util::mat4_scale(1.0),
+            u_obstacle2: vecmath_util::mat4_scale(1.0),
+            u_obstacle3: vecmath_util::mat4_scale(1.0),
+            u_gorkov: [0.0, 0.0],
+            u_wave_length: 1.0,
             u_color_map: (
                 AcousticFiledSliceViewer::generate_empty_view(factory),
                 factory.create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp)),
@@ -327,7 +977,43 @@ impl AcousticFiledSliceViewer {
                 AcousticFiledSliceViewer::generate_empty_view(factory),
                 factory.create_sampler(sampler_info),
             ),
+            u_trans_dir: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(sampler_info),
+            ),
+            u_directivity: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp)),
+            ),
             u_trans_drive: (drive_view, factory.create_sampler(sampler_info)),
+            u_approximation: 0,
+            u_group_num: 0.0,
+            u_group_center: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(sampler_info),
+            ),
+            u_group_dir: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(sampler_info),
+            ),
+            u_facet_num: 0.0,
+            u_facet_pos: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(sampler_info),
+            ),
+            u_facet_normal: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(sampler_info),
+            ),
+            u_compare: 0,
+            u_ref_drive: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(sampler_info),
+            ),
+            u_ref_facet: (
+                AcousticFiledSliceViewer::generate_empty_view(factory),
+                factory.create_sampler(sampler_info),
+            ),
             out_color,
             out_depth,
         }
@@ -369,4 +1055,85 @@ impl AcousticFiledSliceViewer {
             )
             .unwrap()
     }
+
+    fn initialize_sample_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, sample_pipe::Meta> {
+        factory
+            .create_pipeline_simple(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/slice.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/slice.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                sample_pipe::new(),
+            )
+            .unwrap()
+    }
+
+    fn initialize_texture_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, texture_pipe::Meta> {
+        factory
+            .create_pipeline_simple(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/slice_sampled.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/slice_sampled.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                texture_pipe::new(),
+            )
+            .unwrap()
+    }
+
+    fn initialize_pick_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, pick_pipe::Meta> {
+        factory
+            .create_pipeline_simple(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/slice.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/pick.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                pick_pipe::new(),
+            )
+            .unwrap()
+    }
 }
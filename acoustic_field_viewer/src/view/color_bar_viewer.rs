@@ -0,0 +1,295 @@
+/*
+ * File: color_bar_viewer.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+extern crate gfx;
+
+use gfx::{
+    format,
+    handle::Buffer,
+    state::ColorMask,
+    texture::{FilterMethod, SamplerInfo, WrapMode},
+    traits::*,
+    BlendTarget, Global, PipelineState, Slice, TextureSampler, VertexBuffer,
+};
+use gfx_device_gl::{CommandBuffer, Resources};
+use glutin::event::{Event, WindowEvent};
+use image::{imageops, Rgba, RgbaImage};
+use scarlet::color::RGBColor;
+use shader_version::{glsl::GLSL, OpenGL, Shaders};
+
+use crate::{
+    common::texture::create_texture_from_image,
+    text::TextRenderer,
+    view::{render_system, render_system::RenderSystem, shader_watcher},
+    Matrix4,
+};
+
+const FONT_SIZE: f32 = 14.0;
+const BAR_WIDTH: u32 = 16;
+const BAR_HEIGHT: u32 = 240;
+const TICK_LENGTH: u32 = 4;
+const PADDING: u32 = 8;
+/// Distance from the right edge of the view in pixels
+const MARGIN: u32 = 16;
+const PANEL: Rgba<u8> = Rgba([0, 0, 0, 160]);
+const FOREGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+gfx_vertex_struct!(Vertex {
+    a_pos: [i8; 4] = "a_pos",
+    a_tex_coord: [i8; 2] = "a_tex_coord",
+});
+
+impl Vertex {
+    fn new(pos: [i8; 3], tc: [i8; 2]) -> Vertex {
+        Vertex {
+            a_pos: [pos[0], pos[1], pos[2], 1],
+            a_tex_coord: tc,
+        }
+    }
+}
+
+gfx_pipeline!( pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
+    t_color: TextureSampler<[f32; 4]> = "t_color",
+    i_color: Global<[f32; 4]> = "i_Color",
+    out_color: BlendTarget<format::Srgba8> = ("o_Color", ColorMask::all(), gfx::preset::blend::ALPHA),
+});
+
+/// Contents of the color bar, compared to rasterize it again only on changes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorBarLegend {
+    /// Quantity and unit shown above the bar
+    pub title: String,
+    /// Labels at the positions from the bottom 0 to the top 1 of the bar
+    pub ticks: Vec<(f32, String)>,
+    pub colors: Vec<RGBColor>,
+}
+
+/// Legend of the color map drawn at the right of the view in pixels, over the scene.
+///
+/// The legend is rasterized on the CPU into a texture, so it is also in the screenshots and the
+/// videos, which do not capture the UI.
+pub struct ColorBarViewer {
+    pso: PipelineState<Resources, pipe::Meta>,
+    slice: Slice<Resources>,
+    vertex_buffer: Buffer<Resources, Vertex>,
+    pipe_data: Option<pipe::Data<Resources>>,
+    text: TextRenderer,
+    legend: Option<ColorBarLegend>,
+    image_size: (u32, u32),
+}
+
+impl ColorBarViewer {
+    pub fn new(render_sys: &RenderSystem, opengl: OpenGL) -> ColorBarViewer {
+        let mut factory = render_sys.factory.clone();
+
+        let vertex_data = vec![
+            Vertex::new([-1, -1, 0], [0, 0]),
+            Vertex::new([1, -1, 0], [1, 0]),
+            Vertex::new([1, 1, 0], [1, 1]),
+            Vertex::new([-1, 1, 0], [0, 1]),
+        ];
+        let index_data: &[u16] = &[0, 1, 2, 2, 3, 0];
+        let (vertex_buffer, slice) =
+            factory.create_vertex_buffer_with_slice(&vertex_data, index_data);
+
+        let pso = Self::initialize_shader(&mut factory, opengl.to_glsl());
+
+        ColorBarViewer {
+            pso,
+            slice,
+            vertex_buffer,
+            pipe_data: None,
+            text: TextRenderer::new(),
+            legend: None,
+            image_size: (0, 0),
+        }
+    }
+
+    /// Shows `legend`, which is rasterized again if changed
+    pub fn update(&mut self, render_sys: &mut RenderSystem, legend: ColorBarLegend) {
+        if self.legend.as_ref() == Some(&legend) {
+            return;
+        }
+        let image = self.rasterize(&legend);
+        self.image_size = image.dimensions();
+        // the first row of the texture is at the bottom of the quad
+        let image = imageops::flip_vertical(&image);
+        let factory = &mut render_sys.factory;
+        let view = match create_texture_from_image(&image, factory) {
+            Ok(view) => view,
+            Err(e) => {
+                tracing::error!("failed to create color bar texture: {}", e);
+                return;
+            }
+        };
+        let sampler_info = SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp);
+        let (width, height, _, _) = render_sys.output_color.get_dimensions();
+        self.pipe_data = Some(pipe::Data {
+            vertex_buffer: self.vertex_buffer.clone(),
+            u_model_view_proj: self.transform(width as _, height as _),
+            t_color: (view, factory.create_sampler(sampler_info)),
+            i_color: [1., 1., 1., 1.],
+            out_color: render_sys.output_color.clone(),
+        });
+        self.legend = Some(legend);
+    }
+
+    fn rasterize(&self, legend: &ColorBarLegend) -> RgbaImage {
+        let text_height = self.text.measure("0", FONT_SIZE)[1].ceil() as u32;
+        let label_width = legend
+            .ticks
+            .iter()
+            .map(|(_, label)| self.text.measure(label, FONT_SIZE)[0])
+            .fold(0.0, f32::max)
+            .ceil() as u32;
+        let title_width = self.text.measure(&legend.title, FONT_SIZE)[0].ceil() as u32;
+        // the labels at the ends stick out of the bar by half of the text
+        let bar_top = PADDING + text_height + PADDING / 2 + text_height / 2;
+        let width = (2 * PADDING + BAR_WIDTH + TICK_LENGTH + 3 + label_width)
+            .max(2 * PADDING + title_width);
+        let height = bar_top + BAR_HEIGHT + text_height / 2 + PADDING;
+        let mut image = RgbaImage::from_pixel(width, height, PANEL);
+
+        let pos = [PADDING as f32, PADDING as f32];
+        self.text.draw(
+            &mut image,
+            &legend.title,
+            pos,
+            FONT_SIZE,
+            [0.0, 0.0],
+            FOREGROUND,
+        );
+
+        if !legend.colors.is_empty() {
+            for iy in 0..BAR_HEIGHT {
+                let t = 1.0 - iy as f32 / (BAR_HEIGHT - 1) as f32;
+                let c = legend.colors[(t * (legend.colors.len() - 1) as f32) as usize];
+                let color = Rgba([
+                    (c.r * 255.0) as u8,
+                    (c.g * 255.0) as u8,
+                    (c.b * 255.0) as u8,
+                    255,
+                ]);
+                for ix in 0..BAR_WIDTH {
+                    image.put_pixel(PADDING + ix, bar_top + iy, color);
+                }
+            }
+        }
+        for (t, label) in &legend.ticks {
+            let y = bar_top + BAR_HEIGHT - 1 - (t.clamp(0.0, 1.0) * (BAR_HEIGHT - 1) as f32) as u32;
+            let x = PADDING + BAR_WIDTH;
+            for ix in 0..TICK_LENGTH {
+                image.put_pixel(x + ix, y, FOREGROUND);
+            }
+            let pos = [(x + TICK_LENGTH + 3) as f32, y as f32];
+            self.text
+                .draw(&mut image, label, pos, FONT_SIZE, [0.0, 0.5], FOREGROUND);
+        }
+        image
+    }
+
+    // Scale and translation of the quad in the normalized device coordinates, aligned to the
+    // pixels of the view of `width` by `height`
+    fn transform(&self, width: u32, height: u32) -> Matrix4 {
+        let (w, h) = (width.max(1) as f32, height.max(1) as f32);
+        let (iw, ih) = self.image_size;
+        let left = width.saturating_sub(iw + MARGIN) as f32;
+        let bottom = (height.saturating_sub(ih) / 2) as f32;
+        let (sx, sy) = (iw as f32 / w, ih as f32 / h);
+        [
+            [sx, 0., 0., 0.],
+            [0., sy, 0., 0.],
+            [0., 0., 1., 0.],
+            [2. * left / w - 1. + sx, 2. * bottom / h - 1. + sy, 0., 1.],
+        ]
+    }
+
+    /// Rebuilds the pipeline from the shaders in assets/shaders.
+    /// The current pipeline is kept on error.
+    pub fn reload_shaders(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        opengl: OpenGL,
+    ) -> Result<(), String> {
+        self.pso = shader_watcher::create_pipeline(
+            factory,
+            opengl.to_glsl(),
+            "circle.vert",
+            "circle.frag",
+            pipe::new(),
+        )?;
+        Ok(())
+    }
+
+    pub fn handle_event(&mut self, render_sys: &RenderSystem, event: &Event<()>) {
+        if let Event::WindowEvent {
+            event: WindowEvent::Resized(size),
+            ..
+        } = event
+        {
+            let transform = self.transform(size.width, size.height);
+            if let Some(pipe_data) = &mut self.pipe_data {
+                pipe_data.out_color = render_sys.output_color.clone();
+                pipe_data.u_model_view_proj = transform;
+            }
+        }
+    }
+
+    /// Draws into the current output of `render_sys` from the next frame
+    pub fn retarget(&mut self, render_sys: &RenderSystem) {
+        let (width, height, _, _) = render_sys.output_color.get_dimensions();
+        let transform = self.transform(width as _, height as _);
+        if let Some(pipe_data) = &mut self.pipe_data {
+            pipe_data.out_color = render_sys.output_color.clone();
+            pipe_data.u_model_view_proj = transform;
+        }
+    }
+
+    pub fn renderer(
+        &mut self,
+        encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
+    ) {
+        if let Some(pipe_data) = &self.pipe_data {
+            encoder.draw(&self.slice, &self.pso, pipe_data);
+        }
+    }
+
+    fn initialize_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, pipe::Meta> {
+        factory
+            .create_pipeline_simple(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/circle.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/circle.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                pipe::new(),
+            )
+            .unwrap()
+    }
+}
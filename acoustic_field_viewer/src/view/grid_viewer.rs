@@ -0,0 +1,181 @@
+/*
+ * File: grid_viewer.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+extern crate gfx;
+
+use camera_controllers::model_view_projection;
+use gfx::{
+    format, preset::depth, state::ColorMask, state::Rasterizer, traits::*, BlendTarget,
+    DepthTarget, Global, PipelineState, Primitive, Slice, VertexBuffer,
+};
+use gfx_device_gl::{CommandBuffer, Resources};
+use glutin::event::{Event, WindowEvent};
+use shader_version::{glsl::GLSL, OpenGL, Shaders};
+
+use crate::{
+    view::{render_system, render_system::RenderSystem, shader_watcher},
+    Matrix4,
+};
+
+/// Spacing of the lines in mm
+pub const GRID_SPACING: f32 = 10.0;
+/// Lines from -GRID_LINES to GRID_LINES times the spacing along each axis
+const GRID_LINES: i32 = 50;
+/// Every tenth line, i.e., 100 mm, is brighter
+const MAJOR_EVERY: i32 = 10;
+const MINOR_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 0.25];
+const MAJOR_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 0.5];
+const X_AXIS_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 0.8];
+const Y_AXIS_COLOR: [f32; 4] = [0.3, 1.0, 0.3, 0.8];
+
+gfx_vertex_struct!(Vertex {
+    a_pos: [f32; 3] = "a_pos",
+    a_color: [f32; 4] = "a_color",
+});
+
+gfx_pipeline!( pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
+    out_color: BlendTarget<format::Srgba8> = ("o_Color", ColorMask::all(), gfx::preset::blend::ALPHA),
+    out_depth: DepthTarget<format::DepthStencil> = depth::LESS_EQUAL_TEST,
+});
+
+/// Ground grid on the plane z = 0 with the lines through the origin along x in red and along y
+/// in green.
+///
+/// The grid is tested against the depth without writing it, so that it is drawn before and under
+/// the transducers on the same plane.
+pub struct GridViewer {
+    pipe_data: pipe::Data<Resources>,
+    pso: PipelineState<Resources, pipe::Meta>,
+    slice: Slice<Resources>,
+}
+
+impl GridViewer {
+    pub fn new(render_sys: &RenderSystem, opengl: OpenGL) -> GridViewer {
+        let factory = &mut render_sys.factory.clone();
+        let (vertex_buffer, slice) = factory.create_vertex_buffer_with_slice(&Self::lines(), ());
+        GridViewer {
+            pipe_data: pipe::Data {
+                vertex_buffer,
+                u_model_view_proj: [[0.; 4]; 4],
+                out_color: render_sys.output_color.clone(),
+                out_depth: render_sys.output_stencil.clone(),
+            },
+            pso: Self::initialize_shader(factory, opengl.to_glsl()),
+            slice,
+        }
+    }
+
+    fn lines() -> Vec<Vertex> {
+        let end = GRID_SPACING * GRID_LINES as f32;
+        (-GRID_LINES..=GRID_LINES)
+            .flat_map(|i| {
+                let t = GRID_SPACING * i as f32;
+                let color = |axis| match i {
+                    0 => axis,
+                    i if i % MAJOR_EVERY == 0 => MAJOR_COLOR,
+                    _ => MINOR_COLOR,
+                };
+                [
+                    ([-end, t, 0.], color(X_AXIS_COLOR)),
+                    ([end, t, 0.], color(X_AXIS_COLOR)),
+                    ([t, -end, 0.], color(Y_AXIS_COLOR)),
+                    ([t, end, 0.], color(Y_AXIS_COLOR)),
+                ]
+            })
+            .map(|(a_pos, a_color)| Vertex { a_pos, a_color })
+            .collect()
+    }
+
+    pub fn update(&mut self, view_projection: (Matrix4, Matrix4)) {
+        let (view, projection) = view_projection;
+        self.pipe_data.u_model_view_proj =
+            model_view_projection(vecmath_util::mat4_scale(1.0), view, projection);
+    }
+
+    /// Rebuilds the pipeline from the shaders in assets/shaders.
+    /// The current pipeline is kept on error.
+    pub fn reload_shaders(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        opengl: OpenGL,
+    ) -> Result<(), String> {
+        self.pso = shader_watcher::create_pipeline_of(
+            factory,
+            opengl.to_glsl(),
+            "grid.vert",
+            "grid.frag",
+            Primitive::LineList,
+            pipe::new(),
+        )?;
+        Ok(())
+    }
+
+    pub fn handle_event(&mut self, render_sys: &RenderSystem, event: &Event<()>) {
+        if let Event::WindowEvent {
+            event: WindowEvent::Resized(_),
+            ..
+        } = event
+        {
+            self.retarget(render_sys);
+        }
+    }
+
+    /// Draws into the current output of `render_sys` from the next frame
+    pub fn retarget(&mut self, render_sys: &RenderSystem) {
+        self.pipe_data.out_color = render_sys.output_color.clone();
+        self.pipe_data.out_depth = render_sys.output_stencil.clone();
+    }
+
+    pub fn renderer(
+        &mut self,
+        encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
+    ) {
+        encoder.draw(&self.slice, &self.pso, &self.pipe_data);
+    }
+
+    fn initialize_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, pipe::Meta> {
+        let set = factory
+            .create_shader_set(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/grid.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/grid.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+            )
+            .unwrap();
+        factory
+            .create_pipeline_state(
+                &set,
+                Primitive::LineList,
+                Rasterizer::new_fill(),
+                pipe::new(),
+            )
+            .unwrap()
+    }
+}
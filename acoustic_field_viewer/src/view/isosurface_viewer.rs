@@ -0,0 +1,190 @@
+/*
+ * File: isosurface_viewer.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+extern crate gfx;
+
+use camera_controllers::model_view_projection;
+use gfx::{
+    format,
+    preset::depth,
+    state::{Blend, ColorMask},
+    traits::*,
+    BlendTarget, DepthTarget, Global, PipelineState, Slice, VertexBuffer,
+};
+use gfx_device_gl::{CommandBuffer, Resources};
+use glutin::event::{Event, WindowEvent};
+use shader_version::{glsl::GLSL, OpenGL, Shaders};
+
+use crate::{
+    mesh::Mesh,
+    view::{render_system, render_system::RenderSystem, shader_watcher},
+    Matrix4,
+};
+
+gfx_vertex_struct!(Vertex {
+    a_pos: [f32; 3] = "a_pos",
+    a_normal: [f32; 3] = "a_normal",
+});
+
+fn alpha_blender() -> Blend {
+    use gfx::state::{BlendValue, Equation, Factor};
+    Blend::new(
+        Equation::Add,
+        Factor::ZeroPlus(BlendValue::SourceAlpha),
+        Factor::OneMinus(BlendValue::SourceAlpha),
+    )
+}
+
+// The depth is not written, so that the translucent triangles do not hide each other regardless
+// of their order
+gfx_pipeline!( pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
+    u_eye: Global<[f32; 3]> = "u_eye",
+    u_color: Global<[f32; 4]> = "u_color",
+    out_color: BlendTarget<format::Srgba8> = ("o_Color", ColorMask::all(), alpha_blender()),
+    out_depth: DepthTarget<format::DepthStencil> = depth::LESS_EQUAL_TEST,
+});
+
+/// Renders a triangle [Mesh], e.g., an isosurface of the field, translucent in a single color
+/// shaded by the headlight
+pub struct IsosurfaceViewer {
+    pipe_data: pipe::Data<Resources>,
+    pso: PipelineState<Resources, pipe::Meta>,
+    slice: Option<Slice<Resources>>,
+}
+
+impl IsosurfaceViewer {
+    pub fn new(renderer_sys: &RenderSystem, opengl: OpenGL) -> IsosurfaceViewer {
+        let factory = &mut renderer_sys.factory.clone();
+        let vertex_buffer = factory.create_vertex_buffer(&[Vertex {
+            a_pos: [0.0; 3],
+            a_normal: [0.0; 3],
+        }]);
+        IsosurfaceViewer {
+            pipe_data: pipe::Data {
+                vertex_buffer,
+                u_model_view_proj: [[0.; 4]; 4],
+                u_eye: [0.0; 3],
+                u_color: [1.0; 4],
+                out_color: renderer_sys.output_color.clone(),
+                out_depth: renderer_sys.output_stencil.clone(),
+            },
+            pso: Self::initialize_shader(factory, opengl.to_glsl()),
+            slice: None,
+        }
+    }
+
+    /// Uploads the triangles of `mesh` with their face normals, where the degenerate ones are
+    /// dropped, and nothing is drawn if none is left
+    pub fn set_mesh(&mut self, factory: &mut gfx_device_gl::Factory, mesh: &Mesh) {
+        let vertex_data: Vec<_> = mesh
+            .triangles
+            .iter()
+            .filter_map(|&[a, b, c]| {
+                let normal = vecmath::vec3_cross(vecmath::vec3_sub(b, a), vecmath::vec3_sub(c, a));
+                let len = vecmath::vec3_len(normal);
+                (len > 0.0).then(|| {
+                    let a_normal = vecmath::vec3_scale(normal, 1.0 / len);
+                    [a, b, c].map(|a_pos| Vertex { a_pos, a_normal })
+                })
+            })
+            .flatten()
+            .collect();
+        if vertex_data.is_empty() {
+            self.slice = None;
+            return;
+        }
+        let (vertex_buffer, slice) = factory.create_vertex_buffer_with_slice(&vertex_data, ());
+        self.pipe_data.vertex_buffer = vertex_buffer;
+        self.slice = Some(slice);
+    }
+
+    pub fn update(&mut self, view_projection: (Matrix4, Matrix4), color: [f32; 4]) {
+        let (view, projection) = view_projection;
+        let eye = vecmath::mat4_inv(view)[3];
+        self.pipe_data.u_eye = [eye[0], eye[1], eye[2]];
+        self.pipe_data.u_model_view_proj =
+            model_view_projection(vecmath_util::mat4_scale(1.0), view, projection);
+        self.pipe_data.u_color = color;
+    }
+
+    /// Rebuilds the pipeline from the shaders in assets/shaders.
+    /// The current pipeline is kept on error.
+    pub fn reload_shaders(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        opengl: OpenGL,
+    ) -> Result<(), String> {
+        self.pso = shader_watcher::create_pipeline(
+            factory,
+            opengl.to_glsl(),
+            "isosurface.vert",
+            "isosurface.frag",
+            pipe::new(),
+        )?;
+        Ok(())
+    }
+
+    pub fn handle_event(&mut self, renderer_sys: &RenderSystem, event: &Event<()>) {
+        if let Event::WindowEvent {
+            event: WindowEvent::Resized(_),
+            ..
+        } = event
+        {
+            self.retarget(renderer_sys);
+        }
+    }
+
+    /// Draws into the current output of `renderer_sys` from the next frame
+    pub fn retarget(&mut self, renderer_sys: &RenderSystem) {
+        self.pipe_data.out_color = renderer_sys.output_color.clone();
+        self.pipe_data.out_depth = renderer_sys.output_stencil.clone();
+    }
+
+    pub fn renderer(
+        &mut self,
+        encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
+    ) {
+        if let Some(slice) = &self.slice {
+            encoder.draw(slice, &self.pso, &self.pipe_data);
+        }
+    }
+
+    fn initialize_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, pipe::Meta> {
+        factory
+            .create_pipeline_simple(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/isosurface.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/isosurface.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                pipe::new(),
+            )
+            .unwrap()
+    }
+}
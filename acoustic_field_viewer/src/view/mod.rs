@@ -12,14 +12,29 @@
  */
 
 mod acoustic_field_slice_viewer;
+mod color_bar_viewer;
+mod grid_viewer;
+mod isosurface_viewer;
+mod picking;
+pub mod render_graph;
 pub mod render_system;
+mod screenshot;
 mod setting;
+mod shader_watcher;
 mod sound_source_viewer;
 mod system;
 mod update_flag;
+mod volume_viewer;
 
 pub use acoustic_field_slice_viewer::AcousticFiledSliceViewer;
-pub use setting::ViewerSettings;
+pub use color_bar_viewer::{ColorBarLegend, ColorBarViewer};
+pub use grid_viewer::{GridViewer, GRID_SPACING};
+pub use isosurface_viewer::IsosurfaceViewer;
+pub use picking::{PickId, PickingBuffer};
+pub use screenshot::ScreenshotTarget;
+pub use setting::{CompareView, FieldQuantity, SourceColoring, ViewerSettings};
+pub use shader_watcher::ShaderWatcher;
 pub use sound_source_viewer::SoundSourceViewer;
 pub use system::System;
 pub use update_flag::UpdateFlag;
+pub use volume_viewer::VolumeViewer;
@@ -0,0 +1,170 @@
+/*
+ * File: picking.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use gfx::{
+    format::{self, ChannelType, DepthStencil, Formatted, R8_G8_B8_A8},
+    handle::{Buffer, DepthStencilView, RenderTargetView, Texture},
+    memory::{Bind, Typed, Usage},
+    texture::{AaMode, Kind, RawImageInfo},
+    traits::*,
+};
+use gfx_device_gl::{CommandBuffer, Resources};
+
+use crate::view::render_system::types;
+
+const KIND_SLICE: u8 = 1;
+const KIND_SOURCE: u8 = 2;
+const MAX_INDEX: usize = 0x00FF_FFFF;
+
+/// Object under a pixel of the [PickingBuffer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickId {
+    Slice,
+    Source(usize),
+}
+
+impl PickId {
+    /// Color written to the ID buffer. The kind is stored in alpha and the 24-bit index in RGB,
+    /// so that the cleared buffer (all zero) means nothing was hit.
+    pub fn to_color(self) -> [f32; 4] {
+        let (kind, idx) = match self {
+            PickId::Slice => (KIND_SLICE, 0),
+            PickId::Source(i) => (KIND_SOURCE, i.min(MAX_INDEX)),
+        };
+        [
+            (idx & 0xFF) as f32 / 255.0,
+            ((idx >> 8) & 0xFF) as f32 / 255.0,
+            ((idx >> 16) & 0xFF) as f32 / 255.0,
+            kind as f32 / 255.0,
+        ]
+    }
+
+    pub fn from_texel(texel: [u8; 4]) -> Option<PickId> {
+        let idx = texel[0] as usize | (texel[1] as usize) << 8 | (texel[2] as usize) << 16;
+        match texel[3] {
+            KIND_SLICE => Some(PickId::Slice),
+            KIND_SOURCE => Some(PickId::Source(idx)),
+            _ => None,
+        }
+    }
+}
+
+/// Offscreen ID buffer shared by all viewers.
+///
+/// Each viewer draws its objects into it with `render_picking`, writing [PickId::to_color]
+/// instead of shading, and [PickingBuffer::read] returns the object under a pixel. The depth
+/// test is the same as on screen, so the result is what the user sees under the cursor.
+/// Overlays drawn by imgui over the scene, such as the slice gizmo, are not in the buffer.
+pub struct PickingBuffer {
+    width: u16,
+    height: u16,
+    texture: Texture<Resources, R8_G8_B8_A8>,
+    target: RenderTargetView<Resources, format::Rgba8>,
+    depth: DepthStencilView<Resources, DepthStencil>,
+    download: Buffer<Resources, [u8; 4]>,
+}
+
+impl PickingBuffer {
+    pub fn new(factory: &mut types::Factory, width: u16, height: u16) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        let texture = factory
+            .create_texture::<R8_G8_B8_A8>(
+                Kind::D2(width, height, AaMode::Single),
+                1,
+                Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
+                Usage::Data,
+                Some(ChannelType::Unorm),
+            )
+            .unwrap();
+        let target = factory
+            .view_texture_as_render_target::<format::Rgba8>(&texture, 0, None)
+            .unwrap();
+        let depth = factory
+            .create_depth_stencil_view_only::<DepthStencil>(width, height)
+            .unwrap();
+        let download = factory.create_download_buffer::<[u8; 4]>(1).unwrap();
+        Self {
+            width,
+            height,
+            texture,
+            target,
+            depth,
+            download,
+        }
+    }
+
+    /// Recreates the buffer if the size differs, to be called with the window size before rendering
+    pub fn resize(&mut self, factory: &mut types::Factory, width: u16, height: u16) {
+        if (self.width, self.height) != (width.max(1), height.max(1)) {
+            *self = Self::new(factory, width, height);
+        }
+    }
+
+    pub fn target(&self) -> &RenderTargetView<Resources, format::Rgba8> {
+        &self.target
+    }
+
+    pub fn depth(&self) -> &DepthStencilView<Resources, DepthStencil> {
+        &self.depth
+    }
+
+    pub fn clear(&self, encoder: &mut gfx::Encoder<Resources, CommandBuffer>) {
+        encoder.clear(&self.target, [0.0; 4]);
+        encoder.clear_depth(&self.depth, 1.0);
+    }
+
+    /// Returns the object at the window coordinate (`x`, `y`), top-left origin.
+    ///
+    /// This flushes `encoder` and waits for the GPU, so call it only when picking is requested.
+    pub fn read(
+        &self,
+        encoder: &mut gfx::Encoder<Resources, CommandBuffer>,
+        device: &mut types::Device,
+        factory: &mut types::Factory,
+        x: u16,
+        y: u16,
+    ) -> Option<PickId> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let info = RawImageInfo {
+            xoffset: x,
+            yoffset: self.height - 1 - y,
+            zoffset: 0,
+            width: 1,
+            height: 1,
+            depth: 1,
+            format: <format::Rgba8 as Formatted>::get_format(),
+            mipmap: 0,
+        };
+        if let Err(e) = encoder.copy_texture_to_buffer_raw(
+            self.texture.raw(),
+            None,
+            info,
+            self.download.raw(),
+            0,
+        ) {
+            tracing::error!("failed to read picking buffer: {:?}", e);
+            return None;
+        }
+        encoder.flush(device);
+        match factory.read_mapping(&self.download) {
+            Ok(reader) => PickId::from_texel(reader[0]),
+            Err(e) => {
+                tracing::error!("failed to map picking buffer: {:?}", e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+/*
+ * File: render_graph.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+/// Render targets owned by [crate::view::render_system::RenderSystem]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// Window color and depth
+    Screen,
+    /// ID and depth of [crate::view::PickingBuffer]
+    Picking,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// Transducers and other opaque objects
+    Scene,
+    Slice,
+    /// Translucent isosurfaces, tested against the depth of the scene without writing it
+    Isosurface,
+    Volume,
+    /// Markers drawn over the scene, in 3D space, and the color bar in pixels
+    Overlays,
+    Ui,
+    /// Object IDs for picking, executed only on request
+    Picking,
+}
+
+impl Pass {
+    /// Resource written by the pass
+    pub fn target(self) -> Resource {
+        match self {
+            Pass::Picking => Resource::Picking,
+            _ => Resource::Screen,
+        }
+    }
+}
+
+/// Ordered list of passes executed every frame.
+///
+/// Each resource is cleared before the first pass writing it, so a pass only draws into the
+/// targets and never manages them.
+pub struct RenderGraph {
+    passes: Vec<(Pass, bool)>,
+}
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self {
+            passes: vec![
+                (Pass::Scene, true),
+                (Pass::Slice, true),
+                (Pass::Isosurface, true),
+                (Pass::Volume, true),
+                (Pass::Overlays, true),
+                (Pass::Ui, true),
+                (Pass::Picking, true),
+            ],
+        }
+    }
+
+    pub fn is_enabled(&self, pass: Pass) -> bool {
+        self.passes.iter().any(|&(p, enabled)| p == pass && enabled)
+    }
+
+    pub fn set_enabled(&mut self, pass: Pass, enabled: bool) {
+        for (p, e) in self.passes.iter_mut() {
+            if *p == pass {
+                *e = enabled;
+            }
+        }
+    }
+
+    /// Enabled passes of this frame in execution order. [Pass::Picking] is included only if `picking`.
+    pub fn schedule(&self, picking: bool) -> Vec<Pass> {
+        self.passes
+            .iter()
+            .filter(|&&(_, enabled)| enabled)
+            .map(|&(pass, _)| pass)
+            .filter(|&pass| picking || pass != Pass::Picking)
+            .collect()
+    }
+}
+
+impl Default for RenderGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
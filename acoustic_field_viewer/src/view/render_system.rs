@@ -19,9 +19,12 @@ use imgui::Context;
 use imgui_gfx_renderer::{Renderer, Shaders};
 use old_school_gfx_glutin_ext::*;
 
-use crate::Matrix4;
+use crate::{Matrix4, Vector4};
 
-use super::ViewerSettings;
+use super::{
+    render_graph::{Pass, RenderGraph, Resource},
+    PickingBuffer, ViewerSettings,
+};
 
 type ColorFormat = gfx::format::Srgba8;
 type DepthFormat = gfx::format::DepthStencil;
@@ -31,6 +34,7 @@ pub mod types {
     pub type Device = gfx_device_gl::Device;
     pub type Factory = gfx_device_gl::Factory;
     pub type Resources = gfx_device_gl::Resources;
+    pub type Encoder = gfx::Encoder<Resources, gfx_device_gl::CommandBuffer>;
 }
 
 pub struct RenderSystem {
@@ -41,6 +45,8 @@ pub struct RenderSystem {
     pub output_color: gfx::handle::RenderTargetView<types::Resources, ColorFormat>,
     pub output_stencil: gfx::handle::DepthStencilView<types::Resources, DepthFormat>,
     pub camera: Camera<f32>,
+    pub graph: RenderGraph,
+    pub picking: PickingBuffer,
 }
 
 impl RenderSystem {
@@ -76,6 +82,9 @@ impl RenderSystem {
             FirstPerson::new([0., -500.0, 120.0], FirstPersonSettings::keyboard_wasd()).camera(0.);
         camera.set_yaw_pitch(0., -std::f32::consts::PI / 2.0);
 
+        let size = windowed_context.window().inner_size();
+        let picking = PickingBuffer::new(&mut factory, size.width as _, size.height as _);
+
         Self {
             renderer,
             windowed_context,
@@ -84,6 +93,8 @@ impl RenderSystem {
             output_color,
             output_stencil,
             camera,
+            graph: RenderGraph::new(),
+            picking,
         }
     }
     pub fn window(&self) -> &glutin::window::Window {
@@ -92,6 +103,37 @@ impl RenderSystem {
     pub fn update_views(&mut self) {
         self.windowed_context
             .update_gfx(&mut self.output_color, &mut self.output_stencil);
+        let size = self.window().inner_size();
+        self.picking
+            .resize(&mut self.factory, size.width as _, size.height as _);
+    }
+
+    /// Executes the passes of [RenderGraph] in order, calling `draw` for each of them.
+    /// The screen is cleared with `background`, and [Pass::Picking] runs only if `picking`.
+    pub fn render<F>(
+        &mut self,
+        encoder: &mut types::Encoder,
+        background: Vector4,
+        picking: bool,
+        mut draw: F,
+    ) where
+        F: FnMut(Pass, &mut RenderSystem, &mut types::Encoder),
+    {
+        let mut cleared = Vec::new();
+        for pass in self.graph.schedule(picking) {
+            let target = pass.target();
+            if !cleared.contains(&target) {
+                match target {
+                    Resource::Screen => {
+                        encoder.clear(&self.output_color, background);
+                        encoder.clear_depth(&self.output_stencil, 1.0);
+                    }
+                    Resource::Picking => self.picking.clear(encoder),
+                }
+                cleared.push(target);
+            }
+            draw(pass, self, encoder);
+        }
     }
     pub fn swap_buffers(&mut self) {
         self.windowed_context.swap_buffers().unwrap();
@@ -0,0 +1,111 @@
+/*
+ * File: screenshot.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use gfx::{
+    format::{self, ChannelType, DepthStencil, Formatted, R8_G8_B8_A8},
+    handle::{Buffer, DepthStencilView, RenderTargetView, Texture},
+    memory::{Bind, Typed, Usage},
+    texture::{AaMode, Kind, RawImageInfo},
+    traits::*,
+};
+use gfx_device_gl::{CommandBuffer, Resources};
+
+use crate::view::render_system::types;
+
+/// Offscreen color and depth targets into which the scene is rendered for a screenshot.
+///
+/// The targets replace [crate::view::render_system::RenderSystem::output_color] and
+/// [crate::view::render_system::RenderSystem::output_stencil] while the passes are drawn, and
+/// [ScreenshotTarget::read] copies the color back, so neither the UI nor the other windows over
+/// the screen are captured.
+pub struct ScreenshotTarget {
+    width: u16,
+    height: u16,
+    texture: Texture<Resources, R8_G8_B8_A8>,
+    pub color: RenderTargetView<Resources, format::Srgba8>,
+    pub depth: DepthStencilView<Resources, DepthStencil>,
+    download: Buffer<Resources, [u8; 4]>,
+}
+
+impl ScreenshotTarget {
+    pub fn new(factory: &mut types::Factory, width: u16, height: u16) -> Result<Self, String> {
+        let width = width.max(1);
+        let height = height.max(1);
+        let texture = factory
+            .create_texture::<R8_G8_B8_A8>(
+                Kind::D2(width, height, AaMode::Single),
+                1,
+                Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
+                Usage::Data,
+                Some(ChannelType::Srgb),
+            )
+            .map_err(|e| format!("{:?}", e))?;
+        let color = factory
+            .view_texture_as_render_target::<format::Srgba8>(&texture, 0, None)
+            .map_err(|e| format!("{:?}", e))?;
+        let depth = factory
+            .create_depth_stencil_view_only::<DepthStencil>(width, height)
+            .map_err(|e| format!("{:?}", e))?;
+        let download = factory
+            .create_download_buffer::<[u8; 4]>(width as usize * height as usize)
+            .map_err(|e| format!("{:?}", e))?;
+        Ok(Self {
+            width,
+            height,
+            texture,
+            color,
+            depth,
+            download,
+        })
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Returns the RGBA pixels in sRGB row by row from the top.
+    ///
+    /// This flushes `encoder` and waits for the GPU.
+    pub fn read(
+        &self,
+        encoder: &mut gfx::Encoder<Resources, CommandBuffer>,
+        device: &mut types::Device,
+        factory: &mut types::Factory,
+    ) -> Result<Vec<u8>, String> {
+        let info = RawImageInfo {
+            xoffset: 0,
+            yoffset: 0,
+            zoffset: 0,
+            width: self.width,
+            height: self.height,
+            depth: 1,
+            format: <format::Srgba8 as Formatted>::get_format(),
+            mipmap: 0,
+        };
+        encoder
+            .copy_texture_to_buffer_raw(self.texture.raw(), None, info, self.download.raw(), 0)
+            .map_err(|e| format!("{:?}", e))?;
+        encoder.flush(device);
+        let reader = factory
+            .read_mapping(&self.download)
+            .map_err(|e| format!("{:?}", e))?;
+        // the rows of the texture are from the bottom
+        Ok(reader
+            .chunks_exact(self.width as usize)
+            .rev()
+            .flatten()
+            .flatten()
+            .copied()
+            .collect())
+    }
+}
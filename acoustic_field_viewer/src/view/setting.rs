@@ -13,20 +13,221 @@
 
 use std::f32::consts::PI;
 
-use crate::{Vector3, Vector4};
+use crate::{
+    calibration::{PressureCalibration, PressureUnit},
+    color_map::ColorMapKind,
+    directivity::Directivity,
+    field::FieldApproximation,
+    levitation::Particle,
+    medium::Medium,
+    obstacle::{Obstacle, MAX_OBSTACLES},
+    reflector::Reflector,
+    Vector3, Vector4,
+};
 use autd3_core::hardware_defined::TRANS_SPACING_MM;
 use serde::{Deserialize, Serialize};
 
+/// Diameter of the T4010A1 mounted on AUTD3 in mm
+const TRANS_DIAMETER_MM: f32 = 10.0;
+
+/// Quantity of the complex pressure `p` shown on the slice
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldQuantity {
+    /// |p|
+    #[default]
+    Amplitude,
+    /// arg(p), mapped from -pi to pi over the color map
+    Phase,
+    /// Re(p e^{iωt}), with 0 at the middle of the color map
+    RealPart,
+    /// Time-averaged intensity, proportional to |p|^2
+    Intensity,
+    /// Radiation pressure on a perfectly reflecting surface, |p|^2 / (ρc^2), see
+    /// [crate::medium::Medium::radiation_pressure]
+    RadiationPressure,
+    /// Gor'kov potential of [ViewerSettings::particle], with 0 at the middle of the color map,
+    /// whose minima are the traps
+    GorkovPotential,
+    /// Magnitude of the gradient of the Gor'kov potential, i.e., the force on the particle
+    GorkovForce,
+}
+
+impl FieldQuantity {
+    pub const ALL: [FieldQuantity; 7] = [
+        FieldQuantity::Amplitude,
+        FieldQuantity::Phase,
+        FieldQuantity::RealPart,
+        FieldQuantity::Intensity,
+        FieldQuantity::RadiationPressure,
+        FieldQuantity::GorkovPotential,
+        FieldQuantity::GorkovForce,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldQuantity::Amplitude => "Amplitude",
+            FieldQuantity::Phase => "Phase",
+            FieldQuantity::RealPart => "Real part",
+            FieldQuantity::Intensity => "Intensity",
+            FieldQuantity::RadiationPressure => "Radiation pressure",
+            FieldQuantity::GorkovPotential => "Gor'kov potential",
+            FieldQuantity::GorkovForce => "Gor'kov force",
+        }
+    }
+
+    /// Non-negative quantities, to which [ViewerSettings::dynamic_range] applies
+    pub fn is_magnitude(&self) -> bool {
+        matches!(
+            self,
+            FieldQuantity::Amplitude
+                | FieldQuantity::Intensity
+                | FieldQuantity::RadiationPressure
+                | FieldQuantity::GorkovForce
+        )
+    }
+
+    /// Index passed to the slice shader as `u_quantity`
+    pub fn index(&self) -> i32 {
+        match self {
+            FieldQuantity::Amplitude => 0,
+            FieldQuantity::Phase => 1,
+            FieldQuantity::RealPart => 2,
+            FieldQuantity::Intensity => 3,
+            FieldQuantity::RadiationPressure => 4,
+            FieldQuantity::GorkovPotential => 5,
+            FieldQuantity::GorkovForce => 6,
+        }
+    }
+}
+
+/// Drive of which the slice shows the field, where the reference is a drive frozen for the
+/// comparison, see [crate::view::AcousticFiledSliceViewer::set_reference]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompareView {
+    /// The current drive, i.e., B
+    #[default]
+    Current,
+    /// The reference drive, i.e., A
+    Reference,
+    /// B minus A of [ViewerSettings::field_quantity], with 0 at the middle of the color map, and
+    /// the phase of B relative to A for [FieldQuantity::Phase]
+    Difference,
+}
+
+impl CompareView {
+    pub const ALL: [CompareView; 3] = [
+        CompareView::Current,
+        CompareView::Reference,
+        CompareView::Difference,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompareView::Current => "B (current)",
+            CompareView::Reference => "A (reference)",
+            CompareView::Difference => "B - A",
+        }
+    }
+
+    /// Index passed to the slice shader as `u_compare`
+    pub fn index(&self) -> i32 {
+        match self {
+            CompareView::Current => 0,
+            CompareView::Reference => 1,
+            CompareView::Difference => 2,
+        }
+    }
+}
+
+/// Color of the markers of the transducers
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SourceColoring {
+    /// Hue of the phase and brightness of the amplitude
+    #[default]
+    PhaseAmplitude,
+    /// Hue of the phase at the full brightness, so that the array is seen as a phase plate
+    Phase,
+    /// [ViewerSettings::color_map] of the amplitude from 0 to 1
+    Amplitude,
+}
+
+impl SourceColoring {
+    pub const ALL: [SourceColoring; 3] = [
+        SourceColoring::PhaseAmplitude,
+        SourceColoring::Phase,
+        SourceColoring::Amplitude,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceColoring::PhaseAmplitude => "Phase and amplitude",
+            SourceColoring::Phase => "Phase",
+            SourceColoring::Amplitude => "Amplitude",
+        }
+    }
+}
+
+/// The fields missing on deserialization are those of [ViewerSettings::default]
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
 pub struct ViewerSettings {
     pub frequency: f32,
+    /// Diameter of the transducers drawn in mm
     pub source_size: f32,
     pub wave_length: f32,
+    /// Air temperature in Celsius from which the sound speed is computed,
+    /// or `None` if the sound speed is set directly
+    pub temperature: Option<f32>,
+    /// Relative humidity in % from which the attenuation of `medium` is computed as the absorption
+    /// of air at the frequency and the temperature, 20 Celsius if not set, or `None` if the
+    /// attenuation is set directly, see [Medium::air_absorption]
+    #[serde(default)]
+    pub humidity: Option<f32>,
+    /// The sound speed of `medium` is kept equal to [ViewerSettings::sound_speed]
+    #[serde(default)]
+    pub medium: Medium,
+    #[serde(default)]
+    pub directivity: Directivity,
+    #[serde(default)]
+    pub approximation: FieldApproximation,
+    #[serde(default)]
+    pub reflector: Reflector,
+    #[serde(default)]
+    pub obstacles: [Obstacle; MAX_OBSTACLES],
+    #[serde(default)]
+    pub field_quantity: FieldQuantity,
+    #[serde(default)]
+    pub compare: CompareView,
+    /// Particle for [FieldQuantity::GorkovPotential] and [FieldQuantity::GorkovForce]
+    #[serde(default)]
+    pub particle: Particle,
+    #[serde(default)]
+    pub calibration: PressureCalibration,
+    /// Unit of the pressure shown in the UI, which does not change the slice
+    #[serde(default)]
+    pub pressure_unit: PressureUnit,
+    #[serde(default)]
+    pub color_map: ColorMapKind,
+    /// Color map of the signed quantities, see [ViewerSettings::is_signed]
+    #[serde(default = "default_diverging_color_map")]
+    pub diverging_color_map: ColorMapKind,
     pub color_scale: f32,
+    pub gamma: f32,
+    /// Dynamic range in dB of the logarithmic color scale below [ViewerSettings::color_scale], or
+    /// `None` for the linear scale with [ViewerSettings::gamma]. Only for
+    /// [FieldQuantity::is_magnitude].
+    #[serde(default)]
+    pub dynamic_range: Option<f32>,
     pub slice_alpha: f32,
     pub source_alpha: f32,
+    #[serde(default)]
+    pub source_coloring: SourceColoring,
     pub slice_width: i32,
     pub slice_height: i32,
+    /// Density in points per mm of the grid at which the slice is sampled, one texel per point,
+    /// or `None` to evaluate the field at each pixel on screen
+    #[serde(default)]
+    pub slice_sampling: Option<f32>,
     pub slice_pos: Vector4,
     pub slice_angle: Vector3,
     pub camera_pos: Vector3,
@@ -37,23 +238,152 @@ pub struct ViewerSettings {
     pub background: Vector4,
 }
 
+fn default_diverging_color_map() -> ColorMapKind {
+    ViewerSettings::default().diverging_color_map
+}
+
 impl ViewerSettings {
     pub fn new() -> ViewerSettings {
         Self::default()
     }
+
+    /// Whether the slice shows a signed quantity with 0 at the middle of the color map, i.e.,
+    /// [FieldQuantity::RealPart], [FieldQuantity::GorkovPotential] or [CompareView::Difference]
+    pub fn is_signed(&self) -> bool {
+        self.compare == CompareView::Difference
+            || matches!(
+                self.field_quantity,
+                FieldQuantity::RealPart | FieldQuantity::GorkovPotential
+            )
+    }
+
+    /// [ViewerSettings::diverging_color_map] for the signed quantities, otherwise
+    /// [ViewerSettings::color_map]
+    pub fn active_color_map(&self) -> ColorMapKind {
+        if self.is_signed() {
+            self.diverging_color_map
+        } else {
+            self.color_map
+        }
+    }
+
+    /// Sound speed in mm/s implied by the current frequency and wavelength
+    pub fn sound_speed(&self) -> f32 {
+        self.frequency * self.wave_length
+    }
+
+    /// Sound speed in mm/s of air at `temperature` in Celsius
+    pub fn sound_speed_in_air(temperature: f32) -> f32 {
+        331.3e3 * (1.0 + temperature / 273.15).max(0.0).sqrt()
+    }
+
+    /// Changes the sound speed in mm/s, e.g., for media other than air,
+    /// and recomputes the wavelength
+    pub fn set_sound_speed(&mut self, sound_speed: f32) {
+        if sound_speed <= 0.0 {
+            return;
+        }
+        self.wave_length = sound_speed / self.frequency;
+        self.medium.sound_speed = sound_speed;
+        self.temperature = None;
+        self.update_air_absorption();
+    }
+
+    /// Changes the medium and recomputes the wavelength from its sound speed, with the attenuation
+    /// of the medium
+    pub fn set_medium(&mut self, medium: Medium) {
+        self.medium = medium;
+        self.humidity = None;
+        self.set_sound_speed(medium.sound_speed);
+    }
+
+    /// Computes the attenuation of air from the relative `humidity` in %, or keeps the current
+    /// attenuation from then on if `None`
+    pub fn set_humidity(&mut self, humidity: Option<f32>) {
+        self.humidity = humidity.map(|h| h.clamp(0.0, 100.0));
+        self.update_air_absorption();
+    }
+
+    fn update_air_absorption(&mut self) {
+        if let Some(humidity) = self.humidity {
+            let temperature = self.temperature.unwrap_or(20.0);
+            self.medium.attenuation = Medium::air_absorption(self.frequency, temperature, humidity);
+        }
+    }
+
+    /// Sets the sound speed to that of air at `temperature` in Celsius
+    pub fn set_temperature(&mut self, temperature: f32) {
+        let sound_speed = Self::sound_speed_in_air(temperature);
+        if sound_speed <= 0.0 {
+            return;
+        }
+        self.wave_length = sound_speed / self.frequency;
+        self.medium.sound_speed = sound_speed;
+        self.temperature = Some(temperature);
+        self.update_air_absorption();
+    }
+
+    /// Changes the frequency in Hz and recomputes the wavelength, keeping the sound speed
+    pub fn set_frequency(&mut self, frequency: f32) {
+        if frequency <= 0.0 {
+            return;
+        }
+        self.wave_length = self.sound_speed() / frequency;
+        self.frequency = frequency;
+        self.update_air_absorption();
+    }
+
+    /// High-contrast preset for washed-out projectors. Poses and the field parameters are kept.
+    pub fn projector(&self) -> Self {
+        Self {
+            gamma: 2.2,
+            source_size: self.source_size * 1.25,
+            source_alpha: 1.0,
+            slice_alpha: 1.0,
+            background: [0.0, 0.0, 0.0, 1.0],
+            ..*self
+        }
+    }
+
+    /// Restores the display parameters changed by [ViewerSettings::projector] from `other`
+    pub fn restore_display(&mut self, other: &Self) {
+        self.gamma = other.gamma;
+        self.source_size = other.source_size;
+        self.source_alpha = other.source_alpha;
+        self.slice_alpha = other.slice_alpha;
+        self.background = other.background;
+    }
 }
 
 impl Default for ViewerSettings {
     fn default() -> Self {
         ViewerSettings {
             frequency: autd3_core::hardware_defined::ULTRASOUND_FREQUENCY as _,
-            source_size: autd3_core::hardware_defined::TRANS_SPACING_MM as _,
+            source_size: TRANS_DIAMETER_MM,
+            field_quantity: FieldQuantity::Amplitude,
+            compare: CompareView::Current,
             color_scale: 2.0,
+            gamma: 1.0,
+            dynamic_range: None,
             slice_alpha: 0.95,
             wave_length: 8.5,
+            temperature: None,
+            humidity: None,
+            medium: Medium::air(),
+            directivity: Directivity::Monopole,
+            approximation: FieldApproximation::Exact,
+            reflector: Reflector::new(),
+            obstacles: [Obstacle::new(); MAX_OBSTACLES],
+            particle: Particle::polystyrene(),
+            calibration: PressureCalibration::t4010a1(),
+            pressure_unit: PressureUnit::Arbitrary,
+            color_map: ColorMapKind::Inferno,
+            diverging_color_map: ColorMapKind::Coolwarm,
             slice_width: 400,
             slice_height: 300,
+            slice_sampling: None,
             source_alpha: 1.0,
+            source_coloring: SourceColoring::PhaseAmplitude,
             slice_pos: [
                 TRANS_SPACING_MM as f32 * 8.5,
                 TRANS_SPACING_MM as f32 * 6.5,
@@ -0,0 +1,131 @@
+/*
+ * File: shader_watcher.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use gfx::{pso::PipelineInit, state::Rasterizer, traits::*, PipelineState, Primitive};
+use gfx_device_gl::Resources;
+use shader_version::{glsl::GLSL, Shaders};
+
+use crate::view::render_system::types;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn shader_dir() -> Option<PathBuf> {
+    find_folder::Search::ParentsThenKids(3, 3)
+        .for_folder("assets")
+        .ok()
+        .map(|assets| assets.join("shaders"))
+}
+
+/// Polls the modification times of the shaders in assets/shaders, for hot-reloading them
+pub struct ShaderWatcher {
+    dir: PathBuf,
+    stamps: Vec<(PathBuf, SystemTime)>,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    /// Returns `None` if the assets folder is not found
+    pub fn new() -> Option<Self> {
+        let dir = shader_dir()?;
+        let stamps = Self::scan(&dir);
+        tracing::info!("watching shaders in {}", dir.display());
+        Some(Self {
+            dir,
+            stamps,
+            last_poll: Instant::now(),
+        })
+    }
+
+    fn scan(dir: &std::path::Path) -> Vec<(PathBuf, SystemTime)> {
+        let mut stamps: Vec<_> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| {
+                        let modified = e.metadata().and_then(|m| m.modified()).ok()?;
+                        Some((e.path(), modified))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        stamps.sort();
+        stamps
+    }
+
+    /// Returns true if any shader has been modified, added or removed since the last change
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        let stamps = Self::scan(&self.dir);
+        if stamps == self.stamps {
+            return false;
+        }
+        self.stamps = stamps;
+        true
+    }
+}
+
+/// Reads the shader `name` from assets/shaders at runtime instead of the one built in
+pub(crate) fn load_shader(name: &str) -> Result<String, String> {
+    let path = shader_dir()
+        .ok_or_else(|| "assets folder is not found".to_owned())?
+        .join(name);
+    std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Compiles a pipeline from sources loaded with [load_shader], returning the log on error
+pub(crate) fn create_pipeline<I: PipelineInit>(
+    factory: &mut types::Factory,
+    version: GLSL,
+    vs: &str,
+    fs: &str,
+    init: I,
+) -> Result<PipelineState<Resources, I::Meta>, String> {
+    create_pipeline_of(factory, version, vs, fs, Primitive::TriangleList, init)
+}
+
+/// [create_pipeline] of another primitive, e.g., lines
+pub(crate) fn create_pipeline_of<I: PipelineInit>(
+    factory: &mut types::Factory,
+    version: GLSL,
+    vs: &str,
+    fs: &str,
+    primitive: Primitive,
+    init: I,
+) -> Result<PipelineState<Resources, I::Meta>, String> {
+    let vs_src = load_shader(vs)?;
+    let fs_src = load_shader(fs)?;
+    let vs_src = Shaders::new()
+        .set(GLSL::V4_50, &vs_src)
+        .get(version)
+        .ok_or_else(|| format!("{}: unsupported GLSL version", vs))?
+        .to_owned();
+    let fs_src = Shaders::new()
+        .set(GLSL::V4_50, &fs_src)
+        .get(version)
+        .ok_or_else(|| format!("{}: unsupported GLSL version", fs))?
+        .to_owned();
+    let set = factory
+        .create_shader_set(vs_src.as_bytes(), fs_src.as_bytes())
+        .map_err(|e| format!("{} + {}: {}", vs, fs, e))?;
+    factory
+        .create_pipeline_state(&set, primitive, Rasterizer::new_fill(), init)
+        .map_err(|e| format!("{} + {}: {}", vs, fs, e))
+}
@@ -15,99 +15,161 @@ extern crate gfx;
 
 use std::f32::consts::PI;
 
-use camera_controllers::model_view_projection;
 use gfx::{
-    format,
-    handle::{Buffer, DepthStencilView, RenderTargetView, ShaderResourceView},
-    preset::depth,
-    state::ColorMask,
-    texture::{FilterMethod, SamplerInfo, WrapMode},
-    traits::*,
-    BlendTarget, DepthTarget, Global, PipelineState, Slice, TextureSampler, VertexBuffer,
+    format, handle::Buffer, preset::depth, state::ColorMask, traits::*, BlendTarget, DepthTarget,
+    Global, InstanceBuffer, PipelineState, RenderTarget, Slice, VertexBuffer,
 };
 use gfx_device_gl::{CommandBuffer, Resources};
 use glutin::event::{Event, WindowEvent};
+use scarlet::color::RGBColor;
 use shader_version::{glsl::GLSL, OpenGL, Shaders};
 
 use crate::{
     common::coloring_method::{coloring_hsv, ColoringMethod},
-    common::texture::create_texture_resource,
     sound_source::SoundSource,
-    view::{render_system, render_system::RenderSystem, UpdateFlag, ViewerSettings},
+    view::{
+        render_system, render_system::RenderSystem, shader_watcher, PickId, PickingBuffer,
+        SourceColoring, UpdateFlag, ViewerSettings,
+    },
     Matrix4,
 };
 
+/// Segments of the circumference of the disc
+const DISC_SEGMENTS: usize = 32;
+/// Depth of the housing behind the radiating surface relative to the diameter, 7 mm of the
+/// T4010A1 of 10 mm in diameter
+const HOUSING_DEPTH: f32 = 0.7;
+
 gfx_vertex_struct!(Vertex {
-    a_pos: [i8; 4] = "a_pos",
-    a_tex_coord: [i8; 2] = "a_tex_coord",
+    a_pos: [f32; 3] = "a_pos",
+    a_normal: [f32; 3] = "a_normal",
 });
 
-impl Vertex {
-    fn new(pos: [i8; 3], tc: [i8; 2]) -> Vertex {
-        Vertex {
-            a_pos: [pos[0], pos[1], pos[2], 1],
-            a_tex_coord: tc,
-        }
-    }
-}
+gfx_vertex_struct!(Instance {
+    i_model0: [f32; 4] = "i_model0",
+    i_model1: [f32; 4] = "i_model1",
+    i_model2: [f32; 4] = "i_model2",
+    i_model3: [f32; 4] = "i_model3",
+    i_color: [f32; 4] = "i_color",
+    i_id: [f32; 4] = "i_id",
+});
 
 gfx_pipeline!( pipe {
     vertex_buffer: VertexBuffer<Vertex> = (),
-    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
-    t_color: TextureSampler<[f32; 4]> = "t_color",
-    i_color: Global<[f32; 4]> = "i_Color",
+    instances: InstanceBuffer<Instance> = (),
+    u_view_proj: Global<[[f32; 4]; 4]> = "u_view_proj",
+    u_eye: Global<[f32; 3]> = "u_eye",
     out_color: BlendTarget<format::Srgba8> = ("o_Color", ColorMask::all(), gfx::preset::blend::ALPHA),
     out_depth: DepthTarget<format::DepthStencil> = depth::LESS_EQUAL_WRITE,
 });
 
+gfx_pipeline!( pick_pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    instances: InstanceBuffer<Instance> = (),
+    u_view_proj: Global<[[f32; 4]; 4]> = "u_view_proj",
+    out_id: RenderTarget<format::Rgba8> = "o_Id",
+    out_depth: DepthTarget<format::DepthStencil> = depth::LESS_EQUAL_WRITE,
+});
+
+/// Renders each transducer as a disc of [ViewerSettings::source_size] in diameter at its
+/// position, facing [SoundSource::dir], with the housing behind it.
+///
+/// All the transducers are drawn in one instanced draw call.
 pub struct SoundSourceViewer {
-    pipe_data_list: Vec<pipe::Data<Resources>>,
+    pipe_data: Option<pipe::Data<Resources>>,
     pso: PipelineState<Resources, pipe::Meta>,
+    pick_pso: PipelineState<Resources, pick_pipe::Meta>,
     slice: Slice<Resources>,
-    models: Vec<Matrix4>,
     vertex_buffer: Buffer<Resources, Vertex>,
-    view: ShaderResourceView<Resources, [f32; 4]>,
+    instances: Vec<Instance>,
     coloring_method: ColoringMethod,
+    // of the amplitude for SourceColoring::Amplitude
+    color_map: Vec<RGBColor>,
 }
 
 impl SoundSourceViewer {
     pub fn new(render_sys: &RenderSystem, opengl: OpenGL) -> SoundSourceViewer {
         let mut factory = render_sys.factory.clone();
 
-        let vertex_data = vec![
-            Vertex::new([-1, -1, 0], [0, 0]),
-            Vertex::new([1, -1, 0], [1, 0]),
-            Vertex::new([1, 1, 0], [1, 1]),
-            Vertex::new([-1, 1, 0], [0, 1]),
-        ];
-        let index_data: &[u16] = &[0, 1, 2, 2, 3, 0];
         let (vertex_buffer, slice) =
-            factory.create_vertex_buffer_with_slice(&vertex_data, index_data);
+            factory.create_vertex_buffer_with_slice(&Self::transducer_mesh(), ());
 
         let glsl = opengl.to_glsl();
         let pso = Self::initialize_shader(&mut factory, glsl);
-
-        let assets = find_folder::Search::ParentsThenKids(3, 3)
-            .for_folder("assets")
-            .unwrap();
-        let view =
-            create_texture_resource(assets.join("textures/circle.png"), &mut factory).unwrap();
+        let pick_pso = Self::initialize_pick_shader(&mut factory, glsl);
 
         SoundSourceViewer {
-            pipe_data_list: vec![],
+            pipe_data: None,
             pso,
+            pick_pso,
             slice,
-            models: vec![],
             vertex_buffer,
-            view,
+            instances: vec![],
             coloring_method: coloring_hsv,
+            color_map: Vec::new(),
+        }
+    }
+
+    // Cylinder of the unit diameter, whose front face is the radiating surface at the origin
+    // facing +z
+    fn transducer_mesh() -> Vec<Vertex> {
+        let depth = -HOUSING_DEPTH;
+        let rim: Vec<_> = (0..=DISC_SEGMENTS)
+            .map(|i| {
+                let t = 2.0 * PI * i as f32 / DISC_SEGMENTS as f32;
+                [0.5 * t.cos(), 0.5 * t.sin()]
+            })
+            .collect();
+        let v = |a_pos, a_normal| Vertex { a_pos, a_normal };
+        let mut vertices = Vec::with_capacity(DISC_SEGMENTS * 12);
+        for w in rim.windows(2) {
+            let ([x0, y0], [x1, y1]) = (w[0], w[1]);
+            vertices.extend([
+                v([0., 0., 0.], [0., 0., 1.]),
+                v([x0, y0, 0.], [0., 0., 1.]),
+                v([x1, y1, 0.], [0., 0., 1.]),
+                v([0., 0., depth], [0., 0., -1.]),
+                v([x1, y1, depth], [0., 0., -1.]),
+                v([x0, y0, depth], [0., 0., -1.]),
+            ]);
+            let (n0, n1) = ([2. * x0, 2. * y0, 0.], [2. * x1, 2. * y1, 0.]);
+            vertices.extend([
+                v([x0, y0, 0.], n0),
+                v([x0, y0, depth], n0),
+                v([x1, y1, depth], n1),
+                v([x1, y1, depth], n1),
+                v([x1, y1, 0.], n1),
+                v([x0, y0, 0.], n0),
+            ]);
         }
+        vertices
     }
 
-    fn init_model(&mut self, settings: &ViewerSettings, sources: &[SoundSource]) {
-        let len = sources.len();
-        let s = 0.5 * settings.source_size;
-        self.models = vec![vecmath_util::mat4_scale(s); len];
+    /// Colors of [SourceColoring::Amplitude], applied with [UpdateFlag::UPDATE_COLOR_MAP]
+    pub fn set_color_map(&mut self, colors: &[RGBColor]) {
+        self.color_map = colors.to_vec();
+    }
+
+    fn source_color(&self, source: &SoundSource, settings: &ViewerSettings) -> [f32; 4] {
+        let alpha = settings.source_alpha;
+        match settings.source_coloring {
+            SourceColoring::PhaseAmplitude => {
+                (self.coloring_method)(source.phase / (2.0 * PI), source.amp, alpha)
+            }
+            SourceColoring::Phase => (self.coloring_method)(source.phase / (2.0 * PI), 1.0, alpha),
+            SourceColoring::Amplitude if !self.color_map.is_empty() => {
+                let t = source.amp.clamp(0.0, 1.0);
+                let c = self.color_map[(t * (self.color_map.len() - 1) as f32) as usize];
+                [c.r as f32, c.g as f32, c.b as f32, alpha]
+            }
+            SourceColoring::Amplitude => [source.amp, source.amp, source.amp, alpha],
+        }
+    }
+
+    fn model(settings: &ViewerSettings, source: &SoundSource) -> Matrix4 {
+        let rot = vecmath_util::quaternion_to([0., 0., 1.], source.dir);
+        let ts = vecmath_util::mat4_ts(source.pos, settings.source_size);
+        vecmath::col_mat4_mul(ts, vecmath_util::mat4_rot(rot))
     }
 
     pub fn update(
@@ -118,65 +180,121 @@ impl SoundSourceViewer {
         sources: &[SoundSource],
         update_flag: UpdateFlag,
     ) {
+        let mut changed = false;
         if update_flag.contains(UpdateFlag::INIT_SOURCE) {
-            let factory = &mut render_sys.factory;
-            self.pipe_data_list = Self::initialize_pipe_data(
-                factory,
-                self.vertex_buffer.clone(),
-                self.view.clone(),
-                render_sys.output_color.clone(),
-                render_sys.output_stencil.clone(),
-                sources,
-            );
-            self.init_model(settings, sources);
-            for (i, source) in sources.iter().enumerate() {
-                self.models[i][3][0] = source.pos[0];
-                self.models[i][3][1] = source.pos[1];
-                self.models[i][3][2] = source.pos[2];
-                let rot = vecmath_util::quaternion_to([0., 0., 1.], source.dir);
-                let rotm = vecmath_util::mat4_rot(rot);
-                self.models[i] = vecmath::col_mat4_mul(self.models[i], rotm);
-            }
-            for i in 0..self.pipe_data_list.len() {
-                self.pipe_data_list[i].u_model_view_proj =
-                    model_view_projection(self.models[i], view_projection.0, view_projection.1);
-            }
+            tracing::debug!("init {} sound sources", sources.len());
+            self.instances = sources
+                .iter()
+                .enumerate()
+                .map(|(i, source)| {
+                    let [i_model0, i_model1, i_model2, i_model3] = Self::model(settings, source);
+                    Instance {
+                        i_model0,
+                        i_model1,
+                        i_model2,
+                        i_model3,
+                        i_color: self.source_color(source, settings),
+                        i_id: PickId::Source(i).to_color(),
+                    }
+                })
+                .collect();
+            changed = true;
         }
 
-        if update_flag.contains(UpdateFlag::UPDATE_CAMERA_POS) {
-            for i in 0..self.pipe_data_list.len() {
-                self.pipe_data_list[i].u_model_view_proj =
-                    model_view_projection(self.models[i], view_projection.0, view_projection.1);
+        if update_flag.intersects(UpdateFlag::UPDATE_SOURCE_DRIVE | UpdateFlag::UPDATE_COLOR_MAP) {
+            let colors: Vec<_> = sources
+                .iter()
+                .map(|source| self.source_color(source, settings))
+                .collect();
+            for (instance, color) in self.instances.iter_mut().zip(colors) {
+                instance.i_color = color;
             }
+            changed = true;
         }
 
-        if update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE) {
-            for (i, source) in sources.iter().enumerate() {
-                self.pipe_data_list[i].i_color = (self.coloring_method)(
-                    source.phase / (2.0 * PI),
-                    source.amp,
-                    settings.source_alpha,
-                );
+        if update_flag.contains(UpdateFlag::UPDATE_SOURCE_ALPHA) {
+            for instance in self.instances.iter_mut() {
+                instance.i_color[3] = settings.source_alpha;
             }
+            changed = true;
         }
 
-        if update_flag.contains(UpdateFlag::UPDATE_SOURCE_ALPHA) {
-            for pipe_data in self.pipe_data_list.iter_mut() {
-                pipe_data.i_color[3] = settings.source_alpha;
+        if changed {
+            self.upload(render_sys);
+        }
+
+        if let Some(pipe_data) = &mut self.pipe_data {
+            let (view, projection) = view_projection;
+            let eye = vecmath::mat4_inv(view)[3];
+            pipe_data.u_eye = [eye[0], eye[1], eye[2]];
+            pipe_data.u_view_proj = vecmath::col_mat4_mul(projection, view);
+        }
+    }
+
+    // Creates the buffer of the instances again, or draws nothing if there are no sources
+    fn upload(&mut self, render_sys: &mut RenderSystem) {
+        if self.instances.is_empty() {
+            self.pipe_data = None;
+            return;
+        }
+        let instances = render_sys.factory.create_vertex_buffer(&self.instances);
+        self.slice.instances = Some((self.instances.len() as u32, 0));
+        match &mut self.pipe_data {
+            Some(pipe_data) => pipe_data.instances = instances,
+            None => {
+                self.pipe_data = Some(pipe::Data {
+                    vertex_buffer: self.vertex_buffer.clone(),
+                    instances,
+                    u_view_proj: [[0.; 4]; 4],
+                    u_eye: [0.; 3],
+                    out_color: render_sys.output_color.clone(),
+                    out_depth: render_sys.output_stencil.clone(),
+                })
             }
         }
     }
 
+    /// Rebuilds the pipelines from the shaders in assets/shaders. The current ones are kept on error.
+    pub fn reload_shaders(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        opengl: OpenGL,
+    ) -> Result<(), String> {
+        let glsl = opengl.to_glsl();
+        let pso = shader_watcher::create_pipeline(
+            factory,
+            glsl,
+            "transducer.vert",
+            "transducer.frag",
+            pipe::new(),
+        )?;
+        let pick_pso = shader_watcher::create_pipeline(
+            factory,
+            glsl,
+            "transducer.vert",
+            "pick_transducer.frag",
+            pick_pipe::new(),
+        )?;
+        self.pso = pso;
+        self.pick_pso = pick_pso;
+        Ok(())
+    }
+
     pub fn handle_event(&mut self, render_sys: &RenderSystem, event: &Event<()>) {
         if let Event::WindowEvent {
             event: WindowEvent::Resized(_),
             ..
         } = event
         {
-            for pipe_data in &mut self.pipe_data_list {
-                pipe_data.out_color = render_sys.output_color.clone();
-                pipe_data.out_depth = render_sys.output_stencil.clone();
-            }
+            self.retarget(render_sys);
+        }
+    }
+
+    /// Draws into the current output of `render_sys` from the next frame
+    pub fn retarget(&mut self, render_sys: &RenderSystem) {
+        if let Some(pipe_data) = &mut self.pipe_data {
+            pipe_data.out_color = render_sys.output_color.clone();
+            pipe_data.out_depth = render_sys.output_stencil.clone();
         }
     }
 
@@ -184,31 +302,27 @@ impl SoundSourceViewer {
         &mut self,
         encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
     ) {
-        for i in 0..self.pipe_data_list.len() {
-            encoder.draw(&self.slice, &self.pso, &self.pipe_data_list[i]);
+        if let Some(pipe_data) = &self.pipe_data {
+            encoder.draw(&self.slice, &self.pso, pipe_data);
         }
     }
 
-    fn initialize_pipe_data(
-        factory: &mut gfx_device_gl::Factory,
-        vertex_buffer: Buffer<Resources, Vertex>,
-        view: ShaderResourceView<Resources, [f32; 4]>,
-        out_color: RenderTargetView<Resources, (format::R8_G8_B8_A8, format::Srgb)>,
-        out_depth: DepthStencilView<Resources, (format::D24_S8, format::Unorm)>,
-        sources: &[SoundSource],
-    ) -> Vec<pipe::Data<Resources>> {
-        let sampler_info = SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp);
-        vec![
-            pipe::Data {
-                vertex_buffer,
-                u_model_view_proj: [[0.; 4]; 4],
-                t_color: (view, factory.create_sampler(sampler_info)),
-                i_color: [0., 0., 0., 1.],
-                out_color,
-                out_depth,
+    /// Draws each source into the ID buffer as [PickId::Source] with its index
+    pub fn render_picking(
+        &self,
+        encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
+        picking: &PickingBuffer,
+    ) {
+        if let Some(pipe_data) = &self.pipe_data {
+            let data = pick_pipe::Data {
+                vertex_buffer: pipe_data.vertex_buffer.clone(),
+                instances: pipe_data.instances.clone(),
+                u_view_proj: pipe_data.u_view_proj,
+                out_id: picking.target().clone(),
+                out_depth: picking.depth().clone(),
             };
-            sources.len()
-        ]
+            encoder.draw(&self.slice, &self.pick_pso, &data);
+        }
     }
 
     fn initialize_shader(
@@ -220,7 +334,7 @@ impl SoundSourceViewer {
                 Shaders::new()
                     .set(
                         GLSL::V4_50,
-                        include_str!("../../../assets/shaders/circle.vert"),
+                        include_str!("../../../assets/shaders/transducer.vert"),
                     )
                     .get(version)
                     .unwrap()
@@ -228,7 +342,7 @@ impl SoundSourceViewer {
                 Shaders::new()
                     .set(
                         GLSL::V4_50,
-                        include_str!("../../../assets/shaders/circle.frag"),
+                        include_str!("../../../assets/shaders/transducer.frag"),
                     )
                     .get(version)
                     .unwrap()
@@ -237,4 +351,31 @@ impl SoundSourceViewer {
             )
             .unwrap()
     }
+
+    fn initialize_pick_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, pick_pipe::Meta> {
+        factory
+            .create_pipeline_simple(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/transducer.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/pick_transducer.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                pick_pipe::new(),
+            )
+            .unwrap()
+    }
 }
@@ -12,7 +12,11 @@
  */
 
 use gfx_device_gl::CommandBuffer;
-use glutin::{dpi::LogicalSize, event_loop::EventLoop, window::WindowBuilder};
+use glutin::{
+    dpi::{LogicalSize, PhysicalSize},
+    event_loop::EventLoop,
+    window::WindowBuilder,
+};
 use imgui::{Context, FontConfig, FontGlyphRanges, FontSource};
 use imgui_winit_support::{HiDpiMode, WinitPlatform};
 
@@ -33,10 +37,23 @@ pub struct System {
 
 impl System {
     pub fn init(title: &str, width: f64, heigh: f64) -> Self {
-        let events_loop = EventsLoop::new();
         let builder = WindowBuilder::new()
             .with_title(title.to_owned())
             .with_inner_size(LogicalSize::new(width, heigh));
+        Self::with_builder(builder)
+    }
+
+    /// System of a hidden window, whose GL context renders offscreen without showing anything,
+    /// which still needs a display for the event loop unlike a headless context
+    pub fn init_hidden(width: u32, height: u32) -> Self {
+        let builder = WindowBuilder::new()
+            .with_visible(false)
+            .with_inner_size(PhysicalSize::new(width.max(1), height.max(1)));
+        Self::with_builder(builder)
+    }
+
+    fn with_builder(builder: WindowBuilder) -> Self {
+        let events_loop = EventsLoop::new();
 
         let mut imgui = Context::create();
 
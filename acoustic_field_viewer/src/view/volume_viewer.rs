@@ -0,0 +1,276 @@
+/*
+ * File: volume_viewer.rs
+ * Project: view
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+extern crate gfx;
+
+use camera_controllers::model_view_projection;
+use gfx::{
+    format::{self, Float, R32},
+    handle::{Buffer, ShaderResourceView},
+    preset::depth,
+    state::{Blend, ColorMask},
+    texture::{FilterMethod, Kind, Mipmap, SamplerInfo, WrapMode},
+    traits::*,
+    BlendTarget, DepthTarget, Global, PipelineState, Slice, TextureSampler, VertexBuffer,
+};
+use gfx_device_gl::{CommandBuffer, Resources};
+use glutin::event::{Event, WindowEvent};
+use scarlet::color::RGBColor;
+use shader_version::{glsl::GLSL, OpenGL, Shaders};
+
+use crate::{
+    view::{render_system, render_system::RenderSystem, shader_watcher, ViewerSettings},
+    volume::{VolumeGrid, VolumeMode, VolumeSettings},
+    Matrix4,
+};
+
+gfx_vertex_struct!(Vertex {
+    a_pos: [f32; 3] = "a_pos",
+});
+
+fn alpha_blender() -> Blend {
+    use gfx::state::{BlendValue, Equation, Factor};
+    Blend::new(
+        Equation::Add,
+        Factor::ZeroPlus(BlendValue::SourceAlpha),
+        Factor::OneMinus(BlendValue::SourceAlpha),
+    )
+}
+
+// The volume is blended over the scene without the depth test, since the rays are rasterized at
+// the exit faces of the box, which are behind the slice through it
+gfx_pipeline!( pipe {
+    vertex_buffer: VertexBuffer<Vertex> = (),
+    u_model_view_proj: Global<[[f32; 4]; 4]> = "u_model_view_proj",
+    u_model: Global<[[f32; 4]; 4]> = "u_model",
+    u_eye: Global<[f32; 3]> = "u_eye",
+    u_box_min: Global<[f32; 3]> = "u_box_min",
+    u_box_max: Global<[f32; 3]> = "u_box_max",
+    u_steps: Global<i32> = "u_steps",
+    u_mode: Global<i32> = "u_mode",
+    u_opacity: Global<f32> = "u_opacity",
+    u_threshold: Global<f32> = "u_threshold",
+    u_falloff: Global<f32> = "u_falloff",
+    u_color_scale : Global<f32> = "u_color_scale",
+    u_gamma : Global<f32> = "u_gamma",
+    u_dynamic_range : Global<f32> = "u_dynamic_range",
+    u_color_map: TextureSampler<[f32; 4]> = "u_color_map",
+    u_volume: TextureSampler<f32> = "u_volume",
+    out_color: BlendTarget<format::Srgba8> = ("o_Color", ColorMask::all(), alpha_blender()),
+    out_depth: DepthTarget<format::DepthStencil> = depth::PASS_TEST,
+});
+
+/// Ray-marches the amplitude of a [VolumeGrid] through its box with the opacity transfer function
+/// of [VolumeSettings], or projects its maximum along the rays, in the color map of the slice
+pub struct VolumeViewer {
+    pipe_data: pipe::Data<Resources>,
+    pso: PipelineState<Resources, pipe::Meta>,
+    slice: Slice<Resources>,
+}
+
+impl VolumeViewer {
+    pub fn new(renderer_sys: &RenderSystem, opengl: OpenGL) -> VolumeViewer {
+        let factory = &mut renderer_sys.factory.clone();
+        let (vertex_buffer, slice) = Self::initialize_vertex_buf_and_slice(factory);
+        let sampler =
+            factory.create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp));
+        let (_, volume) = factory
+            .create_texture_immutable::<(R32, Float)>(Kind::D3(1, 1, 1), Mipmap::Provided, &[&[0]])
+            .unwrap();
+        let texels = vec![[0, 0, 0, 0]; 1];
+        let (_, color_map) = factory
+            .create_texture_immutable::<format::Rgba8>(Kind::D1(1), Mipmap::Provided, &[&texels])
+            .unwrap();
+        VolumeViewer {
+            pipe_data: pipe::Data {
+                vertex_buffer,
+                u_model_view_proj: [[0.; 4]; 4],
+                u_model: vecmath_util::mat4_scale(1.0),
+                u_eye: [0.0; 3],
+                u_box_min: [0.0; 3],
+                u_box_max: [1.0; 3],
+                u_steps: 0,
+                u_mode: 0,
+                u_opacity: 0.0,
+                u_threshold: 0.0,
+                u_falloff: 1.0,
+                u_color_scale: 1.0,
+                u_gamma: 1.0,
+                u_dynamic_range: 0.0,
+                u_color_map: (color_map, sampler.clone()),
+                u_volume: (volume, sampler),
+                out_color: renderer_sys.output_color.clone(),
+                out_depth: renderer_sys.output_stencil.clone(),
+            },
+            pso: Self::initialize_shader(factory, opengl.to_glsl()),
+            slice,
+        }
+    }
+
+    /// Uploads the amplitude of `grid`, whose box is rendered until the next grid
+    pub fn set_grid(&mut self, factory: &mut gfx_device_gl::Factory, grid: &VolumeGrid) {
+        let [w, h, d] = grid.dims;
+        let texels: Vec<u32> = grid.amps.iter().map(|a| a.to_bits()).collect();
+        let (_, view) = factory
+            .create_texture_immutable::<(R32, Float)>(
+                Kind::D3(w as u16, h as u16, d as u16),
+                Mipmap::Provided,
+                &[&texels],
+            )
+            .unwrap();
+        self.pipe_data.u_volume.0 = view;
+        self.pipe_data.u_box_min = grid.min;
+        self.pipe_data.u_box_max = grid.max;
+        let size = vecmath::vec3_sub(grid.max, grid.min);
+        let mut model = vecmath_util::mat4_scale(1.0);
+        for (k, s) in size.iter().enumerate() {
+            model[k][k] = *s;
+        }
+        model[3] = vecmath_util::to_vec4(grid.min);
+        self.pipe_data.u_model = model;
+    }
+
+    /// Color map of the slice, opaque
+    pub fn set_color_map(&mut self, factory: &mut gfx_device_gl::Factory, colors: &[RGBColor]) {
+        let texels: Vec<_> = colors
+            .iter()
+            .map(|c| {
+                [
+                    (c.r * 255.) as u8,
+                    (c.g * 255.) as u8,
+                    (c.b * 255.) as u8,
+                    0xFF,
+                ]
+            })
+            .collect();
+        let (_, view): (_, ShaderResourceView<Resources, [f32; 4]>) = factory
+            .create_texture_immutable::<format::Rgba8>(
+                Kind::D1(colors.len() as u16),
+                Mipmap::Provided,
+                &[&texels],
+            )
+            .unwrap();
+        self.pipe_data.u_color_map.0 = view;
+    }
+
+    pub fn update(
+        &mut self,
+        view_projection: (Matrix4, Matrix4),
+        settings: &ViewerSettings,
+        volume: &VolumeSettings,
+    ) {
+        let (view, projection) = view_projection;
+        let eye = vecmath::mat4_inv(view)[3];
+        self.pipe_data.u_eye = [eye[0], eye[1], eye[2]];
+        self.pipe_data.u_model_view_proj =
+            model_view_projection(self.pipe_data.u_model, view, projection);
+        self.pipe_data.u_steps = volume.steps as i32;
+        self.pipe_data.u_mode = match volume.mode {
+            VolumeMode::Composite => 0,
+            VolumeMode::MaximumIntensity => 1,
+        };
+        self.pipe_data.u_opacity = volume.opacity;
+        self.pipe_data.u_threshold = volume.threshold;
+        self.pipe_data.u_falloff = volume.falloff;
+        self.pipe_data.u_color_scale = settings.color_scale;
+        self.pipe_data.u_gamma = settings.gamma;
+        self.pipe_data.u_dynamic_range = settings.dynamic_range.unwrap_or(0.0);
+    }
+
+    /// Rebuilds the pipeline from the shaders in assets/shaders.
+    /// The current pipeline is kept on error.
+    pub fn reload_shaders(
+        &mut self,
+        factory: &mut gfx_device_gl::Factory,
+        opengl: OpenGL,
+    ) -> Result<(), String> {
+        self.pso = shader_watcher::create_pipeline(
+            factory,
+            opengl.to_glsl(),
+            "volume.vert",
+            "volume.frag",
+            pipe::new(),
+        )?;
+        Ok(())
+    }
+
+    pub fn handle_event(&mut self, renderer_sys: &RenderSystem, event: &Event<()>) {
+        if let Event::WindowEvent {
+            event: WindowEvent::Resized(_),
+            ..
+        } = event
+        {
+            self.retarget(renderer_sys);
+        }
+    }
+
+    /// Draws into the current output of `renderer_sys` from the next frame
+    pub fn retarget(&mut self, renderer_sys: &RenderSystem) {
+        self.pipe_data.out_color = renderer_sys.output_color.clone();
+        self.pipe_data.out_depth = renderer_sys.output_stencil.clone();
+    }
+
+    pub fn renderer(
+        &mut self,
+        encoder: &mut gfx::Encoder<render_system::types::Resources, CommandBuffer>,
+    ) {
+        encoder.draw(&self.slice, &self.pso, &self.pipe_data);
+    }
+
+    // Unit cube, whose faces are counterclockwise seen from the outside
+    fn initialize_vertex_buf_and_slice(
+        factory: &mut gfx_device_gl::Factory,
+    ) -> (Buffer<Resources, Vertex>, Slice<Resources>) {
+        let vertex_data: Vec<_> = (0..8)
+            .map(|i| Vertex {
+                a_pos: [(i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32],
+            })
+            .collect();
+        let index_data: &[u16] = &[
+            0, 2, 3, 3, 1, 0, // -z
+            4, 5, 7, 7, 6, 4, // +z
+            0, 4, 6, 6, 2, 0, // -x
+            1, 3, 7, 7, 5, 1, // +x
+            0, 1, 5, 5, 4, 0, // -y
+            2, 6, 7, 7, 3, 2, // +y
+        ];
+        factory.create_vertex_buffer_with_slice(&vertex_data, index_data)
+    }
+
+    fn initialize_shader(
+        factory: &mut gfx_device_gl::Factory,
+        version: GLSL,
+    ) -> PipelineState<Resources, pipe::Meta> {
+        factory
+            .create_pipeline_simple(
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/volume.vert"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                Shaders::new()
+                    .set(
+                        GLSL::V4_50,
+                        include_str!("../../../assets/shaders/volume.frag"),
+                    )
+                    .get(version)
+                    .unwrap()
+                    .as_bytes(),
+                pipe::new(),
+            )
+            .unwrap()
+    }
+}
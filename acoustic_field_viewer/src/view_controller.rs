@@ -0,0 +1,125 @@
+/*
+ * File: view_controller.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use camera_controllers::Camera;
+
+use crate::Vector3;
+
+/// Limit of the elevation of the orbit, short of the poles where the azimuth is undefined
+const MAX_ELEVATION: f32 = 89.0 * std::f32::consts::PI / 180.0;
+/// Closest distance of the camera to the target in mm
+const MIN_DISTANCE: f32 = 1.0;
+/// Angular speed below which the inertia stops, in radians per second
+const MIN_SPEED: f32 = 1e-3;
+
+/// Mouse navigation of the camera around a target: the orbit, the pan and the zoom.
+///
+/// The camera is kept upright, with the global z axis upward on the screen, and the orbit coasts
+/// after the release of the mouse as given by [ViewController::coast].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ViewController {
+    /// Angular velocity of the azimuth and the elevation in radians per second
+    velocity: [f32; 2],
+}
+
+impl ViewController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turns the camera around `target` by `azimuth` around the z axis and `elevation` in radians
+    /// in `dt` seconds, keeping the distance
+    pub fn orbit(
+        &mut self,
+        camera: &mut Camera<f32>,
+        target: Vector3,
+        azimuth: f32,
+        elevation: f32,
+        dt: f32,
+    ) {
+        if dt > 0.0 {
+            self.velocity = [azimuth / dt, elevation / dt];
+        }
+        Self::turn(camera, target, azimuth, elevation);
+    }
+
+    /// Continues the orbit after the release, slowing down with the time constant `inertia` in
+    /// seconds, or stops it if `inertia` is zero. Returns whether the camera moved.
+    pub fn coast(
+        &mut self,
+        camera: &mut Camera<f32>,
+        target: Vector3,
+        dt: f32,
+        inertia: f32,
+    ) -> bool {
+        let [va, ve] = self.velocity;
+        if inertia <= 0.0 || va.hypot(ve) < MIN_SPEED {
+            self.stop();
+            return false;
+        }
+        Self::turn(camera, target, va * dt, ve * dt);
+        let decay = (-dt / inertia).exp();
+        self.velocity = [va * decay, ve * decay];
+        true
+    }
+
+    pub fn stop(&mut self) {
+        self.velocity = [0.0, 0.0];
+    }
+
+    /// Moves the camera and `target` by `dx` and `dy` in mm along the right and the up of the view
+    pub fn pan(&mut self, camera: &mut Camera<f32>, target: &mut Vector3, dx: f32, dy: f32) {
+        self.stop();
+        let d = vecmath::vec3_add(
+            vecmath::vec3_scale(camera.right, dx),
+            vecmath::vec3_scale(camera.up, dy),
+        );
+        camera.position = vecmath::vec3_add(camera.position, d);
+        *target = vecmath::vec3_add(*target, d);
+    }
+
+    /// Scales the distance of the camera to `target` by `factor`, not closer than 1 mm
+    pub fn zoom(&mut self, camera: &mut Camera<f32>, target: Vector3, factor: f32) {
+        let backward = vecmath::vec3_sub(camera.position, target);
+        let distance = vecmath::vec3_len(backward);
+        if distance < 1e-6 {
+            return;
+        }
+        let scale = (distance * factor).max(MIN_DISTANCE) / distance;
+        camera.position = vecmath::vec3_add(target, vecmath::vec3_scale(backward, scale));
+    }
+
+    /// Size of a pixel in mm at the distance of `target` from the camera, for the pan to follow
+    /// the mouse
+    pub fn pixel_size(camera: &Camera<f32>, target: Vector3, fov: f32, height: f32) -> f32 {
+        let distance = vecmath::vec3_len(vecmath::vec3_sub(camera.position, target));
+        2.0 * distance * (fov / 2.0).tan() / height.max(1.0)
+    }
+
+    fn turn(camera: &mut Camera<f32>, target: Vector3, azimuth: f32, elevation: f32) {
+        let backward = vecmath::vec3_sub(camera.position, target);
+        let distance = vecmath::vec3_len(backward);
+        if distance < 1e-6 {
+            return;
+        }
+        let az = backward[1].atan2(backward[0]) + azimuth;
+        let el = ((backward[2] / distance).clamp(-1.0, 1.0).asin() + elevation)
+            .clamp(-MAX_ELEVATION, MAX_ELEVATION);
+        let forward = [el.cos() * az.cos(), el.cos() * az.sin(), el.sin()];
+        let right = vecmath::vec3_normalized(vecmath::vec3_cross([0.0, 0.0, 1.0], forward));
+        camera.position = vecmath::vec3_add(target, vecmath::vec3_scale(forward, distance));
+        camera.forward = forward;
+        camera.right = right;
+        camera.up = vecmath::vec3_cross(forward, right);
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * File: volume.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use autd3_core::hardware_defined::TRANS_SPACING_MM;
+use serde::{Deserialize, Serialize};
+
+use crate::{field::FieldModel, Vector3};
+
+/// Largest number of the grid points along an axis, which is the limit of the 3D textures
+pub const MAX_VOLUME_DIM: usize = 256;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeMode {
+    /// Emission and absorption by the opacity transfer function
+    #[default]
+    Composite,
+    /// Maximum amplitude along each ray, which is transparent below the threshold
+    MaximumIntensity,
+}
+
+/// Box in which the field is rendered volumetrically, and its opacity transfer function
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct VolumeSettings {
+    /// Center in mm
+    pub center: Vector3,
+    /// Size in mm along the x, y and z axes
+    pub size: Vector3,
+    /// Spacing of the grid of the amplitude in mm
+    pub resolution: f32,
+    /// Number of the samples along each ray through the box
+    pub steps: u32,
+    #[serde(default)]
+    pub mode: VolumeMode,
+    /// Opacity of a layer of 10 mm at the full scale
+    pub opacity: f32,
+    /// Position on the color map below which the field is transparent
+    pub threshold: f32,
+    /// Exponent of the ramp of the opacity from the threshold to the full scale
+    pub falloff: f32,
+}
+
+/// Amplitude sampled on a grid in a box, with x the fastest and z the slowest
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeGrid {
+    /// Corners of the box in mm, which are the first and the last grid points
+    pub min: Vector3,
+    pub max: Vector3,
+    pub dims: [usize; 3],
+    pub amps: Vec<f32>,
+}
+
+impl VolumeSettings {
+    /// Above the default array up to 250 mm
+    pub fn new() -> Self {
+        Self {
+            center: [
+                TRANS_SPACING_MM as f32 * 8.5,
+                TRANS_SPACING_MM as f32 * 6.5,
+                130.0,
+            ],
+            size: [200.0, 160.0, 240.0],
+            resolution: 4.0,
+            steps: 128,
+            mode: VolumeMode::Composite,
+            opacity: 0.3,
+            threshold: 0.3,
+            falloff: 2.0,
+        }
+    }
+
+    pub fn min(&self) -> Vector3 {
+        vecmath::vec3_sub(self.center, vecmath::vec3_scale(self.size, 0.5))
+    }
+
+    pub fn max(&self) -> Vector3 {
+        vecmath::vec3_add(self.center, vecmath::vec3_scale(self.size, 0.5))
+    }
+
+    /// Number of the grid points along each axis
+    pub fn dims(&self) -> [usize; 3] {
+        self.size
+            .map(|s| ((s / self.resolution.max(0.1)).round() as usize + 1).clamp(2, MAX_VOLUME_DIM))
+    }
+
+    /// Whether the grids of both are the same, regardless of the transfer functions
+    pub fn same_grid(&self, other: &Self) -> bool {
+        self.center == other.center
+            && self.size == other.size
+            && self.resolution == other.resolution
+    }
+
+    /// Opacity of a layer of 10 mm at the position `t` on the color map, or the opacity of the
+    /// projected maximum in [VolumeMode::MaximumIntensity]
+    pub fn transfer(&self, t: f32) -> f32 {
+        let ramp = ((t - self.threshold) / (1.0 - self.threshold).max(1e-3)).clamp(0.0, 1.0);
+        match self.mode {
+            VolumeMode::Composite => self.opacity * ramp.powf(self.falloff),
+            VolumeMode::MaximumIntensity => ramp.powf(self.falloff),
+        }
+    }
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VolumeGrid {
+    pub fn compute(model: &FieldModel, settings: &VolumeSettings) -> Self {
+        let (min, max) = (settings.min(), settings.max());
+        let dims = settings.dims();
+        let at = |i: usize, k: usize| min[k] + (max[k] - min[k]) * i as f32 / (dims[k] - 1) as f32;
+        let amps = (0..dims[2])
+            .flat_map(|z| (0..dims[1]).map(move |y| (y, z)))
+            .flat_map(|(y, z)| (0..dims[0]).map(move |x| (x, y, z)))
+            .map(|(x, y, z)| {
+                let [re, im] = model.pressure_at([at(x, 0), at(y, 1), at(z, 2)]);
+                (re * re + im * im).sqrt()
+            })
+            .collect();
+        Self {
+            min,
+            max,
+            dims,
+            amps,
+        }
+    }
+
+    pub fn max_amp(&self) -> f32 {
+        self.amps.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Grid point of the maximum amplitude in mm, and the amplitude
+    pub fn argmax(&self) -> Option<(Vector3, f32)> {
+        let (i, &amp) = self
+            .amps
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        let [w, h, _] = self.dims;
+        let idx = [i % w, i / w % h, i / (w * h)];
+        let mut pos = [0.0; 3];
+        for k in 0..3 {
+            pos[k] = self.min[k]
+                + (self.max[k] - self.min[k]) * idx[k] as f32 / (self.dims[k] - 1).max(1) as f32;
+        }
+        Some((pos, amp))
+    }
+}
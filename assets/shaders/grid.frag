@@ -0,0 +1,8 @@
+#version 450 core
+
+in vec4 v_color;
+out vec4 o_Color;
+
+void main() {
+    o_Color = v_color;
+}
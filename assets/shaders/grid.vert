@@ -0,0 +1,11 @@
+#version 450 core
+
+in vec3 a_pos;
+in vec4 a_color;
+out vec4 v_color;
+uniform mat4 u_model_view_proj;
+
+void main() {
+    v_color = a_color;
+    gl_Position = u_model_view_proj * vec4(a_pos, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 450 core
+
+in vec3 v_gpos;
+in vec3 v_normal;
+out vec4 o_Color;
+
+uniform vec3 u_eye;
+uniform vec4 u_color;
+
+// Lit from the eye on both sides, since the surface may be seen from the inside
+void main() {
+  vec3 v = normalize(u_eye - v_gpos);
+  float diffuse = abs(dot(normalize(v_normal), v));
+  o_Color = vec4(u_color.rgb * (0.35 + 0.65 * diffuse), u_color.a);
+}
@@ -0,0 +1,13 @@
+#version 450 core
+
+in vec3 a_pos;
+in vec3 a_normal;
+out vec3 v_gpos;
+out vec3 v_normal;
+uniform mat4 u_model_view_proj;
+
+void main() {
+    gl_Position = u_model_view_proj * vec4(a_pos, 1.0);
+    v_gpos = a_pos;
+    v_normal = a_normal;
+}
@@ -0,0 +1,8 @@
+#version 450 core
+
+out vec4 o_Id;
+uniform vec4 u_id;
+
+void main() {
+    o_Id = u_id;
+}
@@ -0,0 +1,8 @@
+#version 450 core
+
+flat in vec4 v_id;
+out vec4 o_Id;
+
+void main() {
+    o_Id = v_id;
+}
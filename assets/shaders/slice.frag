@@ -3,33 +3,307 @@
 in vec3 v_gpos;
 out vec4 o_Color;
 
-uniform float u_wavenum;
 uniform float u_color_scale;
+uniform float u_gamma;
+uniform float u_dynamic_range;
+uniform int u_quantity;
+uniform float u_wt;
 uniform float u_trans_num;
+uniform float u_attenuation;
+uniform int u_reflector;
+uniform vec3 u_reflector_center;
+uniform vec3 u_reflector_normal;
+uniform float u_reflection;
+uniform vec4 u_obstacle_shapes;
+uniform mat4 u_obstacle0;
+uniform mat4 u_obstacle1;
+uniform mat4 u_obstacle2;
+uniform mat4 u_obstacle3;
+uniform vec2 u_gorkov;
+uniform float u_wave_length;
 uniform sampler1D u_color_map;
 uniform sampler1D u_trans_pos;
+uniform sampler1D u_trans_dir;
+uniform sampler1D u_directivity;
 uniform sampler1D u_trans_drive;
+uniform int u_approximation;
+uniform float u_group_num;
+uniform sampler1D u_group_center;
+uniform sampler1D u_group_dir;
+uniform float u_facet_num;
+uniform sampler1D u_facet_pos;
+uniform sampler1D u_facet_normal;
+uniform int u_compare;
+uniform sampler1D u_ref_drive;
+uniform sampler1D u_ref_facet;
 
 const float PI = 3.141592653589793;
 
+// Whether the field is that of the reference drive and the pressure on the facets of it
+bool g_reference = false;
+
+vec2 drive(float s) {
+  return g_reference ? texture(u_ref_drive, s).xy : texture(u_trans_drive, s).xy;
+}
+
 vec4 coloring(float t)
 {
   return texture(u_color_map, clamp(t, 0.0, 1.0));
 }
 
-void main() {
-    float re = 0.0;
-    float im = 0.0;
+// Position on the color map of the magnitude v relative to the full scale, in dB over
+// u_dynamic_range if positive, where db is 20 for amplitudes and 10 for powers, otherwise with the
+// gamma
+float magnitude(float v, float db) {
+  if (u_dynamic_range > 0.0) {
+    return 1.0 + db * log(max(v, 1e-30)) / log(10.0) / u_dynamic_range;
+  }
+  return pow(v, 1.0/u_gamma);
+}
+
+// Whether the segment from a to b crosses the obstacle of the shape, 0 for none, 1 for the unit
+// sphere, 2 for the cube from -1 to 1 and 3 for the square from -1 to 1 in the xy plane in the
+// coordinates mapped by m, the same as obstacle.rs
+bool occludes(mat4 m, float shape, vec3 a, vec3 b) {
+    if (shape == 0.0) return false;
+    vec3 la = (m * vec4(a, 1.0)).xyz;
+    vec3 lb = (m * vec4(b, 1.0)).xyz;
+    vec3 d = lb - la;
+    if (shape == 1.0) {
+        float t = clamp(-dot(la, d) / max(dot(d, d), 1e-12), 0.0, 1.0);
+        vec3 q = la + t * d;
+        return dot(q, q) < 1.0;
+    }
+    if (shape == 2.0) {
+        vec3 t0 = (-1.0 - la) / d;
+        vec3 t1 = (1.0 - la) / d;
+        vec3 tmin = min(t0, t1);
+        vec3 tmax = max(t0, t1);
+        float near = max(max(tmin.x, tmin.y), max(tmin.z, 0.0));
+        float far = min(min(tmax.x, tmax.y), min(tmax.z, 1.0));
+        return near <= far;
+    }
+    if (la.z * lb.z >= 0.0) return false;
+    vec2 q = la.xy + la.z / (la.z - lb.z) * d.xy;
+    return abs(q.x) <= 1.0 && abs(q.y) <= 1.0;
+}
+
+bool blocked(vec3 a, vec3 b) {
+    return occludes(u_obstacle0, u_obstacle_shapes.x, a, b)
+        || occludes(u_obstacle1, u_obstacle_shapes.y, a, b)
+        || occludes(u_obstacle2, u_obstacle_shapes.z, a, b)
+        || occludes(u_obstacle3, u_obstacle_shapes.w, a, b);
+}
+
+// Adds the pressure at pos of the source at tpos toward dir with the wavenumber k, the drive
+// p_amp and the factor gain of the amplitude, and its gradient if with_grad
+void add_source(vec3 pos, vec3 tpos, vec3 dir, float k, vec2 p_amp, float gain, bool with_grad,
+                inout vec2 p, inout vec3 grad_re, inout vec3 grad_im) {
+    vec3 r = pos - tpos;
+    float d = length(r);
+    float theta = acos(clamp(dot(r, dir) / d, -1.0, 1.0));
+    float directivity = texture(u_directivity, theta / PI).x;
+    float phase = 2.0*PI*p_amp.x - k*d;
+    float amp = gain * p_amp.y * directivity * exp(-u_attenuation * d) / d;
+    vec2 pi = amp * vec2(cos(phase), sin(phase));
+    p += pi;
+    if (with_grad) {
+        float a = -1.0 / d - u_attenuation;
+        grad_re += (a * pi.x + k * pi.y) * r / d;
+        grad_im += (a * pi.y - k * pi.x) * r / d;
+    }
+}
+
+// Adds the pressure at pos of the sources from start to end exclusive around center toward dir, or
+// of their images if image, in the Fresnel approximation about the center, where the amplitude,
+// the directivity, the attenuation and the obstacles are evaluated once for all of them
+void add_group(vec3 pos, vec3 center, vec3 dir, float start, float end, bool image, bool with_grad,
+               inout vec2 p, inout vec3 grad_re, inout vec3 grad_im) {
+    vec3 n = u_reflector_normal;
+    vec3 c = center;
+    vec3 cdir = dir;
+    float gain = 1.0;
+    if (image) {
+        c = center - 2.0 * dot(center - u_reflector_center, n) * n;
+        cdir = dir - 2.0 * dot(dir, n) * n;
+        gain = u_reflection;
+        float de = dot(c - u_reflector_center, n);
+        vec3 r = c + de / (de - dot(pos - u_reflector_center, n)) * (pos - c);
+        if (blocked(center, r) || blocked(r, pos)) return;
+    } else if (blocked(center, pos)) {
+        return;
+    }
+    vec3 r = pos - c;
+    float R = length(r);
+    if (R == 0.0) return;
+    vec3 u = r / R;
+    float theta = acos(clamp(dot(u, cdir), -1.0, 1.0));
+    float common = gain * texture(u_directivity, theta / PI).x * exp(-u_attenuation * R) / R;
+    float a = -1.0 / R - u_attenuation;
+    for(float idx = 0.0; idx < 65536.0; idx++){
+        if (start + idx >= end) break;
+        float s = (start + idx + 0.5) / u_trans_num;
+        vec4 tp = texture(u_trans_pos, s);
+        vec2 p_amp = drive(s);
+        vec3 sp = image ? tp.xyz - 2.0 * dot(tp.xyz - u_reflector_center, n) * n : tp.xyz;
+        // |R u - r'| to the second order of r'
+        vec3 offset = sp - c;
+        float along = dot(u, offset);
+        float d = R - along + (dot(offset, offset) - along * along) / (2.0 * R);
+        float phase = 2.0*PI*p_amp.x - tp.w*d;
+        vec2 pi = common * p_amp.y * vec2(cos(phase), sin(phase));
+        p += pi;
+        if (with_grad) {
+            grad_re += (a * pi.x + tp.w * pi.y) * u;
+            grad_im += (a * pi.y - tp.w * pi.x) * u;
+        }
+    }
+}
+
+vec2 cmul(vec2 a, vec2 b) {
+  return vec2(a.x*b.x - a.y*b.y, a.x*b.y + a.y*b.x);
+}
+
+// Adds the pressure at pos scattered by the facet at fpos with the normal n scaled by its area and
+// the pressure ps on it, and its gradient if with_grad, where the facet radiates the normal
+// derivative of the Green function times the pressure
+void add_facet(vec3 pos, vec3 fpos, vec3 n, vec2 ps, float k, bool with_grad,
+               inout vec2 p, inout vec3 grad_re, inout vec3 grad_im) {
+    vec3 r = pos - fpos;
+    float d = length(r);
+    if (d == 0.0) return;
+    float rn = dot(r, n);
+    vec2 e = exp(-u_attenuation * d) / (4.0*PI) * vec2(cos(k*d), -sin(k*d));
+    vec2 h = cmul(cmul(ps, vec2(1.0/(d*d*d), k/(d*d))), e);
+    p += rn * h;
+    if (with_grad) {
+        vec2 dh = cmul(cmul(ps, vec2(-3.0/(d*d*d*d) + k*k/(d*d), -3.0*k/(d*d*d))), e)
+            - u_attenuation * h;
+        grad_re += n * h.x + rn * dh.x * r / d;
+        grad_im += n * h.y + rn * dh.y * r / d;
+    }
+}
+
+// Complex pressure at pos, and its gradient if with_grad, with the image sources in the reflector
+// if enabled, behind which the field is zero, without the contributions blocked by the obstacles,
+// and the field scattered by the facets, in the Fresnel approximation of the groups of the sources
+// if u_approximation is 1
+void field(vec3 pos, bool with_grad, out vec2 p, out vec3 grad_re, out vec3 grad_im) {
+    p = vec2(0.0);
+    grad_re = vec3(0.0);
+    grad_im = vec3(0.0);
+    vec3 n = u_reflector_normal;
+    if (u_reflector != 0 && dot(pos - u_reflector_center, n) < 0.0) return;
+    for(float idx = 0.0; idx < 65536.0; idx++){
+        if (u_approximation == 0 || idx >= u_group_num) break;
+        vec4 gc = texture(u_group_center, (idx+0.5) / u_group_num);
+        vec4 gd = texture(u_group_dir, (idx+0.5) / u_group_num);
+        add_group(pos, gc.xyz, gd.xyz, gc.w, gd.w, false, with_grad, p, grad_re, grad_im);
+        if (u_reflector != 0) {
+            add_group(pos, gc.xyz, gd.xyz, gc.w, gd.w, true, with_grad, p, grad_re, grad_im);
+        }
+    }
+    for(float idx = 0.0; idx < 65536.0; idx++){
+        if (u_approximation != 0 || idx >= u_trans_num) break;
+        vec4 tp = texture(u_trans_pos, (idx+0.5) / u_trans_num);
+        vec3 dir = texture(u_trans_dir, (idx+0.5) / u_trans_num).xyz;
+        vec2 p_amp = drive((idx+0.5) / u_trans_num);
+        if (!blocked(tp.xyz, pos)) {
+            add_source(pos, tp.xyz, dir, tp.w, p_amp, 1.0, with_grad, p, grad_re, grad_im);
+        }
+        if (u_reflector != 0) {
+            vec3 image = tp.xyz - 2.0 * dot(tp.xyz - u_reflector_center, n) * n;
+            vec3 image_dir = dir - 2.0 * dot(dir, n) * n;
+            // reflected at the point of the reflector on the segment from the image to pos
+            float de = dot(image - u_reflector_center, n);
+            vec3 r = image + de / (de - dot(pos - u_reflector_center, n)) * (pos - image);
+            if (!blocked(tp.xyz, r) && !blocked(r, pos)) {
+                add_source(pos, image, image_dir, tp.w, p_amp, u_reflection, with_grad,
+                           p, grad_re, grad_im);
+            }
+        }
+    }
+    float k = 2.0*PI / u_wave_length;
     for(float idx = 0.0; idx < 65536.0; idx++){
-        if (idx >= u_trans_num) break;
-        vec3 tp = texture(u_trans_pos, (idx+0.5) / u_trans_num).xyz;
-        float d = length(v_gpos - tp);
-        vec2 p_amp = texture(u_trans_drive, (idx+0.5) / u_trans_num).xy;
-        float p = 2.0*PI*p_amp.x;
-        float amp = p_amp.y / d;
-        im += amp * cos(p - u_wavenum*d);
-        re += amp * sin(p - u_wavenum*d);
-    }
-    float c = sqrt(re*re+im*im);
-    o_Color = coloring(c/u_color_scale);
+        if (idx >= u_facet_num) break;
+        vec4 fp = texture(u_facet_pos, (idx+0.5) / u_facet_num);
+        vec4 fd = texture(u_facet_normal, (idx+0.5) / u_facet_num);
+        vec2 ps = g_reference ? texture(u_ref_facet, (idx+0.5) / u_facet_num).xy : vec2(fp.w, fd.w);
+        add_facet(pos, fp.xyz, fd.xyz, ps, k, with_grad, p, grad_re, grad_im);
+    }
+}
+
+// Gor'kov potential normalized to that of the pressure term at u_color_scale
+float gorkov(vec3 pos) {
+    vec2 p;
+    vec3 grad_re;
+    vec3 grad_im;
+    field(pos, true, p, grad_re, grad_im);
+    return u_gorkov.x * dot(p, p) - u_gorkov.y * (dot(grad_re, grad_re) + dot(grad_im, grad_im));
+}
+
+// Value of u_quantity at pos relative to the full scale of the color map, and the phase in radian
+float quantity(vec3 pos) {
+    if (u_quantity == 5) {
+        return gorkov(pos);
+    }
+    if (u_quantity == 6) {
+        float h = u_wave_length / 20.0;
+        vec3 grad = vec3(
+            gorkov(pos + vec3(h, 0.0, 0.0)) - gorkov(pos - vec3(h, 0.0, 0.0)),
+            gorkov(pos + vec3(0.0, h, 0.0)) - gorkov(pos - vec3(0.0, h, 0.0)),
+            gorkov(pos + vec3(0.0, 0.0, h)) - gorkov(pos - vec3(0.0, 0.0, h))) / (2.0*h);
+        return length(grad) * u_wave_length / (2.0*PI);
+    }
+    vec2 p;
+    vec3 grad_re;
+    vec3 grad_im;
+    field(pos, false, p, grad_re, grad_im);
+    float re = p.x;
+    float im = p.y;
+    if (u_quantity == 1) {
+        return atan(im, re);
+    }
+    if (u_quantity == 2) {
+        return (re*cos(u_wt) - im*sin(u_wt))/u_color_scale;
+    }
+    if (u_quantity == 3 || u_quantity == 4) {
+        // the radiation pressure at the full scale is that of u_color_scale, so the medium cancels
+        return (re*re+im*im)/(u_color_scale*u_color_scale);
+    }
+    return sqrt(re*re+im*im)/u_color_scale;
+}
+
+float signed_position(float v) {
+    return 0.5 + 0.5*sign(v)*pow(min(abs(v), 1.0), 1.0/u_gamma);
+}
+
+// u_compare is 0 for the current drive, 1 for the reference and 2 for the current minus the
+// reference, whose phase difference is wrapped from -pi to pi
+void main() {
+    float t;
+    if (u_compare == 2) {
+        float v = quantity(v_gpos);
+        g_reference = true;
+        v -= quantity(v_gpos);
+        if (u_quantity == 1) {
+            t = mod(v + PI, 2.0*PI) / (2.0*PI);
+        } else {
+            t = signed_position(v);
+        }
+        o_Color = coloring(t);
+        return;
+    }
+    g_reference = u_compare == 1;
+    float v = quantity(v_gpos);
+    if (u_quantity == 1) {
+        t = (v + PI) / (2.0*PI);
+    } else if (u_quantity == 2 || u_quantity == 5) {
+        t = signed_position(v);
+    } else if (u_quantity == 0) {
+        t = magnitude(v, 20.0);
+    } else {
+        t = magnitude(v, 10.0);
+    }
+    o_Color = coloring(t);
 }
@@ -0,0 +1,11 @@
+#version 450 core
+
+in vec2 v_uv;
+out vec4 o_Color;
+
+// Field evaluated at the points of the sampling grid, in the color map with the alpha of the slice
+uniform sampler2D u_field;
+
+void main() {
+    o_Color = texture(u_field, v_uv);
+}
@@ -0,0 +1,12 @@
+#version 450 core
+
+in ivec3 a_pos;
+out vec2 v_uv;
+uniform mat4 u_model_view_proj;
+uniform vec2 u_uv_offset;
+uniform vec2 u_uv_scale;
+
+void main() {
+    gl_Position = u_model_view_proj * vec4(a_pos, 1.0);
+    v_uv = (vec2(a_pos.xy) - u_uv_offset) * u_uv_scale;
+}
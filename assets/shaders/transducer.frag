@@ -0,0 +1,18 @@
+#version 450 core
+
+in vec3 v_gpos;
+in vec3 v_normal;
+in vec4 v_color;
+flat in float v_lit;
+out vec4 o_Color;
+
+uniform vec3 u_eye;
+
+// The housings are lit from the eye, whereas the radiating surfaces are not, so that their colors
+// are read as they are at any angle
+void main() {
+  vec3 v = normalize(u_eye - v_gpos);
+  float diffuse = max(dot(normalize(v_normal), v), 0.0);
+  float shade = mix(1.0, 0.35 + 0.65 * diffuse, v_lit);
+  o_Color = vec4(v_color.rgb * shade, v_color.a);
+}
@@ -0,0 +1,27 @@
+#version 450 core
+
+in vec3 a_pos;
+in vec3 a_normal;
+in vec4 i_model0;
+in vec4 i_model1;
+in vec4 i_model2;
+in vec4 i_model3;
+in vec4 i_color;
+in vec4 i_id;
+out vec3 v_gpos;
+out vec3 v_normal;
+out vec4 v_color;
+flat out vec4 v_id;
+flat out float v_lit;
+uniform mat4 u_view_proj;
+
+void main() {
+    mat4 model = mat4(i_model0, i_model1, i_model2, i_model3);
+    vec4 gpos = model * vec4(a_pos, 1.0);
+    gl_Position = u_view_proj * gpos;
+    v_gpos = gpos.xyz;
+    v_normal = mat3(model) * a_normal;
+    v_color = i_color;
+    v_id = i_id;
+    v_lit = a_normal.z > 0.5 ? 0.0 : 1.0;
+}
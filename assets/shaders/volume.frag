@@ -0,0 +1,80 @@
+#version 450 core
+
+in vec3 v_gpos;
+out vec4 o_Color;
+
+uniform vec3 u_eye;
+uniform vec3 u_box_min;
+uniform vec3 u_box_max;
+uniform int u_steps;
+// 0: composite, 1: maximum intensity projection
+uniform int u_mode;
+uniform float u_opacity;
+uniform float u_threshold;
+uniform float u_falloff;
+uniform float u_color_scale;
+uniform float u_gamma;
+uniform float u_dynamic_range;
+uniform sampler1D u_color_map;
+uniform sampler3D u_volume;
+
+// Thickness in mm of the layer whose opacity is u_opacity
+const float REFERENCE_LENGTH = 10.0;
+
+// Position on the color map of the amplitude v relative to the full scale as the slice
+float magnitude(float v) {
+  if (u_dynamic_range > 0.0) {
+    return 1.0 + 20.0 * log(max(v, 1e-30)) / log(10.0) / u_dynamic_range;
+  }
+  return pow(v, 1.0/u_gamma);
+}
+
+float transfer(float t) {
+  float ramp = clamp((t - u_threshold) / max(1.0 - u_threshold, 1e-3), 0.0, 1.0);
+  return u_opacity * pow(ramp, u_falloff);
+}
+
+// Each face of the box is rasterized, and the ray is marched front to back from the fragments
+// of the exit faces only, so that the camera may be inside the box
+void main() {
+  vec3 dir = normalize(v_gpos - u_eye);
+  vec3 t0 = (u_box_min - u_eye) / dir;
+  vec3 t1 = (u_box_max - u_eye) / dir;
+  vec3 tmin = min(t0, t1);
+  vec3 tmax = max(t0, t1);
+  float t_near = max(max(max(tmin.x, tmin.y), tmin.z), 0.0);
+  float t_far = min(min(tmax.x, tmax.y), tmax.z);
+  if (length(v_gpos - u_eye) < t_far - 1e-3 * length(u_box_max - u_box_min)) {
+    discard;
+  }
+  vec3 n = vec3(textureSize(u_volume, 0));
+  float dt = (t_far - t_near) / float(max(u_steps, 1));
+  vec4 acc = vec4(0.0);
+  float v_max = 0.0;
+  for (int i = 0; i < 4096; i++) {
+    if (i >= u_steps || acc.a > 0.99) break;
+    vec3 p = u_eye + dir * (t_near + (float(i) + 0.5) * dt);
+    // the grid points are at the centers of the texels
+    vec3 uvw = ((p - u_box_min) / (u_box_max - u_box_min) * (n - 1.0) + 0.5) / n;
+    float v = texture(u_volume, uvw).x;
+    if (u_mode == 1) {
+      v_max = max(v_max, v);
+      continue;
+    }
+    float t = magnitude(v / u_color_scale);
+    float a = 1.0 - pow(1.0 - min(transfer(t), 0.999), dt / REFERENCE_LENGTH);
+    vec3 c = texture(u_color_map, clamp(t, 0.0, 1.0)).rgb;
+    acc.rgb += (1.0 - acc.a) * a * c;
+    acc.a += (1.0 - acc.a) * a;
+  }
+  if (u_mode == 1) {
+    float t = magnitude(v_max / u_color_scale);
+    float ramp = clamp((t - u_threshold) / max(1.0 - u_threshold, 1e-3), 0.0, 1.0);
+    acc = vec4(texture(u_color_map, clamp(t, 0.0, 1.0)).rgb, pow(ramp, u_falloff));
+    acc.rgb *= acc.a;
+  }
+  if (acc.a <= 0.0) {
+    discard;
+  }
+  o_Color = vec4(acc.rgb / acc.a, acc.a);
+}
@@ -11,15 +11,16 @@
  *
  */
 
+use std::{fmt, ops::Range};
+
 use acoustic_field_viewer::sound_source::SoundSource;
-use autd3_core::hardware_defined::{
-    RxGlobalControlFlags, NUM_TRANS_X, NUM_TRANS_Y, TRANS_SPACING_MM,
-};
+use autd3_core::hardware_defined::{RxGlobalControlFlags, NUM_TRANS_IN_UNIT, TRANS_SPACING_MM};
+use serde::{Deserialize, Serialize};
 
-use crate::Vector3;
+use crate::{CommandedFocus, Layout, Vector3};
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Modulation {
     pub mod_data: Vec<u8>,
     pub mod_div: u16,
@@ -36,6 +37,10 @@ pub(crate) struct SeqFocus {
 }
 
 impl SeqFocus {
+    pub(crate) fn new(buf: [u16; 4]) -> Self {
+        Self { buf }
+    }
+
     pub(crate) fn x(&self, wavelength: u16) -> f32 {
         let v: u32 = self.buf[0] as u32;
         let v: u32 = v | (((self.buf[1] as u32) & 0x0001) << 16);
@@ -69,22 +74,24 @@ impl SeqFocus {
     }
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Sequence {
     pub seq_div: u16,
+    /// STM points in the coordinates of device 0 as sent by the SDK, and their duty
     pub seq_data: Vec<(Vector3, u8)>,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DelayOffset {
     pub delay_offset: Vec<(u8, u8)>,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Geometry {
     pub origin: Vector3,
     pub right: Vector3,
     pub up: Vector3,
+    pub layout: Layout,
 }
 
 #[derive(Debug)]
@@ -102,25 +109,177 @@ pub enum AutdData {
     RequestCpuVerLsb,
     Sequence(Sequence),
     DelayOffset(DelayOffset),
+    ViewerSetting(Vec<u8>),
+    Cycles(Vec<u16>),
+    CommandedFoci(Vec<CommandedFocus>),
+    /// Name of the export preset to run
+    Export(String),
+    /// File name of the screenshot to take, timestamped if empty
+    Screenshot(String),
+    /// Free-form tags attached to the next gain
+    Tags(Vec<String>),
+    /// Simulation time in seconds of the emulator handing off the session
+    Handoff(f64),
 }
 
 impl Geometry {
     pub fn make_autd_transducers(&self) -> Vec<SoundSource> {
-        let mut transducers = Vec::new();
-        for y in 0..NUM_TRANS_Y {
-            for x in 0..NUM_TRANS_X {
-                if autd3_core::hardware_defined::is_missing_transducer(x, y) {
-                    continue;
-                }
-                let x_dir = vecmath::vec3_scale(self.right, TRANS_SPACING_MM as f32 * x as f32);
-                let y_dir = vecmath::vec3_scale(self.up, TRANS_SPACING_MM as f32 * y as f32);
-                let zdir = vecmath::vec3_cross(self.right, self.up);
+        let zdir = self.normal();
+        self.layout
+            .positions()
+            .iter()
+            .map(|p| {
+                let x_dir = vecmath::vec3_scale(self.right, p[0]);
+                let y_dir = vecmath::vec3_scale(self.up, p[1]);
                 let pos = self.origin;
                 let pos = vecmath::vec3_add(pos, x_dir);
                 let pos = vecmath::vec3_add(pos, y_dir);
-                transducers.push(SoundSource::new(pos, zdir, 0.0, 0.0));
+                SoundSource::new(pos, zdir, 0.0, 0.0)
+            })
+            .collect()
+    }
+
+    /// Direction of the emission, normal to the device plane
+    pub fn normal(&self) -> Vector3 {
+        vecmath::vec3_cross(self.right, self.up)
+    }
+
+    /// Position in the global coordinates of `local` in the coordinates of the device, in which
+    /// the SDK sends the STM points
+    pub fn global_position(&self, local: Vector3) -> Vector3 {
+        let pos = vecmath::vec3_add(self.origin, vecmath::vec3_scale(self.right, local[0]));
+        let pos = vecmath::vec3_add(pos, vecmath::vec3_scale(self.up, local[1]));
+        vecmath::vec3_add(pos, vecmath::vec3_scale(self.normal(), local[2]))
+    }
+
+    /// Corners of the board of [Layout::board] in the global coordinates, counterclockwise from
+    /// the lower left seen from the front
+    pub fn board_corners(&self) -> [Vector3; 4] {
+        let ([x0, y0], [x1, y1]) = self.layout.board();
+        [[x0, y0], [x1, y0], [x1, y1], [x0, y1]].map(|[x, y]| {
+            let pos = vecmath::vec3_add(self.origin, vecmath::vec3_scale(self.right, x));
+            vecmath::vec3_add(pos, vecmath::vec3_scale(self.up, y))
+        })
+    }
+
+    /// Checks that `right` and `up` are orthonormal and that no two devices overlap
+    pub fn validate(geometries: &[Geometry]) -> Vec<GeometryWarning> {
+        const EPS: f32 = 1e-3;
+
+        let mut warnings = Vec::new();
+        for (device, geometry) in geometries.iter().enumerate() {
+            for (axis, v) in [("right", geometry.right), ("up", geometry.up)] {
+                let norm = vecmath::vec3_len(v);
+                if (norm - 1.0).abs() > EPS {
+                    warnings.push(GeometryWarning::NotNormalized { device, axis, norm });
+                }
+            }
+            let dot = vecmath::vec3_dot(geometry.right, geometry.up);
+            if dot.abs() > EPS {
+                warnings.push(GeometryWarning::NotOrthogonal { device, dot });
             }
         }
-        transducers
+
+        let transducers: Vec<_> = geometries
+            .iter()
+            .map(|g| g.make_autd_transducers())
+            .collect();
+        let spacing: Vec<_> = geometries
+            .iter()
+            .map(|g| g.layout.min_spacing().unwrap_or(TRANS_SPACING_MM as f32))
+            .collect();
+        for i in 0..transducers.len() {
+            for j in (i + 1)..transducers.len() {
+                let min_dist = spacing[i].min(spacing[j]) / 2.0;
+                let overlap = transducers[i].iter().any(|a| {
+                    transducers[j]
+                        .iter()
+                        .any(|b| vecmath_util::dist(a.pos, b.pos) < min_dist)
+                });
+                if overlap {
+                    warnings.push(GeometryWarning::Overlap { devices: (i, j) });
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryWarning {
+    NotNormalized {
+        device: usize,
+        axis: &'static str,
+        norm: f32,
+    },
+    NotOrthogonal {
+        device: usize,
+        dot: f32,
+    },
+    Overlap {
+        devices: (usize, usize),
+    },
+}
+
+impl fmt::Display for GeometryWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryWarning::NotNormalized { device, axis, norm } => write!(
+                f,
+                "device {}: {} vector is not normalized (|{}| = {:.4})",
+                device, axis, axis, norm
+            ),
+            GeometryWarning::NotOrthogonal { device, dot } => write!(
+                f,
+                "device {}: right and up are not orthogonal (right . up = {:.4})",
+                device, dot
+            ),
+            GeometryWarning::Overlap { devices } => {
+                write!(f, "device {} and device {} overlap", devices.0, devices.1)
+            }
+        }
+    }
+}
+
+/// Range of the entries of each device of `device_sizes` in the per-transducer frames of the SDK,
+/// e.g., the gain, the cycles and the delay offset.
+///
+/// Every device has a block of NUM_TRANS_IN_UNIT entries, whose first ones are its transducers
+/// followed by the padding, or a block as large as its transducers for a larger custom layout.
+pub fn frame_blocks(device_sizes: &[usize]) -> Vec<Range<usize>> {
+    let mut start = 0;
+    device_sizes
+        .iter()
+        .map(|&size| {
+            let block = start..start + size;
+            start += size.max(NUM_TRANS_IN_UNIT);
+            block
+        })
+        .collect()
+}
+
+/// Entry in the per-transducer frames of each source of the devices of `device_sizes` in order
+pub fn frame_entries(device_sizes: &[usize]) -> Vec<usize> {
+    frame_blocks(device_sizes).into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_blocks_of_custom_devices() {
+        assert_eq!(
+            frame_blocks(&[249, 100, 300]),
+            vec![0..249, 249..349, 498..798]
+        );
+        assert!(frame_blocks(&[]).is_empty());
+    }
+
+    #[test]
+    fn frame_entries_skip_the_unused_entries() {
+        let entries = frame_entries(&[2, 3]);
+        assert_eq!(entries, vec![0, 1, 249, 250, 251]);
     }
 }
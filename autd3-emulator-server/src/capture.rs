@@ -0,0 +1,110 @@
+/*
+ * File: capture.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    sync::mpsc::Sender,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Writes received frames to a capture file, which can be replayed with [CaptureReplay].
+///
+/// A capture file is a sequence of records, each of which is the arrival time in microseconds
+/// since the start of the capture (u64), the frame size (u32) and the raw frame, in little endian.
+pub(crate) struct CaptureWriter {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl CaptureWriter {
+    pub(crate) fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+        })
+    }
+
+    pub(crate) fn write(&mut self, received: Instant, frame: &[u8]) -> std::io::Result<()> {
+        let time = received.saturating_duration_since(self.start).as_micros() as u64;
+        self.writer.write_all(&time.to_le_bytes())?;
+        self.writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+        self.writer.write_all(frame)
+    }
+
+    pub(crate) fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Frames of a capture file with their times from the start of the capture
+pub fn read_capture<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<(Duration, Vec<u8>)>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut frames = Vec::new();
+    loop {
+        let mut time = [0; 8];
+        match reader.read_exact(&mut time) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let mut frame = vec![0; u32::from_le_bytes(len) as usize];
+        reader.read_exact(&mut frame)?;
+        frames.push((Duration::from_micros(u64::from_le_bytes(time)), frame));
+    }
+    Ok(frames)
+}
+
+/// Feeds the frames of a capture file as if received from the client
+pub(crate) struct CaptureReplay {
+    th_handle: JoinHandle<()>,
+}
+
+impl CaptureReplay {
+    /// Frames are sent with the recorded timing if `realtime`, otherwise all at once
+    pub(crate) fn start<P: AsRef<Path>>(
+        path: P,
+        realtime: bool,
+        tx: Sender<(Instant, Vec<u8>)>,
+    ) -> std::io::Result<Self> {
+        let frames = read_capture(&path)?;
+        tracing::info!(
+            "replaying {} frames from {}",
+            frames.len(),
+            path.as_ref().display()
+        );
+        let th_handle = thread::spawn(move || {
+            let start = Instant::now();
+            for (time, frame) in frames {
+                if realtime {
+                    if let Some(wait) = time.checked_sub(start.elapsed()) {
+                        thread::sleep(wait);
+                    }
+                }
+                if tx.send((Instant::now(), frame)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { th_handle })
+    }
+
+    /// True once every frame has been sent
+    pub(crate) fn is_finished(&self) -> bool {
+        self.th_handle.is_finished()
+    }
+}
@@ -0,0 +1,267 @@
+/*
+ * File: firmware.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use autd3_core::hardware_defined::{CommandType, FPGA_CLOCK};
+
+/// Write state of a buffer transferred over multiple frames (modulation and sequence)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteState {
+    /// The last committed buffer is in use
+    Idle,
+    /// BEGIN has been received and END has not yet
+    Writing,
+}
+
+/// State of the emulated CPU visible to the viewer
+#[derive(Debug, Clone, Copy)]
+pub struct FirmwareState {
+    pub msg_id: Option<u8>,
+    /// Ack code returned in the RX data of every device
+    pub ack: u8,
+    /// Number of devices answering, i.e., that of the received geometry
+    pub num_devices: usize,
+    pub mod_state: WriteState,
+    pub mod_len: usize,
+    pub seq_state: WriteState,
+    pub seq_len: usize,
+}
+
+/// Version reported to the firmware version queries of the SDK, for both CPU and FPGA
+pub const EMULATED_FIRMWARE_VERSION: u16 = 0x0011;
+
+/// RX data returned by each device after processing a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RxData {
+    /// Response to a read command, e.g., a byte of the firmware version, or 0
+    pub ack: u8,
+    /// Echo of the msg id of the last processed frame
+    pub msg_id: u8,
+}
+
+impl RxData {
+    /// Serializes the RX data of all devices in order, as received by the SDK
+    pub fn to_bytes(rx_data: &[RxData]) -> Vec<u8> {
+        rx_data.iter().flat_map(|rx| [rx.ack, rx.msg_id]).collect()
+    }
+}
+
+/// Emulates the write cursor of the CPU firmware.
+///
+/// As on the real firmware, BEGIN rewinds the cursor, data frames are appended at the cursor
+/// and END commits everything written so far. So data sent without BEGIN is appended to the
+/// previously committed buffer instead of being rejected.
+pub(crate) struct BufferWriter<T> {
+    name: &'static str,
+    capacity: usize,
+    state: WriteState,
+    buf: Vec<T>,
+}
+
+impl<T: Clone> BufferWriter<T> {
+    pub(crate) fn new(name: &'static str, capacity: usize) -> Self {
+        Self {
+            name,
+            capacity,
+            state: WriteState::Idle,
+            buf: Vec::new(),
+        }
+    }
+
+    pub(crate) fn state(&self) -> WriteState {
+        self.state
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub(crate) fn begin(&mut self) {
+        if self.state == WriteState::Writing {
+            tracing::warn!(
+                "{}: BEGIN received before END, {} written entries are discarded",
+                self.name,
+                self.buf.len()
+            );
+        }
+        self.buf.clear();
+        self.state = WriteState::Writing;
+    }
+
+    pub(crate) fn write<I: IntoIterator<Item = T>>(&mut self, data: I) {
+        if self.state == WriteState::Idle {
+            tracing::warn!(
+                "{}: data received without BEGIN, appended to the previous buffer ({} entries)",
+                self.name,
+                self.buf.len()
+            );
+        }
+        let before = self.buf.len();
+        let mut overflow = 0;
+        for d in data {
+            if self.buf.len() < self.capacity {
+                self.buf.push(d);
+            } else {
+                overflow += 1;
+            }
+        }
+        if overflow > 0 {
+            tracing::warn!(
+                "{}: buffer overflow, {} entries beyond {} are dropped",
+                self.name,
+                overflow,
+                self.capacity
+            );
+        }
+        tracing::trace!(
+            "{}: write {} entries at {}",
+            self.name,
+            self.buf.len() - before,
+            before
+        );
+    }
+
+    pub(crate) fn end(&mut self) -> Vec<T> {
+        if self.state == WriteState::Idle {
+            tracing::warn!("{}: END received without BEGIN", self.name);
+        }
+        self.state = WriteState::Idle;
+        self.buf.clone()
+    }
+}
+
+/// Emulator extension carrying the viewer setting as JSON, sent on handoff
+pub(crate) const EMULATOR_SET_VIEWER_SETTING: u8 = 0xFE;
+/// Emulator extension carrying the transducer layout of each device as a JSON array of [crate::Layout]
+pub(crate) const EMULATOR_SET_LAYOUT: u8 = 0xFD;
+/// Emulator extension carrying the cycle (u16) of each transducer, as in the advanced mode
+pub(crate) const EMULATOR_SET_CYCLES: u8 = 0xFC;
+/// Emulator extension carrying the focal points commanded by the SDK as JSON, see [crate::CommandedFocus]
+pub(crate) const EMULATOR_SET_COMMANDED_FOCI: u8 = 0xFB;
+/// Emulator extension triggering the export preset named by the UTF-8 string after the header
+pub(crate) const EMULATOR_EXPORT: u8 = 0xFA;
+/// Emulator extension tagging the next gain with the UTF-8 string after the header, a tag per line
+pub(crate) const EMULATOR_TAG_GAIN: u8 = 0xF9;
+/// Emulator extension taking a screenshot named by the UTF-8 string after the header, which is
+/// timestamped if empty
+pub(crate) const EMULATOR_SCREENSHOT: u8 = 0xF8;
+/// Emulator extension starting a handoff with the simulation time in seconds (f64) after the
+/// header, after which the frames are processed regardless of the msg_id of the last frame
+pub(crate) const EMULATOR_HANDOFF: u8 = 0xF7;
+
+/// Frequency in Hz of a transducer driven with `cycle` of the FPGA clock of the decoded protocol,
+/// or `None` if `cycle` is 0
+pub fn cycle_to_frequency(cycle: u16) -> Option<f32> {
+    if cycle == 0 {
+        None
+    } else {
+        Some(FPGA_CLOCK as f32 / cycle as f32)
+    }
+}
+
+pub(crate) fn decode_command(op: u8) -> Option<CommandType> {
+    match op {
+        0x00 => Some(CommandType::Op),
+        0x02 => Some(CommandType::ReadCpuVerLsb),
+        0x03 => Some(CommandType::ReadCpuVerMsb),
+        0x04 => Some(CommandType::ReadFpgaVerLsb),
+        0x05 => Some(CommandType::ReadFpgaVerMsb),
+        0x06 => Some(CommandType::SeqMode),
+        0x09 => Some(CommandType::Clear),
+        0x0A => Some(CommandType::SetDelay),
+        0x0B => Some(CommandType::Pause),
+        0x0C => Some(CommandType::Resume),
+        0xFF => Some(CommandType::EmulatorSetGeometry),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_writer_commits_on_end() {
+        let mut writer = BufferWriter::new("test", 8);
+        assert_eq!(writer.state(), WriteState::Idle);
+        writer.begin();
+        writer.write([1, 2, 3]);
+        assert_eq!(writer.state(), WriteState::Writing);
+        assert_eq!(writer.len(), 3);
+        writer.write([4]);
+        assert_eq!(writer.end(), vec![1, 2, 3, 4]);
+        assert_eq!(writer.state(), WriteState::Idle);
+
+        // BEGIN rewinds the cursor, also before END
+        writer.begin();
+        writer.write([5, 6]);
+        writer.begin();
+        writer.write([7]);
+        assert_eq!(writer.end(), vec![7]);
+    }
+
+    #[test]
+    fn buffer_writer_appends_without_begin() {
+        let mut writer = BufferWriter::new("test", 8);
+        writer.begin();
+        writer.write([1, 2]);
+        writer.end();
+        writer.write([3]);
+        assert_eq!(writer.state(), WriteState::Idle);
+        assert_eq!(writer.end(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn buffer_writer_drops_the_overflow() {
+        let mut writer = BufferWriter::new("test", 4);
+        writer.begin();
+        writer.write([1, 2, 3]);
+        writer.write([4, 5, 6]);
+        assert_eq!(writer.end(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn rx_data_of_each_device() {
+        let rx_data = [RxData { ack: 1, msg_id: 2 }, RxData { ack: 3, msg_id: 4 }];
+        assert_eq!(RxData::to_bytes(&rx_data), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn frequency_of_the_cycle() {
+        assert_eq!(cycle_to_frequency(510), Some(40000.0));
+        assert_eq!(cycle_to_frequency(0), None);
+    }
+
+    #[test]
+    fn commands_of_the_opcodes() {
+        for cmd in [
+            CommandType::Op,
+            CommandType::ReadCpuVerLsb,
+            CommandType::ReadCpuVerMsb,
+            CommandType::ReadFpgaVerLsb,
+            CommandType::ReadFpgaVerMsb,
+            CommandType::SeqMode,
+            CommandType::Clear,
+            CommandType::SetDelay,
+            CommandType::Pause,
+            CommandType::Resume,
+            CommandType::EmulatorSetGeometry,
+        ] {
+            assert_eq!(decode_command(cmd as u8), Some(cmd));
+        }
+        // the extensions are handled before decoding the command
+        for op in EMULATOR_HANDOFF..=EMULATOR_SET_VIEWER_SETTING {
+            assert_eq!(decode_command(op), None);
+        }
+        assert_eq!(decode_command(0x01), None);
+    }
+}
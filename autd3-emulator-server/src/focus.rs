@@ -0,0 +1,75 @@
+/*
+ * File: focus.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::Vector3;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    M,
+    #[default]
+    Mm,
+    Um,
+}
+
+impl Unit {
+    fn to_mm(self) -> f32 {
+        match self {
+            Unit::M => 1000.0,
+            Unit::Mm => 1.0,
+            Unit::Um => 0.001,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawFocus {
+    pos: Vector3,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawFoci {
+    #[serde(default)]
+    unit: Unit,
+    foci: Vec<RawFocus>,
+}
+
+/// Focal point commanded by the SDK, sent as metadata alongside the drive data
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommandedFocus {
+    /// Position in mm
+    pub pos: Vector3,
+    pub label: String,
+}
+
+impl CommandedFocus {
+    /// Parses `{"unit": "m" | "mm" | "um", "foci": [{"pos": [x, y, z], "label": "..."}]}`.
+    /// `unit` defaults to mm and `label` to the index.
+    pub(crate) fn parse(json: &[u8]) -> Result<Vec<CommandedFocus>, serde_json::Error> {
+        let raw: RawFoci = serde_json::from_slice(json)?;
+        let scale = raw.unit.to_mm();
+        Ok(raw
+            .foci
+            .into_iter()
+            .enumerate()
+            .map(|(i, f)| CommandedFocus {
+                pos: vecmath::vec3_scale(f.pos, scale),
+                label: f.label.unwrap_or_else(|| format!("#{}", i)),
+            })
+            .collect())
+    }
+}
@@ -12,12 +12,18 @@
  */
 
 use std::{
-    net::UdpSocket,
-    sync::{mpsc::Sender, Arc, RwLock},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{mpsc::Sender, Arc, Mutex, RwLock},
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-const BUF_SIZE: usize = 65536;
+use crate::{firmware::EMULATOR_SET_VIEWER_SETTING, parser::COMMAND_OFFSET, spectator::read_frame};
+
+/// Size of the receive buffer, and hence the largest frame received and relayed to the spectators
+pub(crate) const BUF_SIZE: usize = 65536;
+/// Timeout of a read or write of a handoff, after which the other side is given up
+pub(crate) const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
 
 macro_rules! if_not_open_or_cannot_read {
     ($is_open:expr, $cnt:stmt) => {
@@ -37,54 +43,269 @@ macro_rules! write_rwlock {
     };
 }
 
+/// The viewer setting, e.g., of a handoff, may change every display setting, so it is taken only
+/// from the trusted peers. The last rejected is kept to be shown to the user.
+#[derive(Clone, Default)]
+struct ViewerSettingFilter {
+    trusted: Arc<RwLock<Vec<IpAddr>>>,
+    rejected: Arc<Mutex<Option<SocketAddr>>>,
+}
+
+impl ViewerSettingFilter {
+    fn accept(&self, frame: &[u8], src: SocketAddr) -> bool {
+        if frame.get(COMMAND_OFFSET) != Some(&EMULATOR_SET_VIEWER_SETTING) {
+            return true;
+        }
+        let trusted = self
+            .trusted
+            .read()
+            .is_ok_and(|trusted| trusted.contains(&src.ip()));
+        if !trusted {
+            tracing::warn!("viewer setting from {} ignored, not a trusted peer", src);
+            if let Ok(mut rejected) = self.rejected.lock() {
+                *rejected = Some(src);
+            }
+        }
+        trusted
+    }
+}
+
+// Reads the frames handed off over `stream` until the sender closes it, after which the stream
+// is closed here so that the sender knows every frame has been read
+fn receive_handoff(
+    mut stream: TcpStream,
+    tx: Sender<(Instant, Vec<u8>)>,
+    filter: ViewerSettingFilter,
+) {
+    let peer = match stream.peer_addr() {
+        Ok(peer) => peer,
+        Err(e) => {
+            tracing::warn!("failed to accept handoff: {}", e);
+            return;
+        }
+    };
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT)).ok();
+    thread::spawn(move || {
+        let mut received = 0;
+        loop {
+            match read_frame(&mut stream) {
+                Ok(Some(frame)) => {
+                    received += 1;
+                    if filter.accept(&frame, peer) && tx.send((Instant::now(), frame)).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => {
+                    tracing::info!("received {} handed off frames from {}", received, peer);
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "handoff from {} interrupted after {} frames: {}",
+                        peer,
+                        received,
+                        e
+                    );
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Receives the frames of the client over UDP, and those handed off by another emulator over
+/// TCP on the same port
 pub struct Interface {
     is_open: Arc<RwLock<bool>>,
     socket: UdpSocket,
+    listener: TcpListener,
     th_handle: Option<JoinHandle<()>>,
-    addr: String,
+    handoff_handle: Option<JoinHandle<()>>,
+    addr: SocketAddr,
+    handoff_addr: SocketAddr,
+    peer: Arc<Mutex<Option<SocketAddr>>>,
+    filter: ViewerSettingFilter,
 }
 
 impl Interface {
-    pub fn open(addr: &str) -> Result<Interface, std::io::Error> {
+    /// Binds the client at `addr`, and accepts the handoff on `handoff_host` at the same port
+    pub fn open(addr: &str, handoff_host: &str) -> Result<Interface, std::io::Error> {
         let socket = UdpSocket::bind(addr)?;
+        let addr = socket.local_addr()?;
+        let listener = TcpListener::bind((handoff_host, addr.port()))?;
+        let mut handoff_addr = listener.local_addr()?;
+        if handoff_addr.ip().is_unspecified() {
+            handoff_addr.set_ip(addr.ip());
+        }
         Ok(Interface {
             is_open: Arc::new(RwLock::new(false)),
             socket,
+            listener,
             th_handle: None,
-            addr: addr.to_owned(),
+            handoff_handle: None,
+            addr,
+            handoff_addr,
+            peer: Arc::new(Mutex::new(None)),
+            filter: ViewerSettingFilter::default(),
         })
     }
 
-    pub fn start(&mut self, tx: Sender<Vec<u8>>) -> Result<(), std::io::Error> {
+    /// Sets the hosts from which the viewer setting is accepted, none by default
+    pub fn set_trusted_peers(&self, peers: &[IpAddr]) {
+        write_rwlock!(self.filter.trusted, peers.to_vec());
+    }
+
+    /// Returns the sender of the viewer setting rejected last since the previous call
+    pub fn take_rejected_viewer_setting(&self) -> Option<SocketAddr> {
+        self.filter
+            .rejected
+            .lock()
+            .ok()
+            .and_then(|mut rejected| rejected.take())
+    }
+
+    pub fn start(&mut self, tx: Sender<(Instant, Vec<u8>)>) -> Result<(), std::io::Error> {
         let socket = self.socket.try_clone()?;
         write_rwlock!(self.is_open, true);
         let is_open = self.is_open.clone();
+        let peer = self.peer.clone();
+        let filter = self.filter.clone();
         let mut buf = [0; BUF_SIZE];
+        let handoff_tx = tx.clone();
         let th_handle = thread::spawn(move || loop {
             if_not_open_or_cannot_read!(is_open, break);
             match socket.recv_from(&mut buf) {
-                Ok((amt, _src)) => {
+                Ok((amt, src)) => {
                     let rx_buf = &mut buf[..amt];
-                    tx.send(rx_buf.to_vec()).ok();
+                    if !filter.accept(rx_buf, src) {
+                        continue;
+                    }
+                    if let Ok(mut peer) = peer.lock() {
+                        *peer = Some(src);
+                    }
+                    tx.send((Instant::now(), rx_buf.to_vec())).ok();
                 }
-                Err(e) => eprintln!("{}", e),
+                Err(e) => tracing::warn!("failed to receive: {}", e),
             }
         });
         self.th_handle = Some(th_handle);
 
+        let listener = self.listener.try_clone()?;
+        let is_open = self.is_open.clone();
+        let filter = self.filter.clone();
+        let handoff_handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if_not_open_or_cannot_read!(is_open, break);
+                match stream {
+                    Ok(stream) => receive_handoff(stream, handoff_tx.clone(), filter.clone()),
+                    Err(e) => tracing::warn!("failed to accept handoff: {}", e),
+                }
+            }
+        });
+        self.handoff_handle = Some(handoff_handle);
+
         Ok(())
     }
 
+    /// Sends `buf` back to the client from which the last frame was received
+    pub fn reply(&self, buf: &[u8]) {
+        let peer = match self.peer.lock().ok().and_then(|peer| *peer) {
+            Some(peer) => peer,
+            None => return,
+        };
+        if let Err(e) = self.socket.send_to(buf, peer) {
+            tracing::warn!("failed to send RX data to {}: {}", peer, e);
+        }
+    }
+
     pub fn close(&mut self) {
         if_not_open_or_cannot_read!(self.is_open, return);
         write_rwlock!(self.is_open, false);
 
-        let socket = UdpSocket::bind("127.0.0.1:8080").unwrap();
-        socket.send_to(&[0x00], &self.addr).unwrap();
+        // wake up the receiving thread blocked in recv_from
+        match UdpSocket::bind("127.0.0.1:0").and_then(|socket| socket.send_to(&[0x00], self.addr)) {
+            Ok(_) => {
+                if let Some(handle) = self.th_handle.take() {
+                    if handle.join().is_err() {
+                        tracing::error!("receiving thread panicked");
+                    }
+                }
+            }
+            Err(e) => tracing::error!("failed to stop receiving thread: {}", e),
+        }
+        // and the accepting thread blocked in accept
+        match TcpStream::connect(self.handoff_addr) {
+            Ok(_) => {
+                if let Some(handle) = self.handoff_handle.take() {
+                    if handle.join().is_err() {
+                        tracing::error!("handoff thread panicked");
+                    }
+                }
+            }
+            Err(e) => tracing::error!("failed to stop handoff thread: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::Shutdown,
+        sync::mpsc,
+    };
+
+    use super::*;
+    use crate::spectator::write_frame;
 
-        if let Some(handle) = self.th_handle.take() {
-            handle.join().unwrap();
-            self.th_handle = None;
+    #[test]
+    fn receive_handoff_in_order() {
+        let (tx, rx) = mpsc::channel();
+        let mut interface = Interface::open("127.0.0.1:0", "127.0.0.1").unwrap();
+        interface.set_trusted_peers(&[interface.addr.ip()]);
+        interface.start(tx).unwrap();
+
+        let mut viewer_setting = vec![0x00; 3];
+        viewer_setting[COMMAND_OFFSET] = EMULATOR_SET_VIEWER_SETTING;
+        viewer_setting.extend_from_slice(b"{}");
+        let frames = [vec![0x01, 0x00, 0x00], vec![0x02; 1000], viewer_setting];
+        let mut stream = TcpStream::connect(interface.handoff_addr).unwrap();
+        for frame in &frames {
+            write_frame(&mut stream, frame).unwrap();
         }
+        stream.flush().unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        // closed by the receiver after every frame is read
+        assert_eq!(stream.read(&mut [0; 1]).unwrap(), 0);
+
+        let received: Vec<_> = (0..frames.len())
+            .map(|_| rx.recv_timeout(HANDOFF_TIMEOUT).unwrap().1)
+            .collect();
+        interface.close();
+        assert_eq!(received, frames.to_vec());
+        assert_eq!(interface.take_rejected_viewer_setting(), None);
+    }
+
+    #[test]
+    fn reject_viewer_setting_from_untrusted_peer() {
+        let (tx, rx) = mpsc::channel();
+        let mut interface = Interface::open("127.0.0.1:0", "127.0.0.1").unwrap();
+        interface.start(tx).unwrap();
+
+        let mut viewer_setting = vec![0x00; 3];
+        viewer_setting[COMMAND_OFFSET] = EMULATOR_SET_VIEWER_SETTING;
+        let gain = vec![0x01, 0x00, 0x00];
+        let mut stream = TcpStream::connect(interface.handoff_addr).unwrap();
+        write_frame(&mut stream, &viewer_setting).unwrap();
+        write_frame(&mut stream, &gain).unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(stream.read(&mut [0; 1]).unwrap(), 0);
+
+        // the rest of the handoff is received
+        assert_eq!(rx.recv_timeout(HANDOFF_TIMEOUT).unwrap().1, gain);
+        assert!(rx.try_recv().is_err());
+        let rejected = interface.take_rejected_viewer_setting();
+        interface.close();
+        assert_eq!(rejected, stream.local_addr().ok());
     }
 }
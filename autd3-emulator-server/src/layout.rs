@@ -0,0 +1,122 @@
+/*
+ * File: layout.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use autd3_core::hardware_defined::{
+    AUTD_HEIGHT, AUTD_WIDTH, NUM_TRANS_X, NUM_TRANS_Y, TRANS_SPACING_MM,
+};
+use serde::{Deserialize, Serialize};
+
+/// Arrangement of the transducers on a device.
+///
+/// Positions are in mm on the device plane, x along `right` and y along `up` of the geometry.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Layout {
+    /// AUTD3 grid of NUM_TRANS_X × NUM_TRANS_Y without the missing transducers
+    #[default]
+    Autd3,
+    /// `nx` × `ny` grid with `pitch`, where `missing` lists the [x, y] indices to skip
+    Grid {
+        nx: usize,
+        ny: usize,
+        pitch: f32,
+        #[serde(default)]
+        missing: Vec<[usize; 2]>,
+    },
+    /// `num` transducers equally spaced on a circle of `radius` around the origin
+    Circle { num: usize, radius: f32 },
+    /// Arbitrary positions, e.g., sparse arrays
+    Points { points: Vec<[f32; 2]> },
+}
+
+impl Layout {
+    pub fn positions(&self) -> Vec<[f32; 2]> {
+        match self {
+            Layout::Autd3 => (0..NUM_TRANS_Y)
+                .flat_map(|y| (0..NUM_TRANS_X).map(move |x| (x, y)))
+                .filter(|&(x, y)| !autd3_core::hardware_defined::is_missing_transducer(x, y))
+                .map(|(x, y)| {
+                    [
+                        TRANS_SPACING_MM as f32 * x as f32,
+                        TRANS_SPACING_MM as f32 * y as f32,
+                    ]
+                })
+                .collect(),
+            Layout::Grid {
+                nx,
+                ny,
+                pitch,
+                missing,
+            } => (0..*ny)
+                .flat_map(|y| (0..*nx).map(move |x| (x, y)))
+                .filter(|&(x, y)| !missing.contains(&[x, y]))
+                .map(|(x, y)| [pitch * x as f32, pitch * y as f32])
+                .collect(),
+            Layout::Circle { num, radius } => (0..*num)
+                .map(|i| {
+                    let t = 2.0 * PI * i as f32 / *num as f32;
+                    [radius * t.cos(), radius * t.sin()]
+                })
+                .collect(),
+            Layout::Points { points } => points.clone(),
+        }
+    }
+
+    /// Lower left and upper right corners of the board on the device plane, which is the PCB of
+    /// AUTD3 around its transducers, or the bounding rectangle of the transducers with the margin
+    /// of half the spacing for the other layouts
+    pub fn board(&self) -> ([f32; 2], [f32; 2]) {
+        let positions = self.positions();
+        if positions.is_empty() {
+            return ([0.0, 0.0], [0.0, 0.0]);
+        }
+        let (min, max) = positions.iter().fold(
+            ([f32::MAX, f32::MAX], [f32::MIN, f32::MIN]),
+            |(min, max), p| {
+                (
+                    [min[0].min(p[0]), min[1].min(p[1])],
+                    [max[0].max(p[0]), max[1].max(p[1])],
+                )
+            },
+        );
+        let margin = match self {
+            Layout::Autd3 => [
+                (AUTD_WIDTH as f32 - (max[0] - min[0])) / 2.0,
+                (AUTD_HEIGHT as f32 - (max[1] - min[1])) / 2.0,
+            ],
+            _ => [self.min_spacing().unwrap_or(TRANS_SPACING_MM as f32) / 2.0; 2],
+        };
+        (
+            [min[0] - margin[0], min[1] - margin[1]],
+            [max[0] + margin[0], max[1] + margin[1]],
+        )
+    }
+
+    /// Minimum distance between two transducers, or `None` if there are less than two
+    pub fn min_spacing(&self) -> Option<f32> {
+        if let Layout::Autd3 = self {
+            return Some(TRANS_SPACING_MM as f32);
+        }
+        let positions = self.positions();
+        let mut min: Option<f32> = None;
+        for (i, a) in positions.iter().enumerate() {
+            for b in &positions[i + 1..] {
+                let d = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+                min = Some(min.map_or(d, |m| m.min(d)));
+            }
+        }
+        min
+    }
+}
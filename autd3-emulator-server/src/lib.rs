@@ -1,9 +1,23 @@
 mod autd_data;
+mod capture;
+mod firmware;
+mod focus;
 mod interface;
+mod layout;
 mod parser;
 mod server;
+mod snapshot;
+mod spectator;
 
 type Vector3 = vecmath::Vector3<f32>;
 
 pub use autd_data::*;
+pub use capture::read_capture;
+pub use firmware::{
+    cycle_to_frequency, FirmwareState, RxData, WriteState, EMULATED_FIRMWARE_VERSION,
+};
+pub use focus::CommandedFocus;
+pub use layout::Layout;
+pub use parser::Parser;
 pub use server::AutdServer;
+pub use snapshot::{HistoryLimits, HistoryUsage};
@@ -11,83 +11,253 @@
  *
  */
 
-use std::mem::size_of;
+use std::{convert::TryInto, mem::size_of};
 
-use autd3_core::hardware_defined::{RxGlobalControlFlags, RxGlobalHeader};
+use autd3_core::hardware_defined::{
+    CommandType, RxGlobalControlFlags, RxGlobalHeader, MOD_BUF_SIZE_MAX, POINT_SEQ_BUFFER_SIZE_MAX,
+};
 
 use crate::{
     autd_data::{AutdData, Gain, Geometry, Modulation},
-    DelayOffset, SeqFocus, Sequence, Vector3,
+    firmware::{
+        decode_command, BufferWriter, FirmwareState, RxData, EMULATED_FIRMWARE_VERSION,
+        EMULATOR_EXPORT, EMULATOR_HANDOFF, EMULATOR_SCREENSHOT, EMULATOR_SET_COMMANDED_FOCI,
+        EMULATOR_SET_CYCLES, EMULATOR_SET_LAYOUT, EMULATOR_SET_VIEWER_SETTING, EMULATOR_TAG_GAIN,
+    },
+    CommandedFocus, DelayOffset, Layout, SeqFocus, Sequence, Vector3,
 };
 
+const MSG_ID_OFFSET: usize = 0;
+const CTRL_FLAG_OFFSET: usize = 1;
+pub(crate) const COMMAND_OFFSET: usize = 2;
+const MOD_SIZE_OFFSET: usize = 3;
+const MOD_DATA_OFFSET: usize = 4;
+
+const GEOMETRY_SIZE: usize = 3 * size_of::<Vector3>();
+
 pub struct Parser {
+    msg_id: Option<u8>,
+    ack: u8,
     mod_div: u16,
-    mod_buf: Option<Vec<u8>>,
+    mod_buf: BufferWriter<u8>,
     wavelength: u16,
-    seq_buf: Option<Vec<(Vector3, u8)>>,
+    seq_buf: BufferWriter<(Vector3, u8)>,
     seq_div: u16,
+    layouts: Vec<Layout>,
+    geometries: Option<Vec<Geometry>>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Parser {
     pub fn new() -> Self {
         Self {
+            msg_id: None,
+            ack: 0,
             mod_div: 10,
-            mod_buf: None,
+            mod_buf: BufferWriter::new("modulation", MOD_BUF_SIZE_MAX),
             wavelength: 8500,
-            seq_buf: None,
+            seq_buf: BufferWriter::new("sequence", POINT_SEQ_BUFFER_SIZE_MAX),
             seq_div: 0,
+            layouts: Vec::new(),
+            geometries: None,
         }
     }
 
-    pub fn parse(&mut self, raw_buf: Vec<u8>) -> Vec<AutdData> {
+    pub fn state(&self) -> FirmwareState {
+        FirmwareState {
+            msg_id: self.msg_id,
+            ack: self.ack,
+            num_devices: self.num_devices(),
+            mod_state: self.mod_buf.state(),
+            mod_len: self.mod_buf.len(),
+            seq_state: self.seq_buf.state(),
+            seq_len: self.seq_buf.len(),
+        }
+    }
+
+    fn num_devices(&self) -> usize {
+        self.geometries
+            .as_ref()
+            .map_or(0, |geometries| geometries.len())
+    }
+
+    /// RX data of each device, as many as the devices of the received geometry
+    pub fn rx_data(&self) -> Vec<RxData> {
+        vec![
+            RxData {
+                ack: self.ack,
+                msg_id: self.msg_id.unwrap_or(0),
+            };
+            self.num_devices()
+        ]
+    }
+
+    pub fn parse(&mut self, raw_buf: &[u8]) -> Vec<AutdData> {
         let mut res = Vec::new();
 
-        let (cmd, ctrl_flag) = unsafe {
-            let header = raw_buf.as_ptr() as *const RxGlobalHeader;
-            ((*header).command, (*header).ctrl_flag)
+        if raw_buf.len() < size_of::<RxGlobalHeader>() {
+            tracing::warn!(
+                "dropped {} bytes frame shorter than header ({} bytes)",
+                raw_buf.len(),
+                size_of::<RxGlobalHeader>()
+            );
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_SET_VIEWER_SETTING {
+            res.push(AutdData::ViewerSetting(
+                raw_buf[size_of::<RxGlobalHeader>()..].to_vec(),
+            ));
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_HANDOFF {
+            // the handed off frames start over, and the first may have the msg_id of the last
+            // frame received here
+            self.msg_id = None;
+            match raw_buf[size_of::<RxGlobalHeader>()..].try_into() {
+                Ok(time) => res.push(AutdData::Handoff(f64::from_le_bytes(time))),
+                Err(_) => tracing::warn!("invalid handoff time"),
+            }
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_SET_CYCLES {
+            let cycles = raw_buf[size_of::<RxGlobalHeader>()..]
+                .chunks_exact(2)
+                .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+                .collect();
+            res.push(AutdData::Cycles(cycles));
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_SET_COMMANDED_FOCI {
+            match CommandedFocus::parse(&raw_buf[size_of::<RxGlobalHeader>()..]) {
+                Ok(foci) => res.push(AutdData::CommandedFoci(foci)),
+                Err(e) => tracing::warn!("invalid commanded foci: {}", e),
+            }
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_EXPORT {
+            match std::str::from_utf8(&raw_buf[size_of::<RxGlobalHeader>()..]) {
+                Ok(name) => res.push(AutdData::Export(name.trim().to_owned())),
+                Err(e) => tracing::warn!("invalid export preset name: {}", e),
+            }
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_SCREENSHOT {
+            match std::str::from_utf8(&raw_buf[size_of::<RxGlobalHeader>()..]) {
+                Ok(name) => res.push(AutdData::Screenshot(name.trim().to_owned())),
+                Err(e) => tracing::warn!("invalid screenshot name: {}", e),
+            }
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_TAG_GAIN {
+            match std::str::from_utf8(&raw_buf[size_of::<RxGlobalHeader>()..]) {
+                Ok(tags) => res.push(AutdData::Tags(
+                    tags.lines()
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_owned)
+                        .collect(),
+                )),
+                Err(e) => tracing::warn!("invalid gain tags: {}", e),
+            }
+            return res;
+        }
+
+        if raw_buf[COMMAND_OFFSET] == EMULATOR_SET_LAYOUT {
+            match serde_json::from_slice(&raw_buf[size_of::<RxGlobalHeader>()..]) {
+                Ok(layouts) => {
+                    self.layouts = layouts;
+                    // re-layout the devices already received
+                    if let Some(geometries) = self.geometries.as_mut() {
+                        Self::apply_layouts(geometries, &self.layouts);
+                        res.push(AutdData::Geometries(geometries.clone()));
+                    }
+                }
+                Err(e) => tracing::warn!("invalid layout: {}", e),
+            }
+            return res;
+        }
+
+        let msg_id = raw_buf[MSG_ID_OFFSET];
+        let ctrl_flag = RxGlobalControlFlags::from_bits_truncate(raw_buf[CTRL_FLAG_OFFSET]);
+        let cmd = match decode_command(raw_buf[COMMAND_OFFSET]) {
+            Some(cmd) => cmd,
+            None => {
+                tracing::warn!("unknown command 0x{:02X}", raw_buf[COMMAND_OFFSET]);
+                return res;
+            }
+        };
+
+        // The firmware only processes a frame whose msg_id differs from the previous one.
+        // Geometry is an emulator extension and is not subject to this.
+        if cmd != CommandType::EmulatorSetGeometry {
+            if self.msg_id == Some(msg_id) {
+                tracing::warn!(
+                    "frame ignored because msg_id 0x{:02X} is the same as the previous one ({:?})",
+                    msg_id,
+                    cmd
+                );
+                return res;
+            }
+            self.msg_id = Some(msg_id);
+        }
+        let [version_lsb, version_msb] = EMULATED_FIRMWARE_VERSION.to_le_bytes();
+        self.ack = match cmd {
+            CommandType::ReadCpuVerLsb | CommandType::ReadFpgaVerLsb => version_lsb,
+            CommandType::ReadCpuVerMsb | CommandType::ReadFpgaVerMsb => version_msb,
+            _ => 0,
         };
 
         res.push(AutdData::CtrlFlag(ctrl_flag));
+        let body = &raw_buf[size_of::<RxGlobalHeader>()..];
         match cmd {
-            autd3_core::hardware_defined::CommandType::Clear => res.push(AutdData::Clear),
-            autd3_core::hardware_defined::CommandType::Op => {
+            CommandType::Clear => {
+                self.mod_buf = BufferWriter::new("modulation", MOD_BUF_SIZE_MAX);
+                self.seq_buf = BufferWriter::new("sequence", POINT_SEQ_BUFFER_SIZE_MAX);
+                res.push(AutdData::Clear)
+            }
+            CommandType::Op => {
                 res.push(AutdData::Resume);
-                if let Some(modulation) = self.parse_as_modulation(&raw_buf) {
+                if let Some(modulation) = self.parse_as_modulation(raw_buf, ctrl_flag) {
                     res.push(AutdData::Modulation(modulation));
                 }
 
-                if raw_buf.len() > size_of::<RxGlobalHeader>() {
-                    let gain = Self::parse_as_gain(&raw_buf[size_of::<RxGlobalHeader>()..]);
+                if !body.is_empty() {
+                    let gain = Self::parse_as_gain(body);
                     res.push(AutdData::Gain(gain));
                 }
             }
-            autd3_core::hardware_defined::CommandType::ReadCpuVerLsb => {
-                res.push(AutdData::RequestCpuVerLsb)
-            }
-            autd3_core::hardware_defined::CommandType::ReadCpuVerMsb => {
-                res.push(AutdData::RequestCpuVerMsb)
-            }
-            autd3_core::hardware_defined::CommandType::ReadFpgaVerLsb => {
-                res.push(AutdData::RequestFpgaVerLsb)
-            }
-            autd3_core::hardware_defined::CommandType::ReadFpgaVerMsb => {
-                res.push(AutdData::RequestFpgaVerMsb)
-            }
-            autd3_core::hardware_defined::CommandType::SeqMode => {
-                if let Some(sequence) = self.parse_as_sequence(&raw_buf) {
+            CommandType::ReadCpuVerLsb => res.push(AutdData::RequestCpuVerLsb),
+            CommandType::ReadCpuVerMsb => res.push(AutdData::RequestCpuVerMsb),
+            CommandType::ReadFpgaVerLsb => res.push(AutdData::RequestFpgaVerLsb),
+            CommandType::ReadFpgaVerMsb => res.push(AutdData::RequestFpgaVerMsb),
+            CommandType::SeqMode => {
+                if let Some(sequence) = self.parse_as_sequence(body, ctrl_flag) {
                     res.push(AutdData::Sequence(sequence));
                     res.push(AutdData::Resume);
                 }
             }
-            autd3_core::hardware_defined::CommandType::SetDelay => {
-                let delay_enable =
-                    Self::parse_as_delay_enable(&raw_buf[size_of::<RxGlobalHeader>()..]);
+            CommandType::SetDelay => {
+                let delay_enable = Self::parse_as_delay_enable(body);
                 res.push(AutdData::DelayOffset(delay_enable));
             }
-            autd3_core::hardware_defined::CommandType::Pause => res.push(AutdData::Pause),
-            autd3_core::hardware_defined::CommandType::Resume => res.push(AutdData::Resume),
-            autd3_core::hardware_defined::CommandType::EmulatorSetGeometry => {
-                let geo = Self::parse_as_geometry(&raw_buf[size_of::<RxGlobalHeader>()..]);
+            CommandType::Pause => res.push(AutdData::Pause),
+            CommandType::Resume => res.push(AutdData::Resume),
+            CommandType::EmulatorSetGeometry => {
+                let mut geo = Self::parse_as_geometry(body);
+                Self::apply_layouts(&mut geo, &self.layouts);
+                self.geometries = Some(geo.clone());
                 res.push(AutdData::Geometries(geo))
             }
         }
@@ -97,81 +267,127 @@ impl Parser {
 
     fn parse_as_geometry(buf: &[u8]) -> Vec<Geometry> {
         let mut res = Vec::new();
-        for bytes in buf.chunks_exact(std::mem::size_of::<Geometry>()) {
+        let chunks = buf.chunks_exact(GEOMETRY_SIZE);
+        if !chunks.remainder().is_empty() {
+            tracing::warn!(
+                "geometry data has {} trailing bytes",
+                chunks.remainder().len()
+            );
+        }
+        for bytes in chunks {
             let origin = to_vec3(&bytes[0..12]);
             let right = to_vec3(&bytes[12..24]);
             let up = to_vec3(&bytes[24..36]);
-            res.push(Geometry { origin, right, up });
+            res.push(Geometry {
+                origin,
+                right,
+                up,
+                layout: Layout::Autd3,
+            });
         }
         res
     }
 
-    fn parse_as_sequence(&mut self, buf: &[u8]) -> Option<Sequence> {
-        unsafe {
-            let header = buf.as_ptr() as *const RxGlobalHeader;
-            let seq_begin = (*header)
-                .ctrl_flag
-                .contains(RxGlobalControlFlags::SEQ_BEGIN);
-            let seq_end = (*header).ctrl_flag.contains(RxGlobalControlFlags::SEQ_END);
-            let cursor = buf.as_ptr().add(size_of::<RxGlobalHeader>()) as *const u16;
-            let seq_size = cursor.read();
-            let offset = if seq_begin {
-                self.seq_buf = Some(vec![]);
-                self.seq_div = cursor.add(1).read();
-                self.wavelength = cursor.add(2).read();
-                5
-            } else {
-                1
-            };
-            let mut cursor = cursor.add(offset) as *const SeqFocus;
-            for _ in 0..seq_size {
-                let x = (*cursor).x(self.wavelength);
-                let y = (*cursor).y(self.wavelength);
-                let z = (*cursor).z(self.wavelength);
-                let duty = (*cursor).amp();
-                if let Some(buf) = &mut self.seq_buf {
-                    buf.push(([x, y, z], duty));
-                }
-                cursor = cursor.add(1);
-            }
+    /// Devices without a corresponding entry in `layouts` are AUTD3
+    fn apply_layouts(geometries: &mut [Geometry], layouts: &[Layout]) {
+        for (i, geometry) in geometries.iter_mut().enumerate() {
+            geometry.layout = layouts.get(i).cloned().unwrap_or_default();
+        }
+    }
 
-            if seq_end {
-                Some(Sequence {
-                    seq_div: self.seq_div,
-                    seq_data: self.seq_buf.take().unwrap(),
-                })
-            } else {
-                None
-            }
+    fn parse_as_sequence(
+        &mut self,
+        buf: &[u8],
+        ctrl_flag: RxGlobalControlFlags,
+    ) -> Option<Sequence> {
+        let seq_begin = ctrl_flag.contains(RxGlobalControlFlags::SEQ_BEGIN);
+        let seq_end = ctrl_flag.contains(RxGlobalControlFlags::SEQ_END);
+
+        let words: Vec<u16> = buf
+            .chunks_exact(2)
+            .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+            .collect();
+        let header_len = if seq_begin { 5 } else { 1 };
+        if words.len() < header_len {
+            tracing::warn!("sequence frame is too short ({} bytes)", buf.len());
+            return None;
+        }
+
+        let seq_size = words[0] as usize;
+        if seq_begin {
+            self.seq_buf.begin();
+            self.seq_div = words[1];
+            self.wavelength = words[2];
+        }
+
+        let foci = &words[header_len..];
+        let num_foci = foci.len() / 4;
+        if seq_size > num_foci {
+            tracing::warn!(
+                "sequence frame declares {} points but contains only {}",
+                seq_size,
+                num_foci
+            );
+        }
+        if seq_size > 0 {
+            let wavelength = self.wavelength;
+            self.seq_buf
+                .write(foci.chunks_exact(4).take(seq_size).map(|f| {
+                    let focus = SeqFocus::new([f[0], f[1], f[2], f[3]]);
+                    (
+                        [
+                            focus.x(wavelength),
+                            focus.y(wavelength),
+                            focus.z(wavelength),
+                        ],
+                        focus.amp(),
+                    )
+                }));
+        }
+
+        if seq_end {
+            Some(Sequence {
+                seq_div: self.seq_div,
+                seq_data: self.seq_buf.end(),
+            })
+        } else {
+            None
         }
     }
 
-    fn parse_as_modulation(&mut self, buf: &[u8]) -> Option<Modulation> {
-        unsafe {
-            let header = buf.as_ptr() as *const RxGlobalHeader;
-            let mod_size = (*header).mod_size as usize;
-            let offset = if (*header)
-                .ctrl_flag
-                .contains(RxGlobalControlFlags::MOD_BEGIN)
-            {
-                self.mod_buf = Some(vec![]);
-                self.mod_div = u16::from_ne_bytes([(*header).mod_data[0], (*header).mod_data[1]]);
-                2
-            } else {
-                0
-            };
-            if let Some(buf) = &mut self.mod_buf {
-                buf.extend_from_slice(&(*header).mod_data[offset..(offset + mod_size)]);
-            }
+    fn parse_as_modulation(
+        &mut self,
+        buf: &[u8],
+        ctrl_flag: RxGlobalControlFlags,
+    ) -> Option<Modulation> {
+        let mod_size = buf[MOD_SIZE_OFFSET] as usize;
+        let mod_data = &buf[MOD_DATA_OFFSET..size_of::<RxGlobalHeader>()];
+        let offset = if ctrl_flag.contains(RxGlobalControlFlags::MOD_BEGIN) {
+            self.mod_buf.begin();
+            self.mod_div = u16::from_ne_bytes([mod_data[0], mod_data[1]]);
+            2
+        } else {
+            0
+        };
+        if offset + mod_size > mod_data.len() {
+            tracing::warn!(
+                "modulation size {} exceeds frame capacity {}",
+                mod_size,
+                mod_data.len() - offset
+            );
+        }
+        let end = (offset + mod_size).min(mod_data.len());
+        if mod_size > 0 {
+            self.mod_buf.write(mod_data[offset..end].iter().copied());
+        }
 
-            if (*header).ctrl_flag.contains(RxGlobalControlFlags::MOD_END) {
-                Some(Modulation {
-                    mod_div: self.mod_div,
-                    mod_data: self.mod_buf.take().unwrap(),
-                })
-            } else {
-                None
-            }
+        if ctrl_flag.contains(RxGlobalControlFlags::MOD_END) {
+            Some(Modulation {
+                mod_div: self.mod_div,
+                mod_data: self.mod_buf.end(),
+            })
+        } else {
+            None
         }
     }
 
@@ -204,3 +420,275 @@ fn to_vec3(buf: &[u8]) -> Vector3 {
 fn to_f32(buf: &[u8]) -> f32 {
     f32::from_ne_bytes([buf[0], buf[1], buf[2], buf[3]])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::firmware::WriteState;
+
+    const HEADER_SIZE: usize = size_of::<RxGlobalHeader>();
+
+    fn frame(msg_id: u8, ctrl_flag: RxGlobalControlFlags, cmd: u8, body: &[u8]) -> Vec<u8> {
+        let mut buf = vec![0; HEADER_SIZE];
+        buf[MSG_ID_OFFSET] = msg_id;
+        buf[CTRL_FLAG_OFFSET] = ctrl_flag.bits();
+        buf[COMMAND_OFFSET] = cmd;
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    fn mod_frame(msg_id: u8, ctrl_flag: RxGlobalControlFlags, mod_data: &[u8]) -> Vec<u8> {
+        let mut buf = frame(msg_id, ctrl_flag, CommandType::Op as u8, &[]);
+        buf[MOD_SIZE_OFFSET] = mod_data.len() as u8;
+        let offset = if ctrl_flag.contains(RxGlobalControlFlags::MOD_BEGIN) {
+            buf[MOD_DATA_OFFSET..MOD_DATA_OFFSET + 2].copy_from_slice(&10u16.to_ne_bytes());
+            2
+        } else {
+            0
+        };
+        let start = MOD_DATA_OFFSET + offset;
+        buf[start..start + mod_data.len()].copy_from_slice(mod_data);
+        buf
+    }
+
+    // Positive fixed-point coordinates in 1/256 of the wavelength, as the SDK packs them
+    fn seq_focus([x, y, z]: [u32; 3], amp: u8) -> [u16; 4] {
+        [
+            x as u16,
+            ((x >> 16) & 0x0001) as u16 | ((y & 0x3FFF) << 2) as u16,
+            ((y >> 14) & 0x0007) as u16 | ((z & 0x0FFF) << 4) as u16,
+            ((z >> 12) & 0x001F) as u16 | (amp as u16) << 6,
+        ]
+    }
+
+    fn seq_frame(msg_id: u8, ctrl_flag: RxGlobalControlFlags, foci: &[[u16; 4]]) -> Vec<u8> {
+        let mut words = vec![foci.len() as u16];
+        if ctrl_flag.contains(RxGlobalControlFlags::SEQ_BEGIN) {
+            // division, wavelength in um, and the padding
+            words.extend_from_slice(&[4, 8500, 0, 0]);
+        }
+        words.extend(foci.iter().flatten());
+        let body: Vec<u8> = words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+        frame(
+            msg_id,
+            ctrl_flag | RxGlobalControlFlags::SEQ_MODE,
+            CommandType::SeqMode as u8,
+            &body,
+        )
+    }
+
+    fn geometry_frame(msg_id: u8) -> Vec<u8> {
+        let body: Vec<u8> = [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]]
+            .iter()
+            .flatten()
+            .flat_map(|v: &f32| v.to_ne_bytes())
+            .collect();
+        frame(
+            msg_id,
+            RxGlobalControlFlags::NONE,
+            CommandType::EmulatorSetGeometry as u8,
+            &body,
+        )
+    }
+
+    #[test]
+    fn modulation_over_frames() {
+        let mut parser = Parser::new();
+        let res = parser.parse(&mod_frame(1, RxGlobalControlFlags::MOD_BEGIN, &[1, 2, 3]));
+        assert!(!res.iter().any(|d| matches!(d, AutdData::Modulation(_))));
+        let state = parser.state();
+        assert_eq!(state.mod_state, WriteState::Writing);
+        assert_eq!(state.mod_len, 3);
+
+        let res = parser.parse(&mod_frame(2, RxGlobalControlFlags::MOD_END, &[4, 5]));
+        let modulation = res.into_iter().find_map(|d| match d {
+            AutdData::Modulation(m) => Some(m),
+            _ => None,
+        });
+        let modulation = modulation.unwrap();
+        assert_eq!(modulation.mod_div, 10);
+        assert_eq!(modulation.mod_data, vec![1, 2, 3, 4, 5]);
+        assert_eq!(parser.state().mod_state, WriteState::Idle);
+    }
+
+    #[test]
+    fn modulation_without_begin_is_appended() {
+        let mut parser = Parser::new();
+        let both = RxGlobalControlFlags::MOD_BEGIN | RxGlobalControlFlags::MOD_END;
+        parser.parse(&mod_frame(1, both, &[1, 2]));
+        let res = parser.parse(&mod_frame(2, RxGlobalControlFlags::MOD_END, &[3]));
+        let modulation = res.into_iter().find_map(|d| match d {
+            AutdData::Modulation(m) => Some(m),
+            _ => None,
+        });
+        assert_eq!(modulation.unwrap().mod_data, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn sequence_over_frames() {
+        let mut parser = Parser::new();
+        let first = seq_focus([256, 512, 2560], 0xFF);
+        let second = seq_focus([0, 0x10000, 0x10000], 0x80);
+        let res = parser.parse(&seq_frame(1, RxGlobalControlFlags::SEQ_BEGIN, &[first]));
+        assert!(!res.iter().any(|d| matches!(d, AutdData::Sequence(_))));
+        assert_eq!(parser.state().seq_state, WriteState::Writing);
+        assert_eq!(parser.state().seq_len, 1);
+
+        let res = parser.parse(&seq_frame(2, RxGlobalControlFlags::SEQ_END, &[second]));
+        let seq = res.into_iter().find_map(|d| match d {
+            AutdData::Sequence(seq) => Some(seq),
+            _ => None,
+        });
+        let seq = seq.unwrap();
+        assert_eq!(seq.seq_div, 4);
+        assert_eq!(
+            seq.seq_data,
+            vec![
+                ([8.5, 17.0, 85.0], 0xFF),
+                ([0.0, 8.5 * 256.0, 8.5 * 256.0], 0x80)
+            ]
+        );
+        assert_eq!(parser.state().seq_state, WriteState::Idle);
+    }
+
+    #[test]
+    fn clear_resets_the_writers() {
+        let mut parser = Parser::new();
+        parser.parse(&mod_frame(1, RxGlobalControlFlags::MOD_BEGIN, &[1, 2, 3]));
+        let focus = seq_focus([256, 0, 0], 0xFF);
+        parser.parse(&seq_frame(2, RxGlobalControlFlags::SEQ_BEGIN, &[focus]));
+        let res = parser.parse(&frame(
+            3,
+            RxGlobalControlFlags::NONE,
+            CommandType::Clear as u8,
+            &[],
+        ));
+        assert!(res.iter().any(|d| matches!(d, AutdData::Clear)));
+        let state = parser.state();
+        assert_eq!(state.mod_state, WriteState::Idle);
+        assert_eq!(state.mod_len, 0);
+        assert_eq!(state.seq_state, WriteState::Idle);
+        assert_eq!(state.seq_len, 0);
+    }
+
+    #[test]
+    fn duplicate_msg_id_is_ignored() {
+        let mut parser = Parser::new();
+        let pause = frame(1, RxGlobalControlFlags::NONE, CommandType::Pause as u8, &[]);
+        assert!(parser
+            .parse(&pause)
+            .iter()
+            .any(|d| matches!(d, AutdData::Pause)));
+        assert!(parser.parse(&pause).is_empty());
+        assert_eq!(parser.state().msg_id, Some(1));
+
+        // the geometry is processed with the same msg_id, and does not update it
+        let res = parser.parse(&geometry_frame(1));
+        assert!(res
+            .iter()
+            .any(|d| matches!(d, AutdData::Geometries(g) if g.len() == 1)));
+        let res = parser.parse(&geometry_frame(2));
+        assert!(res.iter().any(|d| matches!(d, AutdData::Geometries(_))));
+        assert_eq!(parser.state().msg_id, Some(1));
+        assert_eq!(parser.state().num_devices, 1);
+        assert!(parser.parse(&pause).is_empty());
+
+        // the handed off frames start over
+        let time = 1.5f64.to_le_bytes();
+        let res = parser.parse(&frame(
+            1,
+            RxGlobalControlFlags::NONE,
+            EMULATOR_HANDOFF,
+            &time,
+        ));
+        assert!(matches!(res[..], [AutdData::Handoff(t)] if t == 1.5));
+        assert_eq!(parser.state().msg_id, None);
+        assert!(parser
+            .parse(&pause)
+            .iter()
+            .any(|d| matches!(d, AutdData::Pause)));
+    }
+
+    #[test]
+    fn version_in_the_ack() {
+        let mut parser = Parser::new();
+        parser.parse(&geometry_frame(0));
+        let [lsb, msb] = EMULATED_FIRMWARE_VERSION.to_le_bytes();
+        for (msg_id, cmd, ack) in [
+            (1, CommandType::ReadCpuVerLsb, lsb),
+            (2, CommandType::ReadCpuVerMsb, msb),
+            (3, CommandType::ReadFpgaVerLsb, lsb),
+            (4, CommandType::ReadFpgaVerMsb, msb),
+            (5, CommandType::Pause, 0),
+        ] {
+            parser.parse(&frame(msg_id, RxGlobalControlFlags::NONE, cmd as u8, &[]));
+            assert_eq!(parser.rx_data(), vec![RxData { ack, msg_id }]);
+        }
+    }
+
+    #[test]
+    fn extension_opcodes() {
+        let mut parser = Parser::new();
+        let parse = |parser: &mut Parser, cmd: u8, body: &[u8]| {
+            // the extensions are processed regardless of the msg_id
+            parser.parse(&frame(0, RxGlobalControlFlags::NONE, cmd, body))
+        };
+
+        let res = parse(&mut parser, EMULATOR_SET_VIEWER_SETTING, b"{}");
+        assert!(matches!(&res[..], [AutdData::ViewerSetting(json)] if json == b"{}"));
+
+        // the layout applies to the devices received before and after it
+        let layout = br#"[{"type": "circle", "num": 8, "radius": 30.0}]"#;
+        assert!(parse(&mut parser, EMULATOR_SET_LAYOUT, layout).is_empty());
+        let res = parser.parse(&geometry_frame(0));
+        assert!(matches!(
+            &res[..],
+            [AutdData::CtrlFlag(_), AutdData::Geometries(g)]
+                if g[0].layout == Layout::Circle { num: 8, radius: 30.0 }
+        ));
+        let res = parse(&mut parser, EMULATOR_SET_LAYOUT, b"[]");
+        assert!(matches!(&res[..], [AutdData::Geometries(g)] if g[0].layout == Layout::Autd3));
+
+        let cycles: Vec<u8> = [4096u16, 2048]
+            .iter()
+            .flat_map(|c| c.to_ne_bytes())
+            .collect();
+        let res = parse(&mut parser, EMULATOR_SET_CYCLES, &cycles);
+        assert!(matches!(&res[..], [AutdData::Cycles(c)] if c == &[4096, 2048]));
+
+        let foci = br#"{"unit": "m", "foci": [{"pos": [0.0, 0.0, 0.15]}]}"#;
+        let res = parse(&mut parser, EMULATOR_SET_COMMANDED_FOCI, foci);
+        assert!(matches!(
+            &res[..],
+            [AutdData::CommandedFoci(f)] if f[0].pos == [0.0, 0.0, 150.0] && f[0].label == "#0"
+        ));
+
+        let res = parse(&mut parser, EMULATOR_EXPORT, b" preset \n");
+        assert!(matches!(&res[..], [AutdData::Export(name)] if name == "preset"));
+
+        let res = parse(&mut parser, EMULATOR_TAG_GAIN, b"focus\n\n  trial 1 \n");
+        assert!(matches!(&res[..], [AutdData::Tags(tags)] if tags == &["focus", "trial 1"]));
+
+        let res = parse(&mut parser, EMULATOR_SCREENSHOT, b"");
+        assert!(matches!(&res[..], [AutdData::Screenshot(name)] if name.is_empty()));
+
+        // the malformed extensions are dropped
+        assert!(parse(&mut parser, EMULATOR_HANDOFF, &[0; 4]).is_empty());
+        assert!(parse(&mut parser, EMULATOR_SET_LAYOUT, b"[").is_empty());
+        assert!(parse(&mut parser, EMULATOR_SET_COMMANDED_FOCI, b"{}").is_empty());
+        assert!(parse(&mut parser, EMULATOR_EXPORT, &[0xFF]).is_empty());
+
+        assert_eq!(parser.state().msg_id, None);
+    }
+
+    #[test]
+    fn short_and_unknown_frames_are_dropped() {
+        let mut parser = Parser::new();
+        assert!(parser.parse(&[0; HEADER_SIZE - 1]).is_empty());
+        assert!(parser
+            .parse(&frame(1, RxGlobalControlFlags::NONE, 0x01, &[]))
+            .is_empty());
+        assert_eq!(parser.state().msg_id, None);
+    }
+}
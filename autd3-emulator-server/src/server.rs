@@ -11,37 +11,290 @@
  *
  */
 
-use std::sync::mpsc::{self, Receiver};
+use std::{
+    collections::VecDeque,
+    io::Read,
+    net::{IpAddr, Shutdown, SocketAddr, TcpStream},
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-use crate::{autd_data::AutdData, interface::Interface, parser::Parser};
+use autd3_core::hardware_defined::RxGlobalHeader;
+
+use crate::{
+    autd_data::AutdData,
+    capture::{CaptureReplay, CaptureWriter},
+    firmware::{
+        FirmwareState, RxData, EMULATOR_HANDOFF, EMULATOR_SET_LAYOUT, EMULATOR_SET_VIEWER_SETTING,
+    },
+    interface::{Interface, HANDOFF_TIMEOUT},
+    parser::Parser,
+    snapshot::{HistoryLimits, HistoryUsage, Snapshot},
+    spectator::{write_frame, SpectatorClient, SpectatorHost},
+    Layout,
+};
 
 pub struct AutdServer {
-    interface: Interface,
-    rx: Receiver<Vec<u8>>,
+    interface: Option<Interface>,
+    spectator_client: Option<SpectatorClient>,
+    spectator_host: Option<SpectatorHost>,
+    rx: Receiver<(Instant, Vec<u8>)>,
+    pending: VecDeque<(Instant, Vec<u8>)>,
+    injected: Vec<AutdData>,
+    latency: Duration,
     parser: Parser,
+    snapshot: Arc<Mutex<Snapshot>>,
+    capture: Option<CaptureWriter>,
+    replay: Option<CaptureReplay>,
+    replay_finished: bool,
 }
 
 impl AutdServer {
-    pub fn new(addr: &str) -> Result<Self, std::io::Error> {
+    pub fn new(addr: &str, handoff_host: &str) -> Result<Self, std::io::Error> {
         let (tx, rx) = mpsc::channel();
-        let mut interface = Interface::open(addr)?;
+        let mut interface = Interface::open(addr, handoff_host)?;
         interface.start(tx)?;
+        tracing::info!("listening on {}", addr);
+
+        let mut server = Self::with_receiver(rx);
+        server.interface = Some(interface);
+        Ok(server)
+    }
+
+    /// Connects to the spectator host of another emulator instead of listening to a client
+    pub fn spectate(addr: &str) -> Result<Self, std::io::Error> {
+        let (tx, rx) = mpsc::channel();
+        let spectator_client = SpectatorClient::connect(addr, tx)?;
+
+        let mut server = Self::with_receiver(rx);
+        server.spectator_client = Some(spectator_client);
+        Ok(server)
+    }
 
-        Ok(Self {
-            interface,
+    /// Replays a capture file written by [AutdServer::start_capture] instead of listening to a
+    /// client. Frames are fed with the recorded timing if `realtime`, otherwise all at once.
+    pub fn replay<P: AsRef<Path>>(path: P, realtime: bool) -> Result<Self, std::io::Error> {
+        let (tx, rx) = mpsc::channel();
+        let replay = CaptureReplay::start(path, realtime, tx)?;
+
+        let mut server = Self::with_receiver(rx);
+        server.replay = Some(replay);
+        Ok(server)
+    }
+
+    fn with_receiver(rx: Receiver<(Instant, Vec<u8>)>) -> Self {
+        Self {
+            interface: None,
+            spectator_client: None,
+            spectator_host: None,
             rx,
+            pending: VecDeque::new(),
+            injected: Vec::new(),
+            latency: Duration::ZERO,
             parser: Parser::new(),
-        })
+            snapshot: Arc::new(Mutex::new(Snapshot::new())),
+            capture: None,
+            replay: None,
+            replay_finished: false,
+        }
+    }
+
+    pub fn is_spectator(&self) -> bool {
+        self.spectator_client.is_some()
+    }
+
+    /// Starts relaying received frames to spectators connecting to `addr`
+    pub fn open_spectator_host(&mut self, addr: &str) -> Result<(), std::io::Error> {
+        self.spectator_host = Some(SpectatorHost::open(addr, self.snapshot.clone())?);
+        Ok(())
+    }
+
+    pub fn num_spectators(&self) -> usize {
+        self.spectator_host
+            .as_ref()
+            .map_or(0, |host| host.num_spectators())
     }
 
-    pub fn update<F: FnOnce(Vec<AutdData>)>(&mut self, f: F) {
-        if let Ok(raw_buf) = self.rx.try_recv() {
-            let data = self.parser.parse(raw_buf);
-            f(data);
+    /// Sets the hosts from which the viewer setting, e.g., of a handoff, is accepted, none by
+    /// default
+    pub fn set_trusted_peers(&self, peers: &[IpAddr]) {
+        if let Some(interface) = &self.interface {
+            interface.set_trusted_peers(peers);
         }
     }
 
+    /// Returns the sender of the viewer setting rejected last since the previous call
+    pub fn take_rejected_viewer_setting(&self) -> Option<SocketAddr> {
+        self.interface
+            .as_ref()
+            .and_then(|interface| interface.take_rejected_viewer_setting())
+    }
+
+    /// Delays applying each frame by `latency` after it is received, emulating link and firmware
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+    }
+
+    /// Caps of the frames retained for spectators and handoff
+    pub fn set_history_limits(&mut self, limits: HistoryLimits) {
+        if let Ok(mut snapshot) = self.snapshot.lock() {
+            snapshot.set_limits(limits);
+        }
+    }
+
+    pub fn history_usage(&self) -> HistoryUsage {
+        self.snapshot
+            .lock()
+            .map(|snapshot| snapshot.usage())
+            .unwrap_or_default()
+    }
+
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Starts writing every received frame to the capture file `path`
+    pub fn start_capture<P: AsRef<Path>>(&mut self, path: P) -> Result<(), std::io::Error> {
+        self.capture = Some(CaptureWriter::create(path)?);
+        Ok(())
+    }
+
+    pub fn stop_capture(&mut self) {
+        if let Some(mut capture) = self.capture.take() {
+            if let Err(e) = capture.flush() {
+                tracing::error!("failed to write capture: {}", e);
+            }
+        }
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// True if this server replays a capture file and all of its frames have been processed
+    pub fn is_replay_finished(&self) -> bool {
+        self.replay_finished
+    }
+
+    /// Delivers `data` on the next [AutdServer::update] as if received from the client.
+    /// Unlike received frames, it is not relayed to spectators nor handed off.
+    pub fn inject(&mut self, data: Vec<AutdData>) {
+        self.injected.extend(data);
+    }
+
+    pub fn update<F: FnMut(Vec<AutdData>)>(&mut self, mut f: F) {
+        self.update_raw(|_, data| f(data))
+    }
+
+    /// Same as [AutdServer::update], also passing the received frame of the data, which is empty
+    /// for the injected data
+    pub fn update_raw<F: FnMut(&[u8], Vec<AutdData>)>(&mut self, mut f: F) {
+        if !self.injected.is_empty() {
+            f(&[], std::mem::take(&mut self.injected));
+        }
+        let replay_sent = self.replay.as_ref().is_some_and(|r| r.is_finished());
+        self.pending.extend(self.rx.try_iter());
+        let now = Instant::now();
+        while let Some(&(received, _)) = self.pending.front() {
+            if now.duration_since(received) < self.latency {
+                break;
+            }
+            let (_, raw_buf) = match self.pending.pop_front() {
+                Some(frame) => frame,
+                None => break,
+            };
+            tracing::trace!(size = raw_buf.len(), "receive");
+            if let Some(capture) = &mut self.capture {
+                if let Err(e) = capture.write(received, &raw_buf) {
+                    tracing::error!("failed to write capture, stopped: {}", e);
+                    self.capture = None;
+                }
+            }
+            if let Ok(mut snapshot) = self.snapshot.lock() {
+                snapshot.push(&raw_buf);
+                if let Some(host) = &self.spectator_host {
+                    host.broadcast(&raw_buf);
+                }
+            }
+            let data = self.parser.parse(&raw_buf);
+            if let Some(interface) = &self.interface {
+                interface.reply(&RxData::to_bytes(&self.parser.rx_data()));
+            }
+            f(&raw_buf, data);
+        }
+        if replay_sent && self.pending.is_empty() && !self.replay_finished {
+            tracing::info!("replay finished");
+            self.replay_finished = true;
+        }
+    }
+
+    /// Sets the transducer layout of each device. It is handled like a received frame, so it is
+    /// also relayed to spectators and handed off.
+    pub fn set_layouts(&mut self, layouts: &[Layout]) -> Result<(), serde_json::Error> {
+        let mut frame = vec![0x00; std::mem::size_of::<RxGlobalHeader>()];
+        frame[2] = EMULATOR_SET_LAYOUT;
+        frame.extend(serde_json::to_vec(layouts)?);
+        self.pending.push_back((Instant::now(), frame));
+        Ok(())
+    }
+
+    /// Sends the current state to the emulator listening on `addr` so that the session can be
+    /// continued there from the simulation time `time` in seconds. `viewer_setting` is applied
+    /// after the frames, if given.
+    ///
+    /// The frames are sent over TCP on a background thread, which logs whether the other side has
+    /// read all of them.
+    pub fn handoff(&self, addr: &str, time: f64, viewer_setting: Option<&[u8]>) {
+        let mut start = vec![0x00; std::mem::size_of::<RxGlobalHeader>()];
+        start[2] = EMULATOR_HANDOFF;
+        start.extend_from_slice(&time.to_le_bytes());
+        let mut frames = vec![start];
+        if let Ok(snapshot) = self.snapshot.lock() {
+            frames.extend(snapshot.iter().cloned());
+        }
+        if let Some(viewer_setting) = viewer_setting {
+            let mut frame = vec![0x00; std::mem::size_of::<RxGlobalHeader>()];
+            frame[2] = EMULATOR_SET_VIEWER_SETTING;
+            frame.extend_from_slice(viewer_setting);
+            frames.push(frame);
+        }
+
+        let addr = addr.to_owned();
+        thread::spawn(move || {
+            let res = TcpStream::connect(&addr).and_then(|mut stream| {
+                stream.set_write_timeout(Some(HANDOFF_TIMEOUT))?;
+                stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+                frames
+                    .iter()
+                    .try_for_each(|frame| write_frame(&mut stream, frame))?;
+                // the other side closes the stream after reading every frame
+                stream.shutdown(Shutdown::Write)?;
+                stream.read(&mut [0; 1]).map(|_| ())
+            });
+            match res {
+                Ok(()) => tracing::info!("handed off {} frames to {}", frames.len(), addr),
+                Err(e) => tracing::error!("handoff to {} failed: {}", addr, e),
+            }
+        });
+    }
+
+    pub fn firmware_state(&self) -> FirmwareState {
+        self.parser.state()
+    }
+
+    /// RX data of each device returned to the client after the last processed frame
+    pub fn rx_data(&self) -> Vec<RxData> {
+        self.parser.rx_data()
+    }
+
     pub fn close(&mut self) {
-        self.interface.close()
+        if let Some(interface) = &mut self.interface {
+            interface.close()
+        }
     }
 }
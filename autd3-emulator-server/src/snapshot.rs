@@ -0,0 +1,335 @@
+/*
+ * File: snapshot.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::collections::VecDeque;
+
+use autd3_core::hardware_defined::{CommandType, RxGlobalControlFlags};
+
+use crate::firmware::{
+    EMULATOR_EXPORT, EMULATOR_HANDOFF, EMULATOR_SCREENSHOT, EMULATOR_SET_LAYOUT,
+    EMULATOR_SET_VIEWER_SETTING,
+};
+
+/// Caps of the frames retained by [crate::AutdServer] for spectators and handoff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryLimits {
+    pub max_frames: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for HistoryLimits {
+    fn default() -> Self {
+        Self {
+            max_frames: 4096,
+            max_bytes: 64 << 20,
+        }
+    }
+}
+
+/// Memory used by the retained frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HistoryUsage {
+    pub frames: usize,
+    pub bytes: usize,
+    /// Frames evicted since the last geometry or clear
+    pub evicted: usize,
+}
+
+/// Buffer transferred over multiple frames from BEGIN to END
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    Modulation,
+    Sequence,
+}
+
+// The transfer of `frame`, if any, and whether it has BEGIN and END
+fn transfer(frame: &[u8]) -> Option<(Transfer, bool, bool)> {
+    let ctrl_flag = RxGlobalControlFlags::from_bits_truncate(*frame.get(1)?);
+    match *frame.get(2)? {
+        op if op == CommandType::Op as u8 => {
+            let begin = ctrl_flag.contains(RxGlobalControlFlags::MOD_BEGIN);
+            let end = ctrl_flag.contains(RxGlobalControlFlags::MOD_END);
+            // the frames of the gain without the modulation data are not a part of it
+            let mod_size = frame.get(3).copied().unwrap_or(0);
+            (begin || end || mod_size > 0).then_some((Transfer::Modulation, begin, end))
+        }
+        op if op == CommandType::SeqMode as u8 => Some((
+            Transfer::Sequence,
+            ctrl_flag.contains(RxGlobalControlFlags::SEQ_BEGIN),
+            ctrl_flag.contains(RxGlobalControlFlags::SEQ_END),
+        )),
+        _ => None,
+    }
+}
+
+// Clears the modulation data of an Op frame, leaving the gain and the other flags
+fn strip_modulation(frame: &mut [u8]) {
+    let ctrl_flag = RxGlobalControlFlags::from_bits_truncate(frame[1])
+        - RxGlobalControlFlags::MOD_BEGIN
+        - RxGlobalControlFlags::MOD_END;
+    frame[1] = ctrl_flag.bits();
+    frame[3] = 0;
+}
+
+/// Raw frames needed to rebuild the current state on another emulator.
+///
+/// Frames since the last clear are retained within [HistoryLimits], evicting the oldest first.
+/// The frames of a modulation or a sequence from BEGIN to END are evicted together, since the
+/// firmware receiving the rest would append it to another buffer.
+pub(crate) struct Snapshot {
+    layout: Option<Vec<u8>>,
+    geometry: Option<Vec<u8>>,
+    frames: VecDeque<Vec<u8>>,
+    limits: HistoryLimits,
+    bytes: usize,
+    evicted: usize,
+    /// Whether the BEGIN of the modulation and the sequence being transferred is evicted, so
+    /// that the frames until the next BEGIN are dropped, or kept only for the gain
+    orphaned: [bool; 2],
+}
+
+impl Snapshot {
+    pub(crate) fn new() -> Self {
+        Self {
+            layout: None,
+            geometry: None,
+            frames: VecDeque::new(),
+            limits: HistoryLimits::default(),
+            bytes: 0,
+            evicted: 0,
+            orphaned: [false; 2],
+        }
+    }
+
+    pub(crate) fn set_limits(&mut self, limits: HistoryLimits) {
+        self.limits = limits;
+        self.evict();
+    }
+
+    pub(crate) fn usage(&self) -> HistoryUsage {
+        HistoryUsage {
+            frames: self.frames.len(),
+            bytes: self.bytes,
+            evicted: self.evicted,
+        }
+    }
+
+    fn clear_frames(&mut self) {
+        self.frames.clear();
+        self.bytes = 0;
+        self.evicted = 0;
+        self.orphaned = [false; 2];
+    }
+
+    fn push_frame(&mut self, frame: &[u8]) {
+        let mut frame = frame.to_vec();
+        if let Some((kind, begin, end)) = transfer(&frame) {
+            let orphaned = &mut self.orphaned[kind as usize];
+            if begin {
+                *orphaned = false;
+            } else if *orphaned {
+                *orphaned = !end;
+                match kind {
+                    // the gain of the frame is kept without the modulation data
+                    Transfer::Modulation => strip_modulation(&mut frame),
+                    Transfer::Sequence => {
+                        self.evicted += 1;
+                        return;
+                    }
+                }
+            }
+        }
+        self.bytes += frame.len();
+        self.frames.push_back(frame);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.frames.len() > self.limits.max_frames || self.bytes > self.limits.max_bytes {
+            if self.evicted == 0 {
+                tracing::warn!(
+                    "history exceeds {} frames or {} bytes, the oldest frames are evicted",
+                    self.limits.max_frames,
+                    self.limits.max_bytes
+                );
+            }
+            let frame = match self.remove(0) {
+                Some(frame) => frame,
+                None => break,
+            };
+            if let Some((kind, true, false)) = transfer(&frame) {
+                self.evict_transfer(kind);
+            }
+        }
+    }
+
+    // Evicts the frames following an evicted BEGIN of `kind` up to its END
+    fn evict_transfer(&mut self, kind: Transfer) {
+        loop {
+            let next =
+                self.frames
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, frame)| match transfer(frame) {
+                        Some((k, begin, end)) if k == kind => Some((i, begin, end)),
+                        _ => None,
+                    });
+            match next {
+                // a BEGIN without END before it starts over
+                Some((_, true, _)) => return,
+                Some((i, false, end)) => {
+                    self.remove(i);
+                    if end {
+                        return;
+                    }
+                }
+                None => {
+                    // still being transferred
+                    self.orphaned[kind as usize] = true;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, i: usize) -> Option<Vec<u8>> {
+        let frame = self.frames.remove(i)?;
+        self.bytes -= frame.len();
+        self.evicted += 1;
+        Some(frame)
+    }
+
+    pub(crate) fn push(&mut self, frame: &[u8]) {
+        match frame.get(2) {
+            Some(&op) if op == CommandType::EmulatorSetGeometry as u8 => {
+                self.geometry = Some(frame.to_vec());
+                self.clear_frames();
+            }
+            Some(&op) if op == CommandType::Clear as u8 => {
+                self.clear_frames();
+                self.push_frame(frame);
+            }
+            Some(&op) if op == EMULATOR_SET_LAYOUT => self.layout = Some(frame.to_vec()),
+            Some(&op)
+                if op == EMULATOR_SET_VIEWER_SETTING
+                    || op == EMULATOR_EXPORT
+                    || op == EMULATOR_SCREENSHOT
+                    || op == EMULATOR_HANDOFF => {}
+            _ => self.push_frame(frame),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.layout
+            .iter()
+            .chain(self.geometry.iter())
+            .chain(self.frames.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::mem::size_of;
+
+    use autd3_core::hardware_defined::RxGlobalHeader;
+
+    // Frame of `cmd` whose msg_id identifies it
+    fn frame(msg_id: u8, ctrl_flag: RxGlobalControlFlags, cmd: CommandType) -> Vec<u8> {
+        let mut buf = vec![0; size_of::<RxGlobalHeader>()];
+        buf[0] = msg_id;
+        buf[1] = ctrl_flag.bits();
+        buf[2] = cmd as u8;
+        if cmd == CommandType::Op && !ctrl_flag.is_empty() {
+            buf[3] = 1;
+        }
+        buf
+    }
+
+    fn msg_ids(snapshot: &Snapshot) -> Vec<u8> {
+        snapshot.iter().map(|frame| frame[0]).collect()
+    }
+
+    fn limits(max_frames: usize) -> HistoryLimits {
+        HistoryLimits {
+            max_frames,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn evict_the_transfers_as_a_whole() {
+        use RxGlobalControlFlags as F;
+
+        let mut snapshot = Snapshot::new();
+        snapshot.push(&frame(0, F::NONE, CommandType::EmulatorSetGeometry));
+        snapshot.push(&frame(1, F::MOD_BEGIN, CommandType::Op));
+        snapshot.push(&frame(2, F::SEQ_BEGIN | F::SEQ_MODE, CommandType::SeqMode));
+        snapshot.push(&frame(3, F::NONE, CommandType::Op));
+        snapshot.push(&frame(4, F::NONE, CommandType::Pause));
+        snapshot.push(&frame(5, F::SEQ_MODE, CommandType::SeqMode));
+        snapshot.push(&frame(6, F::MOD_END, CommandType::Op));
+        snapshot.push(&frame(7, F::SEQ_END | F::SEQ_MODE, CommandType::SeqMode));
+        snapshot.push(&frame(8, F::NONE, CommandType::Resume));
+        assert_eq!(msg_ids(&snapshot), vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // the modulation with its END, keeping the gain and the pinned geometry
+        snapshot.set_limits(limits(7));
+        assert_eq!(msg_ids(&snapshot), vec![0, 2, 3, 4, 5, 7, 8]);
+        assert_eq!(snapshot.usage().evicted, 2);
+
+        snapshot.set_limits(limits(5));
+        assert_eq!(msg_ids(&snapshot), vec![0, 3, 4, 8]);
+        assert_eq!(snapshot.usage().frames, 3);
+        assert_eq!(snapshot.usage().evicted, 5);
+    }
+
+    #[test]
+    fn drop_the_rest_of_an_evicted_transfer() {
+        use RxGlobalControlFlags as F;
+
+        let mut snapshot = Snapshot::new();
+        snapshot.set_limits(limits(2));
+        snapshot.push(&frame(1, F::MOD_BEGIN, CommandType::Op));
+        snapshot.push(&frame(2, F::NONE, CommandType::Pause));
+        snapshot.push(&frame(3, F::NONE, CommandType::Resume));
+        assert_eq!(msg_ids(&snapshot), vec![2, 3]);
+
+        // the rest of the modulation whose BEGIN is evicted, but not the gain
+        snapshot.push(&frame(4, F::NONE, CommandType::Op));
+        let mut continued = frame(5, F::SILENT, CommandType::Op);
+        continued[3] = 1;
+        continued.extend_from_slice(&[0x12, 0x34]);
+        snapshot.push(&continued);
+        assert_eq!(msg_ids(&snapshot), vec![4, 5]);
+        let kept = snapshot.iter().last().unwrap();
+        assert_eq!(kept[1], F::SILENT.bits());
+        assert_eq!(kept[3], 0);
+        assert_eq!(kept[size_of::<RxGlobalHeader>()..], [0x12, 0x34]);
+
+        snapshot.push(&frame(6, F::MOD_END, CommandType::Op));
+        assert_eq!(msg_ids(&snapshot), vec![5, 6]);
+        assert_eq!(snapshot.iter().last().unwrap()[1], F::NONE.bits());
+
+        snapshot.push(&frame(7, F::MOD_BEGIN | F::MOD_END, CommandType::Op));
+        assert_eq!(msg_ids(&snapshot), vec![6, 7]);
+
+        // a clear starts over
+        snapshot.set_limits(limits(1));
+        snapshot.push(&frame(8, F::MOD_BEGIN, CommandType::Op));
+        snapshot.push(&frame(9, F::NONE, CommandType::Clear));
+        snapshot.push(&frame(10, F::MOD_END, CommandType::Op));
+        assert_eq!(msg_ids(&snapshot), vec![10]);
+    }
+}
@@ -0,0 +1,228 @@
+/*
+ * File: spectator.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Sender, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{interface::BUF_SIZE, snapshot::Snapshot};
+
+/// Frames queued for a spectator, beyond which it is dropped as too slow
+const QUEUE_SIZE: usize = 4096;
+/// Timeout of a write, after which a stalled spectator is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Frame = Arc<[u8]>;
+
+struct HostState {
+    snapshot: Arc<Mutex<Snapshot>>,
+    clients: Arc<Mutex<Vec<Client>>>,
+}
+
+// Spectator served by its own writer thread, so that neither the render thread nor the accept
+// thread waits for the network
+struct Client {
+    peer: Option<SocketAddr>,
+    tx: SyncSender<Frame>,
+}
+
+/// Writes `frame` prefixed by its length, as read by [read_frame]
+pub(crate) fn write_frame<W: Write>(stream: &mut W, frame: &[u8]) -> std::io::Result<()> {
+    stream.write_all(&(frame.len() as u32).to_le_bytes())?;
+    stream.write_all(frame)
+}
+
+/// Reads a frame written by [write_frame], or returns `None` if the stream is closed between
+/// frames
+pub(crate) fn read_frame<R: Read>(stream: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    // no host relays a larger frame, so the length is not trusted to allocate
+    let len = u32::from_le_bytes(len) as usize;
+    if len > BUF_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds {} bytes", len, BUF_SIZE),
+        ));
+    }
+    let mut frame = vec![0; len];
+    stream.read_exact(&mut frame)?;
+    Ok(Some(frame))
+}
+
+// Writes the snapshot and then the queued frames to `stream` until an error or the client is
+// dropped
+fn spawn_writer(mut stream: TcpStream, snapshot: Vec<Frame>, peer: Option<SocketAddr>) -> Client {
+    let (tx, rx) = mpsc::sync_channel::<Frame>(QUEUE_SIZE);
+    thread::spawn(move || {
+        let res = snapshot
+            .iter()
+            .try_for_each(|frame| write_frame(&mut stream, frame))
+            .and_then(|_| {
+                tracing::info!("spectator {:?} connected", peer);
+                rx.iter()
+                    .try_for_each(|frame| write_frame(&mut stream, &frame))
+            });
+        if let Err(e) = res {
+            tracing::info!("spectator {:?} disconnected: {}", peer, e);
+        }
+    });
+    Client { peer, tx }
+}
+
+/// Relays every received frame to read-only spectators
+pub(crate) struct SpectatorHost {
+    clients: Arc<Mutex<Vec<Client>>>,
+    _th_handle: JoinHandle<()>,
+}
+
+impl SpectatorHost {
+    pub(crate) fn open(addr: &str, snapshot: Arc<Mutex<Snapshot>>) -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind(addr)?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let state = HostState {
+            snapshot,
+            clients: clients.clone(),
+        };
+        let th_handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::warn!("failed to accept spectator: {}", e);
+                        continue;
+                    }
+                };
+                let peer = stream.peer_addr().ok();
+                stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok();
+                // holding the snapshot lock until registered so that no frame is missed, while
+                // the snapshot is written by the writer thread
+                if let Ok(snapshot) = state.snapshot.lock() {
+                    let frames = snapshot
+                        .iter()
+                        .map(|frame| Frame::from(&frame[..]))
+                        .collect();
+                    if let Ok(mut clients) = state.clients.lock() {
+                        clients.push(spawn_writer(stream, frames, peer));
+                    }
+                }
+            }
+        });
+        tracing::info!("accepting spectators on {}", addr);
+        Ok(Self {
+            clients,
+            _th_handle: th_handle,
+        })
+    }
+
+    pub(crate) fn num_spectators(&self) -> usize {
+        self.clients.lock().map(|c| c.len()).unwrap_or(0)
+    }
+
+    /// Queues `frame` to every spectator without waiting, dropping those whose queue is full,
+    /// since a spectator missing a frame would show a wrong state.
+    ///
+    /// Must be called while holding the snapshot lock, after pushing `frame` to it.
+    pub(crate) fn broadcast(&self, frame: &[u8]) {
+        let frame = Frame::from(frame);
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.retain(|client| match client.tx.try_send(frame.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    tracing::warn!("spectator {:?} dropped for falling behind", client.peer);
+                    false
+                }
+                // the writer has already logged the error
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+        }
+    }
+}
+
+/// Receives frames from a [SpectatorHost]
+pub(crate) struct SpectatorClient {
+    _th_handle: JoinHandle<()>,
+}
+
+impl SpectatorClient {
+    pub(crate) fn connect(
+        addr: &str,
+        tx: Sender<(Instant, Vec<u8>)>,
+    ) -> Result<Self, std::io::Error> {
+        let mut stream = TcpStream::connect(addr)?;
+        tracing::info!("spectating {}", addr);
+        let addr = addr.to_owned();
+        let th_handle = thread::spawn(move || loop {
+            match read_frame(&mut stream) {
+                Ok(Some(frame)) => {
+                    if tx.send((Instant::now(), frame)).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => {
+                    tracing::error!("disconnected from {}: closed by the host", addr);
+                    break;
+                }
+                Err(e) => {
+                    tracing::error!("disconnected from {}: {}", addr, e);
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            _th_handle: th_handle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &[0x01, 0x02, 0x03]).unwrap();
+        write_frame(&mut buf, &[]).unwrap();
+
+        let mut stream = &buf[..];
+        assert_eq!(
+            read_frame(&mut stream).unwrap(),
+            Some(vec![0x01, 0x02, 0x03])
+        );
+        assert_eq!(read_frame(&mut stream).unwrap(), Some(vec![]));
+        assert_eq!(read_frame(&mut stream).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_frame_is_an_error() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &[0x01, 0x02, 0x03]).unwrap();
+        buf.pop();
+        assert!(read_frame(&mut &buf[..]).is_err());
+
+        let len = (BUF_SIZE as u32 + 1).to_le_bytes();
+        assert!(read_frame(&mut &len[..]).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+/*
+ * File: acoustic_camera.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use acoustic_field_viewer::{
+    calibration, field::FieldModel, metadata::ExperimentMetadata, Vector3,
+};
+use autd3_core::hardware_defined::TRANS_SPACING_MM;
+use serde::{Deserialize, Serialize};
+
+/// Virtual acoustic camera, which maps the SPL on the plane at its focus distance over its field
+/// of view as a microphone-array camera does
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct AcousticCamera {
+    /// Position in mm
+    pub pos: Vector3,
+    /// Point the camera looks at in mm, which is on the focal plane
+    pub target: Vector3,
+    /// Horizontal field of view in degree
+    pub fov: f32,
+    /// Number of the pixels in width and height
+    pub resolution: [usize; 2],
+    /// Range of the SPL shown below the maximum in dB
+    pub dynamic_range: f32,
+}
+
+/// SPL map seen by an [AcousticCamera]
+#[derive(Clone)]
+pub struct AcousticImage {
+    pub camera: AcousticCamera,
+    /// SPL in dB row by row from the top
+    pub spl: Vec<f32>,
+}
+
+impl AcousticCamera {
+    /// Looking at the default focus from the side of the array
+    pub fn new() -> Self {
+        let target = [
+            TRANS_SPACING_MM as f32 * 8.5,
+            TRANS_SPACING_MM as f32 * 6.5,
+            150.0,
+        ];
+        Self {
+            pos: [target[0], target[1] - 300.0, target[2]],
+            target,
+            fov: 60.0,
+            resolution: [64, 48],
+            dynamic_range: 10.0,
+        }
+    }
+
+    /// Forward, right and up unit vectors of the view, up being +z unless looking along it
+    pub fn axes(&self) -> [Vector3; 3] {
+        let forward = vecmath::vec3_normalized(vecmath::vec3_sub(self.target, self.pos));
+        let up = if forward[2].abs() < 0.99 {
+            [0.0, 0.0, 1.0]
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+        let right = vecmath::vec3_normalized(vecmath::vec3_cross(forward, up));
+        let up = vecmath::vec3_cross(right, forward);
+        [forward, right, up]
+    }
+
+    /// Point on the focal plane seen at (`x`, `y`) from -1 to 1 from the left and the top
+    pub fn point(&self, x: f32, y: f32) -> Vector3 {
+        let [forward, right, up] = self.axes();
+        let distance = vecmath_util::dist(self.pos, self.target);
+        let [w, h] = self.resolution;
+        let tan = (self.fov.to_radians() / 2.0).tan();
+        let aspect = h as f32 / w.max(1) as f32;
+        let dir = vecmath::vec3_add(
+            forward,
+            vecmath::vec3_add(
+                vecmath::vec3_scale(right, tan * x),
+                vecmath::vec3_scale(up, -tan * aspect * y),
+            ),
+        );
+        vecmath::vec3_add(self.pos, vecmath::vec3_scale(dir, distance))
+    }
+
+    pub fn capture(&self, model: &FieldModel) -> AcousticImage {
+        let [w, h] = self.resolution;
+        let calibration = model.settings.calibration;
+        let spl = (0..h)
+            .flat_map(|j| (0..w).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let x = 2.0 * (i as f32 + 0.5) / w as f32 - 1.0;
+                let y = 2.0 * (j as f32 + 0.5) / h as f32 - 1.0;
+                let [re, im] = model.pressure_at(self.point(x, y));
+                // silence is clipped at the reference pressure
+                calibration::pa_to_spl(calibration.to_pa((re * re + im * im).sqrt())).max(0.0)
+            })
+            .collect();
+        AcousticImage { camera: *self, spl }
+    }
+}
+
+impl Default for AcousticCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AcousticImage {
+    pub fn max(&self) -> f32 {
+        self.spl.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    /// Writes the SPL map in dB as a matrix of the pixels, row by row from the top, after the
+    /// parameters of the camera, as the acoustic cameras export their maps
+    pub fn write_csv(&self, path: &Path, metadata: &ExperimentMetadata) -> Result<(), String> {
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let camera = &self.camera;
+        let [w, h] = camera.resolution;
+        let res: std::io::Result<()> = (|| {
+            for (key, value) in metadata.entries() {
+                writeln!(writer, "# {}: {}", key, value.replace('\n', " "))?;
+            }
+            let [x, y, z] = camera.pos;
+            writeln!(writer, "# camera position: ({}, {}, {}) mm", x, y, z)?;
+            let [x, y, z] = camera.target;
+            writeln!(writer, "# camera target: ({}, {}, {}) mm", x, y, z)?;
+            writeln!(
+                writer,
+                "# focus distance: {} mm",
+                vecmath_util::dist(camera.pos, camera.target)
+            )?;
+            writeln!(writer, "# horizontal field of view: {} deg", camera.fov)?;
+            writeln!(writer, "# {} x {} pixels, SPL in dB re 20 uPa", w, h)?;
+            for row in self.spl.chunks_exact(w.max(1)) {
+                let row: Vec<_> = row.iter().map(|v| format!("{:.2}", v)).collect();
+                writeln!(writer, "{}", row.join(","))?;
+            }
+            writer.flush()
+        })();
+        res.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
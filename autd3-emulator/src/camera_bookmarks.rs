@@ -0,0 +1,134 @@
+/*
+ * File: camera_bookmarks.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::time::{Duration, Instant};
+
+use acoustic_field_viewer::Vector3;
+use camera_controllers::Camera;
+use serde::{Deserialize, Serialize};
+
+/// Named pose of the camera, recalled from the `Camera` tab or with the number keys
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CameraBookmark {
+    pub name: String,
+    /// Position in mm
+    pub pos: Vector3,
+    /// Unit vector from the target toward the camera, as `Camera::forward`
+    pub forward: Vector3,
+    /// Unit vector upward on the screen
+    pub up: Vector3,
+    /// Look-at target in mm, around which the camera orbits
+    pub target: Vector3,
+}
+
+impl CameraBookmark {
+    pub fn capture(name: &str, camera: &Camera<f32>, target: Vector3) -> Self {
+        Self {
+            name: name.to_owned(),
+            pos: camera.position,
+            forward: camera.forward,
+            up: camera.up,
+            target,
+        }
+    }
+
+    /// Upright camera at `distance` mm from `target` in the direction `dir`
+    pub fn looking_at(name: &str, target: Vector3, dir: Vector3, distance: f32) -> Self {
+        let forward = vecmath::vec3_normalized(dir);
+        let right = vecmath::vec3_cross([0., 0., 1.], forward);
+        let right = if vecmath::vec3_len(right) < 1e-6 {
+            [1., 0., 0.]
+        } else {
+            vecmath::vec3_normalized(right)
+        };
+        Self {
+            name: name.to_owned(),
+            pos: vecmath::vec3_add(target, vecmath::vec3_scale(forward, distance)),
+            forward,
+            up: vecmath::vec3_cross(forward, right),
+            target,
+        }
+    }
+
+    /// Views from the front (the -y side), the top and the oblique front right above of `target`
+    pub fn presets(target: Vector3, distance: f32) -> Vec<Self> {
+        vec![
+            Self::looking_at("front", target, [0., -1., 0.], distance),
+            Self::looking_at("top", target, [0., 0., 1.], distance),
+            Self::looking_at("iso", target, [1., -1., 1.], distance),
+        ]
+    }
+
+    /// Sets the pose to `camera`, orthonormalized, returning the target
+    pub fn apply(&self, camera: &mut Camera<f32>) -> Vector3 {
+        camera.position = self.pos;
+        camera.forward = vecmath::vec3_normalized(self.forward);
+        let right = vecmath::vec3_cross(self.up, camera.forward);
+        // the right is kept if the up is broken, e.g., edited to the forward in setting.json
+        if vecmath::vec3_len(right) > 1e-6 {
+            camera.right = vecmath::vec3_normalized(right);
+        }
+        camera.up = vecmath::vec3_cross(camera.forward, camera.right);
+        self.target
+    }
+}
+
+/// Smooth move of the camera from its pose to a bookmark, eased in and out
+pub struct CameraTransition {
+    from: CameraBookmark,
+    to: CameraBookmark,
+    started: Instant,
+    duration: Duration,
+}
+
+impl CameraTransition {
+    pub fn new(camera: &Camera<f32>, target: Vector3, to: CameraBookmark, duration: f32) -> Self {
+        Self {
+            from: CameraBookmark::capture("", camera, target),
+            to,
+            started: Instant::now(),
+            duration: Duration::from_secs_f32(duration.max(0.0)),
+        }
+    }
+
+    /// Moves `camera` to the pose at `now`, returning the target and whether it is finished
+    pub fn update(&self, camera: &mut Camera<f32>, now: Instant) -> (Vector3, bool) {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed >= self.duration {
+            return (self.to.apply(camera), true);
+        }
+        let t = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        let t = t * t * (3.0 - 2.0 * t);
+        let lerp = |a: Vector3, b: Vector3| {
+            vecmath::vec3_add(a, vecmath::vec3_scale(vecmath::vec3_sub(b, a), t))
+        };
+        // the directions are interpolated on the chord, which is undefined only for the opposite
+        // ones at the midpoint, where the start is kept instead
+        let nlerp = |a: Vector3, b: Vector3| {
+            let v = lerp(a, b);
+            if vecmath::vec3_len(v) < 1e-3 {
+                a
+            } else {
+                vecmath::vec3_normalized(v)
+            }
+        };
+        let pose = CameraBookmark {
+            name: String::new(),
+            pos: lerp(self.from.pos, self.to.pos),
+            forward: nlerp(self.from.forward, self.to.forward),
+            up: nlerp(self.from.up, self.to.up),
+            target: lerp(self.from.target, self.to.target),
+        };
+        (pose.apply(camera), false)
+    }
+}
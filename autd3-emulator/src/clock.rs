@@ -0,0 +1,171 @@
+/*
+ * File: clock.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::time::Duration;
+
+pub const TIME_SCALE_MIN: f32 = 0.01;
+pub const TIME_SCALE_MAX: f32 = 10.0;
+
+/// Simulation clock driving the playback of modulation and STM
+pub struct Clock {
+    time: f64,
+    scale: f32,
+    paused: bool,
+}
+
+impl Clock {
+    pub fn new(scale: f32) -> Self {
+        Self {
+            time: 0.0,
+            scale: scale.clamp(TIME_SCALE_MIN, TIME_SCALE_MAX),
+            paused: false,
+        }
+    }
+
+    /// Simulation time in seconds
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(TIME_SCALE_MIN, TIME_SCALE_MAX);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Advances by the real elapsed time `dt` multiplied by the time scale, unless paused
+    pub fn tick(&mut self, dt: Duration) {
+        if !self.paused {
+            self.time += dt.as_secs_f64() * self.scale as f64;
+        }
+    }
+
+    /// Advances by `dt` seconds regardless of the pause state
+    pub fn step(&mut self, dt: f64) {
+        self.time += dt;
+    }
+
+    pub fn reset(&mut self) {
+        self.time = 0.0;
+    }
+
+    /// Jumps to `time` in seconds, e.g., that of the emulator handing off
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Index of the current sample of a periodic buffer of `len` samples played at `freq` Hz
+    pub fn sample_index(&self, freq: f64, len: usize) -> usize {
+        if len == 0 {
+            return 0;
+        }
+        ((self.time * freq) as u64 % len as u64) as usize
+    }
+}
+
+/// Linear ramp-up of the amplitudes, like the soft start of the silencer, when the output is
+/// enabled
+pub struct Ramp {
+    start: f64,
+    duration: f64,
+    /// Amplitudes of the sources at the end of the ramp
+    pub amps: Vec<f32>,
+}
+
+impl Ramp {
+    /// Ramp of `duration` seconds from `start` in the simulation time
+    pub fn new(start: f64, duration: f64, amps: Vec<f32>) -> Self {
+        Self {
+            start,
+            duration,
+            amps,
+        }
+    }
+
+    /// Factor of the amplitudes from 0 to 1 at `time`, which is 1 if the clock is reset
+    pub fn progress(&self, time: f64) -> f32 {
+        if time < self.start || self.duration <= 0.0 {
+            return 1.0;
+        }
+        ((time - self.start) / self.duration).min(1.0) as f32
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_scale_and_pause() {
+        let mut clock = Clock::new(100.0);
+        assert_eq!(clock.scale(), TIME_SCALE_MAX);
+        clock.set_scale(0.0);
+        assert_eq!(clock.scale(), TIME_SCALE_MIN);
+
+        clock.set_scale(2.0);
+        clock.tick(Duration::from_millis(500));
+        assert!((clock.time() - 1.0).abs() < 1e-9);
+
+        clock.set_paused(true);
+        clock.tick(Duration::from_millis(500));
+        assert!((clock.time() - 1.0).abs() < 1e-9);
+        // stepping advances even while paused
+        clock.step(0.25);
+        assert!((clock.time() - 1.25).abs() < 1e-9);
+
+        clock.set_paused(false);
+        clock.tick(Duration::from_millis(250));
+        assert!((clock.time() - 1.75).abs() < 1e-9);
+
+        clock.reset();
+        assert_eq!(clock.time(), 0.0);
+        assert!(!clock.is_paused());
+    }
+
+    #[test]
+    fn clock_sample_index() {
+        let mut clock = Clock::new(1.0);
+        clock.set_time(0.0105);
+        assert_eq!(clock.sample_index(1000.0, 4), 10 % 4);
+        assert_eq!(clock.sample_index(1000.0, 0), 0);
+    }
+
+    #[test]
+    fn ramp_progress() {
+        let ramp = Ramp::new(1.0, 2.0, vec![1.0]);
+        assert_eq!(ramp.progress(1.0), 0.0);
+        assert_eq!(ramp.progress(2.0), 0.5);
+        assert_eq!(ramp.progress(3.0), 1.0);
+        // clamped after the end
+        assert_eq!(ramp.progress(10.0), 1.0);
+        // before the start, e.g. after the clock is reset
+        assert_eq!(ramp.progress(0.5), 1.0);
+
+        let ramp = Ramp::new(1.0, 0.0, vec![1.0]);
+        assert_eq!(ramp.progress(1.0), 1.0);
+    }
+}
@@ -0,0 +1,190 @@
+/*
+ * File: coalesce.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::time::{Duration, Instant};
+
+use acoustic_field_viewer::view::UpdateFlag;
+
+/// Numbers of the drive updates from the SDK since the start of the session
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CoalesceStats {
+    pub received: u64,
+    /// Renderer updates, each of which applies one or more drive updates
+    pub rendered: u64,
+    /// Drive updates superseded by a later one before being rendered
+    pub merged: u64,
+}
+
+/// Holds the updates of the renderer for the data from the SDK for a window from the first
+/// held, so that a burst of drive updates is rendered once. The drive itself is not held.
+pub struct Coalescer {
+    pending: UpdateFlag,
+    held: u64,
+    since: Option<Instant>,
+    stats: CoalesceStats,
+}
+
+impl Coalescer {
+    pub fn new() -> Self {
+        Self {
+            pending: UpdateFlag::empty(),
+            held: 0,
+            since: None,
+            stats: CoalesceStats::default(),
+        }
+    }
+
+    /// Holds `flag` for the `updates` drive updates received at `now`
+    pub fn push(&mut self, flag: UpdateFlag, updates: u64, now: Instant) {
+        if flag.is_empty() && updates == 0 {
+            return;
+        }
+        self.pending |= flag;
+        self.held += updates;
+        self.stats.received += updates;
+        self.since.get_or_insert(now);
+    }
+
+    /// Releases the held flags once `window` has passed since the first held, or right away for
+    /// a new geometry, which the renderer must follow before anything else
+    pub fn poll(&mut self, window: Duration, now: Instant) -> UpdateFlag {
+        let since = match self.since {
+            Some(since) => since,
+            None => return UpdateFlag::empty(),
+        };
+        if now.duration_since(since) < window && !self.pending.contains(UpdateFlag::INIT_SOURCE) {
+            return UpdateFlag::empty();
+        }
+        if self.held > 0 {
+            self.stats.rendered += 1;
+            self.stats.merged += self.held - 1;
+        }
+        self.held = 0;
+        self.since = None;
+        std::mem::replace(&mut self.pending, UpdateFlag::empty())
+    }
+
+    pub fn stats(&self) -> CoalesceStats {
+        self.stats
+    }
+
+    /// Starts the statistics of a new session, keeping the held flags
+    pub fn reset_stats(&mut self) {
+        self.stats = CoalesceStats {
+            received: self.held,
+            ..CoalesceStats::default()
+        };
+    }
+}
+
+impl Default for Coalescer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(16);
+
+    #[test]
+    fn merge_within_window() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new();
+        coalescer.push(UpdateFlag::UPDATE_SOURCE_DRIVE, 1, start);
+        coalescer.push(UpdateFlag::UPDATE_SOURCE_DRIVE, 1, start + WINDOW / 4);
+        coalescer.push(UpdateFlag::UPDATE_SOURCE_ALPHA, 1, start + WINDOW / 2);
+        assert_eq!(
+            coalescer.poll(WINDOW, start + WINDOW / 2),
+            UpdateFlag::empty()
+        );
+
+        let flag = coalescer.poll(WINDOW, start + WINDOW);
+        assert_eq!(
+            flag,
+            UpdateFlag::UPDATE_SOURCE_DRIVE | UpdateFlag::UPDATE_SOURCE_ALPHA
+        );
+        assert_eq!(
+            coalescer.stats(),
+            CoalesceStats {
+                received: 3,
+                rendered: 1,
+                merged: 2,
+            }
+        );
+        assert_eq!(
+            coalescer.poll(WINDOW, start + WINDOW * 2),
+            UpdateFlag::empty()
+        );
+    }
+
+    #[test]
+    fn keep_outside_window() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new();
+        coalescer.push(UpdateFlag::UPDATE_SOURCE_DRIVE, 1, start);
+        assert_eq!(
+            coalescer.poll(WINDOW, start + WINDOW),
+            UpdateFlag::UPDATE_SOURCE_DRIVE
+        );
+        // the window starts again from the next update
+        coalescer.push(UpdateFlag::UPDATE_SOURCE_DRIVE, 1, start + WINDOW * 3);
+        assert_eq!(
+            coalescer.poll(WINDOW, start + WINDOW * 3),
+            UpdateFlag::empty()
+        );
+        assert_eq!(
+            coalescer.poll(WINDOW, start + WINDOW * 4),
+            UpdateFlag::UPDATE_SOURCE_DRIVE
+        );
+        assert_eq!(
+            coalescer.stats(),
+            CoalesceStats {
+                received: 2,
+                rendered: 2,
+                merged: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn zero_window_and_new_geometry() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new();
+        coalescer.push(UpdateFlag::UPDATE_SOURCE_DRIVE, 1, start);
+        assert_eq!(
+            coalescer.poll(Duration::ZERO, start),
+            UpdateFlag::UPDATE_SOURCE_DRIVE
+        );
+
+        // a new geometry is released without waiting for the window
+        coalescer.push(UpdateFlag::INIT_SOURCE, 1, start);
+        assert_eq!(coalescer.poll(WINDOW, start), UpdateFlag::INIT_SOURCE);
+    }
+
+    #[test]
+    fn reset_stats_keeps_held() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new();
+        coalescer.push(UpdateFlag::UPDATE_SOURCE_DRIVE, 2, start);
+        coalescer.reset_stats();
+        assert_eq!(coalescer.stats().received, 2);
+        assert_eq!(
+            coalescer.poll(WINDOW, start + WINDOW),
+            UpdateFlag::UPDATE_SOURCE_DRIVE
+        );
+        assert_eq!(coalescer.stats().merged, 1);
+    }
+}
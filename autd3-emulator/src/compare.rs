@@ -0,0 +1,67 @@
+/*
+ * File: compare.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use acoustic_field_viewer::{scatterer::Scattering, sound_source::SoundSource, Vector3};
+
+/// Drive frozen as A for the comparison with the current drive B on the slices, i.e., the
+/// amplitude and the phase of each source, while the geometry and the frequencies are those of
+/// the current sources
+#[derive(Debug, Clone)]
+pub struct Reference {
+    drive: Vec<(f32, f32)>,
+    positions: Vec<Vector3>,
+    /// Gain tags at the freeze
+    pub label: String,
+    /// Pressure on the facets of the scatterers in the field of the reference
+    pub scattering: Option<Scattering>,
+}
+
+impl Reference {
+    pub fn freeze(sources: &[SoundSource], scattering: Option<Scattering>, label: String) -> Self {
+        Self {
+            drive: sources.iter().map(|s| (s.amp, s.phase)).collect(),
+            positions: sources.iter().map(|s| s.pos).collect(),
+            label,
+            scattering,
+        }
+    }
+
+    /// Whether the reference was frozen on the same sources
+    pub fn matches(&self, sources: &[SoundSource]) -> bool {
+        self.positions.len() == sources.len()
+            && self.positions.iter().zip(sources).all(|(p, s)| *p == s.pos)
+    }
+
+    /// `sources` driven as the reference
+    pub fn sources(&self, sources: &[SoundSource]) -> Vec<SoundSource> {
+        sources
+            .iter()
+            .zip(&self.drive)
+            .map(|(s, &(amp, phase))| SoundSource { amp, phase, ..*s })
+            .collect()
+    }
+
+    /// Largest differences of the amplitude and of the phase in rad, wrapped from -pi to pi, of
+    /// the drive of `sources` from the reference
+    pub fn max_difference(&self, sources: &[SoundSource]) -> (f32, f32) {
+        sources
+            .iter()
+            .zip(&self.drive)
+            .fold((0.0f32, 0.0f32), |(da, dp), (s, &(amp, phase))| {
+                let d = (s.phase - phase + PI).rem_euclid(2.0 * PI) - PI;
+                (da.max((s.amp - amp).abs()), dp.max(d.abs()))
+            })
+    }
+}
@@ -0,0 +1,119 @@
+/*
+ * File: console.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use acoustic_field_viewer::{sound_source::SoundSource, view::ViewerSettings};
+use autd3_emulator_server::{AutdData, Gain};
+
+pub const HELP: &str = "\
+focus <x> <y> <z> [amp]  drive all transducers to focus at (x, y, z) mm, amp in 0-1 (default 1)
+phases <file>            set phases [rad] from a CSV file, one 'phase[,amp]' line per transducer
+clear | pause | resume   same as the commands of the SDK
+help                     show this message";
+
+fn amp_to_duty(amp: f32) -> u8 {
+    (amp.clamp(0.0, 1.0).asin() * 510.0 / PI).round().min(255.0) as u8
+}
+
+fn phase_to_u8(phase: f32) -> u8 {
+    ((1.0 - phase.rem_euclid(2.0 * PI) / (2.0 * PI)) * 255.0).round() as u8
+}
+
+fn parse_f32(s: &str) -> Result<f32, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid number: {}", s))
+}
+
+fn gain<I: Iterator<Item = (f32, f32)>>(drives: I) -> AutdData {
+    let (phases, amps) = drives
+        .map(|(phase, amp)| (phase_to_u8(phase), amp_to_duty(amp)))
+        .unzip();
+    AutdData::Gain(Gain { amps, phases })
+}
+
+fn focus(
+    args: &[&str],
+    sources: &[SoundSource],
+    settings: &ViewerSettings,
+) -> Result<AutdData, String> {
+    if args.len() != 3 && args.len() != 4 {
+        return Err("usage: focus <x> <y> <z> [amp]".to_owned());
+    }
+    let pos = [
+        parse_f32(args[0])?,
+        parse_f32(args[1])?,
+        parse_f32(args[2])?,
+    ];
+    let amp = match args.get(3) {
+        Some(amp) => parse_f32(amp)?,
+        None => 1.0,
+    };
+    Ok(gain(sources.iter().map(|source| {
+        let d = vecmath_util::dist(source.pos, pos);
+        (source.wavenumber(settings) * d, amp)
+    })))
+}
+
+fn phases(args: &[&str], sources: &[SoundSource]) -> Result<AutdData, String> {
+    let path = match args {
+        [path] => path,
+        _ => return Err("usage: phases <file>".to_owned()),
+    };
+    let csv = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let drives = csv
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut cols = line.split(',');
+            let phase = parse_f32(cols.next().unwrap_or(""))?;
+            let amp = cols.next().map(parse_f32).transpose()?.unwrap_or(1.0);
+            Ok((phase, amp))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if drives.len() != sources.len() {
+        tracing::warn!(
+            "{} has {} lines for {} transducers",
+            path,
+            drives.len(),
+            sources.len()
+        );
+    }
+    Ok(gain(drives.into_iter()))
+}
+
+/// Parses a console command into synthetic events, which are injected as if sent by the SDK
+pub fn execute(
+    line: &str,
+    sources: &[SoundSource],
+    settings: &ViewerSettings,
+) -> Result<Vec<AutdData>, String> {
+    let tokens: Vec<_> = line.split_whitespace().collect();
+    let (cmd, args) = match tokens.split_first() {
+        Some((cmd, args)) => (*cmd, args),
+        None => return Ok(Vec::new()),
+    };
+    if sources.is_empty() && (cmd == "focus" || cmd == "phases") {
+        return Err("no geometry, connect the SDK or load a session first".to_owned());
+    }
+    match cmd {
+        "focus" => Ok(vec![focus(args, sources, settings)?]),
+        "phases" => Ok(vec![phases(args, sources)?]),
+        "clear" => Ok(vec![AutdData::Clear]),
+        "pause" => Ok(vec![AutdData::Pause]),
+        "resume" => Ok(vec![AutdData::Resume]),
+        _ => Err(format!("unknown command: {}, type 'help'", cmd)),
+    }
+}
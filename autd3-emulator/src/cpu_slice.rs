@@ -0,0 +1,161 @@
+/*
+ * File: cpu_slice.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use acoustic_field_viewer::{
+    directivity::DirectivityTable,
+    field::{self, FieldModel},
+    scatterer::Scattering,
+    sound_source::SoundSource,
+    view::{CompareView, ViewerSettings},
+    Matrix4,
+};
+use scarlet::color::RGBColor;
+
+/// Slice to compute, owned by the background thread
+pub struct SliceRequest {
+    pub sources: Vec<SoundSource>,
+    /// Number of the sources of each device
+    pub device_sizes: Vec<usize>,
+    pub settings: ViewerSettings,
+    pub directivity_table: Option<DirectivityTable>,
+    pub scattering: Option<Scattering>,
+    pub model: Matrix4,
+    /// Spacing of the points in mm
+    pub resolution: f32,
+    /// Phase at which the real part is taken
+    pub wt: f32,
+    pub color_map: Vec<RGBColor>,
+    /// Sources and the scattering of the reference drive, from whose values those of `sources`
+    /// are subtracted for [CompareView::Difference]
+    pub reference: Option<(Vec<SoundSource>, Option<Scattering>)>,
+}
+
+/// Computes the colors of the slice with [field::compute_slice] on a background thread, one at a
+/// time, where the requests arriving meanwhile are coalesced into the latest
+pub struct CpuSlice {
+    running: Option<Receiver<Vec<[u8; 4]>>>,
+    pending: Option<SliceRequest>,
+}
+
+impl CpuSlice {
+    pub fn new() -> Self {
+        Self {
+            running: None,
+            pending: None,
+        }
+    }
+
+    pub fn request(&mut self, request: SliceRequest) {
+        self.pending = Some(request);
+    }
+
+    /// Drops the pending request, and the result of the running one
+    pub fn cancel(&mut self) {
+        self.running = None;
+        self.pending = None;
+    }
+
+    /// Starts the pending computation if idle, and returns the colors of the finished one, row by
+    /// row from the bottom of the slice
+    pub fn poll(&mut self) -> Option<Vec<[u8; 4]>> {
+        let result = match &self.running {
+            Some(rx) => match rx.try_recv() {
+                Ok(colors) => Some(colors),
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    tracing::error!("slice computation panicked");
+                    None
+                }
+            },
+            None => None,
+        };
+        self.running = None;
+        if let Some(request) = self.pending.take() {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                let model = FieldModel {
+                    sources: &request.sources,
+                    settings: &request.settings,
+                    directivity_table: request.directivity_table.as_ref(),
+                    scattering: request.scattering.as_ref(),
+                    device_sizes: &request.device_sizes,
+                };
+                let (_, mut values) =
+                    field::compute_slice(&model, request.model, request.resolution, request.wt);
+                if let Some((sources, scattering)) = &request.reference {
+                    let reference = FieldModel {
+                        sources,
+                        scattering: scattering.as_ref(),
+                        ..model
+                    };
+                    let (_, reference) = field::compute_slice(
+                        &reference,
+                        request.model,
+                        request.resolution,
+                        request.wt,
+                    );
+                    values = match request.settings.compare {
+                        CompareView::Current => values,
+                        CompareView::Reference => reference,
+                        CompareView::Difference => values
+                            .iter()
+                            .zip(&reference)
+                            .map(|(&b, &a)| field::value_difference(b, a, &request.settings))
+                            .collect(),
+                    };
+                }
+                let alpha = (request.settings.slice_alpha * 255.) as u8;
+                let colors = values
+                    .iter()
+                    .map(|&v| {
+                        let t = field::color_position(v, &request.settings);
+                        color_texel(&request.color_map, t, alpha)
+                    })
+                    .collect();
+                // the receiver is dropped if the emulator exits or the request is cancelled
+                let _ = tx.send(colors);
+            });
+            self.running = Some(rx);
+        }
+        result
+    }
+}
+
+impl Default for CpuSlice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Linear interpolation of the color map at `t` with the clamp to the edges, as sampled by the
+// slice shader from the texture of 8-bit colors
+fn color_texel(color_map: &[RGBColor], t: f32, alpha: u8) -> [u8; 4] {
+    if color_map.is_empty() {
+        return [0, 0, 0, alpha];
+    }
+    let n = color_map.len();
+    let x = (t * n as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+    let i = (x.floor() as usize).min(n - 1);
+    let f = x - i as f32;
+    let (c0, c1) = (&color_map[i], &color_map[(i + 1).min(n - 1)]);
+    let lerp = |a: f64, b: f64| {
+        let (a, b) = ((a * 255.) as u8 as f32, (b * 255.) as u8 as f32);
+        (a + (b - a) * f).round() as u8
+    };
+    [lerp(c0.r, c1.r), lerp(c0.g, c1.g), lerp(c0.b, c1.b), alpha]
+}
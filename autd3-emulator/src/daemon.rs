@@ -0,0 +1,96 @@
+/*
+ * File: daemon.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{thread, time::Duration};
+
+use autd3_emulator_server::{AutdData, AutdServer};
+
+use crate::{journal::Journal, settings::Setting};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Serves the SDK without the window until the process is terminated, relaying every frame to the
+/// viewers attached as spectators, so that closing a viewer never drops the connection.
+/// The layout file and the journal of `setting` are applied as in the viewer.
+pub fn run(setting: &Setting) -> Result<(), String> {
+    let addr = format!("127.0.0.1:{}", setting.port);
+    let mut server =
+        AutdServer::new(&addr, &setting.handoff_host).map_err(|e| format!("{}: {}", addr, e))?;
+    if setting.spectator_enable {
+        let spectator_addr = format!("{}:{}", setting.spectator_host, setting.spectator_port);
+        server
+            .open_spectator_host(&spectator_addr)
+            .map_err(|e| format!("{}: {}", spectator_addr, e))?;
+    } else {
+        tracing::warn!("spectators are not accepted, so no viewer can attach");
+    }
+    server.set_latency(Duration::from_secs_f32(
+        setting.latency_ms.max(0.0) / 1000.0,
+    ));
+    server.set_history_limits(setting.history_limits());
+    server.set_trusted_peers(&setting.trusted_peers());
+    if !setting.layout_file.is_empty() {
+        match crate::read_layouts(&setting.layout_file) {
+            Ok(layouts) => match server.set_layouts(&layouts) {
+                Ok(()) => tracing::info!(
+                    num_devices = layouts.len(),
+                    "load layout {}",
+                    setting.layout_file
+                ),
+                Err(e) => tracing::error!("failed to set layout: {}", e),
+            },
+            Err(e) => tracing::error!("failed to load layout {}: {}", setting.layout_file, e),
+        }
+    }
+    let mut journal = if setting.journal_enable {
+        match Journal::open(&setting.journal_path, setting.journal_max_kb as u64 * 1024) {
+            Ok(journal) => {
+                tracing::info!("journal opened: {}", setting.journal_path);
+                Some(journal)
+            }
+            Err(e) => {
+                tracing::error!("failed to open journal {}: {}", setting.journal_path, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut num_viewers = 0;
+    loop {
+        server.update(|data| {
+            for d in data {
+                if let Some(journal) = &mut journal {
+                    journal.record(&d);
+                }
+                match d {
+                    AutdData::Geometries(geometries) => {
+                        tracing::info!(num_devices = geometries.len(), "geometry")
+                    }
+                    AutdData::Clear => tracing::info!("clear"),
+                    _ => (),
+                }
+            }
+        });
+        if let Some(journal) = &mut journal {
+            journal.flush();
+        }
+        let n = server.num_spectators();
+        if n != num_viewers {
+            tracing::info!(num_viewers = n, "viewers attached");
+            num_viewers = n;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,428 @@
+/*
+ * File: decoder.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{collections::BTreeMap, f32::consts::PI};
+
+use acoustic_field_viewer::{sound_source::SoundSource, view::ViewerSettings, Vector3};
+use autd3_core::hardware_defined::{
+    RxGlobalControlFlags, MOD_SAMPLING_FREQ_BASE, POINT_SEQ_BASE_FREQ,
+};
+use autd3_emulator_server::{
+    cycle_to_frequency, frame_entries, AutdData, DelayOffset, Gain, Geometry, Modulation, Sequence,
+};
+
+use crate::{
+    clock::Clock,
+    perturbation::{Perturbation, PerturbationSample},
+    trans_calibration::TransCalibration,
+};
+
+/// Drive of the transducers decoded from the data of the SDK as the firmware plays it, shared by
+/// the viewer and the headless replays of the recordings
+pub struct Decoder {
+    pub sources: Vec<SoundSource>,
+    /// Number of transducers of each device of the last geometry
+    pub device_sizes: Vec<usize>,
+    /// Devices of the last geometry, which are unknown for the placeholder and the older sessions
+    pub geometries: Vec<Geometry>,
+    /// Amplitudes restored on resume, which are not empty while paused
+    pub last_amp: Vec<f32>,
+    pub ctrl_flag: RxGlobalControlFlags,
+    pub modulation: Option<Modulation>,
+    pub sequence: Option<Sequence>,
+    pub delay_offset: Option<DelayOffset>,
+    pub mod_index: Option<usize>,
+    pub seq_index: Option<usize>,
+    /// Amplitude and phase of the last drive before the calibration and the perturbation
+    pub sent_drive: Vec<(f32, f32)>,
+    /// Amplitude and phase by the index of the source, replacing the drive sent by the SDK
+    pub drive_overrides: BTreeMap<usize, (f32, f32)>,
+    /// Multiplied into the drive of each gain and STM point
+    pub trans_calibration: Option<TransCalibration>,
+    pub perturbation_sample: Option<PerturbationSample>,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self {
+            sources: Vec::new(),
+            device_sizes: Vec::new(),
+            geometries: Vec::new(),
+            last_amp: Vec::new(),
+            ctrl_flag: RxGlobalControlFlags::empty(),
+            modulation: None,
+            sequence: None,
+            delay_offset: None,
+            mod_index: None,
+            seq_index: None,
+            sent_drive: Vec::new(),
+            drive_overrides: BTreeMap::new(),
+            trans_calibration: None,
+            perturbation_sample: None,
+        }
+    }
+}
+
+impl Decoder {
+    /// Applies `data` other than the viewer setting without the ramp, as for the recordings
+    pub fn apply(&mut self, data: AutdData, perturbation: &Perturbation) {
+        match data {
+            AutdData::Geometries(geometries) => self.set_geometries(geometries, false),
+            AutdData::Gain(gain) => {
+                self.set_gain(&gain);
+                self.adjust(perturbation);
+            }
+            AutdData::Clear => self.clear(),
+            AutdData::Pause => self.pause(None),
+            AutdData::Resume => {
+                self.resume();
+            }
+            AutdData::Modulation(m) => self.modulation = Some(m),
+            AutdData::CtrlFlag(flag) => self.ctrl_flag = flag,
+            AutdData::Sequence(seq) => self.set_sequence(seq),
+            AutdData::DelayOffset(d) => self.delay_offset = Some(d),
+            AutdData::Cycles(cycles) => self.set_cycles(&cycles),
+            _ => (),
+        }
+    }
+
+    /// Replaces the sources by those of `geometries`, keeping the drive of the devices with the
+    /// same number of transducers if `keep_drive`, e.g., on a re-layout
+    pub fn set_geometries(&mut self, geometries: Vec<Geometry>, keep_drive: bool) {
+        let old_sources = std::mem::take(&mut self.sources);
+        let old_sizes = std::mem::take(&mut self.device_sizes);
+        // the indices may refer to other transducers in the new geometry
+        self.drive_overrides.clear();
+        self.sent_drive.clear();
+        let mut old_offset = 0;
+        for (i, geometry) in geometries.iter().enumerate() {
+            let mut transducers = geometry.make_autd_transducers();
+            if keep_drive && old_sizes.get(i) == Some(&transducers.len()) {
+                for (trans, old) in transducers.iter_mut().zip(&old_sources[old_offset..]) {
+                    trans.amp = old.amp;
+                    trans.phase = old.phase;
+                    trans.frequency = old.frequency;
+                }
+            }
+            old_offset += old_sizes.get(i).copied().unwrap_or(0);
+            self.device_sizes.push(transducers.len());
+            self.sources.extend(transducers);
+        }
+        self.geometries = geometries;
+    }
+
+    /// Entry of each source in the per-transducer frames of the SDK, in order if the devices are
+    /// unknown
+    pub fn frame_entries(&self) -> Vec<usize> {
+        if self.device_sizes.is_empty() {
+            (0..self.sources.len()).collect()
+        } else {
+            frame_entries(&self.device_sizes)
+        }
+    }
+
+    /// Sets the drive of `gain` as sent, which [Decoder::adjust] adjusts
+    pub fn set_gain(&mut self, gain: &Gain) {
+        let entries = self.frame_entries();
+        for (source, &entry) in self.sources.iter_mut().zip(&entries) {
+            if let (Some(&phase), Some(&amp)) = (gain.phases.get(entry), gain.amps.get(entry)) {
+                source.amp = (amp as f32 / 510.0 * PI).sin();
+                source.phase = 2.0 * PI * (1.0 - (phase as f32 / 255.0));
+            }
+        }
+    }
+
+    pub fn set_cycles(&mut self, cycles: &[u16]) {
+        let entries = self.frame_entries();
+        for (source, &entry) in self.sources.iter_mut().zip(&entries) {
+            if let Some(&cycle) = cycles.get(entry) {
+                source.frequency = cycle_to_frequency(cycle);
+            }
+        }
+    }
+
+    /// Stops the output, and discards the modulation, the STM and the delay offsets
+    pub fn clear(&mut self) {
+        for source in self.sources.iter_mut() {
+            source.amp = 0.;
+            source.phase = 0.;
+            source.frequency = None;
+        }
+        self.sent_drive.clear();
+        self.modulation = None;
+        self.sequence = None;
+        self.delay_offset = None;
+        self.mod_index = None;
+        self.seq_index = None;
+    }
+
+    /// Silences the output, where `amps` are restored on resume, the current ones if None
+    pub fn pause(&mut self, amps: Option<Vec<f32>>) {
+        self.last_amp = amps.unwrap_or_else(|| self.sources.iter().map(|s| s.amp).collect());
+        for source in self.sources.iter_mut() {
+            source.amp = 0.;
+        }
+    }
+
+    /// Restores the amplitudes, returning whether it was paused, because every frame of Op also
+    /// resumes, which is not the output enabled
+    pub fn resume(&mut self) -> bool {
+        let paused = !self.last_amp.is_empty();
+        for (source, &amp) in self.sources.iter_mut().zip(self.last_amp.iter()) {
+            source.amp = amp;
+        }
+        self.last_amp.clear();
+        paused
+    }
+
+    pub fn set_sequence(&mut self, seq: Sequence) {
+        self.sequence = Some(seq);
+        self.seq_index = None;
+    }
+
+    /// The STM in sequence mode, if any
+    pub fn active_sequence(&self) -> Option<&Sequence> {
+        self.sequence.as_ref().filter(|seq| {
+            self.ctrl_flag.contains(RxGlobalControlFlags::SEQ_MODE) && !seq.seq_data.is_empty()
+        })
+    }
+
+    /// STM point `idx` of the sequence, if any, in the global coordinates
+    pub fn stm_point(&self, idx: usize) -> Option<(Vector3, u8)> {
+        let seq = self.sequence.as_ref()?;
+        seq.seq_data.get(idx).map(|&point| self.global_point(point))
+    }
+
+    /// STM points of the sequence, if any, in the global coordinates
+    pub fn stm_points(&self) -> Vec<(Vector3, u8)> {
+        self.sequence.as_ref().map_or_else(Vec::new, |seq| {
+            seq.seq_data
+                .iter()
+                .map(|&point| self.global_point(point))
+                .collect()
+        })
+    }
+
+    // The SDK sends the STM points in the coordinates of device 0, which are the global ones if
+    // the devices are unknown
+    fn global_point(&self, (focus, duty): (Vector3, u8)) -> (Vector3, u8) {
+        match self.geometries.first() {
+            Some(geometry) => (geometry.global_position(focus), duty),
+            None => (focus, duty),
+        }
+    }
+
+    /// Updates the modulation and STM sample indices at the time of `clock`, and drives the
+    /// sources toward the focus of the current STM point, returning whether the drive changed
+    pub fn update_playback(
+        &mut self,
+        clock: &Clock,
+        settings: &ViewerSettings,
+        perturbation: &Perturbation,
+    ) -> bool {
+        self.mod_index = self.modulation.as_ref().map(|m| {
+            let freq = MOD_SAMPLING_FREQ_BASE / m.mod_div.max(1) as f64;
+            clock.sample_index(freq, m.mod_data.len())
+        });
+
+        let (idx, (focus, amp)) = match self.active_sequence() {
+            Some(seq) => {
+                let freq = POINT_SEQ_BASE_FREQ as f64 / seq.seq_div.max(1) as f64;
+                let idx = clock.sample_index(freq, seq.seq_data.len());
+                (idx, self.global_point(seq.seq_data[idx]))
+            }
+            None => {
+                self.seq_index = None;
+                return false;
+            }
+        };
+        if self.seq_index == Some(idx) {
+            return false;
+        }
+        self.seq_index = Some(idx);
+        // the firmware is paused, and the drive is restored on resume
+        if !self.last_amp.is_empty() {
+            return false;
+        }
+
+        let amp = (amp as f32 / 510.0 * PI).sin();
+        for source in self.sources.iter_mut() {
+            let k = source.wavenumber(settings);
+            let d = vecmath_util::dist(source.pos, focus);
+            source.amp = amp;
+            source.phase = (k * d).rem_euclid(2.0 * PI);
+        }
+        self.adjust(perturbation);
+        true
+    }
+
+    /// Keeps the drive as sent to be adjusted again by [Decoder::readjust], and adjusts it
+    pub fn adjust(&mut self, perturbation: &Perturbation) {
+        self.sent_drive = self.sources.iter().map(|s| (s.amp, s.phase)).collect();
+        self.apply_drive_errors(perturbation);
+    }
+
+    fn apply_drive_errors(&mut self, perturbation: &Perturbation) {
+        let mut sources = std::mem::take(&mut self.sources);
+        self.apply_drive_errors_to(&mut sources, perturbation);
+        self.sources = sources;
+    }
+
+    /// Drive of the sources without the perturbation, i.e., the last sent drive with the
+    /// overrides and the calibration
+    pub fn nominal_drive(&self) -> Vec<SoundSource> {
+        let mut sources = self.sources.clone();
+        if self.sent_drive.len() == sources.len() {
+            for (source, &(amp, phase)) in sources.iter_mut().zip(&self.sent_drive) {
+                source.amp = amp;
+                source.phase = phase;
+            }
+            self.apply_nominal_errors(&mut sources);
+        }
+        sources
+    }
+
+    // Replaces the overridden drives, and then multiplies the calibration
+    fn apply_nominal_errors(&self, sources: &mut [SoundSource]) {
+        for (&i, &(amp, phase)) in &self.drive_overrides {
+            if let Some(source) = sources.get_mut(i) {
+                source.amp = amp;
+                source.phase = phase;
+            }
+        }
+        if let Some(calibration) = &self.trans_calibration {
+            calibration.apply(sources, &self.device_sizes);
+        }
+    }
+
+    /// Replaces the overridden drives, and then multiplies the calibration and the perturbation
+    /// into the drive of `sources`, which are those of the geometry
+    pub fn apply_drive_errors_to(
+        &mut self,
+        sources: &mut [SoundSource],
+        perturbation: &Perturbation,
+    ) {
+        self.apply_nominal_errors(sources);
+        if !perturbation.enabled {
+            return;
+        }
+        let n = sources.len();
+        let sample = match &self.perturbation_sample {
+            Some(sample) if sample.errors.len() == n => sample,
+            _ => self.perturbation_sample.insert(perturbation.sample(n)),
+        };
+        sample.apply(sources);
+    }
+
+    /// Adjusts the last sent drive again, e.g., after resampling the perturbation, returning
+    /// whether it is adjusted, which is not while paused
+    pub fn readjust(&mut self, perturbation: &Perturbation) -> bool {
+        if !self.last_amp.is_empty() || self.sent_drive.len() != self.sources.len() {
+            return false;
+        }
+        for (source, &(amp, phase)) in self.sources.iter_mut().zip(&self.sent_drive) {
+            source.amp = amp;
+            source.phase = phase;
+        }
+        self.apply_drive_errors(perturbation);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder(device_sizes: &[usize]) -> Decoder {
+        let n = device_sizes.iter().sum();
+        Decoder {
+            sources: vec![SoundSource::new([0., 0., 0.], [0., 0., 1.], 0., 0.); n],
+            device_sizes: device_sizes.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn gain_of_the_frame_entries() {
+        let mut decoder = decoder(&[2, 3]);
+        let gain = Gain {
+            amps: (0..498)
+                .map(|i| if i % 249 < 3 { 255 } else { 0 })
+                .collect(),
+            phases: (0..498).map(|i| (i % 251) as u8).collect(),
+        };
+        decoder.set_gain(&gain);
+        for (source, &entry) in decoder.sources.iter().zip(&[0, 1, 249, 250, 251]) {
+            let phase = 2.0 * PI * (1.0 - gain.phases[entry] as f32 / 255.0);
+            assert!((source.amp - 1.0).abs() < 1e-6);
+            assert!((source.phase - phase).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn gain_in_order_without_devices() {
+        let mut decoder = decoder(&[3]);
+        decoder.device_sizes.clear();
+        decoder.set_gain(&Gain {
+            amps: vec![0, 255, 0],
+            phases: vec![0; 3],
+        });
+        let amps: Vec<_> = decoder.sources.iter().map(|s| s.amp).collect();
+        assert_eq!(amps, vec![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn cycles_of_the_frame_entries() {
+        let mut decoder = decoder(&[2, 3]);
+        let cycles: Vec<u16> = (0..498).map(|i| 4000 + i as u16).collect();
+        decoder.set_cycles(&cycles);
+        for (source, &entry) in decoder.sources.iter().zip(&[0, 1, 249, 250, 251]) {
+            assert_eq!(source.frequency, cycle_to_frequency(cycles[entry]));
+        }
+
+        // the sources of the missing entries keep their frequency
+        decoder.set_cycles(&[8000; 250]);
+        let frequencies: Vec<_> = decoder.sources.iter().map(|s| s.frequency).collect();
+        let expected = [8000, 8000, 8000, 4250, 4251].map(cycle_to_frequency);
+        assert_eq!(frequencies, expected.to_vec());
+    }
+
+    #[test]
+    fn stm_focus_of_a_moved_device() {
+        // device 0 rotated by 90 degrees around z, so that its x is the global y
+        let geometry = Geometry {
+            origin: [100., 50., 20.],
+            right: [0., 1., 0.],
+            up: [-1., 0., 0.],
+            layout: Default::default(),
+        };
+        let mut decoder = Decoder::default();
+        decoder.set_geometries(vec![geometry], false);
+        decoder.ctrl_flag = RxGlobalControlFlags::SEQ_MODE;
+        decoder.set_sequence(Sequence {
+            seq_div: 1,
+            seq_data: vec![([10., 20., 150.], 255)],
+        });
+        let focus = [80., 60., 170.];
+        assert_eq!(decoder.stm_point(0), Some((focus, 255)));
+        assert_eq!(decoder.stm_points(), vec![(focus, 255)]);
+        let settings = ViewerSettings::default();
+        assert!(decoder.update_playback(&Clock::new(1.0), &settings, &Perturbation::new()));
+        for source in &decoder.sources {
+            let phase = (source.wavenumber(&settings) * vecmath_util::dist(source.pos, focus))
+                .rem_euclid(2.0 * PI);
+            assert!((source.amp - 1.0).abs() < 1e-6);
+            assert!((source.phase - phase).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * File: drift.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{view::ViewerSettings, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Room temperature over a session, linearly interpolated between the points and held after the
+/// last one
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemperatureProfile {
+    /// [time in minutes, temperature in Celsius], in ascending order of time
+    pub points: Vec<[f32; 2]>,
+    /// Minutes of the profile per second of the simulation clock
+    pub speed: f32,
+}
+
+impl TemperatureProfile {
+    /// Warming up from 20 C to 26 C over 8 hours, e.g., of an exhibition hall
+    pub fn new() -> Self {
+        Self {
+            points: vec![[0.0, 20.0], [480.0, 26.0]],
+            speed: 60.0,
+        }
+    }
+
+    /// Temperature at the first point, at which the drive is assumed to be calibrated
+    pub fn reference(&self) -> Option<f32> {
+        self.points.first().map(|p| p[1])
+    }
+
+    /// Temperature at `minutes` from the start of the profile
+    pub fn temperature_at(&self, minutes: f32) -> Option<f32> {
+        let first = self.points.first()?;
+        if minutes <= first[0] {
+            return Some(first[1]);
+        }
+        let temperature = self.points.windows(2).find(|w| minutes < w[1][0]).map(|w| {
+            let [t0, v0] = w[0];
+            let [t1, v1] = w[1];
+            if t1 <= t0 {
+                v1
+            } else {
+                v0 + (v1 - v0) * (minutes - t0) / (t1 - t0)
+            }
+        });
+        temperature.or_else(|| self.points.last().map(|p| p[1]))
+    }
+
+    /// Ratio of the sound speed at `temperature` to that at [TemperatureProfile::reference], by
+    /// which the distances of the foci from the array scale approximately
+    pub fn focal_scale(&self, temperature: f32) -> f32 {
+        match self.reference() {
+            Some(reference) => {
+                ViewerSettings::sound_speed_in_air(temperature)
+                    / ViewerSettings::sound_speed_in_air(reference)
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Sorts the points by time after editing
+    pub fn sort(&mut self) {
+        self.points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    }
+}
+
+impl Default for TemperatureProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Estimated position of the focus commanded at `focus` for the array centered at `center`, when
+/// the sound speed is `scale` times that at the calibration
+pub fn drifted_focus(center: Vector3, focus: Vector3, scale: f32) -> Vector3 {
+    let r = vecmath::vec3_sub(focus, center);
+    vecmath::vec3_add(center, vecmath::vec3_scale(r, scale))
+}
@@ -0,0 +1,261 @@
+/*
+ * File: drive_history.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    collections::VecDeque,
+    f64::consts::PI,
+    fs::File,
+    io::{BufWriter, Write},
+    mem::size_of,
+    path::Path,
+};
+
+use acoustic_field_viewer::{
+    metadata::ExperimentMetadata, sound_source::SoundSource, view::ViewerSettings, Vector3,
+};
+use autd3_core::hardware_defined::MOD_SAMPLING_FREQ_BASE;
+use autd3_emulator_server::{HistoryLimits, Modulation};
+
+use crate::wav;
+
+/// Amplitude, phase and frequency of a source from a simulation time in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriveChange {
+    pub time: f64,
+    pub amp: f32,
+    /// In radian
+    pub phase: f32,
+    /// In Hz
+    pub frequency: f32,
+}
+
+// Amplitude, phase and frequency of a source
+type Drive = (f32, f32, f32);
+
+/// Drives of all the sources over the session, where only the sources changed are recorded on
+/// each change, within the limits of the retained frames as well, beyond which the oldest
+/// changes and modulations are evicted
+pub struct DriveHistory {
+    // Indices in ascending order and drives of the sources changed
+    drives: VecDeque<(f64, Vec<(usize, Drive)>)>,
+    // Last change of each source evicted from `drives`, which holds until its next change
+    base: Vec<Option<(f64, Drive)>>,
+    last: Vec<Drive>,
+    bytes: usize,
+    limits: HistoryLimits,
+    modulations: VecDeque<(f64, Option<Modulation>)>,
+}
+
+/// Drive of one source over the session, with the modulations applied to it
+#[derive(Clone)]
+pub struct SourceHistory {
+    pub index: usize,
+    pub pos: Vector3,
+    pub changes: Vec<DriveChange>,
+    modulations: Vec<(f64, Option<Modulation>)>,
+}
+
+impl DriveHistory {
+    pub fn new(limits: HistoryLimits) -> Self {
+        Self {
+            drives: VecDeque::new(),
+            base: Vec::new(),
+            last: Vec::new(),
+            bytes: 0,
+            limits,
+            modulations: VecDeque::new(),
+        }
+    }
+
+    /// Changes the limits, evicting the oldest beyond them at once
+    pub fn set_limits(&mut self, limits: HistoryLimits) {
+        self.limits = limits;
+        self.evict_over_limits();
+        while self.modulations.len() > limits.max_frames {
+            self.modulations.pop_front();
+        }
+    }
+
+    /// Records the drive of the sources of `sources` changed from the last at `time`
+    pub fn record(&mut self, time: f64, sources: &[SoundSource], settings: &ViewerSettings) {
+        let drive: Vec<Drive> = sources
+            .iter()
+            .map(|s| (s.amp, s.phase, s.frequency.unwrap_or(settings.frequency)))
+            .collect();
+        // all the sources are recorded on a new geometry
+        let resized = drive.len() != self.last.len();
+        let changes: Vec<_> = drive
+            .iter()
+            .enumerate()
+            .filter(|&(i, d)| resized || self.last[i] != *d)
+            .map(|(i, &d)| (i, d))
+            .collect();
+        self.last = drive;
+        if changes.is_empty() {
+            return;
+        }
+        self.bytes += changes.len() * size_of::<(usize, Drive)>();
+        self.drives.push_back((time, changes));
+        self.evict_over_limits();
+    }
+
+    fn evict_over_limits(&mut self) {
+        while self.drives.len() > self.limits.max_frames || self.bytes > self.limits.max_bytes {
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) {
+        let (time, changes) = match self.drives.pop_front() {
+            Some(entry) => entry,
+            None => return,
+        };
+        self.bytes -= changes.len() * size_of::<(usize, Drive)>();
+        for (i, drive) in changes {
+            if self.base.len() <= i {
+                self.base.resize(i + 1, None);
+            }
+            self.base[i] = Some((time, drive));
+        }
+    }
+
+    /// Records the modulation from `time`, where None is no modulation, i.e., after `Clear`
+    pub fn record_modulation(&mut self, time: f64, modulation: Option<Modulation>) {
+        self.modulations.push_back((time, modulation));
+        while self.modulations.len() > self.limits.max_frames {
+            self.modulations.pop_front();
+        }
+    }
+
+    /// History of the source `index`, which is None if it has never existed
+    pub fn source(&self, index: usize, pos: Vector3) -> Option<SourceHistory> {
+        let base = self.base.get(index).copied().flatten();
+        let changes: Vec<_> = base
+            .into_iter()
+            .chain(self.drives.iter().filter_map(|(time, changes)| {
+                changes
+                    .binary_search_by_key(&index, |&(i, _)| i)
+                    .ok()
+                    .map(|k| (*time, changes[k].1))
+            }))
+            .map(|(time, (amp, phase, frequency))| DriveChange {
+                time,
+                amp,
+                phase,
+                frequency,
+            })
+            .collect();
+        if changes.is_empty() {
+            return None;
+        }
+        Some(SourceHistory {
+            index,
+            pos,
+            changes,
+            modulations: self.modulations.iter().cloned().collect(),
+        })
+    }
+}
+
+// The last entry from `time` or before
+fn last_at<T>(entries: &[(f64, T)], time: f64) -> Option<&T> {
+    let i = entries.partition_point(|(t, _)| *t <= time);
+    i.checked_sub(1).map(|i| &entries[i].1)
+}
+
+impl SourceHistory {
+    /// Amplitude normalized to 1 at the maximum duty with the modulation applied, phase and
+    /// frequency at `time`
+    pub fn drive_at(&self, time: f64) -> (f32, f32, f32) {
+        let i = self.changes.partition_point(|c| c.time <= time);
+        let change = match i.checked_sub(1) {
+            Some(i) => self.changes[i],
+            None => return (0.0, 0.0, self.changes[0].frequency),
+        };
+        let m = match last_at(&self.modulations, time) {
+            Some(Some(m)) if !m.mod_data.is_empty() => {
+                let freq = MOD_SAMPLING_FREQ_BASE / m.mod_div.max(1) as f64;
+                let idx = ((time * freq) as u64 % m.mod_data.len() as u64) as usize;
+                (m.mod_data[idx] as f32 / 512.0 * std::f32::consts::PI).sin()
+            }
+            _ => 1.0,
+        };
+        (change.amp * m, change.phase, change.frequency)
+    }
+
+    /// Samples from `start` for `duration` seconds at `sample_rate` of the amplitude, the phase
+    /// divided by pi in [-1, 1), and the drive waveform, i.e., the PWM of the duty
+    /// asin(amplitude) / pi centered at the peaks of cos(2 pi f t + phase), which is 1 when high
+    /// and 0 when low, interleaved in this order
+    pub fn waveform(&self, start: f64, duration: f64, sample_rate: f64) -> Vec<f32> {
+        let n = (duration * sample_rate).max(0.0) as usize;
+        (0..n)
+            .flat_map(|i| {
+                let t = start + i as f64 / sample_rate;
+                let (amp, phase, freq) = self.drive_at(t);
+                let duty = amp.clamp(0.0, 1.0).asin() as f64 / PI;
+                let cycle = (freq as f64 * t + phase as f64 / (2.0 * PI)).rem_euclid(1.0);
+                let high = (cycle - 0.5).abs() > 0.5 - duty / 2.0;
+                let wrapped = (phase as f64 / PI + 1.0).rem_euclid(2.0) - 1.0;
+                [amp, wrapped as f32, if high { 1.0 } else { 0.0 }]
+            })
+            .collect()
+    }
+
+    /// Writes the drive changes, where the duty is the fraction of the period of the PWM
+    pub fn write_csv(&self, path: &Path, metadata: &ExperimentMetadata) -> Result<(), String> {
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let res: std::io::Result<()> = (|| {
+            for (key, value) in metadata.entries() {
+                writeln!(writer, "# {}: {}", key, value.replace('\n', " "))?;
+            }
+            let [x, y, z] = self.pos;
+            writeln!(
+                writer,
+                "# source: {} at ({}, {}, {}) mm",
+                self.index, x, y, z
+            )?;
+            writeln!(writer, "# modulation is not applied to amp")?;
+            writeln!(writer, "t [s],amp,phase [rad],duty,frequency [Hz]")?;
+            for c in &self.changes {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{}",
+                    c.time,
+                    c.amp,
+                    c.phase,
+                    c.amp.clamp(0.0, 1.0).asin() / std::f32::consts::PI,
+                    c.frequency
+                )?;
+            }
+            writer.flush()
+        })();
+        res.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Writes [SourceHistory::waveform] as a WAV of three channels
+    pub fn write_wav(
+        &self,
+        path: &Path,
+        start: f64,
+        duration: f64,
+        sample_rate: f64,
+    ) -> Result<(), String> {
+        wav::write_float(
+            path,
+            sample_rate,
+            3,
+            &self.waveform(start, duration, sample_rate),
+        )
+    }
+}
@@ -0,0 +1,339 @@
+/*
+ * File: export.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use acoustic_field_viewer::{
+    directivity::DirectivityTable,
+    field::{self, Complex, FieldModel, SliceGrid},
+    metadata::ExperimentMetadata,
+    scatterer::Scattering,
+    sound_source::SoundSource,
+    view::ViewerSettings,
+    Matrix4, Vector3,
+};
+use scarlet::color::RGBColor;
+use serde::{Deserialize, Serialize};
+
+use crate::npy;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Coordinates and the field quantity of each point
+    Csv,
+    /// The slice colored with the color map
+    Png,
+    /// NumPy array of the rows of the slice, with the coordinate axes and the metadata in a JSON
+    /// file next to it
+    Npy,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Csv, ExportFormat::Png, ExportFormat::Npy];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Png => "png",
+            ExportFormat::Npy => "npy",
+        }
+    }
+
+    /// Whether the values are written, rather than the colors
+    pub fn has_values(&self) -> bool {
+        matches!(self, ExportFormat::Csv | ExportFormat::Npy)
+    }
+}
+
+/// Values of the points of the exported slice
+enum SliceValues {
+    /// Field quantity of the slice
+    Quantity(Vec<f32>),
+    /// Complex pressure in the unit of the field
+    Pressure(Vec<Complex>),
+}
+
+/// Unit of the coordinates in exported files
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    Mm,
+    M,
+}
+
+impl LengthUnit {
+    pub const ALL: [LengthUnit; 2] = [LengthUnit::Mm, LengthUnit::M];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LengthUnit::Mm => "mm",
+            LengthUnit::M => "m",
+        }
+    }
+
+    fn convert_mm(&self, v: f32) -> f32 {
+        match self {
+            LengthUnit::Mm => v,
+            LengthUnit::M => v / 1000.0,
+        }
+    }
+}
+
+/// Named export settings, triggered from the `Export` tab, hotkeys or the SDK
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportPreset {
+    pub name: String,
+    pub format: ExportFormat,
+    /// Spacing of the exported points in mm
+    pub resolution: f32,
+    /// The real and the imaginary parts of the pressure are written instead of the field quantity,
+    /// except in PNG
+    #[serde(default)]
+    pub complex: bool,
+    pub unit: LengthUnit,
+    pub folder: String,
+    /// File name, where `{name}`, `{timestamp}` and `{gain}` (the number of gains received) are
+    /// replaced. The extension of the format is appended if missing.
+    pub filename: String,
+}
+
+impl ExportPreset {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            format: ExportFormat::Csv,
+            resolution: 1.0,
+            complex: false,
+            unit: LengthUnit::Mm,
+            folder: std::env::current_dir()
+                .unwrap_or_default()
+                .join("export")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            filename: "{name}_{timestamp}_{gain}".to_owned(),
+        }
+    }
+
+    pub fn path(&self, gain_index: usize, time: chrono::DateTime<chrono::Local>) -> PathBuf {
+        let filename = self
+            .filename
+            .replace("{name}", &self.name)
+            .replace(
+                "{timestamp}",
+                &time.format("%Y-%m-%d_%H-%M-%S_%3f").to_string(),
+            )
+            .replace("{gain}", &gain_index.to_string());
+        let mut path = Path::new(&self.folder).join(filename);
+        if path
+            .extension()
+            .is_none_or(|ext| ext != self.format.extension())
+        {
+            let mut name = path.into_os_string();
+            name.push(".");
+            name.push(self.format.extension());
+            path = PathBuf::from(name);
+        }
+        path
+    }
+
+    /// Computes the field of `slice` and writes it to `path`
+    pub fn export(&self, slice: &SliceSnapshot, path: &Path) -> Result<(), String> {
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+        }
+        let model = FieldModel {
+            sources: &slice.sources,
+            settings: &slice.settings,
+            directivity_table: slice.directivity_table.as_ref(),
+            scattering: slice.scattering.as_ref(),
+            device_sizes: &slice.device_sizes,
+        };
+        let (grid, values) = if self.complex && self.format.has_values() {
+            let (grid, values) =
+                field::compute_slice_pressure(&model, slice.model, self.resolution);
+            (grid, SliceValues::Pressure(values))
+        } else {
+            let (grid, values) =
+                field::compute_slice(&model, slice.model, self.resolution, slice.wave_phase);
+            (grid, SliceValues::Quantity(values))
+        };
+        match (self.format, &values) {
+            (ExportFormat::Csv, values) => self.write_csv(path, slice, &grid, values),
+            (ExportFormat::Npy, values) => self.write_npy(path, slice, &grid, values),
+            (ExportFormat::Png, SliceValues::Quantity(values)) => {
+                Self::write_png(path, slice, &grid, values)
+            }
+            (ExportFormat::Png, SliceValues::Pressure(_)) => unreachable!(),
+        }
+        .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // The first point and the steps to the next points along the rows and the columns, in the
+    // unit of the coordinates
+    fn axes(&self, grid: &SliceGrid) -> (Vector3, Vector3, Vector3) {
+        let convert = |p: Vector3| vecmath_util::vec3_map(p, |c| self.unit.convert_mm(c));
+        let origin = grid.points.first().copied().unwrap_or([0.0; 3]);
+        let step = |i: usize| {
+            grid.points
+                .get(i)
+                .filter(|_| i > 0)
+                .map_or([0.0; 3], |&p| convert(vecmath::vec3_sub(p, origin)))
+        };
+        (convert(origin), step(1), step(grid.nx))
+    }
+
+    fn write_csv(
+        &self,
+        path: &Path,
+        slice: &SliceSnapshot,
+        grid: &SliceGrid,
+        values: &SliceValues,
+    ) -> Result<(), String> {
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let columns = match values {
+            SliceValues::Quantity(_) => slice.settings.field_quantity.name(),
+            SliceValues::Pressure(_) => "re,im",
+        };
+        let unit = self.unit.name();
+        let (origin, u, v) = self.axes(grid);
+        let res: std::io::Result<()> = (|| {
+            for (key, value) in slice.metadata.entries() {
+                writeln!(writer, "# {}: {}", key, value.replace('\n', " "))?;
+            }
+            writeln!(
+                writer,
+                "# calibration: {} Pa per unit",
+                slice.settings.calibration.pa_per_unit()
+            )?;
+            writeln!(writer, "# {} x {} points", grid.nx, grid.ny)?;
+            writeln!(
+                writer,
+                "# origin {:?}, row step {:?}, column step {:?} [{}]",
+                origin, u, v, unit
+            )?;
+            writeln!(writer, "x [{0}],y [{0}],z [{0}],{1}", unit, columns)?;
+            for (i, p) in grid.points.iter().enumerate() {
+                let [x, y, z] = vecmath_util::vec3_map(*p, |c| self.unit.convert_mm(c));
+                match values {
+                    SliceValues::Quantity(values) => {
+                        writeln!(writer, "{},{},{},{}", x, y, z, values[i])?
+                    }
+                    SliceValues::Pressure(values) => {
+                        let [re, im] = values[i];
+                        writeln!(writer, "{},{},{},{},{}", x, y, z, re, im)?
+                    }
+                }
+            }
+            writer.flush()
+        })();
+        res.map_err(|e| e.to_string())
+    }
+
+    // The array is of the shape (ny, nx) from the bottom row, and the point [j, i] is at
+    // origin + i * u + j * v in the JSON file
+    fn write_npy(
+        &self,
+        path: &Path,
+        slice: &SliceSnapshot,
+        grid: &SliceGrid,
+        values: &SliceValues,
+    ) -> Result<(), String> {
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let shape = [grid.ny, grid.nx];
+        let quantity = match values {
+            SliceValues::Quantity(values) => {
+                npy::write_f32(&mut writer, &shape, values).map_err(|e| e.to_string())?;
+                slice.settings.field_quantity.name()
+            }
+            SliceValues::Pressure(values) => {
+                npy::write_complex(&mut writer, &shape, values).map_err(|e| e.to_string())?;
+                "Complex pressure"
+            }
+        };
+        writer.flush().map_err(|e| e.to_string())?;
+        let (origin, u, v) = self.axes(grid);
+        let metadata: serde_json::Map<_, _> = slice
+            .metadata
+            .entries()
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), serde_json::Value::from(value)))
+            .collect();
+        let axes = serde_json::json!({
+            "quantity": quantity,
+            "shape": shape,
+            "unit": self.unit.name(),
+            "origin": origin,
+            "u": u,
+            "v": v,
+            "calibration_pa_per_unit": slice.settings.calibration.pa_per_unit(),
+            "metadata": metadata,
+        });
+        let json = path.with_extension("json");
+        std::fs::write(
+            &json,
+            serde_json::to_string_pretty(&axes).unwrap_or_default(),
+        )
+        .map_err(|e| format!("{}: {}", json.display(), e))
+    }
+
+    fn write_png(
+        path: &Path,
+        slice: &SliceSnapshot,
+        grid: &SliceGrid,
+        values: &[f32],
+    ) -> Result<(), String> {
+        let color_map = &slice.color_map;
+        if color_map.is_empty() {
+            return Err("empty color map".to_owned());
+        }
+        let pixels: Vec<_> = values
+            .chunks_exact(grid.nx)
+            .rev()
+            .flatten()
+            .map(|&v| {
+                let t = field::color_position(v, &slice.settings);
+                color_map[(t * (color_map.len() - 1) as f32) as usize]
+            })
+            .flat_map(|c| vecmath_util::vec3_map([c.r, c.g, c.b], |v| (v * 255.0) as u8))
+            .collect();
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), grid.nx as u32, grid.ny as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (key, value) in slice.metadata.entries() {
+            encoder
+                .add_itxt_chunk(key.to_owned(), value.to_owned())
+                .map_err(|e| e.to_string())?;
+        }
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&pixels).map_err(|e| e.to_string())
+    }
+}
+
+/// State of the slice owned by the export, so that the field can be computed on the I/O worker
+pub struct SliceSnapshot {
+    pub sources: Vec<SoundSource>,
+    /// Number of the sources of each device
+    pub device_sizes: Vec<usize>,
+    pub settings: ViewerSettings,
+    pub directivity_table: Option<DirectivityTable>,
+    pub scattering: Option<Scattering>,
+    pub model: Matrix4,
+    pub wave_phase: f32,
+    pub color_map: Vec<RGBColor>,
+    pub metadata: ExperimentMetadata,
+}
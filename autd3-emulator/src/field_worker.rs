@@ -0,0 +1,102 @@
+/*
+ * File: field_worker.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use acoustic_field_viewer::{
+    directivity::DirectivityTable, field::FieldModel, scatterer::Scattering,
+    sound_source::SoundSource, view::ViewerSettings,
+};
+
+/// Field of the drive owned by a background thread, as [FieldModel] borrows it
+#[derive(Clone)]
+pub struct OwnedField {
+    pub sources: Vec<SoundSource>,
+    /// Number of the sources of each device
+    pub device_sizes: Vec<usize>,
+    pub settings: ViewerSettings,
+    pub directivity_table: Option<DirectivityTable>,
+    pub scattering: Option<Scattering>,
+}
+
+impl OwnedField {
+    pub fn model(&self) -> FieldModel<'_> {
+        FieldModel {
+            sources: &self.sources,
+            settings: &self.settings,
+            directivity_table: self.directivity_table.as_ref(),
+            scattering: self.scattering.as_ref(),
+            device_sizes: &self.device_sizes,
+        }
+    }
+}
+
+type Job<T> = Box<dyn FnOnce() -> T + Send>;
+
+/// Runs a computation on an [OwnedField] on a background thread, one at a time, where the
+/// requests arriving meanwhile are coalesced into the latest
+pub struct FieldWorker<T> {
+    name: &'static str,
+    running: Option<Receiver<T>>,
+    pending: Option<Job<T>>,
+}
+
+impl<T: Send + 'static> FieldWorker<T> {
+    /// `name` is of the computation in the logs
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            running: None,
+            pending: None,
+        }
+    }
+
+    pub fn request<F>(&mut self, field: OwnedField, f: F)
+    where
+        F: FnOnce(&FieldModel) -> T + Send + 'static,
+    {
+        self.pending = Some(Box::new(move || f(&field.model())));
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.running.is_some() || self.pending.is_some()
+    }
+
+    /// Starts the pending computation if idle, and returns the result of the finished one
+    pub fn poll(&mut self) -> Option<T> {
+        let result = match &self.running {
+            Some(rx) => match rx.try_recv() {
+                Ok(result) => Some(result),
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    tracing::error!("{} panicked", self.name);
+                    None
+                }
+            },
+            None => None,
+        };
+        self.running = None;
+        if let Some(job) = self.pending.take() {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                // the receiver is dropped if the emulator exits or the request is cancelled
+                let _ = tx.send(job());
+            });
+            self.running = Some(rx);
+        }
+        result
+    }
+}
@@ -0,0 +1,615 @@
+/*
+ * File: figure.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    time::Duration,
+};
+
+use acoustic_field_viewer::{
+    calibration::PressureUnit,
+    color_map::{self, ColorMapKind},
+    directivity::{Directivity, DirectivityTable},
+    field::{self, FieldModel, SliceGrid},
+    scatterer::{Facet, Scattering},
+    view::{FieldQuantity, ViewerSettings},
+    Matrix4, Vector3,
+};
+use autd3_emulator_server::{read_capture, AutdData, Parser};
+use image::{Rgb, RgbImage};
+use rusttype::{point, Font, Scale};
+use scarlet::color::RGBColor;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::Clock, decoder::Decoder, fixtures::Fixture, settings::Setting,
+    trans_calibration::TransCalibration,
+};
+
+const FONT: &[u8] = include_bytes!("../../assets/fonts/NotoSans-Regular.ttf");
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const FOREGROUND: Rgb<u8> = Rgb([0, 0, 0]);
+const MARKER: Rgb<u8> = Rgb([255, 255, 255]);
+const TEXT_SIZE: f32 = 16.0;
+const TITLE_SIZE: f32 = 22.0;
+const TICK_LENGTH: u32 = 5;
+const COLOR_BAR_WIDTH: u32 = 20;
+
+/// Figures rendered headlessly from a recording with `--figure`, where the paths are relative
+/// to the spec file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FigureSpec {
+    /// Capture file recorded in the `Capture` section of the `Log` tab
+    pub recording: String,
+    /// Folder of the images, the folder of the spec if omitted
+    #[serde(default)]
+    pub output: String,
+    /// Settings before the recording is replayed, those of `setting.json` if omitted
+    pub viewer_setting: Option<ViewerSettings>,
+    /// Table for the imported directivity, that of `setting.json` if omitted
+    pub directivity_file: Option<String>,
+    pub figures: Vec<Figure>,
+}
+
+/// Labeled image of a slice
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Figure {
+    /// File name of the image without the extension
+    pub name: String,
+    /// Time in seconds from the start of the recording, the end if omitted
+    pub time: Option<f32>,
+    #[serde(default)]
+    pub title: String,
+    /// Center of the slice in mm
+    pub center: Option<Vector3>,
+    /// Euler angles of the slice in degrees
+    pub angle: Option<Vector3>,
+    /// Width and height of the slice in mm
+    pub size: Option<[i32; 2]>,
+    pub quantity: Option<FieldQuantity>,
+    /// In the unit of the field, as [ViewerSettings::color_scale]
+    pub color_scale: Option<f32>,
+    /// Turns on the log scale, see [ViewerSettings::dynamic_range]
+    pub dynamic_range: Option<f32>,
+    pub color_map: Option<ColorMapKind>,
+    /// Spacing of the points in mm, 0.5 if omitted
+    pub resolution: Option<f32>,
+    /// Pixels per point, 2 if omitted
+    pub pixel_size: Option<u32>,
+    /// Shown if omitted
+    pub color_bar: Option<bool>,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+/// Marker with a label at a point, e.g., a focus
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Annotation {
+    /// Position in mm, projected onto the slice
+    pub pos: Vector3,
+    #[serde(default)]
+    pub label: String,
+}
+
+/// Drive of the transducers replayed from the frames of a recording by the [Decoder] of the
+/// viewer, with the transducer calibration and the perturbation of the setting
+pub struct ReplayState {
+    pub drive: Decoder,
+    /// The last viewer setting sent by the SDK, or the base one
+    pub viewer_setting: ViewerSettings,
+}
+
+impl ReplayState {
+    /// Replays `frames` up to `time` in seconds, to the end if None, where the modulation and
+    /// the STM are played at that time
+    pub fn replay(
+        frames: &[(Duration, Vec<u8>)],
+        time: Option<f32>,
+        base: ViewerSettings,
+        setting: &Setting,
+    ) -> Self {
+        let mut drive = Decoder::default();
+        if !setting.trans_calibration_file.is_empty() {
+            match TransCalibration::from_csv(&setting.trans_calibration_file) {
+                Ok(calibration) => drive.trans_calibration = Some(calibration),
+                Err(e) => tracing::error!("failed to load transducer calibration: {}", e),
+            }
+        }
+        let mut viewer_setting = base;
+        let mut parser = Parser::new();
+        let mut end = Duration::ZERO;
+        for (t, frame) in frames {
+            if time.is_some_and(|time| t.as_secs_f32() > time) {
+                break;
+            }
+            end = *t;
+            for data in parser.parse(frame) {
+                match data {
+                    AutdData::ViewerSetting(json) => match serde_json::from_slice(&json) {
+                        Ok(setting) => viewer_setting = setting,
+                        Err(e) => tracing::warn!("invalid viewer setting: {}", e),
+                    },
+                    data => drive.apply(data, &setting.perturbation),
+                }
+            }
+        }
+        let mut clock = Clock::new(1.0);
+        clock.set_time(time.map_or(end.as_secs_f64(), f64::from));
+        drive.update_playback(&clock, &viewer_setting, &setting.perturbation);
+        Self {
+            drive,
+            viewer_setting,
+        }
+    }
+
+    /// Scattering off the fixtures of `setting` marked as scatterers in the field of the drive,
+    /// as the viewer evaluates it
+    pub fn scattering(
+        &self,
+        setting: &Setting,
+        directivity_table: Option<&DirectivityTable>,
+    ) -> Option<Scattering> {
+        let fixtures: Vec<_> = setting
+            .fixtures
+            .iter()
+            .filter(|f| f.scatterer)
+            .map(Fixture::load)
+            .collect();
+        let meshes: Vec<_> = fixtures
+            .iter()
+            .filter_map(|f| match &f.mesh {
+                Ok(mesh) => Some(mesh),
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    None
+                }
+            })
+            .collect();
+        if meshes.is_empty() {
+            return None;
+        }
+        let facets = Facet::split(&meshes, self.viewer_setting.wave_length / 4.0);
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings: &self.viewer_setting,
+            directivity_table,
+            scattering: None,
+            device_sizes: &self.drive.device_sizes,
+        };
+        Some(Scattering::new(facets, &model))
+    }
+}
+
+/// Renders the figures of the spec at `path`, returning the number of images written
+pub fn render_figures(path: &Path, setting: &Setting) -> Result<usize, String> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let spec: FigureSpec = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let recording = dir.join(&spec.recording);
+    let frames = read_capture(&recording).map_err(|e| format!("{}: {}", recording.display(), e))?;
+    let base = spec.viewer_setting.unwrap_or(setting.viewer_setting);
+    let directivity_table =
+        load_directivity(dir, spec.directivity_file.as_deref(), setting, &base)?;
+    let imported_colors = match setting.color_map_file.as_str() {
+        "" => None,
+        file => Some(color_map::read_csv(file)?),
+    };
+    let output = dir.join(&spec.output);
+    std::fs::create_dir_all(&output).map_err(|e| e.to_string())?;
+    let font = Font::try_from_bytes(FONT).ok_or("invalid font")?;
+    for figure in &spec.figures {
+        let state = ReplayState::replay(&frames, figure.time, base, setting);
+        let scattering = state.scattering(setting, directivity_table.as_ref());
+        let settings = figure.settings(state.viewer_setting);
+        let field = FieldModel {
+            sources: &state.drive.sources,
+            settings: &settings,
+            directivity_table: directivity_table.as_ref(),
+            scattering: scattering.as_ref(),
+            device_sizes: &state.drive.device_sizes,
+        };
+        let image = figure.render(&field, imported_colors.as_deref(), &font);
+        let path = output.join(format!("{}.png", figure.name));
+        write_png(&path, &image, figure, &spec.recording)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        tracing::info!("figure written: {}", path.display());
+    }
+    Ok(spec.figures.len())
+}
+
+/// Table of `file` relative to `dir`, or of `setting.json` if None and `base` uses the imported
+/// directivity
+pub fn load_directivity(
+    dir: &Path,
+    file: Option<&str>,
+    setting: &Setting,
+    base: &ViewerSettings,
+) -> Result<Option<DirectivityTable>, String> {
+    let file = match file {
+        Some(file) => dir.join(file),
+        None if base.directivity == Directivity::Imported => {
+            Path::new(&setting.directivity_file).to_owned()
+        }
+        None => return Ok(None),
+    };
+    if file.as_os_str().is_empty() {
+        return Ok(None);
+    }
+    DirectivityTable::from_csv(&file).map(Some)
+}
+
+fn write_png(
+    path: &Path,
+    image: &RgbImage,
+    figure: &Figure,
+    recording: &str,
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let time = figure
+        .time
+        .map_or_else(|| "end".to_owned(), |t| format!("{} s", t));
+    for (key, value) in [
+        ("Title", figure.title.clone()),
+        ("Source", recording.to_owned()),
+        ("Time", time),
+    ] {
+        encoder
+            .add_itxt_chunk(key.to_owned(), value)
+            .map_err(|e| e.to_string())?;
+    }
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(image.as_raw())
+        .map_err(|e| e.to_string())
+}
+
+impl Figure {
+    fn settings(&self, mut settings: ViewerSettings) -> ViewerSettings {
+        if let Some([x, y, z]) = self.center {
+            settings.slice_pos = [x, y, z, 1.0];
+        }
+        if let Some(angle) = self.angle {
+            settings.slice_angle = vecmath_util::vec3_map(angle, f32::to_radians);
+        }
+        if let Some([width, height]) = self.size {
+            settings.slice_width = width.max(1);
+            settings.slice_height = height.max(1);
+        }
+        if let Some(quantity) = self.quantity {
+            settings.field_quantity = quantity;
+        }
+        if let Some(color_scale) = self.color_scale {
+            settings.color_scale = color_scale;
+        }
+        if let Some(dynamic_range) = self.dynamic_range {
+            settings.dynamic_range = Some(dynamic_range);
+        }
+        // the map replaced is the one shown for the quantity
+        match self.color_map {
+            Some(color_map) if settings.is_signed() => settings.diverging_color_map = color_map,
+            Some(color_map) => settings.color_map = color_map,
+            None => (),
+        }
+        settings
+    }
+
+    fn render(
+        &self,
+        field: &FieldModel,
+        imported_colors: Option<&[RGBColor]>,
+        font: &Font,
+    ) -> RgbImage {
+        let settings = field.settings;
+        let [rx, ry, rz] = settings.slice_angle;
+        let mut model = vecmath_util::mat4_rot(quaternion::euler_angles(rx, ry, rz));
+        model[3] = settings.slice_pos;
+        let grid = SliceGrid::new(model, settings, self.resolution.unwrap_or(0.5));
+        let values = field.quantity(&grid.points, 0.0);
+
+        let color_map = settings.active_color_map().colors(imported_colors);
+        let color = |t: f32| {
+            let c = color_map[(t * (color_map.len() - 1) as f32) as usize];
+            Rgb(vecmath_util::vec3_map([c.r, c.g, c.b], |v| {
+                (v * 255.0) as u8
+            }))
+        };
+        let color_bar = self.color_bar.unwrap_or(true);
+        let pixel_size = self.pixel_size.unwrap_or(2).max(1);
+        let (plot_w, plot_h) = (grid.nx as u32 * pixel_size, grid.ny as u32 * pixel_size);
+        let left = 80;
+        let top = if self.title.is_empty() { 20 } else { 50 };
+        let right = if color_bar { 130 } else { 20 };
+        let bottom = 60;
+        let mut image =
+            RgbImage::from_pixel(left + plot_w + right, top + plot_h + bottom, BACKGROUND);
+
+        for (i, &v) in values.iter().enumerate() {
+            let (ix, iy) = ((i % grid.nx) as u32, (grid.ny - 1 - i / grid.nx) as u32);
+            let c = color(field::color_position(v, settings));
+            fill_rect(
+                &mut image,
+                left + ix * pixel_size,
+                top + iy * pixel_size,
+                pixel_size,
+                pixel_size,
+                c,
+            );
+        }
+        draw_frame(&mut image, left, top, plot_w, plot_h);
+
+        let (width, height) = (settings.slice_width as f32, settings.slice_height as f32);
+        let (x_label, x_offset, x_sign) = axis(model, 0);
+        let (y_label, y_offset, y_sign) = axis(model, 1);
+        for (v, step) in ticks(x_offset - width / 2.0, x_offset + width / 2.0) {
+            let u = (v - x_offset) * x_sign + width / 2.0;
+            let x = left + (u / width * plot_w as f32).round() as u32;
+            fill_rect(&mut image, x, top + plot_h, 1, TICK_LENGTH, FOREGROUND);
+            let pos = [x as f32, (top + plot_h + TICK_LENGTH + 2) as f32];
+            draw_text(
+                &mut image,
+                font,
+                &format_tick(v, step),
+                pos,
+                TEXT_SIZE,
+                [0.5, 0.0],
+            );
+        }
+        for (v, step) in ticks(y_offset - height / 2.0, y_offset + height / 2.0) {
+            let u = (v - y_offset) * y_sign + height / 2.0;
+            let y = top + plot_h - (u / height * plot_h as f32).round() as u32;
+            fill_rect(
+                &mut image,
+                left - TICK_LENGTH,
+                y,
+                TICK_LENGTH,
+                1,
+                FOREGROUND,
+            );
+            let pos = [(left - TICK_LENGTH - 3) as f32, y as f32];
+            draw_text(
+                &mut image,
+                font,
+                &format_tick(v, step),
+                pos,
+                TEXT_SIZE,
+                [1.0, 0.5],
+            );
+        }
+        let x_label = format!("{} [mm]", x_label);
+        let pos = [(left + plot_w / 2) as f32, (top + plot_h + 30) as f32];
+        draw_text(&mut image, font, &x_label, pos, TEXT_SIZE, [0.5, 0.0]);
+        let y_label = format!("{} [mm]", y_label);
+        let pos = [left as f32, (top - 4) as f32];
+        draw_text(&mut image, font, &y_label, pos, TEXT_SIZE, [0.5, 1.0]);
+
+        if !self.title.is_empty() {
+            let pos = [(image.width() / 2) as f32, 8.0];
+            draw_text(&mut image, font, &self.title, pos, TITLE_SIZE, [0.5, 0.0]);
+        }
+
+        let inv = vecmath::mat4_inv(model);
+        for annotation in &self.annotations {
+            let [x, y, z] = annotation.pos;
+            let [u, v, _, _] = vecmath::col_mat4_transform(inv, [x, y, z, 1.0]);
+            let (u, v) = ((u + width / 2.0) / width, (v + height / 2.0) / height);
+            if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+                tracing::warn!("annotation out of the slice: {}", annotation.label);
+                continue;
+            }
+            let x = left + (u * plot_w as f32) as u32;
+            let y = top + plot_h - (v * plot_h as f32) as u32;
+            fill_rect(&mut image, x.saturating_sub(6), y, 13, 1, MARKER);
+            fill_rect(&mut image, x, y.saturating_sub(6), 1, 13, MARKER);
+            let pos = [(x + 8) as f32, y as f32 - 8.0];
+            draw_text_colored(
+                &mut image,
+                font,
+                &annotation.label,
+                pos,
+                TEXT_SIZE,
+                [0.0, 1.0],
+                MARKER,
+            );
+        }
+
+        if color_bar {
+            let x = left + plot_w + 20;
+            for iy in 0..plot_h {
+                let t = 1.0 - iy as f32 / (plot_h - 1).max(1) as f32;
+                fill_rect(&mut image, x, top + iy, COLOR_BAR_WIDTH, 1, color(t));
+            }
+            draw_frame(&mut image, x, top, COLOR_BAR_WIDTH, plot_h);
+            let (label, ticks) = color_bar_labels(settings);
+            for (t, tick) in ticks {
+                let y = top + plot_h - 1 - (t * (plot_h - 1) as f32).round() as u32;
+                fill_rect(
+                    &mut image,
+                    x + COLOR_BAR_WIDTH,
+                    y,
+                    TICK_LENGTH,
+                    1,
+                    FOREGROUND,
+                );
+                let pos = [(x + COLOR_BAR_WIDTH + TICK_LENGTH + 3) as f32, y as f32];
+                draw_text(&mut image, font, &tick, pos, TEXT_SIZE, [0.0, 0.5]);
+            }
+            let pos = [(x + COLOR_BAR_WIDTH / 2) as f32, (top + plot_h + 30) as f32];
+            draw_text(&mut image, font, &label, pos, TEXT_SIZE, [0.5, 0.0]);
+        }
+        image
+    }
+}
+
+/// Label of the `i`-th in-plane axis of the slice `model`, with the coordinate at the center and
+/// the direction. The world coordinate is used if the axis is along that of the world.
+fn axis(model: Matrix4, i: usize) -> (&'static str, f32, f32) {
+    let dir = model[i];
+    let center = model[3];
+    match (0..3).find(|&j| dir[j].abs() > 0.999) {
+        Some(j) => (["x", "y", "z"][j], center[j], dir[j].signum()),
+        None => (["u", "v"][i], 0.0, 1.0),
+    }
+}
+
+/// Title of the color bar with the unit and the labels at the positions from 0 to 1 of the bar
+pub fn color_bar_labels(settings: &ViewerSettings) -> (String, Vec<(f32, String)>) {
+    let (unit, display) = display_unit(settings);
+    let values: Vec<_> = (0..=4)
+        .map(|i| i as f32 / 4.0)
+        .map(|t| (t, display(field::color_value(t, settings))))
+        .collect();
+    let step = values
+        .windows(2)
+        .map(|w| (w[1].1 - w[0].1).abs())
+        .filter(|d| d.is_finite())
+        .fold(f32::INFINITY, f32::min);
+    let ticks = values
+        .into_iter()
+        .filter(|(_, value)| value.is_finite())
+        .map(|(t, value)| (t, format_tick(value, step)))
+        .collect();
+    let title = match settings.dynamic_range {
+        Some(range) if settings.field_quantity.is_magnitude() => format!(
+            "{} [{}], {} dB",
+            settings.field_quantity.name(),
+            unit,
+            range
+        ),
+        _ => format!("{} [{}]", settings.field_quantity.name(), unit),
+    };
+    (title, ticks)
+}
+
+/// Value shown on the color bar for the value of the field and its unit
+fn display_unit(settings: &ViewerSettings) -> (&'static str, impl Fn(f32) -> f32) {
+    let unit = settings.pressure_unit;
+    let calibration = settings.calibration;
+    let pa2 = calibration.pa_per_unit() * calibration.pa_per_unit();
+    let (name, scale): (&'static str, Option<f32>) = match settings.field_quantity {
+        FieldQuantity::Amplitude => (unit.name(), None),
+        FieldQuantity::RealPart if unit == PressureUnit::Arbitrary => ("a.u.", Some(1.0)),
+        FieldQuantity::RealPart => ("Pa", Some(calibration.pa_per_unit())),
+        FieldQuantity::Phase => ("rad", Some(1.0)),
+        FieldQuantity::Intensity => ("a.u.", Some(1.0)),
+        FieldQuantity::RadiationPressure => ("Pa", Some(pa2)),
+        FieldQuantity::GorkovPotential => ("J", Some(pa2)),
+        FieldQuantity::GorkovForce => ("N", Some(pa2)),
+    };
+    (name, move |v: f32| match scale {
+        Some(scale) => v * scale,
+        None => unit.convert(v, &calibration),
+    })
+}
+
+/// Round values from `min` to `max` spaced by 1, 2 or 5 times a power of ten, with the spacing
+fn ticks(min: f32, max: f32) -> Vec<(f32, f32)> {
+    let raw = (max - min) / 5.0;
+    if raw <= 0.0 || !raw.is_finite() {
+        return vec![];
+    }
+    let mag = 10f32.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * mag)
+        .find(|&s| s >= raw)
+        .unwrap_or(10.0 * mag);
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|i| (i as f32 * step, step)).collect()
+}
+
+fn format_tick(v: f32, step: f32) -> String {
+    if v == 0.0 {
+        return "0".to_owned();
+    }
+    if !(1e-3..1e5).contains(&step) {
+        return format!("{:.2e}", v);
+    }
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{:.*}", decimals, v)
+}
+
+fn fill_rect(image: &mut RgbImage, x: u32, y: u32, w: u32, h: u32, color: Rgb<u8>) {
+    for py in y..(y + h).min(image.height()) {
+        for px in x..(x + w).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn draw_frame(image: &mut RgbImage, x: u32, y: u32, w: u32, h: u32) {
+    fill_rect(image, x, y, w, 1, FOREGROUND);
+    fill_rect(image, x, y + h, w + 1, 1, FOREGROUND);
+    fill_rect(image, x, y, 1, h, FOREGROUND);
+    fill_rect(image, x + w, y, 1, h, FOREGROUND);
+}
+
+fn draw_text(
+    image: &mut RgbImage,
+    font: &Font,
+    text: &str,
+    pos: [f32; 2],
+    size: f32,
+    align: [f32; 2],
+) {
+    draw_text_colored(image, font, text, pos, size, align, FOREGROUND)
+}
+
+/// Draws `text` at `pos`, where `align` is the position of `pos` in the box of the text from the
+/// upper left [0, 0] to the lower right [1, 1]
+fn draw_text_colored(
+    image: &mut RgbImage,
+    font: &Font,
+    text: &str,
+    pos: [f32; 2],
+    size: f32,
+    align: [f32; 2],
+    color: Rgb<u8>,
+) {
+    let scale = Scale::uniform(size);
+    let v_metrics = font.v_metrics(scale);
+    let width = font
+        .layout(text, scale, point(0.0, 0.0))
+        .last()
+        .map_or(0.0, |g| {
+            g.position().x + g.unpositioned().h_metrics().advance_width
+        });
+    let height = v_metrics.ascent - v_metrics.descent;
+    let origin = point(
+        pos[0] - width * align[0],
+        pos[1] - height * align[1] + v_metrics.ascent,
+    );
+    for glyph in font.layout(text, scale, origin) {
+        let bb = match glyph.pixel_bounding_box() {
+            Some(bb) => bb,
+            None => continue,
+        };
+        glyph.draw(|gx, gy, coverage| {
+            let (x, y) = (gx as i32 + bb.min.x, gy as i32 + bb.min.y);
+            if x < 0 || y < 0 || x >= image.width() as i32 || y >= image.height() as i32 {
+                return;
+            }
+            let pixel = image.get_pixel_mut(x as u32, y as u32);
+            for (p, &c) in pixel.0.iter_mut().zip(color.0.iter()) {
+                *p = (*p as f32 * (1.0 - coverage) + c as f32 * coverage) as u8;
+            }
+        });
+    }
+}
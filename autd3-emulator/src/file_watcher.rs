@@ -0,0 +1,61 @@
+/*
+ * File: file_watcher.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Polls the modification time of a file, for hot-reloading it
+pub struct FileWatcher {
+    path: PathBuf,
+    stamp: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_owned();
+        Self {
+            stamp: modified(&path),
+            path,
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if the file has been modified since the last change. A removed file is not a
+    /// change, so that an editor replacing the file is seen once it is written.
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        match modified(&self.path) {
+            Some(stamp) if Some(stamp) != self.stamp => {
+                self.stamp = Some(stamp);
+                true
+            }
+            _ => false,
+        }
+    }
+}
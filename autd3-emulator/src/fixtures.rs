@@ -0,0 +1,130 @@
+/*
+ * File: fixtures.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{mesh::Mesh, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Object imported into the workspace from a mesh file, e.g., a reflector, a mounting frame or a
+/// hand model
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FixtureSetting {
+    pub name: String,
+    /// STL or OBJ file
+    pub path: String,
+    /// Translation in mm applied after the scale
+    pub offset: Vector3,
+    /// Length in mm of a unit of the file, e.g., 1000 for a model in m
+    pub scale: f32,
+    /// Rigid body scattering the field, see [acoustic_field_viewer::scatterer::Scattering]
+    #[serde(default)]
+    pub scatterer: bool,
+}
+
+impl FixtureSetting {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            path: String::new(),
+            offset: [0.0, 0.0, 0.0],
+            scale: 1.0,
+            scatterer: false,
+        }
+    }
+}
+
+/// Fixture loaded from its [FixtureSetting], placed in the workspace
+pub struct Fixture {
+    pub setting: FixtureSetting,
+    pub mesh: Result<Mesh, String>,
+    pub bounds: Option<(Vector3, Vector3)>,
+}
+
+impl Fixture {
+    pub fn load(setting: &FixtureSetting) -> Self {
+        let mesh = if setting.path.is_empty() {
+            Err("no file".to_owned())
+        } else {
+            Mesh::load(&setting.path).map(|m| m.transformed(setting.offset, setting.scale))
+        };
+        let bounds = mesh.as_ref().ok().and_then(|m| m.bounds());
+        Self {
+            setting: setting.clone(),
+            mesh,
+            bounds,
+        }
+    }
+
+    pub fn contains(&self, p: Vector3) -> bool {
+        self.mesh.as_ref().is_ok_and(|m| m.contains(p))
+    }
+
+    /// Whether the segment from `a` to `b` crosses the surface
+    pub fn crosses(&self, a: Vector3, b: Vector3) -> bool {
+        let (mesh, (min, max)) = match (&self.mesh, self.bounds) {
+            (Ok(mesh), Some(bounds)) => (mesh, bounds),
+            _ => return false,
+        };
+        if (0..3).any(|i| a[i].max(b[i]) < min[i] || a[i].min(b[i]) > max[i]) {
+            return false;
+        }
+        let len = vecmath_util::dist(a, b);
+        if len <= 0.0 {
+            return false;
+        }
+        let dir = vecmath::vec3_scale(vecmath::vec3_sub(b, a), 1.0 / len);
+        mesh.ray_intersect(a, dir).is_some_and(|t| t <= len)
+    }
+}
+
+/// Foci and STM points colliding with the fixtures, as pairs of the index of the point and that
+/// of the fixture
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Collisions {
+    /// Commanded foci inside a fixture
+    pub foci: Vec<(usize, usize)>,
+    /// STM points inside a fixture or whose step to the next, looping at the end, crosses one
+    pub stm: Vec<(usize, usize)>,
+}
+
+impl Collisions {
+    pub fn is_empty(&self) -> bool {
+        self.foci.is_empty() && self.stm.is_empty()
+    }
+}
+
+pub fn check(fixtures: &[Fixture], foci: &[Vector3], stm: &[Vector3]) -> Collisions {
+    let foci = foci
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &p)| {
+            fixtures
+                .iter()
+                .enumerate()
+                .filter(move |(_, f)| f.contains(p))
+                .map(move |(j, _)| (i, j))
+        })
+        .collect();
+    let stm = stm
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &p)| {
+            let next = stm[(i + 1) % stm.len()];
+            fixtures
+                .iter()
+                .enumerate()
+                .filter(move |(_, f)| f.contains(p) || f.crosses(p, next))
+                .map(move |(j, _)| (i, j))
+        })
+        .collect();
+    Collisions { foci, stm }
+}
@@ -0,0 +1,250 @@
+/*
+ * File: focus_fit.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use acoustic_field_viewer::{sound_source::SoundSource, view::ViewerSettings, Vector3};
+
+/// Gains whose phases fit a single focus worse than this are not single-focus-like
+pub const MIN_COHERENCE: f32 = 0.9;
+const LATERAL_STEP: f32 = 6.0;
+const LATERAL_MARGIN: f32 = 50.0;
+const DEPTH_STEP: f32 = 15.0;
+const MAX_DEPTH: f32 = 400.0;
+const MIN_REFINE_STEP: f32 = 0.05;
+
+/// Focal point implied by the phases of a gain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimatedFocus {
+    pub pos: Vector3,
+    /// Magnitude of the mean of the phasors of the sources relative to the focus, weighted by
+    /// their amplitudes, which is 1 if the phases are exactly of the focus
+    pub coherence: f32,
+}
+
+impl EstimatedFocus {
+    /// Circular standard deviation of the phases from the fit in degree
+    pub fn phase_error(&self) -> f32 {
+        (-2.0 * self.coherence.max(1e-6).ln()).sqrt().to_degrees()
+    }
+}
+
+// Coherence of the phases with the focus at `pos`, i.e., the least-squares fit of
+// phase = k |r - pos| + const in the circular sense
+fn coherence(drives: &[(Vector3, f32, f32, f32)], total: f32, pos: Vector3) -> f32 {
+    let [re, im] = drives
+        .iter()
+        .fold([0.0, 0.0], |[re, im], &(p, amp, phase, k)| {
+            let t = phase - k * vecmath_util::dist(p, pos);
+            [re + amp * t.cos(), im + amp * t.sin()]
+        });
+    (re * re + im * im).sqrt() / total
+}
+
+/// Fits a focus to the phases of `sources`, searched on a grid in front of the array and refined
+/// around the best point, or returns None if the gain is not single-focus-like
+pub fn estimate(sources: &[SoundSource], settings: &ViewerSettings) -> Option<EstimatedFocus> {
+    let drives: Vec<_> = sources
+        .iter()
+        .filter(|s| s.amp > 0.0)
+        .map(|s| (s.pos, s.amp, s.phase, s.wavenumber(settings)))
+        .collect();
+    let total: f32 = drives.iter().map(|d| d.1).sum();
+    let first = sources.first()?;
+    if drives.len() < 3 || total <= 0.0 {
+        return None;
+    }
+    let n = drives.len() as f32;
+    let center = drives.iter().fold([0.0; 3], |acc, d| {
+        vecmath::vec3_add(acc, vecmath::vec3_scale(d.0, 1.0 / n))
+    });
+    let normal = vecmath::vec3_normalized(first.dir);
+    let a = if normal[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    let u = vecmath::vec3_normalized(vecmath::vec3_cross(a, normal));
+    let v = vecmath::vec3_cross(normal, u);
+    let extent = drives
+        .iter()
+        .map(|d| vecmath_util::dist(d.0, center))
+        .fold(0.0, f32::max)
+        + LATERAL_MARGIN;
+    let m = (extent / LATERAL_STEP).ceil() as i32;
+    let depths = (MAX_DEPTH / DEPTH_STEP) as i32;
+    let at = |x: f32, y: f32, z: f32| {
+        let p = vecmath::vec3_add(center, vecmath::vec3_scale(u, x));
+        let p = vecmath::vec3_add(p, vecmath::vec3_scale(v, y));
+        vecmath::vec3_add(p, vecmath::vec3_scale(normal, z))
+    };
+    let best = (1..=depths)
+        .flat_map(|iz| (-m..=m).map(move |ix| (ix, iz)))
+        .flat_map(|(ix, iz)| (-m..=m).map(move |iy| (ix, iy, iz)))
+        .map(|(ix, iy, iz)| {
+            at(
+                ix as f32 * LATERAL_STEP,
+                iy as f32 * LATERAL_STEP,
+                iz as f32 * DEPTH_STEP,
+            )
+        })
+        .map(|p| (p, coherence(&drives, total, p)))
+        .fold(([0.0; 3], -1.0), |b, c| if c.1 > b.1 { c } else { b });
+
+    // pattern search along the axes with the step halved on no improvement
+    let (mut pos, mut c) = best;
+    let mut step = LATERAL_STEP / 2.0;
+    while step >= MIN_REFINE_STEP {
+        let candidate = [u, v, normal]
+            .iter()
+            .flat_map(|&axis| [-1.0, 1.0].map(|s| vecmath::vec3_scale(axis, s * step)))
+            .map(|d| vecmath::vec3_add(pos, d))
+            .map(|p| (p, coherence(&drives, total, p)))
+            .fold((pos, c), |b, c| if c.1 > b.1 { c } else { b });
+        if candidate.1 > c {
+            pos = candidate.0;
+            c = candidate.1;
+        } else {
+            step /= 2.0;
+        }
+    }
+    // a steered plane wave also fits a focus far away
+    let r = vecmath::vec3_sub(pos, center);
+    let depth = vecmath::vec3_dot(r, normal);
+    let lateral = vecmath::vec3_len(vecmath::vec3_sub(r, vecmath::vec3_scale(normal, depth)));
+    if c < MIN_COHERENCE || depth <= 0.0 || depth > MAX_DEPTH || lateral > extent * 2f32.sqrt() {
+        return None;
+    }
+    Some(EstimatedFocus { pos, coherence: c })
+}
+
+/// Runs [estimate] on a background thread, one at a time, where the gains arriving meanwhile
+/// are coalesced into the latest
+pub struct FocusEstimator {
+    running: Option<Receiver<Option<EstimatedFocus>>>,
+    pending: Option<(Vec<SoundSource>, ViewerSettings)>,
+}
+
+impl FocusEstimator {
+    pub fn new() -> Self {
+        Self {
+            running: None,
+            pending: None,
+        }
+    }
+
+    /// Queues the estimation for the drive of `sources`
+    pub fn request(&mut self, sources: Vec<SoundSource>, settings: ViewerSettings) {
+        self.pending = Some((sources, settings));
+    }
+
+    /// Discards the pending and running estimations, e.g., on clear
+    pub fn cancel(&mut self) {
+        self.pending = None;
+        self.running = None;
+    }
+
+    /// Starts the pending estimation if idle, and returns the result of the finished one, which
+    /// is None inside if the gain is not single-focus-like
+    pub fn poll(&mut self) -> Option<Option<EstimatedFocus>> {
+        let result = match &self.running {
+            Some(rx) => match rx.try_recv() {
+                Ok(result) => Some(result),
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    tracing::error!("focus estimation panicked");
+                    None
+                }
+            },
+            None => None,
+        };
+        self.running = None;
+        if let Some((sources, settings)) = self.pending.take() {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                // the receiver is dropped if the emulator exits meanwhile
+                let _ = tx.send(estimate(&sources, &settings));
+            });
+            self.running = Some(rx);
+        }
+        result
+    }
+}
+
+impl Default for FocusEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use autd3_core::hardware_defined::TRANS_SPACING_MM;
+
+    // Array of 18x14 sources in the xy plane facing +z, driven with `phase` at each position
+    fn array<F: Fn(Vector3) -> f32>(phase: F) -> Vec<SoundSource> {
+        (0..14)
+            .flat_map(|iy| (0..18).map(move |ix| (ix, iy)))
+            .map(|(ix, iy)| {
+                let pos = [
+                    TRANS_SPACING_MM as f32 * ix as f32,
+                    TRANS_SPACING_MM as f32 * iy as f32,
+                    0.0,
+                ];
+                SoundSource::new(pos, [0., 0., 1.], 1.0, phase(pos))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fit_synthetic_focus() {
+        let settings = ViewerSettings::new();
+        let k = 2.0 * std::f32::consts::PI / settings.wave_length;
+        let focus = [70.0, 60.0, 150.0];
+        let sources =
+            array(|p| (k * vecmath_util::dist(p, focus)).rem_euclid(2.0 * std::f32::consts::PI));
+
+        let estimated = estimate(&sources, &settings).unwrap();
+        assert!(
+            vecmath_util::dist(estimated.pos, focus) < 1.0,
+            "{:?}",
+            estimated.pos
+        );
+        assert!(estimated.coherence > 0.999);
+        assert!(estimated.phase_error() < 3.0);
+    }
+
+    #[test]
+    fn reject_plane_wave() {
+        let settings = ViewerSettings::new();
+        let sources = array(|_| 0.0);
+        assert_eq!(estimate(&sources, &settings), None);
+    }
+
+    #[test]
+    fn reject_random_phases() {
+        let settings = ViewerSettings::new();
+        // phases scrambled by a hash of the position
+        let sources = array(|p| {
+            ((p[0] * 12.9898 + p[1] * 78.233).sin() * 43758.547).fract()
+                * 2.0
+                * std::f32::consts::PI
+        });
+        assert_eq!(estimate(&sources, &settings), None);
+    }
+}
@@ -0,0 +1,600 @@
+/*
+ * File: hdf5.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+//! Writer of the flat HDF5 files of the contiguous datasets in the root group, in the oldest
+//! format of the file (superblock version 0, a symbol table of the root group and the object
+//! headers of version 1), which any version of the HDF5 library and MATLAB read
+
+use std::io::{self, Write};
+
+use acoustic_field_viewer::field::Complex;
+
+const UNDEFINED: u64 = u64::MAX;
+/// Half the number of the entries of a symbol table node, so the root holds up to 16 datasets
+const GROUP_LEAF_K: usize = 8;
+const GROUP_INTERNAL_K: usize = 16;
+
+const SUPERBLOCK_SIZE: usize = 96;
+const HEAP_HEADER_SIZE: usize = 32;
+const BTREE_SIZE: usize = 24 + (2 * GROUP_INTERNAL_K + 1) * 8 + 2 * GROUP_INTERNAL_K * 8;
+const SYMBOL_ENTRY_SIZE: usize = 40;
+const SYMBOL_NODE_SIZE: usize = 8 + 2 * GROUP_LEAF_K * SYMBOL_ENTRY_SIZE;
+/// The end of the free list of the local heap of the HDF5 library
+const HEAP_FREE_NULL: u64 = 1;
+
+const MSG_DATASPACE: u16 = 0x0001;
+const MSG_DATATYPE: u16 = 0x0003;
+const MSG_LAYOUT: u16 = 0x0008;
+const MSG_ATTRIBUTE: u16 = 0x000C;
+const MSG_SYMBOL_TABLE: u16 = 0x0011;
+
+pub enum Values<'a> {
+    F64(&'a [f64]),
+    /// Compound of the float32 `real` and `imag`, which MATLAB reads as a complex array
+    Complex(&'a [Complex]),
+}
+
+impl Values<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Values::F64(v) => v.len(),
+            Values::Complex(v) => v.len(),
+        }
+    }
+
+    fn datatype(&self) -> Vec<u8> {
+        match self {
+            Values::F64(_) => float_type(8),
+            Values::Complex(_) => complex_type(),
+        }
+    }
+
+    fn element_size(&self) -> usize {
+        match self {
+            Values::F64(_) => 8,
+            Values::Complex(_) => 8,
+        }
+    }
+}
+
+pub struct Dataset<'a> {
+    pub name: &'a str,
+    /// In C order, i.e., the last axis the fastest
+    pub shape: Vec<usize>,
+    pub values: Values<'a>,
+    /// Attributes of fixed-length ASCII strings
+    pub attributes: Vec<(&'a str, String)>,
+}
+
+fn pad8(buf: &mut Vec<u8>) {
+    buf.resize(buf.len().next_multiple_of(8), 0);
+}
+
+fn u16_le(v: usize) -> [u8; 2] {
+    (v as u16).to_le_bytes()
+}
+
+fn u32_le(v: usize) -> [u8; 4] {
+    (v as u32).to_le_bytes()
+}
+
+fn u64_le(v: u64) -> [u8; 8] {
+    v.to_le_bytes()
+}
+
+/// IEEE little endian float of `size` bytes
+fn float_type(size: usize) -> Vec<u8> {
+    let (sign, exp_loc, exp_size, mantissa, bias) = match size {
+        4 => (31, 23, 8, 23, 127),
+        _ => (63, 52, 11, 52, 1023),
+    };
+    let mut buf = vec![0x11, 0x20, sign, 0];
+    buf.extend_from_slice(&u32_le(size));
+    buf.extend_from_slice(&u16_le(0));
+    buf.extend_from_slice(&u16_le(size * 8));
+    buf.extend_from_slice(&[exp_loc, exp_size, 0, mantissa]);
+    buf.extend_from_slice(&u32_le(bias));
+    buf
+}
+
+fn complex_type() -> Vec<u8> {
+    let mut buf = vec![0x16, 2, 0, 0];
+    buf.extend_from_slice(&u32_le(8));
+    for (i, name) in ["real", "imag"].iter().enumerate() {
+        // the names are null-terminated and padded to 8 bytes
+        let mut name = name.as_bytes().to_vec();
+        name.push(0);
+        pad8(&mut name);
+        buf.extend_from_slice(&name);
+        buf.extend_from_slice(&u32_le(i * 4));
+        // the dimensionality, the permutation and the sizes of the array members
+        buf.extend_from_slice(&[0; 28]);
+        buf.extend_from_slice(&float_type(4));
+    }
+    buf
+}
+
+/// Null-terminated ASCII string of `size` bytes
+fn string_type(size: usize) -> Vec<u8> {
+    let mut buf = vec![0x13, 0, 0, 0];
+    buf.extend_from_slice(&u32_le(size));
+    buf
+}
+
+/// Simple dataspace of `shape`, or the scalar if empty
+fn dataspace(shape: &[usize]) -> Vec<u8> {
+    let mut buf = vec![1, shape.len() as u8, 0, 0, 0, 0, 0, 0];
+    for &n in shape {
+        buf.extend_from_slice(&u64_le(n as u64));
+    }
+    buf
+}
+
+fn attribute(name: &str, value: &str) -> Vec<u8> {
+    let size = value.len().max(1);
+    let datatype = string_type(size);
+    let space = dataspace(&[]);
+    let mut buf = vec![1, 0];
+    buf.extend_from_slice(&u16_le(name.len() + 1));
+    buf.extend_from_slice(&u16_le(datatype.len()));
+    buf.extend_from_slice(&u16_le(space.len()));
+    buf.extend_from_slice(name.as_bytes());
+    buf.push(0);
+    pad8(&mut buf);
+    buf.extend_from_slice(&datatype);
+    pad8(&mut buf);
+    buf.extend_from_slice(&space);
+    pad8(&mut buf);
+    let start = buf.len();
+    buf.extend_from_slice(value.as_bytes());
+    buf.resize(start + size, 0);
+    buf
+}
+
+fn object_header(messages: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (kind, data) in messages {
+        let size = data.len().next_multiple_of(8);
+        body.extend_from_slice(&kind.to_le_bytes());
+        body.extend_from_slice(&u16_le(size));
+        body.extend_from_slice(&[0; 4]);
+        body.extend_from_slice(data);
+        pad8(&mut body);
+    }
+    let mut buf = vec![1, 0];
+    buf.extend_from_slice(&u16_le(messages.len()));
+    buf.extend_from_slice(&u32_le(1));
+    buf.extend_from_slice(&u32_le(body.len()));
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(&body);
+    buf
+}
+
+fn dataset_header(dataset: &Dataset, address: u64) -> Vec<u8> {
+    let size = dataset.values.len() * dataset.values.element_size();
+    let mut layout = vec![3, 1];
+    layout.extend_from_slice(&u64_le(address));
+    layout.extend_from_slice(&u64_le(size as u64));
+    let mut messages = vec![
+        (MSG_DATASPACE, dataspace(&dataset.shape)),
+        (MSG_DATATYPE, dataset.values.datatype()),
+        (MSG_LAYOUT, layout),
+    ];
+    messages.extend(
+        dataset
+            .attributes
+            .iter()
+            .map(|(name, value)| (MSG_ATTRIBUTE, attribute(name, value))),
+    );
+    object_header(&messages)
+}
+
+fn symbol_entry(buf: &mut Vec<u8>, name_offset: usize, header: u64, scratch: Option<[u64; 2]>) {
+    buf.extend_from_slice(&u64_le(name_offset as u64));
+    buf.extend_from_slice(&u64_le(header));
+    buf.extend_from_slice(&u32_le(scratch.is_some() as usize));
+    buf.extend_from_slice(&[0; 4]);
+    for v in scratch.unwrap_or([0, 0]) {
+        buf.extend_from_slice(&u64_le(v));
+    }
+}
+
+/// MATLAB 7.3 header padded to the user block of 512 bytes, so that the HDF5 file after it is a
+/// MAT-file
+pub fn mat_userblock() -> Vec<u8> {
+    let text = format!(
+        "MATLAB 7.3 MAT-file, Platform: {}, Created on: {} HDF5 schema 1.00 .",
+        std::env::consts::OS,
+        chrono::Local::now().format("%a %b %e %H:%M:%S %Y")
+    );
+    let mut buf = text.into_bytes();
+    buf.resize(116, b' ');
+    buf.resize(124, 0);
+    buf.extend_from_slice(&0x0200u16.to_le_bytes());
+    buf.extend_from_slice(b"IM");
+    buf.resize(512, 0);
+    buf
+}
+
+/// Writes `datasets` in the root group of `root_attributes` after `userblock`, whose length is
+/// zero or a power of two from 512
+pub fn write<W: Write>(
+    writer: &mut W,
+    userblock: &[u8],
+    root_attributes: &[(&str, String)],
+    datasets: &[Dataset],
+) -> io::Result<()> {
+    if datasets.len() > 2 * GROUP_LEAF_K {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("at most {} datasets", 2 * GROUP_LEAF_K),
+        ));
+    }
+    if let Some(d) = datasets
+        .iter()
+        .find(|d| d.shape.iter().product::<usize>() != d.values.len())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: {:?} for {} values", d.name, d.shape, d.values.len()),
+        ));
+    }
+
+    // the names in the local heap, after the empty name of the root
+    let mut heap = vec![0; 8];
+    let name_offsets: Vec<usize> = datasets
+        .iter()
+        .map(|d| {
+            let offset = heap.len();
+            heap.extend_from_slice(d.name.as_bytes());
+            heap.push(0);
+            pad8(&mut heap);
+            offset
+        })
+        .collect();
+    let free_block = heap.len();
+    heap.extend_from_slice(&u64_le(HEAP_FREE_NULL));
+    heap.extend_from_slice(&u64_le(16));
+
+    let mut root_messages = vec![(MSG_SYMBOL_TABLE, vec![0; 16])];
+    root_messages.extend(
+        root_attributes
+            .iter()
+            .map(|(name, value)| (MSG_ATTRIBUTE, attribute(name, value))),
+    );
+    let root_size = object_header(&root_messages).len();
+    let root_address = SUPERBLOCK_SIZE;
+    let heap_address = root_address + root_size;
+    let btree_address = heap_address + HEAP_HEADER_SIZE + heap.len();
+    let node_address = btree_address + BTREE_SIZE;
+    let mut header_address = node_address + SYMBOL_NODE_SIZE;
+    let mut data_address = header_address
+        + datasets
+            .iter()
+            .map(|d| dataset_header(d, 0).len())
+            .sum::<usize>();
+    let headers: Vec<(usize, Vec<u8>)> = datasets
+        .iter()
+        .map(|d| {
+            let header = dataset_header(d, data_address as u64);
+            let address = header_address;
+            header_address += header.len();
+            data_address += d.values.len() * d.values.element_size();
+            (address, header)
+        })
+        .collect();
+    let end = data_address;
+
+    let mut meta = Vec::with_capacity(header_address);
+    meta.extend_from_slice(b"\x89HDF\r\n\x1a\n");
+    meta.extend_from_slice(&[0, 0, 0, 0, 0, 8, 8, 0]);
+    meta.extend_from_slice(&u16_le(GROUP_LEAF_K));
+    meta.extend_from_slice(&u16_le(GROUP_INTERNAL_K));
+    meta.extend_from_slice(&u32_le(0));
+    meta.extend_from_slice(&u64_le(userblock.len() as u64));
+    meta.extend_from_slice(&u64_le(UNDEFINED));
+    meta.extend_from_slice(&u64_le(end as u64));
+    meta.extend_from_slice(&u64_le(UNDEFINED));
+    symbol_entry(
+        &mut meta,
+        0,
+        root_address as u64,
+        Some([btree_address as u64, heap_address as u64]),
+    );
+
+    let mut symbol_table = u64_le(btree_address as u64).to_vec();
+    symbol_table.extend_from_slice(&u64_le(heap_address as u64));
+    root_messages[0].1 = symbol_table;
+    meta.extend_from_slice(&object_header(&root_messages));
+
+    meta.extend_from_slice(b"HEAP\0\0\0\0");
+    meta.extend_from_slice(&u64_le(heap.len() as u64));
+    meta.extend_from_slice(&u64_le(free_block as u64));
+    meta.extend_from_slice(&u64_le((heap_address + HEAP_HEADER_SIZE) as u64));
+    meta.extend_from_slice(&heap);
+
+    // the entries of the symbol table node are looked up by the binary search of the names
+    let mut order: Vec<usize> = (0..datasets.len()).collect();
+    order.sort_by_key(|&i| datasets[i].name.as_bytes());
+    let last_name = order.last().map_or(0, |&i| name_offsets[i]);
+    meta.extend_from_slice(b"TREE\0\0");
+    meta.extend_from_slice(&u16_le(1));
+    meta.extend_from_slice(&u64_le(UNDEFINED));
+    meta.extend_from_slice(&u64_le(UNDEFINED));
+    meta.extend_from_slice(&u64_le(0));
+    meta.extend_from_slice(&u64_le(node_address as u64));
+    meta.extend_from_slice(&u64_le(last_name as u64));
+    meta.resize(node_address, 0);
+
+    meta.extend_from_slice(b"SNOD\x01\0");
+    meta.extend_from_slice(&u16_le(datasets.len()));
+    for &i in &order {
+        symbol_entry(&mut meta, name_offsets[i], headers[i].0 as u64, None);
+    }
+    meta.resize(node_address + SYMBOL_NODE_SIZE, 0);
+    for (_, header) in &headers {
+        meta.extend_from_slice(header);
+    }
+
+    writer.write_all(userblock)?;
+    writer.write_all(&meta)?;
+    for dataset in datasets {
+        match dataset.values {
+            Values::F64(values) => {
+                for v in values {
+                    writer.write_all(&v.to_le_bytes())?;
+                }
+            }
+            Values::Complex(values) => {
+                for [re, im] in values {
+                    writer.write_all(&re.to_le_bytes())?;
+                    writer.write_all(&im.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    struct Parsed {
+        name: String,
+        shape: Vec<usize>,
+        datatype: u8,
+        data: Vec<u8>,
+        attributes: Vec<(String, String)>,
+    }
+
+    fn u16_at(buf: &[u8], at: usize) -> usize {
+        u16::from_le_bytes(buf[at..at + 2].try_into().unwrap()) as usize
+    }
+
+    fn u32_at(buf: &[u8], at: usize) -> usize {
+        u32::from_le_bytes(buf[at..at + 4].try_into().unwrap()) as usize
+    }
+
+    fn u64_at(buf: &[u8], at: usize) -> u64 {
+        u64::from_le_bytes(buf[at..at + 8].try_into().unwrap())
+    }
+
+    fn c_str(buf: &[u8], at: usize) -> String {
+        let end = at + buf[at..].iter().position(|&b| b == 0).unwrap();
+        String::from_utf8(buf[at..end].to_vec()).unwrap()
+    }
+
+    // Messages of the version 1 object header at `at`
+    fn messages(file: &[u8], at: usize) -> Vec<(u16, &[u8])> {
+        assert_eq!(file[at], 1);
+        let n = u16_at(file, at + 2);
+        let size = u32_at(file, at + 8);
+        let mut messages = Vec::new();
+        let mut p = at + 16;
+        while p < at + 16 + size {
+            let kind = u16_at(file, p) as u16;
+            let len = u16_at(file, p + 2);
+            assert_eq!(len % 8, 0);
+            messages.push((kind, &file[p + 8..p + 8 + len]));
+            p += 8 + len;
+        }
+        assert_eq!(messages.len(), n);
+        messages
+    }
+
+    fn parse_attribute(msg: &[u8]) -> (String, String) {
+        assert_eq!(msg[0], 1);
+        let (name_size, type_size, space_size) = (u16_at(msg, 2), u16_at(msg, 4), u16_at(msg, 6));
+        let name = c_str(msg, 8);
+        assert_eq!(name.len() + 1, name_size);
+        let datatype = 8 + name_size.next_multiple_of(8);
+        assert_eq!(msg[datatype] & 0x0f, 3);
+        let size = u32_at(msg, datatype + 4);
+        let space = datatype + type_size.next_multiple_of(8);
+        // scalar
+        assert_eq!(msg[space + 1], 0);
+        let value = space + space_size.next_multiple_of(8);
+        let value = String::from_utf8(msg[value..value + size].to_vec()).unwrap();
+        (name, value.trim_end_matches('\0').to_owned())
+    }
+
+    // Follows the structure from the superblock as a reader does, into the root attributes and
+    // the datasets in the order of the symbol table node
+    fn parse(file: &[u8]) -> (Vec<(String, String)>, Vec<Parsed>) {
+        let base = [0, 512, 1024, 2048]
+            .iter()
+            .copied()
+            .find(|&b| file[b..].starts_with(b"\x89HDF\r\n\x1a\n"))
+            .unwrap();
+        let file_at = |address: u64| (base as u64 + address) as usize;
+        let sb = &file[base..];
+        assert_eq!(sb[8], 0);
+        assert_eq!((sb[13], sb[14]), (8, 8));
+        assert_eq!(u16_at(sb, 16), GROUP_LEAF_K);
+        assert_eq!(u64_at(sb, 24), base as u64);
+        assert_eq!(file_at(u64_at(sb, 40)), file.len());
+
+        let root = file_at(u64_at(sb, 64));
+        assert_eq!(u32_at(sb, 72), 1);
+        let (btree, heap) = (u64_at(sb, 80), u64_at(sb, 88));
+        let mut root_attributes = Vec::new();
+        for (kind, msg) in messages(file, root) {
+            match kind {
+                MSG_SYMBOL_TABLE => assert_eq!((u64_at(msg, 0), u64_at(msg, 8)), (btree, heap)),
+                MSG_ATTRIBUTE => root_attributes.push(parse_attribute(msg)),
+                _ => panic!("message {} in the root", kind),
+            }
+        }
+
+        let heap = file_at(heap);
+        assert_eq!(&file[heap..heap + 4], b"HEAP");
+        let names = file_at(u64_at(file, heap + 24));
+
+        let btree = file_at(btree);
+        assert_eq!(&file[btree..btree + 4], b"TREE");
+        assert_eq!((file[btree + 4], file[btree + 5]), (0, 0));
+        assert_eq!(u16_at(file, btree + 6), 1);
+        let node = file_at(u64_at(file, btree + 32));
+
+        assert_eq!(&file[node..node + 5], b"SNOD\x01");
+        let n = u16_at(file, node + 6);
+        let datasets = (0..n)
+            .map(|i| {
+                let entry = node + 8 + i * SYMBOL_ENTRY_SIZE;
+                let name = c_str(file, names + u64_at(file, entry) as usize);
+                let header = file_at(u64_at(file, entry + 8));
+                let mut parsed = Parsed {
+                    name,
+                    shape: Vec::new(),
+                    datatype: 0,
+                    data: Vec::new(),
+                    attributes: Vec::new(),
+                };
+                for (kind, msg) in messages(file, header) {
+                    match kind {
+                        MSG_DATASPACE => {
+                            parsed.shape = (0..msg[1] as usize)
+                                .map(|k| u64_at(msg, 8 + 8 * k) as usize)
+                                .collect()
+                        }
+                        MSG_DATATYPE => parsed.datatype = msg[0] & 0x0f,
+                        MSG_LAYOUT => {
+                            assert_eq!((msg[0], msg[1]), (3, 1));
+                            let data = file_at(u64_at(msg, 2));
+                            parsed.data = file[data..data + u64_at(msg, 10) as usize].to_vec();
+                        }
+                        MSG_ATTRIBUTE => parsed.attributes.push(parse_attribute(msg)),
+                        _ => panic!("message {} in {}", kind, parsed.name),
+                    }
+                }
+                parsed
+            })
+            .collect();
+        (root_attributes, datasets)
+    }
+
+    fn write_to_vec(userblock: &[u8], datasets: &[Dataset]) -> io::Result<Vec<u8>> {
+        let mut file = Vec::new();
+        let root_attributes = [("recording", "capture.bin".to_owned())];
+        write(&mut file, userblock, &root_attributes, datasets)?;
+        Ok(file)
+    }
+
+    #[test]
+    fn round_trip() {
+        let x = [1.0, -2.5, 3.25, 0.0, 1e-9, 6.0];
+        let pressure = [[1.0, -1.0], [0.5, 2.0], [0.0, 0.0], [-3.0, 4.0]];
+        let datasets = [
+            Dataset {
+                name: "x",
+                shape: vec![2, 3],
+                values: Values::F64(&x),
+                attributes: vec![("unit", "mm".to_owned())],
+            },
+            Dataset {
+                name: "pressure",
+                shape: vec![4],
+                values: Values::Complex(&pressure),
+                attributes: Vec::new(),
+            },
+        ];
+        for userblock in [Vec::new(), mat_userblock()] {
+            let file = write_to_vec(&userblock, &datasets).unwrap();
+            assert!(file.starts_with(&userblock));
+
+            let (root_attributes, parsed) = parse(&file);
+            assert_eq!(
+                root_attributes,
+                [("recording".to_owned(), "capture.bin".to_owned())]
+            );
+            // sorted by the names for the binary search
+            assert_eq!(parsed[0].name, "pressure");
+            assert_eq!(parsed[1].name, "x");
+
+            assert_eq!(parsed[0].shape, [4]);
+            assert_eq!(parsed[0].datatype, 6);
+            let values: Vec<f32> = parsed[0]
+                .data
+                .chunks(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            assert_eq!(values, pressure.concat());
+
+            assert_eq!(parsed[1].shape, [2, 3]);
+            assert_eq!(parsed[1].datatype, 1);
+            let values: Vec<f64> = parsed[1]
+                .data
+                .chunks(8)
+                .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            assert_eq!(values, x);
+            assert_eq!(parsed[1].attributes, [("unit".to_owned(), "mm".to_owned())]);
+        }
+    }
+
+    #[test]
+    fn mat_userblock_header() {
+        let userblock = mat_userblock();
+        assert_eq!(userblock.len(), 512);
+        assert!(userblock.starts_with(b"MATLAB 7.3 MAT-file"));
+        assert_eq!(&userblock[124..128], b"\x00\x02IM");
+    }
+
+    #[test]
+    fn invalid_datasets() {
+        let v = [0.0; 3];
+        let mismatch = Dataset {
+            name: "v",
+            shape: vec![2],
+            values: Values::F64(&v),
+            attributes: Vec::new(),
+        };
+        assert!(write_to_vec(&[], &[mismatch]).is_err());
+
+        let names: Vec<String> = (0..=2 * GROUP_LEAF_K).map(|i| format!("d{}", i)).collect();
+        let many: Vec<_> = names
+            .iter()
+            .map(|name| Dataset {
+                name,
+                shape: vec![3],
+                values: Values::F64(&v),
+                attributes: Vec::new(),
+            })
+            .collect();
+        assert!(write_to_vec(&[], &many).is_err());
+        assert!(write_to_vec(&[], &many[1..]).is_ok());
+    }
+}
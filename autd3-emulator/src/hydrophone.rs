@@ -0,0 +1,197 @@
+/*
+ * File: hydrophone.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    f32::consts::PI,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use acoustic_field_viewer::{
+    directivity::DirectivityTable,
+    field::{Complex, FieldModel},
+    metadata::ExperimentMetadata,
+    sound_source::SoundSource,
+    view::ViewerSettings,
+    Vector3,
+};
+use autd3_core::hardware_defined::{MOD_SAMPLING_FREQ_BASE, POINT_SEQ_BASE_FREQ};
+use autd3_emulator_server::Modulation;
+
+use crate::{stm_duty, wav};
+
+/// What is driven during a recording, taken from the emulator at its start
+pub struct TraceSource<'a> {
+    pub sources: &'a [SoundSource],
+    /// Number of the sources of each device
+    pub device_sizes: &'a [usize],
+    pub settings: &'a ViewerSettings,
+    pub directivity_table: Option<&'a DirectivityTable>,
+    pub modulation: Option<&'a Modulation>,
+    /// Sampling frequency division and points in the global coordinates of the STM played, if
+    /// the sequence mode is enabled
+    pub stm: Option<(u16, &'a [(Vector3, u8)])>,
+}
+
+/// Instantaneous pressure at a point over the simulation time, as a hydrophone records it
+#[derive(Clone)]
+pub struct HydrophoneTrace {
+    pub pos: Vector3,
+    /// Simulation time of the first sample in seconds
+    pub start: f64,
+    /// Samples per second
+    pub sample_rate: f64,
+    /// Pressure in Pa
+    pub samples: Vec<f32>,
+}
+
+// Complex pressure of each drive frequency at the point
+type Phasors = Vec<(f32, Complex)>;
+
+impl<'a> TraceSource<'a> {
+    fn phasors(&self, sources: &[SoundSource], pos: Vector3) -> Phasors {
+        let mut frequencies: Vec<f32> = sources
+            .iter()
+            .map(|s| s.frequency.unwrap_or(self.settings.frequency))
+            .collect();
+        frequencies.sort_by(f32::total_cmp);
+        frequencies.dedup();
+        frequencies
+            .into_iter()
+            .map(|freq| {
+                let of_freq =
+                    |s: &&SoundSource| s.frequency.unwrap_or(self.settings.frequency) == freq;
+                let group: Vec<_> = sources.iter().filter(of_freq).copied().collect();
+                // the sources of each device in the group
+                let mut start = 0;
+                let device_sizes: Vec<_> = self
+                    .device_sizes
+                    .iter()
+                    .map(|&size| {
+                        let end = (start + size).min(sources.len());
+                        let n = sources[start.min(end)..end].iter().filter(of_freq).count();
+                        start = end;
+                        n
+                    })
+                    .collect();
+                let model = FieldModel {
+                    sources: &group,
+                    settings: self.settings,
+                    directivity_table: self.directivity_table,
+                    scattering: None,
+                    device_sizes: &device_sizes,
+                };
+                (freq, model.pressure_at(pos))
+            })
+            .collect()
+    }
+
+    fn stm_phasors(&self, points: &[(Vector3, u8)], idx: usize, pos: Vector3) -> Phasors {
+        let (focus, duty) = points[idx];
+        let sources = stm_duty::focused_drive(self.sources, self.settings, focus, duty);
+        self.phasors(&sources, pos)
+    }
+
+    /// Records the pressure at `pos` from `start` for `duration` seconds at `sample_rate`
+    pub fn record(
+        &self,
+        pos: Vector3,
+        start: f64,
+        duration: f64,
+        sample_rate: f64,
+    ) -> HydrophoneTrace {
+        let n = (duration * sample_rate).max(0.0) as usize;
+        let calibration = self.settings.calibration;
+        let modulation = self.modulation.filter(|m| !m.mod_data.is_empty()).map(|m| {
+            let freq = MOD_SAMPLING_FREQ_BASE / m.mod_div.max(1) as f64;
+            (m, freq)
+        });
+        let stm = self
+            .stm
+            .filter(|(_, points)| !points.is_empty())
+            .map(|(div, points)| {
+                let freq = POINT_SEQ_BASE_FREQ as f64 / div.max(1) as f64;
+                (points, freq)
+            });
+        let still = self.phasors(self.sources, pos);
+        let mut stm_cache: Vec<Option<Phasors>> = vec![None; stm.map_or(0, |(p, _)| p.len())];
+        let samples = (0..n)
+            .map(|i| {
+                let t = start + i as f64 / sample_rate;
+                let m = modulation.map_or(1.0, |(m, freq)| {
+                    let idx = ((t * freq) as u64 % m.mod_data.len() as u64) as usize;
+                    (m.mod_data[idx] as f32 / 512.0 * PI).sin()
+                });
+                let phasors = match stm {
+                    Some((points, freq)) => {
+                        let idx = ((t * freq) as u64 % points.len() as u64) as usize;
+                        &*stm_cache[idx].get_or_insert_with(|| self.stm_phasors(points, idx, pos))
+                    }
+                    None => &still,
+                };
+                // the real part of p exp(j w t), as the slice shows
+                let p: f32 = phasors
+                    .iter()
+                    .map(|&(freq, [re, im])| {
+                        let wt = (2.0 * std::f64::consts::PI * freq as f64 * t)
+                            .rem_euclid(2.0 * std::f64::consts::PI)
+                            as f32;
+                        re * wt.cos() - im * wt.sin()
+                    })
+                    .sum();
+                calibration.to_pa(m * p)
+            })
+            .collect();
+        HydrophoneTrace {
+            pos,
+            start,
+            sample_rate,
+            samples,
+        }
+    }
+}
+
+impl HydrophoneTrace {
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.sample_rate
+    }
+
+    /// Writes the time in seconds from the start and the pressure in Pa
+    pub fn write_csv(&self, path: &Path, metadata: &ExperimentMetadata) -> Result<(), String> {
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let res: std::io::Result<()> = (|| {
+            for (key, value) in metadata.entries() {
+                writeln!(writer, "# {}: {}", key, value.replace('\n', " "))?;
+            }
+            writeln!(
+                writer,
+                "# probe: ({}, {}, {}) mm",
+                self.pos[0], self.pos[1], self.pos[2]
+            )?;
+            writeln!(writer, "# start: {} s", self.start)?;
+            writeln!(writer, "# sample rate: {} Hz", self.sample_rate)?;
+            writeln!(writer, "t [s],p [Pa]")?;
+            for (i, p) in self.samples.iter().enumerate() {
+                writeln!(writer, "{},{}", i as f64 / self.sample_rate, p)?;
+            }
+            writer.flush()
+        })();
+        res.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Writes a mono WAV of 32-bit float samples in Pa, which audio tools read without scaling
+    pub fn write_wav(&self, path: &Path) -> Result<(), String> {
+        wav::write_float(path, self.sample_rate, 1, &self.samples)
+    }
+}
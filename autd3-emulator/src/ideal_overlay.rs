@@ -0,0 +1,98 @@
+/*
+ * File: ideal_overlay.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{
+    field::{self, FieldModel},
+    view::{CompareView, FieldQuantity},
+    Matrix4, Vector3,
+};
+use autd3_core::hardware_defined::TRANS_SPACING_MM;
+use serde::{Deserialize, Serialize};
+
+use crate::stm_duty;
+
+/// Contours over the slice of the amplitude of the ideal single focus, i.e., all the sources at
+/// the full amplitude focusing at `focus`, against which the simulated field is compared
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct IdealOverlay {
+    pub enabled: bool,
+    /// Focus in mm
+    pub focus: Vector3,
+    /// Spacing in mm of the grid on which the contours are traced
+    pub spacing: f32,
+    /// The contours are at multiples of this in dB below the ideal focal amplitude
+    pub step_db: f32,
+    pub levels: usize,
+}
+
+/// Contours of an [IdealOverlay] on the slice
+#[derive(Debug, Clone, Default)]
+pub struct IdealContours {
+    /// Amplitude of the ideal focus at the focus
+    pub focal_amp: f32,
+    /// Level in dB below the focal amplitude and the segments of each contour
+    pub lines: Vec<(f32, Vec<(Vector3, Vector3)>)>,
+}
+
+impl IdealOverlay {
+    /// 150 mm above the center of the default array, the same as the slice
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            focus: [
+                TRANS_SPACING_MM as f32 * 8.5,
+                TRANS_SPACING_MM as f32 * 6.5,
+                150.0,
+            ],
+            spacing: 1.0,
+            step_db: 3.0,
+            levels: 4,
+        }
+    }
+
+    /// Traces the contours on the slice transformed by `slice_model` in the field model of `field`
+    /// driven as the ideal focus, without the scatterers
+    pub fn compute(&self, field: &FieldModel, slice_model: Matrix4) -> IdealContours {
+        let sources = stm_duty::focused_drive(field.sources, field.settings, self.focus, 255);
+        let mut settings = *field.settings;
+        settings.field_quantity = FieldQuantity::Amplitude;
+        settings.compare = CompareView::Current;
+        let ideal = FieldModel {
+            sources: &sources,
+            settings: &settings,
+            directivity_table: field.directivity_table,
+            scattering: None,
+            device_sizes: field.device_sizes,
+        };
+        let [re, im] = ideal.pressure_at(self.focus);
+        let focal_amp = (re * re + im * im).sqrt();
+        if focal_amp <= 0.0 {
+            return IdealContours::default();
+        }
+        let (grid, amps) = field::compute_slice(&ideal, slice_model, self.spacing.max(0.1), 0.0);
+        let lines = (1..=self.levels)
+            .map(|i| {
+                let db = -self.step_db * i as f32;
+                let level = focal_amp * 10f32.powf(db / 20.0);
+                (db, grid.contour(&amps, level))
+            })
+            .collect();
+        IdealContours { focal_amp, lines }
+    }
+}
+
+impl Default for IdealOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,124 @@
+/*
+ * File: io_worker.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+type Job = (String, Box<dyn FnOnce() -> Result<(), String> + Send>);
+
+/// Outcome of a job executed by [IoWorker]
+pub struct JobResult {
+    pub name: String,
+    pub result: Result<(), String>,
+    pub elapsed: Duration,
+}
+
+/// Executes file writes in order on a background thread, so that they never block rendering
+pub struct IoWorker {
+    tx: Option<Sender<Job>>,
+    done: Receiver<JobResult>,
+    th_handle: Option<JoinHandle<()>>,
+    pending: Vec<String>,
+}
+
+impl IoWorker {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let (done_tx, done) = mpsc::channel();
+        let th_handle = thread::spawn(move || {
+            for (name, job) in rx {
+                let start = Instant::now();
+                let result = job();
+                let elapsed = start.elapsed();
+                if done_tx
+                    .send(JobResult {
+                        name,
+                        result,
+                        elapsed,
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        Self {
+            tx: Some(tx),
+            done,
+            th_handle: Some(th_handle),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Queues `job`, whose error is reported by [IoWorker::poll] with `name`
+    pub fn submit<F>(&mut self, name: &str, job: F)
+    where
+        F: FnOnce() -> Result<(), String> + Send + 'static,
+    {
+        let tx = match &self.tx {
+            Some(tx) => tx,
+            None => return,
+        };
+        if tx.send((name.to_owned(), Box::new(job))).is_err() {
+            tracing::error!("{}: I/O worker is not running", name);
+            return;
+        }
+        self.pending.push(name.to_owned());
+    }
+
+    /// Names of the jobs queued or running
+    pub fn pending(&self) -> &[String] {
+        &self.pending
+    }
+
+    /// Jobs finished since the last call, which are also logged
+    pub fn poll(&mut self) -> Vec<JobResult> {
+        let results: Vec<_> = self.done.try_iter().collect();
+        for r in &results {
+            if let Some(i) = self.pending.iter().position(|name| *name == r.name) {
+                self.pending.remove(i);
+            }
+            match &r.result {
+                Ok(()) => tracing::debug!("{} finished in {:?}", r.name, r.elapsed),
+                Err(e) => tracing::error!("{} failed: {}", r.name, e),
+            }
+        }
+        results
+    }
+
+    /// Waits for all queued jobs
+    pub fn finish(&mut self) {
+        self.tx = None;
+        if let Some(handle) = self.th_handle.take() {
+            if handle.join().is_err() {
+                tracing::error!("I/O worker panicked");
+            }
+        }
+        self.poll();
+    }
+}
+
+impl Default for IoWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for IoWorker {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
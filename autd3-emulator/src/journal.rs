@@ -0,0 +1,309 @@
+/*
+ * File: journal.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use autd3_core::hardware_defined::{RxGlobalControlFlags, NUM_TRANS_IN_UNIT};
+use autd3_emulator_server::{frame_blocks, AutdData};
+use serde_json::json;
+
+/// Number of rotated files kept as `<path>.1` ... `<path>.N`
+const MAX_ROTATED: usize = 3;
+
+/// Writes every decoded command to a JSON lines file, rotated when it exceeds `max_bytes`
+pub struct Journal {
+    path: PathBuf,
+    max_bytes: u64,
+    written: u64,
+    writer: BufWriter<File>,
+    device_sizes: Vec<usize>,
+    ctrl_flag: Option<RxGlobalControlFlags>,
+}
+
+impl Journal {
+    pub fn open<P: Into<PathBuf>>(path: P, max_bytes: u64) -> std::io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            written,
+            writer: BufWriter::new(file),
+            device_sizes: Vec::new(),
+            ctrl_flag: None,
+        })
+    }
+
+    pub fn record(&mut self, data: &AutdData) {
+        if let AutdData::Geometries(geometries) = data {
+            self.device_sizes = geometries
+                .iter()
+                .map(|g| g.layout.positions().len())
+                .collect();
+        }
+        if let AutdData::CtrlFlag(flag) = data {
+            // every frame has the control flag, so only changes are recorded
+            if self.ctrl_flag == Some(*flag) {
+                return;
+            }
+            self.ctrl_flag = Some(*flag);
+        }
+
+        let time = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, false);
+        let (kind, entries) = self.summarize(data);
+        for (device, summary) in entries {
+            let line = json!({
+                "time": time,
+                "kind": kind,
+                "device": device,
+                "summary": summary,
+            })
+            .to_string();
+            if let Err(e) = self.write_line(&line) {
+                tracing::error!("failed to write journal {}: {}", self.path.display(), e);
+                return;
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        self.writer.flush().ok();
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.written > 0 && self.written + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.writer, "{}", line)?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        let rotated = |i: usize| {
+            let mut p = self.path.clone().into_os_string();
+            p.push(format!(".{}", i));
+            PathBuf::from(p)
+        };
+        for i in (1..MAX_ROTATED).rev() {
+            if rotated(i).exists() {
+                std::fs::rename(rotated(i), rotated(i + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, rotated(1))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.written = 0;
+        Ok(())
+    }
+
+    // Returns the kind of the command and the summary for each device, where `None` means
+    // that the command is not specific to a device
+    fn summarize(&self, data: &AutdData) -> (&'static str, Vec<(Option<usize>, String)>) {
+        match data {
+            AutdData::Modulation(m) => (
+                "modulation",
+                vec![(None, format!("size={} div={}", m.mod_data.len(), m.mod_div))],
+            ),
+            AutdData::Gain(gain) => (
+                "gain",
+                self.per_device(gain.amps.len(), |range| {
+                    let amps = &gain.amps[range.clone()];
+                    let phases = &gain.phases[range];
+                    let max = amps.iter().max().copied().unwrap_or(0);
+                    let mean =
+                        amps.iter().map(|&a| a as f32).sum::<f32>() / amps.len().max(1) as f32;
+                    format!(
+                        "num={} amp_max={} amp_mean={:.1} phase[0]={}",
+                        amps.len(),
+                        max,
+                        mean,
+                        phases.first().copied().unwrap_or(0)
+                    )
+                }),
+            ),
+            AutdData::Geometries(geometries) => (
+                "geometry",
+                geometries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, g)| {
+                        (
+                            Some(i),
+                            format!(
+                                "origin={:?} right={:?} up={:?} layout={:?}",
+                                g.origin, g.right, g.up, g.layout
+                            ),
+                        )
+                    })
+                    .collect(),
+            ),
+            AutdData::CtrlFlag(flag) => ("ctrl_flag", vec![(None, format!("{:?}", flag))]),
+            AutdData::Clear => ("clear", vec![(None, String::new())]),
+            AutdData::Pause => ("pause", vec![(None, String::new())]),
+            AutdData::Resume => ("resume", vec![(None, String::new())]),
+            AutdData::RequestFpgaVerMsb => ("read_fpga_ver_msb", vec![(None, String::new())]),
+            AutdData::RequestFpgaVerLsb => ("read_fpga_ver_lsb", vec![(None, String::new())]),
+            AutdData::RequestCpuVerMsb => ("read_cpu_ver_msb", vec![(None, String::new())]),
+            AutdData::RequestCpuVerLsb => ("read_cpu_ver_lsb", vec![(None, String::new())]),
+            AutdData::Sequence(seq) => (
+                "sequence",
+                vec![(
+                    None,
+                    format!("size={} div={}", seq.seq_data.len(), seq.seq_div),
+                )],
+            ),
+            AutdData::DelayOffset(d) => (
+                "delay_offset",
+                self.per_device(d.delay_offset.len(), |range| {
+                    let d = &d.delay_offset[range];
+                    format!(
+                        "num={} delay_max={} offset_max={}",
+                        d.len(),
+                        d.iter().map(|v| v.0).max().unwrap_or(0),
+                        d.iter().map(|v| v.1).max().unwrap_or(0)
+                    )
+                }),
+            ),
+            AutdData::ViewerSetting(json) => (
+                "viewer_setting",
+                vec![(None, format!("{} bytes", json.len()))],
+            ),
+            AutdData::Cycles(cycles) => (
+                "cycles",
+                self.per_device(cycles.len(), |range| {
+                    let c = &cycles[range];
+                    format!(
+                        "num={} min={} max={}",
+                        c.len(),
+                        c.iter().min().copied().unwrap_or(0),
+                        c.iter().max().copied().unwrap_or(0)
+                    )
+                }),
+            ),
+            AutdData::CommandedFoci(foci) => (
+                "commanded_foci",
+                vec![(
+                    None,
+                    foci.iter()
+                        .map(|f| format!("{}={:?}", f.label, f.pos))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )],
+            ),
+            AutdData::Export(name) => ("export", vec![(None, name.clone())]),
+            AutdData::Screenshot(name) => ("screenshot", vec![(None, name.clone())]),
+            AutdData::Tags(tags) => ("tags", vec![(None, tags.join(", "))]),
+            AutdData::Handoff(time) => ("handoff", vec![(None, format!("time={}", time))]),
+        }
+    }
+
+    // Splits per-transducer data of `len` entries by the frame blocks of the devices of the last
+    // geometry, without the padding
+    fn per_device<F>(&self, len: usize, f: F) -> Vec<(Option<usize>, String)>
+    where
+        F: Fn(std::ops::Range<usize>) -> String,
+    {
+        if self.device_sizes.is_empty() {
+            return vec![(None, f(0..len))];
+        }
+        let blocks = frame_blocks(&self.device_sizes);
+        let mut res = Vec::new();
+        for (i, block) in blocks.iter().enumerate() {
+            if block.start >= len {
+                break;
+            }
+            res.push((Some(i), f(block.start..block.end.min(len))));
+        }
+        let end = blocks
+            .last()
+            .map_or(0, |b| b.end.max(b.start + NUM_TRANS_IN_UNIT));
+        if end < len {
+            res.push((None, f(end..len)));
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_by_size_and_count() {
+        let dir =
+            std::env::temp_dir().join(format!("autd-emulator-journal-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("journal.jsonl");
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+
+        // 10 bytes per line, so that each file has 2 lines
+        let mut journal = Journal::open(&path, 25).unwrap();
+        for i in 0..12 {
+            journal.write_line(&format!("{:09}", i)).unwrap();
+        }
+        journal.flush();
+        drop(journal);
+        // a reopened journal continues with the size of the file
+        let mut journal = Journal::open(&path, 25).unwrap();
+        journal.write_line(&format!("{:09}", 12)).unwrap();
+        journal.flush();
+        drop(journal);
+
+        let files = [
+            read("journal.jsonl"),
+            read("journal.jsonl.1"),
+            read("journal.jsonl.2"),
+            read("journal.jsonl.3"),
+            read("journal.jsonl.4"),
+        ];
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files[0].as_deref(), Some("000000012\n"));
+        assert_eq!(files[1].as_deref(), Some("000000010\n000000011\n"));
+        assert_eq!(files[2].as_deref(), Some("000000008\n000000009\n"));
+        assert_eq!(files[3].as_deref(), Some("000000006\n000000007\n"));
+        assert_eq!(files[4], None);
+    }
+
+    #[test]
+    fn write_line_longer_than_limit() {
+        let dir =
+            std::env::temp_dir().join(format!("autd-emulator-journal-long-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("journal.jsonl");
+
+        let mut journal = Journal::open(&path, 4).unwrap();
+        journal.write_line("0123456789").unwrap();
+        journal.write_line("abcdefghij").unwrap();
+        journal.flush();
+        drop(journal);
+
+        let current = std::fs::read_to_string(&path).unwrap();
+        let rotated = std::fs::read_to_string(dir.join("journal.jsonl.1")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // a line is not split, and a fresh file takes it even if it exceeds the limit
+        assert_eq!(current, "abcdefghij\n");
+        assert_eq!(rotated, "0123456789\n");
+    }
+}
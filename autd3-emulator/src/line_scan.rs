@@ -0,0 +1,180 @@
+/*
+ * File: line_scan.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use acoustic_field_viewer::{
+    field::FieldModel, medium::Medium, metadata::ExperimentMetadata, Vector3,
+};
+use serde::{Deserialize, Serialize};
+
+/// Line segment along which the amplitude is profiled, e.g., an axial scan through the focus
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LineScan {
+    /// Start point in mm
+    pub start: Vector3,
+    /// End point in mm
+    pub end: Vector3,
+    /// Number of the points including both ends
+    pub samples: usize,
+}
+
+/// Amplitude along a [LineScan]
+#[derive(Clone)]
+pub struct LineProfile {
+    pub scan: LineScan,
+    /// Amplitude in the unit of the field at each point
+    pub amps: Vec<f32>,
+}
+
+impl LineScan {
+    /// Scan of `length` mm along `axis` centered at `center`
+    pub fn centered(center: Vector3, axis: Vector3, length: f32, samples: usize) -> Self {
+        let half = vecmath::vec3_scale(vecmath::vec3_normalized(axis), length / 2.0);
+        Self {
+            start: vecmath::vec3_sub(center, half),
+            end: vecmath::vec3_add(center, half),
+            samples,
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        vecmath_util::dist(self.start, self.end)
+    }
+
+    /// Position of the `i`-th point
+    pub fn point(&self, i: usize) -> Vector3 {
+        let t = if self.samples < 2 {
+            0.0
+        } else {
+            i as f32 / (self.samples - 1) as f32
+        };
+        let d = vecmath::vec3_sub(self.end, self.start);
+        vecmath::vec3_add(self.start, vecmath::vec3_scale(d, t))
+    }
+
+    pub fn profile(&self, model: &FieldModel) -> LineProfile {
+        let amps = (0..self.samples)
+            .map(|i| {
+                let [re, im] = model.pressure_at(self.point(i));
+                (re * re + im * im).sqrt()
+            })
+            .collect();
+        LineProfile { scan: *self, amps }
+    }
+}
+
+impl LineProfile {
+    /// Distance from the start in mm and the amplitude of the maximum
+    pub fn peak(&self) -> Option<(f32, f32)> {
+        let (i, &amp) = self
+            .amps
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        Some((self.distance(i as f32), amp))
+    }
+
+    /// Full width in mm at half the maximum of the amplitude around the maximum, linearly
+    /// interpolated between the points, or None if the peak is not within the line
+    pub fn fwhm(&self) -> Option<f32> {
+        let (i, &amp) = self
+            .amps
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        let half = amp / 2.0;
+        let crossing = |j: usize, k: usize| {
+            let (a, b) = (self.amps[j], self.amps[k]);
+            j as f32 + (a - half) / (a - b) * (k as f32 - j as f32)
+        };
+        let left = (0..i).rev().find(|&j| self.amps[j] < half)?;
+        let right = (i + 1..self.amps.len()).find(|&j| self.amps[j] < half)?;
+        let (left, right) = (crossing(left + 1, left), crossing(right - 1, right));
+        Some(self.distance(right) - self.distance(left))
+    }
+
+    /// Shock parameter at each point accumulated from the start, as if the wave at the local
+    /// amplitude propagated as a plane wave along the line, for the amplitude in Pa of
+    /// `pa_per_unit` times the field. The second harmonic is about half of it relative to the
+    /// fundamental while it is below 1, where a shock forms.
+    pub fn shock_parameter(&self, medium: &Medium, frequency: f32, pa_per_unit: f32) -> Vec<f32> {
+        let ds = self.distance(1.0);
+        let rate = |amp: f32| medium.shock_parameter_rate(amp * pa_per_unit, frequency);
+        let mut sigma = 0.0;
+        let mut prev = None;
+        self.amps
+            .iter()
+            .map(|&amp| {
+                // trapezoidal
+                if let Some(prev) = prev.replace(amp) {
+                    sigma += (rate(prev) + rate(amp)) / 2.0 * ds;
+                }
+                sigma
+            })
+            .collect()
+    }
+
+    /// Distance from the start in mm where the shock parameter reaches 1, linearly interpolated
+    pub fn shock_distance(&self, sigma: &[f32]) -> Option<f32> {
+        let i = sigma.iter().position(|&s| s >= 1.0)?;
+        if i == 0 {
+            return Some(0.0);
+        }
+        let (a, b) = (sigma[i - 1], sigma[i]);
+        Some(self.distance(i as f32 - 1.0 + (1.0 - a) / (b - a)))
+    }
+
+    // Distance from the start of the fractional index `i`
+    fn distance(&self, i: f32) -> f32 {
+        if self.amps.len() < 2 {
+            return 0.0;
+        }
+        self.scan.length() * i / (self.amps.len() - 1) as f32
+    }
+
+    /// Writes the distance from the start, the coordinates and the amplitude of each point
+    pub fn write_csv(
+        &self,
+        path: &Path,
+        metadata: &ExperimentMetadata,
+        pa_per_unit: f32,
+    ) -> Result<(), String> {
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let res: std::io::Result<()> = (|| {
+            for (key, value) in metadata.entries() {
+                writeln!(writer, "# {}: {}", key, value.replace('\n', " "))?;
+            }
+            writeln!(writer, "# calibration: {} Pa per unit", pa_per_unit)?;
+            writeln!(writer, "s [mm],x [mm],y [mm],z [mm],amplitude")?;
+            for (i, amp) in self.amps.iter().enumerate() {
+                let [x, y, z] = self.scan.point(i);
+                writeln!(
+                    writer,
+                    "{},{},{},{},{}",
+                    self.distance(i as f32),
+                    x,
+                    y,
+                    z,
+                    amp
+                )?;
+            }
+            writer.flush()
+        })();
+        res.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
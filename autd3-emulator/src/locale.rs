@@ -0,0 +1,135 @@
+/*
+ * File: locale.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::Vector3;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Number and date format of the values shown in the UI.
+///
+/// Machine-readable exports (log files, file names, embedded metadata) always use the canonical
+/// format regardless of this.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Ja,
+}
+
+pub const LOCALES: [Locale; 4] = [Locale::En, Locale::De, Locale::Fr, Locale::Ja];
+
+impl Locale {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Locale::En => "English (1,234.5)",
+            Locale::De => "Deutsch (1.234,5)",
+            Locale::Fr => "Français (1 234,5)",
+            Locale::Ja => "日本語 (1,234.5)",
+        }
+    }
+
+    // decimal separator and digit group separator
+    fn separators(&self) -> (char, char) {
+        match self {
+            Locale::En | Locale::Ja => ('.', ','),
+            Locale::De => (',', '.'),
+            Locale::Fr => (',', ' '),
+        }
+    }
+
+    pub fn number(&self, v: f64, precision: usize) -> String {
+        let (decimal, group) = self.separators();
+        let s = format!("{:.*}", precision, v.abs());
+        let (int, frac) = match s.find('.') {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (&s[..], None),
+        };
+
+        let mut res = String::new();
+        if v.is_sign_negative() && s.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            res.push('-');
+        }
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                res.push(group);
+            }
+            res.push(c);
+        }
+        if let Some(frac) = frac {
+            res.push(decimal);
+            res.push_str(frac);
+        }
+        res
+    }
+
+    /// Formats as `(x, y, z)`, or `(x; y; z)` if the decimal separator is a comma
+    pub fn vec3(&self, v: Vector3, precision: usize) -> String {
+        let sep = if self.separators().0 == ',' {
+            "; "
+        } else {
+            ", "
+        };
+        format!(
+            "({})",
+            v.iter()
+                .map(|&x| self.number(x as f64, precision))
+                .collect::<Vec<_>>()
+                .join(sep)
+        )
+    }
+
+    pub fn datetime(&self, time: &DateTime<Local>) -> String {
+        let fmt = match self {
+            Locale::En => "%Y-%m-%d %H:%M:%S%.3f",
+            Locale::De => "%d.%m.%Y %H:%M:%S,%3f",
+            Locale::Fr => "%d/%m/%Y %H:%M:%S,%3f",
+            Locale::Ja => "%Y/%m/%d %H:%M:%S%.3f",
+        };
+        time.format(fmt).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number() {
+        let cases = [
+            (Locale::En, 0.0, 1, "0.0"),
+            (Locale::En, 1234.5, 1, "1,234.5"),
+            (Locale::En, 1234567.891, 2, "1,234,567.89"),
+            (Locale::En, 123.0, 0, "123"),
+            (Locale::En, -1234.5, 1, "-1,234.5"),
+            (Locale::En, -0.01, 1, "0.0"),
+            (Locale::De, 1234.5, 1, "1.234,5"),
+            (Locale::De, 1234567.0, 0, "1.234.567"),
+            (Locale::De, -0.25, 2, "-0,25"),
+            (Locale::Fr, 1234.5, 1, "1 234,5"),
+            (Locale::Fr, 999.99, 1, "1 000,0"),
+            (Locale::Ja, 1234.5, 1, "1,234.5"),
+        ];
+        for &(locale, v, precision, expected) in &cases {
+            assert_eq!(
+                locale.number(v, precision),
+                expected,
+                "{:?} {} {}",
+                locale,
+                v,
+                precision
+            );
+        }
+    }
+}
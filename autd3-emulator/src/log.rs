@@ -0,0 +1,265 @@
+/*
+ * File: log.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    fs::OpenOptions,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Local};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    filter::LevelFilter, layer::Context, prelude::*, reload, Layer, Registry,
+};
+
+use crate::{locale::Locale, settings::Setting};
+
+pub const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+pub struct LogRecord {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+struct Inner {
+    enable: bool,
+    max: usize,
+    records: VecDeque<LogRecord>,
+}
+
+/// Ring buffer of the most recent tracing events, shown in the Log tab
+#[derive(Clone)]
+pub struct LogBuffer {
+    inner: Arc<Mutex<Inner>>,
+    /// Level of the events captured by the subscriber installed by [init]
+    level: Option<reload::Handle<LevelFilter, Registry>>,
+}
+
+impl LogBuffer {
+    pub fn new(enable: bool, max: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                enable,
+                max,
+                records: VecDeque::new(),
+            })),
+            level: None,
+        }
+    }
+
+    /// Captures the events at or above `level` in the buffer, the log file and stderr
+    pub fn set_level(&self, level: Level) {
+        if let Some(handle) = &self.level {
+            if let Err(e) = handle.reload(LevelFilter::from_level(level)) {
+                tracing::error!("failed to set log level: {}", e);
+            }
+        }
+    }
+
+    pub fn set_enable(&self, enable: bool) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.enable = enable;
+        }
+    }
+
+    pub fn set_max(&self, max: usize) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.max = max;
+            while inner.records.len() > max {
+                inner.records.pop_front();
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.records.clear();
+        }
+    }
+
+    /// Formats the records at or above `level` whose target or message contains `filter`, among
+    /// those captured at the level of [LogBuffer::set_level]
+    pub fn text(&self, level: Level, filter: &str, locale: Locale) -> String {
+        let mut log = String::new();
+        if let Ok(inner) = self.inner.lock() {
+            for record in inner
+                .records
+                .iter()
+                .filter(|r| r.level <= level)
+                .filter(|r| r.target.contains(filter) || r.message.contains(filter))
+            {
+                writeln!(
+                    log,
+                    "{} {:>5} {}: {}",
+                    locale.datetime(&record.time),
+                    record.level,
+                    record.target,
+                    record.message
+                )
+                .ok();
+            }
+        }
+        log
+    }
+
+    fn push(&self, record: LogRecord) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if !inner.enable {
+                return;
+            }
+            inner.records.push_back(record);
+            while inner.records.len() > inner.max {
+                inner.records.pop_front();
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            write!(self.message, "{:?}", value).ok();
+        } else {
+            write!(self.message, " {}={:?}", field.name(), value).ok();
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogBuffer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        self.push(LogRecord {
+            time: Local::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_owned(),
+            message: visitor.message,
+        });
+    }
+}
+
+/// Level of the events captured, INFO if `level` is not a level
+pub fn parse_level(level: &str) -> Level {
+    level.parse().unwrap_or(Level::INFO)
+}
+
+/// Subscriber feeding the returned buffer and, if enabled, the log file, with the error opening it
+fn subscriber(
+    setting: &Setting,
+    stderr: bool,
+) -> (
+    impl Subscriber + Send + Sync,
+    LogBuffer,
+    Option<std::io::Error>,
+) {
+    let mut log_buffer = LogBuffer::new(setting.log_enable, setting.log_max as _);
+    // every layer is filtered here, so that the frequent TRACE events of the frames do not push
+    // the others out of the buffer nor flood the log file
+    let (level, handle) =
+        reload::Layer::new(LevelFilter::from_level(parse_level(&setting.log_level)));
+    log_buffer.level = Some(handle);
+
+    let (file_layer, file_err) = if setting.log_file_enable {
+        match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&setting.log_file_path)
+        {
+            Ok(file) => (
+                Some(
+                    tracing_subscriber::fmt::layer()
+                        .with_ansi(false)
+                        .with_writer(Mutex::new(file)),
+                ),
+                None,
+            ),
+            Err(e) => (None, Some(e)),
+        }
+    } else {
+        (None, None)
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(level)
+        .with(log_buffer.clone())
+        .with(file_layer)
+        .with(stderr.then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr)));
+    (subscriber, log_buffer, file_err)
+}
+
+/// Installs the global subscriber, which feeds the returned buffer and, if enabled, the log file.
+/// The events are also printed to stderr if `stderr`, e.g., without the window.
+pub fn init(setting: &Setting, stderr: bool) -> LogBuffer {
+    let (subscriber, log_buffer, file_err) = subscriber(setting, stderr);
+    subscriber.init();
+
+    if let Some(e) = file_err {
+        tracing::error!("failed to open log file {}: {}", setting.log_file_path, e);
+    }
+
+    log_buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(log_buffer: &LogBuffer) -> Vec<String> {
+        let inner = log_buffer.inner.lock().unwrap();
+        inner.records.iter().map(|r| r.message.clone()).collect()
+    }
+
+    #[test]
+    fn capture_at_the_level_of_the_setting() {
+        let mut setting = Setting::new();
+        setting.log_file_enable = false;
+        setting.log_level = "INFO".to_owned();
+        let (subscriber, log_buffer, _) = subscriber(&setting, false);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::trace!("receive");
+            tracing::info!("geometry");
+            assert_eq!(messages(&log_buffer), vec!["geometry"]);
+
+            log_buffer.set_level(Level::TRACE);
+            tracing::trace!("receive");
+            assert_eq!(messages(&log_buffer), vec!["geometry", "receive"]);
+
+            log_buffer.set_level(Level::WARN);
+            tracing::info!("geometry");
+            tracing::warn!("overlap");
+            assert_eq!(
+                messages(&log_buffer),
+                vec!["geometry", "receive", "overlap"]
+            );
+        });
+    }
+}
@@ -11,84 +11,511 @@
  *
  */
 
+mod acoustic_camera;
+mod camera_bookmarks;
+mod clock;
+mod coalesce;
+mod compare;
+mod console;
+mod cpu_slice;
+mod daemon;
+mod decoder;
+mod drift;
+mod drive_history;
+mod export;
+mod field_worker;
+mod figure;
+mod file_watcher;
+mod fixtures;
+mod focus_fit;
+mod hdf5;
+mod hydrophone;
+mod ideal_overlay;
+mod io_worker;
+mod journal;
+mod line_scan;
+mod locale;
+mod log;
+mod npy;
+mod panel;
+mod perturbation;
+mod render;
+mod replay;
+mod screenshot;
+mod session;
 mod settings;
+mod slice_gizmo;
+mod slices;
+mod steering;
+mod stm_duty;
+mod tags;
+mod trans_calibration;
+mod ui_layout;
+mod video;
+mod volume;
+mod volume_export;
+mod wav;
 
-use std::{collections::VecDeque, f32::consts::PI, path::Path, time::Instant};
+use std::{
+    f32::consts::PI,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "offscreen_renderer")]
+use crate::panel::OffscreenPanel;
 
 use acoustic_field_viewer::{
-    camera_helper,
-    sound_source::SoundSource,
+    calibration::{self, PressureCalibration, PressureUnit},
+    camera_helper, color_map,
+    directivity::{Directivity, DirectivityTable},
+    far_field::FarFieldPattern,
+    field::{self, FieldApproximation, FieldModel, FocalSpot},
+    isosurface,
+    lobes::{LobeReport, LobeSearch},
+    medium::MEDIUM_PRESETS,
+    mesh::Mesh,
+    metadata::ExperimentMetadata,
+    obstacle::ObstacleShape,
+    power::PowerSummary,
+    region::{RegionIntegral, RegionShape},
+    scatterer::{Facet, Scattering, MAX_FACETS},
     view::{
-        render_system::RenderSystem, AcousticFiledSliceViewer, SoundSourceViewer, System,
-        UpdateFlag,
+        render_graph::Pass,
+        render_system::{types, RenderSystem},
+        AcousticFiledSliceViewer, ColorBarLegend, ColorBarViewer, CompareView, FieldQuantity,
+        GridViewer, IsosurfaceViewer, PickId, ScreenshotTarget, ShaderWatcher, SoundSourceViewer,
+        SourceColoring, System, UpdateFlag, ViewerSettings, VolumeViewer,
     },
-    Matrix4,
+    view_controller::ViewController,
+    volume::{VolumeGrid, VolumeMode, VolumeSettings},
+    Matrix4, Vector3,
 };
 use autd3_core::hardware_defined::{
-    RxGlobalControlFlags, MOD_SAMPLING_FREQ_BASE, POINT_SEQ_BASE_FREQ,
+    RxGlobalControlFlags, AUTD_WIDTH, MOD_SAMPLING_FREQ_BASE, POINT_SEQ_BASE_FREQ, TRANS_SPACING_MM,
+};
+use autd3_emulator_server::{
+    AutdData, AutdServer, CommandedFocus, FirmwareState, Geometry, GeometryWarning, HistoryLimits,
+    HistoryUsage, Layout,
 };
-use autd3_emulator_server::{AutdData, AutdServer, DelayOffset, Modulation, Sequence};
 use gfx::Device;
 use glutin::{
-    event::{Event, WindowEvent},
+    event::{
+        ElementState, Event, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode,
+        WindowEvent,
+    },
     event_loop::ControlFlow,
     platform::run_return::EventLoopExtRunReturn,
 };
 use imgui::*;
 use shader_version::OpenGL;
 
-use crate::settings::Setting;
+use crate::{
+    clock::{Clock, Ramp, TIME_SCALE_MAX, TIME_SCALE_MIN},
+    coalesce::Coalescer,
+    compare::Reference,
+    cpu_slice::{CpuSlice, SliceRequest},
+    decoder::Decoder,
+    export::{ExportFormat, ExportPreset, LengthUnit, SliceSnapshot},
+    field_worker::{FieldWorker, OwnedField},
+    fixtures::{Collisions, Fixture, FixtureSetting},
+    focus_fit::{EstimatedFocus, FocusEstimator},
+    hydrophone::TraceSource,
+    ideal_overlay::IdealContours,
+    io_worker::{IoWorker, JobResult},
+    locale::LOCALES,
+    log::LogBuffer,
+    panel::{
+        AcousticCameraPanel, CameraPanel, ConsolePanel, DriveHistoryPanel, ExperimentPanel,
+        HydrophonePanel, LineScanPanel, LogPanel, ParticlesPanel, SessionPanel, SlicePanel,
+        UiLayoutPanel, BOOKMARK_HOTKEYS,
+    },
+    perturbation::TrialSummary,
+    replay::{ReplayCheck, ReplayOptions, StateHasher},
+    session::Session,
+    settings::Setting,
+    slice_gizmo::{GizmoDrag, GizmoHandle, SliceGizmo},
+    slices::{ExtraSlice, PLANES},
+    steering::{SteeringLimit, SteeringModel},
+    stm_duty::FocusDuty,
+    tags::TagHistory,
+    trans_calibration::TransCalibration,
+    video::{VideoFormat, VideoRecorder},
+    volume::VolumeSampler,
+};
+
+const OPENGL: OpenGL = OpenGL::V4_5;
+const PROJECTOR_FONT_SCALE: f32 = 1.5;
+// Interval of the picking under the moving mouse, which reads the ID buffer back
+const HOVER_INTERVAL: Duration = Duration::from_millis(100);
+// Export presets are triggered by F1 to F12 in order
+const EXPORT_HOTKEYS: [VirtualKeyCode; 12] = [
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F8,
+    VirtualKeyCode::F9,
+    VirtualKeyCode::F10,
+    VirtualKeyCode::F11,
+    VirtualKeyCode::F12,
+];
+// The force arrows are thinned out beyond, since they are computed on every change of the drive
+const MAX_QUIVER_ARROWS: usize = 4096;
+// Changes on which the force arrows are recomputed
+const QUIVER_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    UpdateFlag::UPDATE_SOURCE_DRIVE.bits()
+        | UpdateFlag::UPDATE_COLOR_MAP.bits()
+        | UpdateFlag::UPDATE_WAVENUM.bits()
+        | UpdateFlag::UPDATE_SLICE_POS.bits()
+        | UpdateFlag::UPDATE_SLICE_SIZE.bits()
+        | UpdateFlag::INIT_SOURCE.bits(),
+);
+// Changes on which the maximum amplitude on the slice is recomputed for the auto scale
+const AUTO_SCALE_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    UpdateFlag::UPDATE_SOURCE_DRIVE.bits()
+        | UpdateFlag::UPDATE_WAVENUM.bits()
+        | UpdateFlag::UPDATE_SLICE_POS.bits()
+        | UpdateFlag::UPDATE_SLICE_SIZE.bits()
+        | UpdateFlag::INIT_SOURCE.bits(),
+);
+// Changes on which the slice is computed on the CPU again
+const CPU_SLICE_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    UpdateFlag::UPDATE_SOURCE_DRIVE.bits()
+        | UpdateFlag::UPDATE_COLOR_MAP.bits()
+        | UpdateFlag::UPDATE_WAVENUM.bits()
+        | UpdateFlag::UPDATE_SLICE_POS.bits()
+        | UpdateFlag::UPDATE_SLICE_SIZE.bits()
+        | UpdateFlag::INIT_SOURCE.bits(),
+);
+// Changes on which the pressure on the scatterers is evaluated again
+const SCATTERING_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    UpdateFlag::UPDATE_SOURCE_DRIVE.bits()
+        | UpdateFlag::UPDATE_WAVENUM.bits()
+        | UpdateFlag::INIT_SOURCE.bits(),
+);
+// Changes on which the far-field pattern is computed again
+const FAR_FIELD_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    UpdateFlag::UPDATE_SOURCE_DRIVE.bits()
+        | UpdateFlag::UPDATE_WAVENUM.bits()
+        | UpdateFlag::INIT_SOURCE.bits(),
+);
+// Changes on which the contours of the ideal focus are traced again
+const IDEAL_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    UpdateFlag::UPDATE_WAVENUM.bits()
+        | UpdateFlag::UPDATE_SLICE_POS.bits()
+        | UpdateFlag::UPDATE_SLICE_SIZE.bits()
+        | UpdateFlag::INIT_SOURCE.bits(),
+);
+// Changes on which the power summary is computed again
+const POWER_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    FAR_FIELD_UPDATE.bits()
+        | UpdateFlag::UPDATE_SLICE_POS.bits()
+        | UpdateFlag::UPDATE_SLICE_SIZE.bits(),
+);
+// Changes on which the volume is sampled again
+const VOLUME_UPDATE: UpdateFlag = UpdateFlag::from_bits_truncate(
+    UpdateFlag::UPDATE_SOURCE_DRIVE.bits()
+        | UpdateFlag::UPDATE_WAVENUM.bits()
+        | UpdateFlag::INIT_SOURCE.bits(),
+);
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+const SETTING_PATH: &str = "setting.json";
+const TOAST_DURATION: Duration = Duration::from_secs(4);
 
 struct App {
     setting: Setting,
-    sources: Vec<SoundSource>,
-    last_amp: Vec<f32>,
+    drive: Decoder,
+    perturbation_trials: Option<TrialSummary>,
+    trials_worker: FieldWorker<TrialSummary>,
+    ramp: Option<Ramp>,
+    // true after Clear until the output is enabled by a gain
+    ramp_armed: bool,
     sound_source_viewer: SoundSourceViewer,
+    slice: SlicePanel,
     field_slice_viewer: AcousticFiledSliceViewer,
+    // one for each of the extra slices in the setting, sharing the color setting of the main one
+    extra_slice_viewers: Vec<AcousticFiledSliceViewer>,
+    color_bar_viewer: ColorBarViewer,
+    grid_viewer: GridViewer,
+    cpu_slice: CpuSlice,
+    // phase of the last computation on the CPU, which is None if not computed on the CPU
+    cpu_slice_wt: Option<f32>,
+    volume_viewer: VolumeViewer,
+    volume_sampler: VolumeSampler,
+    volume_grid: Option<VolumeGrid>,
+    // settings of the last sampling, which is stale if None
+    volume_sampled: Option<VolumeSettings>,
+    isosurface_viewer: IsosurfaceViewer,
+    isosurface: Option<Mesh>,
+    // level in the unit of the field of the isosurface, which is stale if None
+    isosurface_level: Option<f32>,
+    isosurface_path: ImString,
     view_projection: (Matrix4, Matrix4),
     init: bool,
-    ctrl_flag: RxGlobalControlFlags,
-    modulation: Option<Modulation>,
-    sequence: Option<Sequence>,
-    delay_offset: Option<DelayOffset>,
-    log_buf: VecDeque<String>,
+    // true while the sources are the placeholder, until a geometry is received or loaded
+    placeholder: bool,
+    firmware_state: Option<FirmwareState>,
+    geometry_warnings: Vec<GeometryWarning>,
+    log: LogPanel,
     #[cfg(feature = "offscreen_renderer")]
-    offscreen_renderer: offscreen_renderer::OffscreenRenderer,
-    save_path: ImString,
-    record_path: ImString,
-    recording: bool,
+    offscreen: OffscreenPanel,
+    modifiers: ModifiersState,
+    key_update_flag: UpdateFlag,
+    spectate: Option<String>,
+    num_spectators: usize,
+    num_pending: usize,
+    coalescer: Coalescer,
+    history_usage: HistoryUsage,
+    reset_requested: bool,
+    projector_backup: Option<ViewerSettings>,
+    directivity_file: ImString,
+    directivity_requested: bool,
+    trans_calibration_file: ImString,
+    trans_calibration_requested: bool,
+    ui_layout: UiLayoutPanel,
+    session: SessionPanel,
+    camera: CameraPanel,
+    experiment: ExperimentPanel,
+    clock: Clock,
+    commanded_foci: Vec<CommandedFocus>,
+    cursor: Option<(f64, f64)>,
+    pick_requested: bool,
+    // picked under the cursor after it moves, at most every HOVER_INTERVAL
+    hover_requested: bool,
+    last_hover: Instant,
+    hovered: Option<PickId>,
+    probe_dragging: bool,
+    slice_drag: Option<GizmoDrag>,
+    view_controller: ViewController,
+    // button of the mouse navigating the camera while held
+    camera_drag: Option<imgui::MouseButton>,
+    hydrophone: HydrophonePanel,
+    drive_history: DriveHistoryPanel,
+    // Limits of the histories in memory, applied when the setting changes
+    history_limits: HistoryLimits,
+    region_integral: Option<(RegionIntegral, Instant)>,
+    region_worker: FieldWorker<RegionIntegral>,
+    // Maximum amplitude on the slice for the auto scale
+    auto_scale_worker: FieldWorker<f32>,
+    auto_scale_requested: bool,
+    line_scan: LineScanPanel,
+    ideal_contours: Option<IdealContours>,
+    stm_duty: Option<Option<Vec<FocusDuty>>>,
+    steering_limit: Option<SteeringLimit>,
+    focal_spot: Option<(FocalSpot, Instant)>,
+    lobe_report: Option<LobeReport>,
+    far_field: Option<FarFieldPattern>,
+    power_summary: Option<PowerSummary>,
+    reference: Option<Reference>,
+    reference_changed: bool,
+    fixtures: Vec<Fixture>,
+    collisions: Option<Collisions>,
+    // Of the fixtures marked as scatterers, None if there is none
+    scattering: Option<Scattering>,
+    focus_estimator: FocusEstimator,
+    estimated_focus: Option<EstimatedFocus>,
+    acoustic_camera: AcousticCameraPanel,
+    picked: Option<PickId>,
+    console: ConsolePanel,
+    shader_watcher: Option<ShaderWatcher>,
+    shader_errors: Vec<String>,
+    replay: Option<ReplayOptions>,
+    replay_check: Option<ReplayCheck>,
+    exit_code: Option<i32>,
+    wave_phase: f32,
+    gain_index: usize,
+    tags: TagHistory,
+    // Base and force in N of the arrows on the slice
+    quiver: Vec<(Vector3, Vector3)>,
+    particles: ParticlesPanel,
+    // Simulation time at which the temperature profile started
+    drift_start: f64,
+    // Air temperature, or the sound speed in mm/s if not set, restored when the drift stops
+    air_before_drift: (Option<f32>, f32),
+    export_requested: Vec<String>,
+    // File names of the screenshots to take at the end of the frame, timestamped if empty
+    screenshot_requested: Vec<String>,
+    video: Option<VideoRecorder>,
+    // Starts or stops the video at the end of the frame
+    video_requested: bool,
+    io_worker: IoWorker,
+    toasts: Vec<(JobResult, Instant)>,
+    last_autosave: Instant,
+    // JSON of the setting last written, to skip autosaving when nothing has changed
+    saved_setting: String,
 }
 
 impl App {
-    pub fn new(setting: Setting, system: &System) -> Self {
-        let opengl = OpenGL::V4_5;
-        let sound_source_viewer = SoundSourceViewer::new(&system.render_sys, opengl);
+    pub fn new(
+        mut setting: Setting,
+        system: &System,
+        log_buffer: LogBuffer,
+        spectate: Option<String>,
+        replay: Option<ReplayOptions>,
+    ) -> Self {
+        // the reference of the comparison is not kept over the sessions
+        setting.viewer_setting.compare = CompareView::Current;
+        let sound_source_viewer = SoundSourceViewer::new(&system.render_sys, OPENGL);
         let field_slice_viewer =
-            AcousticFiledSliceViewer::new(&system.render_sys, opengl, &setting.viewer_setting);
+            AcousticFiledSliceViewer::new(&system.render_sys, OPENGL, &setting.viewer_setting);
+        let extra_slice_viewers = setting
+            .extra_slices
+            .iter()
+            .map(|_| {
+                AcousticFiledSliceViewer::new(&system.render_sys, OPENGL, &setting.viewer_setting)
+            })
+            .collect();
+        let color_bar_viewer = ColorBarViewer::new(&system.render_sys, OPENGL);
+        let grid_viewer = GridViewer::new(&system.render_sys, OPENGL);
+        let mut volume_viewer = VolumeViewer::new(&system.render_sys, OPENGL);
+        volume_viewer.set_color_map(
+            &mut system.render_sys.factory.clone(),
+            field_slice_viewer.color_map(),
+        );
+        let isosurface_viewer = IsosurfaceViewer::new(&system.render_sys, OPENGL);
+        let mut isosurface_path = ImString::with_capacity(256);
+        isosurface_path.push_str(&setting.isosurface_path);
         let view_projection = system
             .render_sys
             .get_view_projection(&setting.viewer_setting);
 
-        let save_path = ImString::new(&setting.save_file_path);
-        let record_path = ImString::new(&setting.record_path);
+        let clock = Clock::new(setting.time_scale);
+        let mut directivity_file = ImString::with_capacity(256);
+        directivity_file.push_str(&setting.directivity_file);
+        let directivity_requested = !setting.directivity_file.is_empty();
+        let slice = SlicePanel::new(&setting);
+        let mut trans_calibration_file = ImString::with_capacity(256);
+        trans_calibration_file.push_str(&setting.trans_calibration_file);
+        let trans_calibration_requested = !setting.trans_calibration_file.is_empty();
+        let line_scan = LineScanPanel::new(&setting);
+        let acoustic_camera = AcousticCameraPanel::new(&setting);
+        let particle_drop_pos = vecmath_util::to_vec3(&setting.viewer_setting.slice_pos);
+        let history_limits = setting.history_limits();
+        let hydrophone = HydrophonePanel::new(&setting);
+        let drive_history = DriveHistoryPanel::new(&setting, history_limits);
+        let air_before_drift = (
+            setting.viewer_setting.temperature,
+            setting.viewer_setting.medium.sound_speed,
+        );
+        let session = SessionPanel::new(&setting);
+        let ui_layout = UiLayoutPanel::new(&setting);
+        #[cfg(feature = "offscreen_renderer")]
+        let offscreen = OffscreenPanel::new(&setting);
+        let experiment = ExperimentPanel::new(&setting.metadata);
+        let log = LogPanel::new(log_buffer, &setting);
+        let placeholder = spectate.is_none() && replay.is_none();
+        let mut drive = Decoder::default();
+        if placeholder {
+            drive.set_geometries(placeholder_geometries(&setting), false);
+        }
         Self {
             setting,
-            sources: Vec::new(),
-            last_amp: Vec::new(),
+            drive,
+            perturbation_trials: None,
+            trials_worker: FieldWorker::new("perturbation trials"),
+            ramp: None,
+            ramp_armed: false,
             sound_source_viewer,
+            slice,
             field_slice_viewer,
+            extra_slice_viewers,
+            color_bar_viewer,
+            volume_viewer,
+            grid_viewer,
+            cpu_slice: CpuSlice::new(),
+            cpu_slice_wt: None,
+            volume_sampler: VolumeSampler::new(),
+            volume_grid: None,
+            volume_sampled: None,
+            isosurface_viewer,
+            isosurface: None,
+            isosurface_level: None,
+            isosurface_path,
             view_projection,
             init: true,
-            ctrl_flag: RxGlobalControlFlags::empty(),
-            modulation: None,
-            sequence: None,
-            delay_offset: None,
-            log_buf: VecDeque::new(),
+            placeholder,
+            firmware_state: None,
+            geometry_warnings: Vec::new(),
+            log,
             #[cfg(feature = "offscreen_renderer")]
-            offscreen_renderer: offscreen_renderer::OffscreenRenderer::new(),
-            save_path,
-            record_path,
-            recording: false,
+            offscreen,
+            modifiers: ModifiersState::empty(),
+            key_update_flag: UpdateFlag::empty(),
+            spectate,
+            num_spectators: 0,
+            num_pending: 0,
+            coalescer: Coalescer::new(),
+            history_usage: HistoryUsage::default(),
+            reset_requested: false,
+            projector_backup: None,
+            directivity_file,
+            directivity_requested,
+            trans_calibration_file,
+            trans_calibration_requested,
+            ui_layout,
+            session,
+            camera: CameraPanel::new(),
+            experiment,
+            clock,
+            commanded_foci: Vec::new(),
+            cursor: None,
+            pick_requested: false,
+            hover_requested: false,
+            last_hover: Instant::now(),
+            hovered: None,
+            probe_dragging: false,
+            slice_drag: None,
+            view_controller: ViewController::new(),
+            camera_drag: None,
+            hydrophone,
+            drive_history,
+            history_limits,
+            region_integral: None,
+            region_worker: FieldWorker::new("region integration"),
+            auto_scale_worker: FieldWorker::new("auto scale"),
+            auto_scale_requested: false,
+            line_scan,
+            ideal_contours: None,
+            stm_duty: None,
+            steering_limit: None,
+            focal_spot: None,
+            lobe_report: None,
+            far_field: None,
+            power_summary: None,
+            reference: None,
+            reference_changed: false,
+            fixtures: Vec::new(),
+            collisions: None,
+            scattering: None,
+            focus_estimator: FocusEstimator::new(),
+            estimated_focus: None,
+            acoustic_camera,
+            picked: None,
+            console: ConsolePanel::new(),
+            shader_watcher: None,
+            shader_errors: Vec::new(),
+            replay,
+            replay_check: None,
+            exit_code: None,
+            wave_phase: 0.0,
+            gain_index: 0,
+            tags: TagHistory::default(),
+            quiver: Vec::new(),
+            particles: ParticlesPanel::new(particle_drop_pos),
+            drift_start: 0.0,
+            air_before_drift,
+            export_requested: Vec::new(),
+            screenshot_requested: Vec::new(),
+            video: None,
+            video_requested: false,
+            io_worker: IoWorker::new(),
+            toasts: Vec::new(),
+            last_autosave: Instant::now(),
+            saved_setting: String::new(),
         }
     }
 
@@ -102,15 +529,30 @@ impl App {
             ..
         } = system;
 
-        let mut autd_server = AutdServer::new(&format!("127.0.0.1:{}", self.setting.port)).unwrap();
+        let mut autd_server = self.open_server();
+        if self.exit_code.is_some() {
+            return;
+        }
+        if let Some(autd_server) = &mut autd_server {
+            autd_server.set_history_limits(self.history_limits);
+        }
 
         self.reset(&mut render_sys);
 
+        let font_scale = imgui.io().font_global_scale;
         let mut last_frame = Instant::now();
         let mut run = true;
         while run {
             events_loop.run_return(|event, _, control_flow| {
-                self.handle_event(&mut render_sys, &event);
+                let io = imgui.io();
+                let (want_capture_keyboard, want_capture_mouse) =
+                    (io.want_capture_keyboard, io.want_capture_mouse);
+                self.handle_event(
+                    &mut render_sys,
+                    &event,
+                    want_capture_keyboard,
+                    want_capture_mouse,
+                );
                 platform.handle_event(imgui.io_mut(), render_sys.window(), &event);
                 if let Event::WindowEvent { event, .. } = event {
                     match event {
@@ -127,58 +569,910 @@ impl App {
                 break;
             }
 
+            self.ui_layout.update(&mut imgui, &mut self.setting);
+
             let io = imgui.io_mut();
+            io.font_global_scale = if self.projector_backup.is_some() {
+                font_scale * PROJECTOR_FONT_SCALE
+            } else {
+                font_scale
+            };
             platform
                 .prepare_frame(io, render_sys.window())
                 .expect("Failed to start frame");
             let now = Instant::now();
             io.update_delta_time(now - last_frame);
+            self.clock.tick(now - last_frame);
+            if self.setting.wave_animation {
+                self.wave_phase +=
+                    2.0 * PI * self.setting.wave_animation_rate * (now - last_frame).as_secs_f32();
+                self.wave_phase %= 2.0 * PI;
+                self.field_slice_viewer.set_wave_phase(self.wave_phase);
+            }
             last_frame = now;
             let ui = imgui.frame();
 
-            let mut update_flag = self.handle_autd(&mut autd_server);
+            self.log.update_journal(&mut self.setting);
+            self.update_shaders(&mut render_sys);
+            self.update_io(&render_sys);
+            if let Some(autd_server) = &mut autd_server {
+                autd_server.set_latency(Duration::from_secs_f32(
+                    self.setting.latency_ms.max(0.0) / 1000.0,
+                ));
+                self.handle_autd(autd_server);
+                self.firmware_state = Some(autd_server.firmware_state());
+                self.num_spectators = autd_server.num_spectators();
+                self.num_pending = autd_server.num_pending();
+                let limits = self.setting.history_limits();
+                if limits != self.history_limits {
+                    autd_server.set_history_limits(limits);
+                    self.drive_history.set_limits(limits);
+                    self.history_limits = limits;
+                }
+                self.history_usage = autd_server.history_usage();
+                if autd_server.is_replay_finished() {
+                    if let Some(check) = self.replay_check.take() {
+                        self.exit_code = Some(if check.finish() { 0 } else { 1 });
+                        run = false;
+                    }
+                }
+            }
+            let mut update_flag = self.coalescer.poll(
+                Duration::from_secs_f32(self.setting.coalesce_ms.max(0.0) / 1000.0),
+                now,
+            );
+            update_flag |= self.update_playback();
+            update_flag |= self.update_follow_focus();
+            update_flag |= self.update_ramp();
+            update_flag |= self.update_drift();
+            self.particles.update(
+                self.clock.time(),
+                &mut self.drive,
+                &self.setting,
+                self.field_slice_viewer.directivity_table(),
+                self.scattering.as_ref(),
+            );
+            if let Some(path) = self.session.take_load() {
+                update_flag |= self.load_session(&path);
+            }
+            if std::mem::take(&mut self.reset_requested) {
+                self.reset(&mut render_sys);
+            }
+            update_flag |= std::mem::replace(&mut self.key_update_flag, UpdateFlag::empty());
             update_flag |= self.update_ui(&ui, &mut render_sys);
-            self.update_view(&mut render_sys, update_flag);
-            #[cfg(feature = "offscreen_renderer")]
-            {
-                if self.setting.save_file_enable {
-                    self.offscreen_renderer.update(
-                        &self.sources,
-                        &self.field_slice_viewer,
-                        &self.setting.viewer_setting,
-                        update_flag,
-                    );
+            let injection = self.console.take_injection();
+            if !injection.is_empty() {
+                match &mut autd_server {
+                    Some(autd_server) => autd_server.inject(injection),
+                    None => tracing::error!("cannot inject commands: server is not running"),
                 }
             }
+            for name in std::mem::take(&mut self.export_requested) {
+                self.export(&name);
+            }
+            if let Some(path) = self.session.take_save() {
+                self.save_session(path);
+            }
+            self.session
+                .update(autd_server.as_mut(), &self.setting, self.clock.time());
+            if std::mem::take(&mut self.directivity_requested) {
+                update_flag |= self.load_directivity();
+            }
+            if let Some(path) = self.slice.take_color_map() {
+                update_flag |= self.load_color_map(&path);
+            }
+            if std::mem::take(&mut self.trans_calibration_requested) {
+                update_flag |= self.load_trans_calibration();
+            }
+            if update_flag.contains(UpdateFlag::INIT_SOURCE) {
+                update_flag |= self.check_reference();
+            }
+            if update_flag.intersects(SCATTERING_UPDATE) {
+                self.update_scattering(&mut render_sys);
+            }
+            if std::mem::take(&mut self.reference_changed) {
+                self.upload_reference(&mut render_sys);
+            }
+            if self.setting.show_far_field
+                && (self.far_field.is_none() || update_flag.intersects(FAR_FIELD_UPDATE))
+            {
+                self.update_far_field();
+            }
+            if self.setting.show_power_summary
+                && (self.power_summary.is_none() || update_flag.intersects(POWER_UPDATE))
+            {
+                self.update_power_summary();
+            }
+            if self.setting.ideal_overlay.enabled
+                && (self.ideal_contours.is_none() || update_flag.intersects(IDEAL_UPDATE))
+            {
+                self.update_ideal_contours();
+            }
+            if std::mem::take(&mut self.auto_scale_requested)
+                || (self.setting.auto_scale && update_flag.intersects(AUTO_SCALE_UPDATE))
+            {
+                let model = self.field_slice_viewer.model();
+                self.auto_scale_worker
+                    .request(self.owned_field(), move |field| {
+                        field.peak_on_slice(model).1
+                    });
+            }
+            if let Some(amp) = self.auto_scale_worker.poll() {
+                update_flag |= self.update_auto_scale(amp);
+            }
+            if self.setting.show_force_quiver && update_flag.intersects(QUIVER_UPDATE) {
+                self.update_quiver();
+            }
+            self.update_view(&mut render_sys, update_flag);
+            #[cfg(feature = "offscreen_renderer")]
+            self.offscreen.update(
+                &self.setting,
+                &self.drive.sources,
+                &self.field_slice_viewer,
+                update_flag,
+            );
 
-            encoder.clear(
-                &render_sys.output_color,
+            let clicked = std::mem::take(&mut self.pick_requested);
+            let hover = (self.setting.show_source_tooltip || self.setting.show_coordinate_readout)
+                && self.hover_requested
+                && self.last_hover.elapsed() >= HOVER_INTERVAL;
+            let pick_at = if clicked || hover { self.cursor } else { None };
+            let mut ui = Some(ui);
+            render_sys.render(
+                &mut encoder,
                 self.setting.viewer_setting.background,
+                pick_at.is_some(),
+                |pass, render_sys, encoder| match pass {
+                    Pass::Ui => {
+                        if let Some(ui) = ui.take() {
+                            platform.prepare_render(&ui, render_sys.window());
+                            let draw_data = ui.render();
+                            render_sys
+                                .renderer
+                                .render(
+                                    &mut render_sys.factory,
+                                    encoder,
+                                    &mut render_sys.output_color,
+                                    draw_data,
+                                )
+                                .expect("Rendering failed");
+                        }
+                    }
+                    pass => self.draw_pass(pass, render_sys, encoder),
+                },
             );
-            encoder.clear_depth(&render_sys.output_stencil, 1.0);
-            self.sound_source_viewer.renderer(&mut encoder);
-            self.field_slice_viewer.renderer(&mut encoder);
-
-            platform.prepare_render(&ui, render_sys.window());
-            let draw_data = ui.render();
-            render_sys
-                .renderer
-                .render(
-                    &mut render_sys.factory,
-                    &mut encoder,
-                    &mut render_sys.output_color,
-                    draw_data,
-                )
-                .expect("Rendering failed");
+            if let Some((x, y)) = pick_at {
+                let RenderSystem {
+                    picking,
+                    device,
+                    factory,
+                    ..
+                } = &mut render_sys;
+                let id = picking.read(&mut encoder, device, factory, x as _, y as _);
+                if clicked {
+                    self.picked = id;
+                    tracing::debug!("picked {:?} at ({}, {})", id, x, y);
+                }
+                self.hovered = id;
+                self.hover_requested = false;
+                self.last_hover = Instant::now();
+            }
+            for name in std::mem::take(&mut self.screenshot_requested) {
+                self.take_screenshot(&mut render_sys, &mut encoder, &name);
+            }
+            if std::mem::take(&mut self.video_requested) {
+                self.toggle_video(&render_sys);
+            }
+            if self.video.as_ref().is_some_and(|v| v.is_due(now)) {
+                self.record_video_frame(&mut render_sys, &mut encoder, now);
+            }
             encoder.flush(&mut render_sys.device);
             render_sys.swap_buffers();
             render_sys.device.cleanup();
         }
 
-        self.setting.save_file_path = self.save_path.to_str().to_owned();
-        self.setting.record_path = self.record_path.to_str().to_owned();
-        self.setting.merge_render_sys(&render_sys);
-        self.setting.save("setting.json");
+        if let Some(video) = self.video.take() {
+            self.finish_video(video);
+        }
+        self.save_setting(&render_sys);
+        self.io_worker.finish();
+    }
+
+    // Draws the passes but the UI, which needs the frame of imgui
+    fn draw_pass(&mut self, pass: Pass, render_sys: &RenderSystem, encoder: &mut types::Encoder) {
+        match pass {
+            Pass::Scene => {
+                if self.setting.show_grid {
+                    self.grid_viewer.renderer(encoder);
+                }
+                self.sound_source_viewer.renderer(encoder);
+            }
+            Pass::Slice => {
+                self.field_slice_viewer.renderer(encoder);
+                for (viewer, extra) in self
+                    .extra_slice_viewers
+                    .iter_mut()
+                    .zip(&self.setting.extra_slices)
+                {
+                    if extra.enabled {
+                        viewer.renderer(encoder);
+                    }
+                }
+            }
+            Pass::Picking => {
+                self.sound_source_viewer
+                    .render_picking(encoder, &render_sys.picking);
+                self.field_slice_viewer
+                    .render_picking(encoder, &render_sys.picking);
+            }
+            Pass::Isosurface => {
+                if self.setting.show_isosurface {
+                    self.isosurface_viewer.renderer(encoder);
+                }
+            }
+            Pass::Volume => {
+                if self.setting.show_volume && self.volume_grid.is_some() {
+                    self.volume_viewer.renderer(encoder);
+                }
+            }
+            Pass::Overlays => {
+                if self.setting.show_color_bar {
+                    self.color_bar_viewer.renderer(encoder);
+                }
+            }
+            Pass::Ui => (),
+        }
+    }
+
+    fn retarget_viewers(&mut self, render_sys: &RenderSystem) {
+        self.sound_source_viewer.retarget(render_sys);
+        self.field_slice_viewer.retarget(render_sys);
+        for viewer in self.extra_slice_viewers.iter_mut() {
+            viewer.retarget(render_sys);
+        }
+        self.color_bar_viewer.retarget(render_sys);
+        self.grid_viewer.retarget(render_sys);
+        self.volume_viewer.retarget(render_sys);
+        self.isosurface_viewer.retarget(render_sys);
+    }
+
+    // Renders the scene again without the UI into an offscreen target of the window size, and
+    // returns the size and the RGBA pixels from the top
+    fn render_offscreen(
+        &mut self,
+        render_sys: &mut RenderSystem,
+        encoder: &mut types::Encoder,
+        transparent: bool,
+    ) -> Result<((u32, u32), Vec<u8>), String> {
+        let size = render_sys.window().inner_size();
+        let target =
+            ScreenshotTarget::new(&mut render_sys.factory, size.width as _, size.height as _)?;
+        let color = std::mem::replace(&mut render_sys.output_color, target.color.clone());
+        let depth = std::mem::replace(&mut render_sys.output_stencil, target.depth.clone());
+        self.retarget_viewers(render_sys);
+        let ui_enabled = render_sys.graph.is_enabled(Pass::Ui);
+        render_sys.graph.set_enabled(Pass::Ui, false);
+        let mut background = self.setting.viewer_setting.background;
+        if transparent {
+            background[3] = 0.0;
+        }
+        render_sys.render(encoder, background, false, |pass, render_sys, encoder| {
+            self.draw_pass(pass, render_sys, encoder)
+        });
+        render_sys.graph.set_enabled(Pass::Ui, ui_enabled);
+        render_sys.output_color = color;
+        render_sys.output_stencil = depth;
+        self.retarget_viewers(render_sys);
+
+        let pixels = target.read(encoder, &mut render_sys.device, &mut render_sys.factory)?;
+        let (width, height) = target.size();
+        Ok(((width as u32, height as u32), pixels))
+    }
+
+    // Writes the view rendered offscreen on the I/O worker
+    fn take_screenshot(
+        &mut self,
+        render_sys: &mut RenderSystem,
+        encoder: &mut types::Encoder,
+        name: &str,
+    ) {
+        let transparent = self.setting.screenshot_transparent;
+        let (size, pixels) = match self.render_offscreen(render_sys, encoder, transparent) {
+            Ok(frame) => frame,
+            Err(e) => {
+                tracing::error!("failed to render screenshot: {}", e);
+                return;
+            }
+        };
+        let path = screenshot::path(&self.setting.screenshot_folder, name, chrono::Local::now());
+        let metadata = self.metadata();
+        self.io_worker.submit("Screenshot", move || {
+            screenshot::write_png(&path, size, &pixels, transparent, &metadata)?;
+            tracing::info!("screenshot saved: {}", path.display());
+            Ok(())
+        });
+    }
+
+    fn toggle_video(&mut self, render_sys: &RenderSystem) {
+        if let Some(video) = self.video.take() {
+            self.finish_video(video);
+            return;
+        }
+        let size = render_sys.window().inner_size();
+        let setting = &self.setting.video;
+        let path = setting.path(&self.setting.screenshot_folder, chrono::Local::now());
+        match VideoRecorder::start(setting, path, (size.width.max(1), size.height.max(1))) {
+            Ok(video) => {
+                tracing::info!("recording video: {}", video.path().display());
+                self.video = Some(video);
+            }
+            Err(e) => tracing::error!("failed to start video: {}", e),
+        }
+    }
+
+    fn finish_video(&mut self, video: VideoRecorder) {
+        let path = video.path().to_owned();
+        let (frames, dropped) = (video.frames(), video.dropped());
+        let job = video.finish();
+        self.io_worker.submit("Video", move || {
+            job()?;
+            tracing::info!(frames, dropped, "video saved: {}", path.display());
+            Ok(())
+        });
+    }
+
+    fn record_video_frame(
+        &mut self,
+        render_sys: &mut RenderSystem,
+        encoder: &mut types::Encoder,
+        now: Instant,
+    ) {
+        let res = self
+            .render_offscreen(render_sys, encoder, false)
+            .and_then(|(size, pixels)| match &mut self.video {
+                Some(video) => video.push(now, size, pixels),
+                None => Ok(()),
+            });
+        if let Err(e) = res {
+            tracing::error!("video stopped: {}", e);
+            if let Some(video) = self.video.take() {
+                self.finish_video(video);
+            }
+        }
+    }
+
+    // The setting to be saved, with the values edited in the UI
+    fn setting_snapshot(&self, render_sys: &RenderSystem) -> Setting {
+        let mut setting = self.setting.clone();
+        setting.directivity_file = self.directivity_file.to_str().to_owned();
+        setting.trans_calibration_file = self.trans_calibration_file.to_str().to_owned();
+        setting.viewer_setting.compare = CompareView::Current;
+        self.ui_layout.save(&mut setting);
+        self.slice.save(&mut setting);
+        #[cfg(feature = "offscreen_renderer")]
+        self.offscreen.save(&mut setting);
+        self.session.save(&mut setting);
+        self.hydrophone.save(&mut setting);
+        setting.isosurface_path = self.isosurface_path.to_str().to_owned();
+        self.drive_history.save(&mut setting);
+        self.line_scan.save(&mut setting);
+        self.acoustic_camera.save(&mut setting);
+        setting.metadata = self.metadata();
+        self.log.save(&mut setting);
+        setting.time_scale = self.clock.scale();
+        if let Some(backup) = &self.projector_backup {
+            setting.viewer_setting.restore_display(backup);
+        }
+        // the drifted air is not saved, so that the drift starts again from it
+        if setting.temperature_drift {
+            Self::restore_air(self.air_before_drift, &mut setting.viewer_setting);
+        }
+        setting.merge_render_sys(render_sys);
+        setting
+    }
+
+    // Writes setting.json on the I/O worker if it has changed. Spectators and replays never
+    // write it.
+    fn save_setting(&mut self, render_sys: &RenderSystem) {
+        if self.spectate.is_some() || self.replay.is_some() {
+            return;
+        }
+        let setting = self.setting_snapshot(render_sys);
+        let json = match serde_json::to_string(&setting) {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::error!("failed to serialize setting: {}", e);
+                return;
+            }
+        };
+        if json == self.saved_setting {
+            return;
+        }
+        self.saved_setting = json;
+        self.io_worker.submit("Save setting", move || {
+            setting.save(SETTING_PATH).map_err(|e| e.to_string())
+        });
+    }
+
+    // The field is computed and written on the I/O worker
+    fn export(&mut self, name: &str) {
+        let preset = match self.setting.export_presets.iter().find(|p| p.name == name) {
+            Some(preset) => preset.clone(),
+            None => {
+                tracing::error!("export preset {} is not found", name);
+                return;
+            }
+        };
+        let mut settings = self.setting.viewer_setting;
+        if let Some(backup) = &self.projector_backup {
+            settings.restore_display(backup);
+        }
+        // the exports are of the current drive
+        settings.compare = CompareView::Current;
+        let slice = SliceSnapshot {
+            sources: self.drive.sources.clone(),
+            device_sizes: self.drive.device_sizes.clone(),
+            settings,
+            directivity_table: self.field_slice_viewer.directivity_table().cloned(),
+            scattering: self.scattering.clone(),
+            model: self.field_slice_viewer.model(),
+            wave_phase: self.wave_phase,
+            color_map: self.field_slice_viewer.color_map().to_vec(),
+            metadata: self.metadata(),
+        };
+        let path = preset.path(self.gain_index, chrono::Local::now());
+        self.io_worker
+            .submit(&format!("Export {}", preset.name), move || {
+                preset.export(&slice, &path)?;
+                tracing::info!("exported {}", path.display());
+                Ok(())
+            });
+    }
+
+    fn update_io(&mut self, render_sys: &RenderSystem) {
+        let now = Instant::now();
+        for result in self.io_worker.poll() {
+            self.toasts.push((result, now));
+        }
+        self.toasts
+            .retain(|(_, shown)| now.duration_since(*shown) < TOAST_DURATION);
+        if now.duration_since(self.last_autosave) >= AUTOSAVE_INTERVAL {
+            self.last_autosave = now;
+            self.save_setting(render_sys);
+        }
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    fn open_server(&mut self) -> Option<AutdServer> {
+        if let Some(replay) = &self.replay {
+            // checking against a manifest must not depend on the timing, so the frames are fed
+            // at once and the clock is stopped
+            if let Some(manifest) = &replay.manifest {
+                match ReplayCheck::new(&replay.capture, manifest) {
+                    Ok(check) => self.replay_check = Some(check),
+                    Err(e) => {
+                        tracing::error!("failed to load manifest {}: {}", manifest, e);
+                        self.exit_code = Some(2);
+                    }
+                }
+                self.clock.reset();
+                self.clock.set_paused(true);
+            }
+            return match AutdServer::replay(&replay.capture, replay.manifest.is_none()) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    tracing::error!("failed to replay {}: {}", replay.capture, e);
+                    self.exit_code = Some(2);
+                    None
+                }
+            };
+        }
+        if let Some(host) = &self.spectate {
+            return match AutdServer::spectate(host) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    tracing::error!("failed to connect to {}: {}", host, e);
+                    None
+                }
+            };
+        }
+
+        let addr = format!("127.0.0.1:{}", self.setting.port);
+        let mut server = match AutdServer::new(&addr, &self.setting.handoff_host) {
+            Ok(server) => server,
+            Err(e) => {
+                tracing::error!("failed to open {}: {}", addr, e);
+                return None;
+            }
+        };
+        if self.setting.spectator_enable {
+            let addr = format!(
+                "{}:{}",
+                self.setting.spectator_host, self.setting.spectator_port
+            );
+            if let Err(e) = server.open_spectator_host(&addr) {
+                tracing::error!("failed to accept spectators on {}: {}", addr, e);
+            }
+        }
+        server.set_trusted_peers(&self.setting.trusted_peers());
+        self.session.load_layout(&mut server, &self.setting);
+        Some(server)
+    }
+
+    fn toggle_projector(&mut self) -> UpdateFlag {
+        match self.projector_backup.take() {
+            Some(backup) => self.setting.viewer_setting.restore_display(&backup),
+            None => {
+                self.projector_backup = Some(self.setting.viewer_setting);
+                self.setting.viewer_setting = self.setting.viewer_setting.projector();
+            }
+        }
+        UpdateFlag::INIT_SOURCE
+            | UpdateFlag::UPDATE_SOURCE_DRIVE
+            | UpdateFlag::UPDATE_SOURCE_ALPHA
+            | UpdateFlag::UPDATE_COLOR_MAP
+    }
+
+    // Updates the modulation and STM sample indices at the current simulation time, and drives
+    // the sources toward the focus of the current STM point
+    fn update_playback(&mut self) -> UpdateFlag {
+        let perturbation = &self.setting.perturbation;
+        if !self
+            .drive
+            .update_playback(&self.clock, &self.setting.viewer_setting, perturbation)
+        {
+            return UpdateFlag::empty();
+        }
+        if std::mem::take(&mut self.ramp_armed) {
+            self.start_ramp();
+        } else {
+            self.retarget_ramp();
+        }
+        UpdateFlag::UPDATE_SOURCE_DRIVE
+    }
+
+    // Moves the slice center onto the active STM point, or onto the estimated focus of the gain
+    fn update_follow_focus(&mut self) -> UpdateFlag {
+        if !self.setting.follow_focus {
+            return UpdateFlag::empty();
+        }
+        let stm_point = self
+            .drive
+            .seq_index
+            .and_then(|idx| self.drive.stm_point(idx));
+        let target = match stm_point {
+            Some((focus, _)) => Some(focus),
+            None => self.estimated_focus.as_ref().map(|e| e.pos),
+        };
+        let slice_pos = &mut self.setting.viewer_setting.slice_pos;
+        match target {
+            Some(target) if slice_pos[..3] != target => {
+                *slice_pos = [target[0], target[1], target[2], slice_pos[3]];
+                self.field_slice_viewer.move_to(*slice_pos);
+                UpdateFlag::UPDATE_SLICE_POS
+            }
+            _ => UpdateFlag::empty(),
+        }
+    }
+
+    // The drive sent by the SDK, i.e., the target of the ramp
+    fn record_drive(&mut self) {
+        self.drive_history.record(
+            self.clock.time(),
+            &self.drive.sources,
+            &self.setting.viewer_setting,
+        );
+    }
+
+    // Starts the ramp toward the current amplitudes if enabled
+    fn start_ramp(&mut self) {
+        if !self.setting.ramp_enable {
+            return;
+        }
+        let amps = self.drive.sources.iter().map(|s| s.amp).collect();
+        self.ramp = Some(Ramp::new(
+            self.clock.time(),
+            self.setting.ramp_duration as f64,
+            amps,
+        ));
+        for source in self.drive.sources.iter_mut() {
+            source.amp = 0.;
+        }
+        tracing::debug!("ramp-up for {} s", self.setting.ramp_duration);
+    }
+
+    // The amplitudes set during the ramp become its target
+    fn retarget_ramp(&mut self) {
+        if let Some(ramp) = &mut self.ramp {
+            ramp.amps = self.drive.sources.iter().map(|s| s.amp).collect();
+        }
+    }
+
+    fn update_ramp(&mut self) -> UpdateFlag {
+        let ramp = match &self.ramp {
+            Some(ramp) => ramp,
+            None => return UpdateFlag::empty(),
+        };
+        let progress = ramp.progress(self.clock.time());
+        for (source, &amp) in self.drive.sources.iter_mut().zip(ramp.amps.iter()) {
+            source.amp = amp * progress;
+        }
+        if progress >= 1.0 {
+            self.ramp = None;
+        }
+        UpdateFlag::UPDATE_SOURCE_DRIVE
+    }
+
+    // Minutes elapsed on the temperature profile
+    fn drift_minutes(&self) -> f32 {
+        let elapsed = (self.clock.time() - self.drift_start).max(0.0) as f32;
+        elapsed * self.setting.temperature_profile.speed
+    }
+
+    // Sets the air of `settings` back to `air` before the drift
+    fn restore_air(air: (Option<f32>, f32), settings: &mut ViewerSettings) {
+        match air {
+            (Some(temperature), _) => settings.set_temperature(temperature),
+            (None, sound_speed) => settings.set_sound_speed(sound_speed),
+        }
+    }
+
+    fn update_drift(&mut self) -> UpdateFlag {
+        if !self.setting.temperature_drift {
+            return UpdateFlag::empty();
+        }
+        // the profile restarts with the clock
+        if self.clock.time() < self.drift_start {
+            self.drift_start = self.clock.time();
+        }
+        let profile = &self.setting.temperature_profile;
+        let temperature = match profile.temperature_at(self.drift_minutes()) {
+            Some(temperature) => temperature,
+            None => return UpdateFlag::empty(),
+        };
+        if self
+            .setting
+            .viewer_setting
+            .temperature
+            .is_some_and(|t| (t - temperature).abs() < 1e-3)
+        {
+            return UpdateFlag::empty();
+        }
+        self.setting.viewer_setting.set_temperature(temperature);
+        UpdateFlag::UPDATE_WAVENUM
+    }
+
+    // One sample of the STM in sequence mode, otherwise one sample of the modulation
+    fn step_clock(&mut self) {
+        let dt = match (self.drive.active_sequence(), &self.drive.modulation) {
+            (Some(seq), _) => seq.seq_div.max(1) as f64 / POINT_SEQ_BASE_FREQ as f64,
+            (_, Some(m)) => m.mod_div.max(1) as f64 / MOD_SAMPLING_FREQ_BASE,
+            _ => 1.0 / 60.0,
+        };
+        self.clock.set_paused(true);
+        self.clock.step(dt);
+    }
+
+    fn metadata(&self) -> ExperimentMetadata {
+        self.experiment.metadata(self.tags.current().join(", "))
+    }
+
+    fn load_trans_calibration(&mut self) -> UpdateFlag {
+        let path = self.trans_calibration_file.to_str();
+        if path.is_empty() {
+            return UpdateFlag::empty();
+        }
+        match TransCalibration::from_csv(path) {
+            Ok(calibration) => {
+                tracing::info!(
+                    entries = calibration.len(),
+                    "load transducer calibration {}",
+                    path
+                );
+                self.drive.trans_calibration = Some(calibration);
+                self.readjust_drive()
+            }
+            Err(e) => {
+                tracing::error!("failed to load transducer calibration: {}", e);
+                UpdateFlag::empty()
+            }
+        }
+    }
+
+    fn adjust_drive(&mut self) {
+        self.drive.adjust(&self.setting.perturbation);
+    }
+
+    // Adjusts the last sent drive again, e.g., after resampling the perturbation, except while
+    // paused or ramping, when the adjustment applies from the next drive
+    fn readjust_drive(&mut self) -> UpdateFlag {
+        if self.ramp.is_some() || !self.drive.readjust(&self.setting.perturbation) {
+            return UpdateFlag::empty();
+        }
+        UpdateFlag::UPDATE_SOURCE_DRIVE
+    }
+
+    // Applies the overrides at once, from the current drive if none has been sent, e.g., to the
+    // placeholder
+    fn apply_drive_overrides(&mut self) -> UpdateFlag {
+        if self.drive.sent_drive.len() != self.drive.sources.len() {
+            self.drive.sent_drive = self
+                .drive
+                .sources
+                .iter()
+                .map(|s| (s.amp, s.phase))
+                .collect();
+        }
+        self.readjust_drive()
+    }
+
+    fn drive_override_ui(&mut self, ui: &Ui, i: usize) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let id = ui.push_id(im_str!("drive override"));
+        let mut enabled = self.drive.drive_overrides.contains_key(&i);
+        if ui.checkbox(im_str!("Override drive"), &mut enabled) {
+            if enabled {
+                let sent = self.drive.sent_drive.get(i).copied();
+                let source = &self.drive.sources[i];
+                self.drive
+                    .drive_overrides
+                    .insert(i, sent.unwrap_or((source.amp, source.phase)));
+            } else {
+                self.drive.drive_overrides.remove(&i);
+            }
+            update_flag |= self.apply_drive_overrides();
+        }
+        if let Some((amp, phase)) = self.drive.drive_overrides.get_mut(&i) {
+            let mut changed = Slider::new(im_str!("Amplitude"))
+                .range(0.0..=1.0)
+                .build(ui, amp);
+            changed |= AngleSlider::new(im_str!("Phase"))
+                .range_degrees(0.0..=360.0)
+                .build(ui, phase);
+            if changed {
+                update_flag |= self.apply_drive_overrides();
+            }
+        }
+        if !self.drive.drive_overrides.is_empty() {
+            if ui.small_button(im_str!("Reset all overrides")) {
+                self.drive.drive_overrides.clear();
+                update_flag |= self.apply_drive_overrides();
+            }
+            ui.same_line(0.);
+            ui.text(format!("{} overridden", self.drive.drive_overrides.len()));
+        }
+        id.pop(ui);
+        update_flag
+    }
+
+    // The focus of the calibrated drive without the perturbation is the nominal one
+    // The trials are run on the nominal drive in the background, and the summary is taken by
+    // poll_perturbation_trials
+    fn run_perturbation_trials(&mut self) {
+        let field = OwnedField {
+            sources: self.drive.nominal_drive(),
+            ..self.owned_field()
+        };
+        let perturbation = self.setting.perturbation;
+        let model = self.field_slice_viewer.model();
+        self.trials_worker
+            .request(field, move |field| perturbation.trials(field, model));
+    }
+
+    fn poll_perturbation_trials(&mut self) {
+        let summary = match self.trials_worker.poll() {
+            Some(summary) => summary,
+            None => return,
+        };
+        tracing::info!(
+            trials = summary.trials,
+            amp_mean = summary.amp_mean,
+            amp_std = summary.amp_std,
+            shift_mean = summary.shift_mean,
+            "perturbation trials"
+        );
+        self.perturbation_trials = Some(summary);
+    }
+
+    fn load_directivity(&mut self) -> UpdateFlag {
+        let path = self.directivity_file.to_str();
+        if path.is_empty() {
+            return UpdateFlag::empty();
+        }
+        match DirectivityTable::from_csv(path) {
+            Ok(table) => {
+                tracing::info!(
+                    frequencies = ?table.frequencies(),
+                    "load directivity {}",
+                    path
+                );
+                for viewer in self.extra_slice_viewers.iter_mut() {
+                    viewer.set_directivity_table(Some(table.clone()));
+                }
+                self.field_slice_viewer.set_directivity_table(Some(table));
+                UpdateFlag::UPDATE_WAVENUM
+            }
+            Err(e) => {
+                tracing::error!("failed to load directivity: {}", e);
+                UpdateFlag::empty()
+            }
+        }
+    }
+
+    fn load_color_map(&mut self, path: &str) -> UpdateFlag {
+        if path.is_empty() {
+            return UpdateFlag::empty();
+        }
+        match color_map::read_csv(path) {
+            Ok(colors) => {
+                tracing::info!(colors = colors.len(), "load color map {}", path);
+                for viewer in self.extra_slice_viewers.iter_mut() {
+                    viewer.set_imported_color_map(Some(colors.clone()));
+                }
+                self.field_slice_viewer.set_imported_color_map(Some(colors));
+                UpdateFlag::UPDATE_COLOR_MAP
+            }
+            Err(e) => {
+                tracing::error!("failed to load color map: {}", e);
+                UpdateFlag::empty()
+            }
+        }
+    }
+
+    fn save_session(&mut self, path: String) {
+        let mut viewer_setting = self.setting.viewer_setting;
+        if let Some(backup) = &self.projector_backup {
+            viewer_setting.restore_display(backup);
+        }
+        let session = Session::capture(
+            &self.drive,
+            viewer_setting,
+            &self.commanded_foci,
+            self.clock.time(),
+        );
+        self.io_worker.submit("Save session", move || {
+            session
+                .save(&path)
+                .map_err(|e| format!("{}: {}", path, e))?;
+            tracing::info!("save session {}", path);
+            Ok(())
+        });
+    }
+
+    // The state received afterwards from the SDK is applied on top of the loaded session
+    fn load_session(&mut self, path: &str) -> UpdateFlag {
+        let mut session = match Session::load(path) {
+            Ok(session) => session,
+            Err(e) => {
+                tracing::error!("failed to load session {}: {}", path, e);
+                return UpdateFlag::empty();
+            }
+        };
+        tracing::info!(num_sources = session.sources.len(), "load session {}", path);
+        self.setting.viewer_setting = session.viewer_setting;
+        self.projector_backup = None;
+        self.commanded_foci = std::mem::take(&mut session.commanded_foci);
+        let clock_time = session.clock_time;
+        session.restore_drive(&mut self.drive);
+        self.placeholder = false;
+        self.ramp = None;
+        self.ramp_armed = false;
+        self.stm_duty = None;
+        self.collisions = None;
+        self.geometry_warnings.clear();
+        self.clock.reset();
+        self.clock.step(clock_time);
+        // the times of the previous history are of the previous clock
+        self.drive_history.reset(self.history_limits);
+        self.drive_history
+            .record_modulation(self.clock.time(), self.drive.modulation.clone());
+        self.record_drive();
+        self.coalescer.reset_stats();
+        self.reset_requested = true;
+        UpdateFlag::all()
     }
 
     fn reset(&mut self, render_sys: &mut RenderSystem) {
@@ -186,6 +1480,14 @@ impl App {
             .move_to(self.setting.viewer_setting.slice_pos);
         self.field_slice_viewer
             .rotate_to(self.setting.viewer_setting.slice_angle);
+        for (viewer, extra) in self
+            .extra_slice_viewers
+            .iter_mut()
+            .zip(&self.setting.extra_slices)
+        {
+            viewer.move_to(extra.pos4());
+            viewer.rotate_to(extra.angle);
+        }
 
         render_sys.camera.position = self.setting.viewer_setting.camera_pos;
         camera_helper::set_camera_angle(
@@ -196,557 +1498,667 @@ impl App {
         self.view_projection = render_sys.get_view_projection(&self.setting.viewer_setting);
     }
 
-    fn handle_autd(&mut self, autd_server: &mut AutdServer) -> UpdateFlag {
+    // Hash of the received frame and of the state derived from it, compared on replay
+    fn state_hash(&self, frame: &[u8]) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write(frame);
+        for source in &self.drive.sources {
+            hasher.write_f32(source.amp);
+            hasher.write_f32(source.phase);
+            hasher.write_f32(source.frequency.unwrap_or(0.0));
+        }
+        hasher.write(&[self.drive.ctrl_flag.bits()]);
+        for warning in &self.geometry_warnings {
+            hasher.write(warning.to_string().as_bytes());
+        }
+        for index in [self.drive.mod_index, self.drive.seq_index] {
+            // 0 for None
+            hasher.write(&index.map_or(0, |i| i as u64 + 1).to_le_bytes());
+        }
+        hasher.finish()
+    }
+
+    // The updates of the renderer are held in the coalescer
+    fn handle_autd(&mut self, autd_server: &mut AutdServer) {
         let mut update_flag = UpdateFlag::empty();
-        autd_server.update(|data| {
+        let mut drive_updates = 0;
+        autd_server.update_raw(|frame, data| {
             for d in data {
+                if let Some(journal) = self.log.journal() {
+                    journal.record(&d);
+                }
+                if matches!(
+                    d,
+                    AutdData::Geometries(_)
+                        | AutdData::Gain(_)
+                        | AutdData::Clear
+                        | AutdData::Pause
+                        | AutdData::Resume
+                        | AutdData::Cycles(_)
+                ) {
+                    drive_updates += 1;
+                }
                 match d {
                     AutdData::Geometries(geometries) => {
-                        self.sources.clear();
-                        for geometry in geometries {
-                            for trans in geometry.make_autd_transducers() {
-                                self.sources.push(trans);
-                            }
+                        let keep_drive = !std::mem::replace(&mut self.placeholder, false);
+                        self.ramp = None;
+                        let num_devices = geometries.len();
+                        self.geometry_warnings = Geometry::validate(&geometries);
+                        for warning in &self.geometry_warnings {
+                            tracing::warn!("{}", warning);
                         }
-                        self.log("geometry");
+                        self.drive.set_geometries(geometries, keep_drive);
+                        // the STM points are in the coordinates of device 0
+                        self.stm_duty = None;
+                        self.collisions = None;
+                        self.record_drive();
+                        tracing::info!(num_devices, "geometry");
                         update_flag |= UpdateFlag::INIT_SOURCE;
                         update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                     }
                     AutdData::Gain(gain) => {
-                        for ((&phase, &amp), source) in gain
-                            .phases
-                            .iter()
-                            .zip(gain.amps.iter())
-                            .zip(self.sources.iter_mut())
-                        {
-                            source.amp = (amp as f32 / 510.0 * std::f32::consts::PI).sin();
-                            source.phase = 2.0 * PI * (1.0 - (phase as f32 / 255.0));
+                        self.drive.set_gain(&gain);
+                        self.gain_index += 1;
+                        self.record_drive();
+                        self.adjust_drive();
+                        self.tags.on_gain(self.gain_index, self.clock.time());
+                        if self.setting.show_estimated_focus || self.setting.follow_focus {
+                            self.focus_estimator
+                                .request(self.drive.sources.clone(), self.setting.viewer_setting);
                         }
-                        self.log("gain");
+                        if std::mem::take(&mut self.ramp_armed) {
+                            self.start_ramp();
+                        } else {
+                            self.retarget_ramp();
+                        }
+                        tracing::debug!("gain");
                         update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                     }
                     AutdData::Clear => {
-                        for source in self.sources.iter_mut() {
-                            source.amp = 0.;
-                            source.phase = 0.;
-                        }
-                        self.modulation = None;
-                        self.sequence = None;
-                        self.delay_offset = None;
-                        self.log("clear");
+                        self.drive.clear();
+                        update_flag |= UpdateFlag::UPDATE_WAVENUM;
+                        self.drive_history
+                            .record_modulation(self.clock.time(), None);
+                        self.record_drive();
+                        self.stm_duty = None;
+                        self.commanded_foci.clear();
+                        self.collisions = None;
+                        self.ramp = None;
+                        self.ramp_armed = true;
+                        self.tags.clear_current();
+                        self.focus_estimator.cancel();
+                        self.estimated_focus = None;
+                        tracing::info!("clear");
                         update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                     }
                     AutdData::Pause => {
-                        self.last_amp.clear();
-                        for source in self.sources.iter_mut() {
-                            self.last_amp.push(source.amp);
-                            source.amp = 0.;
-                        }
-                        self.log("pause");
+                        // the amplitudes at the end of the ramp are restored on resume
+                        self.drive.pause(self.ramp.take().map(|ramp| ramp.amps));
+                        self.record_drive();
+                        tracing::info!("pause");
                         update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                     }
                     AutdData::Resume => {
-                        for (source, &amp) in self.sources.iter_mut().zip(self.last_amp.iter()) {
-                            source.amp = amp;
+                        let paused = self.drive.resume();
+                        self.record_drive();
+                        if paused {
+                            self.start_ramp();
                         }
-                        self.last_amp.clear();
-                        self.log("resume");
+                        tracing::debug!("resume");
                         update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                     }
                     AutdData::Modulation(m) => {
-                        self.modulation = Some(m);
-                        self.log("receive modulation");
+                        tracing::info!(
+                            size = m.mod_data.len(),
+                            div = m.mod_div,
+                            "receive modulation"
+                        );
+                        self.drive_history
+                            .record_modulation(self.clock.time(), Some(m.clone()));
+                        self.drive.modulation = Some(m);
                     }
                     AutdData::CtrlFlag(flag) => {
-                        self.ctrl_flag = flag;
+                        self.drive.ctrl_flag = flag;
                     }
                     AutdData::RequestFpgaVerMsb => {
-                        self.log("req fpga ver msb");
+                        tracing::info!("req fpga ver msb");
                     }
                     AutdData::RequestFpgaVerLsb => {
-                        self.log("req fpga ver lsb");
+                        tracing::info!("req fpga ver lsb");
                     }
                     AutdData::RequestCpuVerMsb => {
-                        self.log("req cpu ver lsb");
+                        tracing::info!("req cpu ver msb");
                     }
                     AutdData::RequestCpuVerLsb => {
-                        self.log("req cpu ver lsb");
+                        tracing::info!("req cpu ver lsb");
                     }
                     AutdData::Sequence(seq) => {
-                        self.sequence = Some(seq);
-                        self.log("receive sequence");
+                        tracing::info!(
+                            size = seq.seq_data.len(),
+                            div = seq.seq_div,
+                            "receive sequence"
+                        );
+                        self.drive.set_sequence(seq);
+                        self.stm_duty = None;
+                        self.collisions = None;
                     }
                     AutdData::DelayOffset(d) => {
-                        self.delay_offset = Some(d);
-                        self.log("receive delay offset");
+                        tracing::info!(size = d.delay_offset.len(), "receive delay offset");
+                        self.drive.delay_offset = Some(d);
+                    }
+                    AutdData::CommandedFoci(foci) => {
+                        tracing::debug!(size = foci.len(), "receive commanded foci");
+                        self.commanded_foci = foci;
+                        self.collisions = None;
+                    }
+                    AutdData::Handoff(time) => {
+                        tracing::info!("handoff received at {:.3} s", time);
+                        self.clock.set_time(time);
+                    }
+                    AutdData::Tags(tags) => {
+                        tracing::debug!("gain tags: {}", tags.join(", "));
+                        self.tags.tag(tags);
+                    }
+                    AutdData::Export(name) => {
+                        tracing::info!("export requested: {}", name);
+                        self.export_requested.push(name);
+                    }
+                    AutdData::Screenshot(name) => {
+                        tracing::info!("screenshot requested: {}", name);
+                        self.screenshot_requested.push(name);
+                    }
+                    AutdData::Cycles(cycles) => {
+                        self.drive.set_cycles(&cycles);
+                        self.record_drive();
+                        tracing::debug!(size = cycles.len(), "receive cycles");
+                        update_flag |= UpdateFlag::UPDATE_WAVENUM;
+                    }
+                    AutdData::ViewerSetting(json) => {
+                        match serde_json::from_slice::<ViewerSettings>(&json) {
+                            Ok(viewer_setting) => {
+                                tracing::info!("receive viewer setting");
+                                self.setting.viewer_setting = viewer_setting;
+                                self.reset_requested = true;
+                                update_flag |= UpdateFlag::all();
+                            }
+                            Err(e) => tracing::warn!("invalid viewer setting: {}", e),
+                        }
                     }
                 }
             }
+            if self.replay_check.is_some() {
+                update_flag |= self.update_playback();
+                let hash = self.state_hash(frame);
+                if let Some(check) = &mut self.replay_check {
+                    check.push(hash);
+                }
+            }
         });
-        update_flag
+        if let Some(journal) = self.log.journal() {
+            journal.flush();
+        }
+        self.coalescer
+            .push(update_flag, drive_updates, Instant::now());
+    }
+
+    // Rebuilds the pipelines whenever a file in assets/shaders changes. On error the previous
+    // pipelines are kept and the error is shown over the view until the shaders are fixed.
+    fn update_shaders(&mut self, render_sys: &mut RenderSystem) {
+        match (&mut self.shader_watcher, self.setting.shader_hot_reload) {
+            (None, true) => {
+                self.shader_watcher = ShaderWatcher::new();
+                if self.shader_watcher.is_none() {
+                    tracing::error!("cannot hot-reload shaders: assets folder is not found");
+                    self.setting.shader_hot_reload = false;
+                }
+            }
+            (Some(_), false) => {
+                self.shader_watcher = None;
+                self.shader_errors.clear();
+            }
+            (Some(watcher), true) => {
+                if !watcher.poll() {
+                    return;
+                }
+                let factory = &mut render_sys.factory;
+                // the same shaders as the main slice, whose errors are reported
+                for viewer in self.extra_slice_viewers.iter_mut() {
+                    let _ = viewer.reload_shaders(factory, OPENGL);
+                }
+                self.shader_errors = vec![
+                    self.sound_source_viewer.reload_shaders(factory, OPENGL),
+                    self.field_slice_viewer.reload_shaders(factory, OPENGL),
+                    self.color_bar_viewer.reload_shaders(factory, OPENGL),
+                    self.grid_viewer.reload_shaders(factory, OPENGL),
+                    self.volume_viewer.reload_shaders(factory, OPENGL),
+                    self.isosurface_viewer.reload_shaders(factory, OPENGL),
+                ]
+                .into_iter()
+                .filter_map(Result::err)
+                .collect();
+                for e in &self.shader_errors {
+                    tracing::error!("failed to reload shader: {}", e);
+                }
+                if self.shader_errors.is_empty() {
+                    tracing::info!("shaders reloaded");
+                }
+            }
+            (None, false) => (),
+        }
     }
 
-    fn handle_event(&mut self, render_sys: &mut RenderSystem, event: &Event<()>) {
+    fn handle_event(
+        &mut self,
+        render_sys: &mut RenderSystem,
+        event: &Event<()>,
+        want_capture_keyboard: bool,
+        want_capture_mouse: bool,
+    ) {
         if self.init {
             self.update_view(render_sys, UpdateFlag::all());
             self.init = false;
         }
-        self.sound_source_viewer.handle_event(&render_sys, event);
-        self.field_slice_viewer.handle_event(&render_sys, event);
+        self.sound_source_viewer.handle_event(render_sys, event);
+        self.field_slice_viewer.handle_event(render_sys, event);
+        for viewer in self.extra_slice_viewers.iter_mut() {
+            viewer.handle_event(render_sys, event);
+        }
+        self.color_bar_viewer.handle_event(render_sys, event);
+        self.grid_viewer.handle_event(render_sys, event);
+        self.volume_viewer.handle_event(render_sys, event);
+        self.isosurface_viewer.handle_event(render_sys, event);
+
+        if let Event::WindowEvent { event, .. } = event {
+            match event {
+                WindowEvent::ModifiersChanged(modifiers) => self.modifiers = *modifiers,
+                WindowEvent::CursorMoved { position, .. } => {
+                    self.cursor = Some((position.x, position.y));
+                    self.hover_requested = !want_capture_mouse;
+                    if want_capture_mouse {
+                        self.hovered = None;
+                    }
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    self.cursor = None;
+                    self.hovered = None;
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                } if !want_capture_mouse => self.pick_requested = true,
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(key),
+                            ..
+                        },
+                    ..
+                } if !want_capture_keyboard => {
+                    let flag = self.handle_key(render_sys, *key);
+                    self.key_update_flag |= flag;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // WASD/QE translate and the arrow keys rotate the camera, or the slice while Ctrl is held.
+    // Holding Shift switches to the fine step sizes. P toggles the projector preset, Space pauses
+    // the clock and Period steps it. I takes a screenshot and V starts or stops the video.
+    fn handle_key(&mut self, render_sys: &mut RenderSystem, key: VirtualKeyCode) -> UpdateFlag {
+        match key {
+            VirtualKeyCode::P => return self.toggle_projector(),
+            VirtualKeyCode::I => {
+                self.screenshot_requested.push(String::new());
+                return UpdateFlag::empty();
+            }
+            VirtualKeyCode::V => {
+                self.video_requested = true;
+                return UpdateFlag::empty();
+            }
+            VirtualKeyCode::Space => {
+                self.clock.set_paused(!self.clock.is_paused());
+                return UpdateFlag::empty();
+            }
+            VirtualKeyCode::Period => {
+                self.step_clock();
+                return UpdateFlag::empty();
+            }
+            _ => (),
+        }
+        if let Some(i) = EXPORT_HOTKEYS.iter().position(|&k| k == key) {
+            if let Some(preset) = self.setting.export_presets.get(i) {
+                self.export_requested.push(preset.name.clone());
+            }
+            return UpdateFlag::empty();
+        }
+        if let Some(i) = BOOKMARK_HOTKEYS.iter().position(|&k| k == key) {
+            self.camera
+                .recall(&self.setting, render_sys, &mut self.view_controller, i);
+            return UpdateFlag::empty();
+        }
+
+        let (move_step, rotate_step) = if self.modifiers.shift() {
+            (self.setting.move_step_fine, self.setting.rotate_step_fine)
+        } else {
+            (self.setting.move_step, self.setting.rotate_step)
+        };
+
+        let translation = match key {
+            VirtualKeyCode::D => Some([move_step, 0., 0.]),
+            VirtualKeyCode::A => Some([-move_step, 0., 0.]),
+            VirtualKeyCode::E => Some([0., move_step, 0.]),
+            VirtualKeyCode::Q => Some([0., -move_step, 0.]),
+            VirtualKeyCode::S => Some([0., 0., move_step]),
+            VirtualKeyCode::W => Some([0., 0., -move_step]),
+            _ => None,
+        };
+        let rotation = match key {
+            VirtualKeyCode::Up => Some([rotate_step, 0., 0.]),
+            VirtualKeyCode::Down => Some([-rotate_step, 0., 0.]),
+            VirtualKeyCode::Left => Some([0., 0., rotate_step]),
+            VirtualKeyCode::Right => Some([0., 0., -rotate_step]),
+            _ => None,
+        };
+
+        let viewer_setting = &mut self.setting.viewer_setting;
+        if self.modifiers.ctrl() {
+            if let Some(t) = translation {
+                let model = self.field_slice_viewer.model();
+                let mut pos = vecmath_util::to_vec3(&viewer_setting.slice_pos);
+                for (axis, &d) in model.iter().zip(t.iter()) {
+                    pos =
+                        vecmath::vec3_add(pos, vecmath::vec3_scale(vecmath_util::to_vec3(axis), d));
+                }
+                viewer_setting.slice_pos = [pos[0], pos[1], pos[2], viewer_setting.slice_pos[3]];
+                self.field_slice_viewer.move_to(viewer_setting.slice_pos);
+                return UpdateFlag::UPDATE_SLICE_POS;
+            }
+            if let Some(r) = rotation {
+                viewer_setting.slice_angle = vecmath::vec3_add(viewer_setting.slice_angle, r);
+                self.field_slice_viewer
+                    .rotate_to(viewer_setting.slice_angle);
+                return UpdateFlag::UPDATE_SLICE_POS;
+            }
+        } else {
+            if let Some(t) = translation {
+                let camera = &mut render_sys.camera;
+                let axes = [camera.right, camera.up, camera.forward];
+                for (&axis, &d) in axes.iter().zip(t.iter()) {
+                    camera.position =
+                        vecmath::vec3_add(camera.position, vecmath::vec3_scale(axis, d));
+                }
+                viewer_setting.camera_pos = camera.position;
+                self.view_projection = render_sys.get_view_projection(viewer_setting);
+                return UpdateFlag::UPDATE_CAMERA_POS;
+            }
+            if let Some(r) = rotation {
+                viewer_setting.camera_angle = vecmath::vec3_add(viewer_setting.camera_angle, r);
+                camera_helper::set_camera_angle(
+                    &mut render_sys.camera,
+                    viewer_setting.camera_angle,
+                );
+                self.view_projection = render_sys.get_view_projection(viewer_setting);
+                return UpdateFlag::UPDATE_CAMERA_POS;
+            }
+        }
+        UpdateFlag::empty()
+    }
+
+    // Sets the color scale to the maximum amplitude `amp` computed in the background, unless the
+    // auto scale is turned off meanwhile
+    fn update_auto_scale(&mut self, amp: f32) -> UpdateFlag {
+        // the previous scale is kept while nothing is driven
+        if !self.setting.auto_scale || amp <= 0.0 || amp == self.setting.viewer_setting.color_scale
+        {
+            return UpdateFlag::empty();
+        }
+        self.setting.viewer_setting.color_scale = amp;
+        UpdateFlag::UPDATE_COLOR_MAP
+    }
+
+    // Spacing of the arrows, widened so that at most MAX_QUIVER_ARROWS are on the slice
+    fn quiver_spacing(&self) -> f32 {
+        let settings = &self.setting.viewer_setting;
+        let area = settings.slice_width as f32 * settings.slice_height as f32;
+        let min_spacing = (area / MAX_QUIVER_ARROWS as f32).sqrt();
+        self.setting.force_quiver_spacing.max(min_spacing)
+    }
+
+    fn update_quiver(&mut self) {
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings: &self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: self.scattering.as_ref(),
+            device_sizes: &self.drive.device_sizes,
+        };
+        let (grid, forces) = field::compute_slice_force(
+            &model,
+            self.field_slice_viewer.model(),
+            self.quiver_spacing(),
+        );
+        self.quiver = grid.points.into_iter().zip(forces).collect();
     }
 
     fn update_view(&mut self, render_sys: &mut RenderSystem, update_flag: UpdateFlag) {
+        if update_flag.contains(UpdateFlag::UPDATE_COLOR_MAP) {
+            let imported = self.field_slice_viewer.imported_color_map();
+            let colors = self.setting.viewer_setting.color_map.colors(imported);
+            self.sound_source_viewer.set_color_map(&colors);
+        }
+        if update_flag.contains(UpdateFlag::UPDATE_CAMERA_POS) {
+            self.grid_viewer.update(self.view_projection);
+        }
+        if update_flag.contains(UpdateFlag::INIT_SOURCE) {
+            self.field_slice_viewer
+                .set_device_sizes(&self.drive.device_sizes);
+            for viewer in self.extra_slice_viewers.iter_mut() {
+                viewer.set_device_sizes(&self.drive.device_sizes);
+            }
+        }
         self.sound_source_viewer.update(
             render_sys,
             self.view_projection,
             &self.setting.viewer_setting,
-            &self.sources,
+            &self.drive.sources,
             update_flag,
         );
         self.field_slice_viewer.update(
             render_sys,
             self.view_projection,
             &self.setting.viewer_setting,
-            &self.sources,
+            &self.drive.sources,
             update_flag,
         );
+        // the disabled ones are also updated, so that they are up to date when enabled
+        for viewer in self.extra_slice_viewers.iter_mut() {
+            viewer.update(
+                render_sys,
+                self.view_projection,
+                &self.setting.viewer_setting,
+                &self.drive.sources,
+                update_flag,
+            );
+        }
+        if update_flag.contains(UpdateFlag::UPDATE_COLOR_MAP) {
+            let (title, ticks) = figure::color_bar_labels(&self.setting.viewer_setting);
+            let legend = ColorBarLegend {
+                title,
+                ticks,
+                colors: self.field_slice_viewer.color_map().to_vec(),
+            };
+            self.color_bar_viewer.update(render_sys, legend);
+        }
+        self.update_cpu_slice(render_sys, update_flag);
+        self.update_volume(render_sys, update_flag);
     }
 
-    fn update_ui(&mut self, ui: &Ui, render_sys: &mut RenderSystem) -> UpdateFlag {
-        let mut update_flag = UpdateFlag::empty();
-        Window::new(im_str!("Controller")).build(ui, || {
-            TabBar::new(im_str!("Settings")).build(&ui, || {
-                TabItem::new(im_str!("Slice")).build(&ui, || {
-                    ui.text(im_str!("Slice size"));
-                    if Slider::new(im_str!("Slice width"))
-                        .range(0..=1000)
-                        .build(&ui, &mut self.setting.viewer_setting.slice_width)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
-                    }
-                    if Slider::new(im_str!("Slice heigh"))
-                        .range(0..=1000)
-                        .build(&ui, &mut self.setting.viewer_setting.slice_height)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
-                    }
-
-                    ui.separator();
-                    ui.text(im_str!("Slice position"));
-                    if Drag::new(im_str!("Slice X"))
-                        .build(&ui, &mut self.setting.viewer_setting.slice_pos[0])
-                    {
-                        self.field_slice_viewer
-                            .move_to(self.setting.viewer_setting.slice_pos);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if Drag::new(im_str!("Slice Y"))
-                        .build(&ui, &mut self.setting.viewer_setting.slice_pos[1])
-                    {
-                        self.field_slice_viewer
-                            .move_to(self.setting.viewer_setting.slice_pos);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if Drag::new(im_str!("Slice Z"))
-                        .build(&ui, &mut self.setting.viewer_setting.slice_pos[2])
-                    {
-                        self.field_slice_viewer
-                            .move_to(self.setting.viewer_setting.slice_pos);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-
-                    ui.separator();
-                    ui.text(im_str!("Slice Rotation"));
-                    if AngleSlider::new(im_str!("Slice RX"))
-                        .range_degrees(0.0..=360.0)
-                        .build(&ui, &mut self.setting.viewer_setting.slice_angle[0])
-                    {
-                        self.field_slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if AngleSlider::new(im_str!("Slice RY"))
-                        .range_degrees(0.0..=360.0)
-                        .build(&ui, &mut self.setting.viewer_setting.slice_angle[1])
-                    {
-                        self.field_slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if AngleSlider::new(im_str!("Slice RZ"))
-                        .range_degrees(0.0..=360.0)
-                        .build(&ui, &mut self.setting.viewer_setting.slice_angle[2])
-                    {
-                        self.field_slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-
-                    ui.separator();
-                    ui.text(im_str!("Slice color setting"));
-                    if Drag::new(im_str!("Color scale"))
-                        .speed(0.1)
-                        .range(0.0..=f32::INFINITY)
-                        .build(&ui, &mut self.setting.viewer_setting.color_scale)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
-                    }
-                    if Slider::new(im_str!("Slice alpha"))
-                        .range(0.0..=1.0)
-                        .build(&ui, &mut self.setting.viewer_setting.slice_alpha)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
-                    }
+    // Computes the main slice on the CPU again on the changes, and keeps showing the last one or
+    // the shader meanwhile
+    fn update_cpu_slice(&mut self, render_sys: &mut RenderSystem, update_flag: UpdateFlag) {
+        let resolution = match self.field_slice_viewer.sampling() {
+            Some((resolution, _)) if self.setting.cpu_slice => resolution,
+            _ => {
+                if self.cpu_slice_wt.take().is_some() {
+                    self.cpu_slice.cancel();
+                    self.field_slice_viewer.clear_sampled_colors();
+                }
+                return;
+            }
+        };
+        let wt = match self.setting.viewer_setting.field_quantity {
+            FieldQuantity::RealPart => self.wave_phase,
+            _ => 0.0,
+        };
+        if update_flag.intersects(CPU_SLICE_UPDATE) || self.cpu_slice_wt != Some(wt) {
+            let reference = self
+                .reference
+                .as_ref()
+                .map(|r| (r.sources(&self.drive.sources), r.scattering.clone()));
+            let mut settings = self.setting.viewer_setting;
+            if reference.is_none() {
+                settings.compare = CompareView::Current;
+            }
+            self.cpu_slice.request(SliceRequest {
+                sources: self.drive.sources.clone(),
+                device_sizes: self.drive.device_sizes.clone(),
+                settings,
+                directivity_table: self.field_slice_viewer.directivity_table().cloned(),
+                scattering: self.scattering.clone(),
+                model: self.field_slice_viewer.model(),
+                resolution,
+                wt,
+                color_map: self.field_slice_viewer.color_map().to_vec(),
+                reference,
+            });
+            self.cpu_slice_wt = Some(wt);
+        }
+        if let Some(colors) = self.cpu_slice.poll() {
+            // the colors of the last grid are dropped, and the next ones are on the new grid
+            self.field_slice_viewer
+                .set_sampled_colors(&mut render_sys.factory, &colors);
+        }
+    }
 
-                    ui.separator();
-                    if ui.small_button(im_str!("xy")) {
-                        self.setting.viewer_setting.slice_angle = [0., 0., 0.];
-                        self.field_slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    ui.same_line(0.);
-                    if ui.small_button(im_str!("yz")) {
-                        self.setting.viewer_setting.slice_angle = [0., -PI / 2., 0.];
-                        self.field_slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    ui.same_line(0.);
-                    if ui.small_button(im_str!("zx")) {
-                        self.setting.viewer_setting.slice_angle = [PI / 2., 0., 0.];
-                        self.field_slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
+    // Samples the volume again on the changes of the drive or the box, and keeps showing the last
+    // grid meanwhile. The grid is also the source of the isosurface.
+    fn update_volume(&mut self, render_sys: &mut RenderSystem, update_flag: UpdateFlag) {
+        if update_flag.contains(UpdateFlag::UPDATE_COLOR_MAP) {
+            self.volume_viewer
+                .set_color_map(&mut render_sys.factory, self.field_slice_viewer.color_map());
+        }
+        if update_flag.intersects(VOLUME_UPDATE) {
+            self.volume_sampled = None;
+        }
+        if !self.setting.show_volume && !self.setting.show_isosurface {
+            return;
+        }
+        let volume = self.setting.volume;
+        if !self
+            .volume_sampled
+            .is_some_and(|sampled| sampled.same_grid(&volume))
+        {
+            self.volume_sampler.request(
+                self.drive.sources.clone(),
+                self.drive.device_sizes.clone(),
+                self.setting.viewer_setting,
+                self.field_slice_viewer.directivity_table().cloned(),
+                self.scattering.clone(),
+                volume,
+            );
+            self.volume_sampled = Some(volume);
+        }
+        if let Some(grid) = self.volume_sampler.poll() {
+            self.volume_viewer.set_grid(&mut render_sys.factory, &grid);
+            self.volume_grid = Some(grid);
+            self.isosurface_level = None;
+        }
+        self.volume_viewer
+            .update(self.view_projection, &self.setting.viewer_setting, &volume);
+        if self.setting.show_isosurface {
+            self.update_isosurface(render_sys);
+        }
+    }
 
-                    #[cfg(feature = "offscreen_renderer")]
-                    {
-                        ui.separator();
-                        ui.text(im_str!("Save as file"));
-                        if ui.radio_button_bool(
-                            im_str!("save enable"),
-                            self.setting.save_file_enable,
-                        ) {
-                            self.setting.save_file_enable = !self.setting.save_file_enable;
-                        }
-                        if self.setting.save_file_enable {
-                            InputText::new(ui, im_str!("save path"), &mut self.save_path).build();
-                            if ui.small_button(im_str!("save")) {
-                                self.offscreen_renderer
-                                    .calculate_field(&self.sources, &self.setting.viewer_setting);
-                                let bb = (
-                                    self.setting.viewer_setting.slice_width as usize,
-                                    self.setting.viewer_setting.slice_height as usize,
-                                );
-                                self.offscreen_renderer.save(
-                                    self.save_path.to_str(),
-                                    bb,
-                                    self.field_slice_viewer.color_map(),
-                                );
-                            }
+    // Extracted on the main thread, which takes a few ms for the default grid
+    fn update_isosurface(&mut self, render_sys: &mut RenderSystem) {
+        let settings = &self.setting.viewer_setting;
+        let level = settings.calibration.from_pa(self.setting.isosurface_level);
+        if let Some(grid) = &self.volume_grid {
+            if self.isosurface_level != Some(level) {
+                let mesh = isosurface::extract(grid, level);
+                self.isosurface_viewer
+                    .set_mesh(&mut render_sys.factory, &mesh);
+                self.isosurface = Some(mesh);
+                self.isosurface_level = Some(level);
+            }
+        }
+        // in the color of the level on the color map of the amplitude
+        let mut amplitude = *settings;
+        amplitude.field_quantity = FieldQuantity::Amplitude;
+        let colors = self.field_slice_viewer.color_map();
+        let t = field::color_position(level, &amplitude);
+        let c = colors[((t * colors.len() as f32) as usize).min(colors.len() - 1)];
+        self.isosurface_viewer.update(
+            self.view_projection,
+            [
+                c.r as f32,
+                c.g as f32,
+                c.b as f32,
+                self.setting.isosurface_opacity,
+            ],
+        );
+    }
 
-                            ui.separator();
-                            InputText::new(ui, im_str!("record path"), &mut self.record_path)
-                                .build();
-                            if ui.small_button(if self.recording {
-                                im_str!("stop recording")
-                            } else {
-                                im_str!("record")
-                            }) {
-                                self.recording = !self.recording;
-                            }
-                            if self.recording {
-                                self.offscreen_renderer
-                                    .calculate_field(&self.sources, &self.setting.viewer_setting);
-                                let bb = (
-                                    self.setting.viewer_setting.slice_width as usize,
-                                    self.setting.viewer_setting.slice_height as usize,
-                                );
-                                std::fs::create_dir_all(self.record_path.to_str()).unwrap();
-                                let date = chrono::Local::now();
-                                let path = Path::new(self.record_path.to_str())
-                                    .join(format!("{}", date.format("%Y-%m-%d_%H-%M-%S_%3f.png")));
-                                self.offscreen_renderer.save(
-                                    &path,
-                                    bb,
-                                    self.field_slice_viewer.color_map(),
-                                );
-                            }
-                        }
-                    }
+    fn update_ui(&mut self, ui: &Ui, render_sys: &mut RenderSystem) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        Window::new(im_str!("Controller")).build(ui, || {
+            TabBar::new(im_str!("Settings")).build(ui, || {
+                TabItem::new(im_str!("Slice")).build(ui, || {
+                    update_flag |= self.slice_tab(ui);
                 });
-                TabItem::new(im_str!("Camera")).build(&ui, || {
-                    ui.text(im_str!("Camera pos"));
-                    if Drag::new(im_str!("Camera X"))
-                        .build(&ui, &mut self.setting.viewer_setting.camera_pos[0])
-                    {
-                        render_sys.camera.position = self.setting.viewer_setting.camera_pos;
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-                    if Drag::new(im_str!("Camera Y"))
-                        .build(&ui, &mut self.setting.viewer_setting.camera_pos[1])
-                    {
-                        render_sys.camera.position = self.setting.viewer_setting.camera_pos;
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-                    if Drag::new(im_str!("Camera Z"))
-                        .build(&ui, &mut self.setting.viewer_setting.camera_pos[2])
-                    {
-                        render_sys.camera.position = self.setting.viewer_setting.camera_pos;
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-
-                    ui.separator();
-                    ui.text(im_str!("Camera rotation"));
-                    if AngleSlider::new(im_str!("Camera RX"))
-                        .range_degrees(-180.0..=180.0)
-                        .build(&ui, &mut self.setting.viewer_setting.camera_angle[0])
-                    {
-                        camera_helper::set_camera_angle(
-                            &mut render_sys.camera,
-                            self.setting.viewer_setting.camera_angle,
-                        );
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-                    if AngleSlider::new(im_str!("Camera RY"))
-                        .range_degrees(-180.0..=180.0)
-                        .build(&ui, &mut self.setting.viewer_setting.camera_angle[1])
-                    {
-                        camera_helper::set_camera_angle(
-                            &mut render_sys.camera,
-                            self.setting.viewer_setting.camera_angle,
-                        );
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-                    if AngleSlider::new(im_str!("Camera RZ"))
-                        .range_degrees(-180.0..=180.0)
-                        .build(&ui, &mut self.setting.viewer_setting.camera_angle[2])
-                    {
-                        camera_helper::set_camera_angle(
-                            &mut render_sys.camera,
-                            self.setting.viewer_setting.camera_angle,
-                        );
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-
-                    ui.separator();
-                    ui.text(im_str!("Camera perspective"));
-                    if AngleSlider::new(im_str!("FOV"))
-                        .range_degrees(0.0..=180.0)
-                        .build(&ui, &mut self.setting.viewer_setting.fov)
-                    {
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-                    if Drag::new(im_str!("Near clip"))
-                        .range(0.0..=f32::INFINITY)
-                        .build(&ui, &mut self.setting.viewer_setting.near_clip)
-                    {
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
-                    if Drag::new(im_str!("Far clip"))
-                        .range(0.0..=f32::INFINITY)
-                        .build(&ui, &mut self.setting.viewer_setting.far_clip)
-                    {
-                        self.view_projection =
-                            render_sys.get_view_projection(&self.setting.viewer_setting);
-                        update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
-                    }
+                TabItem::new(im_str!("Camera")).build(ui, || {
+                    update_flag |= self.camera_tab(ui, render_sys);
                 });
-                TabItem::new(im_str!("Config")).build(&ui, || {
-                    if Drag::new(im_str!("Wavelength"))
-                        .speed(0.1)
-                        .range(0.0..=f32::INFINITY)
-                        .build(&ui, &mut self.setting.viewer_setting.wave_length)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_WAVENUM;
-                    }
-                    ui.separator();
-                    if Slider::new(im_str!("Transducer alpha"))
-                        .range(0.0..=1.0)
-                        .build(&ui, &mut self.setting.viewer_setting.source_alpha)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_SOURCE_ALPHA;
-                    }
-                    ui.separator();
-                    ColorPicker::new(
-                        im_str!("Background"),
-                        &mut self.setting.viewer_setting.background,
-                    )
-                    .alpha(true)
-                    .build(&ui);
+                TabItem::new(im_str!("Config")).build(ui, || {
+                    update_flag |= self.config_tab(ui);
                 });
-                TabItem::new(im_str!("Info")).build(&ui, || {
-                    ui.text("Control flag");
-                    let mut flag = self.ctrl_flag;
-                    ui.checkbox_flags(
-                        im_str!("MOD BEGIN"),
-                        &mut flag,
-                        RxGlobalControlFlags::MOD_BEGIN,
-                    );
-                    ui.checkbox_flags(im_str!("MOD END"), &mut flag, RxGlobalControlFlags::MOD_END);
-                    ui.checkbox_flags(
-                        im_str!("MOD END"),
-                        &mut flag,
-                        RxGlobalControlFlags::READ_FPGA_INFO,
-                    );
-                    ui.checkbox_flags(im_str!("SILENT"), &mut flag, RxGlobalControlFlags::SILENT);
-                    ui.checkbox_flags(
-                        im_str!("FORCE FAN"),
-                        &mut flag,
-                        RxGlobalControlFlags::FORCE_FAN,
-                    );
-                    ui.checkbox_flags(
-                        im_str!("SEQ MODE"),
-                        &mut flag,
-                        RxGlobalControlFlags::SEQ_MODE,
-                    );
-                    ui.checkbox_flags(
-                        im_str!("SEQ BEGIN"),
-                        &mut flag,
-                        RxGlobalControlFlags::SEQ_BEGIN,
-                    );
-                    ui.checkbox_flags(im_str!("SEQ END"), &mut flag, RxGlobalControlFlags::SEQ_END);
-
-                    if let Some(m) = &self.modulation {
-                        ui.separator();
-                        ui.text("Modulation");
-                        ui.text(format!("Modulation size: {}", m.mod_data.len()));
-                        ui.text(format!("Modulation division: {}", m.mod_div));
-                        let smpl_period =
-                            (1000000.0 / MOD_SAMPLING_FREQ_BASE) as usize * m.mod_div as usize;
-                        ui.text(format!("Modulation sampling period: {} [us]", smpl_period));
-                        ui.text(format!(
-                            "Modulation period: {} [us]",
-                            smpl_period * m.mod_data.len()
-                        ));
-                        if !m.mod_data.is_empty() {
-                            ui.text(format!("mod[0]: {}", m.mod_data[0]));
-                        }
-                        if m.mod_data.len() == 2 || m.mod_data.len() == 3 {
-                            ui.text(format!("mod[1]: {}", m.mod_data[1]));
-                        } else if m.mod_data.len() > 3 {
-                            ui.text("...");
-                        }
-                        if m.mod_data.len() >= 3 {
-                            let idx = m.mod_data.len() - 1;
-                            ui.text(format!("mod[{}]: {}", idx, m.mod_data[idx]));
-                        }
-
-                        if ui
-                            .radio_button_bool(im_str!("show mod plot"), self.setting.show_mod_plot)
-                        {
-                            self.setting.show_mod_plot = !self.setting.show_mod_plot;
-                        }
-
-                        if self.setting.show_mod_plot {
-                            ui.separator();
-                            let mod_v = self.mod_values(|&v| ((v as f32) / 512.0 * PI).sin());
-                            PlotLines::new(ui, im_str!("mod plot"), &mod_v)
-                                .graph_size(self.setting.mod_plot_size)
-                                .build();
-                            if ui.radio_button_bool(
-                                im_str!("show mod plot (raw)"),
-                                self.setting.show_mod_plot_raw,
-                            ) {
-                                self.setting.show_mod_plot_raw = !self.setting.show_mod_plot_raw;
-                            }
-                            if self.setting.show_mod_plot_raw {
-                                ui.separator();
-                                let mod_v = self.mod_values(|&v| v as f32);
-                                PlotLines::new(ui, im_str!("mod plot (raw)"), &mod_v)
-                                    .graph_size(self.setting.mod_plot_size)
-                                    .build();
-                            }
-
-                            Drag::new(im_str!("plot size"))
-                                .range(0.0..=f32::INFINITY)
-                                .build_array(ui, &mut self.setting.mod_plot_size);
-                        }
-                    }
-
-                    if self.ctrl_flag.contains(RxGlobalControlFlags::SEQ_MODE) {
-                        ui.separator();
-                        ui.text("Sequence mode");
-                        if let Some(seq) = &self.sequence {
-                            ui.text(format!("Sequence size: {}", seq.seq_data.len()));
-                            ui.text(format!("Sequence division: {}", seq.seq_div));
-                            let smpl_period =
-                                (1000000 / POINT_SEQ_BASE_FREQ) * seq.seq_div as usize;
-                            ui.text(format!("Sequence sampling period: {} [us]", smpl_period));
-                            ui.text(format!(
-                                "Sequence period: {} [us]",
-                                smpl_period * seq.seq_data.len()
-                            ));
-                            if !seq.seq_data.is_empty() {
-                                ui.text(format!(
-                                    "seq[0]: {:?} / {}",
-                                    seq.seq_data[0].0, seq.seq_data[0].1
-                                ));
-                            }
-                            if seq.seq_data.len() == 2 || seq.seq_data.len() == 3 {
-                                ui.text(format!(
-                                    "seq[1]: {:?} / {}",
-                                    seq.seq_data[1].0, seq.seq_data[1].1
-                                ));
-                            } else if seq.seq_data.len() > 3 {
-                                ui.text("...");
-                            }
-                            if seq.seq_data.len() >= 3 {
-                                let idx = seq.seq_data.len() - 1;
-                                ui.text(format!(
-                                    "seq[{}]: {:?} / {}",
-                                    idx, seq.seq_data[idx].0, seq.seq_data[idx].1
-                                ));
-                            }
-                        }
-                    }
-
-                    if let Some(d) = &self.delay_offset {
-                        ui.separator();
-                        ui.text("Duty offset and Delay");
-                        ui.text(format!(
-                            "offset[0]: {}, delay[0]: {}",
-                            d.delay_offset[0].1, d.delay_offset[0].0
-                        ));
-                        ui.text("...");
-                        let idx = d.delay_offset.len() - 1;
-                        ui.text(format!(
-                            "offset[{0}]: {1}, delay[{0}]: {2}",
-                            idx, d.delay_offset[idx].1, d.delay_offset[idx].0
-                        ));
-                    }
+                TabItem::new(im_str!("Info")).build(ui, || {
+                    update_flag |= self.info_tab(ui);
                 });
-                TabItem::new(im_str!("Log")).build(&ui, || {
-                    if ui.radio_button_bool(im_str!("enable"), self.setting.log_enable) {
-                        self.setting.log_enable = !self.setting.log_enable;
-                    }
-                    if self.setting.log_enable {
-                        Slider::new(im_str!("Max"))
-                            .range(0..=1000)
-                            .build(&ui, &mut self.setting.log_max);
-
-                        ui.text(self.get_log_txt());
-                    }
+                TabItem::new(im_str!("Log")).build(ui, || {
+                    self.log.ui(ui, &mut self.setting);
+                });
+                TabItem::new(im_str!("Particles")).build(ui, || {
+                    let slice_center = vecmath_util::to_vec3(&self.field_slice_viewer.model()[3]);
+                    self.particles.ui(ui, &mut self.setting, slice_center);
+                });
+                TabItem::new(im_str!("Analysis")).build(ui, || {
+                    self.analysis_tab(ui);
+                });
+                TabItem::new(im_str!("Export")).build(ui, || {
+                    self.export_tab(ui);
+                });
+                TabItem::new(im_str!("Console")).build(ui, || {
+                    self.console
+                        .ui(ui, &self.drive.sources, &self.setting.viewer_setting);
                 });
             });
 
+            if !self.geometry_warnings.is_empty() {
+                ui.separator();
+                ui.text_colored(
+                    [1.0, 0.8, 0.0, 1.0],
+                    format!(
+                        "Geometry has {} warning(s), see Info tab",
+                        self.geometry_warnings.len()
+                    ),
+                );
+            }
+
             ui.separator();
 
             if ui.small_button(im_str!("auto")) {
@@ -790,67 +2202,3756 @@ impl App {
 
             ui.same_line(0.);
             if ui.small_button(im_str!("reset")) {
-                self.setting = Setting::load("setting.json");
-                self.reset(render_sys);
-                update_flag = UpdateFlag::all();
+                match Setting::load(SETTING_PATH) {
+                    Ok(setting) => {
+                        self.setting = setting;
+                        self.log.apply(&self.setting);
+                        let settings = &self.setting.viewer_setting;
+                        self.air_before_drift = (settings.temperature, settings.medium.sound_speed);
+                        self.projector_backup = None;
+                        self.reset(render_sys);
+                        update_flag = UpdateFlag::all();
+                    }
+                    Err(e) => tracing::error!("failed to load {}: {}", SETTING_PATH, e),
+                }
             }
 
             ui.same_line(0.);
             if ui.small_button(im_str!("default")) {
                 let default_setting = acoustic_field_viewer::view::ViewerSettings {
+                    frequency: self.setting.viewer_setting.frequency,
                     wave_length: self.setting.viewer_setting.wave_length,
+                    temperature: self.setting.viewer_setting.temperature,
+                    medium: self.setting.viewer_setting.medium,
                     ..Default::default()
                 };
                 self.setting.viewer_setting = default_setting;
+                self.projector_backup = None;
                 self.reset(render_sys);
                 update_flag = UpdateFlag::all();
             }
         });
 
-        update_flag
-    }
+        if self.setting.show_steering_limit {
+            self.update_steering_limit();
+            self.draw_steering_limit(ui);
+        }
 
-    fn mod_values<F>(&self, f: F) -> Vec<f32>
-    where
-        F: Fn(&u8) -> f32,
-    {
-        if let Some(m) = &self.modulation {
-            m.mod_data.iter().map(f).collect()
-        } else {
-            vec![]
+        if self.setting.show_commanded_foci {
+            self.draw_commanded_foci(ui);
         }
-    }
 
-    // TODO: This log system is not so efficient
-    fn log(&mut self, msg: &str) {
-        if self.setting.log_enable {
-            let date = chrono::Local::now();
-            self.log_buf
-                .push_back(format!("{}: {}", date.format("%Y-%m-%d %H:%M:%S.%3f"), msg));
-            while self.log_buf.len() > self.setting.log_max as usize {
-                self.log_buf.pop_front();
+        update_flag |= self.update_collisions();
+        if self.setting.show_fixtures {
+            self.draw_fixtures(ui);
+        }
+        if self.setting.viewer_setting.reflector.enabled {
+            self.draw_reflector(ui);
+        }
+        self.draw_obstacles(ui);
+        self.draw_collisions(ui);
+
+        if self.setting.show_estimated_focus || self.setting.follow_focus {
+            if let Some(estimate) = self.focus_estimator.poll() {
+                self.estimated_focus = estimate;
             }
         }
-    }
+        if self.setting.show_estimated_focus {
+            self.draw_estimated_focus(ui);
+        }
+
+        if self.placeholder {
+            self.draw_waiting(ui);
+        }
+
+        if self.setting.show_devices {
+            self.draw_devices(ui);
+        }
+
+        if self.setting.show_source_tooltip {
+            self.draw_source_tooltip(ui);
+        }
+
+        if self.setting.show_axes {
+            self.draw_axes_gizmo(ui);
+        }
+
+        if self.setting.show_ruler {
+            self.draw_scale_ruler(ui);
+        }
+
+        if self.setting.show_force_quiver {
+            self.draw_quiver(ui);
+        }
+
+        self.particles.draw(ui, self.view_projection);
+
+        update_flag |= self.update_mouse_navigation(ui, render_sys);
+        if self.camera.update(&mut self.setting, render_sys) {
+            update_flag |= self.sync_camera_setting(render_sys);
+        }
+
+        if self.setting.show_probe {
+            self.update_probe(ui);
+        }
+
+        if self.setting.show_slice_gizmo {
+            update_flag |= self.update_slice_gizmo(ui);
+        } else {
+            self.slice_drag = None;
+        }
+
+        if self.setting.show_target_region {
+            self.update_region_integral();
+            self.draw_target_region(ui);
+        }
+
+        if self.setting.show_line_scan {
+            self.line_scan
+                .draw(ui, &self.setting.line_scan, self.view_projection);
+        }
+
+        if self.setting.ideal_overlay.enabled {
+            self.draw_ideal_contours(ui);
+        }
+
+        if self.setting.show_focal_spot {
+            self.update_focal_spot();
+            self.draw_focal_spot(ui);
+        }
+
+        if self.lobe_report.is_some() {
+            self.draw_lobes(ui);
+        }
+
+        if self.setting.show_acoustic_camera {
+            let model = FieldModel {
+                sources: &self.drive.sources,
+                settings: &self.setting.viewer_setting,
+                directivity_table: self.field_slice_viewer.directivity_table(),
+                scattering: self.scattering.as_ref(),
+                device_sizes: &self.drive.device_sizes,
+            };
+            self.acoustic_camera.update(&self.setting, &model);
+            self.acoustic_camera.draw(
+                ui,
+                &self.setting,
+                self.view_projection,
+                self.field_slice_viewer.color_map(),
+            );
+        }
+
+        if self.setting.show_far_field {
+            self.draw_far_field(ui);
+        }
+
+        if self.setting.show_power_summary {
+            self.draw_power_summary(ui);
+        }
+
+        if self.setting.show_coordinate_readout {
+            self.draw_coordinate_readout(ui);
+        }
 
-    fn get_log_txt(&self) -> String {
-        let mut log = String::new();
-        for line in &self.log_buf {
-            log.push_str(line);
-            log.push('\n');
+        if !self.shader_errors.is_empty() {
+            Window::new(im_str!("Shader error"))
+                .position([10.0, 10.0], Condition::FirstUseEver)
+                .always_auto_resize(true)
+                .build(ui, || {
+                    for e in &self.shader_errors {
+                        ui.text_colored([1.0, 0.3, 0.3, 1.0], e);
+                    }
+                    ui.text_disabled("Fix the shader and save it to reload");
+                });
         }
-        log
+
+        self.draw_toasts(ui);
+
+        update_flag
     }
-}
 
-pub fn main() {
-    let setting = Setting::load("setting.json");
-    let system = System::init(
-        "AUTD3 emulator",
-        setting.window_width as _,
-        setting.window_height as _,
-    );
+    fn slice_tab(&mut self, ui: &Ui) -> UpdateFlag {
+        let follow_focus = self.setting.follow_focus;
+        let mut update_flag =
+            self.slice
+                .transform_ui(ui, &mut self.setting, &mut self.field_slice_viewer);
+        if !follow_focus && self.setting.follow_focus {
+            self.focus_estimator
+                .request(self.drive.sources.clone(), self.setting.viewer_setting);
+        }
 
-    let mut app = App::new(setting, &system);
-    app.run(system);
+        ui.separator();
+        update_flag |= self.slice.quantity_ui(ui, &mut self.setting);
+        update_flag |= self.compare_ui(ui);
+        let auto_scale = self.setting.auto_scale;
+        let wave_animation = self.setting.wave_animation;
+        update_flag |= self
+            .slice
+            .color_ui(ui, &mut self.setting, &mut self.field_slice_viewer);
+        if wave_animation && !self.setting.wave_animation {
+            self.wave_phase = 0.0;
+        }
+        if auto_scale != self.setting.auto_scale {
+            self.auto_scale_requested = self.setting.auto_scale;
+        }
+
+        ui.separator();
+        update_flag |= self.slice.planes_ui(
+            ui,
+            &mut self.setting,
+            &mut self.field_slice_viewer,
+            &self.drive.geometries,
+        );
+
+        ui.separator();
+        update_flag |= self.extra_slices_ui(ui);
+        ui.separator();
+        self.probe_ui(ui);
+        ui.separator();
+        self.target_region_ui(ui);
+        ui.separator();
+        let slice_center = vecmath_util::to_vec3(&self.field_slice_viewer.model()[3]);
+        let metadata = self.metadata();
+        // a copy, since the panel edits the setting
+        let settings = self.setting.viewer_setting;
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings: &settings,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: self.scattering.as_ref(),
+            device_sizes: &self.drive.device_sizes,
+        };
+        self.line_scan.ui(
+            ui,
+            &mut self.setting,
+            &model,
+            slice_center,
+            &metadata,
+            &mut self.io_worker,
+        );
+        ui.separator();
+        self.ideal_overlay_ui(ui);
+        ui.separator();
+        update_flag |= self.volume_ui(ui);
+
+        #[cfg(feature = "offscreen_renderer")]
+        {
+            ui.separator();
+            self.offscreen.ui(
+                ui,
+                &mut self.setting,
+                &self.drive.sources,
+                &self.field_slice_viewer,
+                &metadata,
+                &mut self.io_worker,
+            );
+        }
+        update_flag
+    }
+
+    fn camera_tab(&mut self, ui: &Ui, render_sys: &mut RenderSystem) -> UpdateFlag {
+        let update_flag =
+            self.camera
+                .ui(ui, &mut self.setting, render_sys, &mut self.view_controller);
+        if update_flag.contains(UpdateFlag::UPDATE_CAMERA_POS) {
+            self.view_projection = render_sys.get_view_projection(&self.setting.viewer_setting);
+        }
+        update_flag
+    }
+
+    fn config_tab(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let mut locale = LOCALES
+            .iter()
+            .position(|&l| l == self.setting.locale)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Number format")).build_simple(ui, &mut locale, &LOCALES, &|l| {
+            ImString::new(l.name()).into()
+        }) {
+            self.setting.locale = LOCALES[locale];
+        }
+        let mut projector = self.projector_backup.is_some();
+        if ui.checkbox(im_str!("Projector mode (P)"), &mut projector) {
+            update_flag |= self.toggle_projector();
+        }
+        ui.separator();
+        ui.text(im_str!("Keyboard step"));
+        Drag::new(im_str!("Move step"))
+            .speed(0.1)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut self.setting.move_step);
+        Drag::new(im_str!("Move step (fine)"))
+            .speed(0.01)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut self.setting.move_step_fine);
+        AngleSlider::new(im_str!("Rotate step"))
+            .range_degrees(0.0..=90.0)
+            .build(ui, &mut self.setting.rotate_step);
+        AngleSlider::new(im_str!("Rotate step (fine)"))
+            .range_degrees(0.0..=90.0)
+            .build(ui, &mut self.setting.rotate_step_fine);
+        Slider::new(im_str!("Orbit inertia [s]"))
+            .range(0.0..=2.0)
+            .build(ui, &mut self.setting.orbit_inertia);
+        ui.separator();
+        let mut frequency = self.setting.viewer_setting.frequency / 1000.0;
+        if ui
+            .input_float(im_str!("Frequency [kHz]"), &mut frequency)
+            .step(0.1)
+            .step_fast(1.0)
+            .build()
+            && frequency > 0.0
+        {
+            self.setting
+                .viewer_setting
+                .set_frequency(frequency * 1000.0);
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        let medium = self.setting.viewer_setting.medium;
+        // the attenuation of the air absorption differs from that of the preset
+        let absorption = self.setting.viewer_setting.humidity.is_some();
+        let mut preset = MEDIUM_PRESETS
+            .iter()
+            .position(|(_, m)| {
+                m.density == medium.density && (absorption || m.attenuation == medium.attenuation)
+            })
+            .unwrap_or(MEDIUM_PRESETS.len());
+        let mut names: Vec<_> = MEDIUM_PRESETS.iter().map(|&(name, _)| name).collect();
+        names.push("Custom");
+        if ComboBox::new(im_str!("Medium"))
+            .build_simple(ui, &mut preset, &names, &|name| ImString::new(*name).into())
+        {
+            if let Some(&(_, m)) = MEDIUM_PRESETS.get(preset) {
+                self.setting.viewer_setting.set_medium(m);
+                update_flag |= UpdateFlag::UPDATE_WAVENUM;
+            }
+        }
+        Drag::new(im_str!("Density [kg/m^3]"))
+            .speed(0.1)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut self.setting.viewer_setting.medium.density);
+        Drag::new(im_str!("Nonlinearity 1 + B/2A"))
+            .speed(0.01)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut self.setting.viewer_setting.medium.nonlinearity);
+        let mut attenuation = self.setting.viewer_setting.medium.attenuation * 1000.0;
+        if Drag::new(im_str!("Attenuation [Np/m]"))
+            .speed(0.001)
+            .range(0.0..=f32::INFINITY)
+            .display_format(im_str!("%.6f"))
+            .build(ui, &mut attenuation)
+        {
+            self.setting.viewer_setting.medium.attenuation = attenuation / 1000.0;
+            self.setting.viewer_setting.humidity = None;
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        let mut use_humidity = self.setting.viewer_setting.humidity.is_some();
+        if ui.checkbox(im_str!("Air absorption (ISO 9613-1)"), &mut use_humidity) {
+            let humidity = use_humidity.then_some(50.0);
+            self.setting.viewer_setting.set_humidity(humidity);
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        if let Some(mut humidity) = self.setting.viewer_setting.humidity {
+            if Drag::new(im_str!("Humidity [%RH]"))
+                .speed(0.5)
+                .range(0.0..=100.0)
+                .build(ui, &mut humidity)
+            {
+                self.setting.viewer_setting.set_humidity(Some(humidity));
+                update_flag |= UpdateFlag::UPDATE_WAVENUM;
+            }
+            let setting = &self.setting.viewer_setting;
+            ui.text(format!(
+                "{:.2} dB/m at {:.1} kHz, {:.1} C",
+                setting.medium.attenuation * 1000.0 * 20.0 * std::f32::consts::LOG10_E,
+                setting.frequency / 1000.0,
+                setting.temperature.unwrap_or(20.0)
+            ));
+        }
+        update_flag |= self.reflector_ui(ui);
+        update_flag |= self.obstacles_ui(ui);
+        ui.separator();
+        let mut unit = PressureUnit::ALL
+            .iter()
+            .position(|&u| u == self.setting.viewer_setting.pressure_unit)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Pressure unit")).build_simple(
+            ui,
+            &mut unit,
+            &PressureUnit::ALL,
+            &|u| ImString::new(u.name()).into(),
+        ) {
+            self.setting.viewer_setting.pressure_unit = PressureUnit::ALL[unit];
+        }
+        let cal = &mut self.setting.viewer_setting.calibration;
+        Drag::new(im_str!("Calibration [Pa]"))
+            .speed(0.1)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut cal.pressure);
+        let mut spl = calibration::pa_to_spl(cal.pressure);
+        if Drag::new(im_str!("Calibration [dB SPL]"))
+            .speed(0.1)
+            .build(ui, &mut spl)
+        {
+            cal.pressure = calibration::spl_to_pa(spl);
+        }
+        Drag::new(im_str!("Calibration distance [mm]"))
+            .speed(1.0)
+            .range(1.0..=f32::INFINITY)
+            .build(ui, &mut cal.distance);
+        let mut attenuation = cal.attenuation * 1000.0;
+        if Drag::new(im_str!("Calibration attenuation [Np/m]"))
+            .speed(0.001)
+            .range(0.0..=f32::INFINITY)
+            .display_format(im_str!("%.6f"))
+            .build(ui, &mut attenuation)
+        {
+            cal.attenuation = attenuation / 1000.0;
+        }
+        if ui.small_button(im_str!("T4010A1 calibration")) {
+            *cal = PressureCalibration::t4010a1();
+        }
+        ui.separator();
+        let mut directivity = Directivity::ALL
+            .iter()
+            .position(|&d| d == self.setting.viewer_setting.directivity)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Directivity")).build_simple(
+            ui,
+            &mut directivity,
+            &Directivity::ALL,
+            &|d| ImString::new(d.name()).into(),
+        ) {
+            self.setting.viewer_setting.directivity = Directivity::ALL[directivity];
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        if self.setting.viewer_setting.directivity == Directivity::Imported {
+            ui.input_text(im_str!("Directivity file"), &mut self.directivity_file)
+                .build();
+            if ui.small_button(im_str!("Import directivity")) {
+                self.directivity_requested = true;
+            }
+            match self.field_slice_viewer.directivity_table() {
+                Some(table) => ui.text(format!(
+                    "Frequencies [Hz]: {}",
+                    table
+                        .frequencies()
+                        .iter()
+                        .map(|f| f.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+                None => ui.text_disabled("Not imported, same as monopole"),
+            }
+        }
+        update_flag |= self.approximation_ui(ui);
+        update_flag |= self.trans_calibration_ui(ui);
+        update_flag |= self.perturbation_ui(ui);
+        let mut wave_length = self.setting.viewer_setting.wave_length;
+        if Drag::new(im_str!("Wavelength"))
+            .speed(0.1)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut wave_length)
+        {
+            let frequency = self.setting.viewer_setting.frequency;
+            self.setting
+                .viewer_setting
+                .set_sound_speed(frequency * wave_length);
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        let mut sound_speed = self.setting.viewer_setting.sound_speed() / 1000.0;
+        if Drag::new(im_str!("Sound speed [m/s]"))
+            .speed(0.1)
+            .range(1.0..=f32::INFINITY)
+            .build(ui, &mut sound_speed)
+        {
+            self.setting
+                .viewer_setting
+                .set_sound_speed(sound_speed * 1000.0);
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        let mut use_temperature = self.setting.viewer_setting.temperature.is_some();
+        if ui.checkbox(im_str!("Air at temperature"), &mut use_temperature) {
+            if use_temperature {
+                self.setting.viewer_setting.set_temperature(15.0);
+            } else {
+                self.setting.viewer_setting.temperature = None;
+            }
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        if let Some(mut temperature) = self.setting.viewer_setting.temperature {
+            if Drag::new(im_str!("Temperature [C]"))
+                .speed(0.1)
+                .range(-50.0..=100.0)
+                .build(ui, &mut temperature)
+            {
+                self.setting.viewer_setting.set_temperature(temperature);
+                update_flag |= UpdateFlag::UPDATE_WAVENUM;
+            }
+        }
+        if ui.checkbox(
+            im_str!("Temperature drift"),
+            &mut self.setting.temperature_drift,
+        ) {
+            let settings = &mut self.setting.viewer_setting;
+            if self.setting.temperature_drift {
+                self.drift_start = self.clock.time();
+                self.air_before_drift = (settings.temperature, settings.medium.sound_speed);
+            } else {
+                Self::restore_air(self.air_before_drift, settings);
+                update_flag |= UpdateFlag::UPDATE_WAVENUM;
+            }
+        }
+        if self.setting.temperature_drift {
+            self.drift_profile_ui(ui);
+        }
+        ui.separator();
+        if Slider::new(im_str!("Transducer alpha"))
+            .range(0.0..=1.0)
+            .build(ui, &mut self.setting.viewer_setting.source_alpha)
+        {
+            update_flag |= UpdateFlag::UPDATE_SOURCE_ALPHA;
+        }
+        let mut coloring = SourceColoring::ALL
+            .iter()
+            .position(|&c| c == self.setting.viewer_setting.source_coloring)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Transducer color")).build_simple(
+            ui,
+            &mut coloring,
+            &SourceColoring::ALL,
+            &|c| ImString::new(c.name()).into(),
+        ) {
+            self.setting.viewer_setting.source_coloring = SourceColoring::ALL[coloring];
+            update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Amplitude uses the color map of the slice, from 0 to the full duty");
+        }
+        ui.checkbox(im_str!("Show devices"), &mut self.setting.show_devices);
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Board, axes (x red, y green, z blue) and index of each device");
+        }
+        ui.checkbox(
+            im_str!("Transducer tooltip"),
+            &mut self.setting.show_source_tooltip,
+        );
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Index, position and drive of the transducer under the mouse");
+        }
+        ui.separator();
+        Drag::new(im_str!("Latency [ms]"))
+            .speed(0.1)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut self.setting.latency_ms);
+        ui.text(format!("Pending frames: {}", self.num_pending));
+        Drag::new(im_str!("Coalescing [ms]"))
+            .speed(0.1)
+            .range(0.0..=1000.0)
+            .build(ui, &mut self.setting.coalesce_ms);
+        let stats = self.coalescer.stats();
+        ui.text(format!(
+            "Drive updates: {} received, {} rendered, {} merged",
+            stats.received, stats.rendered, stats.merged
+        ));
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Reset##coalesce")) {
+            self.coalescer.reset_stats();
+        }
+        ui.separator();
+        ui.checkbox(
+            im_str!("Ramp-up on output enable"),
+            &mut self.setting.ramp_enable,
+        );
+        if self.setting.ramp_enable {
+            let mut duration = self.setting.ramp_duration * 1000.0;
+            if Drag::new(im_str!("Ramp duration [ms]"))
+                .speed(0.1)
+                .range(0.0..=f32::INFINITY)
+                .build(ui, &mut duration)
+            {
+                self.setting.ramp_duration = duration / 1000.0;
+            }
+        }
+        ui.separator();
+        ui.text(im_str!("History (for spectators, handoff and drives)"));
+        let mut max_frames = self.setting.history_max_frames as i32;
+        if ui
+            .input_int(im_str!("Max frames"), &mut max_frames)
+            .step(256)
+            .build()
+        {
+            self.setting.history_max_frames = max_frames.max(1) as u32;
+        }
+        Drag::new(im_str!("Max size [MB]"))
+            .speed(0.1)
+            .range(0.1..=f32::INFINITY)
+            .build(ui, &mut self.setting.history_max_mb);
+        let usage = self.history_usage;
+        ui.text(format!(
+            "Usage: {} frames, {:.2} MB",
+            usage.frames,
+            usage.bytes as f32 / 1024.0 / 1024.0
+        ));
+        if usage.evicted > 0 {
+            ui.text_colored(
+                [1.0, 0.8, 0.0, 1.0],
+                format!("{} oldest frames evicted", usage.evicted),
+            );
+        }
+        ui.separator();
+        ui.checkbox(
+            im_str!("Show commanded foci"),
+            &mut self.setting.show_commanded_foci,
+        );
+        ui.checkbox(
+            im_str!("Estimate focus from phases"),
+            &mut self.setting.show_estimated_focus,
+        );
+        ui.checkbox(
+            im_str!("Show steering limit"),
+            &mut self.setting.show_steering_limit,
+        );
+        if self.setting.show_steering_limit {
+            let mut percent = self.setting.steering_fraction * 100.0;
+            if Drag::new(im_str!("Steering limit [% of on-axis]"))
+                .range(1.0..=99.0)
+                .build(ui, &mut percent)
+            {
+                self.setting.steering_fraction = percent.clamp(1.0, 99.0) / 100.0;
+            }
+        }
+        ui.checkbox(
+            im_str!("Show focal spot metrics"),
+            &mut self.setting.show_focal_spot,
+        );
+        ui.checkbox(
+            im_str!("Show coordinate under cursor"),
+            &mut self.setting.show_coordinate_readout,
+        );
+        ui.checkbox(
+            im_str!("Hot-reload shaders"),
+            &mut self.setting.shader_hot_reload,
+        );
+        ui.separator();
+        self.experiment.ui(ui);
+        ui.separator();
+        self.session.ui(ui, &mut self.setting);
+        ui.separator();
+        self.ui_layout
+            .ui(ui, &mut self.setting, &mut self.io_worker);
+        ui.separator();
+        ui.text(im_str!("Spectator (applied after restart)"));
+        ui.checkbox(
+            im_str!("Accept spectators"),
+            &mut self.setting.spectator_enable,
+        );
+        let mut port = self.setting.spectator_port as i32;
+        if ui.input_int(im_str!("Spectator port"), &mut port).build() {
+            self.setting.spectator_port = port.clamp(0, u16::MAX as i32) as u16;
+        }
+        ui.separator();
+        ColorPicker::new(
+            im_str!("Background"),
+            &mut self.setting.viewer_setting.background,
+        )
+        .alpha(true)
+        .build(ui);
+        update_flag
+    }
+
+    fn info_tab(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        ui.text(format!(
+            "Clock: {} [s]",
+            self.setting.locale.number(self.clock.time(), 6)
+        ));
+        let mut paused = self.clock.is_paused();
+        if ui.checkbox(im_str!("Pause (Space)"), &mut paused) {
+            self.clock.set_paused(paused);
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Step (.)")) {
+            self.step_clock();
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Reset clock")) {
+            self.clock.reset();
+        }
+        let mut scale = self.clock.scale();
+        if Slider::new(im_str!("Time scale"))
+            .range(TIME_SCALE_MIN..=TIME_SCALE_MAX)
+            .flags(SliderFlags::LOGARITHMIC)
+            .build(ui, &mut scale)
+        {
+            self.clock.set_scale(scale);
+        }
+        if let Some(ramp) = &self.ramp {
+            let progress = ramp.progress(self.clock.time());
+            let overlay = ImString::new(format!(
+                "Ramp-up: {} / {} [ms]",
+                self.setting
+                    .locale
+                    .number(progress as f64 * ramp.duration() * 1000.0, 1),
+                self.setting.locale.number(ramp.duration() * 1000.0, 1)
+            ));
+            ProgressBar::new(progress).overlay_text(&overlay).build(ui);
+        }
+        self.tag_history_ui(ui);
+        ui.separator();
+        match &self.spectate {
+            Some(host) => ui.text(format!("Spectating {} (read only)", host)),
+            None if self.setting.spectator_enable => {
+                ui.text(format!("Spectators: {}", self.num_spectators))
+            }
+            None => (),
+        }
+        let (min, max) = self
+            .drive
+            .sources
+            .iter()
+            .filter_map(|s| s.frequency)
+            .fold((f32::INFINITY, 0.0f32), |(min, max), f| {
+                (min.min(f), max.max(f))
+            });
+        if max > 0.0 {
+            ui.text(format!(
+                "Source frequency: {}-{} kHz",
+                self.setting.locale.number(min as f64 / 1000.0, 3),
+                self.setting.locale.number(max as f64 / 1000.0, 3)
+            ));
+        }
+        match self.picked {
+            Some(PickId::Source(i)) if i < self.drive.sources.len() => {
+                ui.text(format!("Picked: source {}", i));
+                for line in self.source_info(i) {
+                    ui.bullet_text(&ImString::new(line));
+                }
+                update_flag |= self.drive_override_ui(ui, i);
+                let pos = self.drive.sources[i].pos;
+                if let Some(history) = self.drive_history.source(i, pos) {
+                    let metadata = self.metadata();
+                    self.drive_history.ui(
+                        ui,
+                        history,
+                        &mut self.setting,
+                        self.clock.time(),
+                        &metadata,
+                        &mut self.io_worker,
+                    );
+                }
+            }
+            Some(PickId::Slice) => ui.text("Picked: slice"),
+            Some(id) => ui.text(format!("Picked: {:?}", id)),
+            None => ui.text("Picked: - (click the view to pick)"),
+        }
+        if !self.geometry_warnings.is_empty() {
+            ui.text_colored([1.0, 0.8, 0.0, 1.0], "Geometry warnings");
+            for warning in &self.geometry_warnings {
+                ui.text_wrapped(&ImString::new(warning.to_string()));
+            }
+            ui.separator();
+        }
+        if let Some(state) = &self.firmware_state {
+            ui.text("Firmware");
+            match state.msg_id {
+                Some(id) => ui.text(format!("Last msg id: 0x{:02X}", id)),
+                None => ui.text("Last msg id: -"),
+            }
+            ui.text(format!(
+                "RX data: {} devices, ack 0x{:02X}",
+                state.num_devices, state.ack
+            ));
+            ui.text(format!(
+                "Modulation buffer: {:?} ({} bytes)",
+                state.mod_state, state.mod_len
+            ));
+            ui.text(format!(
+                "Sequence buffer: {:?} ({} points)",
+                state.seq_state, state.seq_len
+            ));
+            ui.separator();
+        }
+        ui.text("Control flag");
+        let mut flag = self.drive.ctrl_flag;
+        ui.checkbox_flags(
+            im_str!("MOD BEGIN"),
+            &mut flag,
+            RxGlobalControlFlags::MOD_BEGIN,
+        );
+        ui.checkbox_flags(im_str!("MOD END"), &mut flag, RxGlobalControlFlags::MOD_END);
+        ui.checkbox_flags(
+            im_str!("MOD END"),
+            &mut flag,
+            RxGlobalControlFlags::READ_FPGA_INFO,
+        );
+        ui.checkbox_flags(im_str!("SILENT"), &mut flag, RxGlobalControlFlags::SILENT);
+        ui.checkbox_flags(
+            im_str!("FORCE FAN"),
+            &mut flag,
+            RxGlobalControlFlags::FORCE_FAN,
+        );
+        ui.checkbox_flags(
+            im_str!("SEQ MODE"),
+            &mut flag,
+            RxGlobalControlFlags::SEQ_MODE,
+        );
+        ui.checkbox_flags(
+            im_str!("SEQ BEGIN"),
+            &mut flag,
+            RxGlobalControlFlags::SEQ_BEGIN,
+        );
+        ui.checkbox_flags(im_str!("SEQ END"), &mut flag, RxGlobalControlFlags::SEQ_END);
+
+        if let Some(m) = &self.drive.modulation {
+            ui.separator();
+            ui.text("Modulation");
+            ui.text(format!("Modulation size: {}", m.mod_data.len()));
+            ui.text(format!("Modulation division: {}", m.mod_div));
+            let smpl_period = (1000000.0 / MOD_SAMPLING_FREQ_BASE) as usize * m.mod_div as usize;
+            ui.text(format!("Modulation sampling period: {} [us]", smpl_period));
+            ui.text(format!(
+                "Modulation period: {} [us]",
+                smpl_period * m.mod_data.len()
+            ));
+            if !m.mod_data.is_empty() {
+                ui.text(format!("mod[0]: {}", m.mod_data[0]));
+            }
+            if m.mod_data.len() == 2 || m.mod_data.len() == 3 {
+                ui.text(format!("mod[1]: {}", m.mod_data[1]));
+            } else if m.mod_data.len() > 3 {
+                ui.text("...");
+            }
+            if m.mod_data.len() >= 3 {
+                let idx = m.mod_data.len() - 1;
+                ui.text(format!("mod[{}]: {}", idx, m.mod_data[idx]));
+            }
+            if let Some(idx) = self.drive.mod_index {
+                if let Some(v) = m.mod_data.get(idx) {
+                    ui.text(format!("Playback: mod[{}]: {}", idx, v));
+                }
+            }
+
+            if ui.radio_button_bool(im_str!("show mod plot"), self.setting.show_mod_plot) {
+                self.setting.show_mod_plot = !self.setting.show_mod_plot;
+            }
+
+            if self.setting.show_mod_plot {
+                ui.separator();
+                let mod_v = self.mod_values(|&v| ((v as f32) / 512.0 * PI).sin());
+                PlotLines::new(ui, im_str!("mod plot"), &mod_v)
+                    .graph_size(self.setting.mod_plot_size)
+                    .build();
+                if ui.radio_button_bool(
+                    im_str!("show mod plot (raw)"),
+                    self.setting.show_mod_plot_raw,
+                ) {
+                    self.setting.show_mod_plot_raw = !self.setting.show_mod_plot_raw;
+                }
+                if self.setting.show_mod_plot_raw {
+                    ui.separator();
+                    let mod_v = self.mod_values(|&v| v as f32);
+                    PlotLines::new(ui, im_str!("mod plot (raw)"), &mod_v)
+                        .graph_size(self.setting.mod_plot_size)
+                        .build();
+                }
+
+                Drag::new(im_str!("plot size"))
+                    .range(0.0..=f32::INFINITY)
+                    .build_array(ui, &mut self.setting.mod_plot_size);
+            }
+        }
+
+        if self
+            .drive
+            .ctrl_flag
+            .contains(RxGlobalControlFlags::SEQ_MODE)
+        {
+            ui.separator();
+            ui.text("Sequence mode");
+            if let Some(seq) = &self.drive.sequence {
+                ui.text(format!("Sequence size: {}", seq.seq_data.len()));
+                ui.text(format!("Sequence division: {}", seq.seq_div));
+                let smpl_period = (1000000 / POINT_SEQ_BASE_FREQ) * seq.seq_div as usize;
+                ui.text(format!("Sequence sampling period: {} [us]", smpl_period));
+                ui.text(format!(
+                    "Sequence period: {} [us]",
+                    smpl_period * seq.seq_data.len()
+                ));
+                if !seq.seq_data.is_empty() {
+                    ui.text(format!(
+                        "seq[0]: {:?} / {}",
+                        seq.seq_data[0].0, seq.seq_data[0].1
+                    ));
+                }
+                if seq.seq_data.len() == 2 || seq.seq_data.len() == 3 {
+                    ui.text(format!(
+                        "seq[1]: {:?} / {}",
+                        seq.seq_data[1].0, seq.seq_data[1].1
+                    ));
+                } else if seq.seq_data.len() > 3 {
+                    ui.text("...");
+                }
+                if seq.seq_data.len() >= 3 {
+                    let idx = seq.seq_data.len() - 1;
+                    ui.text(format!(
+                        "seq[{}]: {:?} / {}",
+                        idx, seq.seq_data[idx].0, seq.seq_data[idx].1
+                    ));
+                }
+                if let Some(idx) = self.drive.seq_index {
+                    ui.text(format!(
+                        "Playback: seq[{}]: {:?} / {}",
+                        idx, seq.seq_data[idx].0, seq.seq_data[idx].1
+                    ));
+                }
+            }
+            self.stm_duty_ui(ui);
+        }
+
+        if let Some(d) = &self.drive.delay_offset {
+            ui.separator();
+            ui.text("Duty offset and Delay");
+            ui.text(format!(
+                "offset[0]: {}, delay[0]: {}",
+                d.delay_offset[0].1, d.delay_offset[0].0
+            ));
+            ui.text("...");
+            let idx = d.delay_offset.len() - 1;
+            ui.text(format!(
+                "offset[{0}]: {1}, delay[{0}]: {2}",
+                idx, d.delay_offset[idx].1, d.delay_offset[idx].0
+            ));
+        }
+        update_flag
+    }
+
+    fn analysis_tab(&mut self, ui: &Ui) {
+        let slice_center = vecmath_util::to_vec3(&self.field_slice_viewer.model()[3]);
+        let metadata = self.metadata();
+        self.acoustic_camera.ui(
+            ui,
+            &mut self.setting,
+            slice_center,
+            &metadata,
+            &mut self.io_worker,
+        );
+        ui.separator();
+        self.lobes_ui(ui);
+        ui.separator();
+        self.far_field_ui(ui);
+        if ui.checkbox(
+            im_str!("Power summary"),
+            &mut self.setting.show_power_summary,
+        ) && !self.setting.show_power_summary
+        {
+            self.power_summary = None;
+        }
+        ui.separator();
+        self.fixtures_ui(ui);
+    }
+
+    fn far_field_ui(&mut self, ui: &Ui) {
+        if ui.checkbox(
+            im_str!("Far-field pattern"),
+            &mut self.setting.show_far_field,
+        ) && !self.setting.show_far_field
+        {
+            self.far_field = None;
+        }
+        if !self.setting.show_far_field {
+            return;
+        }
+        let mut resolution = self.setting.far_field_resolution as i32;
+        if Drag::new(im_str!("Resolution##far"))
+            .range(16..=256)
+            .build(ui, &mut resolution)
+        {
+            self.setting.far_field_resolution = resolution.clamp(16, 256) as usize;
+            self.far_field = None;
+        }
+        Drag::new(im_str!("Dynamic range [dB]##far"))
+            .speed(0.1)
+            .range(1.0..=80.0)
+            .build(ui, &mut self.setting.far_field_range);
+    }
+
+    fn update_power_summary(&mut self) {
+        let settings = &self.setting.viewer_setting;
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: self.scattering.as_ref(),
+            device_sizes: &self.drive.device_sizes,
+        };
+        self.power_summary = PowerSummary::compute(
+            &model,
+            self.field_slice_viewer.model(),
+            settings.calibration.pa_per_unit(),
+        );
+    }
+
+    fn draw_power_summary(&self, ui: &Ui) {
+        let summary = match &self.power_summary {
+            Some(summary) => summary,
+            None => return,
+        };
+        let screen_size = ui.io().display_size;
+        let locale = &self.setting.locale;
+        let settings = &self.setting.viewer_setting;
+        let percent = |v: f32| locale.number(v as f64 * 100.0, 1);
+        Window::new(im_str!("Power"))
+            .position([10.0, screen_size[1] - 10.0], Condition::FirstUseEver)
+            .position_pivot([0.0, 1.0])
+            .always_auto_resize(true)
+            .build(ui, || {
+                ui.text(format!(
+                    "Radiated power: {} [W] ({} [mW] per transducer)",
+                    locale.number(summary.power as f64, 3),
+                    locale.number(
+                        summary.power as f64 * 1000.0 / self.drive.sources.len().max(1) as f64,
+                        2
+                    )
+                ));
+                ui.text(format!("Mean drive: {} [%]", percent(summary.drive)));
+                ui.text(format!(
+                    "Peak: {} [{}] at {} [mm]",
+                    locale.number(
+                        settings
+                            .pressure_unit
+                            .convert(summary.peak_amp, &settings.calibration)
+                            as f64,
+                        3
+                    ),
+                    settings.pressure_unit.name(),
+                    locale.vec3(summary.peak_pos, 1)
+                ));
+                ui.text(format!(
+                    "Focal gain: {} ({} [dB])",
+                    locale.number(summary.focal_gain as f64, 1),
+                    locale.number(20.0 * (summary.focal_gain as f64).max(1e-10).log10(), 1)
+                ));
+                ui.text(format!(
+                    "Coherence at peak: {} [%]",
+                    percent(summary.coherence)
+                ));
+                ui.text(format!(
+                    "Array efficiency: {} [%]",
+                    percent(summary.efficiency)
+                ));
+                if ui.small_button(im_str!("Log summary")) {
+                    tracing::info!(
+                        tags = %self.tags.current().join(", "),
+                        power = summary.power,
+                        focal_gain = summary.focal_gain,
+                        coherence = summary.coherence,
+                        efficiency = summary.efficiency,
+                        "power summary"
+                    );
+                }
+            });
+    }
+
+    fn update_far_field(&mut self) {
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings: &self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: None,
+            device_sizes: &self.drive.device_sizes,
+        };
+        self.far_field = FarFieldPattern::compute(&model, self.setting.far_field_resolution);
+    }
+
+    // The hemisphere in the orthographic projection of the direction cosines, with the rings of
+    // the polar angles of 30, 60 and 90 degrees
+    fn draw_far_field(&self, ui: &Ui) {
+        const PLOT_SIZE: f32 = 280.0;
+        const RING_COLOR: [f32; 4] = [0.7, 0.7, 0.7, 1.0];
+        const LOBE_COLOR: [f32; 4] = [1.0, 0.5, 0.5, 1.0];
+        const MAX_LOBES: usize = 6;
+
+        let pattern = match &self.far_field {
+            Some(pattern) => pattern,
+            None => return,
+        };
+        let screen_size = ui.io().display_size;
+        let color_map = self.field_slice_viewer.color_map();
+        let range = self.setting.far_field_range;
+        let n = pattern.resolution;
+        let cell = PLOT_SIZE / n as f32;
+        let lobes = pattern.lobes(-range);
+        Window::new(im_str!("Far field"))
+            .position([screen_size[0] - 10.0, 520.0], Condition::FirstUseEver)
+            .position_pivot([1.0, 0.0])
+            .always_auto_resize(true)
+            .build(ui, || {
+                let origin = ui.cursor_screen_pos();
+                let center = [origin[0] + PLOT_SIZE / 2.0, origin[1] + PLOT_SIZE / 2.0];
+                let draw_list = ui.get_window_draw_list();
+                for (k, level) in pattern.levels.iter().enumerate() {
+                    let level = match level {
+                        Some(level) => *level,
+                        None => continue,
+                    };
+                    let t = (level + range) / range;
+                    let color = if t < 0.0 || color_map.is_empty() {
+                        [0.0, 0.0, 0.0, 1.0]
+                    } else {
+                        let c = color_map[(t.min(1.0) * (color_map.len() - 1) as f32) as usize];
+                        [c.r as f32, c.g as f32, c.b as f32, 1.0]
+                    };
+                    // the first row is at the bottom
+                    let (i, j) = (k % n, k / n);
+                    let p = [
+                        origin[0] + i as f32 * cell,
+                        origin[1] + (n - 1 - j) as f32 * cell,
+                    ];
+                    draw_list
+                        .add_rect(p, [p[0] + cell, p[1] + cell], color)
+                        .filled(true)
+                        .build();
+                }
+                for theta in [30.0f32, 60.0, 90.0] {
+                    let r = theta.to_radians().sin() * PLOT_SIZE / 2.0;
+                    draw_list
+                        .add_circle(center, r, RING_COLOR)
+                        .num_segments(64)
+                        .build();
+                }
+                for lobe in lobes.iter().take(MAX_LOBES) {
+                    let r = lobe.theta.to_radians().sin() * PLOT_SIZE / 2.0;
+                    let phi = lobe.phi.to_radians();
+                    let p = [center[0] + r * phi.cos(), center[1] - r * phi.sin()];
+                    draw_list.add_circle(p, 4.0, LOBE_COLOR).build();
+                }
+                ui.dummy([PLOT_SIZE, PLOT_SIZE]);
+                let locale = &self.setting.locale;
+                ui.text(format!(
+                    "{} - 0 [dB], rings at 30, 60 and 90 deg",
+                    locale.number(-range as f64, 1)
+                ));
+                for (i, lobe) in lobes.iter().take(MAX_LOBES).enumerate() {
+                    ui.text(format!(
+                        "{}: theta {} deg, phi {} deg, {} dB",
+                        i + 1,
+                        locale.number(lobe.theta as f64, 1),
+                        locale.number(lobe.phi as f64, 1),
+                        locale.number(lobe.level as f64, 1)
+                    ));
+                }
+            });
+    }
+
+    fn lobes_ui(&mut self, ui: &Ui) {
+        const MAX_LOBES: usize = 20;
+        const STRONG_LOBE: f32 = -10.0;
+
+        ui.text(im_str!("Lobes"));
+        Drag::new(im_str!("Area [mm]##lobes"))
+            .speed(1.0)
+            .range(1.0..=f32::INFINITY)
+            .build_array(ui, &mut self.setting.lobe_area);
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Whole slice")) {
+            let settings = &self.setting.viewer_setting;
+            self.setting.lobe_area = [settings.slice_width as f32, settings.slice_height as f32];
+        }
+        Drag::new(im_str!("Floor [dB]##lobes"))
+            .speed(0.1)
+            .range(-120.0..=0.0)
+            .build(ui, &mut self.setting.lobe_floor);
+        if ui.small_button(im_str!("Analyze lobes")) {
+            let search = LobeSearch {
+                area: self.setting.lobe_area,
+                floor: self.setting.lobe_floor,
+                max_lobes: MAX_LOBES,
+            };
+            let field = FieldModel {
+                sources: &self.drive.sources,
+                settings: &self.setting.viewer_setting,
+                directivity_table: self.field_slice_viewer.directivity_table(),
+                scattering: self.scattering.as_ref(),
+                device_sizes: &self.drive.device_sizes,
+            };
+            self.lobe_report = search.search(&field, self.field_slice_viewer.model());
+            if self.lobe_report.is_none() {
+                tracing::warn!("no lobe is found on the slice");
+            }
+        }
+        let report = match &self.lobe_report {
+            Some(report) => report,
+            None => return,
+        };
+        ui.same_line(0.);
+        let clear = ui.small_button(im_str!("Clear##lobes"));
+        let locale = &self.setting.locale;
+        let settings = &self.setting.viewer_setting;
+        ui.text(format!(
+            "Main lobe: {} [mm], |p| = {} [{}]",
+            locale.vec3(report.main.pos, 1),
+            locale.number(
+                settings
+                    .pressure_unit
+                    .convert(report.main.amp, &settings.calibration) as f64,
+                2
+            ),
+            settings.pressure_unit.name()
+        ));
+        if report.secondary.is_empty() {
+            ui.text_disabled(format!(
+                "No secondary maximum above {} dB",
+                self.setting.lobe_floor
+            ));
+        } else {
+            ui.columns(3, im_str!("lobes"), true);
+            for header in ["Position [mm]", "Level [dB]", "Distance [mm]"] {
+                ui.text(header);
+                ui.next_column();
+            }
+            ui.separator();
+            for lobe in &report.secondary {
+                ui.text(locale.vec3(lobe.pos, 1));
+                ui.next_column();
+                let level = locale.number(lobe.level as f64, 1);
+                if lobe.level >= STRONG_LOBE {
+                    ui.text_colored([1.0, 0.3, 0.3, 1.0], level);
+                } else {
+                    ui.text(level);
+                }
+                ui.next_column();
+                ui.text(locale.number(lobe.distance as f64, 1));
+                ui.next_column();
+            }
+            ui.columns(1, im_str!(""), false);
+            ui.text_disabled(format!(
+                "Lobes above {} dB, in red, are typically grating lobes",
+                STRONG_LOBE
+            ));
+        }
+        if clear {
+            self.lobe_report = None;
+        }
+    }
+
+    fn fixtures_ui(&mut self, ui: &Ui) {
+        ui.text(im_str!("Fixtures"));
+        ui.checkbox(im_str!("Show fixtures"), &mut self.setting.show_fixtures);
+        let mut remove = None;
+        for (i, fixture) in self.setting.fixtures.iter_mut().enumerate() {
+            let id = ui.push_id(i as i32);
+            if CollapsingHeader::new(&ImString::new(&fixture.name)).build(ui) {
+                let mut name = ImString::with_capacity(64);
+                name.push_str(&fixture.name);
+                if ui.input_text(im_str!("Name"), &mut name).build() {
+                    fixture.name = name.to_str().to_owned();
+                }
+                let mut path = ImString::with_capacity(256);
+                path.push_str(&fixture.path);
+                if ui.input_text(im_str!("Mesh file"), &mut path).build() {
+                    fixture.path = path.to_str().to_owned();
+                }
+                Drag::new(im_str!("Offset [mm]"))
+                    .speed(1.0)
+                    .build_array(ui, &mut fixture.offset);
+                Drag::new(im_str!("Scale [mm/unit]"))
+                    .speed(0.01)
+                    .range(1e-3..=f32::INFINITY)
+                    .build(ui, &mut fixture.scale);
+                ui.checkbox(im_str!("Scatterer"), &mut fixture.scatterer);
+                match self.fixtures.get(i).map(|f| &f.mesh) {
+                    Some(Ok(mesh)) => ui.text(format!("{} triangles", mesh.triangles.len())),
+                    Some(Err(e)) => ui.text_colored([1.0, 0.3, 0.3, 1.0], e),
+                    None => {}
+                }
+                if ui.small_button(im_str!("Remove")) {
+                    remove = Some(i);
+                }
+            }
+            id.pop(ui);
+        }
+        if let Some(i) = remove {
+            self.setting.fixtures.remove(i);
+            self.collisions = None;
+        }
+        if ui.small_button(im_str!("Add fixture")) {
+            let name = format!("fixture{}", self.setting.fixtures.len());
+            self.setting.fixtures.push(FixtureSetting::new(&name));
+        }
+        if let Some(scattering) = &self.scattering {
+            let n = scattering.facets.len();
+            ui.text(format!("Scattering: {} facets", n));
+            if n >= MAX_FACETS {
+                ui.same_line(0.);
+                ui.text_colored([1.0, 0.8, 0.2, 1.0], "(limit, coarser than 1/4 wavelength)");
+            }
+        }
+
+        let collisions = match &self.collisions {
+            Some(collisions) if !collisions.is_empty() => collisions,
+            _ => return,
+        };
+        for &(i, j) in &collisions.foci {
+            if let (Some(focus), Some(fixture)) =
+                (self.commanded_foci.get(i), self.setting.fixtures.get(j))
+            {
+                ui.text_colored(
+                    [1.0, 0.3, 0.3, 1.0],
+                    format!("Focus {} is inside {}", focus.label, fixture.name),
+                );
+            }
+        }
+        for (j, fixture) in self.setting.fixtures.iter().enumerate() {
+            let n = collisions.stm.iter().filter(|c| c.1 == j).count();
+            if n > 0 {
+                ui.text_colored(
+                    [1.0, 0.3, 0.3, 1.0],
+                    format!("{} STM points pass inside {}", n, fixture.name),
+                );
+            }
+        }
+    }
+
+    // Reloads the fixtures whose settings changed, and checks the commanded foci and the STM
+    // trajectory against the fixtures again when any of them changed. The scattering is evaluated
+    // again with UpdateFlag::UPDATE_WAVENUM if the scatterers may have changed.
+    fn update_collisions(&mut self) -> UpdateFlag {
+        let mut reloaded = false;
+        if self.fixtures.len() != self.setting.fixtures.len() {
+            self.fixtures.truncate(self.setting.fixtures.len());
+            self.collisions = None;
+            reloaded = true;
+        }
+        for (i, setting) in self.setting.fixtures.iter().enumerate() {
+            if self.fixtures.get(i).is_some_and(|f| f.setting == *setting) {
+                continue;
+            }
+            let fixture = Fixture::load(setting);
+            // the path is loaded as typed, so only the errors of the existing files are reported
+            if let Err(e) = &fixture.mesh {
+                if Path::new(&setting.path).is_file() {
+                    tracing::error!("{}", e);
+                }
+            }
+            if i < self.fixtures.len() {
+                self.fixtures[i] = fixture;
+            } else {
+                self.fixtures.push(fixture);
+            }
+            self.collisions = None;
+            reloaded = true;
+        }
+        let scattering = if reloaded
+            && (self.scattering.is_some() || self.fixtures.iter().any(|f| f.setting.scatterer))
+        {
+            UpdateFlag::UPDATE_WAVENUM
+        } else {
+            UpdateFlag::empty()
+        };
+        if self.collisions.is_some() {
+            return scattering;
+        }
+        let foci: Vec<_> = self.commanded_foci.iter().map(|f| f.pos).collect();
+        let stm: Vec<_> = self.drive.stm_points().iter().map(|d| d.0).collect();
+        let collisions = fixtures::check(&self.fixtures, &foci, &stm);
+        for &(i, j) in &collisions.foci {
+            let focus = &self.commanded_foci[i];
+            tracing::warn!(
+                "focus {} at {} mm is inside fixture {}",
+                focus.label,
+                self.setting.locale.vec3(focus.pos, 1),
+                self.setting.fixtures[j].name
+            );
+        }
+        for (j, fixture) in self.setting.fixtures.iter().enumerate() {
+            let n = collisions.stm.iter().filter(|c| c.1 == j).count();
+            if n > 0 {
+                tracing::warn!(
+                    "{} of {} STM points pass inside fixture {}",
+                    n,
+                    stm.len(),
+                    fixture.name
+                );
+            }
+        }
+        self.collisions = Some(collisions);
+        scattering
+    }
+
+    // Drops the reference frozen on other sources, e.g., after the geometry changes, and shows the
+    // current drive without the reference
+    fn check_reference(&mut self) -> UpdateFlag {
+        if self
+            .reference
+            .as_ref()
+            .is_some_and(|r| !r.matches(&self.drive.sources))
+        {
+            tracing::info!("drop the reference A frozen on another geometry");
+            self.reference = None;
+            self.reference_changed = true;
+        }
+        if self.reference.is_none() && self.setting.viewer_setting.compare != CompareView::Current {
+            self.setting.viewer_setting.compare = CompareView::Current;
+            return UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        UpdateFlag::empty()
+    }
+
+    fn upload_reference(&mut self, render_sys: &mut RenderSystem) {
+        let factory = &mut render_sys.factory;
+        let sources = self
+            .reference
+            .as_ref()
+            .map(|r| r.sources(&self.drive.sources));
+        let reference = self
+            .reference
+            .as_ref()
+            .zip(sources.as_deref())
+            .map(|(r, sources)| (sources, r.scattering.as_ref()));
+        self.field_slice_viewer.set_reference(factory, reference);
+        for viewer in self.extra_slice_viewers.iter_mut() {
+            viewer.set_reference(factory, reference);
+        }
+    }
+
+    // Splits the scatterers into the facets of a quarter wavelength, and evaluates the pressure on
+    // them in the field of the current drive, which is shown on all the slices
+    fn update_scattering(&mut self, render_sys: &mut RenderSystem) {
+        let meshes: Vec<_> = self
+            .fixtures
+            .iter()
+            .filter(|f| f.setting.scatterer)
+            .filter_map(|f| f.mesh.as_ref().ok())
+            .collect();
+        let scattering = if meshes.is_empty() {
+            None
+        } else {
+            let facets = Facet::split(&meshes, self.setting.viewer_setting.wave_length / 4.0);
+            let model = FieldModel {
+                sources: &self.drive.sources,
+                settings: &self.setting.viewer_setting,
+                directivity_table: self.field_slice_viewer.directivity_table(),
+                scattering: None,
+                device_sizes: &self.drive.device_sizes,
+            };
+            Some(Scattering::new(facets, &model))
+        };
+        if scattering.is_none() && self.scattering.is_none() {
+            return;
+        }
+        // the facets of the reference are the same, with the pressure in its field
+        if let Some(reference) = &mut self.reference {
+            let sources = reference.sources(&self.drive.sources);
+            let model = FieldModel {
+                sources: &sources,
+                settings: &self.setting.viewer_setting,
+                directivity_table: self.field_slice_viewer.directivity_table(),
+                scattering: None,
+                device_sizes: &self.drive.device_sizes,
+            };
+            reference.scattering = scattering
+                .as_ref()
+                .map(|s| Scattering::new(s.facets.clone(), &model));
+            self.reference_changed = true;
+        }
+        let factory = &mut render_sys.factory;
+        self.field_slice_viewer
+            .set_scattering(factory, scattering.as_ref());
+        for viewer in self.extra_slice_viewers.iter_mut() {
+            viewer.set_scattering(factory, scattering.as_ref());
+        }
+        self.scattering = scattering;
+    }
+
+    // The SPL map over the dynamic range below the maximum in the color map of the slice, in a
+    // panel, and the frustum of the camera in the 3D view
+    fn draw_lobes(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 0.5, 0.5, 1.0];
+        const SIZE: f32 = 5.0;
+
+        let report = match &self.lobe_report {
+            Some(report) => report,
+            None => return,
+        };
+        let screen_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        for lobe in &report.secondary {
+            let p =
+                match camera_helper::project_to_screen(self.view_projection, lobe.pos, screen_size)
+                {
+                    Some(p) => p,
+                    None => continue,
+                };
+            draw_list
+                .add_triangle(
+                    [p[0], p[1] - SIZE],
+                    [p[0] + SIZE, p[1] + SIZE],
+                    [p[0] - SIZE, p[1] + SIZE],
+                    COLOR,
+                )
+                .build();
+            draw_list.add_text(
+                [p[0] + SIZE + 2.0, p[1] - SIZE],
+                COLOR,
+                format!("{} dB", self.setting.locale.number(lobe.level as f64, 1)),
+            );
+        }
+    }
+
+    fn export_tab(&mut self, ui: &Ui) {
+        let mut remove = None;
+        let mut export = None;
+        for (i, preset) in self.setting.export_presets.iter_mut().enumerate() {
+            let id = ui.push_id(i as i32);
+            let hotkey = if i < EXPORT_HOTKEYS.len() {
+                format!(" (F{})", i + 1)
+            } else {
+                String::new()
+            };
+            if CollapsingHeader::new(&ImString::new(format!("{}{}", preset.name, hotkey))).build(ui)
+            {
+                let mut name = ImString::with_capacity(64);
+                name.push_str(&preset.name);
+                if ui.input_text(im_str!("Name"), &mut name).build() {
+                    preset.name = name.to_str().to_owned();
+                }
+                let mut format = ExportFormat::ALL
+                    .iter()
+                    .position(|&f| f == preset.format)
+                    .unwrap_or(0);
+                if ComboBox::new(im_str!("Format")).build_simple(
+                    ui,
+                    &mut format,
+                    &ExportFormat::ALL,
+                    &|f| ImString::new(f.extension()).into(),
+                ) {
+                    preset.format = ExportFormat::ALL[format];
+                }
+                Drag::new(im_str!("Resolution [mm]"))
+                    .speed(0.01)
+                    .range(0.01..=f32::INFINITY)
+                    .build(ui, &mut preset.resolution);
+                if let Some((resolution, _)) = self.field_slice_viewer.sampling() {
+                    ui.same_line(0.);
+                    if ui.small_button(im_str!("Slice sampling")) {
+                        preset.resolution = resolution;
+                    }
+                }
+                if preset.format.has_values() {
+                    let mut unit = LengthUnit::ALL
+                        .iter()
+                        .position(|&u| u == preset.unit)
+                        .unwrap_or(0);
+                    if ComboBox::new(im_str!("Unit")).build_simple(
+                        ui,
+                        &mut unit,
+                        &LengthUnit::ALL,
+                        &|u| ImString::new(u.name()).into(),
+                    ) {
+                        preset.unit = LengthUnit::ALL[unit];
+                    }
+                    ui.checkbox(im_str!("Complex pressure"), &mut preset.complex);
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(
+                            "Real and imaginary parts of the pressure instead of the quantity",
+                        );
+                    }
+                }
+                let mut folder = ImString::with_capacity(256);
+                folder.push_str(&preset.folder);
+                if ui.input_text(im_str!("Folder"), &mut folder).build() {
+                    preset.folder = folder.to_str().to_owned();
+                }
+                let mut filename = ImString::with_capacity(256);
+                filename.push_str(&preset.filename);
+                if ui.input_text(im_str!("File name"), &mut filename).build() {
+                    preset.filename = filename.to_str().to_owned();
+                }
+                if ui.small_button(im_str!("Export")) {
+                    export = Some(preset.name.clone());
+                }
+                ui.same_line(0.);
+                if ui.small_button(im_str!("Remove")) {
+                    remove = Some(i);
+                }
+            }
+            id.pop(ui);
+        }
+        if let Some(i) = remove {
+            self.setting.export_presets.remove(i);
+        }
+        if let Some(name) = export {
+            self.export_requested.push(name);
+        }
+        ui.separator();
+        if ui.small_button(im_str!("Add preset")) {
+            let name = format!("preset{}", self.setting.export_presets.len());
+            self.setting.export_presets.push(ExportPreset::new(&name));
+        }
+        ui.text_disabled("{name}, {timestamp} and {gain} in the file name are replaced");
+
+        ui.separator();
+        ui.text("Screenshot");
+        let mut folder = ImString::with_capacity(256);
+        folder.push_str(&self.setting.screenshot_folder);
+        if ui
+            .input_text(im_str!("Folder##screenshot"), &mut folder)
+            .build()
+        {
+            self.setting.screenshot_folder = folder.to_str().to_owned();
+        }
+        ui.checkbox(
+            im_str!("Transparent background"),
+            &mut self.setting.screenshot_transparent,
+        );
+        if ui.small_button(im_str!("Take screenshot (I)")) {
+            self.screenshot_requested.push(String::new());
+        }
+        ui.text_disabled("The view is saved without the UI");
+
+        ui.separator();
+        ui.text("Video");
+        let setting = &mut self.setting.video;
+        let recording = self.video.is_some();
+        if !recording {
+            let mut format = VideoFormat::ALL
+                .iter()
+                .position(|&f| f == setting.format)
+                .unwrap_or(0);
+            if ComboBox::new(im_str!("Format##video")).build_simple(
+                ui,
+                &mut format,
+                &VideoFormat::ALL,
+                &|f| ImString::new(f.name()).into(),
+            ) {
+                setting.format = VideoFormat::ALL[format];
+            }
+            Drag::new(im_str!("Frame rate [fps]"))
+                .range(1.0..=setting.format.max_fps())
+                .speed(0.1)
+                .build(ui, &mut setting.fps);
+            if setting.format == VideoFormat::Mp4 {
+                let mut ffmpeg = ImString::with_capacity(256);
+                ffmpeg.push_str(&setting.ffmpeg);
+                if ui.input_text(im_str!("ffmpeg"), &mut ffmpeg).build() {
+                    setting.ffmpeg = ffmpeg.to_str().to_owned();
+                }
+            }
+        }
+        let label = if recording {
+            im_str!("Stop recording (V)")
+        } else {
+            im_str!("Start recording (V)")
+        };
+        if ui.small_button(label) {
+            self.video_requested = true;
+        }
+        if let Some(video) = &self.video {
+            ui.text_colored(
+                [1.0, 0.3, 0.3, 1.0],
+                format!(
+                    "REC {:.1} s, {} frames ({} dropped)",
+                    video.elapsed().as_secs_f32(),
+                    video.frames(),
+                    video.dropped()
+                ),
+            );
+            ui.text_disabled(video.path().display().to_string());
+        }
+    }
+
+    // Progress of the I/O worker at the lower right corner
+    fn draw_toasts(&self, ui: &Ui) {
+        if self.toasts.is_empty() && self.io_worker.pending().is_empty() {
+            return;
+        }
+        let screen_size = ui.io().display_size;
+        Window::new(im_str!("I/O"))
+            .position(
+                [screen_size[0] - 10.0, screen_size[1] - 10.0],
+                Condition::Always,
+            )
+            .position_pivot([1.0, 1.0])
+            .no_decoration()
+            .no_inputs()
+            .always_auto_resize(true)
+            .bg_alpha(0.6)
+            .build(ui, || {
+                for name in self.io_worker.pending() {
+                    ui.text(format!("{}...", name));
+                }
+                for (result, _) in &self.toasts {
+                    match &result.result {
+                        Ok(()) => ui.text(format!(
+                            "{}: done in {:.1} s",
+                            result.name,
+                            result.elapsed.as_secs_f32()
+                        )),
+                        Err(e) => {
+                            ui.text_colored([1.0, 0.3, 0.3, 1.0], format!("{}: {}", result.name, e))
+                        }
+                    }
+                }
+            });
+    }
+
+    fn draw_waiting(&self, ui: &Ui) {
+        let screen_size = ui.io().display_size;
+        Window::new(im_str!("Waiting"))
+            .position(
+                [screen_size[0] / 2.0, screen_size[1] / 2.0],
+                Condition::Always,
+            )
+            .position_pivot([0.5, 0.5])
+            .no_decoration()
+            .no_inputs()
+            .always_auto_resize(true)
+            .bg_alpha(0.6)
+            .build(ui, || {
+                ui.text(format!(
+                    "Waiting for connection on port {}",
+                    self.setting.port
+                ));
+                ui.text_disabled(
+                    "The transducers shown are a placeholder until the SDK sends the geometry",
+                );
+            });
+    }
+
+    // The local axes are x along right in red, y along up in green and z along the normal in blue
+    fn draw_devices(&self, ui: &Ui) {
+        const OUTLINE: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+        const AXES: [[f32; 4]; 3] = [
+            [1.0, 0.3, 0.3, 1.0],
+            [0.3, 1.0, 0.3, 1.0],
+            [0.4, 0.6, 1.0, 1.0],
+        ];
+        const AXIS_LENGTH: f32 = 30.0;
+
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        for (i, geometry) in self.drive.geometries.iter().enumerate() {
+            let corners = geometry.board_corners();
+            for (j, &a) in corners.iter().enumerate() {
+                if let (Some(a), Some(b)) = (project(a), project(corners[(j + 1) % 4])) {
+                    draw_list.add_line(a, b, OUTLINE).thickness(1.5).build();
+                }
+            }
+            if let Some(o) = project(geometry.origin) {
+                for (axis, color) in [geometry.right, geometry.up, geometry.normal()]
+                    .iter()
+                    .zip(AXES)
+                {
+                    let tip =
+                        vecmath::vec3_add(geometry.origin, vecmath::vec3_scale(*axis, AXIS_LENGTH));
+                    if let Some(tip) = project(tip) {
+                        draw_list.add_line(o, tip, color).thickness(2.0).build();
+                    }
+                }
+            }
+            if let Some(p) = project(corners[3]) {
+                draw_list.add_text([p[0] + 4.0, p[1] + 4.0], OUTLINE, format!("{}", i));
+            }
+        }
+    }
+
+    // Device index and index in the device of the `i`-th source, None if the devices are unknown
+    fn device_index(&self, i: usize) -> Option<(usize, usize)> {
+        let mut offset = 0;
+        for (dev, &size) in self.drive.device_sizes.iter().enumerate() {
+            if i < offset + size {
+                return Some((dev, i - offset));
+            }
+            offset += size;
+        }
+        None
+    }
+
+    // Lines of the index, the position and the drive of the `i`-th source
+    fn source_info(&self, i: usize) -> Vec<String> {
+        let locale = &self.setting.locale;
+        let source = &self.drive.sources[i];
+        let frequency = source
+            .frequency
+            .unwrap_or(self.setting.viewer_setting.frequency);
+        let mut lines = vec![match self.device_index(i) {
+            Some((dev, local)) => format!("Device {}, transducer {} (global {})", dev, local, i),
+            None => format!("Transducer {}", i),
+        }];
+        lines.push(format!("Position: {} [mm]", locale.vec3(source.pos, 1)));
+        lines.push(format!(
+            "Phase: {} [rad] ({} [us] of {} [kHz])",
+            locale.number(source.phase as f64, 3),
+            locale.number((source.phase / (2.0 * PI * frequency)) as f64 * 1e6, 2),
+            locale.number(frequency as f64 / 1000.0, 3),
+        ));
+        lines.push(format!(
+            "Amplitude: {}",
+            locale.number(source.amp as f64, 3)
+        ));
+        let entry = self.drive.frame_entries().get(i).copied();
+        if let Some((delay, offset)) = self
+            .drive
+            .delay_offset
+            .as_ref()
+            .zip(entry)
+            .and_then(|(d, entry)| d.delay_offset.get(entry))
+        {
+            lines.push(format!("Delay: {}, duty offset: {}", delay, offset));
+        }
+        if self.drive.drive_overrides.contains_key(&i) {
+            lines.push("Drive overridden in the Info tab".to_owned());
+        }
+        lines
+    }
+
+    fn draw_source_tooltip(&self, ui: &Ui) {
+        let i = match self.hovered {
+            Some(PickId::Source(i)) if i < self.drive.sources.len() => i,
+            _ => return,
+        };
+        if ui.io().want_capture_mouse {
+            return;
+        }
+        ui.tooltip(|| {
+            for line in self.source_info(i) {
+                ui.text(line);
+            }
+        });
+    }
+
+    // The global axes seen from the camera, the farther ones drawn first
+    fn draw_axes_gizmo(&self, ui: &Ui) {
+        const AXES: [(&str, [f32; 4]); 3] = [
+            ("X", [1.0, 0.3, 0.3, 1.0]),
+            ("Y", [0.3, 1.0, 0.3, 1.0]),
+            ("Z", [0.4, 0.6, 1.0, 1.0]),
+        ];
+        const LENGTH: f32 = 36.0;
+        const MARGIN: f32 = 56.0;
+
+        let screen_size = ui.io().display_size;
+        let o = [screen_size[0] - MARGIN, screen_size[1] - MARGIN];
+        let (view, _) = self.view_projection;
+        // the global axes in the camera coordinates, with y up and z toward the camera
+        let mut axes: Vec<_> = AXES
+            .iter()
+            .enumerate()
+            .map(|(i, &(label, color))| (view[i], label, color))
+            .collect();
+        axes.sort_by(|a, b| a.0[2].total_cmp(&b.0[2]));
+        let draw_list = ui.get_background_draw_list();
+        draw_list
+            .add_circle(o, LENGTH + 12.0, [0.0, 0.0, 0.0, 0.4])
+            .filled(true)
+            .build();
+        for (v, label, color) in axes {
+            let tip = [o[0] + LENGTH * v[0], o[1] - LENGTH * v[1]];
+            draw_list.add_line(o, tip, color).thickness(2.0).build();
+            draw_list.add_text([tip[0] - 3.0, tip[1] - 7.0], color, label);
+        }
+    }
+
+    // The length is 1, 2 or 5 times a power of 10 in mm, so that the bar is about a fixed length
+    fn draw_scale_ruler(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+        const TARGET: f32 = 120.0;
+
+        let screen_size = ui.io().display_size;
+        let (view, _) = self.view_projection;
+        let right = [view[0][0], view[1][0], view[2][0]];
+        let center = vecmath_util::to_vec3(&self.setting.viewer_setting.slice_pos);
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let (a, b) = match (project(center), project(vecmath::vec3_add(center, right))) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return,
+        };
+        let px_per_mm = ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt();
+        if !px_per_mm.is_finite() || px_per_mm <= 0.0 {
+            return;
+        }
+        let raw = TARGET / px_per_mm;
+        let base = 10f32.powf(raw.log10().floor());
+        let mm = [5.0, 2.0, 1.0]
+            .iter()
+            .map(|m| m * base)
+            .find(|&mm| mm <= raw)
+            .unwrap_or(base);
+        let len = mm * px_per_mm;
+        // at the left of the gizmo of the axes, away from the coordinate readout at the left
+        let x1 = screen_size[0] - if self.setting.show_axes { 124.0 } else { 16.0 };
+        let x0 = x1 - len;
+        let y = screen_size[1] - 24.0;
+        let draw_list = ui.get_background_draw_list();
+        draw_list
+            .add_line([x0, y], [x0 + len, y], COLOR)
+            .thickness(2.0)
+            .build();
+        for x in [x0, x0 + len] {
+            draw_list
+                .add_line([x, y - 5.0], [x, y + 5.0], COLOR)
+                .build();
+        }
+        let precision = (-base.log10().round()).max(0.0) as usize;
+        let text = format!("{} mm", self.setting.locale.number(mm as f64, precision));
+        draw_list.add_text([x0, y - 20.0], COLOR, text);
+    }
+
+    // Position under the mouse on the slice, or on the plane of the first transducer outside it.
+    // Whether the slice is under the mouse is taken from the picking buffer, so that the slice
+    // behind the transducers is not read out.
+    fn cursor_position(&self, ui: &Ui) -> Option<(&'static str, Vector3)> {
+        let mouse = ui.io().mouse_pos;
+        let screen_size = ui.io().display_size;
+        if ui.io().want_capture_mouse
+            || mouse[0] < 0.0
+            || mouse[1] < 0.0
+            || mouse[0] > screen_size[0]
+            || mouse[1] > screen_size[1]
+        {
+            return None;
+        }
+        let ray = camera_helper::unproject_ray(self.view_projection, mouse, screen_size);
+        if self.hovered == Some(PickId::Slice) {
+            let model = self.field_slice_viewer.model();
+            let origin = vecmath_util::to_vec3(&model[3]);
+            let normal = vecmath::vec3_normalized(vecmath_util::to_vec3(&model[2]));
+            if let Some(p) = camera_helper::intersect_plane(ray, origin, normal) {
+                return Some(("Slice", p));
+            }
+        }
+        let (point, normal) = self
+            .drive
+            .sources
+            .first()
+            .map_or(([0.0; 3], [0.0, 0.0, 1.0]), |s| (s.pos, s.dir));
+        camera_helper::intersect_plane(ray, point, normal).map(|p| ("Device plane", p))
+    }
+
+    fn probe_ui(&mut self, ui: &Ui) {
+        ui.checkbox(im_str!("Probe"), &mut self.setting.show_probe);
+        if !self.setting.show_probe {
+            return;
+        }
+        Drag::new(im_str!("Probe position [mm]"))
+            .speed(0.1)
+            .build_array(ui, &mut self.setting.probe_pos);
+        if ui.small_button(im_str!("Probe at slice center")) {
+            self.setting.probe_pos = vecmath_util::to_vec3(&self.field_slice_viewer.model()[3]);
+        }
+        let (amp, phase, spl) = self.probe_pressure();
+        let locale = &self.setting.locale;
+        let settings = &self.setting.viewer_setting;
+        ui.text(format!(
+            "|p| = {} [{}]",
+            locale.number(
+                settings.pressure_unit.convert(amp, &settings.calibration) as f64,
+                3
+            ),
+            settings.pressure_unit.name()
+        ));
+        ui.text(format!("arg(p) = {} [deg]", locale.number(phase as f64, 1)));
+        ui.text(format!("SPL = {} [dB]", locale.number(spl as f64, 1)));
+        ui.text_disabled("Drag the marker to move the probe parallel to the slice");
+
+        let metadata = self.metadata();
+        if self
+            .hydrophone
+            .ui(ui, &mut self.setting, &metadata, &mut self.io_worker)
+        {
+            self.record_hydrophone();
+        }
+    }
+
+    // Synthesized from the current drive, modulation and STM
+    fn record_hydrophone(&mut self) {
+        let stm_points = self.drive.stm_points();
+        let stm = self
+            .drive
+            .sequence
+            .as_ref()
+            .filter(|_| {
+                self.drive
+                    .ctrl_flag
+                    .contains(RxGlobalControlFlags::SEQ_MODE)
+            })
+            .map(|seq| (seq.seq_div, stm_points.as_slice()));
+        let source = TraceSource {
+            sources: &self.drive.sources,
+            device_sizes: &self.drive.device_sizes,
+            settings: &self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            modulation: self.drive.modulation.as_ref(),
+            stm,
+        };
+        self.hydrophone
+            .record(&source, &self.setting, self.clock.time());
+    }
+
+    // Amplitude, phase in degree and SPL in dB at the probe
+    fn probe_pressure(&self) -> (f32, f32, f32) {
+        let settings = &self.setting.viewer_setting;
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: self.scattering.as_ref(),
+            device_sizes: &self.drive.device_sizes,
+        };
+        let [re, im] = model.pressure_at(self.setting.probe_pos);
+        let amp = (re * re + im * im).sqrt();
+        let spl = calibration::pa_to_spl(settings.calibration.to_pa(amp));
+        (amp, im.atan2(re).to_degrees(), spl)
+    }
+
+    // The right drag orbits the camera around the look-at target, the middle drag or the right
+    // drag with Shift pans it with the target, and the wheel zooms toward the target
+    fn update_mouse_navigation(&mut self, ui: &Ui, render_sys: &mut RenderSystem) -> UpdateFlag {
+        // radians per pixel
+        const ORBIT_SPEED: f32 = 0.005;
+        const ZOOM_STEP: f32 = 0.9;
+
+        let io = ui.io();
+        let camera = &mut render_sys.camera;
+        let target = self.setting.camera_target;
+        let mut moved = false;
+        if !io.want_capture_mouse {
+            for button in [imgui::MouseButton::Right, imgui::MouseButton::Middle] {
+                if ui.is_mouse_clicked(button) {
+                    self.camera_drag = Some(button);
+                    self.camera.stop();
+                }
+            }
+            if io.mouse_wheel != 0.0 {
+                self.camera.stop();
+                self.view_controller
+                    .zoom(camera, target, ZOOM_STEP.powf(io.mouse_wheel));
+                moved = true;
+            }
+        }
+        match self.camera_drag {
+            Some(button) if ui.is_mouse_down(button) => {
+                let [dx, dy] = io.mouse_delta;
+                if dx == 0.0 && dy == 0.0 {
+                    self.view_controller.stop();
+                } else if button == imgui::MouseButton::Middle || io.key_shift {
+                    let size = ViewController::pixel_size(
+                        camera,
+                        target,
+                        self.setting.viewer_setting.fov,
+                        io.display_size[1],
+                    );
+                    self.view_controller.pan(
+                        camera,
+                        &mut self.setting.camera_target,
+                        -dx * size,
+                        dy * size,
+                    );
+                    moved = true;
+                } else {
+                    self.view_controller.orbit(
+                        camera,
+                        target,
+                        -dx * ORBIT_SPEED,
+                        dy * ORBIT_SPEED,
+                        io.delta_time,
+                    );
+                    moved = true;
+                }
+            }
+            Some(_) => self.camera_drag = None,
+            None => {
+                moved |= self.view_controller.coast(
+                    camera,
+                    target,
+                    io.delta_time,
+                    self.setting.orbit_inertia,
+                )
+            }
+        }
+        if !moved {
+            return UpdateFlag::empty();
+        }
+        self.sync_camera_setting(render_sys)
+    }
+
+    // The angles keep the direction of the view, but not the roll of the upright camera
+    fn sync_camera_setting(&mut self, render_sys: &RenderSystem) -> UpdateFlag {
+        let camera = &render_sys.camera;
+        let viewer_setting = &mut self.setting.viewer_setting;
+        viewer_setting.camera_pos = camera.position;
+        viewer_setting.camera_angle = camera_helper::euler_angles_facing(camera.forward);
+        self.view_projection = render_sys.get_view_projection(viewer_setting);
+        UpdateFlag::UPDATE_CAMERA_POS
+    }
+
+    // The pose of the slice at the start of the drag is kept, so that the snapped rotation does not
+    // accumulate the rounding of every frame and the rings do not turn under the mouse
+    fn update_slice_gizmo(&mut self, ui: &Ui) -> UpdateFlag {
+        const RADIUS: f32 = 60.0;
+        const TOLERANCE: f32 = 6.0;
+        const COLORS: [[f32; 4]; 3] = [
+            [1.0, 0.3, 0.3, 1.0],
+            [0.3, 1.0, 0.3, 1.0],
+            [0.3, 0.5, 1.0, 1.0],
+        ];
+        const ACTIVE: [f32; 4] = [1.0, 1.0, 0.3, 1.0];
+
+        let mut update_flag = UpdateFlag::empty();
+        let screen_size = ui.io().display_size;
+        let mouse = ui.io().mouse_pos;
+        let view_projection = self.view_projection;
+        let ray = camera_helper::unproject_ray(view_projection, mouse, screen_size);
+        let gizmo = match SliceGizmo::new(
+            self.field_slice_viewer.model(),
+            view_projection,
+            screen_size,
+            RADIUS,
+        ) {
+            Some(gizmo) => gizmo,
+            None => return update_flag,
+        };
+        let hovered = if ui.io().want_capture_mouse || self.probe_dragging {
+            None
+        } else {
+            gizmo.hit(view_projection, screen_size, mouse, TOLERANCE)
+        };
+        if let Some(handle) = hovered {
+            if self.slice_drag.is_none() && ui.is_mouse_clicked(imgui::MouseButton::Left) {
+                let angle = self.setting.viewer_setting.slice_angle;
+                self.slice_drag = GizmoDrag::start(gizmo, angle, handle, ray);
+                self.pick_requested = false;
+            }
+        }
+        if let Some(drag) = self.slice_drag {
+            if ui.is_mouse_down(imgui::MouseButton::Left) {
+                if let Some((center, angle)) = drag.pose(ray, self.setting.slice_gizmo_snap) {
+                    let viewer_setting = &mut self.setting.viewer_setting;
+                    viewer_setting.slice_pos =
+                        [center[0], center[1], center[2], viewer_setting.slice_pos[3]];
+                    viewer_setting.slice_angle = angle;
+                    self.field_slice_viewer.move_to(viewer_setting.slice_pos);
+                    self.field_slice_viewer
+                        .rotate_to(viewer_setting.slice_angle);
+                    update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                }
+                self.pick_requested = false;
+            } else {
+                self.slice_drag = None;
+            }
+        }
+
+        let active = self.slice_drag.map(|d| d.handle).or(hovered);
+        let color = |handle, c| if active == Some(handle) { ACTIVE } else { c };
+        let project = |p| camera_helper::project_to_screen(view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        for (i, &c) in COLORS.iter().enumerate() {
+            let points: Vec<_> = gizmo.ring(i).into_iter().map(project).collect();
+            for w in points.windows(2) {
+                if let (Some(a), Some(b)) = (w[0], w[1]) {
+                    draw_list
+                        .add_line(a, b, color(GizmoHandle::Rotate(i), c))
+                        .thickness(2.0)
+                        .build();
+                }
+            }
+        }
+        if let (Some(c), Some(tip)) = (project(gizmo.center), project(gizmo.arrow_tip())) {
+            let c2 = color(GizmoHandle::Translate, COLORS[2]);
+            draw_list.add_line(c, tip, c2).thickness(3.0).build();
+            draw_list.add_circle(tip, 5.0, c2).filled(true).build();
+        }
+        update_flag
+    }
+
+    // The probe is dragged on the plane through it parallel to the slice, so that it does not run
+    // away along the line of sight
+    fn update_probe(&mut self, ui: &Ui) {
+        const COLOR: [f32; 4] = [0.3, 1.0, 0.3, 1.0];
+        const SIZE: f32 = 7.0;
+
+        let screen_size = ui.io().display_size;
+        let mouse = ui.io().mouse_pos;
+        let marker = camera_helper::project_to_screen(
+            self.view_projection,
+            self.setting.probe_pos,
+            screen_size,
+        );
+        if let Some(p) = marker {
+            let hit =
+                (mouse[0] - p[0]).abs() <= SIZE * 1.5 && (mouse[1] - p[1]).abs() <= SIZE * 1.5;
+            if hit && !ui.io().want_capture_mouse && ui.is_mouse_clicked(imgui::MouseButton::Left) {
+                self.probe_dragging = true;
+                self.pick_requested = false;
+            }
+        }
+        if self.probe_dragging {
+            if ui.is_mouse_down(imgui::MouseButton::Left) {
+                let ray = camera_helper::unproject_ray(self.view_projection, mouse, screen_size);
+                let normal = vecmath::vec3_normalized(vecmath_util::to_vec3(
+                    &self.field_slice_viewer.model()[2],
+                ));
+                if let Some(q) = camera_helper::intersect_plane(ray, self.setting.probe_pos, normal)
+                {
+                    self.setting.probe_pos = q;
+                }
+                self.pick_requested = false;
+            } else {
+                self.probe_dragging = false;
+            }
+        }
+
+        let p = match marker {
+            Some(p) => p,
+            None => return,
+        };
+        let draw_list = ui.get_background_draw_list();
+        let points = [
+            [p[0], p[1] - SIZE],
+            [p[0] + SIZE, p[1]],
+            [p[0], p[1] + SIZE],
+            [p[0] - SIZE, p[1]],
+        ];
+        for i in 0..points.len() {
+            draw_list
+                .add_line(points[i], points[(i + 1) % points.len()], COLOR)
+                .thickness(2.0)
+                .build();
+        }
+        let (amp, phase, spl) = self.probe_pressure();
+        let settings = &self.setting.viewer_setting;
+        let locale = &self.setting.locale;
+        draw_list.add_text(
+            [p[0] + SIZE + 4.0, p[1] - SIZE],
+            COLOR,
+            format!(
+                "{} [{}], {} [deg], {} [dB]",
+                locale.number(
+                    settings.pressure_unit.convert(amp, &settings.calibration) as f64,
+                    2
+                ),
+                settings.pressure_unit.name(),
+                locale.number(phase as f64, 1),
+                locale.number(spl as f64, 1)
+            ),
+        );
+    }
+
+    fn target_region_ui(&mut self, ui: &Ui) {
+        ui.checkbox(
+            im_str!("Target region"),
+            &mut self.setting.show_target_region,
+        );
+        if !self.setting.show_target_region {
+            return;
+        }
+        let region = &mut self.setting.target_region;
+        let is_disk = matches!(region.shape, RegionShape::Disk { .. });
+        if ui.radio_button_bool(im_str!("Disk"), is_disk) && !is_disk {
+            region.shape = RegionShape::Disk { radius: 40.0 };
+        }
+        ui.same_line(0.);
+        if ui.radio_button_bool(im_str!("Box"), !is_disk) && is_disk {
+            region.shape = RegionShape::Box {
+                size: [80.0, 80.0, 20.0],
+            };
+        }
+        Drag::new(im_str!("Region center [mm]"))
+            .speed(0.1)
+            .build_array(ui, &mut region.center);
+        match &mut region.shape {
+            RegionShape::Disk { radius } => {
+                Drag::new(im_str!("Radius [mm]##region"))
+                    .speed(0.1)
+                    .range(0.1..=f32::INFINITY)
+                    .build(ui, radius);
+                Drag::new(im_str!("Normal"))
+                    .speed(0.01)
+                    .build_array(ui, &mut region.normal);
+            }
+            RegionShape::Box { size } => {
+                Drag::new(im_str!("Size [mm]##region"))
+                    .speed(0.1)
+                    .range(0.1..=f32::INFINITY)
+                    .build_array(ui, size);
+            }
+        }
+        Drag::new(im_str!("Sampling [mm]##region"))
+            .speed(0.01)
+            .range(0.1..=f32::INFINITY)
+            .build(ui, &mut region.resolution);
+        if ui.small_button(im_str!("Region at slice center")) {
+            region.center = vecmath_util::to_vec3(&self.field_slice_viewer.model()[3]);
+        }
+        if let Some((integral, _)) = self.region_integral {
+            let locale = &self.setting.locale;
+            match self.setting.target_region.shape {
+                RegionShape::Disk { .. } => ui.text(format!(
+                    "Power = {} [mW]",
+                    locale.number(integral.integral as f64 * 1e3, 3)
+                )),
+                RegionShape::Box { .. } => ui.text(format!(
+                    "Energy = {} [uJ]",
+                    locale.number(integral.integral as f64 * 1e6, 3)
+                )),
+            }
+            ui.text(format!(
+                "Mean intensity = {} [W/m^2]",
+                locale.number(integral.mean_intensity as f64, 2)
+            ));
+            ui.text(format!(
+                "Peak pressure = {} [Pa]",
+                locale.number(integral.peak_pressure as f64, 1)
+            ));
+            ui.text_disabled(format!("{} points", integral.samples));
+        }
+    }
+
+    // Analyzed when first shown for each sequence, since it computes the field for every focus
+    fn stm_duty_ui(&mut self, ui: &Ui) {
+        if self.drive.sequence.is_none() {
+            return;
+        }
+        if !CollapsingHeader::new(im_str!("Duty analysis")).build(ui) {
+            return;
+        }
+        if ui.small_button(im_str!("Reanalyze")) {
+            self.stm_duty = None;
+        }
+        let drive = &self.drive;
+        let settings = &self.setting.viewer_setting;
+        let directivity_table = self.field_slice_viewer.directivity_table();
+        let foci = self.stm_duty.get_or_insert_with(|| {
+            stm_duty::analyze(
+                &drive.stm_points(),
+                &drive.sources,
+                &drive.device_sizes,
+                settings,
+                directivity_table,
+            )
+        });
+        let foci = match foci {
+            Some(foci) => foci,
+            None => {
+                ui.text_disabled(format!(
+                    "Not a time-multiplexing of up to {} foci",
+                    stm_duty::MAX_STM_FOCI
+                ));
+                return;
+            }
+        };
+        let locale = &self.setting.locale;
+        let unit = settings.pressure_unit;
+        let value = |amp: f32| locale.number(unit.convert(amp, &settings.calibration) as f64, 2);
+        ui.text(format!("Amplitude in [{}]", unit.name()));
+        ui.columns(5, im_str!("stm duty"), true);
+        for header in [
+            "Focus [mm]",
+            "Dwell [%]",
+            "Focused",
+            "STM RMS",
+            "Multi-focus",
+        ] {
+            ui.text(header);
+            ui.next_column();
+        }
+        ui.separator();
+        for focus in foci.iter() {
+            ui.text(format!(
+                "({}, {}, {})",
+                locale.number(focus.pos[0] as f64, 1),
+                locale.number(focus.pos[1] as f64, 1),
+                locale.number(focus.pos[2] as f64, 1)
+            ));
+            ui.next_column();
+            ui.text(locale.number(focus.dwell as f64 * 100.0, 1));
+            ui.next_column();
+            ui.text(value(focus.focused));
+            ui.next_column();
+            ui.text(value(focus.stm_rms));
+            ui.next_column();
+            ui.text(value(focus.multi_focus));
+            ui.next_column();
+        }
+        ui.columns(1, im_str!(""), false);
+        let mean = |f: fn(&FocusDuty) -> f32| foci.iter().map(f).sum::<f32>() / foci.len() as f32;
+        let (stm, multi) = (mean(|f| f.stm_rms), mean(|f| f.multi_focus));
+        ui.text(format!(
+            "Mean: STM RMS {} / multi-focus {}, higher time-averaged pressure with {}",
+            value(stm),
+            value(multi),
+            if stm >= multi {
+                "the STM"
+            } else {
+                "the multi-focus gain"
+            }
+        ));
+        ui.text_disabled("The multi-focus gain is phase-only at the maximum duty of the STM");
+    }
+
+    fn volume_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        const TRANSFER_POINTS: usize = 64;
+
+        ui.checkbox(im_str!("Volume rendering"), &mut self.setting.show_volume);
+        ui.same_line(0.);
+        ui.checkbox(im_str!("Isosurface"), &mut self.setting.show_isosurface);
+        if !self.setting.show_volume && !self.setting.show_isosurface {
+            return UpdateFlag::empty();
+        }
+        let volume = &mut self.setting.volume;
+        Drag::new(im_str!("Center [mm]##volume"))
+            .speed(1.0)
+            .build_array(ui, &mut volume.center);
+        Drag::new(im_str!("Size [mm]##volume"))
+            .speed(1.0)
+            .range(1.0..=f32::INFINITY)
+            .build_array(ui, &mut volume.size);
+        Drag::new(im_str!("Resolution [mm]##volume"))
+            .speed(0.1)
+            .range(0.5..=f32::INFINITY)
+            .build(ui, &mut volume.resolution);
+        let [w, h, d] = volume.dims();
+        ui.text_disabled(format!("{} x {} x {} points", w, h, d));
+        if self.setting.show_volume {
+            let mip = volume.mode == VolumeMode::MaximumIntensity;
+            if ui.radio_button_bool(im_str!("Composite"), !mip) {
+                volume.mode = VolumeMode::Composite;
+            }
+            ui.same_line(0.);
+            if ui.radio_button_bool(im_str!("Maximum intensity projection"), mip) {
+                volume.mode = VolumeMode::MaximumIntensity;
+            }
+            Drag::new(im_str!("Steps##volume"))
+                .range(8..=1024)
+                .build(ui, &mut volume.steps);
+            ui.text(im_str!("Transfer function"));
+            if !mip {
+                Slider::new(im_str!("Opacity / 10 mm"))
+                    .range(0.0..=1.0)
+                    .build(ui, &mut volume.opacity);
+            }
+            Slider::new(im_str!("Threshold"))
+                .range(0.0..=0.99)
+                .build(ui, &mut volume.threshold);
+            Drag::new(im_str!("Falloff"))
+                .speed(0.05)
+                .range(0.1..=10.0)
+                .build(ui, &mut volume.falloff);
+            let transfer: Vec<_> = (0..TRANSFER_POINTS)
+                .map(|i| volume.transfer(i as f32 / (TRANSFER_POINTS - 1) as f32))
+                .collect();
+            PlotLines::new(ui, im_str!("Opacity##transfer"), &transfer)
+                .scale_min(0.0)
+                .scale_max(1.0)
+                .graph_size([0.0, 60.0])
+                .build();
+        }
+        if self.volume_sampler.is_busy() {
+            ui.text_disabled("Sampling...");
+        }
+        let mut update_flag = UpdateFlag::empty();
+        if let Some((pos, _)) = self.volume_grid.as_ref().and_then(|grid| grid.argmax()) {
+            ui.text(format!(
+                "Hot spot: ({:.1}, {:.1}, {:.1}) mm",
+                pos[0], pos[1], pos[2]
+            ));
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Slice to hot spot")) {
+                let slice_pos = &mut self.setting.viewer_setting.slice_pos;
+                *slice_pos = [pos[0], pos[1], pos[2], slice_pos[3]];
+                self.field_slice_viewer.move_to(*slice_pos);
+                update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+            }
+        }
+        if self.setting.show_isosurface {
+            self.isosurface_ui(ui);
+        }
+        update_flag
+    }
+
+    // Freezes the current drive as A, which the slices show instead of or subtracted from the
+    // current drive B
+    fn compare_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        if ui.small_button(im_str!("Freeze as A")) {
+            if self.drive.sources.is_empty() {
+                tracing::warn!("cannot freeze the drive: no sources");
+            } else {
+                let label = self.tags.current().join(", ");
+                tracing::info!(tags = %label, "freeze the drive as A");
+                self.reference = Some(Reference::freeze(
+                    &self.drive.sources,
+                    self.scattering.clone(),
+                    label,
+                ));
+                self.reference_changed = true;
+                update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+            }
+        }
+        if self.reference.is_some() {
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Clear A")) {
+                self.reference = None;
+                self.reference_changed = true;
+                self.setting.viewer_setting.compare = CompareView::Current;
+                update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+            }
+        }
+        let reference = match &self.reference {
+            Some(reference) => reference,
+            None => {
+                ui.text_disabled("No reference A, showing the current drive");
+                return update_flag;
+            }
+        };
+        let mut view = CompareView::ALL
+            .iter()
+            .position(|&v| v == self.setting.viewer_setting.compare)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Show##compare")).build_simple(
+            ui,
+            &mut view,
+            &CompareView::ALL,
+            &|v| ImString::new(v.name()).into(),
+        ) {
+            self.setting.viewer_setting.compare = CompareView::ALL[view];
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if !reference.label.is_empty() {
+            ui.text(format!("A: {}", reference.label));
+        }
+        let locale = &self.setting.locale;
+        let (amp, phase) = reference.max_difference(&self.drive.sources);
+        ui.text(format!(
+            "Max drive difference: {} amplitude, {} [deg]",
+            locale.number(amp as f64, 3),
+            locale.number(phase.to_degrees() as f64, 1)
+        ));
+        update_flag
+    }
+
+    fn trans_calibration_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        ui.input_text(
+            im_str!("Transducer calibration"),
+            &mut self.trans_calibration_file,
+        )
+        .build();
+        if ui.small_button(im_str!("Import calibration")) {
+            self.trans_calibration_requested = true;
+        }
+        ui.same_line(0.0);
+        if ui.small_button(im_str!("Clear calibration")) {
+            self.drive.trans_calibration = None;
+            self.trans_calibration_file.clear();
+            update_flag |= self.readjust_drive();
+        }
+        match &self.drive.trans_calibration {
+            Some(calibration) => {
+                ui.text(format!("{} transducers calibrated", calibration.len()));
+                let unmatched = calibration.unmatched(&self.drive.device_sizes);
+                if unmatched > 0 {
+                    ui.text_colored(
+                        [1.0, 0.8, 0.0, 1.0],
+                        format!("{} entries out of the geometry", unmatched),
+                    );
+                }
+            }
+            None => ui.text_disabled("Not imported, ideal transducers"),
+        }
+        update_flag
+    }
+
+    fn perturbation_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut changed = false;
+        let perturbation = &mut self.setting.perturbation;
+        changed |= ui.checkbox(im_str!("Error injection"), &mut perturbation.enabled);
+        if perturbation.enabled {
+            changed |= Drag::new(im_str!("Phase jitter sigma [deg]"))
+                .speed(0.1)
+                .range(0.0..=180.0)
+                .build(ui, &mut perturbation.phase_sigma);
+            let mut amp_sigma = perturbation.amp_sigma * 100.0;
+            if Drag::new(im_str!("Amplitude error sigma [%]"))
+                .speed(0.1)
+                .range(0.0..=100.0)
+                .build(ui, &mut amp_sigma)
+            {
+                perturbation.amp_sigma = amp_sigma / 100.0;
+                changed = true;
+            }
+            ui.text(format!("Seed: {}", perturbation.seed));
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Resample")) {
+                perturbation.seed = perturbation.seed.wrapping_add(1);
+                changed = true;
+            }
+            let mut trials = perturbation.trials as i32;
+            if ui.input_int(im_str!("Trials"), &mut trials).build() {
+                perturbation.trials = trials.clamp(1, 1000) as usize;
+            }
+            self.poll_perturbation_trials();
+            if self.trials_worker.is_busy() {
+                ui.text_disabled(im_str!("Running trials..."));
+            } else if ui.small_button(im_str!("Run trials")) {
+                self.run_perturbation_trials();
+            }
+            if let Some(summary) = &self.perturbation_trials {
+                ui.text(format!(
+                    "Peak: {:.1} +/- {:.1} % of {:.3} ({} trials)",
+                    summary.amp_mean * 100.0,
+                    summary.amp_std * 100.0,
+                    summary.nominal_amp,
+                    summary.trials
+                ));
+                ui.text(format!(
+                    "Focus shift: {:.2} mm mean, {:.2} mm max",
+                    summary.shift_mean, summary.shift_max
+                ));
+            }
+        }
+        if !changed {
+            return UpdateFlag::empty();
+        }
+        self.drive.perturbation_sample = None;
+        self.readjust_drive()
+    }
+
+    fn approximation_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let mut approximation = FieldApproximation::ALL
+            .iter()
+            .position(|&a| a == self.setting.viewer_setting.approximation)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Field model")).build_simple(
+            ui,
+            &mut approximation,
+            &FieldApproximation::ALL,
+            &|a| ImString::new(a.name()).into(),
+        ) {
+            self.setting.viewer_setting.approximation = FieldApproximation::ALL[approximation];
+            update_flag |= UpdateFlag::UPDATE_WAVENUM;
+        }
+        if self.setting.viewer_setting.approximation == FieldApproximation::Fresnel {
+            let model = FieldModel {
+                sources: &self.drive.sources,
+                settings: &self.setting.viewer_setting,
+                directivity_table: self.field_slice_viewer.directivity_table(),
+                scattering: None,
+                device_sizes: &self.drive.device_sizes,
+            };
+            let error = model
+                .fresnel_phase_error_on_slice(self.field_slice_viewer.model())
+                .to_degrees();
+            let text = format!("Phase error on slice: < {:.1} deg", error);
+            if error > 45.0 {
+                ui.text_colored([1.0, 0.8, 0.0, 1.0], text);
+            } else {
+                ui.text(text);
+            }
+        }
+        update_flag
+    }
+
+    fn reflector_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        let reflector = &mut self.setting.viewer_setting.reflector;
+        let mut changed = ui.checkbox(im_str!("Reflector"), &mut reflector.enabled);
+        if reflector.enabled {
+            changed |= Drag::new(im_str!("Center [mm]##reflector"))
+                .speed(1.0)
+                .build_array(ui, &mut reflector.center);
+            let mut angle = reflector.angle.map(f32::to_degrees);
+            if Drag::new(im_str!("Angle [deg]##reflector"))
+                .speed(1.0)
+                .build_array(ui, &mut angle)
+            {
+                reflector.angle = angle.map(f32::to_radians);
+                changed = true;
+            }
+            changed |= Slider::new(im_str!("Reflection coefficient"))
+                .range(-1.0..=1.0)
+                .build(ui, &mut reflector.coefficient);
+            Drag::new(im_str!("Outline size [mm]"))
+                .speed(1.0)
+                .range(0.0..=f32::INFINITY)
+                .build_array(ui, &mut reflector.size);
+        }
+        if changed {
+            UpdateFlag::UPDATE_WAVENUM
+        } else {
+            UpdateFlag::empty()
+        }
+    }
+
+    fn obstacles_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut changed = false;
+        for (i, obstacle) in self.setting.viewer_setting.obstacles.iter_mut().enumerate() {
+            let id = ui.push_id(i as i32);
+            let label = ImString::new(format!("Obstacle {}", i));
+            changed |= ui.checkbox(&label, &mut obstacle.enabled);
+            if obstacle.enabled {
+                let mut shape = ObstacleShape::ALL
+                    .iter()
+                    .position(|&s| s == obstacle.shape)
+                    .unwrap_or(0);
+                if ComboBox::new(im_str!("Shape")).build_simple(
+                    ui,
+                    &mut shape,
+                    &ObstacleShape::ALL,
+                    &|s| ImString::new(s.name()).into(),
+                ) {
+                    obstacle.shape = ObstacleShape::ALL[shape];
+                    changed = true;
+                }
+                changed |= Drag::new(im_str!("Center [mm]"))
+                    .speed(1.0)
+                    .build_array(ui, &mut obstacle.center);
+                changed |= Drag::new(im_str!("Half size [mm]"))
+                    .speed(0.5)
+                    .range(0.0..=f32::INFINITY)
+                    .build_array(ui, &mut obstacle.size);
+                let mut angle = obstacle.angle.map(f32::to_degrees);
+                if Drag::new(im_str!("Angle [deg]"))
+                    .speed(1.0)
+                    .build_array(ui, &mut angle)
+                {
+                    obstacle.angle = angle.map(f32::to_radians);
+                    changed = true;
+                }
+            }
+            id.pop(ui);
+        }
+        if changed {
+            UpdateFlag::UPDATE_WAVENUM
+        } else {
+            UpdateFlag::empty()
+        }
+    }
+
+    fn extra_slices_ui(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        ui.text(im_str!("Extra slices"));
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Orthogonal to slice")) {
+            self.setting.extra_slices = ExtraSlice::orthogonal(
+                vecmath_util::to_vec3(&self.setting.viewer_setting.slice_pos),
+                self.setting.viewer_setting.slice_angle,
+            );
+            update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+        }
+        for (i, (extra, viewer)) in self
+            .setting
+            .extra_slices
+            .iter_mut()
+            .zip(self.extra_slice_viewers.iter_mut())
+            .enumerate()
+        {
+            let id = ui.push_id(i as i32);
+            ui.checkbox(
+                &ImString::new(format!("Slice {}", i + 2)),
+                &mut extra.enabled,
+            );
+            if extra.enabled {
+                for (j, (label, angle)) in PLANES.iter().enumerate() {
+                    ui.same_line(0.);
+                    let plane_id = ui.push_id(j as i32);
+                    if ui.small_button(&ImString::new(*label)) {
+                        extra.angle = *angle;
+                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                    }
+                    plane_id.pop(ui);
+                }
+                if Drag::new(im_str!("Center [mm]"))
+                    .speed(1.0)
+                    .build_array(ui, &mut extra.pos)
+                {
+                    update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                }
+            }
+            viewer.move_to(extra.pos4());
+            viewer.rotate_to(extra.angle);
+            id.pop(ui);
+        }
+        update_flag
+    }
+
+    fn isosurface_ui(&mut self, ui: &Ui) {
+        let calibration = self.setting.viewer_setting.calibration;
+        Drag::new(im_str!("Level [Pa]##isosurface"))
+            .speed(10.0)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut self.setting.isosurface_level);
+        ui.same_line(0.);
+        ui.text_disabled(format!(
+            "{:.1} dB SPL",
+            calibration::pa_to_spl(self.setting.isosurface_level)
+        ));
+        if let Some(grid) = &self.volume_grid {
+            if ui.small_button(im_str!("-6 dB of maximum")) {
+                self.setting.isosurface_level = calibration.to_pa(grid.max_amp() / 2.0);
+            }
+        }
+        Slider::new(im_str!("Opacity##isosurface"))
+            .range(0.0..=1.0)
+            .build(ui, &mut self.setting.isosurface_opacity);
+        let mesh = match &self.isosurface {
+            Some(mesh) => mesh,
+            None => return,
+        };
+        ui.text(format!("{} triangles", mesh.triangles.len()));
+        ui.input_text(im_str!("Isosurface file"), &mut self.isosurface_path)
+            .build();
+        if ui.small_button(im_str!("Export STL")) {
+            let mesh = mesh.clone();
+            let path = Path::new(self.isosurface_path.to_str()).with_extension("stl");
+            self.io_worker.submit("Export isosurface", move || {
+                if let Some(folder) = path.parent() {
+                    std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+                }
+                mesh.write_stl(&path)?;
+                tracing::info!(
+                    num_triangles = mesh.triangles.len(),
+                    "exported {}",
+                    path.display()
+                );
+                Ok(())
+            });
+        }
+    }
+
+    fn ideal_overlay_ui(&mut self, ui: &Ui) {
+        let mut changed = ui.checkbox(
+            im_str!("Ideal focus contours"),
+            &mut self.setting.ideal_overlay.enabled,
+        );
+        if !self.setting.ideal_overlay.enabled {
+            self.ideal_contours = None;
+            return;
+        }
+        let overlay = &mut self.setting.ideal_overlay;
+        changed |= Drag::new(im_str!("Focus [mm]##ideal"))
+            .speed(0.1)
+            .build_array(ui, &mut overlay.focus);
+        if ui.small_button(im_str!("Slice center##ideal")) {
+            overlay.focus = vecmath_util::to_vec3(&self.field_slice_viewer.model()[3]);
+            changed = true;
+        }
+        if let Some(focus) = self.commanded_foci.first() {
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Commanded focus##ideal")) {
+                overlay.focus = focus.pos;
+                changed = true;
+            }
+        }
+        changed |= Drag::new(im_str!("Step [dB]##ideal"))
+            .speed(0.1)
+            .range(0.5..=20.0)
+            .build(ui, &mut overlay.step_db);
+        let mut levels = overlay.levels as i32;
+        if Drag::new(im_str!("Levels##ideal"))
+            .range(1..=10)
+            .build(ui, &mut levels)
+        {
+            overlay.levels = levels.clamp(1, 10) as usize;
+            changed = true;
+        }
+        changed |= Drag::new(im_str!("Grid [mm]##ideal"))
+            .speed(0.05)
+            .range(0.5..=20.0)
+            .build(ui, &mut overlay.spacing);
+        if changed {
+            self.ideal_contours = None;
+        }
+
+        let contours = match &self.ideal_contours {
+            Some(contours) if contours.focal_amp > 0.0 => contours,
+            _ => {
+                ui.text_disabled("No ideal focus: no sources");
+                return;
+            }
+        };
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings: &self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: self.scattering.as_ref(),
+            device_sizes: &self.drive.device_sizes,
+        };
+        let [re, im] = model.pressure_at(self.setting.ideal_overlay.focus);
+        let ratio = (re * re + im * im).sqrt() / contours.focal_amp;
+        let locale = &self.setting.locale;
+        let settings = &self.setting.viewer_setting;
+        ui.text(format!(
+            "Ideal at focus: {} [{}]",
+            locale.number(
+                settings
+                    .pressure_unit
+                    .convert(contours.focal_amp, &settings.calibration) as f64,
+                2
+            ),
+            settings.pressure_unit.name()
+        ));
+        ui.text(format!(
+            "Simulated / ideal at focus: {} [%] ({} [dB])",
+            locale.number(ratio as f64 * 100.0, 1),
+            locale.number(20.0 * (ratio as f64).max(1e-10).log10(), 1)
+        ));
+    }
+
+    fn update_ideal_contours(&mut self) {
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings: &self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: None,
+            device_sizes: &self.drive.device_sizes,
+        };
+        self.ideal_contours = Some(
+            self.setting
+                .ideal_overlay
+                .compute(&model, self.field_slice_viewer.model()),
+        );
+    }
+
+    // The outer contours are fainter, and the focus is marked by a cross
+    fn draw_ideal_contours(&self, ui: &Ui) {
+        let contours = match &self.ideal_contours {
+            Some(contours) => contours,
+            None => return,
+        };
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        let n = contours.lines.len().max(1) as f32;
+        for (i, (_, segments)) in contours.lines.iter().enumerate() {
+            let color = [0.4, 1.0, 1.0, 1.0 - 0.6 * i as f32 / n];
+            for &(a, b) in segments {
+                if let (Some(a), Some(b)) = (project(a), project(b)) {
+                    draw_list.add_line(a, b, color).thickness(1.5).build();
+                }
+            }
+        }
+        if let Some(p) = project(self.setting.ideal_overlay.focus) {
+            let color = [0.4, 1.0, 1.0, 1.0];
+            draw_list
+                .add_line([p[0] - 5.0, p[1] - 5.0], [p[0] + 5.0, p[1] + 5.0], color)
+                .build();
+            draw_list
+                .add_line([p[0] - 5.0, p[1] + 5.0], [p[0] + 5.0, p[1] - 5.0], color)
+                .build();
+        }
+    }
+
+    // Integrated periodically, since a box of fine sampling can have many points
+    fn update_region_integral(&mut self) {
+        const INTERVAL: Duration = Duration::from_millis(200);
+
+        if let Some(integral) = self.region_worker.poll() {
+            self.region_integral = Some((integral, Instant::now()));
+        }
+        if self.region_worker.is_busy()
+            || self
+                .region_integral
+                .is_some_and(|(_, t)| t.elapsed() < INTERVAL)
+        {
+            return;
+        }
+        let region = self.setting.target_region;
+        self.region_worker
+            .request(self.owned_field(), move |model| region.integrate(model));
+    }
+
+    // Copy of the field of the current drive for the background computations
+    fn owned_field(&self) -> OwnedField {
+        OwnedField {
+            sources: self.drive.sources.clone(),
+            device_sizes: self.drive.device_sizes.clone(),
+            settings: self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table().cloned(),
+            scattering: self.scattering.clone(),
+        }
+    }
+
+    fn draw_target_region(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [0.3, 0.8, 1.0, 1.0];
+        const SEGMENTS: usize = 48;
+
+        let region = &self.setting.target_region;
+        let [u, v, w] = region.axes();
+        let at = |x: f32, y: f32, z: f32| {
+            let p = vecmath::vec3_add(region.center, vecmath::vec3_scale(u, x));
+            let p = vecmath::vec3_add(p, vecmath::vec3_scale(v, y));
+            vecmath::vec3_add(p, vecmath::vec3_scale(w, z))
+        };
+        let edges: Vec<(Vector3, Vector3)> = match region.shape {
+            RegionShape::Disk { radius } => {
+                let point = |i: usize| {
+                    let t = 2.0 * PI * i as f32 / SEGMENTS as f32;
+                    at(radius * t.cos(), radius * t.sin(), 0.0)
+                };
+                (0..SEGMENTS).map(|i| (point(i), point(i + 1))).collect()
+            }
+            RegionShape::Box { size } => {
+                let [sx, sy, sz] = vecmath_util::vec3_map(size, |s| s / 2.0);
+                let corner = |i: usize| {
+                    let sign = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+                    at(sx * sign(1), sy * sign(2), sz * sign(4))
+                };
+                (0..8)
+                    .flat_map(|i| [1, 2, 4].map(|bit| (i, i | bit)))
+                    .filter(|(i, j)| i != j)
+                    .map(|(i, j)| (corner(i), corner(j)))
+                    .collect()
+            }
+        };
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        for (a, b) in edges {
+            if let (Some(a), Some(b)) = (project(a), project(b)) {
+                draw_list.add_line(a, b, COLOR).thickness(1.5).build();
+            }
+        }
+        if let (Some(p), Some((integral, _))) = (project(region.center), self.region_integral) {
+            let locale = &self.setting.locale;
+            let text = match region.shape {
+                RegionShape::Disk { .. } => {
+                    format!("{} [mW]", locale.number(integral.integral as f64 * 1e3, 3))
+                }
+                RegionShape::Box { .. } => {
+                    format!("{} [uJ]", locale.number(integral.integral as f64 * 1e6, 3))
+                }
+            };
+            draw_list.add_text([p[0] + 4.0, p[1] + 4.0], COLOR, text);
+        }
+    }
+
+    // Searched periodically as the region integral, since the search covers the whole slice
+    fn update_focal_spot(&mut self) {
+        const INTERVAL: Duration = Duration::from_millis(200);
+
+        if self.focal_spot.is_some_and(|(_, t)| t.elapsed() < INTERVAL) {
+            return;
+        }
+        let model = FieldModel {
+            sources: &self.drive.sources,
+            settings: &self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+            scattering: self.scattering.as_ref(),
+            device_sizes: &self.drive.device_sizes,
+        };
+        let spot = model.focal_spot(self.field_slice_viewer.model());
+        self.focal_spot = Some((spot, Instant::now()));
+    }
+
+    // Marker at the maximum and the metrics at the upper right corner
+    fn draw_focal_spot(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+        const SIZE: f32 = 6.0;
+
+        let spot = match self.focal_spot {
+            Some((spot, _)) => spot,
+            None => return,
+        };
+        let screen_size = ui.io().display_size;
+        if let Some(p) =
+            camera_helper::project_to_screen(self.view_projection, spot.pos, screen_size)
+        {
+            let draw_list = ui.get_background_draw_list();
+            draw_list
+                .add_rect(
+                    [p[0] - SIZE, p[1] - SIZE],
+                    [p[0] + SIZE, p[1] + SIZE],
+                    COLOR,
+                )
+                .build();
+        }
+        let locale = &self.setting.locale;
+        let settings = &self.setting.viewer_setting;
+        let width = |w: Option<f32>| match w {
+            Some(w) => format!("{} [mm]", locale.number(w as f64, 2)),
+            None => "out of slice".to_owned(),
+        };
+        Window::new(im_str!("Focal spot"))
+            .position([screen_size[0] - 10.0, 10.0], Condition::Always)
+            .position_pivot([1.0, 0.0])
+            .no_decoration()
+            .no_inputs()
+            .always_auto_resize(true)
+            .bg_alpha(0.6)
+            .build(ui, || {
+                ui.text(format!(
+                    "Peak |p| = {} [{}]",
+                    locale.number(
+                        settings
+                            .pressure_unit
+                            .convert(spot.amp, &settings.calibration)
+                            as f64,
+                        2
+                    ),
+                    settings.pressure_unit.name()
+                ));
+                ui.text(format!("Focus: {} [mm]", locale.vec3(spot.pos, 2)));
+                ui.text(format!("-6 dB width (slice x): {}", width(spot.widths[0])));
+                ui.text(format!("-6 dB width (slice y): {}", width(spot.widths[1])));
+            });
+    }
+
+    // Ribbon at the lower left corner
+    fn draw_coordinate_readout(&self, ui: &Ui) {
+        let screen_size = ui.io().display_size;
+        let text = match self.cursor_position(ui) {
+            Some((plane, p)) => {
+                let locale = &self.setting.locale;
+                let settings = &self.setting.viewer_setting;
+                let model = FieldModel {
+                    sources: &self.drive.sources,
+                    settings,
+                    directivity_table: self.field_slice_viewer.directivity_table(),
+                    scattering: self.scattering.as_ref(),
+                    device_sizes: &self.drive.device_sizes,
+                };
+                let [re, im] = model.pressure_at(p);
+                let amp = (re * re + im * im).sqrt();
+                format!(
+                    "{}: ({}, {}, {}) [mm], |p| = {} [{}]",
+                    plane,
+                    locale.number(p[0] as f64, 2),
+                    locale.number(p[1] as f64, 2),
+                    locale.number(p[2] as f64, 2),
+                    locale.number(
+                        settings.pressure_unit.convert(amp, &settings.calibration) as f64,
+                        2
+                    ),
+                    settings.pressure_unit.name()
+                )
+            }
+            None => "-".to_owned(),
+        };
+        Window::new(im_str!("Coordinate"))
+            .position([10.0, screen_size[1] - 10.0], Condition::Always)
+            .position_pivot([0.0, 1.0])
+            .no_decoration()
+            .no_inputs()
+            .always_auto_resize(true)
+            .bg_alpha(0.6)
+            .build(ui, || {
+                ui.text(text);
+            });
+    }
+
+    // Arrows are scaled by the largest force on the slice
+    fn draw_quiver(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.8];
+        const HEAD: f32 = 4.0;
+
+        let max = self
+            .quiver
+            .iter()
+            .map(|(_, f)| vecmath::vec3_len(*f))
+            .fold(0.0, f32::max);
+        if max <= 0.0 {
+            return;
+        }
+        let scale = self.quiver_spacing() * 0.9 / max;
+        let screen_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        for &(p, f) in &self.quiver {
+            let tip = vecmath::vec3_add(p, vecmath::vec3_scale(f, scale));
+            let (p, tip) = match (
+                camera_helper::project_to_screen(self.view_projection, p, screen_size),
+                camera_helper::project_to_screen(self.view_projection, tip, screen_size),
+            ) {
+                (Some(p), Some(tip)) => (p, tip),
+                _ => continue,
+            };
+            let d = [tip[0] - p[0], tip[1] - p[1]];
+            let len = (d[0] * d[0] + d[1] * d[1]).sqrt();
+            if len < 1.0 {
+                continue;
+            }
+            let (ux, uy) = (d[0] / len, d[1] / len);
+            let head = HEAD.min(len / 2.0);
+            draw_list.add_line(p, tip, COLOR).build();
+            for s in [-1.0, 1.0] {
+                let end = [tip[0] - head * (ux + s * uy), tip[1] - head * (uy - s * ux)];
+                draw_list.add_line(tip, end, COLOR).build();
+            }
+        }
+    }
+
+    fn tag_history_ui(&self, ui: &Ui) {
+        if !self.tags.current().is_empty() {
+            ui.text(format!("Tags: {}", self.tags.current().join(", ")));
+        }
+        let entries = self.tags.entries();
+        if entries.is_empty() {
+            return;
+        }
+        let header = ImString::new(format!("Tagged gains ({})###tagged_gains", entries.len()));
+        if CollapsingHeader::new(&header).build(ui) {
+            ChildWindow::new("tagged_gains")
+                .size([0.0, 120.0])
+                .build(ui, || {
+                    for entry in entries.iter().rev() {
+                        ui.text(format!(
+                            "#{} {} [s]: {}",
+                            entry.gain_index,
+                            self.setting.locale.number(entry.time, 3),
+                            entry.tags.join(", ")
+                        ));
+                    }
+                });
+        }
+    }
+
+    fn drift_profile_ui(&mut self, ui: &Ui) {
+        let profile = &mut self.setting.temperature_profile;
+        Drag::new(im_str!("Drift speed [min/s]"))
+            .speed(1.0)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut profile.speed);
+        let mut remove = None;
+        let mut edited = false;
+        for (i, point) in profile.points.iter_mut().enumerate() {
+            let id = ui.push_id(i as i32);
+            Drag::new(im_str!("[min], [C]"))
+                .speed(0.1)
+                .build_array(ui, point);
+            edited |= ui.is_item_deactivated_after_edit();
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Remove")) {
+                remove = Some(i);
+            }
+            id.pop(ui);
+        }
+        if let Some(i) = remove {
+            profile.points.remove(i);
+        }
+        if edited {
+            profile.sort();
+        }
+        if ui.small_button(im_str!("Add point")) {
+            let [time, temperature] = profile.points.last().copied().unwrap_or([0.0, 20.0]);
+            profile.points.push([time + 60.0, temperature]);
+        }
+        ui.same_line(0.0);
+        if ui.small_button(im_str!("Restart drift")) {
+            self.drift_start = self.clock.time();
+        }
+        let minutes = self.drift_minutes();
+        let profile = &self.setting.temperature_profile;
+        if let Some(temperature) = profile.temperature_at(minutes) {
+            ui.text(format!(
+                "{:.1} min: {:.2} C, focal distance x{:.4}",
+                minutes,
+                temperature,
+                profile.focal_scale(temperature)
+            ));
+        }
+    }
+
+    // Commanded foci are drawn as crosshairs on the background so that they are not hidden
+    // behind the slice
+    fn draw_commanded_foci(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [0.0, 1.0, 1.0, 1.0];
+        const SIZE: f32 = 8.0;
+
+        let screen_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        for focus in &self.commanded_foci {
+            let p = match camera_helper::project_to_screen(
+                self.view_projection,
+                focus.pos,
+                screen_size,
+            ) {
+                Some(p) => p,
+                None => continue,
+            };
+            draw_list.add_circle(p, SIZE, COLOR).thickness(2.0).build();
+            draw_list
+                .add_line([p[0] - SIZE * 1.5, p[1]], [p[0] + SIZE * 1.5, p[1]], COLOR)
+                .build();
+            draw_list
+                .add_line([p[0], p[1] - SIZE * 1.5], [p[0], p[1] + SIZE * 1.5], COLOR)
+                .build();
+            draw_list.add_text(
+                [p[0] + SIZE * 1.5, p[1] - SIZE * 2.5],
+                COLOR,
+                format!(
+                    "{} {} mm",
+                    focus.label,
+                    self.setting.locale.vec3(focus.pos, 1)
+                ),
+            );
+        }
+        if self.setting.temperature_drift {
+            self.draw_drifted_foci(ui);
+        }
+        if self.setting.show_steering_limit {
+            self.draw_foci_out_of_steering(ui);
+        }
+    }
+
+    // An X, labeled with the distance to the nearest commanded focus if any
+    fn draw_estimated_focus(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [0.6, 1.0, 0.2, 1.0];
+        const SIZE: f32 = 6.0;
+
+        let estimate = match self.estimated_focus {
+            Some(estimate) => estimate,
+            None => return,
+        };
+        let screen_size = ui.io().display_size;
+        let p =
+            match camera_helper::project_to_screen(self.view_projection, estimate.pos, screen_size)
+            {
+                Some(p) => p,
+                None => return,
+            };
+        let draw_list = ui.get_background_draw_list();
+        draw_list
+            .add_line(
+                [p[0] - SIZE, p[1] - SIZE],
+                [p[0] + SIZE, p[1] + SIZE],
+                COLOR,
+            )
+            .thickness(2.0)
+            .build();
+        draw_list
+            .add_line(
+                [p[0] - SIZE, p[1] + SIZE],
+                [p[0] + SIZE, p[1] - SIZE],
+                COLOR,
+            )
+            .thickness(2.0)
+            .build();
+        let locale = &self.setting.locale;
+        let mut text = format!(
+            "estimated {} mm (phase error {} deg)",
+            locale.vec3(estimate.pos, 1),
+            locale.number(estimate.phase_error() as f64, 1)
+        );
+        let nearest = self
+            .commanded_foci
+            .iter()
+            .map(|f| (f, vecmath_util::dist(f.pos, estimate.pos)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((focus, d)) = nearest {
+            text.push_str(&format!(
+                ", {} mm from {}",
+                locale.number(d as f64, 2),
+                focus.label
+            ));
+        }
+        draw_list.add_text([p[0] + SIZE * 1.5, p[1] + SIZE], COLOR, text);
+    }
+
+    fn steering_model(&self) -> SteeringModel<'_> {
+        SteeringModel {
+            sources: &self.drive.sources,
+            device_sizes: &self.drive.device_sizes,
+            settings: &self.setting.viewer_setting,
+            directivity_table: self.field_slice_viewer.directivity_table(),
+        }
+    }
+
+    // The contour is recomputed at the depth of the slice center only when it can change
+    fn update_steering_limit(&mut self) {
+        let model = self.steering_model();
+        let (center, normal) = match model.frame() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let slice_center = vecmath_util::to_vec3(&self.field_slice_viewer.model()[3]);
+        let depth = vecmath::vec3_dot(vecmath::vec3_sub(slice_center, center), normal);
+        let fraction = self.setting.steering_fraction;
+        let wave_length = self.setting.viewer_setting.wave_length;
+        if self.steering_limit.as_ref().is_some_and(|l| {
+            l.depth == depth
+                && l.fraction == fraction
+                && l.wave_length == wave_length
+                && l.center == center
+        }) {
+            return;
+        }
+        self.steering_limit = model.limit(depth, fraction);
+    }
+
+    // The contour with a few generatrices of the cone from the center of the array
+    fn draw_steering_limit(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 0.4, 1.0, 1.0];
+        const GENERATRICES: usize = 4;
+
+        let limit = match &self.steering_limit {
+            Some(limit) if !limit.contour.is_empty() => limit,
+            _ => return,
+        };
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        let n = limit.contour.len();
+        for i in 0..n {
+            if let (Some(a), Some(b)) = (
+                project(limit.contour[i]),
+                project(limit.contour[(i + 1) % n]),
+            ) {
+                draw_list.add_line(a, b, COLOR).thickness(1.5).build();
+            }
+        }
+        if let Some(c) = project(limit.center) {
+            for i in 0..GENERATRICES {
+                if let Some(p) = project(limit.contour[i * n / GENERATRICES]) {
+                    draw_list
+                        .add_line(c, p, [COLOR[0], COLOR[1], COLOR[2], 0.4])
+                        .build();
+                }
+            }
+        }
+        if let Some(p) = project(limit.contour[0]) {
+            draw_list.add_text(
+                [p[0] + 4.0, p[1]],
+                COLOR,
+                format!(
+                    "{} % steering limit at {} mm",
+                    self.setting.locale.number(limit.fraction as f64 * 100.0, 0),
+                    self.setting.locale.number(limit.depth as f64, 1)
+                ),
+            );
+        }
+    }
+
+    fn draw_foci_out_of_steering(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+        const SIZE: f32 = 12.0;
+
+        let model = self.steering_model();
+        let screen_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        for focus in &self.commanded_foci {
+            let ratio = match model.relative_focal_amp(focus.pos) {
+                Some(ratio) if ratio < self.setting.steering_fraction => ratio,
+                _ => continue,
+            };
+            let p = match camera_helper::project_to_screen(
+                self.view_projection,
+                focus.pos,
+                screen_size,
+            ) {
+                Some(p) => p,
+                None => continue,
+            };
+            draw_list.add_circle(p, SIZE, COLOR).thickness(2.0).build();
+            draw_list.add_text(
+                [p[0] + SIZE * 1.5, p[1] + SIZE * 0.5],
+                COLOR,
+                format!(
+                    "outside steering range ({} % of on-axis)",
+                    self.setting.locale.number(ratio as f64 * 100.0, 0)
+                ),
+            );
+        }
+    }
+
+    // The edges of the triangles, or the bounding box of a large mesh
+    fn draw_fixtures(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [0.7, 0.7, 0.7, 0.6];
+        const MAX_TRIANGLES: usize = 5000;
+
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        let line = |a, b| {
+            if let (Some(a), Some(b)) = (project(a), project(b)) {
+                draw_list.add_line(a, b, COLOR).build();
+            }
+        };
+        for fixture in &self.fixtures {
+            let (mesh, (min, max)) = match (&fixture.mesh, fixture.bounds) {
+                (Ok(mesh), Some(bounds)) => (mesh, bounds),
+                _ => continue,
+            };
+            if mesh.triangles.len() <= MAX_TRIANGLES {
+                for [a, b, c] in &mesh.triangles {
+                    line(*a, *b);
+                    line(*b, *c);
+                    line(*c, *a);
+                }
+            } else {
+                let corner =
+                    |i: usize| [0, 1, 2].map(|k| if i & (1 << k) == 0 { min[k] } else { max[k] });
+                for i in 0..8 {
+                    for k in 0..3 {
+                        if i & (1 << k) == 0 {
+                            line(corner(i), corner(i | (1 << k)));
+                        }
+                    }
+                }
+            }
+            if let Some(p) = project(max) {
+                draw_list.add_text(p, COLOR, &fixture.setting.name);
+            }
+        }
+    }
+
+    // Outline of the reflector with a tick along the normal at the center
+    fn draw_reflector(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [0.6, 0.8, 1.0, 0.8];
+        const NORMAL_LENGTH: f32 = 20.0;
+
+        let reflector = &self.setting.viewer_setting.reflector;
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        let line = |a, b| {
+            if let (Some(a), Some(b)) = (project(a), project(b)) {
+                draw_list.add_line(a, b, COLOR).build();
+            }
+        };
+        let corners = reflector.corners();
+        for i in 0..4 {
+            line(corners[i], corners[(i + 1) % 4]);
+        }
+        let tip = vecmath::vec3_add(
+            reflector.center,
+            vecmath::vec3_scale(reflector.normal(), NORMAL_LENGTH),
+        );
+        line(reflector.center, tip);
+    }
+
+    fn draw_obstacles(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 0.6, 0.3, 0.8];
+
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        let obstacles = self.setting.viewer_setting.obstacles;
+        for obstacle in obstacles.iter().filter(|o| o.enabled) {
+            for (a, b) in obstacle.edges() {
+                if let (Some(a), Some(b)) = (project(a), project(b)) {
+                    draw_list.add_line(a, b, COLOR).build();
+                }
+            }
+        }
+    }
+
+    fn draw_collisions(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+        const SIZE: f32 = 12.0;
+
+        let collisions = match &self.collisions {
+            Some(collisions) => collisions,
+            None => return,
+        };
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+        let draw_list = ui.get_background_draw_list();
+        for &(i, j) in &collisions.foci {
+            if let Some(p) = project(self.commanded_foci[i].pos) {
+                draw_list.add_circle(p, SIZE, COLOR).thickness(2.0).build();
+                draw_list.add_text(
+                    [p[0] + SIZE * 1.5, p[1] + SIZE * 1.5],
+                    COLOR,
+                    format!("inside {}", self.setting.fixtures[j].name),
+                );
+            }
+        }
+        let stm = self.drive.stm_points();
+        for &(i, _) in &collisions.stm {
+            if let Some(p) = stm.get(i).and_then(|d| project(d.0)) {
+                draw_list.add_circle(p, 3.0, COLOR).filled(true).build();
+            }
+        }
+    }
+
+    // Estimated positions of the commanded foci shifted by the temperature drift
+    fn draw_drifted_foci(&self, ui: &Ui) {
+        const COLOR: [f32; 4] = [1.0, 0.6, 0.0, 1.0];
+        const SIZE: f32 = 5.0;
+
+        let temperature = match self.setting.viewer_setting.temperature {
+            Some(temperature) => temperature,
+            None => return,
+        };
+        if self.drive.sources.is_empty() {
+            return;
+        }
+        let scale = self.setting.temperature_profile.focal_scale(temperature);
+        let center = vecmath::vec3_scale(
+            self.drive
+                .sources
+                .iter()
+                .fold([0.0; 3], |acc, s| vecmath::vec3_add(acc, s.pos)),
+            1.0 / self.drive.sources.len() as f32,
+        );
+        let screen_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        for focus in &self.commanded_foci {
+            let drifted = drift::drifted_focus(center, focus.pos, scale);
+            let project =
+                |p| camera_helper::project_to_screen(self.view_projection, p, screen_size);
+            let (p, q) = match (project(focus.pos), project(drifted)) {
+                (Some(p), Some(q)) => (p, q),
+                _ => continue,
+            };
+            draw_list.add_line(p, q, COLOR).build();
+            draw_list.add_circle(q, SIZE, COLOR).thickness(2.0).build();
+            draw_list.add_text(
+                [q[0] + SIZE * 1.5, q[1] + SIZE],
+                COLOR,
+                // positive away from the array
+                format!(
+                    "{:+.2} mm",
+                    vecmath_util::dist(center, focus.pos) * (scale - 1.0)
+                ),
+            );
+        }
+    }
+
+    fn mod_values<F>(&self, f: F) -> Vec<f32>
+    where
+        F: Fn(&u8) -> f32,
+    {
+        if let Some(m) = &self.drive.modulation {
+            m.mod_data.iter().map(f).collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+// Devices of `placeholder_layout` side by side along x, shown before the first geometry arrives
+fn placeholder_geometries(setting: &Setting) -> Vec<Geometry> {
+    let layout = &setting.placeholder_layout;
+    let pitch = match layout {
+        Layout::Autd3 => AUTD_WIDTH as f32,
+        _ => {
+            let xs = layout.positions().into_iter().map(|p| p[0]);
+            let width = xs.clone().fold(f32::MIN, f32::max) - xs.fold(f32::MAX, f32::min);
+            width.max(0.0) + layout.min_spacing().unwrap_or(TRANS_SPACING_MM as f32)
+        }
+    };
+    (0..setting.placeholder_devices)
+        .map(|i| Geometry {
+            origin: [pitch * i as f32, 0.0, 0.0],
+            right: [1.0, 0.0, 0.0],
+            up: [0.0, 1.0, 0.0],
+            layout: layout.clone(),
+        })
+        .collect()
+}
+
+fn read_layouts(path: &str) -> Result<Vec<Layout>, String> {
+    std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_slice(&json).map_err(|e| e.to_string()))
+}
+
+// Arguments of the modes run without showing the window
+const HEADLESS_ARGS: [&str; 4] = ["--daemon", "--figure", "--compute-volume", "--render"];
+
+pub fn main() {
+    let (setting, setting_err) = match Setting::load(SETTING_PATH) {
+        Ok(setting) => (setting, None),
+        Err(e) => (Setting::new(), Some(e)),
+    };
+    // the modes without the window report on the terminal instead of the Log tab
+    let headless = std::env::args()
+        .skip(1)
+        .any(|arg| HEADLESS_ARGS.contains(&arg.as_str()));
+    let log_buffer = log::init(&setting, headless);
+    if let Some(e) = setting_err {
+        // the default setting is saved over it by the autosave and on exit
+        let backup = format!("{}.bak", SETTING_PATH);
+        match std::fs::copy(SETTING_PATH, &backup) {
+            Ok(_) => tracing::error!(
+                "failed to load {}: {}, which is backed up to {}",
+                SETTING_PATH,
+                e,
+                backup
+            ),
+            Err(be) => tracing::error!(
+                "failed to load {}: {}, and to back it up: {}",
+                SETTING_PATH,
+                e,
+                be
+            ),
+        }
+    }
+    let mut args = std::env::args().skip(1);
+    let mut daemon = false;
+    let mut spectate = None;
+    let mut capture = None;
+    let mut manifest = None;
+    let mut figure = None;
+    let mut volume = None;
+    let mut render = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--daemon" => daemon = true,
+            "--spectate" => spectate = args.next(),
+            "--replay" => capture = args.next(),
+            "--manifest" => manifest = args.next(),
+            "--figure" => figure = args.next(),
+            "--compute-volume" => volume = args.next(),
+            "--render" => render = args.next(),
+            _ => tracing::warn!("unknown argument: {}", arg),
+        }
+    }
+    if manifest.is_some() && capture.is_none() {
+        tracing::warn!("--manifest is ignored without --replay");
+    }
+    if daemon {
+        if let Err(e) = daemon::run(&setting) {
+            tracing::error!("daemon: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(spec) = figure {
+        // rendered headlessly without opening the window
+        match figure::render_figures(Path::new(&spec), &setting) {
+            Ok(num) => tracing::info!(num, "figures rendered"),
+            Err(e) => {
+                tracing::error!("figure: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(spec) = volume {
+        match volume_export::compute_volumes(Path::new(&spec), &setting) {
+            Ok(num) => tracing::info!(num, "volumes computed"),
+            Err(e) => {
+                tracing::error!("compute volume: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(spec) = render {
+        // rendered in a hidden window, which needs a GL context but not a display of it
+        match render::render_frames(Path::new(&spec), setting, log_buffer) {
+            Ok(num) => tracing::info!(num, "frames rendered"),
+            Err(e) => {
+                tracing::error!("render: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let replay = capture.map(|capture| ReplayOptions { capture, manifest });
+    let system = System::init(
+        "AUTD3 emulator",
+        setting.window_width as _,
+        setting.window_height as _,
+    );
+
+    let mut app = App::new(setting, &system, log_buffer, spectate, replay);
+    app.run(system);
+    if let Some(code) = app.exit_code() {
+        std::process::exit(code);
+    }
 }
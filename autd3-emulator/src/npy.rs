@@ -0,0 +1,109 @@
+/*
+ * File: npy.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::io::{self, Write};
+
+use acoustic_field_viewer::field::Complex;
+
+/// Writes the header of the NumPy format 1.0 of the C-order array of `shape` of the type `descr`,
+/// padded so that the data is aligned to 64 bytes
+fn write_header<W: Write>(writer: &mut W, descr: &str, shape: &[usize]) -> io::Result<()> {
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    // the magic, the version and the length take 10 bytes, and the header ends with a newline
+    let len = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - len % 64) % 64));
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())
+}
+
+/// Writes `values` as a float32 array of `shape`, the last axis the fastest
+pub fn write_f32<W: Write>(writer: &mut W, shape: &[usize], values: &[f32]) -> io::Result<()> {
+    write_header(writer, "<f4", shape)?;
+    for v in values {
+        writer.write_all(&v.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes `values` as a complex64 array of `shape`, the last axis the fastest
+pub fn write_complex<W: Write>(
+    writer: &mut W,
+    shape: &[usize],
+    values: &[Complex],
+) -> io::Result<()> {
+    write_header(writer, "<c8", shape)?;
+    for [re, im] in values {
+        writer.write_all(&re.to_le_bytes())?;
+        writer.write_all(&im.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Header of `buf` and the data after it, checking the magic and the alignment
+    fn split(buf: &[u8]) -> (&str, &[u8]) {
+        assert_eq!(&buf[..8], b"\x93NUMPY\x01\x00");
+        let len = u16::from_le_bytes([buf[8], buf[9]]) as usize;
+        assert_eq!((10 + len) % 64, 0);
+        let header = std::str::from_utf8(&buf[10..10 + len]).unwrap();
+        assert!(header.ends_with('\n'));
+        (header, &buf[10 + len..])
+    }
+
+    #[test]
+    fn f32_vector() {
+        let mut buf = Vec::new();
+        write_f32(&mut buf, &[3], &[1.0, -2.0, 0.5]).unwrap();
+        let (header, data) = split(&buf);
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (3,), }"));
+        let values: Vec<_> = data
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(values, vec![1.0, -2.0, 0.5]);
+    }
+
+    #[test]
+    fn complex_matrix() {
+        let values: Vec<Complex> = (0..6).map(|i| [i as f32, -(i as f32)]).collect();
+        let mut buf = Vec::new();
+        write_complex(&mut buf, &[2, 3], &values).unwrap();
+        let (header, data) = split(&buf);
+        assert!(header.contains("'descr': '<c8'"));
+        assert!(header.contains("'shape': (2, 3)"));
+        assert_eq!(data.len(), 6 * 8);
+        assert_eq!(
+            &data[8..16],
+            &[1.0f32.to_le_bytes(), (-1.0f32).to_le_bytes()].concat()[..]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+/*
+ * File: acoustic_camera.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use acoustic_field_viewer::{
+    camera_helper, field::FieldModel, metadata::ExperimentMetadata, Matrix4, Vector3,
+};
+use imgui::*;
+use scarlet::color::RGBColor;
+
+use crate::{acoustic_camera::AcousticImage, io_worker::IoWorker, settings::Setting};
+
+const INTERVAL: Duration = Duration::from_millis(200);
+const IMAGE_WIDTH: f32 = 320.0;
+const FRUSTUM_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+
+/// Map of the SPL seen by a virtual microphone array
+pub struct AcousticCameraPanel {
+    image: Option<(AcousticImage, Instant)>,
+    path: ImString,
+}
+
+impl AcousticCameraPanel {
+    pub fn new(setting: &Setting) -> Self {
+        let mut path = ImString::with_capacity(256);
+        path.push_str(&setting.acoustic_camera_path);
+        Self { image: None, path }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        slice_center: Vector3,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        ui.checkbox(
+            im_str!("Acoustic camera"),
+            &mut setting.show_acoustic_camera,
+        );
+        if !setting.show_acoustic_camera {
+            return;
+        }
+        let camera = &mut setting.acoustic_camera;
+        Drag::new(im_str!("Position [mm]##camera"))
+            .speed(1.0)
+            .build_array(ui, &mut camera.pos);
+        Drag::new(im_str!("Target [mm]##camera"))
+            .speed(1.0)
+            .build_array(ui, &mut camera.target);
+        if ui.small_button(im_str!("Target at slice center")) {
+            camera.target = slice_center;
+        }
+        Drag::new(im_str!("Field of view [deg]##camera"))
+            .range(1.0..=170.0)
+            .build(ui, &mut camera.fov);
+        let mut resolution = camera.resolution.map(|v| v as i32);
+        if Drag::new(im_str!("Pixels##camera"))
+            .range(2..=256)
+            .build_array(ui, &mut resolution)
+        {
+            camera.resolution = resolution.map(|v| v.clamp(2, 256) as usize);
+        }
+        Drag::new(im_str!("Dynamic range [dB]##camera"))
+            .speed(0.1)
+            .range(1.0..=60.0)
+            .build(ui, &mut camera.dynamic_range);
+        ui.input_text(im_str!("Map file"), &mut self.path).build();
+        if ui.small_button(im_str!("Export CSV##camera")) {
+            self.export(metadata, io_worker);
+        }
+    }
+
+    /// Captures the image in `model` periodically as the region integral
+    pub fn update(&mut self, setting: &Setting, model: &FieldModel) {
+        if self
+            .image
+            .as_ref()
+            .is_some_and(|(_, t)| t.elapsed() < INTERVAL)
+        {
+            return;
+        }
+        let image = setting.acoustic_camera.capture(model);
+        self.image = Some((image, Instant::now()));
+    }
+
+    /// Draws the frustum of the camera, and the image in a window colored by `color_map`
+    pub fn draw(
+        &self,
+        ui: &Ui,
+        setting: &Setting,
+        view_projection: (Matrix4, Matrix4),
+        color_map: &[RGBColor],
+    ) {
+        let camera = &setting.acoustic_camera;
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(view_projection, p, screen_size);
+        let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+            .map(|[x, y]| project(camera.point(x, y)));
+        let draw_list = ui.get_background_draw_list();
+        if let Some(eye) = project(camera.pos) {
+            for (i, c) in corners.iter().enumerate() {
+                if let (Some(c), Some(next)) = (c, corners[(i + 1) % corners.len()]) {
+                    draw_list.add_line(eye, *c, FRUSTUM_COLOR).build();
+                    draw_list.add_line(*c, next, FRUSTUM_COLOR).build();
+                }
+            }
+        }
+
+        let image = match &self.image {
+            Some((image, _)) => image,
+            None => return,
+        };
+        let [w, h] = image.camera.resolution;
+        let max = image.max();
+        let range = image.camera.dynamic_range;
+        let pixel = IMAGE_WIDTH / w as f32;
+        Window::new(im_str!("Acoustic camera"))
+            .position([screen_size[0] - 10.0, 160.0], Condition::FirstUseEver)
+            .position_pivot([1.0, 0.0])
+            .always_auto_resize(true)
+            .build(ui, || {
+                let origin = ui.cursor_screen_pos();
+                let draw_list = ui.get_window_draw_list();
+                for (k, &spl) in image.spl.iter().enumerate() {
+                    let (i, j) = (k % w, k / w);
+                    let t = (spl - (max - range)) / range;
+                    let color = if t < 0.0 || color_map.is_empty() {
+                        [0.0, 0.0, 0.0, 1.0]
+                    } else {
+                        let c = color_map[(t.min(1.0) * (color_map.len() - 1) as f32) as usize];
+                        [c.r as f32, c.g as f32, c.b as f32, 1.0]
+                    };
+                    let p = [origin[0] + i as f32 * pixel, origin[1] + j as f32 * pixel];
+                    draw_list
+                        .add_rect(p, [p[0] + pixel, p[1] + pixel], color)
+                        .filled(true)
+                        .build();
+                }
+                ui.dummy([IMAGE_WIDTH, h as f32 * pixel]);
+                let locale = &setting.locale;
+                ui.text(format!(
+                    "{} - {} [dB SPL]",
+                    locale.number((max - range) as f64, 1),
+                    locale.number(max as f64, 1)
+                ));
+            });
+    }
+
+    /// Writes the path edited in the panel into `setting`
+    pub fn save(&self, setting: &mut Setting) {
+        setting.acoustic_camera_path = self.path.to_str().to_owned();
+    }
+
+    fn export(&self, metadata: &ExperimentMetadata, io_worker: &mut IoWorker) {
+        let image = match &self.image {
+            Some((image, _)) => image.clone(),
+            None => return,
+        };
+        let path = Path::new(self.path.to_str()).to_owned();
+        let metadata = metadata.clone();
+        io_worker.submit("Export acoustic camera", move || {
+            if let Some(folder) = path.parent() {
+                std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+            }
+            image.write_csv(&path, &metadata)?;
+            tracing::info!("exported {}", path.display());
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,311 @@
+/*
+ * File: camera.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::time::Instant;
+
+use acoustic_field_viewer::{
+    camera_helper,
+    view::{render_system::RenderSystem, UpdateFlag, GRID_SPACING},
+    view_controller::ViewController,
+};
+use glutin::event::VirtualKeyCode;
+use imgui::*;
+
+use crate::{
+    camera_bookmarks::{CameraBookmark, CameraTransition},
+    settings::Setting,
+};
+
+/// Camera bookmarks are recalled by 1 to 9 in order
+pub const BOOKMARK_HOTKEYS: [VirtualKeyCode; 9] = [
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+];
+
+/// Pose and perspective of the camera, and its bookmarks with the transition to them.
+///
+/// The view projection is not updated here, but by the caller on
+/// [UpdateFlag::UPDATE_CAMERA_POS].
+pub struct CameraPanel {
+    bookmark_name: ImString,
+    transition: Option<CameraTransition>,
+}
+
+impl CameraPanel {
+    pub fn new() -> Self {
+        let mut bookmark_name = ImString::with_capacity(64);
+        bookmark_name.push_str("view");
+        Self {
+            bookmark_name,
+            transition: None,
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        render_sys: &mut RenderSystem,
+        view_controller: &mut ViewController,
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let viewer_setting = &mut setting.viewer_setting;
+        ui.text(im_str!("Camera pos"));
+        for (i, &label) in [
+            im_str!("Camera X"),
+            im_str!("Camera Y"),
+            im_str!("Camera Z"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            if Drag::new(label).build(ui, &mut viewer_setting.camera_pos[i]) {
+                render_sys.camera.position = viewer_setting.camera_pos;
+                update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+            }
+        }
+
+        ui.separator();
+        ui.text(im_str!("Camera rotation"));
+        for (i, &label) in [
+            im_str!("Camera RX"),
+            im_str!("Camera RY"),
+            im_str!("Camera RZ"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            if AngleSlider::new(label)
+                .range_degrees(-180.0..=180.0)
+                .build(ui, &mut viewer_setting.camera_angle[i])
+            {
+                camera_helper::set_camera_angle(
+                    &mut render_sys.camera,
+                    viewer_setting.camera_angle,
+                );
+                update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+            }
+        }
+        update_flag |= Self::pose_ui(ui, setting, render_sys);
+        ui.separator();
+        self.bookmarks_ui(ui, setting, render_sys, view_controller);
+
+        ui.separator();
+        let viewer_setting = &mut setting.viewer_setting;
+        ui.text(im_str!("Camera perspective"));
+        if AngleSlider::new(im_str!("FOV"))
+            .range_degrees(0.0..=180.0)
+            .build(ui, &mut viewer_setting.fov)
+        {
+            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+        }
+        if Drag::new(im_str!("Near clip"))
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut viewer_setting.near_clip)
+        {
+            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+        }
+        if Drag::new(im_str!("Far clip"))
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut viewer_setting.far_clip)
+        {
+            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+        }
+
+        ui.separator();
+        ui.checkbox(im_str!("Show grid"), &mut setting.show_grid);
+        if ui.is_item_hovered() {
+            ui.tooltip_text(format!(
+                "Grid on z = 0 of {} mm spacing, brighter every 100 mm",
+                GRID_SPACING
+            ));
+        }
+        ui.checkbox(im_str!("Show axes"), &mut setting.show_axes);
+        ui.checkbox(im_str!("Show scale ruler"), &mut setting.show_ruler);
+        update_flag
+    }
+
+    /// Starts the transition to the `i`-th bookmark
+    pub fn recall(
+        &mut self,
+        setting: &Setting,
+        render_sys: &RenderSystem,
+        view_controller: &mut ViewController,
+        i: usize,
+    ) {
+        if let Some(bookmark) = setting.camera_bookmarks.get(i).cloned() {
+            view_controller.stop();
+            tracing::info!("recall camera bookmark {}", bookmark.name);
+            self.transition = Some(CameraTransition::new(
+                &render_sys.camera,
+                setting.camera_target,
+                bookmark,
+                setting.camera_transition_time,
+            ));
+        }
+    }
+
+    /// Stops the transition, e.g., when the camera is moved by the mouse
+    pub fn stop(&mut self) {
+        self.transition = None;
+    }
+
+    /// Moves the camera along the transition, and returns whether it has moved
+    pub fn update(&mut self, setting: &mut Setting, render_sys: &mut RenderSystem) -> bool {
+        let transition = match &self.transition {
+            Some(transition) => transition,
+            None => return false,
+        };
+        let (target, finished) = transition.update(&mut render_sys.camera, Instant::now());
+        setting.camera_target = target;
+        if finished {
+            self.transition = None;
+        }
+        true
+    }
+
+    // The exact values of the pose, edited as text rather than dragged
+    fn pose_ui(ui: &Ui, setting: &mut Setting, render_sys: &mut RenderSystem) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let viewer_setting = &mut setting.viewer_setting;
+        if ui
+            .input_float3(
+                im_str!("Camera position [mm]"),
+                &mut viewer_setting.camera_pos,
+            )
+            .build()
+        {
+            render_sys.camera.position = viewer_setting.camera_pos;
+            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+        }
+        let mut angle = vecmath_util::vec3_map(viewer_setting.camera_angle, f32::to_degrees);
+        if ui
+            .input_float3(im_str!("Camera angles [deg]"), &mut angle)
+            .build()
+        {
+            viewer_setting.camera_angle = vecmath_util::vec3_map(angle, f32::to_radians);
+            camera_helper::set_camera_angle(&mut render_sys.camera, viewer_setting.camera_angle);
+            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+        }
+        ui.input_float3(im_str!("Look-at target [mm]"), &mut setting.camera_target)
+            .build();
+        if ui.small_button(im_str!("Look at")) {
+            let backward = vecmath::vec3_sub(viewer_setting.camera_pos, setting.camera_target);
+            if !vecmath_util::is_zero(&backward) {
+                viewer_setting.camera_angle = camera_helper::euler_angles_facing(backward);
+                camera_helper::set_camera_angle(
+                    &mut render_sys.camera,
+                    viewer_setting.camera_angle,
+                );
+                update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Turns the camera toward the target without rolling it");
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Target slice center")) {
+            setting.camera_target = vecmath_util::to_vec3(&viewer_setting.slice_pos);
+        }
+        update_flag
+    }
+
+    fn bookmarks_ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        render_sys: &RenderSystem,
+        view_controller: &mut ViewController,
+    ) {
+        ui.text(im_str!("Bookmarks"));
+        let mut recall = None;
+        let mut remove = None;
+        for (i, bookmark) in setting.camera_bookmarks.iter_mut().enumerate() {
+            let id = ui.push_id(i as i32);
+            if i < BOOKMARK_HOTKEYS.len() {
+                ui.text(format!("{}: {}", i + 1, bookmark.name));
+            } else {
+                ui.text(&bookmark.name);
+            }
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Go")) {
+                recall = Some(i);
+            }
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Overwrite")) {
+                let name = std::mem::take(&mut bookmark.name);
+                *bookmark =
+                    CameraBookmark::capture(&name, &render_sys.camera, setting.camera_target);
+            }
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Remove")) {
+                remove = Some(i);
+            }
+            id.pop(ui);
+        }
+        if let Some(i) = recall {
+            self.recall(setting, render_sys, view_controller, i);
+        }
+        if let Some(i) = remove {
+            setting.camera_bookmarks.remove(i);
+        }
+        ui.input_text(im_str!("Bookmark name"), &mut self.bookmark_name)
+            .build();
+        if ui.small_button(im_str!("Save current view")) && !self.bookmark_name.to_str().is_empty()
+        {
+            let bookmark = CameraBookmark::capture(
+                self.bookmark_name.to_str(),
+                &render_sys.camera,
+                setting.camera_target,
+            );
+            let bookmarks = &mut setting.camera_bookmarks;
+            match bookmarks.iter_mut().find(|b| b.name == bookmark.name) {
+                Some(b) => *b = bookmark,
+                None => bookmarks.push(bookmark),
+            }
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Restore presets")) {
+            let target = setting.camera_target;
+            let distance = vecmath_util::dist(render_sys.camera.position, target);
+            for preset in CameraBookmark::presets(target, distance.max(1.0)) {
+                let bookmarks = &mut setting.camera_bookmarks;
+                match bookmarks.iter_mut().find(|b| b.name == preset.name) {
+                    Some(b) => *b = preset,
+                    None => bookmarks.push(preset),
+                }
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(
+                "Sets front, top and iso around the look-at target at the current distance",
+            );
+        }
+        Slider::new(im_str!("Transition [s]"))
+            .range(0.0..=5.0)
+            .build(ui, &mut setting.camera_transition_time);
+    }
+}
+
+impl Default for CameraPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
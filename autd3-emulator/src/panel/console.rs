@@ -0,0 +1,84 @@
+/*
+ * File: console.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{sound_source::SoundSource, view::ViewerSettings};
+use autd3_emulator_server::AutdData;
+use imgui::*;
+
+use crate::console;
+
+const HISTORY_MAX: usize = 100;
+
+/// Console tab, whose commands are injected as if sent by the SDK
+pub struct ConsolePanel {
+    input: ImString,
+    history: Vec<String>,
+    injection: Vec<AutdData>,
+}
+
+impl ConsolePanel {
+    pub fn new() -> Self {
+        Self {
+            input: ImString::with_capacity(256),
+            history: Vec::new(),
+            injection: Vec::new(),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui, sources: &[SoundSource], settings: &ViewerSettings) {
+        if ui
+            .input_text(im_str!("Command"), &mut self.input)
+            .enter_returns_true(true)
+            .build()
+        {
+            let line = self.input.to_str().trim().to_owned();
+            self.input.clear();
+            if !line.is_empty() {
+                self.run(&line, sources, settings);
+            }
+        }
+        ui.text_disabled("Type 'help' for the commands");
+        ui.separator();
+        for line in &self.history {
+            ui.text(line);
+        }
+    }
+
+    /// Events of the commands run since the last call
+    pub fn take_injection(&mut self) -> Vec<AutdData> {
+        std::mem::take(&mut self.injection)
+    }
+
+    fn run(&mut self, line: &str, sources: &[SoundSource], settings: &ViewerSettings) {
+        self.history.push(format!("> {}", line));
+        if line == "help" {
+            self.history
+                .extend(console::HELP.lines().map(str::to_owned));
+        } else {
+            match console::execute(line, sources, settings) {
+                Ok(data) => self.injection.extend(data),
+                Err(e) => self.history.push(e),
+            }
+        }
+        if self.history.len() > HISTORY_MAX {
+            let n = self.history.len() - HISTORY_MAX;
+            self.history.drain(..n);
+        }
+    }
+}
+
+impl Default for ConsolePanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * File: drive_history.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::path::Path;
+
+use acoustic_field_viewer::{
+    metadata::ExperimentMetadata, sound_source::SoundSource, view::ViewerSettings, Vector3,
+};
+use autd3_emulator_server::{HistoryLimits, Modulation};
+use imgui::*;
+
+use crate::{
+    drive_history::{DriveHistory, SourceHistory},
+    io_worker::IoWorker,
+    settings::Setting,
+};
+
+const PLOT_CHANGES: usize = 200;
+
+/// History of the drive of the picked transducer
+pub struct DriveHistoryPanel {
+    history: DriveHistory,
+    path: ImString,
+}
+
+impl DriveHistoryPanel {
+    pub fn new(setting: &Setting, limits: HistoryLimits) -> Self {
+        let mut path = ImString::with_capacity(256);
+        path.push_str(&setting.drive_history_path);
+        Self {
+            history: DriveHistory::new(limits),
+            path,
+        }
+    }
+
+    /// History of the source `index` at `pos`, which is None if it has never existed
+    pub fn source(&self, index: usize, pos: Vector3) -> Option<SourceHistory> {
+        self.history.source(index, pos)
+    }
+
+    /// `time` is the current simulation time, up to which the waveform is exported
+    pub fn ui(
+        &mut self,
+        ui: &Ui,
+        history: SourceHistory,
+        setting: &mut Setting,
+        time: f64,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        let id = ui.push_id(im_str!("drive history"));
+        let amps: Vec<_> = history
+            .changes
+            .iter()
+            .rev()
+            .take(PLOT_CHANGES)
+            .rev()
+            .map(|c| c.amp)
+            .collect();
+        PlotLines::new(ui, im_str!("Amplitude"), &amps)
+            .scale_min(0.0)
+            .scale_max(1.0)
+            .overlay_text(&ImString::new(format!(
+                "{} drive changes",
+                history.changes.len()
+            )))
+            .graph_size([0.0, 60.0])
+            .build();
+        Drag::new(im_str!("Waveform length [ms]"))
+            .speed(1.0)
+            .range(0.1..=60000.0)
+            .build(ui, &mut setting.drive_waveform_duration);
+        Drag::new(im_str!("Waveform rate [kHz]"))
+            .speed(1.0)
+            .range(1.0..=10000.0)
+            .build(ui, &mut setting.drive_waveform_rate);
+        ui.input_text(im_str!("History file"), &mut self.path)
+            .build();
+        let mut export = None;
+        if ui.small_button(im_str!("Export CSV")) {
+            export = Some("csv");
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Export WAV")) {
+            export = Some("wav");
+        }
+        id.pop(ui);
+        if let Some(extension) = export {
+            self.export(history, extension, setting, time, metadata, io_worker);
+        }
+    }
+
+    pub fn record(&mut self, time: f64, sources: &[SoundSource], settings: &ViewerSettings) {
+        self.history.record(time, sources, settings);
+    }
+
+    pub fn record_modulation(&mut self, time: f64, modulation: Option<Modulation>) {
+        self.history.record_modulation(time, modulation);
+    }
+
+    /// Forgets the history, whose times are of the previous clock
+    pub fn reset(&mut self, limits: HistoryLimits) {
+        self.history = DriveHistory::new(limits);
+    }
+
+    pub fn set_limits(&mut self, limits: HistoryLimits) {
+        self.history.set_limits(limits);
+    }
+
+    /// Writes the path edited in the panel into `setting`
+    pub fn save(&self, setting: &mut Setting) {
+        setting.drive_history_path = self.path.to_str().to_owned();
+    }
+
+    // The waveform is of the last `drive_waveform_duration` up to `time`
+    fn export(
+        &self,
+        history: SourceHistory,
+        extension: &'static str,
+        setting: &Setting,
+        time: f64,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        let path = Path::new(self.path.to_str()).with_extension(extension);
+        let metadata = metadata.clone();
+        let duration = setting.drive_waveform_duration as f64 / 1000.0;
+        let start = (time - duration).max(0.0);
+        let sample_rate = setting.drive_waveform_rate as f64 * 1000.0;
+        io_worker.submit("Export drive history", move || {
+            if let Some(folder) = path.parent() {
+                std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+            }
+            match extension {
+                "wav" => history.write_wav(&path, start, duration, sample_rate)?,
+                _ => history.write_csv(&path, &metadata)?,
+            }
+            tracing::info!("exported {}", path.display());
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,58 @@
+/*
+ * File: experiment.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::metadata::ExperimentMetadata;
+use imgui::*;
+
+/// Description of the experiment embedded into the exported files
+pub struct ExperimentPanel {
+    experiment_id: ImString,
+    operator: ImString,
+    notes: ImString,
+}
+
+impl ExperimentPanel {
+    pub fn new(metadata: &ExperimentMetadata) -> Self {
+        let mut experiment_id = ImString::with_capacity(64);
+        experiment_id.push_str(&metadata.experiment_id);
+        let mut operator = ImString::with_capacity(64);
+        operator.push_str(&metadata.operator);
+        let mut notes = ImString::with_capacity(1024);
+        notes.push_str(&metadata.notes);
+        Self {
+            experiment_id,
+            operator,
+            notes,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui) {
+        ui.text(im_str!("Experiment (embedded into exported files)"));
+        ui.input_text(im_str!("Experiment ID"), &mut self.experiment_id)
+            .build();
+        ui.input_text(im_str!("Operator"), &mut self.operator)
+            .build();
+        ui.input_text_multiline(im_str!("Notes"), &mut self.notes, [0.0, 60.0])
+            .build();
+    }
+
+    /// Metadata as entered, with the tags of the current gain
+    pub fn metadata(&self, gain_tags: String) -> ExperimentMetadata {
+        ExperimentMetadata {
+            experiment_id: self.experiment_id.to_str().to_owned(),
+            operator: self.operator.to_str().to_owned(),
+            notes: self.notes.to_str().to_owned(),
+            gain_tags,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * File: hydrophone.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::path::Path;
+
+use acoustic_field_viewer::metadata::ExperimentMetadata;
+use imgui::*;
+
+use crate::{
+    hydrophone::{HydrophoneTrace, TraceSource},
+    io_worker::IoWorker,
+    settings::Setting,
+};
+
+/// Trace of a hydrophone at the probe
+pub struct HydrophonePanel {
+    trace: Option<HydrophoneTrace>,
+    path: ImString,
+}
+
+impl HydrophonePanel {
+    pub fn new(setting: &Setting) -> Self {
+        let mut path = ImString::with_capacity(256);
+        path.push_str(&setting.hydrophone_path);
+        Self { trace: None, path }
+    }
+
+    /// Returns whether a trace is requested, which is recorded by [HydrophonePanel::record]
+    pub fn ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) -> bool {
+        ui.text(im_str!("Hydrophone"));
+        Drag::new(im_str!("Duration [ms]##hydrophone"))
+            .speed(0.1)
+            .range(0.01..=1000.0)
+            .build(ui, &mut setting.hydrophone_duration);
+        Drag::new(im_str!("Sample rate [kHz]##hydrophone"))
+            .speed(1.0)
+            .range(1.0..=10000.0)
+            .build(ui, &mut setting.hydrophone_sample_rate);
+        let requested = ui.small_button(im_str!("Record trace"));
+        let trace = match &self.trace {
+            Some(trace) => trace,
+            None => return requested,
+        };
+        PlotLines::new(ui, im_str!("##hydrophone"), &trace.samples)
+            .graph_size([0.0, 80.0])
+            .build();
+        let locale = &setting.locale;
+        let peak = trace.samples.iter().fold(0.0f32, |a, p| a.max(p.abs()));
+        ui.text(format!(
+            "{} samples from {} [s], peak {} [Pa]",
+            trace.samples.len(),
+            locale.number(trace.start, 4),
+            locale.number(peak as f64, 1)
+        ));
+        ui.input_text(im_str!("Trace file"), &mut self.path).build();
+        if ui.small_button(im_str!("Export CSV##hydrophone")) {
+            self.export("csv", metadata, io_worker);
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Export WAV##hydrophone")) {
+            self.export("wav", metadata, io_worker);
+        }
+        requested
+    }
+
+    /// Synthesizes the trace at the probe from `time`, as the field is far too fast to sample
+    /// frame by frame
+    pub fn record(&mut self, source: &TraceSource, setting: &Setting, time: f64) {
+        let trace = source.record(
+            setting.probe_pos,
+            time,
+            setting.hydrophone_duration as f64 / 1000.0,
+            setting.hydrophone_sample_rate as f64 * 1000.0,
+        );
+        tracing::info!(
+            "recorded {} samples ({} s) at the probe",
+            trace.samples.len(),
+            trace.duration()
+        );
+        self.trace = Some(trace);
+    }
+
+    /// Writes the path edited in the panel into `setting`
+    pub fn save(&self, setting: &mut Setting) {
+        setting.hydrophone_path = self.path.to_str().to_owned();
+    }
+
+    fn export(
+        &self,
+        extension: &'static str,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        let trace = match &self.trace {
+            Some(trace) => trace.clone(),
+            None => return,
+        };
+        let path = Path::new(self.path.to_str()).with_extension(extension);
+        let metadata = metadata.clone();
+        io_worker.submit("Export hydrophone trace", move || {
+            if let Some(folder) = path.parent() {
+                std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+            }
+            match extension {
+                "wav" => trace.write_wav(&path)?,
+                _ => trace.write_csv(&path, &metadata)?,
+            }
+            tracing::info!("exported {}", path.display());
+            Ok(())
+        });
+    }
+}
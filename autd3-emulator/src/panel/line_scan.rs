@@ -0,0 +1,215 @@
+/*
+ * File: line_scan.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::path::Path;
+
+use acoustic_field_viewer::{
+    camera_helper, field::FieldModel, metadata::ExperimentMetadata, Matrix4, Vector3,
+};
+use imgui::*;
+
+use crate::{
+    io_worker::IoWorker,
+    line_scan::{LineProfile, LineScan},
+    settings::Setting,
+};
+
+/// Profile of the field along a line
+pub struct LineScanPanel {
+    profile: Option<LineProfile>,
+    path: ImString,
+}
+
+impl LineScanPanel {
+    pub fn new(setting: &Setting) -> Self {
+        let mut path = ImString::with_capacity(256);
+        path.push_str(&setting.line_scan_path);
+        Self {
+            profile: None,
+            path,
+        }
+    }
+
+    /// The profile is taken in `model` on every frame while shown
+    pub fn ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        model: &FieldModel,
+        slice_center: Vector3,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        ui.checkbox(im_str!("Line scan"), &mut setting.show_line_scan);
+        if !setting.show_line_scan {
+            self.profile = None;
+            return;
+        }
+        let scan = &mut setting.line_scan;
+        Drag::new(im_str!("Start [mm]##line"))
+            .speed(0.1)
+            .build_array(ui, &mut scan.start);
+        Drag::new(im_str!("End [mm]##line"))
+            .speed(0.1)
+            .build_array(ui, &mut scan.end);
+        let mut samples = scan.samples as i32;
+        if Drag::new(im_str!("Points##line"))
+            .range(2..=10000)
+            .build(ui, &mut samples)
+        {
+            scan.samples = samples.clamp(2, 10000) as usize;
+        }
+        // scans of the same length through the slice center
+        let length = scan.length().max(1.0);
+        for (i, (label, axis)) in [
+            (im_str!("x"), [1.0, 0.0, 0.0]),
+            (im_str!("y"), [0.0, 1.0, 0.0]),
+            (im_str!("z"), [0.0, 0.0, 1.0]),
+        ]
+        .iter()
+        .enumerate()
+        {
+            if i > 0 {
+                ui.same_line(0.);
+            }
+            let id = ui.push_id(i as i32);
+            if ui.small_button(label) {
+                *scan = LineScan::centered(slice_center, *axis, length, scan.samples);
+            }
+            id.pop(ui);
+        }
+        ui.same_line(0.);
+        ui.text("through slice center");
+
+        let profile = setting.line_scan.profile(model);
+        PlotLines::new(ui, im_str!("##line scan"), &profile.amps)
+            .graph_size([0.0, 100.0])
+            .scale_min(0.0)
+            .build();
+        let locale = &setting.locale;
+        let settings = &setting.viewer_setting;
+        if let Some((s, amp)) = profile.peak() {
+            ui.text(format!(
+                "Peak |p| = {} [{}] at {} [mm]",
+                locale.number(
+                    settings.pressure_unit.convert(amp, &settings.calibration) as f64,
+                    2
+                ),
+                settings.pressure_unit.name(),
+                locale.number(s as f64, 1)
+            ));
+        }
+        match profile.fwhm() {
+            Some(w) => ui.text(format!("FWHM = {} [mm]", locale.number(w as f64, 2))),
+            None => ui.text_disabled("FWHM: the peak is not within the line"),
+        }
+        ui.checkbox(
+            im_str!("Nonlinearity##line"),
+            &mut setting.line_scan_nonlinearity,
+        );
+        if setting.line_scan_nonlinearity {
+            let sigma = profile.shock_parameter(
+                &settings.medium,
+                settings.frequency,
+                settings.calibration.pa_per_unit(),
+            );
+            PlotLines::new(ui, im_str!("##shock parameter"), &sigma)
+                .graph_size([0.0, 60.0])
+                .scale_min(0.0)
+                .overlay_text(im_str!("shock parameter"))
+                .build();
+            let max = sigma.last().copied().unwrap_or(0.0);
+            match profile.shock_distance(&sigma) {
+                Some(s) => ui.text_colored(
+                    [1.0, 0.3, 0.3, 1.0],
+                    format!(
+                        "Shock at {} [mm] from the start",
+                        locale.number(s as f64, 1)
+                    ),
+                ),
+                None => ui.text(format!(
+                    "Shock parameter = {}, 2nd harmonic ~ {} [dB]",
+                    locale.number(max as f64, 3),
+                    locale.number(20.0 * (max as f64 / 2.0).max(1e-12).log10(), 1)
+                )),
+            }
+        }
+        ui.input_text(im_str!("Profile file"), &mut self.path)
+            .build();
+        if ui.small_button(im_str!("Export CSV##line")) {
+            self.export(
+                &profile,
+                settings.calibration.pa_per_unit(),
+                metadata,
+                io_worker,
+            );
+        }
+        self.profile = Some(profile);
+    }
+
+    /// Draws the line with its peak marked
+    pub fn draw(&self, ui: &Ui, scan: &LineScan, view_projection: (Matrix4, Matrix4)) {
+        const COLOR: [f32; 4] = [1.0, 0.9, 0.2, 1.0];
+
+        let screen_size = ui.io().display_size;
+        let project = |p| camera_helper::project_to_screen(view_projection, p, screen_size);
+        let (a, b) = match (project(scan.start), project(scan.end)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return,
+        };
+        let draw_list = ui.get_background_draw_list();
+        draw_list.add_line(a, b, COLOR).thickness(1.5).build();
+        draw_list.add_circle(a, 3.0, COLOR).filled(true).build();
+        draw_list.add_circle(b, 3.0, COLOR).build();
+        let peak = self.profile.as_ref().and_then(|profile| {
+            let (s, _) = profile.peak()?;
+            let len = scan.length();
+            let t = if len > 0.0 { s / len } else { 0.0 };
+            let d = vecmath::vec3_sub(scan.end, scan.start);
+            project(vecmath::vec3_add(scan.start, vecmath::vec3_scale(d, t)))
+        });
+        if let Some(p) = peak {
+            draw_list
+                .add_line([p[0] - 5.0, p[1]], [p[0] + 5.0, p[1]], COLOR)
+                .build();
+            draw_list
+                .add_line([p[0], p[1] - 5.0], [p[0], p[1] + 5.0], COLOR)
+                .build();
+        }
+    }
+
+    /// Writes the path edited in the panel into `setting`
+    pub fn save(&self, setting: &mut Setting) {
+        setting.line_scan_path = self.path.to_str().to_owned();
+    }
+
+    fn export(
+        &self,
+        profile: &LineProfile,
+        pa_per_unit: f32,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        let profile = profile.clone();
+        let path = Path::new(self.path.to_str()).to_owned();
+        let metadata = metadata.clone();
+        io_worker.submit("Export line profile", move || {
+            if let Some(folder) = path.parent() {
+                std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+            }
+            profile.write_csv(&path, &metadata, pa_per_unit)?;
+            tracing::info!("exported {}", path.display());
+            Ok(())
+        });
+    }
+}
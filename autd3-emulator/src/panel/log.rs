@@ -0,0 +1,136 @@
+/*
+ * File: log.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use imgui::*;
+
+use crate::{
+    journal::Journal,
+    log::{self, LogBuffer, LEVELS},
+    settings::Setting,
+};
+
+/// Log tab, with the journal of the decoded commands
+pub struct LogPanel {
+    buffer: LogBuffer,
+    // index in LEVELS of the captured records shown, which never shows more than captured
+    view_level: usize,
+    filter: ImString,
+    journal: Option<Journal>,
+    journal_path: ImString,
+}
+
+impl LogPanel {
+    pub fn new(buffer: LogBuffer, setting: &Setting) -> Self {
+        let mut journal_path = ImString::with_capacity(256);
+        journal_path.push_str(&setting.journal_path);
+        Self {
+            buffer,
+            view_level: LEVELS.len() - 1,
+            filter: ImString::with_capacity(64),
+            journal: None,
+            journal_path,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui, setting: &mut Setting) {
+        ui.text(im_str!("Journal of decoded commands (JSON lines)"));
+        ui.checkbox(im_str!("Journal enable"), &mut setting.journal_enable);
+        ui.input_text(im_str!("Journal path"), &mut self.journal_path)
+            .read_only(self.journal.is_some())
+            .build();
+        let mut max_kb = setting.journal_max_kb as i32;
+        if ui
+            .input_int(im_str!("Rotate size [KB]"), &mut max_kb)
+            .build()
+        {
+            setting.journal_max_kb = max_kb.max(1) as u32;
+        }
+        ui.separator();
+        if ui.radio_button_bool(im_str!("enable"), setting.log_enable) {
+            setting.log_enable = !setting.log_enable;
+            self.buffer.set_enable(setting.log_enable);
+        }
+        if !setting.log_enable {
+            return;
+        }
+        if Slider::new(im_str!("Max"))
+            .range(0..=1000)
+            .build(ui, &mut setting.log_max)
+        {
+            self.buffer.set_max(setting.log_max as _);
+        }
+        let mut level = LEVELS
+            .iter()
+            .position(|&l| l == log::parse_level(&setting.log_level))
+            .unwrap_or(2);
+        if ComboBox::new(im_str!("Level")).build_simple(ui, &mut level, &LEVELS, &|l| {
+            ImString::new(l.as_str()).into()
+        }) {
+            setting.log_level = LEVELS[level].as_str().to_owned();
+            self.buffer.set_level(LEVELS[level]);
+        }
+        ComboBox::new(im_str!("Show")).build_simple(ui, &mut self.view_level, &LEVELS, &|l| {
+            ImString::new(l.as_str()).into()
+        });
+        InputText::new(ui, im_str!("Filter"), &mut self.filter).build();
+        if ui.small_button(im_str!("clear")) {
+            self.buffer.clear();
+        }
+        ui.separator();
+        ui.text(self.buffer.text(
+            LEVELS[self.view_level],
+            self.filter.to_str(),
+            setting.locale,
+        ));
+    }
+
+    /// Captures the records at the level of `setting` from now on
+    pub fn apply(&self, setting: &Setting) {
+        self.buffer.set_level(log::parse_level(&setting.log_level));
+    }
+
+    /// Writes the path edited in the panel into `setting`
+    pub fn save(&self, setting: &mut Setting) {
+        if self.journal.is_none() {
+            setting.journal_path = self.journal_path.to_str().to_owned();
+        }
+    }
+
+    pub fn journal(&mut self) -> Option<&mut Journal> {
+        self.journal.as_mut()
+    }
+
+    /// Opens or closes the journal to follow the setting
+    pub fn update_journal(&mut self, setting: &mut Setting) {
+        match (&self.journal, setting.journal_enable) {
+            (None, true) => {
+                setting.journal_path = self.journal_path.to_str().to_owned();
+                match Journal::open(&setting.journal_path, setting.journal_max_kb as u64 * 1024) {
+                    Ok(journal) => {
+                        tracing::info!("journal opened: {}", setting.journal_path);
+                        self.journal = Some(journal);
+                    }
+                    Err(e) => {
+                        tracing::error!("failed to open journal {}: {}", setting.journal_path, e);
+                        setting.journal_enable = false;
+                    }
+                }
+            }
+            (Some(_), false) => {
+                self.journal = None;
+                tracing::info!("journal closed");
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+/*
+ * File: mod.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+//! Panels of the Controller window. Each keeps the state of its widgets, which the setting
+//! does not have, and draws itself with `ui`.
+
+mod acoustic_camera;
+mod camera;
+mod console;
+mod drive_history;
+mod experiment;
+mod hydrophone;
+mod line_scan;
+mod log;
+#[cfg(feature = "offscreen_renderer")]
+mod offscreen;
+mod particles;
+mod session;
+mod slice;
+mod ui_layout;
+
+pub use acoustic_camera::AcousticCameraPanel;
+pub use camera::{CameraPanel, BOOKMARK_HOTKEYS};
+pub use console::ConsolePanel;
+pub use drive_history::DriveHistoryPanel;
+pub use experiment::ExperimentPanel;
+pub use hydrophone::HydrophonePanel;
+pub use line_scan::LineScanPanel;
+pub use log::LogPanel;
+#[cfg(feature = "offscreen_renderer")]
+pub use offscreen::OffscreenPanel;
+pub use particles::ParticlesPanel;
+pub use session::SessionPanel;
+pub use slice::SlicePanel;
+pub use ui_layout::UiLayoutPanel;
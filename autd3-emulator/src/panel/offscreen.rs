@@ -0,0 +1,133 @@
+/*
+ * File: offscreen.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::path::{Path, PathBuf};
+
+use acoustic_field_viewer::{
+    metadata::ExperimentMetadata,
+    sound_source::SoundSource,
+    view::{AcousticFiledSliceViewer, UpdateFlag},
+};
+use imgui::*;
+use offscreen_renderer::OffscreenRenderer;
+
+use crate::{io_worker::IoWorker, settings::Setting};
+
+/// The slice saved as PNG by the offscreen renderer, once or on every frame while recording
+pub struct OffscreenPanel {
+    renderer: OffscreenRenderer,
+    save_path: ImString,
+    record_path: ImString,
+    recording: bool,
+}
+
+impl OffscreenPanel {
+    pub fn new(setting: &Setting) -> Self {
+        Self {
+            renderer: OffscreenRenderer::new(),
+            save_path: ImString::new(&setting.save_file_path),
+            record_path: ImString::new(&setting.record_path),
+            recording: false,
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        sources: &[SoundSource],
+        viewer: &AcousticFiledSliceViewer,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        ui.text(im_str!("Save as file"));
+        if ui.radio_button_bool(im_str!("save enable"), setting.save_file_enable) {
+            setting.save_file_enable = !setting.save_file_enable;
+        }
+        if !setting.save_file_enable {
+            return;
+        }
+        let bb = (
+            setting.viewer_setting.slice_width as usize,
+            setting.viewer_setting.slice_height as usize,
+        );
+        InputText::new(ui, im_str!("save path"), &mut self.save_path).build();
+        if ui.small_button(im_str!("save")) {
+            self.renderer
+                .calculate_field(sources, &setting.viewer_setting);
+            let path = PathBuf::from(self.save_path.to_str());
+            self.export_png(path, bb, viewer, metadata, io_worker);
+        }
+
+        ui.separator();
+        InputText::new(ui, im_str!("record path"), &mut self.record_path).build();
+        if ui.small_button(if self.recording {
+            im_str!("stop recording")
+        } else {
+            im_str!("record")
+        }) {
+            self.recording = !self.recording;
+        }
+        if !self.recording {
+            return;
+        }
+        self.renderer
+            .calculate_field(sources, &setting.viewer_setting);
+        if let Err(e) = std::fs::create_dir_all(self.record_path.to_str()) {
+            tracing::error!("failed to create {}: {}", self.record_path.to_str(), e);
+            self.recording = false;
+            return;
+        }
+        let date = chrono::Local::now();
+        let path = Path::new(self.record_path.to_str())
+            .join(format!("{}", date.format("%Y-%m-%d_%H-%M-%S_%3f.png")));
+        self.export_png(path, bb, viewer, metadata, io_worker);
+    }
+
+    /// Keeps the renderer up to date with the slice while saving is enabled
+    pub fn update(
+        &mut self,
+        setting: &Setting,
+        sources: &[SoundSource],
+        viewer: &AcousticFiledSliceViewer,
+        update_flag: UpdateFlag,
+    ) {
+        if setting.save_file_enable {
+            self.renderer
+                .update(sources, viewer, &setting.viewer_setting, update_flag);
+        }
+    }
+
+    pub fn save(&self, setting: &mut Setting) {
+        setting.save_file_path = self.save_path.to_str().to_owned();
+        setting.record_path = self.record_path.to_str().to_owned();
+    }
+
+    // The field is read here and encoded on the I/O worker
+    fn export_png(
+        &self,
+        path: PathBuf,
+        bb: (usize, usize),
+        viewer: &AcousticFiledSliceViewer,
+        metadata: &ExperimentMetadata,
+        io_worker: &mut IoWorker,
+    ) {
+        let field = self.renderer.field(bb);
+        let colormap = viewer.color_map().to_vec();
+        let metadata = metadata.clone();
+        io_worker.submit("Export PNG", move || {
+            OffscreenRenderer::encode_png(&path, &field, bb, &colormap, &metadata)
+                .map_err(|e| format!("{}: {}", path.display(), e))
+        });
+    }
+}
@@ -0,0 +1,193 @@
+/*
+ * File: particles.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{
+    camera_helper, directivity::DirectivityTable, field::FieldModel, levitation::LevitatedParticle,
+    scatterer::Scattering, sound_source::SoundSource, Matrix4, Vector3,
+};
+use autd3_core::hardware_defined::POINT_SEQ_BASE_FREQ;
+use imgui::*;
+
+use crate::{decoder::Decoder, settings::Setting, stm_duty};
+
+const PARTICLE_STEP: f64 = 1e-4;
+const MAX_SUBSTEPS: usize = 200;
+const MAX_DISTANCE: f32 = 2000.0;
+
+/// Particles dropped into the field, integrated on the clock of the emulator
+pub struct ParticlesPanel {
+    particles: Vec<LevitatedParticle>,
+    drop_pos: Vector3,
+    // Simulation time to which the particles are integrated
+    time: f64,
+}
+
+impl ParticlesPanel {
+    pub fn new(drop_pos: Vector3) -> Self {
+        Self {
+            particles: Vec::new(),
+            drop_pos,
+            time: 0.0,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui, setting: &mut Setting, slice_center: Vector3) {
+        let particle = &mut setting.drop_particle;
+        Drag::new(im_str!("Radius [mm]"))
+            .speed(0.01)
+            .range(0.01..=f32::INFINITY)
+            .build(ui, &mut particle.radius);
+        Drag::new(im_str!("Density [kg/m^3]"))
+            .speed(1.0)
+            .range(0.01..=f32::INFINITY)
+            .build(ui, &mut particle.density);
+        let mut sound_speed = particle.sound_speed / 1000.0;
+        if Drag::new(im_str!("Sound speed [m/s]"))
+            .speed(1.0)
+            .range(1.0..=f32::INFINITY)
+            .build(ui, &mut sound_speed)
+        {
+            particle.sound_speed = sound_speed * 1000.0;
+        }
+        Drag::new(im_str!("Drop at [mm]"))
+            .speed(0.1)
+            .build_array(ui, &mut self.drop_pos);
+        if ui.small_button(im_str!("Drop")) {
+            self.particles
+                .push(LevitatedParticle::new(setting.drop_particle, self.drop_pos));
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Slice center")) {
+            self.drop_pos = slice_center;
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Clear")) {
+            self.particles.clear();
+        }
+        ui.separator();
+        let locale = &setting.locale;
+        for (i, p) in self.particles.iter().enumerate() {
+            ui.text(format!(
+                "{}: {} [mm], {} [mm/s]",
+                i,
+                locale.vec3(p.pos, 2),
+                locale.number(vecmath::vec3_len(p.vel) as f64, 1)
+            ));
+        }
+    }
+
+    /// Integrates the particles up to `time` in substeps of at most PARTICLE_STEP, in the field
+    /// of the current drive
+    pub fn update(
+        &mut self,
+        time: f64,
+        drive: &mut Decoder,
+        setting: &Setting,
+        directivity_table: Option<&DirectivityTable>,
+        scattering: Option<&Scattering>,
+    ) {
+        let dt = time - std::mem::replace(&mut self.time, time);
+        if dt <= 0.0 || self.particles.is_empty() {
+            return;
+        }
+        // after a seek of the clock, only the last substeps are simulated
+        let dt = dt.min(MAX_SUBSTEPS as f64 * PARTICLE_STEP);
+        let n = ((dt / PARTICLE_STEP).ceil() as usize).clamp(1, MAX_SUBSTEPS);
+        let h = dt / n as f64;
+        // the STM point played at the end of each substep, as update_playback does
+        let stm_points: Vec<(usize, Vector3, u8)> = match drive.active_sequence() {
+            Some(seq) if drive.last_amp.is_empty() => {
+                let freq = POINT_SEQ_BASE_FREQ as f64 / seq.seq_div.max(1) as f64;
+                let points = drive.stm_points();
+                let len = points.len() as u64;
+                (1..=n)
+                    .map(|i| {
+                        let t = time - dt + i as f64 * h;
+                        let idx = ((t * freq) as u64 % len) as usize;
+                        let (focus, duty) = points[idx];
+                        (idx, focus, duty)
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let pressure_scale = setting.viewer_setting.calibration.pa_per_unit();
+        let mut stm_drive: Option<(usize, Vec<SoundSource>)> = None;
+        for i in 0..n {
+            if let Some(&(idx, focus, duty)) = stm_points.get(i) {
+                if stm_drive.as_ref().is_none_or(|(j, _)| *j != idx) {
+                    // the drive of the current point is already adjusted
+                    let sources = if drive.seq_index == Some(idx) {
+                        drive.sources.clone()
+                    } else {
+                        let mut sources = stm_duty::focused_drive(
+                            &drive.sources,
+                            &setting.viewer_setting,
+                            focus,
+                            duty,
+                        );
+                        drive.apply_drive_errors_to(&mut sources, &setting.perturbation);
+                        sources
+                    };
+                    stm_drive = Some((idx, sources));
+                }
+            }
+            let model = FieldModel {
+                sources: stm_drive
+                    .as_ref()
+                    .map_or(&drive.sources, |(_, sources)| sources),
+                settings: &setting.viewer_setting,
+                directivity_table,
+                scattering,
+                device_sizes: &drive.device_sizes,
+            };
+            for particle in self.particles.iter_mut() {
+                particle.step(&model, pressure_scale, h as f32);
+            }
+        }
+        let (near, lost): (Vec<_>, Vec<_>) = std::mem::take(&mut self.particles)
+            .into_iter()
+            .partition(|p| vecmath::vec3_len(p.pos) < MAX_DISTANCE);
+        for p in lost {
+            tracing::info!("particle lost at {:?}", p.pos);
+        }
+        self.particles = near;
+    }
+
+    /// Draws the particles as discs of the projected radius, at least a few pixels
+    pub fn draw(&self, ui: &Ui, view_projection: (Matrix4, Matrix4)) {
+        const COLOR: [f32; 4] = [1.0, 0.6, 0.1, 1.0];
+
+        let (view, _) = view_projection;
+        let up = [view[0][1], view[1][1], view[2][1]];
+        let screen_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        for particle in &self.particles {
+            let edge = vecmath::vec3_add(
+                particle.pos,
+                vecmath::vec3_scale(up, particle.particle.radius),
+            );
+            let (p, edge) = match (
+                camera_helper::project_to_screen(view_projection, particle.pos, screen_size),
+                camera_helper::project_to_screen(view_projection, edge, screen_size),
+            ) {
+                (Some(p), Some(edge)) => (p, edge),
+                _ => continue,
+            };
+            let r = ((edge[0] - p[0]).powi(2) + (edge[1] - p[1]).powi(2))
+                .sqrt()
+                .max(3.0);
+            draw_list.add_circle(p, r, COLOR).filled(true).build();
+        }
+    }
+}
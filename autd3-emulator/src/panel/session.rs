@@ -0,0 +1,265 @@
+/*
+ * File: session.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{net::SocketAddr, path::Path};
+
+use autd3_emulator_server::AutdServer;
+use imgui::*;
+
+use crate::{file_watcher::FileWatcher, read_layouts, settings::Setting};
+
+/// Transducer layout, capture, session and handoff.
+///
+/// The requests are carried out by [SessionPanel::update] with the server, except for saving and
+/// loading the session, which are taken by the caller.
+pub struct SessionPanel {
+    layout_file: ImString,
+    layout_requested: bool,
+    layout_watcher: Option<FileWatcher>,
+    capture_path: ImString,
+    capture_requested: bool,
+    capturing: bool,
+    session_path: ImString,
+    save_requested: bool,
+    load_requested: bool,
+    handoff_addr: ImString,
+    handoff_requested: bool,
+    trusted_peers: ImString,
+    peers_changed: bool,
+    rejected: Option<SocketAddr>,
+}
+
+impl SessionPanel {
+    pub fn new(setting: &Setting) -> Self {
+        let mut layout_file = ImString::with_capacity(256);
+        layout_file.push_str(&setting.layout_file);
+        let mut capture_path = ImString::with_capacity(256);
+        capture_path.push_str(&setting.capture_path);
+        let mut session_path = ImString::with_capacity(256);
+        session_path.push_str(&setting.session_path);
+        let mut handoff_addr = ImString::with_capacity(64);
+        handoff_addr.push_str(&setting.handoff_addr);
+        let mut trusted_peers = ImString::with_capacity(256);
+        trusted_peers.push_str(&setting.handoff_trusted_peers.join(", "));
+        Self {
+            layout_file,
+            layout_requested: false,
+            layout_watcher: None,
+            capture_path,
+            capture_requested: false,
+            capturing: false,
+            session_path,
+            save_requested: false,
+            load_requested: false,
+            handoff_addr,
+            handoff_requested: false,
+            trusted_peers,
+            peers_changed: false,
+            rejected: None,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui, setting: &mut Setting) {
+        ui.text(im_str!("Transducer layout"));
+        ui.input_text(im_str!("Layout file"), &mut self.layout_file)
+            .build();
+        if ui.small_button(im_str!("Load layout")) {
+            self.layout_requested = true;
+        }
+        ui.same_line(0.0);
+        if ui.checkbox(im_str!("Watch layout file"), &mut setting.watch_layout_file) {
+            self.watch_layout(setting);
+        }
+        ui.separator();
+        ui.text(im_str!("Capture (raw frames, for --replay)"));
+        ui.input_text(im_str!("Capture file"), &mut self.capture_path)
+            .read_only(self.capturing)
+            .build();
+        if ui.small_button(if self.capturing {
+            im_str!("Stop capture")
+        } else {
+            im_str!("Start capture")
+        }) {
+            self.capture_requested = true;
+        }
+        ui.separator();
+        ui.text(im_str!("Session"));
+        ui.input_text(im_str!("Session file"), &mut self.session_path)
+            .build();
+        if ui.small_button(im_str!("Save session")) {
+            self.save_requested = true;
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Load session")) {
+            self.load_requested = true;
+        }
+        ui.separator();
+        ui.text(im_str!("Handoff"));
+        ui.input_text(im_str!("Handoff address"), &mut self.handoff_addr)
+            .build();
+        if ui.small_button(im_str!("Hand off session")) {
+            self.handoff_requested = true;
+        }
+        if ui
+            .input_text(im_str!("Trusted peers"), &mut self.trusted_peers)
+            .build()
+        {
+            self.peers_changed = true;
+        }
+        if let Some(rejected) = self.rejected {
+            ui.text_colored(
+                [1.0, 0.8, 0.0, 1.0],
+                format!(
+                    "Viewer setting from {} rejected, not a trusted peer",
+                    rejected
+                ),
+            );
+            if ui.small_button(im_str!("Trust")) {
+                if !self.trusted_peers.to_str().trim().is_empty() {
+                    self.trusted_peers.push_str(", ");
+                }
+                self.trusted_peers.push_str(&rejected.ip().to_string());
+                self.peers_changed = true;
+                self.rejected = None;
+            }
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Dismiss")) {
+                self.rejected = None;
+            }
+        }
+        if self.peers_changed {
+            setting.handoff_trusted_peers = self.peers();
+        }
+    }
+
+    /// Carries out the requests from [SessionPanel::ui] and the modified layout file.
+    /// The requests other than the handoff are dropped when the server is not running.
+    pub fn update(&mut self, autd_server: Option<&mut AutdServer>, setting: &Setting, time: f64) {
+        if self.layout_watcher.as_mut().is_some_and(|w| w.poll()) {
+            tracing::info!("layout file modified");
+            self.layout_requested = true;
+        }
+        let autd_server = match autd_server {
+            Some(autd_server) => autd_server,
+            None => {
+                if std::mem::take(&mut self.handoff_requested) {
+                    tracing::error!("handoff failed: server is not running");
+                }
+                self.capture_requested = false;
+                self.layout_requested = false;
+                self.peers_changed = false;
+                return;
+            }
+        };
+        if std::mem::take(&mut self.capture_requested) {
+            self.toggle_capture(autd_server);
+        }
+        self.capturing = autd_server.is_capturing();
+        if std::mem::take(&mut self.peers_changed) {
+            autd_server.set_trusted_peers(&setting.trusted_peers());
+        }
+        if let Some(rejected) = autd_server.take_rejected_viewer_setting() {
+            tracing::warn!(
+                "viewer setting from {} rejected, not a trusted peer",
+                rejected
+            );
+            self.rejected = Some(rejected);
+        }
+        if std::mem::take(&mut self.handoff_requested) {
+            self.handoff(autd_server, setting, time);
+        }
+        if std::mem::take(&mut self.layout_requested) {
+            self.load_layout(autd_server, setting);
+        }
+    }
+
+    /// Returns the session file if saving it is requested
+    pub fn take_save(&mut self) -> Option<String> {
+        std::mem::take(&mut self.save_requested).then(|| self.session_path.to_str().to_owned())
+    }
+
+    /// Returns the session file if loading it is requested
+    pub fn take_load(&mut self) -> Option<String> {
+        std::mem::take(&mut self.load_requested).then(|| self.session_path.to_str().to_owned())
+    }
+
+    pub fn save(&self, setting: &mut Setting) {
+        setting.layout_file = self.layout_file.to_str().to_owned();
+        setting.capture_path = self.capture_path.to_str().to_owned();
+        setting.session_path = self.session_path.to_str().to_owned();
+        setting.handoff_addr = self.handoff_addr.to_str().to_owned();
+        setting.handoff_trusted_peers = self.peers();
+    }
+
+    fn peers(&self) -> Vec<String> {
+        self.trusted_peers
+            .to_str()
+            .split(',')
+            .map(str::trim)
+            .filter(|peer| !peer.is_empty())
+            .map(str::to_owned)
+            .collect()
+    }
+
+    /// Sends the layout file to the server, and watches it if enabled
+    pub fn load_layout(&mut self, autd_server: &mut AutdServer, setting: &Setting) {
+        self.watch_layout(setting);
+        let path = self.layout_file.to_str();
+        if path.is_empty() {
+            return;
+        }
+        let layouts = match read_layouts(path) {
+            Ok(layouts) => layouts,
+            Err(e) => {
+                tracing::error!("failed to load layout {}: {}", path, e);
+                return;
+            }
+        };
+        match autd_server.set_layouts(&layouts) {
+            Ok(()) => tracing::info!(num_devices = layouts.len(), "load layout {}", path),
+            Err(e) => tracing::error!("failed to set layout: {}", e),
+        }
+    }
+
+    fn watch_layout(&mut self, setting: &Setting) {
+        let path = self.layout_file.to_str();
+        self.layout_watcher = if setting.watch_layout_file && !path.is_empty() {
+            match &self.layout_watcher {
+                Some(watcher) if watcher.path() == Path::new(path) => return,
+                _ => Some(FileWatcher::new(path)),
+            }
+        } else {
+            None
+        };
+    }
+
+    fn toggle_capture(&self, autd_server: &mut AutdServer) {
+        if autd_server.is_capturing() {
+            autd_server.stop_capture();
+            tracing::info!("capture stopped");
+            return;
+        }
+        let path = self.capture_path.to_str();
+        match autd_server.start_capture(path) {
+            Ok(()) => tracing::info!("capturing to {}", path),
+            Err(e) => tracing::error!("failed to start capture {}: {}", path, e),
+        }
+    }
+
+    fn handoff(&self, autd_server: &AutdServer, setting: &Setting, time: f64) {
+        let addr = self.handoff_addr.to_str();
+        let viewer_setting = serde_json::to_vec(&setting.viewer_setting).ok();
+        autd_server.handoff(addr, time, viewer_setting.as_deref());
+        tracing::info!("handing off to {}", addr);
+    }
+}
@@ -0,0 +1,474 @@
+/*
+ * File: slice.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{
+    calibration::PressureUnit,
+    camera_helper,
+    color_map::ColorMapKind,
+    view::{AcousticFiledSliceViewer, FieldQuantity, UpdateFlag},
+};
+use autd3_emulator_server::Geometry;
+use imgui::*;
+
+use crate::{settings::Setting, slices::PLANES};
+
+// Dynamic range in dB when the log scale is turned on
+const DEFAULT_DYNAMIC_RANGE: f32 = 40.0;
+// Points per mm of the slice sampling when enabled
+const DEFAULT_SLICE_SAMPLING: f32 = 1.0;
+
+/// Size, pose and color setting of the main slice, which is moved and rotated here along with
+/// the setting.
+///
+/// The imported color map is loaded by the caller from [SlicePanel::take_color_map].
+pub struct SlicePanel {
+    color_map_file: ImString,
+    color_map_requested: bool,
+}
+
+impl SlicePanel {
+    pub fn new(setting: &Setting) -> Self {
+        let mut color_map_file = ImString::with_capacity(256);
+        color_map_file.push_str(&setting.color_map_file);
+        Self {
+            color_map_file,
+            color_map_requested: !setting.color_map_file.is_empty(),
+        }
+    }
+
+    /// Size, sampling, position and rotation of the slice
+    pub fn transform_ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        viewer: &mut AcousticFiledSliceViewer,
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        ui.text(im_str!("Slice size"));
+        if Slider::new(im_str!("Slice width"))
+            .range(0..=1000)
+            .build(ui, &mut setting.viewer_setting.slice_width)
+        {
+            update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+        }
+        if Slider::new(im_str!("Slice heigh"))
+            .range(0..=1000)
+            .build(ui, &mut setting.viewer_setting.slice_height)
+        {
+            update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+        }
+        update_flag |= Self::sampling_ui(ui, setting, viewer);
+
+        ui.separator();
+        ui.text(im_str!("Slice position"));
+        ui.checkbox(im_str!("Follow focus"), &mut setting.follow_focus);
+        for (i, &label) in [im_str!("Slice X"), im_str!("Slice Y"), im_str!("Slice Z")]
+            .iter()
+            .enumerate()
+        {
+            if Drag::new(label).build(ui, &mut setting.viewer_setting.slice_pos[i]) {
+                viewer.move_to(setting.viewer_setting.slice_pos);
+                update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+            }
+        }
+
+        ui.separator();
+        ui.text(im_str!("Slice Rotation"));
+        for (i, &label) in [
+            im_str!("Slice RX"),
+            im_str!("Slice RY"),
+            im_str!("Slice RZ"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            if AngleSlider::new(label)
+                .range_degrees(0.0..=360.0)
+                .build(ui, &mut setting.viewer_setting.slice_angle[i])
+            {
+                viewer.rotate_to(setting.viewer_setting.slice_angle);
+                update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+            }
+        }
+        ui.checkbox(im_str!("Slice gizmo"), &mut setting.show_slice_gizmo);
+        if ui.is_item_hovered() {
+            ui.tooltip_text(
+                "Drag the arrow to move the slice along its normal and the rings to \
+                 rotate it",
+            );
+        }
+        if setting.show_slice_gizmo {
+            ui.same_line(0.);
+            ui.checkbox(im_str!("Snap to 15 deg"), &mut setting.slice_gizmo_snap);
+        }
+        update_flag |= Self::pose_ui(ui, setting, viewer);
+        update_flag
+    }
+
+    /// The field shown on the slice
+    pub fn quantity_ui(&mut self, ui: &Ui, setting: &mut Setting) -> UpdateFlag {
+        ui.text(im_str!("Slice color setting"));
+        let mut quantity = FieldQuantity::ALL
+            .iter()
+            .position(|&q| q == setting.viewer_setting.field_quantity)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Field")).build_simple(
+            ui,
+            &mut quantity,
+            &FieldQuantity::ALL,
+            &|q| ImString::new(q.name()).into(),
+        ) {
+            setting.viewer_setting.field_quantity = FieldQuantity::ALL[quantity];
+            return UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        UpdateFlag::empty()
+    }
+
+    /// Animation, color map and scale of the slice, where the wave phase of `viewer` is reset
+    /// when the animation stops
+    pub fn color_ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        viewer: &mut AcousticFiledSliceViewer,
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        if ui.checkbox(im_str!("Animate wave"), &mut setting.wave_animation) {
+            if setting.wave_animation {
+                setting.viewer_setting.field_quantity = FieldQuantity::RealPart;
+                update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+            } else {
+                viewer.set_wave_phase(0.0);
+            }
+        }
+        if setting.wave_animation {
+            Drag::new(im_str!("Animation rate [cycle/s]"))
+                .speed(0.01)
+                .range(0.0..=10.0)
+                .build(ui, &mut setting.wave_animation_rate);
+        }
+        ui.checkbox(im_str!("Auto scale"), &mut setting.auto_scale);
+        update_flag |= self.color_map_ui(ui, setting, viewer);
+
+        let viewer_setting = &mut setting.viewer_setting;
+        let unit = viewer_setting.pressure_unit;
+        let calibration = viewer_setting.calibration;
+        let mut color_scale = unit.convert(viewer_setting.color_scale, &calibration);
+        let speed = match unit {
+            PressureUnit::Pa => 0.1 * calibration.pa_per_unit(),
+            _ => 0.1,
+        };
+        if Drag::new(&ImString::new(format!("Color scale [{}]", unit.name())))
+            .speed(speed)
+            .range(0.0..=f32::INFINITY)
+            .build(ui, &mut color_scale)
+        {
+            viewer_setting.color_scale = unit.invert(color_scale, &calibration).max(0.0);
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if viewer_setting.field_quantity == FieldQuantity::RadiationPressure {
+            let amp = viewer_setting.calibration.to_pa(viewer_setting.color_scale);
+            ui.text_disabled(format!(
+                "Full scale: {:.3e} [Pa]",
+                viewer_setting.medium.radiation_pressure(amp)
+            ));
+        }
+        update_flag |= Self::particle_ui(ui, setting);
+
+        let viewer_setting = &mut setting.viewer_setting;
+        let mut log_scale = viewer_setting.dynamic_range.is_some();
+        if ui.checkbox(im_str!("Log scale"), &mut log_scale) {
+            viewer_setting.dynamic_range = log_scale.then_some(DEFAULT_DYNAMIC_RANGE);
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if let Some(range) = &mut viewer_setting.dynamic_range {
+            if Drag::new(im_str!("Dynamic range [dB]"))
+                .speed(0.1)
+                .range(1.0..=200.0)
+                .build(ui, range)
+            {
+                update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+            }
+        }
+        if (viewer_setting.dynamic_range.is_none() || !viewer_setting.field_quantity.is_magnitude())
+            && Drag::new(im_str!("Gamma"))
+                .speed(0.01)
+                .range(0.1..=5.0)
+                .build(ui, &mut viewer_setting.gamma)
+        {
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if Slider::new(im_str!("Slice alpha"))
+            .range(0.0..=1.0)
+            .build(ui, &mut viewer_setting.slice_alpha)
+        {
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        ui.checkbox(im_str!("Show color bar"), &mut setting.show_color_bar);
+        update_flag
+    }
+
+    /// Buttons rotating the slice to the planes, and aligning it to one of `geometries`
+    pub fn planes_ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        viewer: &mut AcousticFiledSliceViewer,
+        geometries: &[Geometry],
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        for (i, (label, angle)) in PLANES.iter().enumerate() {
+            if i > 0 {
+                ui.same_line(0.);
+            }
+            if ui.small_button(&ImString::new(*label)) {
+                setting.viewer_setting.slice_angle = *angle;
+                viewer.rotate_to(setting.viewer_setting.slice_angle);
+                update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+            }
+        }
+        if geometries.is_empty() {
+            return update_flag;
+        }
+        let last = geometries.len() - 1;
+        let mut device = setting.align_device.min(last) as i32;
+        if ui.input_int(im_str!("Device"), &mut device).build() {
+            setting.align_device = device.clamp(0, last as i32) as usize;
+        }
+        Drag::new(im_str!("Distance [mm]"))
+            .speed(1.0)
+            .build(ui, &mut setting.align_distance);
+        if ui.small_button(im_str!("Align to device")) {
+            update_flag |= Self::align_to_device(setting, viewer, geometries);
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(
+                "Sets the slice parallel to the device, centered at the distance along its normal",
+            );
+        }
+        update_flag
+    }
+
+    /// Returns the color map file if importing it is requested
+    pub fn take_color_map(&mut self) -> Option<String> {
+        std::mem::take(&mut self.color_map_requested)
+            .then(|| self.color_map_file.to_str().to_owned())
+    }
+
+    pub fn save(&self, setting: &mut Setting) {
+        setting.color_map_file = self.color_map_file.to_str().to_owned();
+    }
+
+    fn sampling_ui(
+        ui: &Ui,
+        setting: &mut Setting,
+        viewer: &AcousticFiledSliceViewer,
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let sampling = &mut setting.viewer_setting.slice_sampling;
+        let mut fixed = sampling.is_some();
+        if ui.checkbox(im_str!("Fixed sampling"), &mut fixed) {
+            *sampling = fixed.then_some(DEFAULT_SLICE_SAMPLING);
+            update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+        }
+        if let Some(density) = sampling {
+            if Drag::new(im_str!("Sampling [points/mm]"))
+                .speed(0.01)
+                .range(0.01..=100.0)
+                .build(ui, density)
+            {
+                *density = density.clamp(0.01, 100.0);
+                update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+            }
+        }
+        if let Some((resolution, [nx, ny])) = viewer.sampling() {
+            ui.text(format!(
+                "{} x {} points, {:.3} mm apart",
+                nx, ny, resolution
+            ));
+            ui.checkbox(im_str!("Compute on CPU"), &mut setting.cpu_slice);
+        }
+        update_flag
+    }
+
+    // The exact values of the pose, edited as text rather than dragged
+    fn pose_ui(
+        ui: &Ui,
+        setting: &mut Setting,
+        viewer: &mut AcousticFiledSliceViewer,
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let viewer_setting = &mut setting.viewer_setting;
+        let mut center = vecmath_util::to_vec3(&viewer_setting.slice_pos);
+        if ui
+            .input_float3(im_str!("Slice center [mm]"), &mut center)
+            .build()
+        {
+            viewer_setting.slice_pos =
+                [center[0], center[1], center[2], viewer_setting.slice_pos[3]];
+            viewer.move_to(viewer_setting.slice_pos);
+            update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+        }
+        let mut angle = vecmath_util::vec3_map(viewer_setting.slice_angle, f32::to_degrees);
+        if ui
+            .input_float3(im_str!("Slice angles [deg]"), &mut angle)
+            .build()
+        {
+            viewer_setting.slice_angle = vecmath_util::vec3_map(angle, f32::to_radians);
+            viewer.rotate_to(viewer_setting.slice_angle);
+            update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+        }
+        update_flag
+    }
+
+    fn color_map_ui(
+        &mut self,
+        ui: &Ui,
+        setting: &mut Setting,
+        viewer: &AcousticFiledSliceViewer,
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let viewer_setting = &mut setting.viewer_setting;
+        let mut color_map = ColorMapKind::ALL
+            .iter()
+            .position(|&c| c == viewer_setting.color_map)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Color map")).build_simple(
+            ui,
+            &mut color_map,
+            &ColorMapKind::ALL,
+            &|c| ImString::new(c.name()).into(),
+        ) {
+            viewer_setting.color_map = ColorMapKind::ALL[color_map];
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        let mut diverging = ColorMapKind::ALL
+            .iter()
+            .position(|&c| c == viewer_setting.diverging_color_map)
+            .unwrap_or(0);
+        if ComboBox::new(im_str!("Diverging color map")).build_simple(
+            ui,
+            &mut diverging,
+            &ColorMapKind::ALL,
+            &|c| ImString::new(c.name()).into(),
+        ) {
+            viewer_setting.diverging_color_map = ColorMapKind::ALL[diverging];
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(
+                "Used instead for the real part, the Gor'kov potential and the \
+                 difference A/B, which are signed with 0 at the middle",
+            );
+        }
+        let active = viewer_setting.active_color_map();
+        if viewer_setting.is_signed() && !active.is_diverging() && active != ColorMapKind::Imported
+        {
+            ui.text_colored(
+                [1.0, 0.8, 0.0, 1.0],
+                "A sequential map hides the sign of the field",
+            );
+        }
+        if viewer_setting.color_map == ColorMapKind::Imported
+            || viewer_setting.diverging_color_map == ColorMapKind::Imported
+        {
+            ui.input_text(im_str!("Color map file"), &mut self.color_map_file)
+                .build();
+            if ui.small_button(im_str!("Import color map")) {
+                self.color_map_requested = true;
+            }
+            match viewer.imported_color_map() {
+                Some(colors) => ui.text(format!("Colors: {}", colors.len())),
+                None => ui.text_disabled("Not imported, same as inferno"),
+            }
+        }
+        update_flag
+    }
+
+    // The force arrows, and the particle of them and of the Gor'kov potential
+    fn particle_ui(ui: &Ui, setting: &mut Setting) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        if ui.checkbox(im_str!("Show force arrows"), &mut setting.show_force_quiver) {
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if setting.show_force_quiver
+            && Drag::new(im_str!("Arrow spacing [mm]"))
+                .speed(0.1)
+                .range(1.0..=f32::INFINITY)
+                .build(ui, &mut setting.force_quiver_spacing)
+        {
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if !setting.show_force_quiver
+            && !matches!(
+                setting.viewer_setting.field_quantity,
+                FieldQuantity::GorkovPotential | FieldQuantity::GorkovForce
+            )
+        {
+            return update_flag;
+        }
+        let particle = &mut setting.viewer_setting.particle;
+        if Drag::new(im_str!("Particle radius [mm]"))
+            .speed(0.01)
+            .range(0.01..=f32::INFINITY)
+            .build(ui, &mut particle.radius)
+        {
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if Drag::new(im_str!("Particle density [kg/m^3]"))
+            .speed(1.0)
+            .range(0.01..=f32::INFINITY)
+            .build(ui, &mut particle.density)
+        {
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        let mut sound_speed = particle.sound_speed / 1000.0;
+        if Drag::new(im_str!("Particle sound speed [m/s]"))
+            .speed(1.0)
+            .range(1.0..=f32::INFINITY)
+            .build(ui, &mut sound_speed)
+        {
+            particle.sound_speed = sound_speed * 1000.0;
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        update_flag
+    }
+
+    // The slice turns only around the x and y axes, so its axes in the plane may differ from those
+    // of a device rotated around its normal
+    fn align_to_device(
+        setting: &mut Setting,
+        viewer: &mut AcousticFiledSliceViewer,
+        geometries: &[Geometry],
+    ) -> UpdateFlag {
+        let geometry = match geometries.get(setting.align_device) {
+            Some(geometry) => geometry,
+            None => return UpdateFlag::empty(),
+        };
+        let corners = geometry.board_corners();
+        let center = vecmath::vec3_scale(
+            corners
+                .iter()
+                .fold([0.; 3], |acc, &c| vecmath::vec3_add(acc, c)),
+            0.25,
+        );
+        let normal = vecmath::vec3_normalized(geometry.normal());
+        let pos = vecmath::vec3_add(center, vecmath::vec3_scale(normal, setting.align_distance));
+        let viewer_setting = &mut setting.viewer_setting;
+        viewer_setting.slice_pos = [pos[0], pos[1], pos[2], viewer_setting.slice_pos[3]];
+        viewer_setting.slice_angle = camera_helper::euler_angles_facing(normal);
+        viewer.move_to(viewer_setting.slice_pos);
+        viewer.rotate_to(viewer_setting.slice_angle);
+        UpdateFlag::UPDATE_SLICE_POS
+    }
+}
@@ -0,0 +1,142 @@
+/*
+ * File: ui_layout.rs
+ * Project: panel
+ * Created Date: 15/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 15/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use imgui::*;
+
+use crate::{
+    io_worker::IoWorker,
+    settings::Setting,
+    ui_layout::{self, UiLayout},
+};
+
+/// Saved layouts of the windows and the panels
+pub struct UiLayoutPanel {
+    file: ImString,
+    name: ImString,
+    // The windows are saved and loaded outside the frame of ImGui
+    capture_requested: Option<String>,
+    apply_requested: Option<usize>,
+}
+
+impl UiLayoutPanel {
+    pub fn new(setting: &Setting) -> Self {
+        let mut file = ImString::with_capacity(256);
+        file.push_str(&setting.ui_layout_file);
+        let mut name = ImString::with_capacity(64);
+        name.push_str("layout");
+        Self {
+            file,
+            name,
+            capture_requested: None,
+            apply_requested: None,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &Ui, setting: &mut Setting, io_worker: &mut IoWorker) {
+        ui.text(im_str!("UI layouts"));
+        let mut remove = None;
+        for (i, layout) in setting.ui_layouts.iter().enumerate() {
+            let id = ui.push_id(i as i32);
+            ui.text(&layout.name);
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Apply")) {
+                self.apply_requested = Some(i);
+            }
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Overwrite")) {
+                self.capture_requested = Some(layout.name.clone());
+            }
+            ui.same_line(0.);
+            if ui.small_button(im_str!("Remove")) {
+                remove = Some(i);
+            }
+            id.pop(ui);
+        }
+        if let Some(i) = remove {
+            setting.ui_layouts.remove(i);
+        }
+        ui.input_text(im_str!("Layout name"), &mut self.name)
+            .build();
+        if ui.small_button(im_str!("Save current layout")) && !self.name.to_str().is_empty() {
+            self.capture_requested = Some(self.name.to_str().to_owned());
+        }
+        ui.input_text(im_str!("Layout file"), &mut self.file)
+            .build();
+        if ui.small_button(im_str!("Export layouts")) {
+            self.export(setting, io_worker);
+        }
+        ui.same_line(0.);
+        if ui.small_button(im_str!("Import layouts")) {
+            self.import(setting);
+        }
+    }
+
+    /// Saves or applies the layout requested in the last frame, to be called before a new frame
+    pub fn update(&mut self, imgui: &mut Context, setting: &mut Setting) {
+        if let Some(name) = self.capture_requested.take() {
+            let mut ini = String::new();
+            imgui.save_ini_settings(&mut ini);
+            let layout = UiLayout::capture(&name, ini, setting);
+            match setting.ui_layouts.iter_mut().find(|l| l.name == name) {
+                Some(l) => *l = layout,
+                None => setting.ui_layouts.push(layout),
+            }
+            tracing::info!("save UI layout {}", name);
+        }
+        if let Some(i) = self.apply_requested.take() {
+            if let Some(layout) = setting.ui_layouts.get(i).cloned() {
+                imgui.load_ini_settings(&layout.ini);
+                layout.apply(setting);
+                tracing::info!("apply UI layout {}", layout.name);
+            }
+        }
+    }
+
+    /// Writes the path edited in the panel into `setting`
+    pub fn save(&self, setting: &mut Setting) {
+        setting.ui_layout_file = self.file.to_str().to_owned();
+    }
+
+    fn export(&self, setting: &Setting, io_worker: &mut IoWorker) {
+        let layouts = setting.ui_layouts.clone();
+        let path = self.file.to_str().to_owned();
+        io_worker.submit("Export UI layouts", move || {
+            ui_layout::save_layouts(&path, &layouts).map_err(|e| format!("{}: {}", path, e))?;
+            tracing::info!(num_layouts = layouts.len(), "export UI layouts {}", path);
+            Ok(())
+        });
+    }
+
+    // Layouts of the same names are replaced
+    fn import(&self, setting: &mut Setting) {
+        let path = self.file.to_str();
+        let layouts = match ui_layout::load_layouts(path) {
+            Ok(layouts) => layouts,
+            Err(e) => {
+                tracing::error!("failed to import UI layouts {}: {}", path, e);
+                return;
+            }
+        };
+        tracing::info!(num_layouts = layouts.len(), "import UI layouts {}", path);
+        for layout in layouts {
+            match setting
+                .ui_layouts
+                .iter_mut()
+                .find(|l| l.name == layout.name)
+            {
+                Some(l) => *l = layout,
+                None => setting.ui_layouts.push(layout),
+            }
+        }
+    }
+}
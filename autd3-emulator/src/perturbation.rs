@@ -0,0 +1,161 @@
+/*
+ * File: perturbation.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use acoustic_field_viewer::{field::FieldModel, sound_source::SoundSource, Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Gaussian errors of the phase and the amplitude of each transducer for tolerance studies,
+/// sampled from `seed`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Perturbation {
+    pub enabled: bool,
+    /// Standard deviation of the phase in degrees
+    pub phase_sigma: f32,
+    /// Standard deviation of the amplitude relative to the drive
+    pub amp_sigma: f32,
+    pub seed: u64,
+    /// Number of the samples of [Perturbation::trials]
+    pub trials: usize,
+}
+
+/// Errors of the transducers sampled by [Perturbation::sample]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerturbationSample {
+    /// Factor of the amplitude and offset of the phase in rad of each transducer
+    pub errors: Vec<(f32, f32)>,
+}
+
+/// Statistics of the focus over the samples of the perturbation, see [Perturbation::trials]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrialSummary {
+    pub trials: usize,
+    /// Maximum amplitude on the slice without the perturbation
+    pub nominal_amp: f32,
+    /// Mean and standard deviation of the maximum amplitude relative to the nominal one
+    pub amp_mean: f32,
+    pub amp_std: f32,
+    /// Mean and largest distance in mm of the maximum from the nominal one
+    pub shift_mean: f32,
+    pub shift_max: f32,
+}
+
+// SplitMix64, which is enough for the errors and reproducible from the seed
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next_f32(&mut self) -> f32 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        // in (0, 1] for the logarithm
+        ((z >> 40) as f32 + 1.0) / (1u64 << 24) as f32
+    }
+
+    // Box-Muller
+    fn normal_pair(&mut self) -> (f32, f32) {
+        let r = (-2.0 * self.next_f32().ln()).sqrt();
+        let t = 2.0 * PI * self.next_f32();
+        (r * t.cos(), r * t.sin())
+    }
+}
+
+impl Perturbation {
+    /// 10 degrees and 10 % of the tolerance of typical transducers
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            phase_sigma: 10.0,
+            amp_sigma: 0.1,
+            seed: 0,
+            trials: 20,
+        }
+    }
+
+    /// Errors of `n` transducers of the seed
+    pub fn sample(&self, n: usize) -> PerturbationSample {
+        self.sample_with_seed(n, self.seed)
+    }
+
+    fn sample_with_seed(&self, n: usize, seed: u64) -> PerturbationSample {
+        let mut rng = SplitMix(seed);
+        let errors = (0..n)
+            .map(|_| {
+                let (a, p) = rng.normal_pair();
+                (
+                    (1.0 + self.amp_sigma * a).max(0.0),
+                    (self.phase_sigma * p).to_radians(),
+                )
+            })
+            .collect();
+        PerturbationSample { errors }
+    }
+
+    /// Maximum amplitude on the slice transformed by `model` of the field of `field` with the
+    /// drive perturbed by the samples of the seeds from [Perturbation::seed], compared with that
+    /// of the drive as is
+    pub fn trials(&self, field: &FieldModel, model: Matrix4) -> TrialSummary {
+        let (nominal_pos, nominal_amp) = field.peak_on_slice(model);
+        let peaks: Vec<(Vector3, f32)> = (0..self.trials.max(1) as u64)
+            .map(|i| {
+                let mut sources = field.sources.to_vec();
+                self.sample_with_seed(sources.len(), self.seed.wrapping_add(i))
+                    .apply(&mut sources);
+                FieldModel {
+                    sources: &sources,
+                    ..*field
+                }
+                .peak_on_slice(model)
+            })
+            .collect();
+        let n = peaks.len() as f32;
+        let ratios: Vec<f32> = peaks
+            .iter()
+            .map(|&(_, amp)| amp / nominal_amp.max(f32::MIN_POSITIVE))
+            .collect();
+        let amp_mean = ratios.iter().sum::<f32>() / n;
+        let amp_var = ratios.iter().map(|r| (r - amp_mean).powi(2)).sum::<f32>() / n;
+        let shifts: Vec<f32> = peaks
+            .iter()
+            .map(|&(pos, _)| vecmath_util::dist(pos, nominal_pos))
+            .collect();
+        TrialSummary {
+            trials: peaks.len(),
+            nominal_amp,
+            amp_mean,
+            amp_std: amp_var.sqrt(),
+            shift_mean: shifts.iter().sum::<f32>() / n,
+            shift_max: shifts.iter().copied().fold(0.0, f32::max),
+        }
+    }
+}
+
+impl Default for Perturbation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PerturbationSample {
+    /// Perturbs the drive of `sources`, whose amplitude is clamped to 1, the largest drive of the
+    /// slice
+    pub fn apply(&self, sources: &mut [SoundSource]) {
+        for (source, &(amp, phase)) in sources.iter_mut().zip(&self.errors) {
+            source.amp = (source.amp * amp).min(1.0);
+            source.phase = (source.phase + phase).rem_euclid(2.0 * PI);
+        }
+    }
+}
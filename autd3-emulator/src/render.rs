@@ -0,0 +1,202 @@
+/*
+ * File: render.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use acoustic_field_viewer::{
+    view::{FieldQuantity, System, UpdateFlag, ViewerSettings},
+    Vector3,
+};
+use autd3_emulator_server::read_capture;
+use gfx::Device;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    figure::{self, ReplayState},
+    log::LogBuffer,
+    screenshot,
+    settings::Setting,
+    App,
+};
+
+/// Frames of the view rendered headlessly with `--render`, where the paths are relative to the
+/// spec file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenderSpec {
+    /// Capture file recorded in the `Capture` section of the `Log` tab
+    pub recording: Option<String>,
+    /// Session saved in the `Session` section, used if no recording is given
+    pub session: Option<String>,
+    /// Folder of the images, the folder of the spec if omitted
+    #[serde(default)]
+    pub output: String,
+    /// Settings before the frames are applied, those of the recording, the session or
+    /// `setting.json` if omitted
+    pub viewer_setting: Option<ViewerSettings>,
+    /// Table for the imported directivity, that of `setting.json` if omitted
+    pub directivity_file: Option<String>,
+    /// Size of the images in pixels, that of the window in `setting.json` if omitted
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Keeps the alpha of the background
+    #[serde(default)]
+    pub transparent: bool,
+    pub frames: Vec<RenderFrame>,
+}
+
+/// Image of the view from a camera, the unspecified parameters left as they are
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenderFrame {
+    /// File name of the image without the extension
+    pub name: String,
+    /// Time in seconds from the start of the recording, the end if omitted
+    pub time: Option<f32>,
+    /// Position of the camera in mm
+    pub camera_pos: Option<Vector3>,
+    /// Euler angles of the camera in degrees
+    pub camera_angle: Option<Vector3>,
+    /// Center of the slice in mm
+    pub center: Option<Vector3>,
+    /// Euler angles of the slice in degrees
+    pub angle: Option<Vector3>,
+    /// Width and height of the slice in mm
+    pub size: Option<[i32; 2]>,
+    pub quantity: Option<FieldQuantity>,
+    /// In the unit of the field, as [ViewerSettings::color_scale]
+    pub color_scale: Option<f32>,
+    /// Turns on the log scale, see [ViewerSettings::dynamic_range]
+    pub dynamic_range: Option<f32>,
+}
+
+impl RenderFrame {
+    fn settings(&self, mut settings: ViewerSettings) -> ViewerSettings {
+        if let Some(pos) = self.camera_pos {
+            settings.camera_pos = pos;
+        }
+        if let Some(angle) = self.camera_angle {
+            settings.camera_angle = vecmath_util::vec3_map(angle, f32::to_radians);
+        }
+        if let Some([x, y, z]) = self.center {
+            settings.slice_pos = [x, y, z, 1.0];
+        }
+        if let Some(angle) = self.angle {
+            settings.slice_angle = vecmath_util::vec3_map(angle, f32::to_radians);
+        }
+        if let Some([width, height]) = self.size {
+            settings.slice_width = width.max(1);
+            settings.slice_height = height.max(1);
+        }
+        if let Some(quantity) = self.quantity {
+            settings.field_quantity = quantity;
+        }
+        if let Some(color_scale) = self.color_scale {
+            settings.color_scale = color_scale;
+        }
+        if let Some(dynamic_range) = self.dynamic_range {
+            settings.dynamic_range = Some(dynamic_range);
+        }
+        settings
+    }
+}
+
+/// Renders the frames of the spec at `path` in a hidden window, returning the number of images
+/// written.
+///
+/// The slice is drawn by the shader, and the volume, the isosurface and the CPU slice, which are
+/// computed in the background, are not rendered, so that the images depend only on the spec.
+pub fn render_frames(
+    path: &Path,
+    mut setting: Setting,
+    log_buffer: LogBuffer,
+) -> Result<usize, String> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let spec: RenderSpec = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let frames = match &spec.recording {
+        Some(recording) => {
+            let recording = dir.join(recording);
+            let frames =
+                read_capture(&recording).map_err(|e| format!("{}: {}", recording.display(), e))?;
+            Some(frames)
+        }
+        None if spec.session.is_some() => None,
+        None => return Err("either recording or session is required".to_owned()),
+    };
+    let directivity_table = figure::load_directivity(
+        dir,
+        spec.directivity_file.as_deref(),
+        &setting,
+        spec.viewer_setting
+            .as_ref()
+            .unwrap_or(&setting.viewer_setting),
+    )?;
+    let output = dir.join(&spec.output);
+    std::fs::create_dir_all(&output).map_err(|e| e.to_string())?;
+
+    setting.directivity_file.clear();
+    setting.cpu_slice = false;
+    setting.show_volume = false;
+    setting.show_isosurface = false;
+    let width = spec.width.unwrap_or(setting.window_width as _);
+    let height = spec.height.unwrap_or(setting.window_height as _);
+    let mut system = System::init_hidden(width, height);
+    let mut app = App::new(setting, &system, log_buffer, None, None);
+    app.placeholder = false;
+    if let Some(path) = app.slice.take_color_map() {
+        app.load_color_map(&path);
+    }
+    app.field_slice_viewer
+        .set_directivity_table(directivity_table.clone());
+    for viewer in app.extra_slice_viewers.iter_mut() {
+        viewer.set_directivity_table(directivity_table.clone());
+    }
+    let mut base = app.setting.viewer_setting;
+    if frames.is_none() {
+        if let Some(session) = &spec.session {
+            if app
+                .load_session(&dir.join(session).to_string_lossy())
+                .is_empty()
+            {
+                return Err(format!("failed to load session {}", session));
+            }
+            base = app.setting.viewer_setting;
+        }
+    }
+    let base = spec.viewer_setting.unwrap_or(base);
+
+    let render_sys = &mut system.render_sys;
+    let encoder = &mut system.encoder;
+    for frame in &spec.frames {
+        let mut settings = base;
+        if let Some(frames) = &frames {
+            let state = ReplayState::replay(frames, frame.time, base, &app.setting);
+            app.drive = state.drive;
+            settings = state.viewer_setting;
+        }
+        app.setting.viewer_setting = frame.settings(settings);
+        app.reset(render_sys);
+        // the fixtures are loaded as in the viewer, whose scatterers are evaluated in the field
+        app.update_collisions();
+        app.update_scattering(render_sys);
+        app.update_view(render_sys, UpdateFlag::all());
+        let (size, pixels) = app.render_offscreen(render_sys, encoder, spec.transparent)?;
+        let path = output.join(format!("{}.png", frame.name));
+        screenshot::write_png(&path, size, &pixels, spec.transparent, &app.metadata())?;
+        tracing::info!("frame rendered: {}", path.display());
+        encoder.flush(&mut render_sys.device);
+        render_sys.device.cleanup();
+    }
+    app.io_worker.finish();
+    Ok(spec.frames.len())
+}
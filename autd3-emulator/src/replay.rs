@@ -0,0 +1,226 @@
+/*
+ * File: replay.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// Options of `--replay <capture> [--manifest <manifest>]`
+pub struct ReplayOptions {
+    pub capture: String,
+    pub manifest: Option<String>,
+}
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions unlike `DefaultHasher`
+#[derive(Clone, Copy)]
+pub struct StateHasher(u64);
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_f32(&mut self, v: f32) {
+        self.write(&v.to_bits().to_le_bytes());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    capture: String,
+    hashes: Vec<String>,
+}
+
+/// Compares the state hash after each replayed frame against a manifest.
+///
+/// If the manifest does not exist, it is written from this replay instead, so the first run
+/// with a capture records the reference and the following runs check against it.
+pub struct ReplayCheck {
+    capture: String,
+    manifest_path: String,
+    expected: Option<Vec<String>>,
+    hashes: Vec<String>,
+    first_mismatch: Option<usize>,
+}
+
+impl ReplayCheck {
+    pub fn new(capture: &str, manifest_path: &str) -> Result<Self, String> {
+        let expected = if Path::new(manifest_path).exists() {
+            let file = File::open(manifest_path).map_err(|e| e.to_string())?;
+            let manifest: Manifest =
+                serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+            tracing::info!(
+                "checking replay against {} ({} frames)",
+                manifest_path,
+                manifest.hashes.len()
+            );
+            Some(manifest.hashes)
+        } else {
+            tracing::info!(
+                "{} does not exist, it is written from this replay",
+                manifest_path
+            );
+            None
+        };
+        Ok(Self {
+            capture: capture.to_owned(),
+            manifest_path: manifest_path.to_owned(),
+            expected,
+            hashes: Vec::new(),
+            first_mismatch: None,
+        })
+    }
+
+    pub fn push(&mut self, hash: u64) {
+        let frame = self.hashes.len();
+        let hash = format!("{:016x}", hash);
+        if let Some(expected) = &self.expected {
+            if self.first_mismatch.is_none() && expected.get(frame) != Some(&hash) {
+                tracing::error!(
+                    "replay mismatch at frame {}: expected {}, got {}",
+                    frame,
+                    expected.get(frame).map_or("-", |h| h.as_str()),
+                    hash
+                );
+                self.first_mismatch = Some(frame);
+            }
+        }
+        self.hashes.push(hash);
+    }
+
+    /// Writes or checks the manifest and returns whether the replay matched it
+    pub fn finish(self) -> bool {
+        match self.expected {
+            Some(expected) => {
+                let passed = self.first_mismatch.is_none() && expected.len() == self.hashes.len();
+                if passed {
+                    tracing::info!(
+                        "replay matches {} ({} frames)",
+                        self.manifest_path,
+                        expected.len()
+                    );
+                } else if self.first_mismatch.is_none() {
+                    tracing::error!(
+                        "replay has {} frames, while {} has {}",
+                        self.hashes.len(),
+                        self.manifest_path,
+                        expected.len()
+                    );
+                }
+                passed
+            }
+            None => {
+                let manifest = Manifest {
+                    capture: self.capture,
+                    hashes: self.hashes,
+                };
+                let res = File::create(&self.manifest_path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|file| {
+                        serde_json::to_writer_pretty(file, &manifest).map_err(|e| e.to_string())
+                    });
+                match res {
+                    Ok(()) => {
+                        tracing::info!(
+                            "manifest written to {} ({} frames)",
+                            self.manifest_path,
+                            manifest.hashes.len()
+                        );
+                        true
+                    }
+                    Err(e) => {
+                        tracing::error!("failed to write {}: {}", self.manifest_path, e);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(frames: &[&[u8]]) -> u64 {
+        let mut hasher = StateHasher::new();
+        for frame in frames {
+            hasher.write(frame);
+        }
+        hasher.finish()
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        // reference values of 64-bit FNV-1a, which must not change across builds since the
+        // manifests store them
+        assert_eq!(hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(&[b"a"]), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(&[b"foobar"]), 0x8594_4171_f739_67e8);
+
+        let mut hasher = StateHasher::new();
+        hasher.write_f32(1.0);
+        assert_eq!(hasher.finish(), hash(&[&1.0f32.to_bits().to_le_bytes()]));
+    }
+
+    #[test]
+    fn hash_differs_on_reordered_frames() {
+        let a: &[u8] = &[0x01, 0x02, 0x03, 0x04];
+        let b: &[u8] = &[0x04, 0x03, 0x02, 0x01];
+        assert_ne!(hash(&[a, b]), hash(&[b, a]));
+        assert_ne!(hash(&[a]), hash(&[b]));
+    }
+
+    #[test]
+    fn check_detects_reordered_frames() {
+        let manifest = std::env::temp_dir().join(format!(
+            "autd-emulator-{}.manifest.json",
+            std::process::id()
+        ));
+        let manifest = manifest.to_str().unwrap();
+        let frames: [&[u8]; 3] = [b"clear", b"gain", b"modulation"];
+        let replay = |order: [usize; 3]| {
+            let mut check = ReplayCheck::new("capture.bin", manifest).unwrap();
+            let mut hasher = StateHasher::new();
+            for &i in &order {
+                hasher.write(frames[i]);
+                check.push(hasher.finish());
+            }
+            check.finish()
+        };
+
+        // the first replay writes the manifest
+        assert!(replay([0, 1, 2]));
+        assert!(replay([0, 1, 2]));
+        let reordered = replay([0, 2, 1]);
+        std::fs::remove_file(manifest).unwrap();
+        assert!(!reordered);
+    }
+}
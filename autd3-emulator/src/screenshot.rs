@@ -0,0 +1,72 @@
+/*
+ * File: screenshot.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use acoustic_field_viewer::metadata::ExperimentMetadata;
+
+/// Path of the screenshot `name` in `folder`, `screenshot_<timestamp>.png` if `name` is empty
+pub fn path(folder: &str, name: &str, time: chrono::DateTime<chrono::Local>) -> PathBuf {
+    let name = match name.trim() {
+        "" => format!("screenshot_{}", time.format("%Y-%m-%d_%H-%M-%S_%3f")),
+        name => name.to_owned(),
+    };
+    let mut path = Path::new(folder).join(name);
+    if path.extension().is_none_or(|ext| ext != "png") {
+        let mut name = path.into_os_string();
+        name.push(".png");
+        path = PathBuf::from(name);
+    }
+    path
+}
+
+/// Writes the RGBA `pixels` from the top, without the alpha unless `transparent`, creating the
+/// folder if missing
+pub fn write_png(
+    path: &Path,
+    (width, height): (u32, u32),
+    pixels: &[u8],
+    transparent: bool,
+    metadata: &ExperimentMetadata,
+) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let (color, data) = if transparent {
+        (png::ColorType::Rgba, pixels.to_vec())
+    } else {
+        let rgb = pixels
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect();
+        (png::ColorType::Rgb, rgb)
+    };
+    let res = (|| {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(color);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (key, value) in metadata.entries() {
+            encoder
+                .add_itxt_chunk(key.to_owned(), value.to_owned())
+                .map_err(|e| e.to_string())?;
+        }
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&data).map_err(|e| e.to_string())
+    })();
+    res.map_err(|e| format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,116 @@
+/*
+ * File: session.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use acoustic_field_viewer::{sound_source::SoundSource, view::ViewerSettings};
+use autd3_core::hardware_defined::RxGlobalControlFlags;
+use autd3_emulator_server::{CommandedFocus, DelayOffset, Geometry, Modulation, Sequence};
+use serde::{Deserialize, Serialize};
+
+use crate::decoder::Decoder;
+
+/// Version of the sessions written, which is 0 for those written before it was saved
+pub const SESSION_VERSION: u32 = 1;
+
+/// Full state of the emulator, saved to a single JSON file to reproduce a field elsewhere.
+///
+/// The geometry is stored as the transducers themselves, so a session can be loaded without
+/// the SDK. The slice pose and the camera are part of `viewer_setting`. The fields missing in
+/// a session, e.g., written by an older version, are empty.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Session {
+    pub version: u32,
+    pub viewer_setting: ViewerSettings,
+    /// Transducers with their drives and the frequencies of the cycles sent by the SDK
+    pub sources: Vec<SoundSource>,
+    /// Number of the sources of each device, empty if unknown, e.g., for the placeholder
+    pub device_sizes: Vec<usize>,
+    /// Devices sent by the SDK with their layouts, empty if unknown
+    pub geometries: Vec<Geometry>,
+    /// Amplitudes to be restored on resume, empty unless the firmware is paused
+    pub paused_amp: Vec<f32>,
+    pub ctrl_flag: u8,
+    pub modulation: Option<Modulation>,
+    pub sequence: Option<Sequence>,
+    pub delay_offset: Option<DelayOffset>,
+    pub commanded_foci: Vec<CommandedFocus>,
+    /// Simulation time in seconds, which selects the current modulation and STM sample
+    pub clock_time: f64,
+}
+
+impl Session {
+    /// Session of the drive decoded by `drive` at `clock_time`
+    pub fn capture(
+        drive: &Decoder,
+        viewer_setting: ViewerSettings,
+        commanded_foci: &[CommandedFocus],
+        clock_time: f64,
+    ) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            viewer_setting,
+            sources: drive.sources.clone(),
+            device_sizes: drive.device_sizes.clone(),
+            geometries: drive.geometries.clone(),
+            paused_amp: drive.last_amp.clone(),
+            ctrl_flag: drive.ctrl_flag.bits(),
+            modulation: drive.modulation.clone(),
+            sequence: drive.sequence.clone(),
+            delay_offset: drive.delay_offset.clone(),
+            commanded_foci: commanded_foci.to_vec(),
+            clock_time,
+        }
+    }
+
+    /// Replaces the drive decoded by `drive` with that of the session, where the overrides and
+    /// the drive last sent are cleared
+    pub fn restore_drive(self, drive: &mut Decoder) {
+        drive.sources = self.sources;
+        drive.device_sizes = self.device_sizes;
+        drive.drive_overrides.clear();
+        drive.geometries = self.geometries;
+        drive.sent_drive.clear();
+        drive.last_amp = self.paused_amp;
+        drive.ctrl_flag = RxGlobalControlFlags::from_bits_truncate(self.ctrl_flag);
+        drive.modulation = self.modulation;
+        drive.sequence = self.sequence;
+        drive.delay_offset = self.delay_offset;
+        drive.mod_index = None;
+        drive.seq_index = None;
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut session: Self =
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+        if session.version > SESSION_VERSION {
+            tracing::warn!(
+                "session of version {} is newer than {}, its unknown fields are ignored",
+                session.version,
+                SESSION_VERSION
+            );
+        }
+        if session.device_sizes.iter().sum::<usize>() != session.sources.len() {
+            session.device_sizes.clear();
+            session.geometries.clear();
+        }
+        Ok(session)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        serde_json::to_writer(file, self).map_err(|e| e.to_string())
+    }
+}
@@ -11,42 +11,404 @@
  *
  */
 
-use acoustic_field_viewer::view::{render_system::RenderSystem, ViewerSettings};
+use acoustic_field_viewer::{
+    levitation::Particle,
+    metadata::ExperimentMetadata,
+    region::TargetRegion,
+    view::{render_system::RenderSystem, ViewerSettings},
+    volume::VolumeSettings,
+    Vector3,
+};
+use autd3_core::hardware_defined::TRANS_SPACING_MM;
+use autd3_emulator_server::{HistoryLimits, Layout};
 use serde::{Deserialize, Serialize};
+
+use crate::{
+    acoustic_camera::AcousticCamera, camera_bookmarks::CameraBookmark, drift::TemperatureProfile,
+    export::ExportPreset, fixtures::FixtureSetting, ideal_overlay::IdealOverlay,
+    line_scan::LineScan, locale::Locale, perturbation::Perturbation, slices::ExtraSlice,
+    slices::NUM_EXTRA_SLICES, ui_layout::UiLayout, video::VideoSetting,
+};
 use std::{
     fs::{File, OpenOptions},
-    io::Write,
+    io::{BufReader, ErrorKind, Write},
+    net::IpAddr,
     path::Path,
 };
 
+/// The fields missing in setting.json, e.g., written by an older version, are those of
+/// [Setting::new]
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Setting {
     pub port: u16,
+    /// Devices shown until a client connects, placed side by side along x
+    pub placeholder_devices: usize,
+    pub placeholder_layout: Layout,
+    pub spectator_enable: bool,
+    pub spectator_port: u16,
+    /// Address on which the spectators are accepted, the loopback unless opened to the network
+    pub spectator_host: String,
+    pub latency_ms: f32,
+    /// Window in ms for which the drive updates from the SDK are held before being rendered, so
+    /// that a burst of them is rendered once
+    pub coalesce_ms: f32,
+    /// Caps of the frames retained for spectators and handoff, and of the drive history
+    pub history_max_frames: u32,
+    pub history_max_mb: f32,
+    pub time_scale: f32,
+    pub handoff_addr: String,
+    /// Address on which the handoff is accepted, the loopback unless opened to the network
+    pub handoff_host: String,
+    /// Hosts from which the viewer setting of a handoff is accepted
+    pub handoff_trusted_peers: Vec<String>,
+    pub layout_file: String,
+    /// Reloads the layout file when it is modified
+    pub watch_layout_file: bool,
+    pub directivity_file: String,
+    /// CSV of the colors of [acoustic_field_viewer::color_map::ColorMapKind::Imported]
+    pub color_map_file: String,
+    /// CSV of the amplitude and the phase of each transducer, see
+    /// [crate::trans_calibration::TransCalibration]
+    pub trans_calibration_file: String,
+    pub session_path: String,
+    pub capture_path: String,
     pub window_width: u32,
     pub window_height: u32,
     pub viewer_setting: ViewerSettings,
+    pub metadata: ExperimentMetadata,
+    pub locale: Locale,
     pub log_enable: bool,
     pub log_max: u32,
+    /// Level of the events captured in the log and the log file
+    pub log_level: String,
+    pub log_file_enable: bool,
+    pub log_file_path: String,
+    pub journal_enable: bool,
+    pub journal_path: String,
+    pub journal_max_kb: u32,
+    pub show_commanded_foci: bool,
+    pub show_coordinate_readout: bool,
+    pub show_steering_limit: bool,
+    /// Estimates the focus of each gain from its phases
+    pub show_estimated_focus: bool,
+    /// Re-centers the slice on the active STM point, or on the estimated focus otherwise
+    pub follow_focus: bool,
+    /// Computes the main slice sampled on the grid on the CPU instead of the shader
+    pub cpu_slice: bool,
+    pub show_focal_spot: bool,
+    /// Fraction of the focal amplitude on the axis of the array bounding the steering range
+    pub steering_fraction: f32,
+    pub show_force_quiver: bool,
+    /// Spacing of the arrows of the force in mm
+    pub force_quiver_spacing: f32,
+    pub show_probe: bool,
+    /// Shows the arrow and the rings around the slice to drag it along its normal and rotate it
+    pub show_slice_gizmo: bool,
+    /// Rotates the slice with the gizmo in the steps of 15 degrees
+    pub slice_gizmo_snap: bool,
+    /// Point in mm toward which `Look at` turns the camera, around which it orbits
+    pub camera_target: Vector3,
+    /// Poses recalled from the `Camera` tab, the first nine with the keys 1 to 9
+    pub camera_bookmarks: Vec<CameraBookmark>,
+    /// Duration in seconds of the move of the camera to a bookmark, 0 to jump
+    pub camera_transition_time: f32,
+    /// Index of the device to which `Align to device` sets the slice parallel
+    pub align_device: usize,
+    /// Distance of the aligned slice from the center of the device along its normal in mm
+    pub align_distance: f32,
+    /// Position of the virtual microphone in mm
+    pub probe_pos: Vector3,
+    /// Length of the hydrophone trace recorded at the probe in ms
+    pub hydrophone_duration: f32,
+    /// Sample rate of the hydrophone trace in kHz
+    pub hydrophone_sample_rate: f32,
+    /// Path of the exported trace, to which the extension of the format is set
+    pub hydrophone_path: String,
+    /// Length of the drive waveform of the picked source exported in ms
+    pub drive_waveform_duration: f32,
+    /// Sample rate of the drive waveform in kHz
+    pub drive_waveform_rate: f32,
+    /// Path of the exported drive history, to which the extension of the format is set
+    pub drive_history_path: String,
+    pub show_target_region: bool,
+    pub show_line_scan: bool,
+    /// Shows the shock parameter along the line scan
+    pub line_scan_nonlinearity: bool,
+    pub show_acoustic_camera: bool,
+    pub acoustic_camera: AcousticCamera,
+    pub acoustic_camera_path: String,
+    /// Width and height in mm of the area around the slice center searched for the lobes
+    pub lobe_area: [f32; 2],
+    /// Secondary maxima below this level in dB are not reported
+    pub lobe_floor: f32,
+    pub show_far_field: bool,
+    pub show_power_summary: bool,
+    /// Number of the samples of each direction cosine of the far-field pattern
+    pub far_field_resolution: usize,
+    /// Range of the far-field pattern shown below the maximum in dB
+    pub far_field_range: f32,
+    pub show_fixtures: bool,
+    /// Slices shown with the main slice, e.g., on the planes orthogonal to it
+    pub extra_slices: [ExtraSlice; NUM_EXTRA_SLICES],
+    pub show_volume: bool,
+    pub volume: VolumeSettings,
+    /// The isosurface is extracted from the grid in the box of [Setting::volume]
+    pub show_isosurface: bool,
+    /// Pressure amplitude of the isosurface in Pa
+    pub isosurface_level: f32,
+    pub isosurface_opacity: f32,
+    /// Path of the isosurface exported as a binary STL in mm
+    pub isosurface_path: String,
+    /// Objects in the workspace checked against the foci and the STM trajectory
+    pub fixtures: Vec<FixtureSetting>,
+    pub line_scan: LineScan,
+    pub line_scan_path: String,
+    pub ideal_overlay: IdealOverlay,
+    pub target_region: TargetRegion,
+    /// The color scale follows the maximum amplitude on the slice
+    pub auto_scale: bool,
+    /// Legend of the color map drawn at the right of the view
+    pub show_color_bar: bool,
+    /// Outline, local axes and index of each device over the view
+    pub show_devices: bool,
+    /// Tooltip of the transducer under the mouse, picked while the mouse moves over the view
+    pub show_source_tooltip: bool,
+    /// Ground grid on the plane z = 0
+    pub show_grid: bool,
+    /// Orientation of the global axes at the lower right of the view
+    pub show_axes: bool,
+    /// Length of a bar on the screen at the depth of the center of the slice
+    pub show_ruler: bool,
+    /// Particle dropped in the `Particles` tab
+    pub drop_particle: Particle,
+    pub shader_hot_reload: bool,
+    pub wave_animation: bool,
+    /// Cycles per second of the animated wave, far slower than the real frequency
+    pub wave_animation_rate: f32,
+    pub ramp_enable: bool,
+    /// Duration of the ramp-up in seconds of the simulation time
+    pub ramp_duration: f32,
+    pub perturbation: Perturbation,
+    /// The temperature of the air follows `temperature_profile` on the simulation clock
+    pub temperature_drift: bool,
+    pub temperature_profile: TemperatureProfile,
     pub show_mod_plot: bool,
     pub show_mod_plot_raw: bool,
     pub mod_plot_size: [f32; 2],
+    pub export_presets: Vec<ExportPreset>,
+    pub ui_layouts: Vec<UiLayout>,
+    pub ui_layout_file: String,
     pub save_file_enable: bool,
     pub save_file_path: String,
     pub record_path: String,
+    /// Folder of the screenshots
+    pub screenshot_folder: String,
+    /// Whether the background of the screenshots is transparent
+    pub screenshot_transparent: bool,
+    pub video: VideoSetting,
+    pub move_step: f32,
+    pub move_step_fine: f32,
+    pub rotate_step: f32,
+    pub rotate_step_fine: f32,
+    /// Time constant in seconds of the slowdown of the orbit after the mouse is released, 0 to stop
+    /// at once
+    pub orbit_inertia: f32,
+}
+
+impl Default for Setting {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Setting {
     pub fn new() -> Self {
         Self {
             port: 50632,
+            placeholder_devices: 1,
+            placeholder_layout: Layout::Autd3,
+            spectator_enable: false,
+            spectator_port: 50633,
+            spectator_host: "127.0.0.1".to_owned(),
+            latency_ms: 0.0,
+            coalesce_ms: 0.0,
+            history_max_frames: 4096,
+            history_max_mb: 64.0,
+            time_scale: 1.0,
+            handoff_addr: "127.0.0.1:50632".to_owned(),
+            handoff_host: "127.0.0.1".to_owned(),
+            handoff_trusted_peers: vec!["127.0.0.1".to_owned(), "::1".to_owned()],
+            layout_file: String::new(),
+            watch_layout_file: false,
+            directivity_file: String::new(),
+            color_map_file: String::new(),
+            trans_calibration_file: String::new(),
+            session_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("session.json")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            capture_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("capture.bin")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
             window_width: 960,
             window_height: 640,
             viewer_setting: ViewerSettings::new(),
+            metadata: ExperimentMetadata::default(),
+            locale: Locale::default(),
             log_enable: true,
             log_max: 100,
+            log_level: "INFO".to_owned(),
+            log_file_enable: false,
+            log_file_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("autd3-emulator.log")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            journal_enable: false,
+            journal_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("journal.jsonl")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            journal_max_kb: 10240,
+            show_commanded_foci: true,
+            show_coordinate_readout: true,
+            show_steering_limit: false,
+            show_estimated_focus: true,
+            follow_focus: false,
+            cpu_slice: false,
+            show_focal_spot: false,
+            steering_fraction: 0.5,
+            show_force_quiver: false,
+            force_quiver_spacing: 10.0,
+            show_probe: false,
+            show_slice_gizmo: false,
+            slice_gizmo_snap: true,
+            camera_target: vecmath_util::to_vec3(&ViewerSettings::new().slice_pos),
+            camera_bookmarks: CameraBookmark::presets(
+                vecmath_util::to_vec3(&ViewerSettings::new().slice_pos),
+                500.0,
+            ),
+            camera_transition_time: 1.0,
+            align_device: 0,
+            align_distance: 150.0,
+            probe_pos: vecmath_util::to_vec3(&ViewerSettings::new().slice_pos),
+            hydrophone_duration: 20.0,
+            hydrophone_sample_rate: 400.0,
+            hydrophone_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("hydrophone.csv")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            drive_waveform_duration: 100.0,
+            drive_waveform_rate: 400.0,
+            drive_history_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("drive_history.csv")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            show_target_region: false,
+            show_line_scan: false,
+            line_scan_nonlinearity: false,
+            show_acoustic_camera: false,
+            acoustic_camera: AcousticCamera::new(),
+            acoustic_camera_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("acoustic_camera.csv")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            lobe_area: [400.0, 300.0],
+            lobe_floor: -30.0,
+            show_far_field: false,
+            show_power_summary: false,
+            far_field_resolution: 96,
+            far_field_range: 30.0,
+            show_fixtures: true,
+            extra_slices: {
+                let viewer = ViewerSettings::new();
+                let [a, b] = ExtraSlice::orthogonal(
+                    vecmath_util::to_vec3(&viewer.slice_pos),
+                    viewer.slice_angle,
+                );
+                [
+                    ExtraSlice {
+                        enabled: false,
+                        ..a
+                    },
+                    ExtraSlice {
+                        enabled: false,
+                        ..b
+                    },
+                ]
+            },
+            show_volume: false,
+            volume: VolumeSettings::new(),
+            show_isosurface: false,
+            isosurface_level: 2000.0,
+            isosurface_opacity: 0.5,
+            isosurface_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("isosurface.stl")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            fixtures: Vec::new(),
+            line_scan: LineScan::centered(
+                [
+                    TRANS_SPACING_MM as f32 * 8.5,
+                    TRANS_SPACING_MM as f32 * 6.5,
+                    150.0,
+                ],
+                [0.0, 0.0, 1.0],
+                200.0,
+                401,
+            ),
+            ideal_overlay: IdealOverlay::new(),
+            line_scan_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("line_scan.csv")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            target_region: TargetRegion::new(),
+            auto_scale: false,
+            show_color_bar: true,
+            show_devices: false,
+            show_source_tooltip: true,
+            show_grid: false,
+            show_axes: true,
+            show_ruler: true,
+            drop_particle: Particle::polystyrene(),
+            shader_hot_reload: false,
+            wave_animation: false,
+            wave_animation_rate: 0.5,
+            ramp_enable: false,
+            ramp_duration: 0.02,
+            perturbation: Perturbation::new(),
+            temperature_drift: false,
+            temperature_profile: TemperatureProfile::new(),
             show_mod_plot: true,
             show_mod_plot_raw: false,
             mod_plot_size: [200.0, 50.],
+            export_presets: vec![ExportPreset::new("default")],
+            ui_layouts: Vec::new(),
+            ui_layout_file: std::env::current_dir()
+                .unwrap_or_default()
+                .join("ui_layouts.json")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
             save_file_enable: false,
             save_file_path: std::env::current_dir()
                 .unwrap_or_default()
@@ -60,9 +422,43 @@ impl Setting {
                 .to_str()
                 .unwrap_or("")
                 .to_owned(),
+            screenshot_folder: std::env::current_dir()
+                .unwrap_or_default()
+                .join("screenshots")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            screenshot_transparent: false,
+            video: VideoSetting::new(),
+            move_step: 10.0,
+            move_step_fine: 1.0,
+            rotate_step: 5.0f32.to_radians(),
+            rotate_step_fine: 0.5f32.to_radians(),
+            orbit_inertia: 0.3,
+        }
+    }
+
+    pub fn history_limits(&self) -> HistoryLimits {
+        HistoryLimits {
+            max_frames: self.history_max_frames as usize,
+            max_bytes: (self.history_max_mb.max(0.0) * 1024.0 * 1024.0) as usize,
         }
     }
 
+    /// Addresses of [Setting::handoff_trusted_peers], without those failing to parse
+    pub fn trusted_peers(&self) -> Vec<IpAddr> {
+        self.handoff_trusted_peers
+            .iter()
+            .filter_map(|peer| match peer.parse() {
+                Ok(addr) => Some(addr),
+                Err(e) => {
+                    tracing::warn!("invalid trusted peer {}: {}", peer, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
     pub fn merge_render_sys(&mut self, render_sys: &RenderSystem) {
         let scale_factor = render_sys.window().scale_factor();
         let size = render_sys.window().inner_size().to_logical(scale_factor);
@@ -70,25 +466,45 @@ impl Setting {
         self.window_height = size.height;
     }
 
-    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+    /// Loads `path`, which is the default setting if it does not exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
         let file = match File::open(path) {
             Ok(file) => file,
-            Err(_) => return Self::new(),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e.to_string()),
         };
-        let setting: Self = match serde_json::from_reader(file) {
-            Ok(setting) => setting,
-            Err(_) => Self::new(),
-        };
-        setting
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())
     }
 
-    pub fn save<P: AsRef<Path>>(&self, path: P) {
-        let json = serde_json::to_string_pretty(self).unwrap();
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
-            .open(path)
-            .unwrap();
-        writeln!(&mut file, "{}", json).unwrap();
+            .truncate(true)
+            .open(path)?;
+        writeln!(&mut file, "{}", json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_reports_a_malformed_file() {
+        let path = std::env::temp_dir().join(format!("autd-emulator-{}.json", std::process::id()));
+        assert_eq!(Setting::load(&path).unwrap().port, Setting::new().port);
+
+        std::fs::write(&path, r#"{"port": 1234, "time_scale": 2.0}"#).unwrap();
+        let setting = Setting::load(&path);
+        std::fs::write(&path, r#"{"port": 1234,"#).unwrap();
+        let malformed = Setting::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let setting = setting.unwrap();
+        assert_eq!(setting.port, 1234);
+        assert_eq!(setting.time_scale, 2.0);
+        assert!(malformed.is_err());
     }
 }
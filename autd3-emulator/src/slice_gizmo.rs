@@ -0,0 +1,206 @@
+/*
+ * File: slice_gizmo.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{camera_helper, Matrix4, Vector3};
+
+/// Step of the snapped rotation in radians
+pub const SNAP_ANGLE: f32 = std::f32::consts::PI / 12.0;
+const RING_SEGMENTS: usize = 48;
+/// Length of the arrow relative to the radius of the rings
+const ARROW_LENGTH: f32 = 1.5;
+
+type ViewProjection = (Matrix4, Matrix4);
+type Ray = (Vector3, Vector3);
+
+/// Part of the gizmo grabbed by the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoHandle {
+    /// Arrow along the normal of the slice
+    Translate,
+    /// Ring around the `i`-th axis of the slice
+    Rotate(usize),
+}
+
+/// Handles of the slice around its center, of a constant size on the screen
+#[derive(Debug, Clone, Copy)]
+pub struct SliceGizmo {
+    pub center: Vector3,
+    /// Unit axes of the slice, the last of which is the normal
+    pub axes: [Vector3; 3],
+    /// Radius of the rings in mm
+    pub radius: f32,
+}
+
+impl SliceGizmo {
+    /// Gizmo of the slice of `model`, whose rings are `pixels` in radius on the screen, or `None`
+    /// if the center is behind the camera
+    pub fn new(
+        model: Matrix4,
+        view_projection: ViewProjection,
+        screen_size: [f32; 2],
+        pixels: f32,
+    ) -> Option<Self> {
+        let center = vecmath_util::to_vec3(&model[3]);
+        let axes = [0, 1, 2].map(|i| vecmath::vec3_normalized(vecmath_util::to_vec3(&model[i])));
+        let view = view_projection.0;
+        let right = [view[0][0], view[1][0], view[2][0]];
+        let project = |p| camera_helper::project_to_screen(view_projection, p, screen_size);
+        let c = project(center)?;
+        let r = project(vecmath::vec3_add(center, right))?;
+        let pixels_per_mm = ((r[0] - c[0]).powi(2) + (r[1] - c[1]).powi(2)).sqrt();
+        if pixels_per_mm < 1e-6 {
+            return None;
+        }
+        Some(Self {
+            center,
+            axes,
+            radius: pixels / pixels_per_mm,
+        })
+    }
+
+    /// Closed polyline of the ring around the `i`-th axis
+    pub fn ring(&self, i: usize) -> Vec<Vector3> {
+        let (u, v) = (self.axes[(i + 1) % 3], self.axes[(i + 2) % 3]);
+        (0..=RING_SEGMENTS)
+            .map(|k| {
+                let t = k as f32 / RING_SEGMENTS as f32 * 2.0 * std::f32::consts::PI;
+                let d = vecmath::vec3_add(
+                    vecmath::vec3_scale(u, t.cos()),
+                    vecmath::vec3_scale(v, t.sin()),
+                );
+                vecmath::vec3_add(self.center, vecmath::vec3_scale(d, self.radius))
+            })
+            .collect()
+    }
+
+    /// Tip of the arrow along the normal from the center
+    pub fn arrow_tip(&self) -> Vector3 {
+        vecmath::vec3_add(
+            self.center,
+            vecmath::vec3_scale(self.axes[2], self.radius * ARROW_LENGTH),
+        )
+    }
+
+    /// Handle within `tolerance` pixels of `mouse`, the arrow before the rings.
+    ///
+    /// The gizmo is drawn by imgui over the scene and is not in the picking buffer, so it is hit
+    /// on the lines as drawn.
+    pub fn hit(
+        &self,
+        view_projection: ViewProjection,
+        screen_size: [f32; 2],
+        mouse: [f32; 2],
+        tolerance: f32,
+    ) -> Option<GizmoHandle> {
+        let near = |polyline: &[Vector3]| {
+            let points: Vec<_> = polyline
+                .iter()
+                .map(|&p| camera_helper::project_to_screen(view_projection, p, screen_size))
+                .collect();
+            points.windows(2).any(|w| match (w[0], w[1]) {
+                (Some(a), Some(b)) => distance_to_segment(mouse, a, b) <= tolerance,
+                _ => false,
+            })
+        };
+        if near(&[self.center, self.arrow_tip()]) {
+            return Some(GizmoHandle::Translate);
+        }
+        (0..3)
+            .find(|&i| near(&self.ring(i)))
+            .map(GizmoHandle::Rotate)
+    }
+
+    // Offset along the normal of the point on the normal line closest to the ray
+    fn offset_along_normal(&self, (origin, dir): Ray) -> Option<f32> {
+        let n = self.axes[2];
+        let w = vecmath::vec3_sub(self.center, origin);
+        let b = vecmath::vec3_dot(n, dir);
+        let denom = 1.0 - b * b;
+        if denom < 1e-6 {
+            return None;
+        }
+        Some((b * vecmath::vec3_dot(dir, w) - vecmath::vec3_dot(n, w)) / denom)
+    }
+
+    // Angle around the `i`-th axis of the point on the plane of the ring hit by the ray
+    fn angle_around(&self, i: usize, ray: Ray) -> Option<f32> {
+        let p = camera_helper::intersect_plane(ray, self.center, self.axes[i])?;
+        let d = vecmath::vec3_sub(p, self.center);
+        let (u, v) = (self.axes[(i + 1) % 3], self.axes[(i + 2) % 3]);
+        Some(vecmath::vec3_dot(d, v).atan2(vecmath::vec3_dot(d, u)))
+    }
+}
+
+/// Drag of a handle from the pose of the slice at the start
+#[derive(Debug, Clone, Copy)]
+pub struct GizmoDrag {
+    pub handle: GizmoHandle,
+    gizmo: SliceGizmo,
+    /// Euler angles of the slice at the start in radians
+    angle: Vector3,
+    /// Offset along the normal in mm or the angle in radians under the mouse at the start
+    start: f32,
+}
+
+impl GizmoDrag {
+    pub fn start(gizmo: SliceGizmo, angle: Vector3, handle: GizmoHandle, ray: Ray) -> Option<Self> {
+        let start = match handle {
+            GizmoHandle::Translate => gizmo.offset_along_normal(ray)?,
+            GizmoHandle::Rotate(i) => gizmo.angle_around(i, ray)?,
+        };
+        Some(Self {
+            handle,
+            gizmo,
+            angle,
+            start,
+        })
+    }
+
+    /// Center and Euler angles of the slice dragged to the ray, or `None` if the ray is parallel
+    /// to the handle.
+    ///
+    /// The ring around the `i`-th axis turns the `i`-th Euler angle, as `Slice RX`, `RY` and `RZ`,
+    /// snapped to the multiples of [SNAP_ANGLE] if `snap`, which is the rotation around the axis
+    /// if the other angles are zero.
+    pub fn pose(&self, ray: Ray, snap: bool) -> Option<(Vector3, Vector3)> {
+        let gizmo = &self.gizmo;
+        match self.handle {
+            GizmoHandle::Translate => {
+                let d = gizmo.offset_along_normal(ray)? - self.start;
+                let center = vecmath::vec3_add(gizmo.center, vecmath::vec3_scale(gizmo.axes[2], d));
+                Some((center, self.angle))
+            }
+            GizmoHandle::Rotate(i) => {
+                let mut angle = self.angle;
+                angle[i] += gizmo.angle_around(i, ray)? - self.start;
+                if snap {
+                    angle[i] = (angle[i] / SNAP_ANGLE).round() * SNAP_ANGLE;
+                }
+                angle[i] = angle[i].rem_euclid(2.0 * std::f32::consts::PI);
+                Some((gizmo.center, angle))
+            }
+        }
+    }
+}
+
+fn distance_to_segment(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 < 1e-6 {
+        0.0
+    } else {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / len2).clamp(0.0, 1.0)
+    };
+    let (x, y) = (a[0] + dx * t - p[0], a[1] + dy * t - p[1]);
+    (x * x + y * y).sqrt()
+}
@@ -0,0 +1,60 @@
+/*
+ * File: slices.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use acoustic_field_viewer::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Number of the slices shown in addition to the main one
+pub const NUM_EXTRA_SLICES: usize = 2;
+
+/// Euler angles of the slices on the xy, yz and zx planes, the same as the buttons of the main
+/// slice
+pub const PLANES: [(&str, Vector3); 3] = [
+    ("xy", [0., 0., 0.]),
+    ("yz", [0., -PI / 2., 0.]),
+    ("zx", [PI / 2., 0., 0.]),
+];
+
+/// Slice shown with the main one, sharing its size and color setting
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ExtraSlice {
+    pub enabled: bool,
+    /// Center in mm
+    pub pos: Vector3,
+    pub angle: Vector3,
+}
+
+impl ExtraSlice {
+    /// The slices on the two planes of [PLANES] other than that of `angle` through `center`, or on
+    /// the xy and yz planes if `angle` is none of them
+    pub fn orthogonal(center: Vector3, angle: Vector3) -> [Self; NUM_EXTRA_SLICES] {
+        let main = PLANES.iter().position(|(_, a)| *a == angle).unwrap_or(2);
+        let mut others =
+            PLANES
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != main)
+                .map(|(_, (_, angle))| Self {
+                    enabled: true,
+                    pos: center,
+                    angle: *angle,
+                });
+        [others.next().unwrap(), others.next().unwrap()]
+    }
+
+    pub fn pos4(&self) -> [f32; 4] {
+        [self.pos[0], self.pos[1], self.pos[2], 1.0]
+    }
+}
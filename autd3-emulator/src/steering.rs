@@ -0,0 +1,151 @@
+/*
+ * File: steering.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use acoustic_field_viewer::{
+    directivity::DirectivityTable, field::FieldModel, sound_source::SoundSource,
+    view::ViewerSettings, Vector3,
+};
+
+use crate::stm_duty;
+
+/// Number of the directions around the axis of the array in the contour
+const CONTOUR_DIRECTIONS: usize = 72;
+/// Largest steering angle searched
+const MAX_STEERING_ANGLE: f32 = 80.0 * PI / 180.0;
+const BISECTION_STEPS: usize = 12;
+
+/// Array focusing at each point, whose focal amplitude drops as the focus is steered off the axis
+pub struct SteeringModel<'a> {
+    pub sources: &'a [SoundSource],
+    /// Number of the sources of each device
+    pub device_sizes: &'a [usize],
+    pub settings: &'a ViewerSettings,
+    pub directivity_table: Option<&'a DirectivityTable>,
+}
+
+/// Lateral extent at a depth within which the focal amplitude is at least a fraction of that on
+/// the axis
+#[derive(Clone)]
+pub struct SteeringLimit {
+    /// Distance of the plane of the contour from the array in mm
+    pub depth: f32,
+    pub fraction: f32,
+    /// Wavelength in mm for which the contour is computed
+    pub wave_length: f32,
+    /// Center of the array
+    pub center: Vector3,
+    pub contour: Vec<Vector3>,
+}
+
+impl<'a> SteeringModel<'a> {
+    /// Center and direction of the array, i.e., the mean position and the direction of the first
+    /// source
+    pub fn frame(&self) -> Option<(Vector3, Vector3)> {
+        let first = self.sources.first()?;
+        let n = self.sources.len() as f32;
+        let center = self.sources.iter().fold([0.0; 3], |acc, s| {
+            vecmath::vec3_add(acc, vecmath::vec3_scale(s.pos, 1.0 / n))
+        });
+        Some((center, vecmath::vec3_normalized(first.dir)))
+    }
+
+    /// Amplitude at `focus` of the drive of the full amplitude focusing there
+    pub fn focal_amp(&self, focus: Vector3) -> f32 {
+        let sources = stm_duty::focused_drive(self.sources, self.settings, focus, 255);
+        let model = FieldModel {
+            sources: &sources,
+            settings: self.settings,
+            directivity_table: self.directivity_table,
+            scattering: None,
+            device_sizes: self.device_sizes,
+        };
+        let [re, im] = model.pressure_at(focus);
+        (re * re + im * im).sqrt()
+    }
+
+    /// Focal amplitude at `focus` relative to that on the axis of the array at the same depth
+    pub fn relative_focal_amp(&self, focus: Vector3) -> Option<f32> {
+        let (center, normal) = self.frame()?;
+        let depth = vecmath::vec3_dot(vecmath::vec3_sub(focus, center), normal);
+        let on_axis = self.focal_amp(vecmath::vec3_add(
+            center,
+            vecmath::vec3_scale(normal, depth),
+        ));
+        if on_axis <= 0.0 {
+            return None;
+        }
+        Some(self.focal_amp(focus) / on_axis)
+    }
+
+    /// Contour of the steering range at `depth`, searched outward from the axis in each direction
+    /// in steps of the wavelength and bisected at the first drop below `fraction`
+    pub fn limit(&self, depth: f32, fraction: f32) -> Option<SteeringLimit> {
+        let (center, normal) = self.frame()?;
+        let a = if normal[0].abs() < 0.9 {
+            [1.0, 0.0, 0.0]
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+        let u = vecmath::vec3_normalized(vecmath::vec3_cross(a, normal));
+        let v = vecmath::vec3_cross(normal, u);
+        let axis = vecmath::vec3_add(center, vecmath::vec3_scale(normal, depth));
+        let threshold = self.focal_amp(axis) * fraction;
+        let r_max = depth.abs() * MAX_STEERING_ANGLE.tan();
+        let step = self.settings.wave_length.max(0.1);
+        let contour = (0..CONTOUR_DIRECTIONS)
+            .map(|i| {
+                let phi = 2.0 * PI * i as f32 / CONTOUR_DIRECTIONS as f32;
+                let dir = vecmath::vec3_add(
+                    vecmath::vec3_scale(u, phi.cos()),
+                    vecmath::vec3_scale(v, phi.sin()),
+                );
+                let at = |r: f32| vecmath::vec3_add(axis, vecmath::vec3_scale(dir, r));
+                let mut inside = 0.0;
+                let mut outside = None;
+                let mut r = step;
+                while r < r_max {
+                    if self.focal_amp(at(r)) < threshold {
+                        outside = Some(r);
+                        break;
+                    }
+                    inside = r;
+                    r += step;
+                }
+                let r = match outside {
+                    Some(mut outside) => {
+                        for _ in 0..BISECTION_STEPS {
+                            let mid = (inside + outside) / 2.0;
+                            if self.focal_amp(at(mid)) < threshold {
+                                outside = mid;
+                            } else {
+                                inside = mid;
+                            }
+                        }
+                        inside
+                    }
+                    None => r_max,
+                };
+                at(r)
+            })
+            .collect();
+        Some(SteeringLimit {
+            depth,
+            fraction,
+            wave_length: self.settings.wave_length,
+            center,
+            contour,
+        })
+    }
+}
@@ -0,0 +1,161 @@
+/*
+ * File: stm_duty.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use acoustic_field_viewer::{
+    directivity::DirectivityTable, field::FieldModel, sound_source::SoundSource,
+    view::ViewerSettings, Vector3,
+};
+
+/// Foci closer than this in mm are regarded as the same
+const FOCUS_TOLERANCE: f32 = 0.5;
+/// Sequences of more distinct foci draw a path rather than time-multiplex foci
+pub const MAX_STM_FOCI: usize = 16;
+
+/// Drive of `sources` focusing at `focus` with the duty `duty` of the STM point, as the firmware
+/// does
+pub fn focused_drive(
+    sources: &[SoundSource],
+    settings: &ViewerSettings,
+    focus: Vector3,
+    duty: u8,
+) -> Vec<SoundSource> {
+    let amp = (duty as f32 / 510.0 * PI).sin();
+    sources
+        .iter()
+        .map(|s| {
+            let k = s.wavenumber(settings);
+            let d = vecmath_util::dist(s.pos, focus);
+            SoundSource {
+                amp,
+                phase: (k * d).rem_euclid(2.0 * PI),
+                ..*s
+            }
+        })
+        .collect()
+}
+
+/// Time-multiplexed focus of an STM compared with the multi-focus gain of all the foci
+pub struct FocusDuty {
+    pub pos: Vector3,
+    /// Fraction of the period in which the STM points to the focus
+    pub dwell: f32,
+    /// Amplitude while the STM points to the focus
+    pub focused: f32,
+    /// RMS over the period of the amplitude, including the contributions while pointing to the
+    /// other foci
+    pub stm_rms: f32,
+    /// Amplitude of the phase-only multi-focus gain of the same drive amplitude
+    pub multi_focus: f32,
+}
+
+/// Analyzes the STM of `points` in the global coordinates driving `sources`, or returns None if it
+/// has no or too many foci
+pub fn analyze(
+    points: &[(Vector3, u8)],
+    sources: &[SoundSource],
+    device_sizes: &[usize],
+    settings: &ViewerSettings,
+    directivity_table: Option<&DirectivityTable>,
+) -> Option<Vec<FocusDuty>> {
+    // distinct (focus, duty) with the number of the points
+    let mut foci: Vec<Vector3> = Vec::new();
+    let mut drives: Vec<(usize, u8, usize)> = Vec::new();
+    for &(pos, duty) in points {
+        let i = match foci
+            .iter()
+            .position(|&f| vecmath_util::dist(f, pos) < FOCUS_TOLERANCE)
+        {
+            Some(i) => i,
+            None => {
+                if foci.len() == MAX_STM_FOCI {
+                    return None;
+                }
+                foci.push(pos);
+                foci.len() - 1
+            }
+        };
+        match drives.iter_mut().find(|d| d.0 == i && d.1 == duty) {
+            Some(d) => d.2 += 1,
+            None => drives.push((i, duty, 1)),
+        }
+    }
+    if foci.is_empty() {
+        return None;
+    }
+
+    let amp_at = |sources: &[SoundSource], pos: Vector3| {
+        let model = FieldModel {
+            sources,
+            settings,
+            directivity_table,
+            scattering: None,
+            device_sizes,
+        };
+        let [re, im] = model.pressure_at(pos);
+        (re * re + im * im).sqrt()
+    };
+    let n = points.len() as f32;
+    // squared amplitude at each focus weighted by the dwell of each drive
+    let mut mean_square = vec![0.0; foci.len()];
+    let mut focused = vec![0.0; foci.len()];
+    let mut dwell = vec![0.0; foci.len()];
+    for &(i, duty, count) in &drives {
+        let drive = focused_drive(sources, settings, foci[i], duty);
+        let w = count as f32 / n;
+        for (j, &pos) in foci.iter().enumerate() {
+            let amp = amp_at(&drive, pos);
+            mean_square[j] += w * amp * amp;
+            if j == i {
+                focused[i] += w * amp;
+            }
+        }
+        dwell[i] += w;
+    }
+
+    let max_duty = points.iter().map(|d| d.1).max().unwrap_or(0);
+    let multi_focus: Vec<_> = sources
+        .iter()
+        .map(|s| {
+            let k = s.wavenumber(settings);
+            let [re, im] = foci.iter().fold([0.0, 0.0], |[re, im], &f| {
+                let p = k * vecmath_util::dist(s.pos, f);
+                [re + p.cos(), im + p.sin()]
+            });
+            SoundSource {
+                amp: (max_duty as f32 / 510.0 * PI).sin(),
+                phase: im.atan2(re).rem_euclid(2.0 * PI),
+                ..*s
+            }
+        })
+        .collect();
+
+    Some(
+        foci.iter()
+            .enumerate()
+            .map(|(i, &pos)| FocusDuty {
+                pos,
+                dwell: dwell[i],
+                // mean over the drives of the focus, which differ only in the duty
+                focused: if dwell[i] > 0.0 {
+                    focused[i] / dwell[i]
+                } else {
+                    0.0
+                },
+                stm_rms: mean_square[i].sqrt(),
+                multi_focus: amp_at(&multi_focus, pos),
+            })
+            .collect(),
+    )
+}
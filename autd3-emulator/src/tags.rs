@@ -0,0 +1,73 @@
+/*
+ * File: tags.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::collections::VecDeque;
+
+/// Maximum number of tagged gains kept in the history
+pub const TAG_HISTORY_MAX: usize = 1000;
+
+/// Gain tagged by the controlling program
+pub struct TaggedGain {
+    /// Number of gains received up to this one
+    pub gain_index: usize,
+    /// Simulation time in seconds
+    pub time: f64,
+    pub tags: Vec<String>,
+}
+
+/// Tags sent by the controlling program, which are attached to the next gain
+#[derive(Default)]
+pub struct TagHistory {
+    pending: Vec<String>,
+    current: Vec<String>,
+    entries: VecDeque<TaggedGain>,
+}
+
+impl TagHistory {
+    pub fn tag(&mut self, tags: Vec<String>) {
+        self.pending.extend(tags);
+    }
+
+    /// Attaches the pending tags to the gain just received. A gain without tags clears the
+    /// current tags.
+    pub fn on_gain(&mut self, gain_index: usize, time: f64) {
+        self.current = std::mem::take(&mut self.pending);
+        if self.current.is_empty() {
+            return;
+        }
+        if self.entries.len() >= TAG_HISTORY_MAX {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TaggedGain {
+            gain_index,
+            time,
+            tags: self.current.clone(),
+        });
+    }
+
+    /// Tags of the current gain
+    pub fn current(&self) -> &[String] {
+        &self.current
+    }
+
+    /// Tagged gains from the oldest
+    pub fn entries(&self) -> &VecDeque<TaggedGain> {
+        &self.entries
+    }
+
+    /// Called on `Clear`, which keeps the history
+    pub fn clear_current(&mut self) {
+        self.pending.clear();
+        self.current.clear();
+    }
+}
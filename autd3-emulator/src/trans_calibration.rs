@@ -0,0 +1,140 @@
+/*
+ * File: trans_calibration.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{collections::HashMap, f32::consts::PI, path::Path};
+
+use acoustic_field_viewer::sound_source::SoundSource;
+
+/// Measured non-uniformity of the transducers of a physical unit, i.e., the factor of the
+/// amplitude and the offset of the phase in rad of each transducer, keyed by the indices of the
+/// device and of the transducer in it. The transducers not in the table are ideal.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransCalibration {
+    entries: HashMap<(usize, usize), (f32, f32)>,
+}
+
+fn parse_field<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid number: {}", s))
+}
+
+impl TransCalibration {
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let csv =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&csv).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Rows of the device index, the transducer index, the amplitude factor and the phase offset
+    /// in degrees after a header, where the lines starting with # are ignored
+    pub fn parse(csv: &str) -> Result<Self, String> {
+        let mut lines = csv
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        lines.next().ok_or_else(|| "empty table".to_owned())?;
+        let entries = lines
+            .enumerate()
+            .map(|(i, line)| {
+                let row: Vec<_> = line.split(',').collect();
+                if row.len() != 4 {
+                    return Err(format!("row {}: {} columns, expected 4", i + 1, row.len()));
+                }
+                let key = (parse_field(row[0])?, parse_field(row[1])?);
+                let amp: f32 = parse_field(row[2])?;
+                let phase: f32 = parse_field(row[3])?;
+                if amp < 0.0 {
+                    return Err(format!("row {}: negative amplitude {}", i + 1, amp));
+                }
+                Ok((key, (amp, phase.to_radians())))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        Ok(Self { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Number of the entries out of the devices of `device_sizes`, which are ignored
+    pub fn unmatched(&self, device_sizes: &[usize]) -> usize {
+        self.entries
+            .keys()
+            .filter(|&&(dev, tr)| device_sizes.get(dev).is_none_or(|&n| tr >= n))
+            .count()
+    }
+
+    /// Multiplies the calibration into the drive of `sources`, which are the transducers of the
+    /// devices of `device_sizes` in order. The amplitude is clamped to 1, the largest drive of the
+    /// slice.
+    pub fn apply(&self, sources: &mut [SoundSource], device_sizes: &[usize]) {
+        let mut offset = 0;
+        for (dev, &size) in device_sizes.iter().enumerate() {
+            let device = match sources.get_mut(offset..offset + size) {
+                Some(device) => device,
+                None => return,
+            };
+            for (tr, source) in device.iter_mut().enumerate() {
+                if let Some(&(amp, phase)) = self.entries.get(&(dev, tr)) {
+                    source.amp = (source.amp * amp).min(1.0);
+                    source.phase = (source.phase + phase).rem_euclid(2.0 * PI);
+                }
+            }
+            offset += size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "# measured on the bench\n\
+        device,transducer,amp,phase\n\
+        0, 1, 0.5, 90\n\
+        \n\
+        1,0,2.0,-90\n\
+        2,0,1.0,0\n";
+
+    #[test]
+    fn parse_rows() {
+        let calibration = TransCalibration::parse(CSV).unwrap();
+        assert_eq!(calibration.len(), 3);
+        assert_eq!(calibration.entries[&(0, 1)], (0.5, PI / 2.0));
+        assert_eq!(calibration.entries[&(1, 0)], (2.0, -PI / 2.0));
+        assert_eq!(calibration.unmatched(&[2, 1]), 1);
+        assert_eq!(calibration.unmatched(&[1]), 3);
+    }
+
+    #[test]
+    fn invalid_tables() {
+        assert!(TransCalibration::parse("# only a comment\n").is_err());
+        assert!(TransCalibration::parse("header\n0,1,0.5\n").is_err());
+        assert!(TransCalibration::parse("header\n0,1,x,0\n").is_err());
+        assert!(TransCalibration::parse("header\n0,1,-0.5,0\n").is_err());
+        assert_eq!(TransCalibration::parse("header\n").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn apply_to_the_devices() {
+        let calibration = TransCalibration::parse(CSV).unwrap();
+        let mut sources = vec![SoundSource::new([0., 0., 0.], [0., 0., 1.], 0.8, 0.0); 3];
+        calibration.apply(&mut sources, &[2, 1]);
+        assert_eq!((sources[0].amp, sources[0].phase), (0.8, 0.0));
+        assert_eq!((sources[1].amp, sources[1].phase), (0.4, PI / 2.0));
+        // the amplitude is clamped and the phase wrapped
+        assert_eq!((sources[2].amp, sources[2].phase), (1.0, 1.5 * PI));
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ * File: ui_layout.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{collections::BTreeMap, fs::File, io::BufReader, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Setting;
+
+/// Named arrangement of the panels, e.g., for demo, debugging or analysis
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLayout {
+    pub name: String,
+    /// Positions, sizes and collapsed states of the windows in the ini format of ImGui
+    pub ini: String,
+    /// Visibility by the name of the flag in [Setting], e.g., `show_probe`, where the panels
+    /// missing in the older layouts are left as they are on apply
+    #[serde(flatten)]
+    pub panels: BTreeMap<String, bool>,
+    #[serde(default)]
+    pub mod_plot_size: Option<[f32; 2]>,
+}
+
+// Visibility flags of the panels saved in the layouts, by their names in setting.json
+macro_rules! panels {
+    ($($flag:ident),*) => {
+        fn capture_panels(setting: &Setting) -> BTreeMap<String, bool> {
+            let mut panels = BTreeMap::new();
+            $(panels.insert(stringify!($flag).to_owned(), setting.$flag);)*
+            panels
+        }
+
+        fn apply_panels(panels: &BTreeMap<String, bool>, setting: &mut Setting) {
+            $(if let Some(&show) = panels.get(stringify!($flag)) {
+                setting.$flag = show;
+            })*
+        }
+    };
+}
+
+panels!(
+    show_coordinate_readout,
+    show_commanded_foci,
+    show_focal_spot,
+    show_mod_plot,
+    show_mod_plot_raw,
+    show_probe,
+    show_target_region,
+    show_line_scan,
+    show_acoustic_camera,
+    show_far_field,
+    show_power_summary,
+    show_fixtures,
+    show_volume,
+    show_isosurface,
+    show_color_bar,
+    show_devices,
+    show_grid,
+    show_axes,
+    show_ruler
+);
+
+impl UiLayout {
+    /// Layout of the windows in `ini` and the visibility of the panels in `setting`
+    pub fn capture(name: &str, ini: String, setting: &Setting) -> Self {
+        Self {
+            name: name.to_owned(),
+            ini,
+            panels: capture_panels(setting),
+            mod_plot_size: Some(setting.mod_plot_size),
+        }
+    }
+
+    /// Applies the visibility of the panels, while the windows are loaded from
+    /// [UiLayout::ini] by the caller
+    pub fn apply(&self, setting: &mut Setting) {
+        apply_panels(&self.panels, setting);
+        if let Some(size) = self.mod_plot_size {
+            setting.mod_plot_size = size;
+        }
+    }
+}
+
+pub fn load_layouts<P: AsRef<Path>>(path: P) -> Result<Vec<UiLayout>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())
+}
+
+pub fn save_layouts<P: AsRef<Path>>(path: P, layouts: &[UiLayout]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(file, layouts).map_err(|e| e.to_string())
+}
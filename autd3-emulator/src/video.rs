@@ -0,0 +1,282 @@
+/*
+ * File: video.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, SyncSender, TrySendError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+
+/// Frames queued for the encoder, beyond which the new frames are dropped
+const QUEUE_FRAMES: usize = 16;
+/// Speed of the color quantization of GIF from 1 (the best) to 30 (the fastest)
+const GIF_SPEED: i32 = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoFormat {
+    /// Animated GIF encoded in the emulator, looping
+    Gif,
+    /// H.264 encoded by ffmpeg, to which the raw frames are piped
+    Mp4,
+}
+
+impl VideoFormat {
+    pub const ALL: [VideoFormat; 2] = [VideoFormat::Gif, VideoFormat::Mp4];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "GIF",
+            VideoFormat::Mp4 => "MP4 (ffmpeg)",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "gif",
+            VideoFormat::Mp4 => "mp4",
+        }
+    }
+
+    /// Largest frame rate, which is limited by the delay of GIF in 10 ms
+    pub fn max_fps(&self) -> f32 {
+        match self {
+            VideoFormat::Gif => 50.0,
+            VideoFormat::Mp4 => 60.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VideoSetting {
+    pub format: VideoFormat,
+    /// Frames per second of the recording, sampled from the rendered frames
+    pub fps: f32,
+    /// Executable of ffmpeg, searched in the PATH if not a path
+    pub ffmpeg: String,
+}
+
+impl VideoSetting {
+    pub fn new() -> Self {
+        Self {
+            format: VideoFormat::Gif,
+            fps: 15.0,
+            ffmpeg: "ffmpeg".to_owned(),
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps.clamp(1.0, self.format.max_fps())
+    }
+
+    /// Path of the video started at `time` in `folder`
+    pub fn path(&self, folder: &str, time: chrono::DateTime<chrono::Local>) -> PathBuf {
+        Path::new(folder).join(format!(
+            "video_{}.{}",
+            time.format("%Y-%m-%d_%H-%M-%S_%3f"),
+            self.format.extension()
+        ))
+    }
+}
+
+impl Default for VideoSetting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Recording of the frames of the viewport of a fixed size, encoded on its own thread so that
+/// the rendering is not blocked
+pub struct VideoRecorder {
+    path: PathBuf,
+    size: (u32, u32),
+    interval: Duration,
+    started: Instant,
+    last: Option<Instant>,
+    frames: usize,
+    dropped: usize,
+    sender: Option<SyncSender<Vec<u8>>>,
+    worker: Option<JoinHandle<Result<(), String>>>,
+}
+
+impl VideoRecorder {
+    /// Starts the recording of the frames of `size` to `path`, creating the folder if missing
+    pub fn start(setting: &VideoSetting, path: PathBuf, size: (u32, u32)) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let fps = setting.fps();
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_FRAMES);
+        let worker = match setting.format {
+            VideoFormat::Gif => {
+                let file = File::create(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                let path = path.clone();
+                thread::spawn(move || {
+                    Self::encode_gif(BufWriter::new(file), receiver, size, fps)
+                        .map_err(|e| format!("{}: {}", path.display(), e))
+                })
+            }
+            VideoFormat::Mp4 => {
+                let child = Command::new(&setting.ffmpeg)
+                    .args([
+                        "-y",
+                        "-loglevel",
+                        "error",
+                        "-f",
+                        "rawvideo",
+                        "-pix_fmt",
+                        "rgba",
+                    ])
+                    .arg("-video_size")
+                    .arg(format!("{}x{}", size.0, size.1))
+                    .arg("-framerate")
+                    .arg(fps.to_string())
+                    .args(["-i", "-", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+                    .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+                    .arg(&path)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| format!("{}: {}", setting.ffmpeg, e))?;
+                thread::spawn(move || Self::pipe_ffmpeg(child, receiver))
+            }
+        };
+        Ok(Self {
+            path,
+            size,
+            interval: Duration::from_secs_f32(1.0 / fps),
+            started: Instant::now(),
+            last: None,
+            frames: 0,
+            dropped: 0,
+            sender: Some(sender),
+            worker: Some(worker),
+        })
+    }
+
+    fn encode_gif<W: Write>(
+        writer: W,
+        receiver: Receiver<Vec<u8>>,
+        (width, height): (u32, u32),
+        fps: f32,
+    ) -> Result<(), String> {
+        let mut encoder = GifEncoder::new_with_speed(writer, GIF_SPEED);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+        let delay = Delay::from_numer_denom_ms(1000, fps.round() as u32);
+        for pixels in receiver {
+            let image = RgbaImage::from_raw(width, height, pixels)
+                .ok_or_else(|| "frame of a wrong size".to_owned())?;
+            encoder
+                .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn pipe_ffmpeg(
+        mut child: std::process::Child,
+        receiver: Receiver<Vec<u8>>,
+    ) -> Result<(), String> {
+        if let Some(mut stdin) = child.stdin.take() {
+            for pixels in receiver {
+                if stdin.write_all(&pixels).is_err() {
+                    // ffmpeg exited, whose error is reported below
+                    break;
+                }
+            }
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "ffmpeg {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Number of the frames dropped because the encoder was behind
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the next frame is due at `now`
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.last
+            .is_none_or(|last| now.duration_since(last) >= self.interval)
+    }
+
+    /// Queues the RGBA `pixels` of `size` rendered at `now`, or fails if the size changed or the
+    /// encoder stopped
+    pub fn push(&mut self, now: Instant, size: (u32, u32), pixels: Vec<u8>) -> Result<(), String> {
+        if size != self.size {
+            return Err(format!(
+                "the view was resized from {}x{} to {}x{}",
+                self.size.0, self.size.1, size.0, size.1
+            ));
+        }
+        // the frames keep the pace of the frame rate unless the rendering is much slower
+        self.last = Some(match self.last {
+            Some(last) if now.duration_since(last) < self.interval * 2 => last + self.interval,
+            _ => now,
+        });
+        let sender = match &self.sender {
+            Some(sender) => sender,
+            None => return Err("the recording is finished".to_owned()),
+        };
+        match sender.try_send(pixels) {
+            Ok(()) => self.frames += 1,
+            Err(TrySendError::Full(_)) => self.dropped += 1,
+            Err(TrySendError::Disconnected(_)) => return Err("the encoder stopped".to_owned()),
+        }
+        Ok(())
+    }
+
+    /// Stops the recording, returning the job waiting for the encoder to finish the file
+    pub fn finish(mut self) -> impl FnOnce() -> Result<(), String> + Send {
+        self.sender = None;
+        let worker = self.worker.take();
+        move || match worker {
+            Some(worker) => worker
+                .join()
+                .map_err(|_| "video encoder panicked".to_owned())?,
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * File: volume.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use acoustic_field_viewer::{
+    directivity::DirectivityTable,
+    field::FieldModel,
+    scatterer::Scattering,
+    sound_source::SoundSource,
+    view::ViewerSettings,
+    volume::{VolumeGrid, VolumeSettings},
+};
+
+struct VolumeRequest {
+    sources: Vec<SoundSource>,
+    device_sizes: Vec<usize>,
+    settings: ViewerSettings,
+    directivity_table: Option<DirectivityTable>,
+    scattering: Option<Scattering>,
+    volume: VolumeSettings,
+}
+
+/// Samples [VolumeGrid]s on a background thread, one at a time, where the requests arriving
+/// meanwhile are coalesced into the latest
+pub struct VolumeSampler {
+    running: Option<Receiver<VolumeGrid>>,
+    pending: Option<VolumeRequest>,
+}
+
+impl VolumeSampler {
+    pub fn new() -> Self {
+        Self {
+            running: None,
+            pending: None,
+        }
+    }
+
+    pub fn request(
+        &mut self,
+        sources: Vec<SoundSource>,
+        device_sizes: Vec<usize>,
+        settings: ViewerSettings,
+        directivity_table: Option<DirectivityTable>,
+        scattering: Option<Scattering>,
+        volume: VolumeSettings,
+    ) {
+        self.pending = Some(VolumeRequest {
+            sources,
+            device_sizes,
+            settings,
+            directivity_table,
+            scattering,
+            volume,
+        });
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.running.is_some() || self.pending.is_some()
+    }
+
+    /// Starts the pending sampling if idle, and returns the grid of the finished one
+    pub fn poll(&mut self) -> Option<VolumeGrid> {
+        let result = match &self.running {
+            Some(rx) => match rx.try_recv() {
+                Ok(grid) => Some(grid),
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    tracing::error!("volume sampling panicked");
+                    None
+                }
+            },
+            None => None,
+        };
+        self.running = None;
+        if let Some(request) = self.pending.take() {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                let model = FieldModel {
+                    sources: &request.sources,
+                    settings: &request.settings,
+                    directivity_table: request.directivity_table.as_ref(),
+                    scattering: request.scattering.as_ref(),
+                    device_sizes: &request.device_sizes,
+                };
+                // the receiver is dropped if the emulator exits meanwhile
+                let _ = tx.send(VolumeGrid::compute(&model, &request.volume));
+            });
+            self.running = Some(rx);
+        }
+        result
+    }
+}
+
+impl Default for VolumeSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,287 @@
+/*
+ * File: volume_export.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use acoustic_field_viewer::{
+    field::{self, Complex, FieldModel},
+    sound_source::SoundSource,
+    view::ViewerSettings,
+    Vector3,
+};
+use autd3_emulator_server::read_capture;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    figure::{self, ReplayState},
+    hdf5::{self, Dataset, Values},
+    settings::Setting,
+};
+
+/// Largest number of the points of a volume, beyond which it is rejected
+pub const MAX_VOLUME_POINTS: usize = 100_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeFormat {
+    /// Coordinates and the real and imaginary parts of the pressure of each point, which is
+    /// several GB of text for a large volume
+    Csv,
+    /// Datasets of the pressure, the grid and the drive
+    #[default]
+    Hdf5,
+    /// The datasets of [VolumeFormat::Hdf5] in a MATLAB 7.3 MAT-file
+    Mat,
+}
+
+impl VolumeFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VolumeFormat::Csv => "csv",
+            VolumeFormat::Hdf5 => "h5",
+            VolumeFormat::Mat => "mat",
+        }
+    }
+}
+
+/// Fields on 3D grids computed headlessly from a recording with `--compute-volume`, where the
+/// paths are relative to the spec file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VolumeSpec {
+    /// Capture file recorded in the `Capture` section of the `Log` tab
+    pub recording: String,
+    /// Folder of the files, the folder of the spec if omitted
+    #[serde(default)]
+    pub output: String,
+    /// Settings before the recording is replayed, those of `setting.json` if omitted
+    pub viewer_setting: Option<ViewerSettings>,
+    /// Table for the imported directivity, that of `setting.json` if omitted
+    pub directivity_file: Option<String>,
+    /// Format of the files, HDF5 if omitted
+    #[serde(default)]
+    pub format: VolumeFormat,
+    pub volumes: Vec<VolumeExport>,
+}
+
+/// Complex pressure on a box
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VolumeExport {
+    /// File name without the extension
+    pub name: String,
+    /// Time in seconds from the start of the recording, the end if omitted
+    pub time: Option<f32>,
+    /// Center of the box in mm
+    pub center: Vector3,
+    /// Size in mm along the x, y and z axes
+    pub size: Vector3,
+    /// Spacing of the points in mm
+    pub resolution: f32,
+}
+
+impl VolumeExport {
+    /// Corner of the box with the smallest coordinates, which is the first point
+    pub fn min(&self) -> Vector3 {
+        vecmath::vec3_sub(self.center, vecmath::vec3_scale(self.size, 0.5))
+    }
+
+    /// Number of the points along each axis, which are spaced by the resolution from the minimum
+    /// corner up to the size
+    pub fn dims(&self) -> [usize; 3] {
+        let resolution = self.resolution.max(1e-3);
+        self.size
+            .map(|s| (s.max(0.0) / resolution + 1e-3).floor() as usize + 1)
+    }
+
+    fn write_csv(
+        &self,
+        path: &Path,
+        recording: &str,
+        settings: &ViewerSettings,
+        values: &[Complex],
+    ) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let [nx, ny, nz] = self.dims();
+        let time = self
+            .time
+            .map_or_else(|| "end".to_owned(), |t| format!("{} s", t));
+        writeln!(writer, "# recording: {} at {}", recording, time)?;
+        writeln!(
+            writer,
+            "# calibration: {} Pa per unit",
+            settings.calibration.pa_per_unit()
+        )?;
+        writeln!(
+            writer,
+            "# {} x {} x {} points, {} mm apart, x the fastest",
+            nx, ny, nz, self.resolution
+        )?;
+        writeln!(writer, "x [mm],y [mm],z [mm],re,im")?;
+        let min = self.min();
+        for (i, [re, im]) in values.iter().enumerate() {
+            let idx = [i % nx, i / nx % ny, i / (nx * ny)];
+            let [x, y, z] = [0, 1, 2].map(|k| min[k] + idx[k] as f32 * self.resolution);
+            writeln!(writer, "{},{},{},{},{}", x, y, z, re, im)?;
+        }
+        writer.flush()
+    }
+
+    /// Writes the pressure of the shape (nz, ny, nx), the axes `x`, `y` and `z` of the grid, and
+    /// the drive of `sources`, which MATLAB reads with the axes reversed
+    fn write_hdf5(
+        &self,
+        path: &Path,
+        recording: &str,
+        settings: &ViewerSettings,
+        sources: &[SoundSource],
+        values: &[Complex],
+        matlab: bool,
+    ) -> std::io::Result<()> {
+        let [nx, ny, nz] = self.dims();
+        let min = self.min();
+        let axis = |k: usize, n: usize| -> Vec<f64> {
+            (0..n)
+                .map(|i| (min[k] + i as f32 * self.resolution) as f64)
+                .collect()
+        };
+        let (x, y, z) = (axis(0, nx), axis(1, ny), axis(2, nz));
+        let flatten = |f: &dyn Fn(&SoundSource) -> Vector3| -> Vec<f64> {
+            sources
+                .iter()
+                .flat_map(|s| f(s).map(|v| v as f64))
+                .collect()
+        };
+        let position = flatten(&|s| s.pos);
+        let direction = flatten(&|s| s.dir);
+        let amplitude: Vec<f64> = sources.iter().map(|s| s.amp as f64).collect();
+        let phase: Vec<f64> = sources.iter().map(|s| s.phase as f64).collect();
+        let frequency: Vec<f64> = sources
+            .iter()
+            .map(|s| s.frequency.unwrap_or(settings.frequency) as f64)
+            .collect();
+        let resolution = [self.resolution as f64];
+        let pa_per_unit = [settings.calibration.pa_per_unit() as f64];
+
+        // MATLAB has no arrays of less than two dimensions
+        let shape = |shape: &[usize]| match shape {
+            [n] if matlab => vec![1, *n],
+            _ => shape.to_vec(),
+        };
+        let class = |class: &str| {
+            if matlab {
+                vec![("MATLAB_class", class.to_owned())]
+            } else {
+                vec![]
+            }
+        };
+        let n = sources.len();
+        let mut datasets = vec![Dataset {
+            name: "pressure",
+            shape: vec![nz, ny, nx],
+            values: Values::Complex(values),
+            attributes: class("single"),
+        }];
+        datasets.extend(
+            vec![
+                ("x", vec![nx], &x[..]),
+                ("y", vec![ny], &y),
+                ("z", vec![nz], &z),
+                ("resolution", vec![1], &resolution),
+                ("pa_per_unit", vec![1], &pa_per_unit),
+                ("source_position", vec![n, 3], &position),
+                ("source_direction", vec![n, 3], &direction),
+                ("amplitude", vec![n], &amplitude),
+                ("phase", vec![n], &phase),
+                ("frequency", vec![n], &frequency),
+            ]
+            .into_iter()
+            .map(|(name, dims, values)| Dataset {
+                name,
+                shape: shape(&dims),
+                values: Values::F64(values),
+                attributes: class("double"),
+            }),
+        );
+        let time = self
+            .time
+            .map_or_else(|| "end".to_owned(), |t| format!("{} s", t));
+        let userblock = if matlab {
+            hdf5::mat_userblock()
+        } else {
+            vec![]
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        hdf5::write(
+            &mut writer,
+            &userblock,
+            &[("recording", recording.to_owned()), ("time", time)],
+            &datasets,
+        )?;
+        writer.flush()
+    }
+}
+
+/// Computes the volumes of the spec at `path` and writes `<name>.csv`, `<name>.h5` or
+/// `<name>.mat`, returning the number of the files written
+pub fn compute_volumes(path: &Path, setting: &Setting) -> Result<usize, String> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let spec: VolumeSpec = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let recording = dir.join(&spec.recording);
+    let frames = read_capture(&recording).map_err(|e| format!("{}: {}", recording.display(), e))?;
+    let base = spec.viewer_setting.unwrap_or(setting.viewer_setting);
+    let directivity_table =
+        figure::load_directivity(dir, spec.directivity_file.as_deref(), setting, &base)?;
+    let output = dir.join(&spec.output);
+    std::fs::create_dir_all(&output).map_err(|e| e.to_string())?;
+    for volume in &spec.volumes {
+        let dims = volume.dims();
+        let num = dims.iter().product::<usize>();
+        if volume.resolution <= 0.0 || num > MAX_VOLUME_POINTS {
+            return Err(format!(
+                "{}: {} x {} x {} points at {} mm",
+                volume.name, dims[0], dims[1], dims[2], volume.resolution
+            ));
+        }
+        let state = ReplayState::replay(&frames, volume.time, base, setting);
+        let scattering = state.scattering(setting, directivity_table.as_ref());
+        let settings = state.viewer_setting;
+        let model = FieldModel {
+            sources: &state.drive.sources,
+            settings: &settings,
+            directivity_table: directivity_table.as_ref(),
+            scattering: scattering.as_ref(),
+            device_sizes: &state.drive.device_sizes,
+        };
+        tracing::info!(num, "computing {}", volume.name);
+        let values = field::compute_grid(&model, volume.min(), dims, volume.resolution);
+        let path = output.join(format!("{}.{}", volume.name, spec.format.extension()));
+        match spec.format {
+            VolumeFormat::Csv => volume.write_csv(&path, &spec.recording, &settings, &values),
+            VolumeFormat::Hdf5 | VolumeFormat::Mat => volume.write_hdf5(
+                &path,
+                &spec.recording,
+                &settings,
+                &state.drive.sources,
+                &values,
+                spec.format == VolumeFormat::Mat,
+            ),
+        }
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+        tracing::info!("volume written: {}", path.display());
+    }
+    Ok(spec.volumes.len())
+}
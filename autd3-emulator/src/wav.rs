@@ -0,0 +1,86 @@
+/*
+ * File: wav.rs
+ * Project: src
+ * Created Date: 14/10/2026
+ * Author: Shun Suzuki
+ * -----
+ * Last Modified: 14/10/2026
+ * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+const FORMAT_IEEE_FLOAT: u16 = 3;
+
+/// Writes a WAV of 32-bit float samples interleaved over `channels`, which audio tools read
+/// without scaling
+pub fn write_float(
+    path: &Path,
+    sample_rate: f64,
+    channels: u16,
+    samples: &[f32],
+) -> Result<(), String> {
+    let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+    let sample_rate = sample_rate.round() as u32;
+    let block_align = 4 * channels;
+    let data_len = (samples.len() * 4) as u32;
+    let res: std::io::Result<()> = (|| {
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + data_len).to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&FORMAT_IEEE_FLOAT.to_le_bytes())?;
+        writer.write_all(&channels.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&32u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&data_len.to_le_bytes())?;
+        for p in samples {
+            writer.write_all(&p.to_le_bytes())?;
+        }
+        writer.flush()
+    })();
+    res.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_header_and_samples() {
+        let path = std::env::temp_dir().join(format!("autd-emulator-{}.wav", std::process::id()));
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0, 0.25];
+        write_float(&path, 40000.4, 2, &samples).unwrap();
+        let buf = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let u16_at = |at: usize| u16::from_le_bytes([buf[at], buf[at + 1]]);
+        let u32_at =
+            |at: usize| u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+        assert_eq!(buf.len(), 44 + 24);
+        assert_eq!(&buf[..4], b"RIFF");
+        assert_eq!(u32_at(4) as usize, buf.len() - 8);
+        assert_eq!(&buf[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(16), 16);
+        assert_eq!(u16_at(20), FORMAT_IEEE_FLOAT);
+        assert_eq!(u16_at(22), 2);
+        assert_eq!(u32_at(24), 40000);
+        assert_eq!(u32_at(28), 40000 * 8);
+        assert_eq!(u16_at(32), 8);
+        assert_eq!(u16_at(34), 32);
+        assert_eq!(&buf[36..40], b"data");
+        assert_eq!(u32_at(40), 24);
+        let data: Vec<_> = (0..6).map(|i| f32::from_bits(u32_at(44 + 4 * i))).collect();
+        assert_eq!(data, samples.to_vec());
+    }
+}
@@ -14,6 +14,7 @@
 use std::f32::consts::PI;
 
 use acoustic_field_viewer::{
+    metadata::ExperimentMetadata,
     sound_source::SoundSource,
     view::{AcousticFiledSliceViewer, System, UpdateFlag, ViewerSettings},
     Vector3,
@@ -69,5 +70,11 @@ fn main() {
         settings.slice_width as usize,
         settings.slice_height as usize,
     );
-    g.save("debug.png", bb, field_slice_viewer.color_map());
+    g.save(
+        "debug.png",
+        bb,
+        field_slice_viewer.color_map(),
+        &ExperimentMetadata::default(),
+    )
+    .expect("failed to save debug.png");
 }
@@ -14,6 +14,7 @@
 use std::{f32::consts::PI, path::Path, sync::Arc};
 
 use acoustic_field_viewer::{
+    metadata::ExperimentMetadata,
     sound_source::SoundSource,
     view::{AcousticFiledSliceViewer, UpdateFlag, ViewerSettings},
     Vector4,
@@ -48,7 +49,7 @@ struct Config {
     num_z: u32,
     wave_num: f32,
     color_scale: f32,
-    _dummy_0: f32,
+    attenuation: f32,
     _dummy_1: f32,
 }
 
@@ -181,11 +182,13 @@ impl OffscreenRenderer {
         self.source_phase_buf = Some(source_phase_buffer);
     }
 
-    fn init_source_pos(&mut self, sources: &[SoundSource]) {
+    fn init_source_pos(&mut self, sources: &[SoundSource], setting: &ViewerSettings) {
         let source_pos_buffer = {
             let pos = (0..Self::align_to_four_multiple(sources.len())).map(|n| {
                 if n < sources.len() {
-                    vecmath_util::to_vec4(sources[n].pos)
+                    let mut pos = vecmath_util::to_vec4(sources[n].pos);
+                    pos[3] = sources[n].wavenumber(setting);
+                    pos
                 } else {
                     Default::default()
                 }
@@ -204,7 +207,7 @@ impl OffscreenRenderer {
     ) {
         self.init_pipeline();
         self.init_renderer_points(field_slice_view, setting);
-        self.init_source_pos(sources);
+        self.init_source_pos(sources, setting);
         self.init_source_drive(sources);
     }
 
@@ -226,8 +229,10 @@ impl OffscreenRenderer {
             self.init_renderer_points(field_slice_view, setting);
         }
 
-        if update_flag.contains(UpdateFlag::INIT_SOURCE) {
-            self.init_source_pos(sources);
+        if update_flag.contains(UpdateFlag::INIT_SOURCE)
+            || update_flag.contains(UpdateFlag::UPDATE_WAVENUM)
+        {
+            self.init_source_pos(sources, setting);
         }
 
         if update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE) {
@@ -246,7 +251,7 @@ impl OffscreenRenderer {
                 num_z: 1,
                 wave_num: 2.0 * PI / setting.wave_length,
                 color_scale: setting.color_scale,
-                _dummy_0: 0.0,
+                attenuation: setting.medium.attenuation,
                 _dummy_1: 0.0,
             };
             CpuAccessibleBuffer::from_data(self.device.clone(), BufferUsage::all(), false, config)
@@ -338,20 +343,43 @@ impl OffscreenRenderer {
         future.wait(None).unwrap();
     }
 
-    pub fn save<P>(&self, path: P, bb: (usize, usize), colormap: &[RGBColor])
+    /// Field of the last [OffscreenRenderer::calculate_field] in `bb`, row by row
+    pub fn field(&self, bb: (usize, usize)) -> Vec<f32> {
+        let result = self.results_buf.clone().unwrap();
+        let res_buffer = result.read().unwrap();
+        res_buffer[0..bb.0 * bb.1].to_vec()
+    }
+
+    /// Saves the field as PNG with `metadata` in the text chunks
+    pub fn save<P>(
+        &self,
+        path: P,
+        bb: (usize, usize),
+        colormap: &[RGBColor],
+        metadata: &ExperimentMetadata,
+    ) -> Result<(), String>
     where
         P: AsRef<Path>,
     {
-        let result = self.results_buf.clone().unwrap();
-        let res_buffer = result.read().unwrap();
+        Self::encode_png(path, &self.field(bb), bb, colormap, metadata)
+    }
 
-        use image::png::PngEncoder;
-        use image::ColorType;
-        use std::fs::File;
+    /// Encodes `field` read with [OffscreenRenderer::field] to PNG, which can be done on another
+    /// thread than the renderer
+    pub fn encode_png<P>(
+        path: P,
+        field: &[f32],
+        bb: (usize, usize),
+        colormap: &[RGBColor],
+        metadata: &ExperimentMetadata,
+    ) -> Result<(), String>
+    where
+        P: AsRef<Path>,
+    {
+        use std::{fs::File, io::BufWriter};
 
-        let output = File::create(path).unwrap();
-        let len = bb.0 * bb.1;
-        let pixels: Vec<_> = (&res_buffer[0..len])
+        let output = File::create(path).map_err(|e| e.to_string())?;
+        let pixels: Vec<_> = field
             .chunks_exact(bb.0)
             .rev()
             .flatten()
@@ -359,10 +387,16 @@ impl OffscreenRenderer {
             .flat_map(|c| vecmath_util::vec3_map([c.r, c.g, c.b], |v| (v * 255.0) as u8))
             .collect();
 
-        let encoder = PngEncoder::new(output);
-        encoder
-            .encode(&pixels, bb.0 as u32, bb.1 as u32, ColorType::Rgb8)
-            .unwrap();
+        let mut encoder = png::Encoder::new(BufWriter::new(output), bb.0 as u32, bb.1 as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (key, value) in metadata.entries() {
+            encoder
+                .add_itxt_chunk(key.to_owned(), value.to_owned())
+                .map_err(|e| e.to_string())?;
+        }
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&pixels).map_err(|e| e.to_string())
     }
 
     fn init_gpu() -> (Arc<Device>, Arc<Queue>) {